@@ -0,0 +1,152 @@
+use proc_macro::TokenStream;
+use quote::quote;
+
+use crate::fields::{cfg_attrs, parse_field_attrs, parse_type, ElementKind};
+
+#[derive(Debug)]
+struct Element {
+    ident: syn::Ident,
+    ty: syn::Path,
+    kind: ElementKind,
+    name: String,
+    attrs: Vec<syn::Attribute>,
+}
+
+fn parse_fields(input: impl IntoIterator<Item = syn::Field>) -> Vec<Element> {
+    let mut elements = Vec::new();
+    for mut field in input {
+        let ident = field.ident.expect("tuple structs not supported");
+        let (ty, kind) = parse_type(field.ty);
+
+        let name = parse_field_attrs(&mut field.attrs, "dynamo", "rename");
+
+        elements.push(Element {
+            ident: ident.clone(),
+            ty,
+            kind,
+            name: name.unwrap_or_else(|| ident.to_string()),
+            attrs: field.attrs,
+        });
+    }
+    elements
+}
+
+pub(crate) fn transform(input: TokenStream) -> TokenStream {
+    let root = quote! {::aws_lib};
+
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    let name = input.ident;
+
+    let elements = match input.data {
+        syn::Data::Struct(s) => match s.fields {
+            syn::Fields::Named(fields) => parse_fields(fields.named),
+            _ => panic!("DynamoItem derive macro requires named fields"),
+        },
+        _ => panic!("DynamoItem derive macro is only applicable to structs"),
+    };
+
+    let into_item_fields: Vec<proc_macro2::TokenStream> = elements
+        .iter()
+        .map(|element| {
+            let ident = &element.ident;
+            let ty = &element.ty;
+            let attribute_name = &element.name;
+            let attrs = cfg_attrs(&element.attrs);
+            match element.kind {
+                ElementKind::Required => quote! {
+                    #(#attrs)
+                    *
+                    {
+                        let _previous = item.insert(
+                            #attribute_name.to_owned(),
+                            <#ty as #root::dynamodb::AttributeType>::into_attribute(self.#ident),
+                        );
+                    }
+                },
+                ElementKind::Optional => quote! {
+                    #(#attrs)
+                    *
+                    {
+                        match self.#ident {
+                            ::std::option::Option::Some(value) => {
+                                let _previous = item.insert(
+                                    #attribute_name.to_owned(),
+                                    <#ty as #root::dynamodb::AttributeType>::into_attribute(value),
+                                );
+                            }
+                            ::std::option::Option::None => {
+                                // absent attributes are not stored at all
+                            }
+                        }
+                    }
+                },
+            }
+        })
+        .collect();
+
+    let from_item_fields: Vec<proc_macro2::TokenStream> = elements
+        .iter()
+        .map(|element| {
+            let ident = &element.ident;
+            let ty = &element.ty;
+            let attribute_name = &element.name;
+            let attrs = cfg_attrs(&element.attrs);
+
+            let convert = quote! {
+                <#ty as #root::dynamodb::AttributeType>::from_attribute(value).map_err(|message| {
+                    #root::dynamodb::ParseItemError::InvalidAttribute {
+                        name: #attribute_name.to_owned(),
+                        message,
+                    }
+                })
+            };
+
+            let transformer = match element.kind {
+                ElementKind::Required => quote! {
+                    let value = value.ok_or_else(|| #root::dynamodb::ParseItemError::MissingAttribute {
+                        name: #attribute_name.to_owned(),
+                    })?;
+                    #convert?
+                },
+                // an explicit NULL attribute means the same as an absent one
+                ElementKind::Optional => quote! {
+                    value
+                        .filter(|value| !value.is_null())
+                        .map(|value| #convert)
+                        .transpose()?
+                },
+            };
+
+            quote! {
+                #(#attrs)
+                *
+                #ident: {
+                    let value: ::std::option::Option<#root::dynamodb::AttributeValue> =
+                        item.remove(#attribute_name);
+                    #transformer
+                }
+            }
+        })
+        .collect();
+
+    quote! {
+        impl #root::dynamodb::Item for #name {
+            fn into_item(self) -> ::std::collections::HashMap<::std::string::String, #root::dynamodb::AttributeValue> {
+                let mut item = ::std::collections::HashMap::new();
+                #(#into_item_fields)
+                *
+                item
+            }
+
+            fn from_item(
+                mut item: ::std::collections::HashMap<::std::string::String, #root::dynamodb::AttributeValue>,
+            ) -> ::std::result::Result<Self, #root::dynamodb::ParseItemError> {
+                ::std::result::Result::Ok(Self {
+                    #(#from_item_fields),*
+                })
+            }
+        }
+    }
+    .into()
+}
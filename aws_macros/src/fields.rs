@@ -0,0 +1,167 @@
+//! Field parsing shared between the struct-based macros
+
+#[derive(Debug)]
+pub(crate) enum ElementKind {
+    Required,
+    Optional,
+}
+
+fn is_cfg_attribute(attr: &syn::Attribute) -> bool {
+    match attr.meta {
+        syn::Meta::List(ref meta_list) => meta_list.path.is_ident("cfg"),
+        _ => false,
+    }
+}
+
+pub(crate) fn cfg_attrs(v: &[syn::Attribute]) -> Vec<&syn::Attribute> {
+    v.iter()
+        .filter(|attr: &&syn::Attribute| is_cfg_attribute(attr))
+        .collect()
+}
+
+pub(crate) fn parse_type(input: syn::Type) -> (syn::Path, ElementKind) {
+    match input {
+        syn::Type::Path(ty) => {
+            let segments = ty.path.segments.clone();
+            let first = segments.first().expect("segments is empty");
+
+            let (ident, optional) = match first.ident.to_string().as_str() {
+                "Option" => match first.arguments {
+                    // It may not be required to parse this, we could just
+                    // extract the inner type and let the compiler beat the
+                    // caller up if there is some bullshit happening.
+                    syn::PathArguments::AngleBracketed(ref genargs) => {
+                        let mut args = genargs.args.clone();
+                        match genargs.args.len() {
+                            1 => {
+                                let ty = args.pop().expect("genargs are empty");
+                                let ty = match ty {
+                                    syn::punctuated::Pair::Punctuated(node, _punct) => node,
+                                    syn::punctuated::Pair::End(node) => node,
+                                };
+                                match ty {
+                                    syn::GenericArgument::Type(ty) => match ty {
+                                        syn::Type::Path(ty) => (ty, ElementKind::Optional),
+                                        _ => panic!("invalid generic type for Option"),
+                                    },
+
+                                    _ => panic!("need simple owned Option generic"),
+                                }
+                            }
+                            _ => panic!("wrong number of Option generic arguments"),
+                        }
+                    }
+                    _ => panic!("invalid Option usage"),
+                },
+                _ => (ty, ElementKind::Required),
+            };
+
+            (ident.path, optional)
+        }
+        _ => panic!("invalid field type"),
+    }
+}
+
+/// Extracts and removes a `#[<attribute>(...)]` attribute from a field
+pub(crate) fn take_field_attr(
+    attrs: &mut Vec<syn::Attribute>,
+    attribute: &str,
+) -> Option<syn::MetaList> {
+    let index_of_attribute = attrs
+        .iter()
+        .enumerate()
+        .filter(|&(_i, attr)| attr.style == syn::AttrStyle::Outer)
+        .find_map(|(i, attr)| match attr.meta {
+            syn::Meta::List(ref meta_list) => {
+                if meta_list.path.is_ident(attribute) {
+                    Some((i, meta_list.clone()))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        });
+
+    let (i, meta_list) = index_of_attribute?;
+
+    let removed_attribute = attrs.remove(i);
+    drop(removed_attribute);
+
+    Some(meta_list)
+}
+
+/// Extracts and removes a `#[<attribute>(<key> = <value>)]` attribute from a
+/// field, returning the key and the value expression
+fn take_field_assign(
+    attrs: &mut Vec<syn::Attribute>,
+    attribute: &str,
+) -> Option<(syn::Path, syn::Expr)> {
+    let meta_list = take_field_attr(attrs, attribute)?;
+
+    let expr: syn::Expr = match meta_list.parse_args() {
+        Ok(expr) => expr,
+        Err(e) => panic!("failed parsing {attribute} field attribute: {e}"),
+    };
+
+    let syn::Expr::Assign(assign) = expr else {
+        panic!("invalid expression in {attribute} field attribute")
+    };
+
+    match *assign.left {
+        syn::Expr::Path(exprpath) => Some((exprpath.path, *assign.right)),
+        _ => panic!("invalid expression in {attribute} field attribute, left side"),
+    }
+}
+
+/// Extracts and removes a `#[<attribute>(<key> = "value")]` attribute from a
+/// field, returning the value
+pub(crate) fn parse_field_attrs(
+    attrs: &mut Vec<syn::Attribute>,
+    attribute: &str,
+    key: &str,
+) -> Option<String> {
+    let (path, value) = take_field_assign(attrs, attribute)?;
+
+    assert!(
+        path.is_ident(key),
+        "invalid {attribute} field attribute key"
+    );
+
+    match value {
+        syn::Expr::Lit(ref expr_lit) => match expr_lit.lit {
+            syn::Lit::Str(ref lit_str) => Some(lit_str.value()),
+            _ => panic!("right side of {attribute} field not a string literal"),
+        },
+        _ => panic!("right side of {attribute} field attribute not a literal"),
+    }
+}
+
+/// Extracts and removes a `#[<attribute>(<flag>)]` attribute from a field,
+/// returning whether it was present
+pub(crate) fn take_field_flag(
+    attrs: &mut Vec<syn::Attribute>,
+    attribute: &str,
+    flag: &str,
+) -> bool {
+    let index_of_attribute = attrs.iter().position(|attr| {
+        attr.style == syn::AttrStyle::Outer
+            && match attr.meta {
+                syn::Meta::List(ref meta_list) => {
+                    meta_list.path.is_ident(attribute)
+                        && meta_list
+                            .parse_args::<syn::Path>()
+                            .is_ok_and(|path| path.is_ident(flag))
+                }
+                _ => false,
+            }
+    });
+
+    match index_of_attribute {
+        Some(i) => {
+            let removed_attribute = attrs.remove(i);
+            drop(removed_attribute);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,133 @@
+use proc_macro::TokenStream;
+use quote::quote;
+
+use crate::fields::{cfg_attrs, parse_field_attrs, parse_type, take_field_flag, ElementKind};
+
+#[derive(Debug)]
+struct Element {
+    ident: syn::Ident,
+    ty: syn::Path,
+    kind: ElementKind,
+    name: String,
+    flatten: bool,
+    attrs: Vec<syn::Attribute>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RenameRule {
+    /// `instance_id` becomes `instanceId`, used by EC2
+    CamelCase,
+    /// `instance_id` becomes `InstanceId`, used by the query protocol and S3
+    PascalCase,
+}
+
+impl RenameRule {
+    fn parse(value: &str) -> Self {
+        match value {
+            "camelCase" => Self::CamelCase,
+            "PascalCase" => Self::PascalCase,
+            _ => panic!("invalid rename_all value \"{value}\", expected camelCase or PascalCase"),
+        }
+    }
+
+    fn apply(self, field: &str) -> String {
+        let mut name = String::with_capacity(field.len());
+        for (i, part) in field.split('_').enumerate() {
+            let mut chars = part.chars();
+            if let Some(first) = chars.next() {
+                match (self, i) {
+                    (Self::CamelCase, 0) => name.push(first),
+                    _ => name.extend(first.to_uppercase()),
+                }
+                name.extend(chars);
+            }
+        }
+        name
+    }
+}
+
+fn parse_fields(input: impl IntoIterator<Item = syn::Field>, rule: RenameRule) -> Vec<Element> {
+    let mut elements = Vec::new();
+    for mut field in input {
+        let ident = field.ident.expect("tuple structs not supported");
+        let (ty, kind) = parse_type(field.ty);
+
+        let flatten = take_field_flag(&mut field.attrs, "xml", "flatten");
+        let name = parse_field_attrs(&mut field.attrs, "xml", "name");
+
+        assert!(
+            !(flatten && matches!(kind, ElementKind::Optional)),
+            "flattened lists cannot be optional, they are empty instead"
+        );
+
+        elements.push(Element {
+            ident: ident.clone(),
+            ty,
+            kind,
+            name: name.unwrap_or_else(|| rule.apply(&ident.to_string())),
+            flatten,
+            attrs: field.attrs,
+        });
+    }
+    elements
+}
+
+pub(crate) fn transform(input: TokenStream) -> TokenStream {
+    let root = quote! {::aws_lib};
+
+    let mut input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    let name = input.ident;
+
+    let rule = parse_field_attrs(&mut input.attrs, "xml", "rename_all")
+        .map_or(RenameRule::CamelCase, |value| RenameRule::parse(&value));
+
+    let elements = match input.data {
+        syn::Data::Struct(s) => match s.fields {
+            syn::Fields::Named(fields) => parse_fields(fields.named, rule),
+            _ => panic!("FromXml derive macro requires named fields"),
+        },
+        _ => panic!("FromXml derive macro is only applicable to structs"),
+    };
+
+    let fields: Vec<proc_macro2::TokenStream> = elements
+        .iter()
+        .map(|element| {
+            let ident = &element.ident;
+            let ty = &element.ty;
+            let element_name = &element.name;
+            let attrs = cfg_attrs(&element.attrs);
+
+            let value = match (element.flatten, &element.kind) {
+                (true, _) => quote! {
+                    element.flattened(#element_name)?
+                },
+                (false, &ElementKind::Required) => quote! {
+                    element.required::<#ty>(#element_name)?
+                },
+                (false, &ElementKind::Optional) => quote! {
+                    element.optional::<#ty>(#element_name)?
+                },
+            };
+
+            quote! {
+                #(#attrs)
+                *
+                #ident: #value
+            }
+        })
+        .collect();
+
+    quote! {
+        impl #root::protocol::FromXml for #name {
+            fn from_xml(
+                element: &#root::protocol::XmlElement,
+            ) -> ::std::result::Result<Self, #root::protocol::ParseXmlError> {
+                ::std::result::Result::Ok(Self {
+                    #(#fields),*
+                })
+            }
+        }
+    }
+    .into()
+}
@@ -6,6 +6,10 @@
 
 use proc_macro::TokenStream;
 
+mod dynamo_item;
+mod fields;
+mod from_xml;
+mod ssm_config;
 mod tag;
 mod tags;
 
@@ -19,3 +23,18 @@ pub fn Tags(attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn tag(input: TokenStream) -> TokenStream {
     tag::transform(input)
 }
+
+#[proc_macro_derive(DynamoItem, attributes(dynamo))]
+pub fn dynamo_item(input: TokenStream) -> TokenStream {
+    dynamo_item::transform(input)
+}
+
+#[proc_macro_derive(SsmConfig, attributes(ssm))]
+pub fn ssm_config(input: TokenStream) -> TokenStream {
+    ssm_config::transform(input)
+}
+
+#[proc_macro_derive(FromXml, attributes(xml))]
+pub fn from_xml(input: TokenStream) -> TokenStream {
+    from_xml::transform(input)
+}
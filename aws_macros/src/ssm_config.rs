@@ -0,0 +1,126 @@
+use proc_macro::TokenStream;
+use quote::quote;
+
+use crate::fields::{cfg_attrs, parse_field_attrs, parse_type, ElementKind};
+
+#[derive(Debug)]
+struct Element {
+    ident: syn::Ident,
+    ty: syn::Path,
+    kind: ElementKind,
+    name: String,
+    attrs: Vec<syn::Attribute>,
+}
+
+fn parse_fields(input: impl IntoIterator<Item = syn::Field>) -> Vec<Element> {
+    let mut elements = Vec::new();
+    for mut field in input {
+        let ident = field.ident.expect("tuple structs not supported");
+        let (ty, kind) = parse_type(field.ty);
+
+        let name = parse_field_attrs(&mut field.attrs, "ssm", "name");
+
+        elements.push(Element {
+            ident: ident.clone(),
+            ty,
+            kind,
+            name: name.unwrap_or_else(|| ident.to_string()),
+            attrs: field.attrs,
+        });
+    }
+    elements
+}
+
+pub(crate) fn transform(input: TokenStream) -> TokenStream {
+    let root = quote! {::aws_lib};
+
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    let name = input.ident;
+
+    let elements = match input.data {
+        syn::Data::Struct(s) => match s.fields {
+            syn::Fields::Named(fields) => parse_fields(fields.named),
+            _ => panic!("SsmConfig derive macro requires named fields"),
+        },
+        _ => panic!("SsmConfig derive macro is only applicable to structs"),
+    };
+
+    let parameter_names: Vec<proc_macro2::TokenStream> = elements
+        .iter()
+        .map(|element| {
+            let parameter_name = &element.name;
+            let attrs = cfg_attrs(&element.attrs);
+            quote! {
+                #(#attrs)
+                *
+                names.push(#root::ssm::parameter_path(prefix, #parameter_name));
+            }
+        })
+        .collect();
+
+    let from_parameters_fields: Vec<proc_macro2::TokenStream> = elements
+        .iter()
+        .map(|element| {
+            let ident = &element.ident;
+            let ty = &element.ty;
+            let parameter_name = &element.name;
+            let attrs = cfg_attrs(&element.attrs);
+
+            let convert = quote! {
+                <#ty as #root::tags::TagValue<#ty>>::from_raw_tag(value).map_err(|e| {
+                    #root::ssm::ParseConfigError::InvalidParameter {
+                        name: name.clone(),
+                        inner: <<#ty as #root::tags::TagValue<#ty>>::Error as Into<#root::tags::ParseTagValueError>>::into(e),
+                    }
+                })
+            };
+
+            let transformer = match element.kind {
+                ElementKind::Required => quote! {
+                    let value = value.ok_or_else(|| #root::ssm::ParseConfigError::MissingParameter {
+                        name: name.clone(),
+                    })?;
+                    #convert?
+                },
+                ElementKind::Optional => quote! {
+                    value.map(|value| #convert).transpose()?
+                },
+            };
+
+            quote! {
+                #(#attrs)
+                *
+                #ident: {
+                    let name: ::std::string::String = #root::ssm::parameter_path(prefix, #parameter_name);
+                    let value: ::std::option::Option<#root::tags::RawTagValue> = parameters
+                        .iter()
+                        .find(|parameter| parameter.name().as_str() == name)
+                        .map(|parameter| #root::tags::RawTagValue::new(parameter.value().to_owned()));
+                    #transformer
+                }
+            }
+        })
+        .collect();
+
+    quote! {
+        impl #root::ssm::SsmConfig for #name {
+            fn parameter_names(prefix: &str) -> ::std::vec::Vec<::std::string::String> {
+                let mut names = ::std::vec::Vec::new();
+                #(#parameter_names)
+                *
+                names
+            }
+
+            fn from_parameters(
+                prefix: &str,
+                parameters: &[#root::ssm::Parameter],
+            ) -> ::std::result::Result<Self, #root::ssm::ParseConfigError> {
+                ::std::result::Result::Ok(Self {
+                    #(#from_parameters_fields),*
+                })
+            }
+        }
+    }
+    .into()
+}
@@ -4,7 +4,7 @@ use quote::quote;
 #[derive(Debug)]
 enum TransparentKind {
     NewtypeStruct {
-        ty: syn::Type,
+        ty: Box<syn::Type>,
     },
     SimpleEnum {
         variants: Vec<(syn::Ident, Option<syn::LitStr>)>,
@@ -122,7 +122,7 @@ fn parse_tag_attribute(expr: syn::Expr, elem: &syn::Data) -> Translator {
                                         )
                                 };
                                 Translator::Transparent(TransparentKind::NewtypeStruct {
-                                    ty: field.ty.clone(),
+                                    ty: Box::new(field.ty.clone()),
                                 })
                             }
                             _ => panic!(
@@ -176,6 +176,10 @@ pub(crate) fn transform(input: TokenStream) -> TokenStream {
             impl #root::tags::TagValue<#name> for #name {
                 type Error = #root::tags::ParseTagValueError;
                 type Translator = #root::tags::TranslateSerde;
+
+                fn value_type() -> #root::tags::ValueType {
+                    #root::tags::ValueType::Json
+                }
             }
         },
         Translator::Manual => quote! {
@@ -193,6 +197,10 @@ pub(crate) fn transform(input: TokenStream) -> TokenStream {
                 impl #root::tags::TagValue<#name> for #name {
                     type Error = #root::tags::ParseTagValueError;
                     type Translator = #root::tags::TranslateManual;
+
+                    fn value_type() -> #root::tags::ValueType {
+                        <#ty as #root::tags::TagValue<#ty>>::value_type()
+                    }
                 }
 
                 impl TryFrom<#root::tags::RawTagValue> for #name {
@@ -211,6 +219,16 @@ pub(crate) fn transform(input: TokenStream) -> TokenStream {
             },
 
             TransparentKind::SimpleEnum { variants } => {
+                let values: Vec<String> = variants
+                    .iter()
+                    .map(|entry| {
+                        let (ref variant, ref rename) = *entry;
+                        rename
+                            .as_ref()
+                            .map_or_else(|| variant.to_string(), syn::LitStr::value)
+                    })
+                    .collect();
+
                 let (into_raw_tag_mapping, from_raw_tag_mapping): (Vec<_>, Vec<_>) = variants
                     .into_iter()
                     .map(|(variant, rename)| {
@@ -234,6 +252,10 @@ pub(crate) fn transform(input: TokenStream) -> TokenStream {
                     impl #root::tags::TagValue<#name> for #name {
                         type Error = #root::tags::ParseTagValueError;
                         type Translator = #root::tags::TranslateManual;
+
+                        fn value_type() -> #root::tags::ValueType {
+                            #root::tags::ValueType::Enum(vec![#(#values.to_owned()),*])
+                        }
                     }
 
                     impl From<#name> for #root::tags::RawTagValue {
@@ -1,17 +1,16 @@
 use proc_macro::TokenStream;
 use quote::quote;
 
+use crate::fields::{cfg_attrs, parse_type, take_field_attr, ElementKind};
+
 #[derive(Debug)]
 struct Input {
     ident: syn::Ident,
     vis: syn::Visibility,
+    attrs: Vec<syn::Attribute>,
     elements: Vec<Element>,
-}
-
-#[derive(Debug)]
-enum ElementKind {
-    Required,
-    Optional,
+    /// Implement `proptest::arbitrary::Arbitrary`, from `#[Tags(arbitrary)]`
+    arbitrary: bool,
 }
 
 #[derive(Debug)]
@@ -20,116 +19,120 @@ struct Element {
     vis: syn::Visibility,
     ty: syn::Path,
     kind: ElementKind,
-    name: String,
+    /// An expression of type `&'static str`
+    name: proc_macro2::TokenStream,
+    /// Keys that are read if `name` is not set, in order
+    fallbacks: Vec<FallbackKey>,
+    /// Values used if neither `name` nor a fallback key is set, in order
+    defaults: Vec<CfgDefault>,
     attrs: Vec<syn::Attribute>,
 }
 
-fn is_cfg_attribute(attr: &syn::Attribute) -> bool {
-    match attr.meta {
-        syn::Meta::List(ref meta_list) => meta_list.path.is_ident("cfg"),
-        _ => false,
-    }
+/// A key from `cfg_key(<predicate>, key = "...")`, only read if the cfg
+/// predicate is true
+#[derive(Debug)]
+struct FallbackKey {
+    predicates: Vec<syn::Meta>,
+    name: proc_macro2::TokenStream,
 }
 
-fn cfg_attrs(v: &[syn::Attribute]) -> Vec<&syn::Attribute> {
-    v.iter()
-        .filter(|attr: &&syn::Attribute| is_cfg_attribute(attr))
-        .collect()
+/// A raw tag value from `cfg_default(<predicate>, value = "...")`, only used
+/// if the cfg predicate is true
+#[derive(Debug)]
+struct CfgDefault {
+    predicates: Vec<syn::Meta>,
+    value: proc_macro2::TokenStream,
 }
 
-fn parse_type(input: syn::Type) -> (syn::Path, ElementKind) {
-    match input {
-        syn::Type::Path(ty) => {
-            let segments = ty.path.segments.clone();
-            let first = segments.first().expect("segments is empty");
-
-            let (ident, optional) = match first.ident.to_string().as_str() {
-                "Option" => match first.arguments {
-                    // It may not be required to parse this, we could just
-                    // extract the inner type and let the compiler beat the
-                    // caller up if there is some bullshit happening.
-                    syn::PathArguments::AngleBracketed(ref genargs) => {
-                        let mut args = genargs.args.clone();
-                        match genargs.args.len() {
-                            1 => {
-                                let ty = args.pop().expect("genargs are empty");
-                                let ty = match ty {
-                                    syn::punctuated::Pair::Punctuated(node, _punct) => node,
-                                    syn::punctuated::Pair::End(node) => node,
-                                };
-                                match ty {
-                                    syn::GenericArgument::Type(ty) => match ty {
-                                        syn::Type::Path(ty) => (ty, ElementKind::Optional),
-                                        _ => panic!("invalid generic type for Option"),
-                                    },
+#[derive(Debug, Default)]
+struct FieldAttrs {
+    name: Option<proc_macro2::TokenStream>,
+    fallbacks: Vec<FallbackKey>,
+    defaults: Vec<CfgDefault>,
+}
 
-                                    _ => panic!("need simple owned Option generic"),
-                                }
-                            }
-                            _ => panic!("wrong number of Option generic arguments"),
-                        }
-                    }
-                    _ => panic!("invalid Option usage"),
-                },
-                _ => (ty, ElementKind::Required),
-            };
+fn parse_metas(meta_list: &syn::MetaList) -> Vec<syn::Meta> {
+    match meta_list
+        .parse_args_with(syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+    {
+        Ok(metas) => metas.into_iter().collect(),
+        Err(e) => panic!("failed parsing tag field attribute: {e}"),
+    }
+}
 
-            (ident.path, optional)
-        }
-        _ => panic!("invalid field type"),
+fn literal_string(name: &str, value: &syn::Expr) -> proc_macro2::TokenStream {
+    match *value {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(ref lit_str),
+            ..
+        }) => quote!(#lit_str),
+        _ => panic!("right side of tag field attribute `{name}` not a string literal"),
     }
 }
 
-fn parse_field_attrs(attrs: &mut Vec<syn::Attribute>) -> Option<String> {
-    let index_of_tag_attribute = attrs
-        .iter()
-        .enumerate()
-        .filter(|&(_i, attr)| attr.style == syn::AttrStyle::Outer)
-        .find_map(|(i, attr)| match attr.meta {
-            syn::Meta::List(ref meta_list) => {
-                if meta_list.path.is_ident("tag") {
-                    Some((i, meta_list.clone()))
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        });
+/// Splits the metas of `cfg_key(...)` or `cfg_default(...)` into the cfg
+/// predicates and the string literal of the single `name = "..."`
+fn parse_cfg_list(list: &syn::MetaList, name: &str) -> (Vec<syn::Meta>, proc_macro2::TokenStream) {
+    let attr = list
+        .path
+        .get_ident()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+
+    let (values, predicates): (Vec<syn::Meta>, Vec<syn::Meta>) = parse_metas(list)
+        .into_iter()
+        .partition(|meta| meta.path().is_ident(name));
+
+    let value = match *values.as_slice() {
+        [syn::Meta::NameValue(ref name_value)] => literal_string(name, &name_value.value),
+        _ => panic!("`{attr}` needs exactly one `{name} = \"...\"`"),
+    };
+    assert!(!predicates.is_empty(), "`{attr}` needs a cfg predicate");
 
-    match index_of_tag_attribute {
-        Some((i, meta_list)) => {
-            let removed_attribute = attrs.remove(i);
-            drop(removed_attribute);
+    (predicates, value)
+}
 
-            let expr: syn::Expr = match meta_list.parse_args() {
-                Ok(expr) => expr,
-                Err(e) => panic!("failed parsing tag field attribute: {e}"),
-            };
+/// Parses `#[tag(key = "...")]` or `#[tag(key_from = <path or macro>)]`,
+/// followed by any number of `cfg_key(<predicate>, key = "...")` and
+/// `cfg_default(<predicate>, value = "...")`
+fn parse_tag_attrs(attrs: &mut Vec<syn::Attribute>) -> FieldAttrs {
+    let Some(meta_list) = take_field_attr(attrs, "tag") else {
+        return FieldAttrs::default();
+    };
 
-            let syn::Expr::Assign(assign) = expr else {
-                panic!("invalid expression in tag field attribute")
-            };
+    let mut field_attrs = FieldAttrs::default();
 
-            match *assign.left {
-                syn::Expr::Path(ref exprpath) => {
-                    assert!(
-                        exprpath.path.is_ident("key"),
-                        "invalid tag field attribute key"
-                    );
+    for meta in parse_metas(&meta_list) {
+        match meta {
+            syn::Meta::NameValue(ref name_value) if name_value.path.is_ident("key") => {
+                field_attrs.name = Some(literal_string("key", &name_value.value));
+            }
+            syn::Meta::NameValue(ref name_value) if name_value.path.is_ident("key_from") => {
+                let value = &name_value.value;
+                match *value {
+                    syn::Expr::Path(_) | syn::Expr::Macro(_) => {
+                        field_attrs.name = Some(quote!(#value));
+                    }
+                    _ => panic!(
+                        "right side of tag field attribute `key_from` must be a path to a constant or a macro call"
+                    ),
                 }
-                _ => panic!("invalid expression in tag field attribute, left side"),
             }
-
-            match *assign.right {
-                syn::Expr::Lit(ref expr_lit) => match expr_lit.lit {
-                    syn::Lit::Str(ref lit_str) => Some(lit_str.value()),
-                    _ => panic!("right side of tag field not a string literal"),
-                },
-                _ => panic!("right side of tag field attribute not a literal"),
+            syn::Meta::List(ref list) if list.path.is_ident("cfg_key") => {
+                let (predicates, name) = parse_cfg_list(list, "key");
+                field_attrs.fallbacks.push(FallbackKey { predicates, name });
+            }
+            syn::Meta::List(ref list) if list.path.is_ident("cfg_default") => {
+                let (predicates, value) = parse_cfg_list(list, "value");
+                field_attrs.defaults.push(CfgDefault { predicates, value });
             }
+            _ => panic!(
+                "invalid tag field attribute, expected `key`, `key_from`, `cfg_key` or `cfg_default`"
+            ),
         }
-        None => None,
     }
+
+    field_attrs
 }
 
 fn parse_fields(input: impl IntoIterator<Item = syn::Field>) -> Vec<Element> {
@@ -139,24 +142,31 @@ fn parse_fields(input: impl IntoIterator<Item = syn::Field>) -> Vec<Element> {
         let vis = field.vis;
         let (ty, kind) = parse_type(field.ty);
 
-        let name = parse_field_attrs(&mut field.attrs);
+        let field_attrs = parse_tag_attrs(&mut field.attrs);
 
         elements.push(Element {
             ident: ident.clone(),
             vis,
             ty,
             kind,
-            name: name.unwrap_or_else(|| ident.to_string()),
+            name: field_attrs.name.unwrap_or_else(|| {
+                let name = ident.to_string();
+                quote!(#name)
+            }),
+            fallbacks: field_attrs.fallbacks,
+            defaults: field_attrs.defaults,
             attrs: field.attrs,
         });
     }
     elements
 }
 
-fn parse_struct(input: syn::ItemStruct) -> Input {
+fn parse_struct(input: syn::ItemStruct, arbitrary: bool) -> Input {
     Input {
         ident: input.ident,
         vis: input.vis,
+        attrs: input.attrs,
+        arbitrary,
         elements: match input.fields {
             syn::Fields::Named(fields) => parse_fields(fields.named),
             _ => panic!("invalid fields"),
@@ -169,6 +179,7 @@ fn build_output(input: Input) -> TokenStream {
 
     let ident = input.ident;
     let vis = input.vis;
+    let struct_attrs = input.attrs;
 
     let type_definition = {
         let elements: Vec<proc_macro2::TokenStream> = input
@@ -198,7 +209,11 @@ fn build_output(input: Input) -> TokenStream {
             })
             .collect();
 
+        // Attributes of the struct (derives, docs) are kept, e.g. for the
+        // `Debug + Clone + PartialEq` that `assert_roundtrip()` needs
         quote! {
+            #(#struct_attrs)
+            *
             #vis struct #ident {
                 #(#elements),*
             }
@@ -243,6 +258,27 @@ fn build_output(input: Input) -> TokenStream {
             let ty = &element.ty;
             let tag_name = &element.name;
             let attrs = cfg_attrs(&element.attrs);
+            let fallbacks = element.fallbacks.iter().map(|fallback| {
+                let predicates = &fallback.predicates;
+                let fallback_name = &fallback.name;
+                quote! {
+                    #[cfg(all(#(#predicates),*))]
+                    let found = found.or_else(|| {
+                        tags.as_slice().iter().find(|tag| tag.key() == #fallback_name)
+                    });
+                }
+            });
+
+            let defaults = element.defaults.iter().map(|default| {
+                let predicates = &default.predicates;
+                let default_value = &default.value;
+                quote! {
+                    #[cfg(all(#(#predicates),*))]
+                    let value = value.or_else(|| {
+                        ::std::option::Option::Some(#root::tags::RawTagValue::new(#default_value.to_owned()))
+                    });
+                }
+            });
 
             let try_convert = quote! {
                 let value: ::std::result::Result<#ty, #root::tags::ParseTagsError> = <#ty as #root::tags::TagValue<#ty>>::from_raw_tag(value)
@@ -297,13 +333,19 @@ fn build_output(input: Input) -> TokenStream {
                 #(#attrs)
                 *
                 #ident: {
-                    let key: #root::tags::TagKey = #root::tags::TagKey::new(#tag_name.to_owned());
-
-                    let value: ::std::option::Option<#root::tags::RawTagValue> = tags
+                    let found: ::std::option::Option<&#root::tags::RawTag> = tags
                         .as_slice()
                         .iter()
-                        .find(|tag| tag.key() == #tag_name)
+                        .find(|tag| tag.key() == #tag_name);
+                    #(#fallbacks)*
+
+                    let key: #root::tags::TagKey = found.map_or_else(
+                        || #root::tags::TagKey::new(#tag_name.to_owned()),
+                        |tag| tag.key().clone(),
+                    );
+                    let value: ::std::option::Option<#root::tags::RawTagValue> = found
                         .map(|tag| tag.value()).cloned();
+                    #(#defaults)*
 
                     let value = {
                          #transformer
@@ -356,8 +398,46 @@ fn build_output(input: Input) -> TokenStream {
             })
             .collect();
 
+        let keys: Vec<proc_macro2::TokenStream> = input
+            .elements
+            .iter()
+            .map(|element| {
+                let tag_name = &element.name;
+                let attrs = cfg_attrs(&element.attrs);
+                quote! {
+                    #(#attrs)
+                    *
+                    #tag_name
+                }
+            })
+            .collect();
+
+        let schema_keys: Vec<proc_macro2::TokenStream> = input
+            .elements
+            .iter()
+            .map(|element| {
+                let ty = &element.ty;
+                let tag_name = &element.name;
+                let attrs = cfg_attrs(&element.attrs);
+                let required = matches!(element.kind, ElementKind::Required);
+                quote! {
+                    #(#attrs)
+                    *
+                    keys.push(#root::tags::SchemaKey::new(
+                        #tag_name,
+                        <#ty as #root::tags::TagValue<#ty>>::value_type(),
+                        #required,
+                    ));
+                }
+            })
+            .collect();
+
+        let name = ident.to_string();
+
         quote! {
             impl #ident {
+                #vis const KEYS: &'static [&'static str] = &[#(#keys),*];
+
                 #vis fn from_values(#(#params),*) -> Self {
                     Self {
                         #(#from_fields),*
@@ -375,33 +455,139 @@ fn build_output(input: Input) -> TokenStream {
                     {
                         #(#fields_to_tags);*;
                     }
-                    #root::tags::TagList::from_vec(v)
+                    #root::tags::TagList::from_vec(v).canonicalize()
+                }
+
+                #vis fn schema() -> #root::tags::Schema {
+                    let mut keys = ::std::vec::Vec::new();
+                    #(#schema_keys)*
+                    #root::tags::Schema::new(#name, keys)
                 }
             }
         }
     };
 
+    let arbitrary = if input.arbitrary {
+        build_arbitrary(&root, &ident, &input.elements)
+    } else {
+        quote! {}
+    };
+
     quote! {
         #type_definition
         #impls
+        #arbitrary
     }
     .into()
 }
 
-#[expect(
-    clippy::needless_pass_by_value,
-    reason = "this is the usual signature for proc macros, and the inner function should have the same signature"
-)]
+/// The strategy is built up one field at a time in a partial struct, so that
+/// fields can still be removed by `cfg` attributes
+fn build_arbitrary(
+    root: &proc_macro2::TokenStream,
+    ident: &syn::Ident,
+    elements: &[Element],
+) -> proc_macro2::TokenStream {
+    let proptest = quote! { #root::tags::proptest };
+
+    let partial_fields = elements.iter().map(|element| {
+        let ident = &element.ident;
+        let ty = &element.ty;
+        let attrs = cfg_attrs(&element.attrs);
+        match element.kind {
+            ElementKind::Required => quote! {
+                #(#attrs)
+                *
+                #ident: ::std::option::Option<#ty>
+            },
+            ElementKind::Optional => quote! {
+                #(#attrs)
+                *
+                #ident: ::std::option::Option<::std::option::Option<#ty>>
+            },
+        }
+    });
+
+    let steps = elements.iter().map(|element| {
+        let ident = &element.ident;
+        let ty = &element.ty;
+        let attrs = cfg_attrs(&element.attrs);
+        let field_strategy = match element.kind {
+            ElementKind::Required => quote! {
+                #proptest::arbitrary::any::<#ty>()
+            },
+            ElementKind::Optional => quote! {
+                #proptest::option::of(#proptest::arbitrary::any::<#ty>())
+            },
+        };
+        quote! {
+            #(#attrs)
+            *
+            let strategy = #proptest::strategy::Strategy::boxed(
+                #proptest::strategy::Strategy::prop_map(
+                    (strategy, #field_strategy),
+                    |(mut partial, value)| {
+                        partial.#ident = ::std::option::Option::Some(value);
+                        partial
+                    },
+                ),
+            );
+        }
+    });
+
+    let fields = elements.iter().map(|element| {
+        let ident = &element.ident;
+        let attrs = cfg_attrs(&element.attrs);
+        quote! {
+            #(#attrs)
+            *
+            #ident: partial.#ident.expect("every field is set by the strategy")
+        }
+    });
+
+    quote! {
+        const _: () = {
+            #[derive(Debug, Default)]
+            struct Partial {
+                #(#partial_fields),*
+            }
+
+            impl #proptest::arbitrary::Arbitrary for #ident {
+                type Parameters = ();
+                type Strategy = #proptest::strategy::BoxedStrategy<Self>;
+
+                fn arbitrary_with(_parameters: ()) -> Self::Strategy {
+                    let strategy = #proptest::strategy::Strategy::boxed(
+                        #proptest::strategy::LazyJust::new(Partial::default),
+                    );
+                    #(#steps)*
+                    #proptest::strategy::Strategy::boxed(
+                        #proptest::strategy::Strategy::prop_map(strategy, |partial| Self {
+                            #(#fields),*
+                        }),
+                    )
+                }
+            }
+        };
+    }
+}
+
 pub(crate) fn transform(attr: TokenStream, item: TokenStream) -> TokenStream {
-    assert!(
-        attr.is_empty(),
-        "cannot take any attribute macro attributes"
-    );
+    let arbitrary = if attr.is_empty() {
+        false
+    } else {
+        let attr = syn::parse_macro_input!(attr as syn::Path);
+        assert!(
+            attr.is_ident("arbitrary"),
+            "invalid attribute macro attribute, only `arbitrary` is supported"
+        );
+        true
+    };
 
     let input = syn::parse_macro_input!(item as syn::Item);
 
     let input = match input {
-        syn::Item::Struct(s) => parse_struct(s),
+        syn::Item::Struct(s) => parse_struct(s, arbitrary),
         _ => panic!("only applicable to structs"),
     };
 
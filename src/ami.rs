@@ -0,0 +1,323 @@
+//! AMI management and tag-based retention
+//!
+//! AMIs created by a build pipeline carry a `build-id` tag and optionally a
+//! `keep-until` tag, see [`AmiRetentionTags`]. [`prune_amis()`] deletes the
+//! AMIs that are stale according to a [`RetentionPolicy`], together with
+//! their snapshots. AMIs without a `build-id` are never touched, and neither
+//! are AMIs that running or stopped instances were launched from.
+
+use std::cmp::Reverse;
+
+use chrono::Duration;
+
+use super::{
+    ebs::SnapshotId,
+    filter::Filter,
+    tags::{ParseTagsError, TagList, Tags},
+    Ami, AmiId, Error, InstanceId, RegionClient, Timestamp,
+};
+
+/// Tags that control how long an AMI is kept, see [`stale_amis()`]
+#[Tags]
+pub struct AmiRetentionTags {
+    #[tag(key = "build-id")]
+    pub build_id: Option<String>,
+    #[tag(key = "keep-until")]
+    pub keep_until: Option<Timestamp>,
+}
+
+impl Ami {
+    /// Returns all AMIs owned by the account that match all `filters`
+    pub async fn list_owned(
+        client: &RegionClient,
+        filters: Vec<Filter>,
+    ) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_images()
+            .owners("self")
+            .set_filters(Some(filters.into_iter().map(Into::into).collect()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Creates an AMI from an instance. `tags` are set on the AMI and its
+    /// snapshots.
+    ///
+    /// Without `no_reboot`, the instance is rebooted to get a consistent
+    /// file system state.
+    pub async fn create(
+        client: &RegionClient,
+        instance: &InstanceId,
+        name: &str,
+        no_reboot: bool,
+        tags: TagList,
+    ) -> Result<AmiId, Error> {
+        let tag_specification = |resource_type| {
+            aws_sdk_ec2::types::TagSpecification::builder()
+                .resource_type(resource_type)
+                .set_tags(Some(tags.clone().into()))
+                .build()
+        };
+
+        Ok(AmiId(
+            client
+                .main
+                .ec2
+                .create_image()
+                .instance_id(instance.as_str())
+                .name(name)
+                .no_reboot(no_reboot)
+                .tag_specifications(tag_specification(aws_sdk_ec2::types::ResourceType::Image))
+                .tag_specifications(tag_specification(
+                    aws_sdk_ec2::types::ResourceType::Snapshot,
+                ))
+                .send()
+                .await?
+                .image_id
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "CreateImageOutput.image_id".to_owned(),
+                })?,
+        ))
+    }
+
+    /// Deregisters the AMI but keeps its snapshots
+    pub async fn deregister(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .deregister_image()
+            .image_id(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deregisters the AMI and deletes its snapshots. Snapshots can only be
+    /// deleted after the AMI is deregistered, so a failure may leave
+    /// orphaned snapshots behind.
+    pub async fn delete(&self, client: &RegionClient) -> Result<(), Error> {
+        self.deregister(client).await?;
+
+        for snapshot in &self.snapshots {
+            let _output = client
+                .main
+                .ec2
+                .delete_snapshot()
+                .snapshot_id(snapshot.as_str())
+                .send()
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub fn snapshots(&self) -> &[SnapshotId] {
+        &self.snapshots
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// The newest AMIs of each build that are always kept, regardless of
+    /// their tags
+    pub keep_latest: usize,
+    /// AMIs without `keep-until` tag that are older than this are stale.
+    /// If `None`, they are kept forever.
+    pub max_age: Option<Duration>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_latest: 3,
+            max_age: None,
+        }
+    }
+}
+
+type Candidate<'a> = (&'a Ami, Option<Timestamp>);
+
+/// Returns the AMIs that are stale according to `policy`
+///
+/// Only AMIs with a `build-id` tag are considered. AMIs are grouped by their
+/// `build-id`, and the `keep_latest` newest AMIs of each group are kept. Of
+/// the remaining ones, an AMI is stale if its `keep-until` tag lies before
+/// `now`, or if it has no `keep-until` tag and is older than the maximum age.
+///
+/// Fails if any AMI has malformed retention tags, so that a typo does not
+/// lead to AMIs being kept (or deleted) silently.
+pub fn stale_amis<'a>(
+    amis: &'a [Ami],
+    now: Timestamp,
+    policy: &RetentionPolicy,
+) -> Result<Vec<&'a Ami>, ParseTagsError> {
+    // The AMIs of each build ID, with their `keep-until` tag
+    let mut builds: Vec<(String, Vec<Candidate<'_>>)> = vec![];
+
+    for ami in amis {
+        let retention = AmiRetentionTags::from_tags(ami.tags.clone())?;
+        let Some(build_id) = retention.build_id else {
+            continue;
+        };
+
+        let entry = (ami, retention.keep_until);
+        match builds.iter_mut().find(|&&mut (ref id, _)| *id == build_id) {
+            Some(&mut (_, ref mut build)) => build.push(entry),
+            None => builds.push((build_id, vec![entry])),
+        }
+    }
+
+    let cutoff = policy
+        .max_age
+        .and_then(|max_age| now.inner().checked_sub_signed(max_age))
+        .map(Timestamp::new);
+
+    let mut stale = vec![];
+    for (_, mut build) in builds {
+        build.sort_by_key(|&(ami, _)| Reverse(ami.creation_date));
+
+        stale.extend(
+            build
+                .into_iter()
+                .skip(policy.keep_latest)
+                .filter(|&(ami, keep_until)| match keep_until {
+                    Some(keep_until) => keep_until < now,
+                    None => cutoff.is_some_and(|cutoff| ami.creation_date < cutoff),
+                })
+                .map(|(ami, _)| ami),
+        );
+    }
+
+    stale.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+    Ok(stale)
+}
+
+/// Deletes all owned AMIs that are stale according to `policy`, together
+/// with their snapshots, and returns them
+///
+/// AMIs that instances in the region were launched from are kept. With
+/// `dry_run`, nothing is deleted.
+pub async fn prune_amis(
+    client: &RegionClient,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+) -> Result<Vec<AmiId>, Error> {
+    let amis = Ami::list_owned(client, vec![Filter::tag_keys(AmiRetentionTags::KEYS)]).await?;
+
+    let in_use: Vec<String> = client
+        .main
+        .ec2
+        .describe_instances()
+        .filters(
+            Filter::new(
+                "instance-state-name",
+                ["pending", "running", "stopping", "stopped"],
+            )
+            .into(),
+        )
+        .into_paginator()
+        .items()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .flat_map(|reservation| reservation.instances.unwrap_or_default())
+        .filter_map(|instance| instance.image_id)
+        .collect();
+
+    let mut pruned = vec![];
+    for ami in stale_amis(&amis, Timestamp::now(), policy)? {
+        if in_use.iter().any(|image_id| image_id == ami.id.as_str()) {
+            continue;
+        }
+
+        if !dry_run {
+            ami.delete(client).await?;
+        }
+        pruned.push(ami.id.clone());
+    }
+
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::tags::RawTag;
+
+    fn ami(id: &str, day: u32, tags: &[(&str, &str)]) -> Ami {
+        Ami {
+            id: AmiId(id.to_owned()),
+            tags: TagList::from_vec(
+                tags.iter()
+                    .map(|&(key, value)| RawTag::new(key.to_owned(), value.to_owned()))
+                    .collect(),
+            ),
+            creation_date: Timestamp::new(Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()),
+            snapshots: vec![],
+        }
+    }
+
+    #[test]
+    fn select_stale_amis() {
+        let amis = vec![
+            ami("ami-newest", 10, &[("build-id", "web")]),
+            ami(
+                "ami-expired",
+                9,
+                &[("build-id", "web"), ("keep-until", "2024-02-01T00:00:00")],
+            ),
+            ami(
+                "ami-pinned",
+                8,
+                &[("build-id", "web"), ("keep-until", "2030-01-01T00:00:00")],
+            ),
+            ami("ami-old", 1, &[("build-id", "web")]),
+            ami("ami-other-build", 1, &[("build-id", "worker")]),
+            ami("ami-unmanaged", 1, &[]),
+        ];
+
+        let now = Timestamp::new(Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap());
+        let policy = RetentionPolicy {
+            keep_latest: 1,
+            max_age: Some(Duration::days(30)),
+        };
+
+        let stale = stale_amis(&amis, now, &policy).unwrap();
+        assert_eq!(
+            stale
+                .iter()
+                .map(|ami| ami.id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["ami-expired", "ami-old"]
+        );
+
+        let keep_all_untagged = RetentionPolicy {
+            keep_latest: 1,
+            max_age: None,
+        };
+        assert_eq!(stale_amis(&amis, now, &keep_all_untagged).unwrap().len(), 1);
+
+        let malformed = vec![ami(
+            "ami-malformed",
+            1,
+            &[("build-id", "web"), ("keep-until", "soon")],
+        )];
+        assert!(
+            stale_amis(&malformed, now, &policy).is_err(),
+            "invalid retention date"
+        );
+    }
+}
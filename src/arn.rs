@@ -0,0 +1,399 @@
+//! Amazon Resource Names
+//!
+//! ARNs have the form `arn:partition:service:region:account:resource`.
+//! Region and account are empty for some services (e.g. S3 buckets or IAM),
+//! and the resource may be prefixed with a resource type, separated by `/`
+//! or `:`.
+//!
+//! ```
+//! use aws_lib::arn::Arn;
+//!
+//! let arn = Arn::parse("arn:aws:iam::123456789012:role/service/deploy").unwrap();
+//! assert_eq!(arn.service(), "iam");
+//! assert_eq!(arn.region(), None);
+//! assert_eq!(arn.resource_type(), Some("role"));
+//! assert_eq!(arn.resource_id(), "service/deploy");
+//! assert_eq!(arn.resource_name(), "deploy");
+//!
+//! let function = Arn::builder()
+//!     .service("lambda")
+//!     .region("eu-central-1")
+//!     .account("123456789012")
+//!     .resource("function:handler")
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(
+//!     function.to_string(),
+//!     "arn:aws:lambda:eu-central-1:123456789012:function:handler"
+//! );
+//! ```
+//!
+//! Clients that take ARNs of one kind of resource use a newtype around
+//! [`Arn`], e.g. [`RdsArn`](crate::rds::RdsArn), so the ARNs returned by AWS
+//! are parsed and validated here.
+
+use std::{fmt, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::partition::Partition;
+
+/// Services whose resources are not prefixed with a resource type
+const UNTYPED_SERVICES: &[&str] = &["s3", "sns", "sqs"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseArnError {
+    MissingPrefix { value: String },
+    MissingParts { value: String },
+    EmptyField { value: String, field: &'static str },
+}
+
+impl std::error::Error for ParseArnError {}
+
+impl fmt::Display for ParseArnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::MissingPrefix { ref value } => {
+                write!(f, "\"{value}\" does not start with \"arn:\"")
+            }
+            Self::MissingParts { ref value } => {
+                write!(f, "\"{value}\" does not have six parts")
+            }
+            Self::EmptyField { ref value, field } => {
+                write!(f, "{field} of \"{value}\" is empty")
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Arn {
+    partition: String,
+    service: String,
+    region: Option<String>,
+    account: Option<String>,
+    resource: String,
+}
+
+impl Arn {
+    pub fn parse(value: &str) -> Result<Self, ParseArnError> {
+        let rest = value
+            .strip_prefix("arn:")
+            .ok_or_else(|| ParseArnError::MissingPrefix {
+                value: value.to_owned(),
+            })?;
+
+        // The resource may contain colons itself, so only split off the
+        // first four fields
+        let mut parts = rest.splitn(5, ':');
+        let (Some(partition), Some(service), Some(region), Some(account), Some(resource)) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return Err(ParseArnError::MissingParts {
+                value: value.to_owned(),
+            });
+        };
+
+        let non_empty = |field: &'static str, content: &str| {
+            if content.is_empty() {
+                Err(ParseArnError::EmptyField {
+                    value: value.to_owned(),
+                    field,
+                })
+            } else {
+                Ok(content.to_owned())
+            }
+        };
+
+        let optional = |content: &str| (!content.is_empty()).then(|| content.to_owned());
+
+        Ok(Self {
+            partition: non_empty("partition", partition)?,
+            service: non_empty("service", service)?,
+            region: optional(region),
+            account: optional(account),
+            resource: non_empty("resource", resource)?,
+        })
+    }
+
+    pub fn builder() -> ArnBuilder {
+        ArnBuilder::default()
+    }
+
+    pub fn partition(&self) -> &str {
+        &self.partition
+    }
+
+    /// The partition as enum, `None` for partitions this crate does not
+    /// know about (e.g. `aws-iso`)
+    pub fn known_partition(&self) -> Option<Partition> {
+        Partition::try_from(self.partition.as_str()).ok()
+    }
+
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+
+    /// Everything after the account, e.g. `role/service/deploy`
+    pub fn resource(&self) -> &str {
+        &self.resource
+    }
+
+    fn split_resource(&self) -> (Option<&str>, &str) {
+        if UNTYPED_SERVICES.contains(&self.service.as_str()) {
+            return (None, &self.resource);
+        }
+
+        // Whichever separator comes first splits off the type, e.g.
+        // "function:name:alias" or "instance/i-123"
+        match self.resource.find([':', '/']) {
+            Some(i) => match (
+                self.resource.get(..i),
+                self.resource.get(i.saturating_add(1)..),
+            ) {
+                (Some(kind), Some(id)) => (Some(kind), id),
+                _ => (None, &self.resource),
+            },
+            None => (None, &self.resource),
+        }
+    }
+
+    /// The resource type, e.g. `instance` or `function`. `None` for
+    /// services without resource types (S3, SNS, SQS) and for resources
+    /// without separator.
+    pub fn resource_type(&self) -> Option<&str> {
+        self.split_resource().0
+    }
+
+    /// The resource without its type, e.g. `i-123` for
+    /// `instance/i-123`. May contain further qualifiers like a Lambda alias
+    /// (`name:alias`), an IAM path (`path/name`) or an S3 key
+    /// (`bucket/key`).
+    pub fn resource_id(&self) -> &str {
+        self.split_resource().1
+    }
+
+    /// The last segment of the resource id, e.g. the role name without its
+    /// IAM path. For S3 object ARNs, this is the last segment of the key.
+    pub fn resource_name(&self) -> &str {
+        let id = self.resource_id();
+        id.rsplit('/').next().unwrap_or(id)
+    }
+}
+
+impl fmt::Display for Arn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "arn:{}:{}:{}:{}:{}",
+            self.partition,
+            self.service,
+            self.region.as_deref().unwrap_or_default(),
+            self.account.as_deref().unwrap_or_default(),
+            self.resource
+        )
+    }
+}
+
+impl FromStr for Arn {
+    type Err = ParseArnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for Arn {
+    type Error = ParseArnError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Arn> for String {
+    fn from(value: Arn) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArnBuilder {
+    partition: Option<String>,
+    service: Option<String>,
+    region: Option<String>,
+    account: Option<String>,
+    resource: Option<String>,
+}
+
+impl ArnBuilder {
+    /// Defaults to the partition of the region, or `aws` if no region is
+    /// set
+    #[must_use]
+    pub fn partition(mut self, partition: impl Into<String>) -> Self {
+        self.partition = Some(partition.into());
+        self
+    }
+
+    #[must_use]
+    pub fn service(mut self, service: impl Into<String>) -> Self {
+        self.service = Some(service.into());
+        self
+    }
+
+    #[must_use]
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    #[must_use]
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    /// The full resource including its type, e.g. `instance/i-123`
+    #[must_use]
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = Some(resource.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Arn, ParseArnError> {
+        let partition = self.partition.unwrap_or_else(|| {
+            self.region
+                .as_deref()
+                .map_or(Partition::Aws, Partition::from_region)
+                .as_str()
+                .to_owned()
+        });
+
+        let required = |field: &'static str, content: Option<String>| {
+            content
+                .filter(|content| !content.is_empty())
+                .ok_or_else(|| ParseArnError::EmptyField {
+                    value: format!("arn:{partition}:..."),
+                    field,
+                })
+        };
+
+        Ok(Arn {
+            service: required("service", self.service)?,
+            resource: required("resource", self.resource)?,
+            region: self.region.filter(|region| !region.is_empty()),
+            account: self.account.filter(|account| !account.is_empty()),
+            partition,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_resource_formats() {
+        let instance = Arn::parse("arn:aws:ec2:eu-central-1:123456789012:instance/i-123").unwrap();
+        assert_eq!(instance.region(), Some("eu-central-1"));
+        assert_eq!(instance.account(), Some("123456789012"));
+        assert_eq!(instance.resource_type(), Some("instance"));
+        assert_eq!(instance.resource_id(), "i-123");
+
+        let alias = Arn::parse("arn:aws:lambda:eu-central-1:123456789012:function:f:live").unwrap();
+        assert_eq!(alias.resource_type(), Some("function"));
+        assert_eq!(alias.resource_id(), "f:live");
+
+        let object = Arn::parse("arn:aws:s3:::bucket/path/to/key").unwrap();
+        assert_eq!(object.region(), None);
+        assert_eq!(object.resource_type(), None);
+        assert_eq!(object.resource_id(), "bucket/path/to/key");
+        assert_eq!(object.resource_name(), "key");
+
+        let queue = Arn::parse("arn:aws-cn:sqs:cn-north-1:123456789012:queue").unwrap();
+        assert_eq!(queue.partition(), "aws-cn");
+        assert_eq!(queue.resource_id(), "queue");
+    }
+
+    #[test]
+    fn roundtrip() {
+        for value in [
+            "arn:aws:iam::123456789012:role/deploy",
+            "arn:aws:rds:eu-central-1:123456789012:db:database-1",
+            "arn:aws:s3:::bucket",
+        ] {
+            assert_eq!(Arn::parse(value).unwrap().to_string(), value);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            Arn::parse("aws:iam::123:role/x"),
+            Err(ParseArnError::MissingPrefix { .. })
+        ));
+        assert!(matches!(
+            Arn::parse("arn:aws:iam"),
+            Err(ParseArnError::MissingParts { .. })
+        ));
+        assert!(matches!(
+            Arn::parse("arn:aws:::123:role/x"),
+            Err(ParseArnError::EmptyField {
+                field: "service",
+                ..
+            })
+        ));
+        assert!(
+            Arn::builder().service("s3").build().is_err(),
+            "missing resource"
+        );
+    }
+
+    #[test]
+    fn partition_from_region() {
+        let arn = Arn::builder()
+            .service("ec2")
+            .region("cn-north-1")
+            .account("123456789012")
+            .resource("instance/i-123")
+            .build()
+            .unwrap();
+
+        assert_eq!(arn.partition(), "aws-cn");
+        assert_eq!(arn.known_partition(), Some(Partition::AwsCn));
+    }
+
+    #[test]
+    fn resource_newtypes() {
+        let arn = crate::rds::RdsArn::parse("arn:aws-cn:rds:cn-north-1:123456789012:db:database-1")
+            .unwrap();
+        assert_eq!(arn.inner().known_partition(), Some(Partition::AwsCn));
+        assert_eq!(arn.inner().region(), Some("cn-north-1"));
+        assert_eq!(arn.inner().account(), Some("123456789012"));
+        assert_eq!(
+            arn.to_string(),
+            "arn:aws-cn:rds:cn-north-1:123456789012:db:database-1"
+        );
+        assert_eq!(Arn::from(arn).resource_id(), "database-1");
+
+        assert!(matches!(
+            crate::lambda::FunctionArn::parse("handler"),
+            Err(ParseArnError::MissingPrefix { .. })
+        ));
+    }
+}
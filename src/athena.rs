@@ -0,0 +1,657 @@
+//! Athena queries and workgroups
+//!
+//! [`start_query()`] submits a query, [`wait_for_query()`] polls it until it
+//! finishes, and [`rows()`] streams the result rows, converted into any type
+//! implementing [`FromRow`]. [`query()`] does all of that in one go:
+//!
+//! ```no_run
+//! # async fn f(client: &aws_lib::RegionClient) -> Result<(), aws_lib::Error> {
+//! use std::time::Duration;
+//!
+//! use aws_lib::{
+//!     athena::{query, QueryConfig, Row},
+//!     waiter::Waiter,
+//! };
+//!
+//! let config = QueryConfig {
+//!     database: Some("billing".to_owned()),
+//!     ..QueryConfig::default()
+//! };
+//!
+//! let rows: Vec<Row> = query(
+//!     client,
+//!     "SELECT owner, SUM(cost) AS cost FROM usage GROUP BY owner",
+//!     &config,
+//!     &Waiter::new(Duration::from_secs(300)),
+//! )
+//! .await?;
+//!
+//! for row in rows {
+//!     let cost: Option<f64> = row.parse("cost")?;
+//!     println!("{:?}: {cost:?}", row.get("owner"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Large results are faster to fetch as CSV directly from S3, see
+//! [`QueryExecution::download_results()`].
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use futures_util::{stream, Stream, TryStreamExt as _};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::waiter::{Match, Waiter};
+use super::{
+    arn::Arn,
+    organizations::AccountId,
+    s3::{Bucket, BucketName, ObjectBody, ObjectKey},
+    tags::{TagKey, TagList},
+    Error, Region, RegionClient,
+};
+
+string_newtype!(QueryExecutionId);
+
+impl QueryExecutionId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(WorkGroupName);
+
+impl WorkGroupName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Where and how a query runs. Everything left as `None` uses the defaults
+/// of the workgroup.
+#[derive(Debug, Clone, Default)]
+pub struct QueryConfig {
+    pub database: Option<String>,
+    pub catalog: Option<String>,
+    pub workgroup: Option<WorkGroupName>,
+    /// An S3 URL like `s3://bucket/prefix/`. Required if the workgroup does
+    /// not define an output location.
+    pub output_location: Option<String>,
+}
+
+pub async fn start_query(
+    client: &RegionClient,
+    sql: &str,
+    config: &QueryConfig,
+) -> Result<QueryExecutionId, Error> {
+    Ok(QueryExecutionId(
+        client
+            .main
+            .athena
+            .start_query_execution()
+            .query_string(sql)
+            .query_execution_context(
+                aws_sdk_athena::types::QueryExecutionContext::builder()
+                    .set_database(config.database.clone())
+                    .set_catalog(config.catalog.clone())
+                    .build(),
+            )
+            .set_work_group(
+                config
+                    .workgroup
+                    .as_ref()
+                    .map(|workgroup| workgroup.as_str().to_owned()),
+            )
+            .set_result_configuration(config.output_location.as_ref().map(|location| {
+                aws_sdk_athena::types::ResultConfiguration::builder()
+                    .output_location(location)
+                    .build()
+            }))
+            .send()
+            .await?
+            .query_execution_id
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "StartQueryExecutionOutput.query_execution_id".to_owned(),
+            })?,
+    ))
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum QueryState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl QueryState {
+    pub const fn is_finished(self) -> bool {
+        match self {
+            Self::Queued | Self::Running => false,
+            Self::Succeeded | Self::Failed | Self::Cancelled => true,
+        }
+    }
+}
+
+impl fmt::Display for QueryState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Self::Queued => "queued",
+                Self::Running => "running",
+                Self::Succeeded => "succeeded",
+                Self::Failed => "failed",
+                Self::Cancelled => "cancelled",
+            }
+        )
+    }
+}
+
+impl TryFrom<aws_sdk_athena::types::QueryExecutionState> for QueryState {
+    type Error = Error;
+
+    fn try_from(value: aws_sdk_athena::types::QueryExecutionState) -> Result<Self, Self::Error> {
+        use aws_sdk_athena::types::QueryExecutionState;
+
+        match value {
+            QueryExecutionState::Queued => Ok(Self::Queued),
+            QueryExecutionState::Running => Ok(Self::Running),
+            QueryExecutionState::Succeeded => Ok(Self::Succeeded),
+            QueryExecutionState::Failed => Ok(Self::Failed),
+            QueryExecutionState::Cancelled => Ok(Self::Cancelled),
+            _ => Err(Error::InvalidResponseError {
+                message: format!("unknown query execution state \"{}\"", value.as_str()),
+            }),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct QueryExecution {
+    id: QueryExecutionId,
+    state: QueryState,
+    state_reason: Option<String>,
+    output_location: Option<String>,
+    data_scanned_bytes: Option<i64>,
+}
+
+impl TryFrom<aws_sdk_athena::types::QueryExecution> for QueryExecution {
+    type Error = Error;
+
+    fn try_from(execution: aws_sdk_athena::types::QueryExecution) -> Result<Self, Self::Error> {
+        let status = execution.status.ok_or_else(|| Error::UnexpectedNoneValue {
+            entity: "QueryExecution.status".to_owned(),
+        })?;
+
+        Ok(Self {
+            id: QueryExecutionId(execution.query_execution_id.ok_or_else(|| {
+                Error::UnexpectedNoneValue {
+                    entity: "QueryExecution.query_execution_id".to_owned(),
+                }
+            })?),
+            state: status
+                .state
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "QueryExecutionStatus.state".to_owned(),
+                })?
+                .try_into()?,
+            state_reason: status.state_change_reason,
+            output_location: execution
+                .result_configuration
+                .and_then(|config| config.output_location),
+            data_scanned_bytes: execution
+                .statistics
+                .and_then(|statistics| statistics.data_scanned_in_bytes),
+        })
+    }
+}
+
+impl QueryExecution {
+    pub async fn describe(client: &RegionClient, id: &QueryExecutionId) -> Result<Self, Error> {
+        client
+            .main
+            .athena
+            .get_query_execution()
+            .query_execution_id(id.as_str())
+            .send()
+            .await?
+            .query_execution
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "GetQueryExecutionOutput.query_execution".to_owned(),
+            })?
+            .try_into()
+    }
+
+    pub const fn id(&self) -> &QueryExecutionId {
+        &self.id
+    }
+
+    pub const fn state(&self) -> QueryState {
+        self.state
+    }
+
+    /// Why the query failed or was cancelled
+    pub fn state_reason(&self) -> Option<&str> {
+        self.state_reason.as_deref()
+    }
+
+    /// The S3 URL of the result CSV file
+    pub fn output_location(&self) -> Option<&str> {
+        self.output_location.as_deref()
+    }
+
+    pub const fn data_scanned_bytes(&self) -> Option<i64> {
+        self.data_scanned_bytes
+    }
+
+    pub async fn cancel(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .athena
+            .stop_query_execution()
+            .query_execution_id(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Downloads the result file from S3. For `SELECT` queries, this is a
+    /// CSV file including a header line.
+    pub async fn download_results(&self, client: &RegionClient) -> Result<ObjectBody, Error> {
+        let location =
+            self.output_location
+                .as_deref()
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "QueryExecution.output_location".to_owned(),
+                })?;
+
+        let (bucket, key) =
+            parse_s3_location(location).ok_or_else(|| Error::InvalidResponseError {
+                message: format!("invalid query output location \"{location}\""),
+            })?;
+
+        Bucket::new(bucket).get_object(client, &key).await
+    }
+}
+
+/// Splits `s3://bucket/key` into bucket and key
+fn parse_s3_location(location: &str) -> Option<(BucketName, ObjectKey)> {
+    let (bucket, key) = location.strip_prefix("s3://")?.split_once('/')?;
+    (!bucket.is_empty() && !key.is_empty()).then(|| {
+        (
+            BucketName::new(bucket.to_owned()),
+            ObjectKey::new(key.to_owned()),
+        )
+    })
+}
+
+/// Polls the query until it is finished, backing off between polls
+///
+/// Fails with [`Error::WaiterFailure`] if the query failed or was cancelled.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn wait_for_query(
+    client: &RegionClient,
+    id: &QueryExecutionId,
+    waiter: &Waiter,
+) -> Result<QueryExecution, Error> {
+    waiter
+        .wait(
+            &format!("athena query {id} finished"),
+            || QueryExecution::describe(client, id),
+            |execution| match execution.state {
+                QueryState::Succeeded => Match::Done(execution),
+                QueryState::Failed | QueryState::Cancelled => Match::Failure(
+                    execution
+                        .state_reason
+                        .unwrap_or_else(|| format!("query {}", execution.state)),
+                ),
+                QueryState::Queued | QueryState::Running => Match::Retry,
+            },
+        )
+        .await
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    /// The Athena type, e.g. `varchar` or `bigint`
+    pub kind: String,
+}
+
+/// A result row. All values are returned as strings by Athena, `None` is
+/// `NULL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    columns: Arc<[Column]>,
+    values: Vec<Option<String>>,
+}
+
+impl Row {
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn values(&self) -> &[Option<String>] {
+        &self.values
+    }
+
+    /// The value of `column`, `None` if it is `NULL` or does not exist
+    pub fn get(&self, column: &str) -> Option<&str> {
+        self.columns
+            .iter()
+            .position(|c| c.name == column)
+            .and_then(|index| self.values.get(index))
+            .and_then(Option::as_deref)
+    }
+
+    /// Parses the value of `column`, `None` if it is `NULL`
+    pub fn parse<T>(&self, column: &str) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        if !self.columns.iter().any(|c| c.name == column) {
+            return Err(Error::InvalidQueryResult {
+                column: column.to_owned(),
+                message: "no such column".to_owned(),
+            });
+        }
+
+        self.get(column)
+            .map(|value| {
+                value.parse().map_err(|e| Error::InvalidQueryResult {
+                    column: column.to_owned(),
+                    message: format!("failed parsing \"{value}\": {e}"),
+                })
+            })
+            .transpose()
+    }
+}
+
+/// A type that can be built from a result row
+pub trait FromRow: Sized {
+    fn from_row(row: Row) -> Result<Self, Error>;
+}
+
+impl FromRow for Row {
+    fn from_row(row: Row) -> Result<Self, Error> {
+        Ok(row)
+    }
+}
+
+/// Converts a page of results. For `SELECT` queries, the first row of the
+/// first page repeats the column names and is skipped.
+fn page_rows(result_set: aws_sdk_athena::types::ResultSet, first_page: bool) -> Vec<Row> {
+    let columns: Arc<[Column]> = result_set
+        .result_set_metadata
+        .and_then(|metadata| metadata.column_info)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|info| Column {
+            name: info.name,
+            kind: info.r#type,
+        })
+        .collect();
+
+    let mut rows = result_set
+        .rows
+        .unwrap_or_default()
+        .into_iter()
+        .map(|row| Row {
+            columns: Arc::clone(&columns),
+            values: row
+                .data
+                .unwrap_or_default()
+                .into_iter()
+                .map(|datum| datum.var_char_value)
+                .collect(),
+        })
+        .peekable();
+
+    if first_page {
+        let _header = rows.next_if(|row| {
+            row.values
+                .iter()
+                .map(Option::as_deref)
+                .eq(columns.iter().map(|column| Some(column.name.as_str())))
+        });
+    }
+
+    rows.collect()
+}
+
+/// Streams the result rows of a finished query, fetching pages on demand
+pub fn rows<'a, T: FromRow + 'a>(
+    client: &'a RegionClient,
+    id: &'a QueryExecutionId,
+) -> impl Stream<Item = Result<T, Error>> + 'a {
+    enum Page {
+        First,
+        Next(String),
+        Done,
+    }
+
+    stream::try_unfold(Page::First, move |page| async move {
+        let token = match page {
+            Page::First => None,
+            Page::Next(token) => Some(token),
+            Page::Done => return Ok(None),
+        };
+        let first_page = token.is_none();
+
+        let output = client
+            .main
+            .athena
+            .get_query_results()
+            .query_execution_id(id.as_str())
+            .set_next_token(token)
+            .send()
+            .await?;
+
+        let rows = output
+            .result_set
+            .map(|result_set| page_rows(result_set, first_page))
+            .unwrap_or_default();
+
+        let next = output.next_token.map_or(Page::Done, Page::Next);
+
+        Ok::<_, Error>(Some((rows, next)))
+    })
+    .map_ok(|rows| stream::iter(rows.into_iter().map(T::from_row)))
+    .try_flatten()
+}
+
+/// Runs `sql`, waits for it to finish and collects all result rows
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn query<T: FromRow>(
+    client: &RegionClient,
+    sql: &str,
+    config: &QueryConfig,
+    waiter: &Waiter,
+) -> Result<Vec<T>, Error> {
+    let id = start_query(client, sql, config).await?;
+    let _execution = wait_for_query(client, &id, waiter).await?;
+    rows(client, &id).try_collect().await
+}
+
+pub async fn list_workgroups(client: &RegionClient) -> Result<Vec<WorkGroupName>, Error> {
+    Ok(client
+        .main
+        .athena
+        .list_work_groups()
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .flat_map(|page| page.work_groups.unwrap_or_default())
+        .filter_map(|workgroup| workgroup.name.map(WorkGroupName))
+        .collect())
+}
+
+/// A workgroup, identified by its ARN for tagging
+#[derive(Debug, Clone)]
+pub struct WorkGroup {
+    name: WorkGroupName,
+    arn: Arn,
+}
+
+impl WorkGroup {
+    pub fn new(name: WorkGroupName, region: Region, account: &AccountId) -> Result<Self, Error> {
+        let arn = Arn::builder()
+            .partition(region.partition().as_str())
+            .service("athena")
+            .region(region.as_str())
+            .account(account.as_str())
+            .resource(format!("workgroup/{name}"))
+            .build()?;
+
+        Ok(Self { name, arn })
+    }
+
+    pub const fn name(&self) -> &WorkGroupName {
+        &self.name
+    }
+
+    pub const fn arn(&self) -> &Arn {
+        &self.arn
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .main
+            .athena
+            .list_tags_for_resource()
+            .resource_arn(self.arn.to_string())
+            .send()
+            .await?
+            .tags
+            .unwrap_or_default()
+            .try_into()?)
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .athena
+            .tag_resource()
+            .resource_arn(self.arn.to_string())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .athena
+            .untag_resource()
+            .resource_arn(self.arn.to_string())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_athena::types::{ColumnInfo, Datum, ResultSet, ResultSetMetadata};
+
+    use super::*;
+
+    fn row(values: &[Option<&str>]) -> aws_sdk_athena::types::Row {
+        aws_sdk_athena::types::Row::builder()
+            .set_data(Some(
+                values
+                    .iter()
+                    .map(|value| {
+                        Datum::builder()
+                            .set_var_char_value(value.map(str::to_owned))
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .build()
+    }
+
+    fn result_set(rows: Vec<aws_sdk_athena::types::Row>) -> ResultSet {
+        ResultSet::builder()
+            .result_set_metadata(
+                ResultSetMetadata::builder()
+                    .column_info(
+                        ColumnInfo::builder()
+                            .name("owner")
+                            .r#type("varchar")
+                            .build()
+                            .unwrap(),
+                    )
+                    .column_info(
+                        ColumnInfo::builder()
+                            .name("cost")
+                            .r#type("double")
+                            .build()
+                            .unwrap(),
+                    )
+                    .build(),
+            )
+            .set_rows(Some(rows))
+            .build()
+    }
+
+    #[test]
+    fn convert_result_pages() {
+        let first = page_rows(
+            result_set(vec![
+                row(&[Some("owner"), Some("cost")]),
+                row(&[Some("team-a"), Some("12.5")]),
+                row(&[None, Some("3")]),
+            ]),
+            true,
+        );
+
+        assert_eq!(first.len(), 2);
+        let mut rows = first.iter();
+        let (a, b) = (rows.next().unwrap(), rows.next().unwrap());
+        assert_eq!(a.get("owner"), Some("team-a"));
+        assert_eq!(a.parse::<f64>("cost").unwrap(), Some(12.5_f64));
+        assert!(a.parse::<i64>("cost").is_err(), "not an integer");
+        assert_eq!(b.get("owner"), None);
+        assert!(b.parse::<f64>("missing").is_err(), "null value");
+
+        // Only the first page has a header row
+        let next = page_rows(result_set(vec![row(&[Some("owner"), Some("cost")])]), false);
+        assert_eq!(next.len(), 1);
+    }
+
+    #[test]
+    fn split_s3_location() {
+        let (bucket, key) = parse_s3_location("s3://results/athena/query.csv").unwrap();
+        assert_eq!(bucket.as_str(), "results");
+        assert_eq!(key.as_str(), "athena/query.csv");
+
+        assert!(parse_s3_location("s3://results/").is_none());
+        assert!(parse_s3_location("https://results/query.csv").is_none());
+    }
+}
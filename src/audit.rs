@@ -0,0 +1,1062 @@
+//! Audit log of mutating API calls
+//!
+//! An [`AuditSink`] is called once per mutating operation (anything that is
+//! not a `Describe*`, `List*`, `Get*`, ... call) after it finished,
+//! including all retries. The [`AuditEvent`] contains the service, action,
+//! resource IDs and tag changes found in the request, the access key that
+//! signed it together with its account and principal, and the outcome. Use
+//! [`ClientConfig::audit`](super::ClientConfig::audit) to get clients that
+//! report to a sink.
+//!
+//! [`JsonLinesSink`] writes one JSON document per event, e.g. to a file:
+//!
+//! ```no_run
+//! # fn f() -> std::io::Result<()> {
+//! use std::{fs::File, sync::Arc};
+//!
+//! use aws_lib::{audit::JsonLinesSink, ClientConfig};
+//!
+//! let file = File::options().create(true).append(true).open("audit.log")?;
+//! let client_config = ClientConfig {
+//!     audit: Some(Arc::new(JsonLinesSink::new(file))),
+//!     ..ClientConfig::default()
+//! };
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`CloudWatchLogsSink`] buffers events until they are flushed to a log
+//! stream.
+//!
+//! Resource IDs and tags are extracted from the request parameters by their
+//! names (`InstanceId`, `ResourceArn`, `Tag.1.Key`, ...), which covers the
+//! query and JSON protocols. For REST APIs like S3, the request path is
+//! recorded instead.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    io::Write,
+    iter::Peekable,
+    str::Chars,
+    sync::{Arc, Mutex},
+};
+
+use aws_credential_types::{
+    provider::{error::CredentialsError, future, ProvideCredentials},
+    Credentials,
+};
+use aws_sdk_ec2::config::SharedCredentialsProvider;
+use aws_smithy_runtime_api::{
+    box_error::BoxError,
+    client::{
+        interceptors::{
+            context::{BeforeTransmitInterceptorContextRef, FinalizerInterceptorContextRef},
+            Intercept,
+        },
+        orchestrator::Metadata,
+        runtime_components::RuntimeComponents,
+    },
+};
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+
+use super::{
+    logs::{LogEvent, LogStream},
+    metrics,
+    protocol::{RequestMetadata, Value},
+    sts::{self, CallerIdentity},
+    Error, RegionClient, Timestamp,
+};
+
+/// Operations with these prefixes only read state
+const READ_ONLY_PREFIXES: &[&str] = &[
+    "Describe", "List", "Get", "Head", "BatchGet", "Query", "Scan", "Search", "Select", "Lookup",
+    "Filter", "Receive", "Decode", "Estimate", "Preview", "Simulate", "Validate", "Check",
+    "Download",
+];
+
+/// Writing the audit log to CloudWatch Logs must not produce new events
+const IGNORED_OPERATIONS: &[&str] = &["PutLogEvents"];
+
+/// Operations with these prefixes remove tags, all others add or update them
+const TAG_REMOVAL_PREFIXES: &[&str] = &["Untag", "Remove", "Delete"];
+
+/// Limit of `PutLogEvents`
+const MAX_LOG_EVENTS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagChange {
+    Set { key: String, value: Option<String> },
+    Remove { key: String },
+}
+
+impl TagChange {
+    fn to_value(&self) -> Value {
+        match *self {
+            Self::Set { ref key, ref value } => Value::optional([
+                ("change", Some(Value::from("set"))),
+                ("key", Some(Value::from(key.as_str()))),
+                ("value", value.as_deref().map(Value::from)),
+            ]),
+            Self::Remove { ref key } => Value::structure([
+                ("change", Value::from("remove")),
+                ("key", Value::from(key.as_str())),
+            ]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditOutcome {
+    Success,
+    Failure {
+        /// `None` if no response was received
+        status: Option<u16>,
+        error_code: Option<String>,
+    },
+}
+
+/// A mutating API call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// When the operation finished
+    pub time: Timestamp,
+    /// The service, e.g. `ec2`
+    pub service: String,
+    /// The operation, e.g. `TerminateInstances`
+    pub action: String,
+    pub region: Option<String>,
+    pub resource_ids: Vec<String>,
+    pub tag_changes: Vec<TagChange>,
+    /// The access key of the credentials that signed the request
+    pub access_key_id: Option<String>,
+    /// The account and principal of the access key, resolved once per key
+    /// with `sts:GetCallerIdentity`. `None` if that failed.
+    pub caller: Option<CallerIdentity>,
+    pub request_id: Option<String>,
+    pub outcome: AuditOutcome,
+}
+
+impl AuditEvent {
+    pub fn to_json(&self) -> String {
+        let (status, error_code) = match self.outcome {
+            AuditOutcome::Success => (None, None),
+            AuditOutcome::Failure {
+                status,
+                ref error_code,
+            } => (status, error_code.as_deref()),
+        };
+
+        Value::optional([
+            (
+                "time",
+                Some(Value::from(
+                    self.time
+                        .inner()
+                        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                )),
+            ),
+            ("service", Some(Value::from(self.service.as_str()))),
+            ("action", Some(Value::from(self.action.as_str()))),
+            ("region", self.region.as_deref().map(Value::from)),
+            (
+                "resource_ids",
+                Some(Value::list(
+                    self.resource_ids.iter().map(|id| Value::from(id.as_str())),
+                )),
+            ),
+            (
+                "tag_changes",
+                Some(Value::list(
+                    self.tag_changes.iter().map(TagChange::to_value),
+                )),
+            ),
+            (
+                "access_key_id",
+                self.access_key_id.as_deref().map(Value::from),
+            ),
+            (
+                "account",
+                self.caller
+                    .as_ref()
+                    .map(|caller| Value::from(caller.account.to_string())),
+            ),
+            (
+                "caller_arn",
+                self.caller
+                    .as_ref()
+                    .map(|caller| Value::from(caller.arn.to_string())),
+            ),
+            ("request_id", self.request_id.as_deref().map(Value::from)),
+            (
+                "outcome",
+                Some(Value::from(match self.outcome {
+                    AuditOutcome::Success => "success",
+                    AuditOutcome::Failure { .. } => "failure",
+                })),
+            ),
+            (
+                "status",
+                status.map(|status| Value::from(i64::from(status))),
+            ),
+            ("error_code", error_code.map(Value::from)),
+        ])
+        .to_json()
+    }
+}
+
+/// Receives an event for each mutating operation
+///
+/// Called synchronously on the request path, so implementations should not
+/// block for long. Errors cannot be reported back to the caller of the
+/// operation and have to be handled by the sink itself.
+pub trait AuditSink: fmt::Debug + Send + Sync {
+    fn record(&self, event: &AuditEvent);
+}
+
+/// Writes each event as a line of JSON
+///
+/// Write errors are ignored, as the operation itself already happened.
+#[derive(Debug)]
+pub struct JsonLinesSink<W> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send + fmt::Debug> JsonLinesSink<W> {
+    pub const fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<W: Write + Send + fmt::Debug> AuditSink for JsonLinesSink<W> {
+    fn record(&self, event: &AuditEvent) {
+        if let Ok(mut writer) = self.writer.lock() {
+            let _result = writeln!(writer, "{}", event.to_json()).and_then(|()| writer.flush());
+        }
+    }
+}
+
+/// Buffers events to write them to CloudWatch Logs with
+/// [`CloudWatchLogsSink::flush()`]
+#[derive(Debug, Default)]
+pub struct CloudWatchLogsSink {
+    pending: Mutex<Vec<LogEvent>>,
+}
+
+impl CloudWatchLogsSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn take(&self) -> Vec<LogEvent> {
+        self.pending
+            .lock()
+            .map(|mut pending| std::mem::take(&mut *pending))
+            .unwrap_or_default()
+    }
+
+    fn requeue(&self, events: Vec<LogEvent>) {
+        if let Ok(mut pending) = self.pending.lock() {
+            let newer = std::mem::replace(&mut *pending, events);
+            pending.extend(newer);
+        }
+    }
+
+    /// Writes all buffered events to `stream`. Events that could not be
+    /// written stay buffered for the next flush.
+    pub async fn flush(&self, client: &RegionClient, stream: &mut LogStream) -> Result<(), Error> {
+        let mut events = self.take();
+        events.sort_by_key(|event| *event.timestamp());
+
+        while !events.is_empty() {
+            let rest = events.split_off(MAX_LOG_EVENTS.min(events.len()));
+            if let Err(e) = stream.put_events(client, events.clone()).await {
+                events.extend(rest);
+                self.requeue(events);
+                return Err(e);
+            }
+            events = rest;
+        }
+
+        Ok(())
+    }
+}
+
+impl AuditSink for CloudWatchLogsSink {
+    fn record(&self, event: &AuditEvent) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.push(LogEvent::new(event.time, event.to_json()));
+        }
+    }
+}
+
+/// Caller identities by access key ID
+#[derive(Debug, Default)]
+pub(crate) struct Identities(Mutex<HashMap<String, CallerIdentity>>);
+
+impl Identities {
+    fn get(&self, access_key_id: &str) -> Option<CallerIdentity> {
+        self.0
+            .lock()
+            .ok()
+            .and_then(|identities| identities.get(access_key_id).cloned())
+    }
+
+    fn insert(&self, access_key_id: String, identity: CallerIdentity) {
+        if let Ok(mut identities) = self.0.lock() {
+            let _previous = identities.insert(access_key_id, identity);
+        }
+    }
+}
+
+/// Wraps credentials providers in an [`IdentityResolver`], see
+/// [`RegionClient::with_config()`](crate::RegionClient::with_config()) for
+/// credentials that are set after the clients were built
+#[derive(Debug, Clone)]
+pub(crate) struct IdentityLookup {
+    /// Without interceptors, so lookups are not counted or audited
+    sts: aws_sdk_sts::Config,
+    identities: Arc<Identities>,
+}
+
+impl IdentityLookup {
+    pub(crate) fn new(config: &aws_config::SdkConfig, identities: &Arc<Identities>) -> Self {
+        Self {
+            sts: aws_sdk_sts::Config::from(config),
+            identities: Arc::clone(identities),
+        }
+    }
+
+    /// The same lookup, with STS in `region`
+    pub(crate) fn with_region(&self, region: &'static str) -> Self {
+        Self {
+            sts: self
+                .sts
+                .to_builder()
+                .region(aws_sdk_sts::config::Region::new(region))
+                .build(),
+            identities: Arc::clone(&self.identities),
+        }
+    }
+
+    pub(crate) fn wrap(&self, credentials: SharedCredentialsProvider) -> SharedCredentialsProvider {
+        SharedCredentialsProvider::new(IdentityResolver {
+            credentials,
+            lookup: self.clone(),
+        })
+    }
+
+    /// Returns `config` with its credentials provider wrapped, or unchanged
+    /// if it has none
+    pub(crate) fn wrap_config(&self, config: &aws_config::SdkConfig) -> aws_config::SdkConfig {
+        let Some(credentials) = config.credentials_provider() else {
+            return config.clone();
+        };
+
+        config
+            .to_builder()
+            .credentials_provider(self.wrap(credentials))
+            .build()
+    }
+}
+
+/// Wraps a credentials provider to look up the caller identity of each new
+/// access key before it signs the first request
+///
+/// Interceptors cannot send requests themselves, so the identity has to be
+/// known by the time [`AuditInterceptor`] records the event.
+#[derive(Debug)]
+struct IdentityResolver {
+    credentials: SharedCredentialsProvider,
+    lookup: IdentityLookup,
+}
+
+impl IdentityResolver {
+    async fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let credentials = self.credentials.provide_credentials().await?;
+        let identities = &self.lookup.identities;
+
+        if identities.get(credentials.access_key_id()).is_none() {
+            let sts = aws_sdk_sts::Client::from_conf(
+                self.lookup
+                    .sts
+                    .to_builder()
+                    .credentials_provider(SharedCredentialsProvider::new(credentials.clone()))
+                    .build(),
+            );
+
+            // The events of keys that cannot be resolved only carry the key,
+            // the next refresh of the credentials tries again
+            if let Ok(identity) = sts::identity_of(&sts).await {
+                identities.insert(credentials.access_key_id().to_owned(), identity);
+            }
+        }
+
+        Ok(credentials)
+    }
+}
+
+impl ProvideCredentials for IdentityResolver {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(self.credentials())
+    }
+}
+
+fn is_audited(operation: &str) -> bool {
+    !IGNORED_OPERATIONS.contains(&operation)
+        && !READ_ONLY_PREFIXES
+            .iter()
+            .any(|prefix| operation.starts_with(prefix))
+}
+
+/// Extracts the access key from a SigV4 `Authorization` header, e.g.
+/// `AWS4-HMAC-SHA256 Credential=AKIA.../20240101/us-east-1/ec2/aws4_request, ...`
+fn access_key_id(authorization: &str) -> Option<String> {
+    let (_, credential) = authorization.split_once("Credential=")?;
+    let (access_key_id, _) = credential.split_once('/')?;
+    Some(access_key_id.to_owned())
+}
+
+fn url_decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '+' => bytes.push(b' '),
+            '%' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) => bytes.push(byte),
+                    Err(_e) => {
+                        bytes.push(b'%');
+                        bytes.extend(hex.bytes());
+                    }
+                }
+            }
+            c => bytes.extend(c.encode_utf8(&mut [0; 4]).bytes()),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// A string parameter of a request, with the names of its enclosing
+/// members. List indices are left out.
+type Parameter = (Vec<String>, String);
+
+fn query_parameters(body: &str) -> Vec<Parameter> {
+    body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|&(name, _)| name != "Action" && name != "Version")
+        .map(|(name, value)| {
+            (
+                url_decode(name)
+                    .split('.')
+                    .filter(|segment| *segment != "member" && segment.parse::<u32>().is_err())
+                    .map(ToOwned::to_owned)
+                    .collect(),
+                url_decode(value),
+            )
+        })
+        .collect()
+}
+
+/// Collects the string values of a JSON document. Stops at the first syntax
+/// error, returning what was found so far.
+struct JsonWalker<'a> {
+    chars: Peekable<Chars<'a>>,
+    path: Vec<String>,
+    parameters: Vec<Parameter>,
+}
+
+impl JsonWalker<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let _quote = self.chars.next_if_eq(&'"')?;
+        let mut value = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(value),
+                '\\' => value.push(match self.chars.next()? {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'u' => {
+                        let hex: String = self.chars.by_ref().take(4).collect();
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .unwrap_or(char::REPLACEMENT_CHARACTER)
+                    }
+                    c => c,
+                }),
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<()> {
+        self.skip_whitespace();
+        match *self.chars.peek()? {
+            '{' => {
+                let _brace = self.chars.next();
+                self.skip_whitespace();
+                if self.chars.next_if_eq(&'}').is_some() {
+                    return Some(());
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    let _colon = self.chars.next_if_eq(&':')?;
+                    self.path.push(key);
+                    self.value()?;
+                    let _key = self.path.pop();
+                    self.skip_whitespace();
+                    match self.chars.next()? {
+                        ',' => {}
+                        '}' => return Some(()),
+                        _ => return None,
+                    }
+                }
+            }
+            '[' => {
+                let _bracket = self.chars.next();
+                self.skip_whitespace();
+                if self.chars.next_if_eq(&']').is_some() {
+                    return Some(());
+                }
+                loop {
+                    self.value()?;
+                    self.skip_whitespace();
+                    match self.chars.next()? {
+                        ',' => {}
+                        ']' => return Some(()),
+                        _ => return None,
+                    }
+                }
+            }
+            '"' => {
+                let value = self.string()?;
+                self.parameters.push((self.path.clone(), value));
+                Some(())
+            }
+            // Numbers, booleans and null
+            _ => {
+                while self
+                    .chars
+                    .next_if(|&c| !matches!(c, ',' | '}' | ']') && !c.is_whitespace())
+                    .is_some()
+                {}
+                Some(())
+            }
+        }
+    }
+}
+
+fn json_parameters(body: &str) -> Vec<Parameter> {
+    let mut walker = JsonWalker {
+        chars: body.chars().peekable(),
+        path: vec![],
+        parameters: vec![],
+    };
+    let _complete = walker.value();
+    walker.parameters
+}
+
+fn is_resource_id(name: &str) -> bool {
+    ["Id", "Ids", "ID", "Arn", "Arns", "ARN", "Identifier"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+fn resource_ids(parameters: &[Parameter]) -> Vec<String> {
+    let mut ids: Vec<String> = vec![];
+    for parameter in parameters {
+        let (ref path, ref value) = *parameter;
+        if path.last().is_some_and(|name| is_resource_id(name)) && !ids.contains(value) {
+            ids.push(value.clone());
+        }
+    }
+    ids
+}
+
+fn tag_changes(action: &str, parameters: &[Parameter]) -> Vec<TagChange> {
+    let removal = TAG_REMOVAL_PREFIXES
+        .iter()
+        .any(|prefix| action.starts_with(prefix));
+    let key_only = |key: String| {
+        if removal {
+            TagChange::Remove { key }
+        } else {
+            TagChange::Set { key, value: None }
+        }
+    };
+
+    let mut changes = vec![];
+    let mut pending_key: Option<String> = None;
+
+    for parameter in parameters {
+        let (ref path, ref value) = *parameter;
+        let Some((name, parents)) = path.split_last() else {
+            continue;
+        };
+        let in_tags = parents
+            .iter()
+            .any(|parent| parent.to_lowercase().contains("tag"));
+        let name = name.to_lowercase();
+
+        if in_tags && name == "key" {
+            if let Some(key) = pending_key.replace(value.clone()) {
+                changes.push(key_only(key));
+            }
+        } else if in_tags && name == "value" {
+            if let Some(key) = pending_key.take() {
+                changes.push(TagChange::Set {
+                    key,
+                    value: Some(value.clone()),
+                });
+            }
+        } else if name.contains("tagkey") {
+            changes.push(key_only(value.clone()));
+        } else if parents
+            .last()
+            .is_some_and(|parent| parent.eq_ignore_ascii_case("tags"))
+        {
+            // Tags as a map, e.g. `"Tags": {"owner": "team-a"}`
+            changes.push(TagChange::Set {
+                key: path.last().cloned().unwrap_or_default(),
+                value: Some(value.clone()),
+            });
+        } else {
+            // Not a tag parameter
+        }
+    }
+
+    if let Some(key) = pending_key {
+        changes.push(key_only(key));
+    }
+
+    changes
+}
+
+/// The parts of an event that are known before the request is sent
+#[derive(Debug, Clone)]
+struct PendingEvent {
+    service: String,
+    action: String,
+    resource_ids: Vec<String>,
+    tag_changes: Vec<TagChange>,
+    access_key_id: Option<String>,
+}
+
+impl Storable for PendingEvent {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Records an [`AuditEvent`] for each mutating operation. The region is
+/// taken from the config of each request, so it follows the region of
+/// [`RegionClient::with_config()`](crate::RegionClient::with_config()).
+#[derive(Debug)]
+pub(crate) struct AuditInterceptor {
+    sink: Arc<dyn AuditSink>,
+    identities: Arc<Identities>,
+}
+
+impl AuditInterceptor {
+    pub(crate) fn new(sink: Arc<dyn AuditSink>, identities: Arc<Identities>) -> Self {
+        Self { sink, identities }
+    }
+}
+
+impl Intercept for AuditInterceptor {
+    fn name(&self) -> &'static str {
+        "AuditInterceptor"
+    }
+
+    fn read_before_transmit(
+        &self,
+        context: &BeforeTransmitInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let Some((service, action)) = cfg
+            .load::<Metadata>()
+            .map(|metadata| (metadata.service().to_owned(), metadata.name().to_owned()))
+        else {
+            return Ok(());
+        };
+        if !is_audited(&action) {
+            return Ok(());
+        }
+
+        let request = context.request();
+        let body = request
+            .body()
+            .bytes()
+            .and_then(|body| std::str::from_utf8(body).ok())
+            .unwrap_or_default();
+        let content_type = request.headers().get("content-type").unwrap_or_default();
+
+        let parameters = if content_type.starts_with("application/x-www-form-urlencoded") {
+            query_parameters(body)
+        } else if content_type.contains("json") {
+            json_parameters(body)
+        } else {
+            vec![]
+        };
+
+        let mut resource_ids = resource_ids(&parameters);
+        let path = request
+            .uri()
+            .split_once("://")
+            .and_then(|(_, rest)| rest.split_once('/'))
+            .map(|(_, path)| path.split('?').next().unwrap_or(path))
+            .unwrap_or_default();
+        if !path.is_empty() {
+            resource_ids.push(format!("/{}", url_decode(path)));
+        }
+
+        let _layer = cfg.interceptor_state().store_put(PendingEvent {
+            tag_changes: tag_changes(&action, &parameters),
+            service,
+            action,
+            resource_ids,
+            access_key_id: request
+                .headers()
+                .get("authorization")
+                .and_then(access_key_id),
+        });
+
+        Ok(())
+    }
+
+    fn read_after_execution(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        // Operations that failed before transmission never reached AWS
+        let Some(pending) = cfg.load::<PendingEvent>().cloned() else {
+            return Ok(());
+        };
+
+        let outcome = match context.output_or_error() {
+            Some(Ok(_output)) => AuditOutcome::Success,
+            Some(Err(_)) | None => AuditOutcome::Failure {
+                status: context
+                    .response()
+                    .map(|response| response.status().as_u16()),
+                error_code: context.response().and_then(metrics::error_code),
+            },
+        };
+
+        self.sink.record(&AuditEvent {
+            time: Timestamp::now(),
+            service: pending.service,
+            action: pending.action,
+            region: cfg
+                .load::<aws_sdk_ec2::config::Region>()
+                .map(ToString::to_string),
+            resource_ids: pending.resource_ids,
+            tag_changes: pending.tag_changes,
+            caller: pending
+                .access_key_id
+                .as_deref()
+                .and_then(|access_key_id| self.identities.get(access_key_id)),
+            access_key_id: pending.access_key_id,
+            request_id: context
+                .response()
+                .and_then(|response| RequestMetadata::from_response(response).request_id),
+            outcome,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audited_operations() {
+        assert!(is_audited("TerminateInstances"));
+        assert!(is_audited("CreateTags"));
+        assert!(!is_audited("DescribeInstances"));
+        assert!(!is_audited("GetObject"));
+        assert!(!is_audited("PutLogEvents"));
+    }
+
+    #[test]
+    fn parse_access_key_id() {
+        assert_eq!(
+            access_key_id(
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240101/us-east-1/ec2/aws4_request, SignedHeaders=host, Signature=abc"
+            )
+            .as_deref(),
+            Some("AKIDEXAMPLE")
+        );
+        assert_eq!(access_key_id("Bearer token"), None);
+    }
+
+    #[test]
+    fn query_request() {
+        let parameters = query_parameters(
+            "Action=CreateTags&Version=2016-11-15&ResourceId.1=i-123&ResourceId.2=vol-456\
+             &Tag.1.Key=owner&Tag.1.Value=team%20a&Tag.2.Key=empty",
+        );
+
+        assert_eq!(resource_ids(&parameters), vec!["i-123", "vol-456"]);
+        assert_eq!(
+            tag_changes("CreateTags", &parameters),
+            vec![
+                TagChange::Set {
+                    key: "owner".to_owned(),
+                    value: Some("team a".to_owned())
+                },
+                TagChange::Set {
+                    key: "empty".to_owned(),
+                    value: None
+                },
+            ]
+        );
+
+        let parameters = query_parameters("Action=DeleteTags&ResourceId.1=i-123&Tag.1.Key=owner");
+        assert_eq!(
+            tag_changes("DeleteTags", &parameters),
+            vec![TagChange::Remove {
+                key: "owner".to_owned()
+            }]
+        );
+    }
+
+    #[test]
+    fn json_request() {
+        let parameters = json_parameters(
+            r#"{"ResourceArn": "arn:aws:sqs:eu-central-1:123456789012:queue",
+                "Tags": [{"Key": "owner", "Value": "team-a"}], "Count": 3, "Nested": {"Enabled": true}}"#,
+        );
+        assert_eq!(
+            resource_ids(&parameters),
+            vec!["arn:aws:sqs:eu-central-1:123456789012:queue"]
+        );
+        assert_eq!(
+            tag_changes("TagResource", &parameters),
+            vec![TagChange::Set {
+                key: "owner".to_owned(),
+                value: Some("team-a".to_owned())
+            }]
+        );
+
+        let parameters = json_parameters(r#"{"tags": {"owner": "team-a"}}"#);
+        assert_eq!(
+            tag_changes("TagResource", &parameters),
+            vec![TagChange::Set {
+                key: "owner".to_owned(),
+                value: Some("team-a".to_owned())
+            }]
+        );
+
+        let parameters = json_parameters(r#"{"resourceArn": "arn", "tagKeys": ["a", "b!"]}"#);
+        assert_eq!(
+            tag_changes("UntagResource", &parameters),
+            vec![
+                TagChange::Remove {
+                    key: "a".to_owned()
+                },
+                TagChange::Remove {
+                    key: "b!".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn event_json() {
+        let event = AuditEvent {
+            time: Timestamp::new(chrono::DateTime::from_timestamp(1_704_067_200, 0).unwrap()),
+            service: "ec2".to_owned(),
+            action: "StopInstances".to_owned(),
+            region: Some("eu-central-1".to_owned()),
+            resource_ids: vec!["i-123".to_owned()],
+            tag_changes: vec![],
+            access_key_id: Some("AKIDEXAMPLE".to_owned()),
+            caller: Some(CallerIdentity {
+                account: crate::organizations::AccountId::new("123456789012".to_owned()),
+                arn: crate::arn::Arn::parse("arn:aws:iam::123456789012:user/alice").unwrap(),
+                user_id: "AIDAEXAMPLE".to_owned(),
+            }),
+            request_id: None,
+            outcome: AuditOutcome::Failure {
+                status: Some(403),
+                error_code: Some("UnauthorizedOperation".to_owned()),
+            },
+        };
+
+        assert_eq!(
+            event.to_json(),
+            r#"{"time":"2024-01-01T00:00:00.000Z","service":"ec2","action":"StopInstances","region":"eu-central-1","resource_ids":["i-123"],"tag_changes":[],"access_key_id":"AKIDEXAMPLE","account":"123456789012","caller_arn":"arn:aws:iam::123456789012:user/alice","outcome":"failure","status":403,"error_code":"UnauthorizedOperation"}"#
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            testing::{block_on, mock_region_client_with, Matcher, MockHttpClient, MockResponse},
+            ClientConfig, ConfigOverride, Region, RegionClient,
+        };
+
+        #[derive(Debug, Default)]
+        struct Collect(Mutex<Vec<AuditEvent>>);
+
+        impl AuditSink for Collect {
+            fn record(&self, event: &AuditEvent) {
+                self.0.lock().unwrap().push(event.clone());
+            }
+        }
+
+        fn caller_identity(account: &str, user: &str) -> MockResponse {
+            MockResponse::ok(format!(
+                "<GetCallerIdentityResponse><GetCallerIdentityResult>\
+                 <Arn>arn:aws:iam::{account}:user/{user}</Arn>\
+                 <UserId>AIDAEXAMPLE</UserId><Account>{account}</Account>\
+                 </GetCallerIdentityResult></GetCallerIdentityResponse>"
+            ))
+        }
+
+        /// Adds answers for `GetCallerIdentity` of the mock credentials and
+        /// for `StopInstances` after `rules`
+        fn mock(rules: MockHttpClient) -> MockHttpClient {
+            rules
+                .on(
+                    Matcher::action("GetCallerIdentity"),
+                    caller_identity("123456789012", "alice"),
+                )
+                .on(
+                    Matcher::action("StopInstances"),
+                    MockResponse::ok(
+                        "<StopInstancesResponse><instancesSet/></StopInstancesResponse>",
+                    ),
+                )
+        }
+
+        fn audited_client(http: &MockHttpClient) -> (RegionClient, Arc<Collect>) {
+            let events = Arc::new(Collect::default());
+            let sink: Arc<dyn AuditSink> = Arc::clone(&events);
+            let client = mock_region_client_with(
+                Region::EuCentral1,
+                http.clone(),
+                &ClientConfig {
+                    audit: Some(sink),
+                    ..ClientConfig::default()
+                },
+            );
+            (client, events)
+        }
+
+        fn stop_instance(client: &RegionClient) {
+            let _output = block_on(
+                client
+                    .main
+                    .ec2
+                    .stop_instances()
+                    .instance_ids("i-123")
+                    .send(),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn caller_identity_once_per_key() {
+            let http = mock(MockHttpClient::new());
+            let (client, events) = audited_client(&http);
+
+            stop_instance(&client);
+            stop_instance(&client);
+
+            let lookups = http
+                .requests_matching(&Matcher::action("GetCallerIdentity"))
+                .unwrap();
+            assert_eq!(lookups.len(), 1);
+
+            let events = events.0.lock().unwrap();
+            assert_eq!(events.len(), 2);
+            for event in events.iter() {
+                assert_eq!(event.access_key_id.as_deref(), Some("AKIDMOCK"));
+                assert_eq!(event.region.as_deref(), Some("eu-central-1"));
+                let caller = event.caller.as_ref().unwrap();
+                assert_eq!(caller.account.to_string(), "123456789012");
+                assert_eq!(
+                    caller.arn.to_string(),
+                    "arn:aws:iam::123456789012:user/alice"
+                );
+            }
+        }
+
+        #[test]
+        fn region_override() {
+            let http = mock(MockHttpClient::new());
+            let (client, events) = audited_client(&http);
+
+            stop_instance(&client.with_config(&ConfigOverride {
+                region: Some(Region::UsEast1),
+                ..ConfigOverride::default()
+            }));
+
+            let events = events.0.lock().unwrap();
+            assert_eq!(
+                events
+                    .iter()
+                    .map(|event| event.region.as_deref())
+                    .collect::<Vec<Option<&str>>>(),
+                [Some("us-east-1")]
+            );
+        }
+
+        #[test]
+        fn credentials_override() {
+            let http = mock(MockHttpClient::new().on(
+                Matcher::All(vec![
+                    Matcher::action("GetCallerIdentity"),
+                    Matcher::predicate(|request| {
+                        request
+                            .header("authorization")
+                            .is_some_and(|authorization| authorization.contains("AKIDOTHER"))
+                    }),
+                ]),
+                caller_identity("210987654321", "bob"),
+            ));
+            let (client, events) = audited_client(&http);
+
+            stop_instance(&client.with_config(&ConfigOverride {
+                credentials: Some(SharedCredentialsProvider::new(Credentials::new(
+                    "AKIDOTHER",
+                    "other",
+                    None,
+                    None,
+                    "test",
+                ))),
+                ..ConfigOverride::default()
+            }));
+
+            let events = events.0.lock().unwrap();
+            let event = events.first().unwrap();
+            assert_eq!(event.access_key_id.as_deref(), Some("AKIDOTHER"));
+            let caller = event.caller.as_ref().unwrap();
+            assert_eq!(caller.account.to_string(), "210987654321");
+            assert_eq!(caller.arn.to_string(), "arn:aws:iam::210987654321:user/bob");
+        }
+    }
+}
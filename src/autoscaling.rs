@@ -0,0 +1,578 @@
+//! Managing Auto Scaling groups
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    filter::Filter,
+    tags::{ParseTagAwsError, ParseTagError, RawTag, Tag, TagKey, TagList},
+    Error, InstanceId, RegionClient,
+};
+
+const RESOURCE_TYPE_ASG: &str = "auto-scaling-group";
+
+string_newtype!(AutoScalingGroupName);
+
+impl AutoScalingGroupName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A tag of an Auto Scaling group.
+///
+/// In addition to key and value, ASG tags carry a flag that determines
+/// whether the tag is copied to instances launched by the group.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsgTag {
+    tag: RawTag,
+    propagate_at_launch: bool,
+}
+
+impl AsgTag {
+    pub const fn new(tag: RawTag, propagate_at_launch: bool) -> Self {
+        Self {
+            tag,
+            propagate_at_launch,
+        }
+    }
+
+    pub const fn tag(&self) -> &RawTag {
+        &self.tag
+    }
+
+    pub const fn propagate_at_launch(&self) -> bool {
+        self.propagate_at_launch
+    }
+
+    pub fn into_tag(self) -> RawTag {
+        self.tag
+    }
+
+    fn into_aws(self, group: &AutoScalingGroupName) -> aws_sdk_autoscaling::types::Tag {
+        aws_sdk_autoscaling::types::Tag::builder()
+            .resource_id(group.as_str())
+            .resource_type(RESOURCE_TYPE_ASG)
+            .key(self.tag.key().as_str())
+            .value(self.tag.value().as_str())
+            .propagate_at_launch(self.propagate_at_launch)
+            .build()
+    }
+}
+
+impl TryFrom<aws_sdk_autoscaling::types::TagDescription> for AsgTag {
+    type Error = ParseTagError;
+
+    fn try_from(tag: aws_sdk_autoscaling::types::TagDescription) -> Result<Self, Self::Error> {
+        let key = TagKey::new(tag.key.ok_or(ParseTagAwsError::AwsKeyNone)?);
+        let value = tag
+            .value
+            .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?;
+        Ok(Self {
+            tag: RawTag::new(key, value),
+            propagate_at_launch: tag.propagate_at_launch.unwrap_or(false),
+        })
+    }
+}
+
+/// The tags of an Auto Scaling group, see [`AsgTag`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AsgTagList(Vec<AsgTag>);
+
+impl AsgTagList {
+    pub const fn from_vec(value: Vec<AsgTag>) -> Self {
+        Self(value)
+    }
+
+    /// Uses the same propagation setting for all tags
+    pub fn from_tags(tags: TagList, propagate_at_launch: bool) -> Self {
+        Self(
+            tags.into_vec()
+                .into_iter()
+                .map(|tag| AsgTag::new(tag, propagate_at_launch))
+                .collect(),
+        )
+    }
+
+    pub fn as_slice(&self) -> &[AsgTag] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<AsgTag> {
+        self.0
+    }
+
+    /// Drops the propagation settings
+    pub fn into_tags(self) -> TagList {
+        TagList::from_vec(self.0.into_iter().map(AsgTag::into_tag).collect())
+    }
+
+    /// Returns only the tags that are propagated to instances
+    pub fn propagated(&self) -> TagList {
+        TagList::from_vec(
+            self.0
+                .iter()
+                .filter(|tag| tag.propagate_at_launch)
+                .map(|tag| tag.tag.clone())
+                .collect(),
+        )
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct AutoScalingGroup {
+    name: AutoScalingGroupName,
+    min_size: i32,
+    max_size: i32,
+    desired_capacity: i32,
+    instances: Vec<InstanceId>,
+    tags: AsgTagList,
+}
+
+impl TryFrom<aws_sdk_autoscaling::types::AutoScalingGroup> for AutoScalingGroup {
+    type Error = Error;
+
+    fn try_from(group: aws_sdk_autoscaling::types::AutoScalingGroup) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                group.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            name: AutoScalingGroupName(extract!(auto_scaling_group_name)?),
+            min_size: extract!(min_size)?,
+            max_size: extract!(max_size)?,
+            desired_capacity: extract!(desired_capacity)?,
+            instances: group
+                .instances
+                .unwrap_or_default()
+                .into_iter()
+                .map(|instance| {
+                    instance
+                        .instance_id
+                        .map(InstanceId)
+                        .ok_or_else(|| Error::UnexpectedNoneValue {
+                            entity: "Instance.instance_id".to_owned(),
+                        })
+                })
+                .collect::<Result<Vec<InstanceId>, Error>>()?,
+            tags: AsgTagList(
+                group
+                    .tags
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<AsgTag>, ParseTagError>>()?,
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleTransition {
+    Launching,
+    Terminating,
+}
+
+impl LifecycleTransition {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Launching => "autoscaling:EC2_INSTANCE_LAUNCHING",
+            Self::Terminating => "autoscaling:EC2_INSTANCE_TERMINATING",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleActionResult {
+    Continue,
+    Abandon,
+}
+
+impl LifecycleActionResult {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Continue => "CONTINUE",
+            Self::Abandon => "ABANDON",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LifecycleHook {
+    name: String,
+    transition: Option<String>,
+    heartbeat_timeout: Option<i32>,
+    default_result: Option<String>,
+}
+
+impl LifecycleHook {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn transition(&self) -> Option<&str> {
+        self.transition.as_deref()
+    }
+
+    pub const fn heartbeat_timeout(&self) -> Option<i32> {
+        self.heartbeat_timeout
+    }
+
+    pub fn default_result(&self) -> Option<&str> {
+        self.default_result.as_deref()
+    }
+}
+
+impl TryFrom<aws_sdk_autoscaling::types::LifecycleHook> for LifecycleHook {
+    type Error = Error;
+
+    fn try_from(hook: aws_sdk_autoscaling::types::LifecycleHook) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: hook
+                .lifecycle_hook_name
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "lifecycle_hook_name".to_owned(),
+                })?,
+            transition: hook.lifecycle_transition,
+            heartbeat_timeout: hook.heartbeat_timeout,
+            default_result: hook.default_result,
+        })
+    }
+}
+
+impl AutoScalingGroup {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .autoscaling
+            .describe_auto_scaling_groups()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Returns all groups matching all `filters`. Only tag filters are
+    /// supported by Auto Scaling.
+    pub async fn list_filtered(
+        client: &RegionClient,
+        filters: Vec<Filter>,
+    ) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .autoscaling
+            .describe_auto_scaling_groups()
+            .set_filters(Some(filters.into_iter().map(Into::into).collect()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_name(
+        client: &RegionClient,
+        name: &AutoScalingGroupName,
+    ) -> Result<Option<Self>, Error> {
+        let mut found = client
+            .main
+            .autoscaling
+            .describe_auto_scaling_groups()
+            .auto_scaling_group_names(name.as_str())
+            .send()
+            .await?
+            .auto_scaling_groups
+            .unwrap_or_default()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<Self>, Error>>()?;
+
+        match (found.len(), found.pop()) {
+            (0, _) => Ok(None),
+            (1, Some(found)) => Ok(Some(found)),
+            _ => Err(Error::MultipleMatches {
+                entity: "auto scaling group".to_owned(),
+            }),
+        }
+    }
+
+    pub const fn name(&self) -> &AutoScalingGroupName {
+        &self.name
+    }
+
+    pub const fn min_size(&self) -> i32 {
+        self.min_size
+    }
+
+    pub const fn max_size(&self) -> i32 {
+        self.max_size
+    }
+
+    pub const fn desired_capacity(&self) -> i32 {
+        self.desired_capacity
+    }
+
+    pub fn instances(&self) -> &[InstanceId] {
+        &self.instances
+    }
+
+    pub const fn tags(&self) -> &AsgTagList {
+        &self.tags
+    }
+
+    /// Creates the given tags, or updates their value and propagation flag
+    /// if they already exist
+    pub async fn create_or_update_tags(
+        &self,
+        client: &RegionClient,
+        tags: AsgTagList,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .autoscaling
+            .create_or_update_tags()
+            .set_tags(Some(
+                tags.0
+                    .into_iter()
+                    .map(|tag| tag.into_aws(&self.name))
+                    .collect(),
+            ))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .autoscaling
+            .delete_tags()
+            .set_tags(Some(
+                keys.into_iter()
+                    .map(|key| {
+                        aws_sdk_autoscaling::types::Tag::builder()
+                            .resource_id(self.name.as_str())
+                            .resource_type(RESOURCE_TYPE_ASG)
+                            .key(key.into_string())
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_desired_capacity(
+        &self,
+        client: &RegionClient,
+        capacity: i32,
+        honor_cooldown: bool,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .autoscaling
+            .set_desired_capacity()
+            .auto_scaling_group_name(self.name.as_str())
+            .desired_capacity(capacity)
+            .honor_cooldown(honor_cooldown)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn lifecycle_hooks(
+        &self,
+        client: &RegionClient,
+    ) -> Result<Vec<LifecycleHook>, Error> {
+        client
+            .main
+            .autoscaling
+            .describe_lifecycle_hooks()
+            .auto_scaling_group_name(self.name.as_str())
+            .send()
+            .await?
+            .lifecycle_hooks
+            .unwrap_or_default()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Creates or updates a lifecycle hook. If the hook times out,
+    /// `default_result` is applied.
+    pub async fn put_lifecycle_hook(
+        &self,
+        client: &RegionClient,
+        name: &str,
+        transition: LifecycleTransition,
+        heartbeat_timeout: i32,
+        default_result: LifecycleActionResult,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .autoscaling
+            .put_lifecycle_hook()
+            .auto_scaling_group_name(self.name.as_str())
+            .lifecycle_hook_name(name)
+            .lifecycle_transition(transition.as_str())
+            .heartbeat_timeout(heartbeat_timeout)
+            .default_result(default_result.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_lifecycle_hook(
+        &self,
+        client: &RegionClient,
+        name: &str,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .autoscaling
+            .delete_lifecycle_hook()
+            .auto_scaling_group_name(self.name.as_str())
+            .lifecycle_hook_name(name)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Extends the timeout of a pending lifecycle action
+    pub async fn record_lifecycle_heartbeat(
+        &self,
+        client: &RegionClient,
+        hook: &str,
+        instance: &InstanceId,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .autoscaling
+            .record_lifecycle_action_heartbeat()
+            .auto_scaling_group_name(self.name.as_str())
+            .lifecycle_hook_name(hook)
+            .instance_id(instance.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn complete_lifecycle_action(
+        &self,
+        client: &RegionClient,
+        hook: &str,
+        instance: &InstanceId,
+        result: LifecycleActionResult,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .autoscaling
+            .complete_lifecycle_action()
+            .auto_scaling_group_name(self.name.as_str())
+            .lifecycle_hook_name(hook)
+            .instance_id(instance.as_str())
+            .lifecycle_action_result(result.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_autoscaling::types::TagDescription;
+
+    use super::*;
+
+    fn raw_tag(key: &str, value: &str) -> RawTag {
+        RawTag::new(key.to_owned(), value.to_owned())
+    }
+
+    #[test]
+    fn asg_tag_to_aws() {
+        let group = AutoScalingGroupName::new("workers".to_owned());
+
+        let tag = AsgTag::new(raw_tag("team", "infra"), true).into_aws(&group);
+        assert_eq!(tag.resource_id(), Some("workers"));
+        assert_eq!(tag.resource_type(), Some(RESOURCE_TYPE_ASG));
+        assert_eq!(tag.key(), Some("team"));
+        assert_eq!(tag.value(), Some("infra"));
+        assert_eq!(tag.propagate_at_launch(), Some(true));
+
+        let tag = AsgTag::new(raw_tag("env", "prod"), false).into_aws(&group);
+        assert_eq!(tag.propagate_at_launch(), Some(false));
+    }
+
+    #[test]
+    fn asg_tag_from_description() {
+        let tag = AsgTag::try_from(
+            TagDescription::builder()
+                .resource_id("workers")
+                .resource_type(RESOURCE_TYPE_ASG)
+                .key("team")
+                .value("infra")
+                .propagate_at_launch(true)
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(tag, AsgTag::new(raw_tag("team", "infra"), true));
+
+        let tag =
+            AsgTag::try_from(TagDescription::builder().key("team").value("infra").build()).unwrap();
+        assert!(!tag.propagate_at_launch());
+
+        assert!(matches!(
+            AsgTag::try_from(TagDescription::builder().key("team").build()),
+            Err(ParseTagError::Aws(ParseTagAwsError::AwsValueNone { ref key }))
+                if key.as_str() == "team"
+        ));
+    }
+
+    #[test]
+    fn asg_tag_list_round_trip() {
+        let tags = TagList::from_vec(vec![raw_tag("team", "infra"), raw_tag("env", "prod")]);
+
+        let list = AsgTagList::from_tags(tags.clone(), true);
+        assert!(list.as_slice().iter().all(AsgTag::propagate_at_launch));
+        assert_eq!(list.propagated(), tags);
+        assert_eq!(list.into_tags(), tags);
+
+        let list = AsgTagList::from_vec(vec![
+            AsgTag::new(raw_tag("team", "infra"), true),
+            AsgTag::new(raw_tag("env", "prod"), false),
+        ]);
+        assert_eq!(
+            list.propagated(),
+            TagList::from_vec(vec![raw_tag("team", "infra")])
+        );
+        assert_eq!(list.into_tags(), tags);
+    }
+}
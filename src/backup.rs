@@ -0,0 +1,580 @@
+//! AWS Backup plans, vaults and recovery points
+//!
+//! The resources a backup plan protects are chosen by a [`Selection`], usually
+//! by tags. [`Selection::from_tags()`] turns the tags of a
+//! [`Tags`](crate::tags::Tags) struct into the conditions of a selection, so
+//! the same struct both tags the resources and selects them for backup:
+//!
+//! ```no_run
+//! # use aws_lib::{arn::Arn, RegionClient};
+//! use aws_lib::{
+//!     backup::{create_backup_selection, BackupPlanId, Selection},
+//!     tags::Tags,
+//! };
+//!
+//! #[Tags]
+//! struct Backup {
+//!     backup: bool,
+//! }
+//!
+//! # async fn f(client: &RegionClient, role: Arn) -> Result<(), aws_lib::Error> {
+//! let plan = BackupPlanId::new("0c1e6f4a-5d1b-4c7f-9d2b-8f6e0e3b7a21".to_owned());
+//! let selection = Selection::from_tags("tagged", role, &Backup { backup: true }.into_tags());
+//!
+//! create_backup_selection(client, &plan, selection).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+pub use aws_sdk_backup::types::RecoveryPointStatus;
+use aws_sdk_backup::types::{ConditionParameter, Conditions};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    arn::Arn,
+    tags::{Selector, TagList},
+    Error, RegionClient, Timestamp,
+};
+
+/// Prefix of the condition keys of a selection that refer to tags
+const TAG_CONDITION_PREFIX: &str = "aws:ResourceTag/";
+
+string_newtype!(VaultName);
+
+impl VaultName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(BackupPlanId);
+
+impl BackupPlanId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(BackupJobId);
+
+impl BackupJobId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn missing(entity: &str) -> Error {
+    Error::UnexpectedNoneValue {
+        entity: entity.to_owned(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupVault {
+    pub name: VaultName,
+    pub arn: String,
+    pub encryption_key_arn: Option<String>,
+    pub recovery_points: i64,
+    pub locked: bool,
+}
+
+pub async fn list_backup_vaults(client: &RegionClient) -> Result<Vec<BackupVault>, Error> {
+    client
+        .main
+        .backup
+        .list_backup_vaults()
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .flat_map(|page| page.backup_vault_list.unwrap_or_default())
+        .map(|vault| {
+            Ok(BackupVault {
+                name: VaultName(
+                    vault
+                        .backup_vault_name
+                        .ok_or_else(|| missing("BackupVaultListMember.backup_vault_name"))?,
+                ),
+                arn: vault
+                    .backup_vault_arn
+                    .ok_or_else(|| missing("BackupVaultListMember.backup_vault_arn"))?,
+                encryption_key_arn: vault.encryption_key_arn,
+                recovery_points: vault.number_of_recovery_points,
+                locked: vault.locked.unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+pub async fn describe_backup_vault(
+    client: &RegionClient,
+    name: &VaultName,
+) -> Result<BackupVault, Error> {
+    let output = client
+        .main
+        .backup
+        .describe_backup_vault()
+        .backup_vault_name(name.as_str())
+        .send()
+        .await?;
+
+    Ok(BackupVault {
+        name: name.clone(),
+        arn: output
+            .backup_vault_arn
+            .ok_or_else(|| missing("DescribeBackupVaultOutput.backup_vault_arn"))?,
+        encryption_key_arn: output.encryption_key_arn,
+        recovery_points: output.number_of_recovery_points,
+        locked: output.locked.unwrap_or(false),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPlanSummary {
+    pub id: BackupPlanId,
+    pub arn: String,
+    pub name: String,
+    pub version_id: String,
+}
+
+pub async fn list_backup_plans(client: &RegionClient) -> Result<Vec<BackupPlanSummary>, Error> {
+    client
+        .main
+        .backup
+        .list_backup_plans()
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .flat_map(|page| page.backup_plans_list.unwrap_or_default())
+        .map(|plan| {
+            Ok(BackupPlanSummary {
+                id: BackupPlanId(
+                    plan.backup_plan_id
+                        .ok_or_else(|| missing("BackupPlansListMember.backup_plan_id"))?,
+                ),
+                arn: plan
+                    .backup_plan_arn
+                    .ok_or_else(|| missing("BackupPlansListMember.backup_plan_arn"))?,
+                name: plan
+                    .backup_plan_name
+                    .ok_or_else(|| missing("BackupPlansListMember.backup_plan_name"))?,
+                version_id: plan
+                    .version_id
+                    .ok_or_else(|| missing("BackupPlansListMember.version_id"))?,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupRule {
+    pub name: String,
+    pub vault: VaultName,
+    /// A cron expression, e.g. `cron(0 5 ? * * *)`
+    pub schedule: Option<String>,
+    pub delete_after_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPlan {
+    pub id: BackupPlanId,
+    pub arn: String,
+    pub name: String,
+    pub version_id: String,
+    pub rules: Vec<BackupRule>,
+}
+
+/// The latest version of the plan
+pub async fn get_backup_plan(
+    client: &RegionClient,
+    id: &BackupPlanId,
+) -> Result<BackupPlan, Error> {
+    let output = client
+        .main
+        .backup
+        .get_backup_plan()
+        .backup_plan_id(id.as_str())
+        .send()
+        .await?;
+
+    let plan = output
+        .backup_plan
+        .ok_or_else(|| missing("GetBackupPlanOutput.backup_plan"))?;
+
+    Ok(BackupPlan {
+        id: id.clone(),
+        arn: output
+            .backup_plan_arn
+            .ok_or_else(|| missing("GetBackupPlanOutput.backup_plan_arn"))?,
+        name: plan.backup_plan_name,
+        version_id: output
+            .version_id
+            .ok_or_else(|| missing("GetBackupPlanOutput.version_id"))?,
+        rules: plan
+            .rules
+            .into_iter()
+            .map(|rule| BackupRule {
+                name: rule.rule_name,
+                vault: VaultName(rule.target_backup_vault_name),
+                schedule: rule.schedule_expression,
+                delete_after_days: rule
+                    .lifecycle
+                    .and_then(|lifecycle| lifecycle.delete_after_days),
+            })
+            .collect(),
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct RecoveryPoint {
+    pub arn: String,
+    pub resource_arn: Option<String>,
+    /// e.g. `EC2`, `EBS` or `RDS`
+    pub resource_type: Option<String>,
+    pub status: Option<RecoveryPointStatus>,
+    pub created: Option<Timestamp>,
+    pub size_bytes: Option<i64>,
+}
+
+/// The recovery points in the vault, optionally only those of one resource
+pub async fn list_recovery_points(
+    client: &RegionClient,
+    vault: &VaultName,
+    resource: Option<&Arn>,
+) -> Result<Vec<RecoveryPoint>, Error> {
+    client
+        .main
+        .backup
+        .list_recovery_points_by_backup_vault()
+        .backup_vault_name(vault.as_str())
+        .set_by_resource_arn(resource.map(ToString::to_string))
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .flat_map(|page| page.recovery_points.unwrap_or_default())
+        .map(|point| {
+            Ok(RecoveryPoint {
+                arn: point
+                    .recovery_point_arn
+                    .ok_or_else(|| missing("RecoveryPointByBackupVault.recovery_point_arn"))?,
+                resource_arn: point.resource_arn,
+                resource_type: point.resource_type,
+                status: point.status,
+                created: point.creation_date.map(Timestamp::try_from).transpose()?,
+                size_bytes: point.backup_size_in_bytes,
+            })
+        })
+        .collect()
+}
+
+/// Starts an on-demand backup of the resource into the vault. AWS Backup
+/// assumes `iam_role` to create the backup.
+pub async fn start_backup_job(
+    client: &RegionClient,
+    vault: &VaultName,
+    resource: &Arn,
+    iam_role: &Arn,
+) -> Result<BackupJobId, Error> {
+    Ok(BackupJobId(
+        client
+            .main
+            .backup
+            .start_backup_job()
+            .backup_vault_name(vault.as_str())
+            .resource_arn(resource.to_string())
+            .iam_role_arn(iam_role.to_string())
+            .send()
+            .await?
+            .backup_job_id
+            .ok_or_else(|| missing("StartBackupJobOutput.backup_job_id"))?,
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionOperator {
+    Equals,
+    NotEquals,
+    /// The value may contain `*` as a wildcard
+    Like,
+    NotLike,
+}
+
+/// A condition on a tag of the resources of a [`Selection`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCondition {
+    pub operator: ConditionOperator,
+    pub key: String,
+    pub value: String,
+}
+
+impl TagCondition {
+    fn into_parameter(self) -> ConditionParameter {
+        ConditionParameter::builder()
+            .condition_key(format!("{TAG_CONDITION_PREFIX}{}", self.key))
+            .condition_value(self.value)
+            .build()
+    }
+}
+
+/// The resources of a backup plan: all resources matching `resources` and
+/// all `conditions`, except those matching `not_resources`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub name: String,
+    pub iam_role: Arn,
+    /// ARNs, may contain `*` as a wildcard
+    pub resources: Vec<String>,
+    pub not_resources: Vec<String>,
+    pub conditions: Vec<TagCondition>,
+}
+
+impl Selection {
+    /// Selects all resources with the tags, e.g. the result of `into_tags()`
+    /// of a [`Tags`](crate::tags::Tags) struct
+    pub fn from_tags(name: impl Into<String>, iam_role: Arn, tags: &TagList) -> Self {
+        Self {
+            name: name.into(),
+            iam_role,
+            resources: vec!["*".to_owned()],
+            not_resources: Vec::new(),
+            conditions: tags
+                .as_slice()
+                .iter()
+                .map(|tag| TagCondition {
+                    operator: ConditionOperator::Equals,
+                    key: tag.key().as_str().to_owned(),
+                    value: tag.value().as_str().to_owned(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Selects all resources that match the selector. The conditions of a
+    /// selection cannot express alternatives, so tags of the selector have to
+    /// have a single value or just exist.
+    pub fn from_selector(
+        name: impl Into<String>,
+        iam_role: Arn,
+        selector: &Selector,
+    ) -> Result<Self, Error> {
+        let name = name.into();
+
+        let conditions = selector
+            .conditions()
+            .map(|(key, values)| {
+                let (operator, value) = match values {
+                    None => (ConditionOperator::Like, "*".to_owned()),
+                    Some(values) => {
+                        let [ref value] = *values else {
+                            return Err(Error::InvalidBackupSelection {
+                                selection: name.clone(),
+                                message: format!("tag {key} has to have exactly one value"),
+                            });
+                        };
+                        (ConditionOperator::Equals, value.clone())
+                    }
+                };
+                Ok(TagCondition {
+                    operator,
+                    key: key.to_owned(),
+                    value,
+                })
+            })
+            .collect::<Result<Vec<TagCondition>, Error>>()?;
+
+        Ok(Self {
+            name,
+            iam_role,
+            resources: vec!["*".to_owned()],
+            not_resources: Vec::new(),
+            conditions,
+        })
+    }
+
+    /// Only selects resources of a type, e.g. `arn:aws:rds:*:*:db:*`, instead
+    /// of all resources
+    #[must_use]
+    pub fn resources(mut self, resources: Vec<String>) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    #[must_use]
+    pub fn exclude(mut self, resource: impl Into<String>) -> Self {
+        self.not_resources.push(resource.into());
+        self
+    }
+
+    fn into_aws(self) -> aws_sdk_backup::types::BackupSelection {
+        let mut conditions = Conditions::builder();
+        for condition in self.conditions {
+            let operator = condition.operator;
+            let parameter = condition.into_parameter();
+            conditions = match operator {
+                ConditionOperator::Equals => conditions.string_equals(parameter),
+                ConditionOperator::NotEquals => conditions.string_not_equals(parameter),
+                ConditionOperator::Like => conditions.string_like(parameter),
+                ConditionOperator::NotLike => conditions.string_not_like(parameter),
+            };
+        }
+
+        aws_sdk_backup::types::BackupSelection::builder()
+            .selection_name(self.name)
+            .iam_role_arn(self.iam_role.to_string())
+            .set_resources(Some(self.resources))
+            .set_not_resources((!self.not_resources.is_empty()).then_some(self.not_resources))
+            .conditions(conditions.build())
+            .build()
+            .expect("builder misused")
+    }
+}
+
+/// Adds the selection to the plan and returns the id of the selection
+pub async fn create_backup_selection(
+    client: &RegionClient,
+    plan: &BackupPlanId,
+    selection: Selection,
+) -> Result<String, Error> {
+    client
+        .main
+        .backup
+        .create_backup_selection()
+        .backup_plan_id(plan.as_str())
+        .backup_selection(selection.into_aws())
+        .send()
+        .await?
+        .selection_id
+        .ok_or_else(|| missing("CreateBackupSelectionOutput.selection_id"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionSummary {
+    pub id: String,
+    pub name: String,
+}
+
+pub async fn list_backup_selections(
+    client: &RegionClient,
+    plan: &BackupPlanId,
+) -> Result<Vec<SelectionSummary>, Error> {
+    client
+        .main
+        .backup
+        .list_backup_selections()
+        .backup_plan_id(plan.as_str())
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .flat_map(|page| page.backup_selections_list.unwrap_or_default())
+        .map(|selection| {
+            Ok(SelectionSummary {
+                id: selection
+                    .selection_id
+                    .ok_or_else(|| missing("BackupSelectionsListMember.selection_id"))?,
+                name: selection
+                    .selection_name
+                    .ok_or_else(|| missing("BackupSelectionsListMember.selection_name"))?,
+            })
+        })
+        .collect()
+}
+
+pub async fn delete_backup_selection(
+    client: &RegionClient,
+    plan: &BackupPlanId,
+    selection_id: &str,
+) -> Result<(), Error> {
+    let _output = client
+        .main
+        .backup
+        .delete_backup_selection()
+        .backup_plan_id(plan.as_str())
+        .selection_id(selection_id)
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::RawTag;
+
+    fn role() -> Arn {
+        Arn::parse("arn:aws:iam::123456789012:role/backup").unwrap()
+    }
+
+    #[test]
+    fn selection_from_tags() {
+        let tags = TagList::from_vec(vec![RawTag::new("backup".to_owned(), "true".to_owned())]);
+        let selection = Selection::from_tags("tagged", role(), &tags).into_aws();
+
+        assert_eq!(selection.selection_name, "tagged");
+        assert_eq!(selection.resources(), ["*".to_owned()]);
+        let equals = selection
+            .conditions()
+            .map(Conditions::string_equals)
+            .unwrap_or_default();
+        assert_eq!(equals.len(), 1);
+        assert_eq!(
+            equals.first().and_then(ConditionParameter::condition_key),
+            Some("aws:ResourceTag/backup")
+        );
+        assert_eq!(
+            equals.first().and_then(ConditionParameter::condition_value),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn selection_from_selector() {
+        let selector = Selector::new()
+            .tag("env")
+            .eq("prod")
+            .and()
+            .tag("owner")
+            .exists();
+        let selection = Selection::from_selector("prod", role(), &selector).unwrap();
+
+        assert_eq!(
+            selection.conditions,
+            vec![
+                TagCondition {
+                    operator: ConditionOperator::Equals,
+                    key: "env".to_owned(),
+                    value: "prod".to_owned(),
+                },
+                TagCondition {
+                    operator: ConditionOperator::Like,
+                    key: "owner".to_owned(),
+                    value: "*".to_owned(),
+                },
+            ]
+        );
+
+        let alternatives = Selector::new().tag("env").any_of(["prod", "staging"]);
+        assert!(
+            Selection::from_selector("prod", role(), &alternatives).is_err(),
+            "multiple values for one key"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! Splitting of batch requests along the limits of an API
+
+use std::ops::Range;
+
+/// Splits records with the given sizes into consecutive batches of at most
+/// `max_records` records and `max_bytes` bytes
+///
+/// A record larger than `max_bytes` ends up in a batch of its own, callers
+/// have to reject those beforehand.
+pub(crate) fn batches(sizes: &[usize], max_records: usize, max_bytes: usize) -> Vec<Range<usize>> {
+    let mut batches = vec![];
+    let mut start = 0;
+    let mut bytes: usize = 0;
+
+    for (index, &size) in sizes.iter().enumerate() {
+        let count = index.saturating_sub(start);
+        if count > 0 && (count >= max_records || bytes.saturating_add(size) > max_bytes) {
+            batches.push(start..index);
+            start = index;
+            bytes = 0;
+        }
+        bytes = bytes.saturating_add(size);
+    }
+
+    if start < sizes.len() {
+        batches.push(start..sizes.len());
+    }
+
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_into_batches() {
+        assert_eq!(batches(&[1, 1, 1, 1, 1], 2, 100), vec![0..2, 2..4, 4..5]);
+        assert_eq!(
+            batches(&[60, 30, 20, 100, 5], 10, 100),
+            vec![0..2, 2..3, 3..4, 4..5]
+        );
+        assert_eq!(batches(&[200, 1], 10, 100), vec![0..1, 1..2]);
+        assert!(batches(&[], 10, 100).is_empty());
+    }
+}
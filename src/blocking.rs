@@ -0,0 +1,411 @@
+//! Blocking wrappers around the async API
+//!
+//! For CLI tools and build scripts that do not want to run an async runtime
+//! themselves. [`Client`] owns a single-threaded tokio runtime and drives a
+//! regular [`RegionClient`] on it, so configuration, signing and retries are
+//! the same as for the async API:
+//!
+//! ```no_run
+//! # fn f() -> Result<(), aws_lib::Error> {
+//! use aws_lib::{blocking::Client, config::ClientBuilder, Region};
+//!
+//! let clients = Client::load(ClientBuilder::default().region(Region::EuCentral1))?;
+//!
+//! for client in &clients {
+//!     for instance in client.ec2().instances(vec![])? {
+//!         println!("{}", instance.instance_id());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Operations without a wrapper can be run via [`Client::block_on()`]. None
+//! of the methods must be called from within an async runtime, as blocking
+//! there panics.
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use super::{
+    config::{AwsConfig, ClientBuilder},
+    filter::Filter,
+    s3::{Bucket, BucketName, Object, ObjectKey},
+    secretsmanager::{SecretId, SecretVersion, SecretVersionId, STAGE_CURRENT},
+    ssm::{Parameter, ParameterName, ParameterType},
+    tags::TagList,
+    Error, Instance, Region, RegionClient,
+};
+
+/// A [`RegionClient`] together with the runtime that drives it
+///
+/// Cloning is cheap, all clones and all clients loaded together share the
+/// same runtime.
+#[derive(Debug, Clone)]
+pub struct Client {
+    runtime: Arc<tokio::runtime::Runtime>,
+    inner: RegionClient,
+}
+
+impl Client {
+    /// Loads one client per region, see [`ClientBuilder::build()`]
+    pub fn load(builder: ClientBuilder) -> Result<Vec<Self>, Error> {
+        Self::from_config(&builder.load()?)
+    }
+
+    /// Loads one client per region, see [`AwsConfig::load_clients()`]
+    pub fn from_config(config: &AwsConfig) -> Result<Vec<Self>, Error> {
+        let runtime = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| Error::Runtime {
+                    message: e.to_string(),
+                })?,
+        );
+
+        Ok(runtime
+            .block_on(config.load_clients())
+            .into_iter()
+            .map(|inner| Self {
+                runtime: Arc::clone(&runtime),
+                inner,
+            })
+            .collect())
+    }
+
+    pub const fn region(&self) -> Region {
+        self.inner.region
+    }
+
+    /// The async client, for use with [`Self::block_on()`]
+    pub const fn inner(&self) -> &RegionClient {
+        &self.inner
+    }
+
+    /// Runs any future of the async API to completion
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    pub const fn ec2(&self) -> Ec2Client<'_> {
+        Ec2Client { client: self }
+    }
+
+    pub const fn s3(&self) -> S3Client<'_> {
+        S3Client { client: self }
+    }
+
+    pub const fn ssm(&self) -> SsmClient<'_> {
+        SsmClient { client: self }
+    }
+
+    pub const fn secretsmanager(&self) -> SecretsManagerClient<'_> {
+        SecretsManagerClient { client: self }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ec2Client<'a> {
+    client: &'a Client,
+}
+
+impl Ec2Client<'_> {
+    /// See [`Instance::list()`]
+    pub fn instances(&self, filters: Vec<Filter>) -> Result<Vec<Instance>, Error> {
+        self.client
+            .block_on(Instance::list(&self.client.inner, filters))
+    }
+
+    /// See [`Instance::stop()`]
+    pub fn stop_instance(&self, instance: &Instance) -> Result<(), Error> {
+        self.client.block_on(instance.stop(&self.client.inner))
+    }
+
+    /// See [`Instance::wait_for_stop()`]
+    pub fn wait_for_stop(&self, instance: &Instance, max_wait: Duration) -> Result<(), Error> {
+        self.client
+            .block_on(instance.wait_for_stop(&self.client.inner, max_wait))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct S3Client<'a> {
+    client: &'a Client,
+}
+
+impl S3Client<'_> {
+    pub fn buckets(&self) -> Result<Vec<Bucket>, Error> {
+        self.client.block_on(Bucket::list(&self.client.inner))
+    }
+
+    pub fn list_objects(
+        &self,
+        bucket: &Bucket,
+        prefix: Option<&str>,
+    ) -> Result<Vec<Object>, Error> {
+        self.client
+            .block_on(bucket.list_objects(&self.client.inner, prefix))
+    }
+
+    /// Reads the whole object into memory
+    pub fn get_object(&self, bucket: &BucketName, key: &ObjectKey) -> Result<Vec<u8>, Error> {
+        self.client.block_on(async {
+            Bucket::new(bucket.clone())
+                .get_object(&self.client.inner, key)
+                .await?
+                .collect()
+                .await
+        })
+    }
+
+    pub fn put_object(
+        &self,
+        bucket: &BucketName,
+        key: &ObjectKey,
+        body: Vec<u8>,
+    ) -> Result<(), Error> {
+        self.client
+            .block_on(Bucket::new(bucket.clone()).put_object(&self.client.inner, key, body))
+    }
+
+    pub fn delete_object(&self, bucket: &BucketName, key: &ObjectKey) -> Result<(), Error> {
+        self.client
+            .block_on(Bucket::new(bucket.clone()).delete_object(&self.client.inner, key))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SsmClient<'a> {
+    client: &'a Client,
+}
+
+impl SsmClient<'_> {
+    /// See [`Parameter::get()`]
+    pub fn parameter(
+        &self,
+        name: &ParameterName,
+        with_decryption: bool,
+    ) -> Result<Option<Parameter>, Error> {
+        self.client
+            .block_on(Parameter::get(&self.client.inner, name, with_decryption))
+    }
+
+    /// See [`Parameter::get_by_path()`]
+    pub fn parameters_by_path(
+        &self,
+        path: &str,
+        recursive: bool,
+        with_decryption: bool,
+    ) -> Result<Vec<Parameter>, Error> {
+        self.client.block_on(Parameter::get_by_path(
+            &self.client.inner,
+            path,
+            recursive,
+            with_decryption,
+        ))
+    }
+
+    /// See [`Parameter::put()`]
+    pub fn put_parameter(
+        &self,
+        name: &ParameterName,
+        value: String,
+        parameter_type: ParameterType,
+        overwrite: bool,
+        tags: Option<TagList>,
+    ) -> Result<i64, Error> {
+        self.client.block_on(Parameter::put(
+            &self.client.inner,
+            name,
+            value,
+            parameter_type,
+            overwrite,
+            tags,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SecretsManagerClient<'a> {
+    client: &'a Client,
+}
+
+impl SecretsManagerClient<'_> {
+    /// Returns the current version of the secret
+    pub fn secret_value(&self, id: &SecretId) -> Result<SecretVersion, Error> {
+        self.client
+            .block_on(id.get_value(&self.client.inner, STAGE_CURRENT))
+    }
+
+    /// See [`SecretId::put_value()`]
+    pub fn put_secret_value(&self, id: &SecretId, value: String) -> Result<SecretVersionId, Error> {
+        self.client
+            .block_on(id.put_value(&self.client.inner, value))
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        tags::RawTag,
+        testing::{mock_region_client, Matcher, MockHttpClient, MockResponse},
+    };
+
+    fn client(http: MockHttpClient) -> Client {
+        Client {
+            runtime: Arc::new(
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap(),
+            ),
+            inner: mock_region_client(Region::EuCentral1, http),
+        }
+    }
+
+    #[test]
+    fn parameters_by_path_follow_next_tokens() {
+        let http = MockHttpClient::new()
+            .on(
+                Matcher::param("NextToken", "page2"),
+                MockResponse::ok(
+                    r#"{"Parameters": [{"Name": "/app/port", "Value": "8080", "Version": 1}]}"#,
+                ),
+            )
+            .on(
+                Matcher::action("GetParametersByPath"),
+                MockResponse::ok(
+                    r#"{"Parameters": [{"Name": "/app/host", "Value": "db", "Version": 3}], "NextToken": "page2"}"#,
+                ),
+            );
+        let client = client(http.clone());
+
+        let parameters = client
+            .ssm()
+            .parameters_by_path("/app", true, false)
+            .unwrap();
+
+        assert_eq!(
+            parameters
+                .iter()
+                .map(|parameter| (parameter.name().as_str(), parameter.value()))
+                .collect::<Vec<_>>(),
+            [("/app/host", "db"), ("/app/port", "8080")]
+        );
+        let sent = http.requests().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert!(sent
+            .iter()
+            .all(|request| request.param("Recursive").as_deref() == Some("true")));
+    }
+
+    #[test]
+    fn missing_parameter_is_none() {
+        let http = MockHttpClient::new().on(
+            Matcher::action("GetParameter"),
+            MockResponse::status(400, r#"{"__type": "ParameterNotFound"}"#),
+        );
+        let client = client(http);
+
+        let parameter = client
+            .ssm()
+            .parameter(&ParameterName::new("/app/missing".to_owned()), false)
+            .unwrap();
+        assert!(parameter.is_none());
+    }
+
+    #[test]
+    fn put_parameter_sends_tags() {
+        let http = MockHttpClient::new().on(
+            Matcher::action("PutParameter"),
+            MockResponse::ok(r#"{"Version": 4}"#),
+        );
+        let client = client(http.clone());
+
+        let version = client
+            .ssm()
+            .put_parameter(
+                &ParameterName::new("/app/host".to_owned()),
+                "db".to_owned(),
+                ParameterType::String,
+                false,
+                Some(TagList::from_vec(vec![RawTag::new(
+                    "team".to_owned(),
+                    "infra".to_owned(),
+                )])),
+            )
+            .unwrap();
+
+        assert_eq!(version, 4);
+        let request = http.requests().unwrap().pop().unwrap();
+        assert_eq!(
+            request
+                .json_param::<Vec<HashMap<String, String>>>("Tags")
+                .unwrap(),
+            [HashMap::from([
+                ("Key".to_owned(), "team".to_owned()),
+                ("Value".to_owned(), "infra".to_owned()),
+            ])]
+        );
+    }
+
+    #[test]
+    fn secret_errors_are_returned() {
+        let http = MockHttpClient::new().on(
+            Matcher::action("GetSecretValue"),
+            MockResponse::status(
+                400,
+                r#"{"__type": "ResourceNotFoundException", "message": "not found"}"#,
+            ),
+        );
+        let client = client(http.clone());
+
+        let error = client
+            .secretsmanager()
+            .secret_value(&SecretId::new("missing".to_owned()))
+            .unwrap_err();
+
+        assert_eq!(
+            error.request_metadata().unwrap().error_code.as_deref(),
+            Some("ResourceNotFoundException")
+        );
+        assert_eq!(
+            http.requests()
+                .unwrap()
+                .pop()
+                .unwrap()
+                .param("VersionStage")
+                .as_deref(),
+            Some(STAGE_CURRENT)
+        );
+    }
+
+    #[test]
+    fn get_object_reads_the_body() {
+        let http = MockHttpClient::new().on(
+            Matcher::predicate(|request| request.method == "GET"),
+            MockResponse::ok("hello"),
+        );
+        let client = client(http.clone());
+
+        let body = client
+            .s3()
+            .get_object(
+                &BucketName::new("artifacts".to_owned()),
+                &ObjectKey::new("greeting.txt".to_owned()),
+            )
+            .unwrap();
+
+        assert_eq!(body, b"hello");
+        assert!(http
+            .requests()
+            .unwrap()
+            .pop()
+            .unwrap()
+            .uri
+            .contains("greeting.txt"));
+    }
+}
@@ -0,0 +1,788 @@
+//! CloudFormation stacks, their resources and drift detection
+//!
+//! Tags applied to a stack are propagated by CloudFormation to all resources
+//! of the stack that support tagging. Together with the [`macro@Tags`] macro,
+//! this allows applying a tag schema to a whole stack at once.
+//!
+//! [`macro@Tags`]: crate::tags::Tags
+
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    create_cloudformation_stack, tags::TagList, CloudformationParameters, Error, RegionClient,
+};
+
+string_newtype!(StackName);
+
+impl StackName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct StackOutput {
+    pub key: String,
+    pub value: String,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Stack {
+    id: Option<String>,
+    name: StackName,
+    status: String,
+    drift_status: Option<String>,
+    outputs: Vec<StackOutput>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_cloudformation::types::Stack> for Stack {
+    type Error = Error;
+
+    fn try_from(stack: aws_sdk_cloudformation::types::Stack) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                stack.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            id: stack.stack_id,
+            name: StackName(extract!(stack_name)?),
+            status: extract!(stack_status)?.as_str().to_owned(),
+            drift_status: stack
+                .drift_information
+                .and_then(|drift| drift.stack_drift_status)
+                .map(|status| status.as_str().to_owned()),
+            outputs: stack
+                .outputs
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|output| {
+                    Some(StackOutput {
+                        key: output.output_key?,
+                        value: output.output_value?,
+                    })
+                })
+                .collect(),
+            tags: stack.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct StackResource {
+    logical_id: String,
+    physical_id: Option<String>,
+    resource_type: String,
+    status: String,
+    drift_status: Option<String>,
+}
+
+impl TryFrom<aws_sdk_cloudformation::types::StackResourceSummary> for StackResource {
+    type Error = Error;
+
+    fn try_from(
+        resource: aws_sdk_cloudformation::types::StackResourceSummary,
+    ) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                resource.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            logical_id: extract!(logical_resource_id)?,
+            physical_id: resource.physical_resource_id,
+            resource_type: extract!(resource_type)?,
+            status: extract!(resource_status)?.as_str().to_owned(),
+            drift_status: resource
+                .drift_information
+                .and_then(|drift| drift.stack_resource_drift_status)
+                .map(|status| status.as_str().to_owned()),
+        })
+    }
+}
+
+impl StackResource {
+    pub fn logical_id(&self) -> &str {
+        &self.logical_id
+    }
+
+    pub fn physical_id(&self) -> Option<&str> {
+        self.physical_id.as_deref()
+    }
+
+    pub fn resource_type(&self) -> &str {
+        &self.resource_type
+    }
+
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    /// The drift status as of the last drift detection, if any
+    pub fn drift_status(&self) -> Option<&str> {
+        self.drift_status.as_deref()
+    }
+}
+
+/// A resource whose actual configuration differs from the template
+#[derive(Debug, Clone)]
+pub struct ResourceDrift {
+    pub logical_id: String,
+    pub physical_id: Option<String>,
+    pub resource_type: String,
+    pub status: String,
+    pub differences: Vec<PropertyDifference>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PropertyDifference {
+    pub path: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl TryFrom<aws_sdk_cloudformation::types::StackResourceDrift> for ResourceDrift {
+    type Error = Error;
+
+    fn try_from(
+        drift: aws_sdk_cloudformation::types::StackResourceDrift,
+    ) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($value:expr, $field:ident) => {
+                $value.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            logical_id: extract!(drift, logical_resource_id)?,
+            physical_id: drift.physical_resource_id,
+            resource_type: extract!(drift, resource_type)?,
+            status: extract!(drift, stack_resource_drift_status)?
+                .as_str()
+                .to_owned(),
+            differences: drift
+                .property_differences
+                .unwrap_or_default()
+                .into_iter()
+                .map(|difference| {
+                    Ok(PropertyDifference {
+                        path: extract!(difference, property_path)?,
+                        expected: difference.expected_value.unwrap_or_default(),
+                        actual: difference.actual_value.unwrap_or_default(),
+                    })
+                })
+                .collect::<Result<Vec<PropertyDifference>, Error>>()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriftDetectionId(String);
+
+#[derive(Debug, Clone)]
+pub struct DriftDetectionResult {
+    /// `IN_SYNC` or `DRIFTED`
+    pub status: String,
+    pub drifted_resources: i32,
+}
+
+impl Stack {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .cdn
+            .cloudformation
+            .describe_stacks()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_name(
+        client: &RegionClient,
+        name: &StackName,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .cdn
+            .cloudformation
+            .describe_stacks()
+            .stack_name(name.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => output
+                .stacks
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .map(TryInto::try_into)
+                .transpose(),
+            // CloudFormation does not have a dedicated error for missing
+            // stacks, only a generic validation error
+            Err(e) => match e
+                .as_service_error()
+                .and_then(|error| error.meta().message())
+            {
+                Some(message) if message.contains("does not exist") => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    /// Creates a stack. Use [`find_by_name()`](Self::find_by_name()) to follow
+    /// the progress of the creation.
+    pub async fn create(
+        client: &RegionClient,
+        name: &StackName,
+        template: &str,
+        parameters: &CloudformationParameters,
+        tags: &TagList,
+    ) -> Result<(), Error> {
+        create_cloudformation_stack(client, name.as_str(), template, parameters, tags).await
+    }
+
+    pub const fn name(&self) -> &StackName {
+        &self.name
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    /// The drift status as of the last drift detection, if any
+    pub fn drift_status(&self) -> Option<&str> {
+        self.drift_status.as_deref()
+    }
+
+    pub fn outputs(&self) -> &[StackOutput] {
+        &self.outputs
+    }
+
+    pub fn output(&self, key: &str) -> Option<&str> {
+        self.outputs
+            .iter()
+            .find(|output| output.key == key)
+            .map(|output| output.value.as_str())
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    /// Updates the stack with a new template, parameters and tags. Changed
+    /// tags are propagated to the resources of the stack.
+    pub async fn update(
+        &self,
+        client: &RegionClient,
+        template: &str,
+        parameters: &CloudformationParameters,
+        tags: &TagList,
+    ) -> Result<(), Error> {
+        let _output = client
+            .cdn
+            .cloudformation
+            .update_stack()
+            .stack_name(self.name.as_str())
+            .template_body(template)
+            .set_parameters(Some(parameters.into()))
+            .capabilities(aws_sdk_cloudformation::types::Capability::CapabilityAutoExpand)
+            .set_tags(Some(tags.clone().into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn resources(&self, client: &RegionClient) -> Result<Vec<StackResource>, Error> {
+        client
+            .cdn
+            .cloudformation
+            .list_stack_resources()
+            .stack_name(self.name.as_str())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Starts drift detection for the stack. Drift detection runs
+    /// asynchronously, use [`wait_for_drift_detection()`](Self::wait_for_drift_detection())
+    /// to get the result.
+    pub async fn detect_drift(&self, client: &RegionClient) -> Result<DriftDetectionId, Error> {
+        Ok(DriftDetectionId(
+            client
+                .cdn
+                .cloudformation
+                .detect_stack_drift()
+                .stack_name(self.name.as_str())
+                .send()
+                .await?
+                .stack_drift_detection_id
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "DetectStackDriftOutput.stack_drift_detection_id".to_owned(),
+                })?,
+        ))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait_for_drift_detection(
+        &self,
+        client: &RegionClient,
+        id: &DriftDetectionId,
+        poll_interval: Duration,
+        max_wait: Duration,
+    ) -> Result<DriftDetectionResult, Error> {
+        let start = Instant::now();
+
+        loop {
+            let output = client
+                .cdn
+                .cloudformation
+                .describe_stack_drift_detection_status()
+                .stack_drift_detection_id(&id.0)
+                .send()
+                .await?;
+
+            match output.detection_status {
+                Some(
+                    aws_sdk_cloudformation::types::StackDriftDetectionStatus::DetectionComplete,
+                ) => {
+                    return Ok(DriftDetectionResult {
+                        status: output
+                            .stack_drift_status
+                            .map(|status| status.as_str().to_owned())
+                            .unwrap_or_default(),
+                        drifted_resources: output.drifted_stack_resource_count.unwrap_or(0),
+                    })
+                }
+                Some(aws_sdk_cloudformation::types::StackDriftDetectionStatus::DetectionFailed) => {
+                    return Err(Error::DriftDetectionFailed {
+                        stack: self.name.to_string(),
+                        reason: output.detection_status_reason.unwrap_or_default(),
+                    })
+                }
+                _ => {
+                    if start.elapsed() >= max_wait {
+                        return Err(Error::DriftDetectionExceededMaxWait {
+                            stack: self.name.to_string(),
+                            max_wait,
+                        });
+                    }
+                    tokio::time::sleep(poll_interval).await;
+                }
+            }
+        }
+    }
+
+    /// Returns the resources that drifted as of the last drift detection
+    pub async fn resource_drifts(
+        &self,
+        client: &RegionClient,
+    ) -> Result<Vec<ResourceDrift>, Error> {
+        client
+            .cdn
+            .cloudformation
+            .describe_stack_resource_drifts()
+            .stack_name(self.name.as_str())
+            .stack_resource_drift_status_filters(
+                aws_sdk_cloudformation::types::StackResourceDriftStatus::Modified,
+            )
+            .stack_resource_drift_status_filters(
+                aws_sdk_cloudformation::types::StackResourceDriftStatus::Deleted,
+            )
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.stack_resource_drifts.unwrap_or_default())
+            .map(TryInto::try_into)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_cloudformation::types::{
+        Output, PropertyDifference as AwsPropertyDifference, StackDriftInformation,
+        StackDriftStatus, StackResourceDrift, StackResourceDriftStatus, StackResourceSummary,
+        StackStatus, Tag,
+    };
+
+    use super::*;
+
+    #[test]
+    fn stack_from_aws() {
+        let stack = Stack::try_from(
+            aws_sdk_cloudformation::types::Stack::builder()
+                .stack_id("arn:aws:cloudformation:eu-central-1:123456789012:stack/network/1")
+                .stack_name("network")
+                .stack_status(StackStatus::UpdateComplete)
+                .drift_information(
+                    StackDriftInformation::builder()
+                        .stack_drift_status(StackDriftStatus::Drifted)
+                        .build(),
+                )
+                .outputs(
+                    Output::builder()
+                        .output_key("VpcId")
+                        .output_value("vpc-0123")
+                        .build(),
+                )
+                .outputs(Output::builder().output_key("Incomplete").build())
+                .tags(Tag::builder().key("team").value("infra").build())
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(stack.name().as_str(), "network");
+        assert_eq!(stack.status(), "UPDATE_COMPLETE");
+        assert_eq!(stack.drift_status(), Some("DRIFTED"));
+        assert_eq!(stack.outputs().len(), 1);
+        assert_eq!(stack.output("VpcId"), Some("vpc-0123"));
+        assert_eq!(stack.output("Incomplete"), None);
+        assert_eq!(
+            stack
+                .tags()
+                .get("team".to_owned())
+                .map(|tag| tag.value().as_str()),
+            Some("infra")
+        );
+
+        assert!(matches!(
+            Stack::try_from(
+                aws_sdk_cloudformation::types::Stack::builder()
+                    .stack_name("network")
+                    .build()
+            ),
+            Err(Error::UnexpectedNoneValue { ref entity }) if entity == "stack_status"
+        ));
+    }
+
+    #[test]
+    fn resource_from_summary() {
+        let resource = StackResource::try_from(
+            StackResourceSummary::builder()
+                .logical_resource_id("Vpc")
+                .physical_resource_id("vpc-0123")
+                .resource_type("AWS::EC2::VPC")
+                .resource_status(aws_sdk_cloudformation::types::ResourceStatus::CreateComplete)
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(resource.logical_id(), "Vpc");
+        assert_eq!(resource.physical_id(), Some("vpc-0123"));
+        assert_eq!(resource.resource_type(), "AWS::EC2::VPC");
+        assert_eq!(resource.status(), "CREATE_COMPLETE");
+        assert_eq!(resource.drift_status(), None);
+    }
+
+    #[test]
+    fn drift_with_differences() {
+        let drift = ResourceDrift::try_from(
+            StackResourceDrift::builder()
+                .logical_resource_id("Bucket")
+                .resource_type("AWS::S3::Bucket")
+                .stack_resource_drift_status(StackResourceDriftStatus::Modified)
+                .property_differences(
+                    AwsPropertyDifference::builder()
+                        .property_path("/VersioningConfiguration/Status")
+                        .expected_value("Enabled")
+                        .actual_value("Suspended")
+                        .build(),
+                )
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(drift.status, "MODIFIED");
+        assert_eq!(drift.differences.len(), 1);
+        let difference = drift.differences.first().unwrap();
+        assert_eq!(difference.path, "/VersioningConfiguration/Status");
+        assert_eq!(difference.expected, "Enabled");
+        assert_eq!(difference.actual, "Suspended");
+
+        assert!(matches!(
+            ResourceDrift::try_from(
+                StackResourceDrift::builder()
+                    .logical_resource_id("Bucket")
+                    .resource_type("AWS::S3::Bucket")
+                    .stack_resource_drift_status(StackResourceDriftStatus::Modified)
+                    .property_differences(AwsPropertyDifference::builder().build())
+                    .build()
+            ),
+            Err(Error::UnexpectedNoneValue { ref entity }) if entity == "property_path"
+        ));
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        fn stacks(next_token: Option<&str>, name: &str) -> MockResponse {
+            MockResponse::ok(format!(
+                r#"<DescribeStacksResponse xmlns="http://cloudformation.amazonaws.com/doc/2010-05-15/">
+      <DescribeStacksResult>
+        <Stacks>
+          <member>
+            <StackId>arn:aws:cloudformation:eu-central-1:123456789012:stack/{name}/1</StackId>
+            <StackName>{name}</StackName>
+            <CreationTime>2024-01-01T00:00:00Z</CreationTime>
+            <StackStatus>CREATE_COMPLETE</StackStatus>
+            <Tags>
+              <member><Key>team</Key><Value>infra</Value></member>
+            </Tags>
+          </member>
+        </Stacks>
+        {}
+      </DescribeStacksResult>
+    </DescribeStacksResponse>"#,
+                next_token.map_or_else(String::new, |token| format!(
+                    "<NextToken>{token}</NextToken>"
+                ))
+            ))
+        }
+
+        fn validation_error(message: &str) -> MockResponse {
+            MockResponse::status(
+                400,
+                format!(
+                    r#"<ErrorResponse xmlns="http://cloudformation.amazonaws.com/doc/2010-05-15/">
+      <Error><Type>Sender</Type><Code>ValidationError</Code><Message>{message}</Message></Error>
+    </ErrorResponse>"#
+                ),
+            )
+        }
+
+        fn detection_status(status: &str, drift_status: &str) -> MockResponse {
+            MockResponse::ok(format!(
+                r#"<DescribeStackDriftDetectionStatusResponse xmlns="http://cloudformation.amazonaws.com/doc/2010-05-15/">
+      <DescribeStackDriftDetectionStatusResult>
+        <StackId>arn:aws:cloudformation:eu-central-1:123456789012:stack/network/1</StackId>
+        <StackDriftDetectionId>detection1</StackDriftDetectionId>
+        <DetectionStatus>{status}</DetectionStatus>
+        <DetectionStatusReason>access denied</DetectionStatusReason>
+        <StackDriftStatus>{drift_status}</StackDriftStatus>
+        <DriftedStackResourceCount>2</DriftedStackResourceCount>
+        <Timestamp>2024-01-01T00:00:00Z</Timestamp>
+      </DescribeStackDriftDetectionStatusResult>
+    </DescribeStackDriftDetectionStatusResponse>"#
+            ))
+        }
+
+        fn stack() -> Stack {
+            Stack::try_from(
+                aws_sdk_cloudformation::types::Stack::builder()
+                    .stack_name("network")
+                    .stack_status(StackStatus::CreateComplete)
+                    .build(),
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn list_follows_next_tokens() {
+            let http = MockHttpClient::new()
+                .on(Matcher::param("NextToken", "page2"), stacks(None, "app"))
+                .on(
+                    Matcher::action("DescribeStacks"),
+                    stacks(Some("page2"), "network"),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let stacks = block_on(Stack::list(&client)).unwrap();
+
+            assert_eq!(
+                stacks
+                    .iter()
+                    .map(|stack| stack.name().as_str())
+                    .collect::<Vec<_>>(),
+                ["network", "app"]
+            );
+            assert_eq!(http.requests().unwrap().len(), 2);
+            assert_eq!(
+                stacks.first().unwrap().tags().as_slice(),
+                [RawTag::new("team".to_owned(), "infra".to_owned())]
+            );
+        }
+
+        #[test]
+        fn missing_stack_is_none() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("StackName", "missing"),
+                    validation_error("Stack with id missing does not exist"),
+                )
+                .on(
+                    Matcher::action("DescribeStacks"),
+                    validation_error("1 validation error detected"),
+                );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let missing = block_on(Stack::find_by_name(
+                &client,
+                &StackName::new("missing".to_owned()),
+            ))
+            .unwrap();
+            assert!(missing.is_none());
+
+            let error = block_on(Stack::find_by_name(
+                &client,
+                &StackName::new("invalid name".to_owned()),
+            ))
+            .unwrap_err();
+            assert_eq!(error.request_metadata().unwrap().status, Some(400));
+        }
+
+        #[test]
+        fn drift_detection_is_polled_until_complete() {
+            let http = MockHttpClient::new()
+                .once(
+                    Matcher::action("DescribeStackDriftDetectionStatus"),
+                    detection_status("DETECTION_IN_PROGRESS", "UNKNOWN"),
+                )
+                .on(
+                    Matcher::action("DescribeStackDriftDetectionStatus"),
+                    detection_status("DETECTION_COMPLETE", "DRIFTED"),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let result = block_on(stack().wait_for_drift_detection(
+                &client,
+                &DriftDetectionId("detection1".to_owned()),
+                Duration::from_millis(1),
+                Duration::from_secs(10),
+            ))
+            .unwrap();
+
+            assert_eq!(result.status, "DRIFTED");
+            assert_eq!(result.drifted_resources, 2);
+            assert_eq!(http.requests().unwrap().len(), 2);
+            assert!(http.requests().unwrap().iter().all(|request| {
+                request.param("StackDriftDetectionId").as_deref() == Some("detection1")
+            }));
+        }
+
+        #[test]
+        fn failed_drift_detection_is_an_error() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeStackDriftDetectionStatus"),
+                detection_status("DETECTION_FAILED", "UNKNOWN"),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let error = block_on(stack().wait_for_drift_detection(
+                &client,
+                &DriftDetectionId("detection1".to_owned()),
+                Duration::from_millis(1),
+                Duration::from_secs(10),
+            ))
+            .unwrap_err();
+
+            assert!(matches!(
+                error,
+                Error::DriftDetectionFailed { ref stack, ref reason }
+                    if stack == "network" && reason == "access denied"
+            ));
+        }
+
+        #[test]
+        fn resource_drifts_are_filtered() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeStackResourceDrifts"),
+                MockResponse::ok(
+                    r#"<DescribeStackResourceDriftsResponse xmlns="http://cloudformation.amazonaws.com/doc/2010-05-15/">
+      <DescribeStackResourceDriftsResult>
+        <StackResourceDrifts>
+          <member>
+            <StackId>arn:aws:cloudformation:eu-central-1:123456789012:stack/network/1</StackId>
+            <LogicalResourceId>Bucket</LogicalResourceId>
+            <PhysicalResourceId>logs-bucket</PhysicalResourceId>
+            <ResourceType>AWS::S3::Bucket</ResourceType>
+            <StackResourceDriftStatus>MODIFIED</StackResourceDriftStatus>
+            <Timestamp>2024-01-01T00:00:00Z</Timestamp>
+            <PropertyDifferences>
+              <member>
+                <PropertyPath>/VersioningConfiguration/Status</PropertyPath>
+                <ExpectedValue>Enabled</ExpectedValue>
+                <ActualValue>Suspended</ActualValue>
+                <DifferenceType>NOT_EQUAL</DifferenceType>
+              </member>
+            </PropertyDifferences>
+          </member>
+        </StackResourceDrifts>
+      </DescribeStackResourceDriftsResult>
+    </DescribeStackResourceDriftsResponse>"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let drifts = block_on(stack().resource_drifts(&client)).unwrap();
+
+            let drift = drifts.first().unwrap();
+            assert_eq!(drift.logical_id, "Bucket");
+            assert_eq!(drift.status, "MODIFIED");
+            assert_eq!(
+                drift
+                    .differences
+                    .iter()
+                    .map(|difference| difference.actual.as_str())
+                    .collect::<Vec<_>>(),
+                ["Suspended"]
+            );
+
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(
+                request
+                    .param("StackResourceDriftStatusFilters.member.1")
+                    .as_deref(),
+                Some("MODIFIED")
+            );
+            assert_eq!(
+                request
+                    .param("StackResourceDriftStatusFilters.member.2")
+                    .as_deref(),
+                Some("DELETED")
+            );
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! CloudFront distributions, invalidations and distribution tags
+//!
+//! CloudFront is a global service, its API is only reachable via the `cdn`
+//! clients of [`RegionClient`].
+//!
+//! A single invalidation can contain at most [`MAX_INVALIDATION_PATHS`]
+//! paths. [`CloudfrontDistribution::invalidate()`] splits larger path lists
+//! into several invalidations, use [`waiter::invalidations_completed()`] to
+//! wait for all of them.
+//!
+//! [`waiter::invalidations_completed()`]: super::waiter::invalidations_completed
+
+use std::{collections::HashSet, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{TagKey, TagList},
+    CloudfrontDistribution, CloudfrontDistributionDomain, CloudfrontDistributionId,
+    CloudfrontDistributionStatus, CloudfrontOrigin, Error, RegionClient,
+};
+
+/// The maximum number of paths in a single invalidation
+pub const MAX_INVALIDATION_PATHS: usize = 3000;
+
+impl CloudfrontDistributionId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<aws_sdk_cloudfront::types::Distribution> for CloudfrontDistribution {
+    type Error = Error;
+
+    fn try_from(
+        distribution: aws_sdk_cloudfront::types::Distribution,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: CloudfrontDistributionId(distribution.id),
+            arn: distribution.arn,
+            status: distribution.status.into(),
+            domain: distribution.domain_name.into(),
+            origins: distribution
+                .distribution_config
+                .and_then(|config| config.origins)
+                .map_or_else(Vec::new, |origins| {
+                    origins.items.into_iter().map(Into::into).collect()
+                }),
+        })
+    }
+}
+
+impl CloudfrontDistribution {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        let mut distributions = vec![];
+        let mut marker = None;
+
+        loop {
+            let Some(list) = client
+                .cdn
+                .cloudfront
+                .list_distributions()
+                .set_marker(marker)
+                .send()
+                .await?
+                .distribution_list
+            else {
+                break;
+            };
+
+            for distribution in list.items.unwrap_or_default() {
+                distributions.push(distribution.try_into()?);
+            }
+
+            marker = list.next_marker;
+            if !list.is_truncated || marker.is_none() {
+                break;
+            }
+        }
+
+        Ok(distributions)
+    }
+
+    pub async fn describe(
+        client: &RegionClient,
+        id: &CloudfrontDistributionId,
+    ) -> Result<Self, Error> {
+        client
+            .cdn
+            .cloudfront
+            .get_distribution()
+            .id(id.as_str())
+            .send()
+            .await?
+            .distribution
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "GetDistributionResult.Distribution".to_owned(),
+            })?
+            .try_into()
+    }
+
+    pub fn origins(&self) -> &[CloudfrontOrigin] {
+        &self.origins
+    }
+
+    pub const fn domain(&self) -> &CloudfrontDistributionDomain {
+        &self.domain
+    }
+
+    pub const fn status(&self) -> &CloudfrontDistributionStatus {
+        &self.status
+    }
+
+    /// Invalidates `paths`, creating one invalidation per batch of at most
+    /// [`MAX_INVALIDATION_PATHS`] paths, see [`invalidation_batches()`]
+    ///
+    /// Returns the IDs of all created invalidations. If creating a batch
+    /// fails, the batches before it have already been submitted.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "only expect() on builder instances"
+    )]
+    pub async fn invalidate<I, S>(
+        &self,
+        client: &RegionClient,
+        paths: I,
+    ) -> Result<Vec<InvalidationId>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let reference = chrono::Utc::now().timestamp_millis();
+
+        let mut ids = vec![];
+        for (index, batch) in invalidation_batches(paths).into_iter().enumerate() {
+            let quantity = i32::try_from(batch.len()).unwrap_or(i32::MAX);
+
+            let invalidation = client
+                .cdn
+                .cloudfront
+                .create_invalidation()
+                .distribution_id(self.id.as_str())
+                .invalidation_batch(
+                    aws_sdk_cloudfront::types::InvalidationBatch::builder()
+                        .paths(
+                            aws_sdk_cloudfront::types::Paths::builder()
+                                .quantity(quantity)
+                                .set_items(Some(batch))
+                                .build()
+                                .expect("builder misused"),
+                        )
+                        .caller_reference(format!("aws-lib-{reference}-{index}"))
+                        .build()
+                        .expect("builder misused"),
+                )
+                .send()
+                .await?
+                .invalidation
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "CreateInvalidationResult.Invalidation".to_owned(),
+                })?;
+
+            ids.push(InvalidationId(invalidation.id));
+        }
+
+        Ok(ids)
+    }
+
+    pub async fn invalidation(
+        &self,
+        client: &RegionClient,
+        id: &InvalidationId,
+    ) -> Result<Invalidation, Error> {
+        let invalidation = client
+            .cdn
+            .cloudfront
+            .get_invalidation()
+            .distribution_id(self.id.as_str())
+            .id(id.as_str())
+            .send()
+            .await?
+            .invalidation
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "GetInvalidationResult.Invalidation".to_owned(),
+            })?;
+
+        Ok(Invalidation {
+            id: InvalidationId(invalidation.id),
+            status: invalidation.status.into(),
+        })
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .cdn
+            .cloudfront
+            .list_tags_for_resource()
+            .resource(&self.arn)
+            .send()
+            .await?
+            .tags
+            .and_then(|tags| tags.items)
+            .unwrap_or_default()
+            .try_into()?)
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .cdn
+            .cloudfront
+            .tag_resource()
+            .resource(&self.arn)
+            .tags(
+                aws_sdk_cloudfront::types::Tags::builder()
+                    .set_items(Some(tags.into()))
+                    .build(),
+            )
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .cdn
+            .cloudfront
+            .untag_resource()
+            .resource(&self.arn)
+            .tag_keys(
+                aws_sdk_cloudfront::types::TagKeys::builder()
+                    .set_items(Some(keys.into_iter().map(TagKey::into_string).collect()))
+                    .build(),
+            )
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+string_newtype!(InvalidationId);
+
+impl InvalidationId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum InvalidationStatus {
+    InProgress,
+    Completed,
+    Other(String),
+}
+
+impl fmt::Display for InvalidationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Self::InProgress => "inprogress",
+                Self::Completed => "completed",
+                Self::Other(ref s) => s,
+            }
+        )
+    }
+}
+
+impl From<String> for InvalidationStatus {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "InProgress" => Self::InProgress,
+            "Completed" => Self::Completed,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Invalidation {
+    pub id: InvalidationId,
+    pub status: InvalidationStatus,
+}
+
+/// Splits `paths` into batches of at most [`MAX_INVALIDATION_PATHS`] paths
+///
+/// Paths are prefixed with `/` if necessary, and duplicates are removed while
+/// keeping the order of the first occurrence. Empty paths are skipped.
+pub fn invalidation_batches<I, S>(paths: I) -> Vec<Vec<String>>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut seen = HashSet::new();
+    let mut unique: Vec<String> = vec![];
+
+    for path in paths {
+        let path: String = path.into();
+        if path.is_empty() {
+            continue;
+        }
+
+        let path = if path.starts_with('/') {
+            path
+        } else {
+            format!("/{path}")
+        };
+
+        if seen.insert(path.clone()) {
+            unique.push(path);
+        }
+    }
+
+    unique
+        .chunks(MAX_INVALIDATION_PATHS)
+        .map(<[String]>::to_vec)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_invalidation_paths() {
+        assert_eq!(
+            invalidation_batches(["index.html", "/index.html", "", "/assets/*"]),
+            vec![vec!["/index.html".to_owned(), "/assets/*".to_owned()]]
+        );
+
+        let batches = invalidation_batches((0..7_000_u32).map(|i| format!("/file-{i}")));
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<usize>>(),
+            vec![3000, 3000, 1000]
+        );
+        assert_eq!(
+            batches
+                .last()
+                .and_then(|batch| batch.first())
+                .map(String::as_str),
+            Some("/file-6000")
+        );
+
+        assert!(invalidation_batches(Vec::<String>::new()).is_empty());
+    }
+}
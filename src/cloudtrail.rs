@@ -0,0 +1,353 @@
+//! CloudTrail lookups of tag changes
+//!
+//! [`lookup_tag_events()`] returns who changed tags on which resources and
+//! when. With the `serde` feature, [`TagEvent::changes()`] additionally
+//! extracts the individual tags that were set or removed from the raw
+//! CloudTrail event.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde_json::Value;
+
+use super::{
+    tags::{RawTag, TagKey},
+    Error, RegionClient, Timestamp,
+};
+
+/// The API calls that modify tags. The names are shared by many services,
+/// e.g. `TagResource` is used by Lambda, SQS, KMS and others.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagEventName {
+    /// EC2
+    CreateTags,
+    /// EC2
+    DeleteTags,
+    TagResource,
+    UntagResource,
+    /// RDS, ELBv2 and others
+    AddTagsToResource,
+    /// RDS and others
+    RemoveTagsFromResource,
+}
+
+impl TagEventName {
+    pub const ALL: [Self; 6] = [
+        Self::CreateTags,
+        Self::DeleteTags,
+        Self::TagResource,
+        Self::UntagResource,
+        Self::AddTagsToResource,
+        Self::RemoveTagsFromResource,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CreateTags => "CreateTags",
+            Self::DeleteTags => "DeleteTags",
+            Self::TagResource => "TagResource",
+            Self::UntagResource => "UntagResource",
+            Self::AddTagsToResource => "AddTagsToResource",
+            Self::RemoveTagsFromResource => "RemoveTagsFromResource",
+        }
+    }
+
+    /// Whether the event removes tags instead of setting them
+    pub const fn is_removal(self) -> bool {
+        matches!(
+            self,
+            Self::DeleteTags | Self::UntagResource | Self::RemoveTagsFromResource
+        )
+    }
+}
+
+impl fmt::Display for TagEventName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for TagEventName {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|name| name.as_str() == value)
+            .ok_or_else(|| Error::InvalidResponseError {
+                message: format!("unexpected event name \"{value}\""),
+            })
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagChange {
+    Set(RawTag),
+    Removed(TagKey),
+}
+
+#[derive(Debug, Clone)]
+pub struct TagEvent {
+    id: String,
+    name: TagEventName,
+    source: Option<String>,
+    time: Timestamp,
+    username: Option<String>,
+    access_key_id: Option<String>,
+    resources: Vec<String>,
+    raw_event: Option<String>,
+}
+
+impl TryFrom<aws_sdk_cloudtrail::types::Event> for TagEvent {
+    type Error = Error;
+
+    fn try_from(event: aws_sdk_cloudtrail::types::Event) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                event.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            id: extract!(event_id)?,
+            name: TagEventName::try_from(extract!(event_name)?.as_str())?,
+            source: event.event_source,
+            time: extract!(event_time)?.try_into()?,
+            username: event.username,
+            access_key_id: event.access_key_id,
+            resources: event
+                .resources
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|resource| resource.resource_name)
+                .collect(),
+            raw_event: event.cloud_trail_event,
+        })
+    }
+}
+
+impl TagEvent {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub const fn name(&self) -> TagEventName {
+        self.name
+    }
+
+    /// The service the call was made to, e.g. `ec2.amazonaws.com`
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub const fn time(&self) -> &Timestamp {
+        &self.time
+    }
+
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
+
+    pub fn access_key_id(&self) -> Option<&str> {
+        self.access_key_id.as_deref()
+    }
+
+    /// IDs or ARNs of the affected resources
+    pub fn resources(&self) -> &[String] {
+        &self.resources
+    }
+
+    /// The full CloudTrail event as JSON
+    pub fn raw_event(&self) -> Option<&str> {
+        self.raw_event.as_deref()
+    }
+
+    /// Extracts the tag changes from the request parameters of the raw
+    /// event.
+    ///
+    /// Services encode tags differently (as list of key/value objects with
+    /// varying capitalization, or as a map), all known variants are handled.
+    /// Returns an empty list if the event does not contain request
+    /// parameters.
+    #[cfg(feature = "serde")]
+    pub fn changes(&self) -> Result<Vec<TagChange>, Error> {
+        let Some(ref raw_event) = self.raw_event else {
+            return Ok(Vec::new());
+        };
+
+        let event: Value = serde_json::from_str(raw_event).map_err(|e| Error::InvalidPayload {
+            message: e.to_string(),
+        })?;
+
+        Ok(parse_changes(self.name, event.get("requestParameters")))
+    }
+}
+
+#[cfg(feature = "serde")]
+fn field<'a>(value: &'a Value, names: &[&str]) -> Option<&'a Value> {
+    names.iter().find_map(|name| value.get(name))
+}
+
+/// Tags are either `{"key": ..., "value": ...}` objects (in any
+/// capitalization, possibly wrapped in an EC2-style `{"items": [...]}`) or a
+/// plain `{key: value}` map
+#[cfg(feature = "serde")]
+fn parse_tag_collection(value: &Value) -> Vec<(String, Option<String>)> {
+    let value = value.get("items").unwrap_or(value);
+
+    match *value {
+        Value::Array(ref items) => items
+            .iter()
+            .filter_map(|item| {
+                let key = field(item, &["key", "Key", "tagKey", "TagKey"])?.as_str()?;
+                let value = field(item, &["value", "Value", "tagValue", "TagValue"])
+                    .and_then(Value::as_str);
+                Some((key.to_owned(), value.map(ToOwned::to_owned)))
+            })
+            .collect(),
+        Value::Object(ref map) => map
+            .iter()
+            .map(|(key, value)| (key.clone(), value.as_str().map(ToOwned::to_owned)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(feature = "serde")]
+fn parse_changes(name: TagEventName, parameters: Option<&Value>) -> Vec<TagChange> {
+    let Some(parameters) = parameters else {
+        return Vec::new();
+    };
+
+    if name.is_removal() {
+        // Removals list either plain keys or tag objects
+        if let Some(keys) = field(parameters, &["tagKeys", "TagKeys", "keys"]) {
+            return keys
+                .as_array()
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(Value::as_str)
+                        .map(|key| TagChange::Removed(TagKey::new(key.to_owned())))
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+    }
+
+    let Some(tags) = field(parameters, &["tagSet", "tags", "Tags", "tagList"]) else {
+        return Vec::new();
+    };
+
+    parse_tag_collection(tags)
+        .into_iter()
+        .map(|(key, value)| match (name.is_removal(), value) {
+            (false, value) => TagChange::Set(RawTag::new(key, value.unwrap_or_default())),
+            (true, _) => TagChange::Removed(TagKey::new(key)),
+        })
+        .collect()
+}
+
+/// Looks up all tag changing events in the given time range, oldest first.
+///
+/// CloudTrail only allows a single lookup attribute per request, so one
+/// lookup is done per event name.
+#[expect(
+    clippy::missing_panics_doc,
+    reason = "only expect() on builder instances"
+)]
+pub async fn lookup_tag_events(
+    client: &RegionClient,
+    names: &[TagEventName],
+    start: Option<Timestamp>,
+    end: Option<Timestamp>,
+) -> Result<Vec<TagEvent>, Error> {
+    let mut events = Vec::new();
+
+    for name in names {
+        let attribute = aws_sdk_cloudtrail::types::LookupAttribute::builder()
+            .attribute_key(aws_sdk_cloudtrail::types::LookupAttributeKey::EventName)
+            .attribute_value(name.as_str())
+            .build()
+            .expect("builder misused");
+
+        let found = client
+            .main
+            .cloudtrail
+            .lookup_events()
+            .lookup_attributes(attribute)
+            .set_start_time(start.map(Into::into))
+            .set_end_time(end.map(Into::into))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?;
+
+        for event in found {
+            events.push(TagEvent::try_from(event)?);
+        }
+    }
+
+    events.sort_by_key(|event| event.time);
+
+    Ok(events)
+}
+
+#[cfg(test)]
+#[cfg(feature = "serde")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ec2_create_tags() {
+        let parameters = serde_json::json!({
+            "resourcesSet": {"items": [{"resourceId": "i-123"}]},
+            "tagSet": {"items": [{"key": "env", "value": "prod"}]}
+        });
+
+        assert_eq!(
+            parse_changes(TagEventName::CreateTags, Some(&parameters)),
+            vec![TagChange::Set(RawTag::new(
+                "env".to_owned(),
+                "prod".to_owned()
+            ))]
+        );
+    }
+
+    #[test]
+    fn parse_tag_resource_map() {
+        let parameters = serde_json::json!({
+            "resource": "arn:aws:lambda:eu-central-1:123:function:f",
+            "tags": {"team": "infra"}
+        });
+
+        assert_eq!(
+            parse_changes(TagEventName::TagResource, Some(&parameters)),
+            vec![TagChange::Set(RawTag::new(
+                "team".to_owned(),
+                "infra".to_owned()
+            ))]
+        );
+    }
+
+    #[test]
+    fn parse_removals() {
+        let untag = serde_json::json!({"tagKeys": ["team"]});
+        assert_eq!(
+            parse_changes(TagEventName::UntagResource, Some(&untag)),
+            vec![TagChange::Removed(TagKey::new("team".to_owned()))]
+        );
+
+        let delete = serde_json::json!({"tagSet": {"items": [{"key": "env"}]}});
+        assert_eq!(
+            parse_changes(TagEventName::DeleteTags, Some(&delete)),
+            vec![TagChange::Removed(TagKey::new("env".to_owned()))]
+        );
+    }
+}
@@ -0,0 +1,941 @@
+//! Publishing and querying CloudWatch metrics, and managing metric alarms
+//!
+//! [`StandardAlarm`]s describe common alarms for a type of resource.
+//! [`standard_alarms()`] creates them for every resource that matches a tag
+//! [`Selector`]:
+//!
+//! ```no_run
+//! # async fn f(client: &aws_lib::RegionClient) -> Result<(), aws_lib::Error> {
+//! use aws_lib::{
+//!     cloudwatch::{put_metric_alarm, standard_alarms, StandardAlarm},
+//!     tags::Selector,
+//! };
+//!
+//! let selector = Selector::new().tag("env").eq("prod");
+//! let standard = StandardAlarm::Ec2CpuUtilization { threshold: 90.0 };
+//!
+//! for mut alarm in standard_alarms(client, &selector, &standard).await? {
+//!     alarm
+//!         .alarm_actions
+//!         .push("arn:aws:sns:eu-central-1:123456789012:oncall".to_owned());
+//!     put_metric_alarm(client, alarm).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    mem,
+    ops::Range,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use aws_sdk_cloudwatch::types::{AlarmType, MetricDataQuery};
+pub use aws_sdk_cloudwatch::types::{ComparisonOperator, StandardUnit, StateValue, Statistic};
+
+use super::{
+    batch::batches,
+    tags::{report::TaggedResource, Selector, TagList},
+    Error, RegionClient, Timestamp,
+};
+
+/// `PutMetricData` accepts at most this many datapoints per request
+const MAX_BATCH_DATUMS: usize = 1000;
+
+/// `PutMetricData` accepts at most 1MB per request. We keep some headroom as the
+/// size of a datum is only estimated.
+const MAX_BATCH_BYTES: usize = 900 * 1024;
+
+/// `DeleteAlarms` accepts at most this many names per request
+const MAX_DELETE_ALARMS: usize = 100;
+
+/// Tags with this prefix are reserved and cannot be set on alarms
+const RESERVED_TAG_PREFIX: &str = "aws:";
+
+/// Rough size of the fixed fields of a serialized datum (value, unit,
+/// timestamp and the surrounding field names)
+const DATUM_OVERHEAD_BYTES: usize = 200;
+
+#[derive(Debug, Clone)]
+pub struct Dimension {
+    pub name: String,
+    pub value: String,
+}
+
+impl Dimension {
+    pub const fn new(name: String, value: String) -> Self {
+        Self { name, value }
+    }
+}
+
+impl TryFrom<aws_sdk_cloudwatch::types::Dimension> for Dimension {
+    type Error = Error;
+
+    fn try_from(dimension: aws_sdk_cloudwatch::types::Dimension) -> Result<Self, Self::Error> {
+        let missing = |entity: &str| Error::UnexpectedNoneValue {
+            entity: entity.to_owned(),
+        };
+        Ok(Self {
+            name: dimension.name.ok_or_else(|| missing("Dimension.name"))?,
+            value: dimension.value.ok_or_else(|| missing("Dimension.value"))?,
+        })
+    }
+}
+
+impl From<Dimension> for aws_sdk_cloudwatch::types::Dimension {
+    fn from(dimension: Dimension) -> Self {
+        Self::builder()
+            .name(dimension.name)
+            .value(dimension.value)
+            .build()
+    }
+}
+
+/// A single datapoint of a metric
+#[derive(Debug, Clone)]
+pub struct Datum {
+    pub name: String,
+    pub dimensions: Vec<Dimension>,
+    pub value: f64,
+    pub unit: StandardUnit,
+    /// Defaults to the time the datapoint is received by CloudWatch
+    pub timestamp: Option<Timestamp>,
+}
+
+impl Datum {
+    pub const fn new(name: String, value: f64, unit: StandardUnit) -> Self {
+        Self {
+            name,
+            dimensions: Vec::new(),
+            value,
+            unit,
+            timestamp: None,
+        }
+    }
+
+    fn estimated_size(&self) -> usize {
+        self.dimensions
+            .iter()
+            .map(|dimension| dimension.name.len().saturating_add(dimension.value.len()))
+            .fold(
+                self.name.len().saturating_add(DATUM_OVERHEAD_BYTES),
+                usize::saturating_add,
+            )
+    }
+}
+
+impl From<Datum> for aws_sdk_cloudwatch::types::MetricDatum {
+    fn from(datum: Datum) -> Self {
+        Self::builder()
+            .metric_name(datum.name)
+            .set_dimensions(Some(datum.dimensions.into_iter().map(Into::into).collect()))
+            .value(datum.value)
+            .unit(datum.unit)
+            .set_timestamp(datum.timestamp.map(Into::into))
+            .build()
+    }
+}
+
+pub async fn put_metric_data(
+    client: &RegionClient,
+    namespace: &str,
+    datums: Vec<Datum>,
+) -> Result<(), Error> {
+    let _output = client
+        .main
+        .cloudwatch
+        .put_metric_data()
+        .namespace(namespace)
+        .set_metric_data(Some(datums.into_iter().map(Into::into).collect()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/// A standard statistic, or an extended one like a percentile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricStatistic {
+    Standard(Statistic),
+    /// e.g. `p99` or `tm90`
+    Extended(String),
+}
+
+impl MetricStatistic {
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Self::Standard(ref statistic) => statistic.as_str(),
+            Self::Extended(ref statistic) => statistic,
+        }
+    }
+
+    /// APIs like `PutMetricAlarm` take standard and extended statistics in
+    /// different fields. Without either, the statistic is the average.
+    fn from_parts(statistic: Option<Statistic>, extended_statistic: Option<String>) -> Self {
+        match (statistic, extended_statistic) {
+            (Some(statistic), _) => Self::Standard(statistic),
+            (None, Some(statistic)) => Self::Extended(statistic),
+            (None, None) => Self::default(),
+        }
+    }
+
+    fn into_parts(self) -> (Option<Statistic>, Option<String>) {
+        match self {
+            Self::Standard(statistic) => (Some(statistic), None),
+            Self::Extended(statistic) => (None, Some(statistic)),
+        }
+    }
+}
+
+impl Default for MetricStatistic {
+    fn default() -> Self {
+        Self::Standard(Statistic::Average)
+    }
+}
+
+impl From<Statistic> for MetricStatistic {
+    fn from(statistic: Statistic) -> Self {
+        Self::Standard(statistic)
+    }
+}
+
+/// A metric to query with [`get_metric_data()`]. The `id` is used to match the
+/// results to the query.
+#[derive(Debug, Clone)]
+pub struct MetricQuery {
+    pub id: String,
+    pub namespace: String,
+    pub name: String,
+    pub dimensions: Vec<Dimension>,
+    pub period: Duration,
+    pub statistic: MetricStatistic,
+}
+
+impl From<MetricQuery> for MetricDataQuery {
+    fn from(query: MetricQuery) -> Self {
+        Self::builder()
+            .id(query.id)
+            .metric_stat(
+                aws_sdk_cloudwatch::types::MetricStat::builder()
+                    .metric(
+                        aws_sdk_cloudwatch::types::Metric::builder()
+                            .namespace(query.namespace)
+                            .metric_name(query.name)
+                            .set_dimensions(Some(
+                                query.dimensions.into_iter().map(Into::into).collect(),
+                            ))
+                            .build(),
+                    )
+                    .period(i32::try_from(query.period.as_secs()).unwrap_or(i32::MAX))
+                    .stat(query.statistic.as_str())
+                    .build(),
+            )
+            .build()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricResult {
+    pub id: String,
+    pub datapoints: Vec<(Timestamp, f64)>,
+}
+
+/// Returns one result per query, following pagination.
+pub async fn get_metric_data(
+    client: &RegionClient,
+    queries: Vec<MetricQuery>,
+    start: Timestamp,
+    end: Timestamp,
+) -> Result<Vec<MetricResult>, Error> {
+    let (ids, queries): (Vec<String>, Vec<MetricDataQuery>) = queries
+        .into_iter()
+        .map(|query| (query.id.clone(), query.into()))
+        .unzip();
+
+    let mut datapoints: HashMap<String, Vec<(Timestamp, f64)>> = HashMap::new();
+
+    for result in client
+        .main
+        .cloudwatch
+        .get_metric_data()
+        .set_metric_data_queries(Some(queries))
+        .start_time(start.into())
+        .end_time(end.into())
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .flat_map(|page| page.metric_data_results.unwrap_or_default())
+    {
+        let id = result.id.ok_or_else(|| Error::UnexpectedNoneValue {
+            entity: "MetricDataResult.id".to_owned(),
+        })?;
+
+        let timestamps = result
+            .timestamps
+            .unwrap_or_default()
+            .into_iter()
+            .map(Timestamp::try_from)
+            .collect::<Result<Vec<Timestamp>, Error>>()?;
+
+        datapoints.entry(id).or_default().extend(
+            timestamps
+                .into_iter()
+                .zip(result.values.unwrap_or_default()),
+        );
+    }
+
+    Ok(ids
+        .into_iter()
+        .map(|id| MetricResult {
+            datapoints: datapoints.remove(&id).unwrap_or_default(),
+            id,
+        })
+        .collect())
+}
+
+/// Datapoints that were not sent yet, in the order they were pushed
+#[derive(Debug, Default)]
+struct Pending {
+    datums: Vec<Datum>,
+    bytes: usize,
+}
+
+impl Pending {
+    /// Returns whether the pending datapoints fill at least one request
+    fn push(&mut self, datum: Datum) -> bool {
+        self.bytes = self.bytes.saturating_add(datum.estimated_size());
+        self.datums.push(datum);
+        self.datums.len() >= MAX_BATCH_DATUMS || self.bytes >= MAX_BATCH_BYTES
+    }
+
+    fn take(&mut self) -> Vec<Datum> {
+        self.bytes = 0;
+        mem::take(&mut self.datums)
+    }
+
+    /// Puts datapoints that could not be sent back in front of the ones that
+    /// were pushed in the meantime
+    fn requeue(&mut self, datums: Vec<Datum>) {
+        let newer = mem::replace(&mut self.datums, datums);
+        self.datums.extend(newer);
+        self.bytes = self
+            .datums
+            .iter()
+            .map(Datum::estimated_size)
+            .fold(0, usize::saturating_add);
+    }
+}
+
+/// Splits datapoints into requests below the limits of `PutMetricData`
+fn request_batches(datums: &[Datum]) -> Vec<Range<usize>> {
+    let sizes: Vec<usize> = datums.iter().map(Datum::estimated_size).collect();
+    batches(&sizes, MAX_BATCH_DATUMS, MAX_BATCH_BYTES)
+}
+
+/// Collects datapoints and publishes them in as few requests as possible.
+///
+/// The buffer is sent as soon as it fills a request of `PutMetricData`.
+/// Datapoints of a failed request stay buffered for the next attempt. To also
+/// publish partial batches regularly, run [`run()`](Self::run())
+/// concurrently:
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use aws_lib::{cloudwatch::MetricBuffer, RegionClient};
+/// # async fn f(client: RegionClient) -> Result<(), aws_lib::Error> {
+/// let buffer = MetricBuffer::new(client, "MyService".to_owned());
+/// let flusher = buffer.run(Duration::from_secs(60), |error| eprintln!("{error}"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MetricBuffer {
+    client: RegionClient,
+    namespace: String,
+    pending: Mutex<Pending>,
+}
+
+impl MetricBuffer {
+    pub fn new(client: RegionClient, namespace: String) -> Self {
+        Self {
+            client,
+            namespace,
+            pending: Mutex::new(Pending::default()),
+        }
+    }
+
+    fn pending(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Adds a datapoint, and sends the buffer if it fills a request
+    pub async fn push(&self, datum: Datum) -> Result<(), Error> {
+        let full = self.pending().push(datum);
+        if full {
+            self.flush().await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sends all buffered datapoints. Datapoints that could not be sent stay
+    /// buffered for the next flush.
+    pub async fn flush(&self) -> Result<(), Error> {
+        let mut datums = self.pending().take();
+
+        for batch in request_batches(&datums) {
+            let rest = datums.split_off(batch.len());
+            if let Err(e) = put_metric_data(&self.client, &self.namespace, datums.clone()).await {
+                datums.extend(rest);
+                self.pending().requeue(datums);
+                return Err(e);
+            }
+            datums = rest;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the buffer every `interval`, forever. Failed flushes are
+    /// passed to `on_error`, their datapoints are retried with the next one.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[expect(clippy::infinite_loop, reason = "runs until the future is dropped")]
+    pub async fn run(&self, interval: Duration, on_error: impl Fn(Error)) {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            let _instant = interval.tick().await;
+            if let Err(e) = self.flush().await {
+                on_error(e);
+            }
+        }
+    }
+}
+
+/// How an alarm treats periods without datapoints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TreatMissingData {
+    Breaching,
+    NotBreaching,
+    Ignore,
+    #[default]
+    Missing,
+}
+
+impl TreatMissingData {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Breaching => "breaching",
+            Self::NotBreaching => "notBreaching",
+            Self::Ignore => "ignore",
+            Self::Missing => "missing",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        [
+            Self::Breaching,
+            Self::NotBreaching,
+            Self::Ignore,
+            Self::Missing,
+        ]
+        .into_iter()
+        .find(|treat| treat.as_str() == value)
+    }
+}
+
+/// The definition of an alarm on a single metric
+#[derive(Debug, Clone)]
+pub struct MetricAlarm {
+    pub name: String,
+    pub description: Option<String>,
+    pub namespace: String,
+    pub metric: String,
+    pub dimensions: Vec<Dimension>,
+    pub statistic: MetricStatistic,
+    pub period: Duration,
+    pub evaluation_periods: i32,
+    pub threshold: f64,
+    pub comparison: ComparisonOperator,
+    pub treat_missing_data: TreatMissingData,
+    /// ARNs of SNS topics or other actions, run when the alarm fires
+    pub alarm_actions: Vec<String>,
+    /// Run when the alarm returns to `OK`
+    pub ok_actions: Vec<String>,
+    /// Only set when the alarm is created, see [`tag_alarm()`] for existing
+    /// alarms
+    pub tags: TagList,
+}
+
+impl MetricAlarm {
+    /// An alarm on the average over 5 minute periods that fires after one
+    /// breaching period
+    pub const fn new(
+        name: String,
+        namespace: String,
+        metric: String,
+        threshold: f64,
+        comparison: ComparisonOperator,
+    ) -> Self {
+        Self {
+            name,
+            description: None,
+            namespace,
+            metric,
+            dimensions: Vec::new(),
+            statistic: MetricStatistic::Standard(Statistic::Average),
+            period: Duration::from_secs(5 * 60),
+            evaluation_periods: 1,
+            threshold,
+            comparison,
+            treat_missing_data: TreatMissingData::Missing,
+            alarm_actions: Vec::new(),
+            ok_actions: Vec::new(),
+            tags: TagList::new(),
+        }
+    }
+}
+
+/// Creates the alarm, or replaces the alarm with the same name
+pub async fn put_metric_alarm(client: &RegionClient, alarm: MetricAlarm) -> Result<(), Error> {
+    let (statistic, extended_statistic) = alarm.statistic.into_parts();
+
+    let _output = client
+        .main
+        .cloudwatch
+        .put_metric_alarm()
+        .alarm_name(alarm.name)
+        .set_alarm_description(alarm.description)
+        .namespace(alarm.namespace)
+        .metric_name(alarm.metric)
+        .set_dimensions(Some(alarm.dimensions.into_iter().map(Into::into).collect()))
+        .set_statistic(statistic)
+        .set_extended_statistic(extended_statistic)
+        .period(i32::try_from(alarm.period.as_secs()).unwrap_or(i32::MAX))
+        .evaluation_periods(alarm.evaluation_periods)
+        .threshold(alarm.threshold)
+        .comparison_operator(alarm.comparison)
+        .treat_missing_data(alarm.treat_missing_data.as_str())
+        .set_alarm_actions(Some(alarm.alarm_actions))
+        .set_ok_actions(Some(alarm.ok_actions))
+        .set_tags((!alarm.tags.as_slice().is_empty()).then(|| alarm.tags.into()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/// An existing alarm with its current state
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    pub arn: String,
+    pub state: StateValue,
+    pub state_reason: Option<String>,
+    pub state_updated: Option<Timestamp>,
+    /// Tags are not part of the description, see [`alarm_tags()`]
+    pub definition: MetricAlarm,
+}
+
+impl Alarm {
+    fn try_from_aws(alarm: aws_sdk_cloudwatch::types::MetricAlarm) -> Result<Self, Error> {
+        let missing = |entity: &str| Error::UnexpectedNoneValue {
+            entity: format!("MetricAlarm.{entity}"),
+        };
+
+        Ok(Self {
+            arn: alarm.alarm_arn.ok_or_else(|| missing("alarm_arn"))?,
+            state: alarm.state_value.ok_or_else(|| missing("state_value"))?,
+            state_reason: alarm.state_reason,
+            state_updated: alarm
+                .state_updated_timestamp
+                .map(Timestamp::try_from)
+                .transpose()?,
+            definition: MetricAlarm {
+                name: alarm.alarm_name.ok_or_else(|| missing("alarm_name"))?,
+                description: alarm.alarm_description,
+                namespace: alarm.namespace.ok_or_else(|| missing("namespace"))?,
+                metric: alarm.metric_name.ok_or_else(|| missing("metric_name"))?,
+                dimensions: alarm
+                    .dimensions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<Dimension>, Error>>()?,
+                statistic: MetricStatistic::from_parts(alarm.statistic, alarm.extended_statistic),
+                period: Duration::from_secs(
+                    alarm
+                        .period
+                        .and_then(|period| u64::try_from(period).ok())
+                        .unwrap_or_default(),
+                ),
+                evaluation_periods: alarm.evaluation_periods.unwrap_or(1),
+                threshold: alarm.threshold.ok_or_else(|| missing("threshold"))?,
+                comparison: alarm
+                    .comparison_operator
+                    .ok_or_else(|| missing("comparison_operator"))?,
+                treat_missing_data: alarm
+                    .treat_missing_data
+                    .as_deref()
+                    .and_then(TreatMissingData::from_str)
+                    .unwrap_or_default(),
+                alarm_actions: alarm.alarm_actions.unwrap_or_default(),
+                ok_actions: alarm.ok_actions.unwrap_or_default(),
+                tags: TagList::new(),
+            },
+        })
+    }
+}
+
+/// Returns all alarms on a single metric whose name starts with
+/// `name_prefix`. Alarms on metric math expressions are skipped.
+pub async fn describe_alarms(
+    client: &RegionClient,
+    name_prefix: Option<&str>,
+) -> Result<Vec<Alarm>, Error> {
+    client
+        .main
+        .cloudwatch
+        .describe_alarms()
+        .set_alarm_name_prefix(name_prefix.map(ToOwned::to_owned))
+        .alarm_types(AlarmType::MetricAlarm)
+        .into_paginator()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .flat_map(|page| page.metric_alarms.unwrap_or_default())
+        .filter(|alarm| alarm.metric_name.is_some())
+        .map(Alarm::try_from_aws)
+        .collect()
+}
+
+pub async fn delete_alarms(client: &RegionClient, names: &[String]) -> Result<(), Error> {
+    for chunk in names.chunks(MAX_DELETE_ALARMS) {
+        let _output = client
+            .main
+            .cloudwatch
+            .delete_alarms()
+            .set_alarm_names(Some(chunk.to_vec()))
+            .send()
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn tag_alarm(client: &RegionClient, arn: &str, tags: TagList) -> Result<(), Error> {
+    let _output = client
+        .main
+        .cloudwatch
+        .tag_resource()
+        .resource_arn(arn)
+        .set_tags(Some(tags.into()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+pub async fn untag_alarm(client: &RegionClient, arn: &str, keys: Vec<String>) -> Result<(), Error> {
+    let _output = client
+        .main
+        .cloudwatch
+        .untag_resource()
+        .resource_arn(arn)
+        .set_tag_keys(Some(keys))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+pub async fn alarm_tags(client: &RegionClient, arn: &str) -> Result<TagList, Error> {
+    Ok(client
+        .main
+        .cloudwatch
+        .list_tags_for_resource()
+        .resource_arn(arn)
+        .send()
+        .await?
+        .tags
+        .unwrap_or_default()
+        .try_into()?)
+}
+
+/// Common alarms for a type of resource
+#[derive(Debug, Clone, PartialEq)]
+pub enum StandardAlarm {
+    /// Average CPU utilization of EC2 instances above `threshold` percent
+    /// for 15 minutes
+    Ec2CpuUtilization { threshold: f64 },
+    /// Failed system or instance status checks of EC2 instances for 2
+    /// minutes
+    Ec2StatusCheckFailed,
+    /// Average CPU utilization of RDS instances above `threshold` percent
+    /// for 15 minutes
+    RdsCpuUtilization { threshold: f64 },
+    /// Free storage of RDS instances below `threshold` bytes
+    RdsFreeStorageSpace { threshold: f64 },
+    /// More than `threshold` errors of a Lambda function in 5 minutes
+    LambdaErrors { threshold: f64 },
+    /// The oldest message of an SQS queue is older than `threshold`
+    SqsMessageAge { threshold: Duration },
+}
+
+impl StandardAlarm {
+    /// The resource type for [`Selector::find()`], e.g. `ec2:instance`
+    pub const fn resource_type(&self) -> &'static str {
+        match *self {
+            Self::Ec2CpuUtilization { .. } | Self::Ec2StatusCheckFailed => "ec2:instance",
+            Self::RdsCpuUtilization { .. } | Self::RdsFreeStorageSpace { .. } => "rds:db",
+            Self::LambdaErrors { .. } => "lambda:function",
+            Self::SqsMessageAge { .. } => "sqs",
+        }
+    }
+
+    /// The alarm for one resource, named `<resource>-<metric>` and tagged
+    /// with the tags of the resource. The metric dimension is the id of the
+    /// resource from its ARN.
+    pub fn alarm(&self, resource: &TaggedResource) -> MetricAlarm {
+        let (namespace, metric, dimension, statistic, threshold, comparison, periods) = match *self
+        {
+            Self::Ec2CpuUtilization { threshold } => (
+                "AWS/EC2",
+                "CPUUtilization",
+                "InstanceId",
+                Statistic::Average,
+                threshold,
+                ComparisonOperator::GreaterThanThreshold,
+                3_i32,
+            ),
+            Self::Ec2StatusCheckFailed => (
+                "AWS/EC2",
+                "StatusCheckFailed",
+                "InstanceId",
+                Statistic::Maximum,
+                1.0_f64,
+                ComparisonOperator::GreaterThanOrEqualToThreshold,
+                2_i32,
+            ),
+            Self::RdsCpuUtilization { threshold } => (
+                "AWS/RDS",
+                "CPUUtilization",
+                "DBInstanceIdentifier",
+                Statistic::Average,
+                threshold,
+                ComparisonOperator::GreaterThanThreshold,
+                3_i32,
+            ),
+            Self::RdsFreeStorageSpace { threshold } => (
+                "AWS/RDS",
+                "FreeStorageSpace",
+                "DBInstanceIdentifier",
+                Statistic::Minimum,
+                threshold,
+                ComparisonOperator::LessThanThreshold,
+                1_i32,
+            ),
+            Self::LambdaErrors { threshold } => (
+                "AWS/Lambda",
+                "Errors",
+                "FunctionName",
+                Statistic::Sum,
+                threshold,
+                ComparisonOperator::GreaterThanThreshold,
+                1_i32,
+            ),
+            Self::SqsMessageAge { threshold } => (
+                "AWS/SQS",
+                "ApproximateAgeOfOldestMessage",
+                "QueueName",
+                Statistic::Maximum,
+                threshold.as_secs_f64(),
+                ComparisonOperator::GreaterThanThreshold,
+                1_i32,
+            ),
+        };
+
+        let id = resource.arn.resource_id();
+
+        MetricAlarm {
+            dimensions: vec![Dimension {
+                name: dimension.to_owned(),
+                value: id.to_owned(),
+            }],
+            statistic: statistic.into(),
+            period: match *self {
+                Self::Ec2StatusCheckFailed => Duration::from_secs(60),
+                _ => Duration::from_secs(5 * 60),
+            },
+            evaluation_periods: periods,
+            tags: TagList::from_vec(
+                resource
+                    .tags
+                    .as_slice()
+                    .iter()
+                    .filter(|tag| !tag.key().as_str().starts_with(RESERVED_TAG_PREFIX))
+                    .cloned()
+                    .collect(),
+            ),
+            ..MetricAlarm::new(
+                format!("{id}-{metric}"),
+                namespace.to_owned(),
+                metric.to_owned(),
+                threshold,
+                comparison,
+            )
+        }
+    }
+}
+
+/// The standard alarm for every resource that matches `selector`. The alarms
+/// have no actions yet and are not created, see [`put_metric_alarm()`].
+pub async fn standard_alarms(
+    client: &RegionClient,
+    selector: &Selector,
+    standard: &StandardAlarm,
+) -> Result<Vec<MetricAlarm>, Error> {
+    Ok(selector
+        .find(client, &[standard.resource_type()])
+        .await?
+        .iter()
+        .map(|resource| standard.alarm(resource))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{arn::Arn, tags::RawTag};
+
+    #[test]
+    fn standard_alarm() {
+        let resource = TaggedResource {
+            arn: Arn::parse("arn:aws:ec2:eu-central-1:123456789012:instance/i-0123456789abcdef0")
+                .unwrap(),
+            tags: TagList::from_vec(vec![
+                RawTag::new("env".to_owned(), "prod".to_owned()),
+                RawTag::new("aws:cloudformation:stack-name".to_owned(), "web".to_owned()),
+            ]),
+        };
+
+        let alarm = StandardAlarm::Ec2CpuUtilization {
+            threshold: 90.0_f64,
+        }
+        .alarm(&resource);
+
+        assert_eq!(alarm.name, "i-0123456789abcdef0-CPUUtilization");
+        assert_eq!(alarm.namespace, "AWS/EC2");
+        assert_eq!(alarm.dimensions.len(), 1);
+        assert_eq!(
+            alarm
+                .dimensions
+                .first()
+                .map(|dimension| dimension.value.as_str()),
+            Some("i-0123456789abcdef0")
+        );
+        assert_eq!(alarm.evaluation_periods, 3_i32);
+        assert_eq!(
+            alarm.tags,
+            TagList::from_vec(vec![RawTag::new("env".to_owned(), "prod".to_owned())])
+        );
+    }
+
+    #[test]
+    fn statistics() {
+        assert_eq!(
+            MetricStatistic::from_parts(Some(Statistic::Maximum), None),
+            MetricStatistic::Standard(Statistic::Maximum)
+        );
+        assert_eq!(
+            MetricStatistic::from_parts(None, Some("p99".to_owned())),
+            MetricStatistic::Extended("p99".to_owned())
+        );
+        assert_eq!(
+            MetricStatistic::from_parts(None, None),
+            MetricStatistic::Standard(Statistic::Average)
+        );
+        assert_eq!(
+            MetricStatistic::Extended("p99".to_owned()).into_parts(),
+            (None, Some("p99".to_owned()))
+        );
+        assert_eq!(
+            TreatMissingData::from_str("notBreaching"),
+            Some(TreatMissingData::NotBreaching)
+        );
+    }
+
+    fn datum(name: &str) -> Datum {
+        Datum::new(name.to_owned(), 1.0_f64, StandardUnit::Count)
+    }
+
+    #[test]
+    fn batch_datum_limit() {
+        let mut pending = Pending::default();
+        let full: Vec<bool> = (0..MAX_BATCH_DATUMS)
+            .map(|_| pending.push(datum("requests")))
+            .collect();
+        assert_eq!(
+            full.iter().position(|&full| full),
+            Some(MAX_BATCH_DATUMS.saturating_sub(1))
+        );
+
+        let datums: Vec<Datum> = (0..2500_u32).map(|_| datum("requests")).collect();
+        assert_eq!(
+            request_batches(&datums),
+            vec![0..1000, 1000..2000, 2000..2500]
+        );
+    }
+
+    #[test]
+    fn batch_size_limit() {
+        let large = datum(&"x".repeat(10 * 1024));
+        let per_request = MAX_BATCH_BYTES.checked_div(large.estimated_size()).unwrap();
+
+        let mut pending = Pending::default();
+        let full: Vec<bool> = (0..=per_request)
+            .map(|_| pending.push(large.clone()))
+            .collect();
+        assert_eq!(full.iter().position(|&full| full), Some(per_request));
+
+        let datums = vec![large; per_request.saturating_mul(2).saturating_add(1)];
+        let batches = request_batches(&datums);
+        assert_eq!(
+            batches
+                .iter()
+                .map(ExactSizeIterator::len)
+                .collect::<Vec<usize>>(),
+            vec![per_request, per_request, 1]
+        );
+    }
+
+    #[test]
+    fn requeue_keeps_order() {
+        let mut pending = Pending::default();
+        pending.push(datum("a"));
+        pending.push(datum("b"));
+        let failed = pending.take();
+        pending.push(datum("c"));
+        pending.requeue(failed);
+
+        assert_eq!(
+            pending
+                .datums
+                .iter()
+                .map(|datum| datum.name.as_str())
+                .collect::<Vec<&str>>(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(
+            pending.bytes,
+            ["a", "b", "c"]
+                .into_iter()
+                .map(|name| datum(name).estimated_size())
+                .sum::<usize>()
+        );
+    }
+}
@@ -0,0 +1,624 @@
+//! Layered configuration of the service clients
+//!
+//! [`ClientBuilder`] resolves each setting from, in this order:
+//!
+//! 1. values set in code
+//! 2. environment variables (`AWS_REGION`, `AWS_PROFILE`, `AWS_RETRY_MODE`,
+//!    ...)
+//! 3. the selected profile in the shared config files, see
+//!    [`super::profile`]
+//!
+//! The result is an [`AwsConfig`], which all service clients of
+//! [`AwsConfig::load_clients()`] are built from:
+//!
+//! ```no_run
+//! # async fn f() -> Result<(), aws_lib::Error> {
+//! use aws_lib::{config::ClientBuilder, profile::RetryMode};
+//!
+//! let clients = ClientBuilder::default()
+//!     .cdn_profile("cdn")
+//!     .retry_mode(RetryMode::Adaptive)
+//!     .build()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, sync::Arc};
+
+use aws_config::retry::RetryConfig;
+use aws_sdk_ec2::config::SharedCredentialsProvider;
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::ratelimit;
+use super::{
+    audit, load_sdk_clients_with, metrics,
+    profile::{Profile, ProfileSet, RetryMode},
+    sts::{AssumeRoleProvider, MfaTokenProvider, TerminalMfaPrompt},
+    ClientConfig, Error, ProfileConfig, ProfileName, Region, RegionClient, Timeouts,
+};
+
+const DEFAULT_PROFILE: &str = "default";
+
+/// Settings set in code, which take precedence over the environment and the
+/// shared config files
+#[derive(Debug, Clone, Default)]
+pub struct ClientBuilder {
+    regions: Vec<Region>,
+    profile: Option<String>,
+    cdn_profile: Option<String>,
+    credentials: Option<SharedCredentialsProvider>,
+    retry_mode: Option<RetryMode>,
+    max_attempts: Option<u32>,
+    timeouts: Option<Timeouts>,
+    endpoint_url: Option<String>,
+    metrics: Option<Arc<dyn metrics::MetricsSink>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    rate_limit: Option<ratelimit::RateLimitConfig>,
+    audit: Option<Arc<dyn audit::AuditSink>>,
+    disable_s3_redirects: bool,
+    mfa_prompt: Option<Arc<dyn MfaTokenProvider>>,
+}
+
+impl ClientBuilder {
+    /// Adds a region to load clients for. Without any, the single region of
+    /// the environment or the profile is used.
+    #[must_use]
+    pub fn region(mut self, region: Region) -> Self {
+        self.regions.push(region);
+        self
+    }
+
+    #[must_use]
+    pub fn regions(mut self, regions: impl IntoIterator<Item = Region>) -> Self {
+        self.regions.extend(regions);
+        self
+    }
+
+    /// The profile has to exist in the shared config files, like one set
+    /// with `AWS_PROFILE`
+    #[must_use]
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// The profile of the CDN clients, defaults to the main profile. It has
+    /// to exist in the shared config files.
+    #[must_use]
+    pub fn cdn_profile(mut self, profile: impl Into<String>) -> Self {
+        self.cdn_profile = Some(profile.into());
+        self
+    }
+
+    /// Used for all service clients instead of the default credentials chain
+    /// of the SDK, which already covers the environment and the profile
+    #[must_use]
+    pub fn credentials(mut self, credentials: SharedCredentialsProvider) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Asks for the MFA code if the profile assumes a role with
+    /// `mfa_serial`, see [`AssumeRoleProvider`]. Defaults to
+    /// [`TerminalMfaPrompt`].
+    #[must_use]
+    pub fn mfa_prompt(mut self, prompt: Arc<dyn MfaTokenProvider>) -> Self {
+        self.mfa_prompt = Some(prompt);
+        self
+    }
+
+    #[must_use]
+    pub const fn retry_mode(mut self, retry_mode: RetryMode) -> Self {
+        self.retry_mode = Some(retry_mode);
+        self
+    }
+
+    /// Including the first attempt
+    #[must_use]
+    pub const fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Neither the environment nor the shared config files define timeouts,
+    /// so they default to [`Timeouts::default()`]
+    #[must_use]
+    pub const fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    /// Sends the requests of all services to this URL, e.g. for LocalStack
+    #[must_use]
+    pub fn endpoint_url(mut self, endpoint_url: impl Into<String>) -> Self {
+        self.endpoint_url = Some(endpoint_url.into());
+        self
+    }
+
+    #[must_use]
+    pub fn metrics(mut self, sink: Arc<dyn metrics::MetricsSink>) -> Self {
+        self.metrics = Some(sink);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub const fn rate_limit(mut self, rate_limit: ratelimit::RateLimitConfig) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    #[must_use]
+    pub fn audit(mut self, sink: Arc<dyn audit::AuditSink>) -> Self {
+        self.audit = Some(sink);
+        self
+    }
+
+    /// Do not follow redirects of S3 to the region of a bucket, see
+    /// [`ClientConfig::disable_s3_redirects`]
+    #[must_use]
+    pub const fn disable_s3_redirects(mut self) -> Self {
+        self.disable_s3_redirects = true;
+        self
+    }
+
+    /// Resolves all settings from the process environment and the shared
+    /// config files
+    pub fn load(self) -> Result<AwsConfig, Error> {
+        let profiles = ProfileSet::load()?;
+        self.resolve(|name| std::env::var(name).ok(), &profiles)
+    }
+
+    /// Shorthand for [`Self::load()`] and [`AwsConfig::load_clients()`]
+    pub async fn build(self) -> Result<Vec<RegionClient>, Error> {
+        Ok(self.load()?.load_clients().await)
+    }
+
+    fn resolve(
+        self,
+        env: impl Fn(&str) -> Option<String>,
+        profiles: &ProfileSet,
+    ) -> Result<AwsConfig, Error> {
+        let env = |name: &str| env(name).filter(|value| !value.is_empty());
+
+        // Only the default profile may be missing, e.g. without any config
+        // file. A profile that was asked for has to exist.
+        let (profile_name, profile) = match self.profile.or_else(|| env("AWS_PROFILE")) {
+            Some(name) => {
+                let profile = find_profile(profiles, &name)?;
+                (name, Some(profile))
+            }
+            None => (
+                DEFAULT_PROFILE.to_owned(),
+                profiles.profile(DEFAULT_PROFILE),
+            ),
+        };
+        if let Some(ref cdn_profile) = self.cdn_profile {
+            find_profile(profiles, cdn_profile)?;
+        }
+        let cdn_profile_name = self.cdn_profile.unwrap_or_else(|| profile_name.clone());
+
+        let regions = if self.regions.is_empty() {
+            let name = profiles
+                .resolve_region_with(&env, &profile_name)
+                .ok_or_else(|| invalid("region", "no region configured".to_owned()))?;
+            vec![Region::from_name(&name)
+                .ok_or_else(|| invalid("region", format!("unknown region {name}")))?]
+        } else {
+            self.regions
+        };
+
+        // The SDK fails for profiles with MFA, so those roles are assumed
+        // here, but only for the clients of that profile. Like in the default
+        // credentials chain, credentials from the environment come first.
+        let env_credentials =
+            env("AWS_ACCESS_KEY_ID").is_some() && env("AWS_SECRET_ACCESS_KEY").is_some();
+        let mut profile_credentials = HashMap::new();
+        if self.credentials.is_none() && !env_credentials {
+            let mfa = self
+                .mfa_prompt
+                .unwrap_or_else(|| Arc::new(TerminalMfaPrompt));
+            for name in [profile_name.as_str(), cdn_profile_name.as_str()] {
+                let needs_mfa = profiles
+                    .profile(name)
+                    .is_some_and(|profile| profile.mfa_serial().is_some());
+                if !needs_mfa || profile_credentials.contains_key(name) {
+                    continue;
+                }
+                if let Some(provider) = AssumeRoleProvider::from_profile(
+                    profiles,
+                    name,
+                    sts_region(profiles, &env, name, &regions)?,
+                    Arc::clone(&mfa),
+                )? {
+                    let _previous = profile_credentials
+                        .insert(name.to_owned(), SharedCredentialsProvider::new(provider));
+                }
+            }
+        }
+
+        let retry_mode = match self.retry_mode {
+            Some(retry_mode) => Some(retry_mode),
+            None => match env("AWS_RETRY_MODE") {
+                Some(value) => Some(
+                    RetryMode::try_from(value.as_str())
+                        .map_err(|()| invalid("retry_mode", format!("unknown mode {value}")))?,
+                ),
+                None => profile.and_then(Profile::retry_mode),
+            },
+        };
+
+        let max_attempts =
+            match self.max_attempts {
+                Some(max_attempts) => Some(max_attempts),
+                None => match env("AWS_MAX_ATTEMPTS") {
+                    Some(value) => Some(value.parse::<u32>().map_err(|e| {
+                        invalid("max_attempts", format!("invalid value {value}: {e}"))
+                    })?),
+                    None => profile.and_then(Profile::max_attempts),
+                },
+            };
+        if max_attempts == Some(0) {
+            return Err(invalid("max_attempts", "must be at least 1".to_owned()));
+        }
+
+        let endpoint_url = self
+            .endpoint_url
+            .or_else(|| env("AWS_ENDPOINT_URL"))
+            .or_else(|| {
+                profile
+                    .and_then(|profile| profile.get("endpoint_url"))
+                    .map(ToOwned::to_owned)
+            });
+
+        Ok(AwsConfig {
+            regions,
+            profile_config: ProfileConfig {
+                profile_name_cdn: ProfileName::new(cdn_profile_name),
+                profile_name_main: ProfileName::new(profile_name),
+            },
+            credentials: self.credentials,
+            profile_credentials,
+            retry_mode: retry_mode.unwrap_or(RetryMode::Standard),
+            max_attempts,
+            endpoint_url,
+            client_config: ClientConfig {
+                timeouts: self.timeouts.unwrap_or_default(),
+                metrics: self.metrics,
+                #[cfg(not(target_arch = "wasm32"))]
+                rate_limit: self.rate_limit,
+                audit: self.audit,
+                disable_s3_redirects: self.disable_s3_redirects,
+            },
+        })
+    }
+}
+
+/// The region of the STS calls for the role of profile `name`: the region of
+/// the profile or of the first profile in its source chain that has one.
+/// Without any, the global region of the partition of the clients is used.
+fn sts_region(
+    profiles: &ProfileSet,
+    env: impl Fn(&str) -> Option<String>,
+    name: &str,
+    regions: &[Region],
+) -> Result<Region, Error> {
+    let region = profiles.resolve_region_with(env, name).or_else(|| {
+        profiles
+            .source_chain(name)
+            .ok()?
+            .into_iter()
+            .find_map(Profile::region)
+            .map(ToOwned::to_owned)
+    });
+
+    match region {
+        Some(region) => Region::from_name(&region)
+            .ok_or_else(|| invalid("region", format!("unknown region {region}"))),
+        None => regions
+            .first()
+            .and_then(|region| Region::from_name(region.partition().global_region()))
+            .ok_or_else(|| invalid("region", "no region for STS configured".to_owned())),
+    }
+}
+
+fn find_profile<'a>(profiles: &'a ProfileSet, name: &str) -> Result<&'a Profile, Error> {
+    profiles
+        .profile(name)
+        .ok_or_else(|| invalid("profile", format!("unknown profile {name}")))
+}
+
+fn invalid(setting: &str, message: String) -> Error {
+    Error::InvalidClientConfig {
+        setting: setting.to_owned(),
+        message,
+    }
+}
+
+/// The resolved configuration, shared by all service clients
+#[derive(Debug, Clone)]
+pub struct AwsConfig {
+    pub regions: Vec<Region>,
+    pub profile_config: ProfileConfig,
+    /// Used for all service clients. `None` uses the default credentials
+    /// chain of the SDK, or `profile_credentials`.
+    pub credentials: Option<SharedCredentialsProvider>,
+    /// Credentials the SDK cannot resolve itself, i.e. roles that need an
+    /// MFA token, by profile name. Only used for the service clients of that
+    /// profile.
+    pub profile_credentials: HashMap<String, SharedCredentialsProvider>,
+    pub retry_mode: RetryMode,
+    /// `None` uses the default of the SDK
+    pub max_attempts: Option<u32>,
+    pub endpoint_url: Option<String>,
+    pub client_config: ClientConfig,
+}
+
+impl AwsConfig {
+    /// The SDK has no legacy retry mode, [`RetryMode::Legacy`] maps to the
+    /// standard mode
+    pub fn retry_config(&self) -> RetryConfig {
+        let config = match self.retry_mode {
+            RetryMode::Legacy | RetryMode::Standard => RetryConfig::standard(),
+            RetryMode::Adaptive => RetryConfig::adaptive(),
+        };
+        match self.max_attempts {
+            Some(max_attempts) => config.with_max_attempts(max_attempts),
+            None => config,
+        }
+    }
+
+    /// Loads one [`RegionClient`] per region, see [`super::load_sdk_clients()`]
+    pub async fn load_clients(&self) -> Vec<RegionClient> {
+        let retry_config = self.retry_config();
+
+        load_sdk_clients_with(
+            &self.regions,
+            &self.profile_config,
+            |loader, profile| {
+                let mut loader = loader.retry_config(retry_config.clone());
+                if let Some(ref endpoint_url) = self.endpoint_url {
+                    loader = loader.endpoint_url(endpoint_url);
+                }
+                if let Some(credentials) = self
+                    .credentials
+                    .as_ref()
+                    .or_else(|| self.profile_credentials.get(profile.as_str()))
+                {
+                    loader = loader.credentials_provider(credentials.clone());
+                }
+                loader
+            },
+            &self.client_config,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+[default]
+region = us-east-1
+retry_mode = adaptive
+max_attempts = 5
+endpoint_url = http://localhost:4566
+
+[profile prod]
+region = eu-central-1
+
+[profile admin]
+role_arn = arn:aws:iam::123456789012:role/admin
+source_profile = default
+mfa_serial = arn:aws:iam::123456789012:mfa/alice
+";
+
+    fn resolve(builder: ClientBuilder, env: &[(&str, &str)]) -> Result<AwsConfig, Error> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+            .collect();
+        let profiles = ProfileSet::parse(CONFIG, "").unwrap();
+        builder.resolve(|name| env.get(name).cloned(), &profiles)
+    }
+
+    fn region_names(config: &AwsConfig) -> Vec<&'static str> {
+        config
+            .regions
+            .iter()
+            .map(|region| region.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn profile_values() {
+        let config = resolve(ClientBuilder::default(), &[]).unwrap();
+
+        assert_eq!(region_names(&config), vec!["us-east-1"]);
+        assert_eq!(config.retry_mode, RetryMode::Adaptive);
+        assert_eq!(config.max_attempts, Some(5));
+        assert_eq!(
+            config.endpoint_url.as_deref(),
+            Some("http://localhost:4566")
+        );
+        assert_eq!(config.client_config.timeouts, Timeouts::default());
+        assert!(config.credentials.is_none());
+        assert!(config.profile_credentials.is_empty());
+    }
+
+    fn mfa_profiles(config: &AwsConfig) -> Vec<&str> {
+        config
+            .profile_credentials
+            .keys()
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn mfa_profile() {
+        let config = resolve(
+            ClientBuilder::default()
+                .profile("admin")
+                .region(Region::EuCentral1),
+            &[],
+        )
+        .unwrap();
+        assert!(config.credentials.is_none());
+        assert_eq!(mfa_profiles(&config), ["admin"]);
+
+        let config = resolve(
+            ClientBuilder::default()
+                .profile("admin")
+                .cdn_profile("prod")
+                .region(Region::EuCentral1),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(mfa_profiles(&config), ["admin"], "not for the CDN profile");
+
+        let config = resolve(
+            ClientBuilder::default()
+                .profile("prod")
+                .cdn_profile("admin")
+                .region(Region::EuCentral1),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(mfa_profiles(&config), ["admin"], "only for the CDN profile");
+    }
+
+    #[test]
+    fn mfa_profile_with_env_credentials() {
+        let config = resolve(
+            ClientBuilder::default()
+                .profile("admin")
+                .region(Region::EuCentral1),
+            &[
+                ("AWS_ACCESS_KEY_ID", "AKIDENV"),
+                ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ],
+        )
+        .unwrap();
+        assert!(config.profile_credentials.is_empty(), "env credentials win");
+    }
+
+    #[test]
+    fn mfa_sts_region() {
+        let profiles = ProfileSet::parse(CONFIG, "").unwrap();
+        let no_env = |_: &str| None;
+
+        // `admin` has no region, its source profile `default` has
+        assert_eq!(
+            sts_region(&profiles, no_env, "admin", &[Region::EuCentral1]).unwrap(),
+            Region::UsEast1
+        );
+        assert_eq!(
+            sts_region(
+                &profiles,
+                |name| (name == "AWS_REGION").then(|| "us-gov-west-1".to_owned()),
+                "admin",
+                &[Region::EuCentral1]
+            )
+            .unwrap(),
+            Region::UsGovWest1
+        );
+    }
+
+    #[test]
+    fn env_overrides_profile() {
+        let config = resolve(
+            ClientBuilder::default(),
+            &[
+                ("AWS_PROFILE", "prod"),
+                ("AWS_REGION", ""),
+                ("AWS_DEFAULT_REGION", "us-gov-west-1"),
+                ("AWS_RETRY_MODE", "legacy"),
+                ("AWS_MAX_ATTEMPTS", "2"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(region_names(&config), vec!["us-gov-west-1"]);
+        assert_eq!(config.retry_mode, RetryMode::Legacy);
+        assert_eq!(config.max_attempts, Some(2));
+        assert_eq!(config.endpoint_url, None);
+    }
+
+    #[test]
+    fn code_overrides_env() {
+        let config = resolve(
+            ClientBuilder::default()
+                .profile("prod")
+                .regions([Region::EuCentral1, Region::UsEast1])
+                .retry_mode(RetryMode::Standard)
+                .max_attempts(4)
+                .endpoint_url("http://localhost:9000"),
+            &[
+                ("AWS_PROFILE", "default"),
+                ("AWS_REGION", "cn-north-1"),
+                ("AWS_RETRY_MODE", "adaptive"),
+                ("AWS_MAX_ATTEMPTS", "2"),
+                ("AWS_ENDPOINT_URL", "http://localhost:4566"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(region_names(&config), vec!["eu-central-1", "us-east-1"]);
+        assert_eq!(config.retry_mode, RetryMode::Standard);
+        assert_eq!(config.max_attempts, Some(4));
+        assert_eq!(
+            config.endpoint_url.as_deref(),
+            Some("http://localhost:9000")
+        );
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let is_profile_error = |result: Result<AwsConfig, Error>| {
+            matches!(
+                result,
+                Err(Error::InvalidClientConfig { ref setting, .. }) if setting == "profile"
+            )
+        };
+        assert!(
+            is_profile_error(resolve(
+                ClientBuilder::default()
+                    .profile("missing")
+                    .region(Region::EuCentral1),
+                &[]
+            )),
+            "unknown profile"
+        );
+        assert!(
+            is_profile_error(resolve(
+                ClientBuilder::default(),
+                &[("AWS_PROFILE", "missing"), ("AWS_REGION", "eu-central-1")]
+            )),
+            "unknown profile from environment"
+        );
+        assert!(
+            is_profile_error(resolve(
+                ClientBuilder::default()
+                    .cdn_profile("missing")
+                    .region(Region::EuCentral1),
+                &[]
+            )),
+            "unknown CDN profile"
+        );
+        assert!(
+            resolve(ClientBuilder::default(), &[("AWS_REGION", "mars-1")]).is_err(),
+            "unknown region"
+        );
+        assert!(
+            resolve(ClientBuilder::default(), &[("AWS_RETRY_MODE", "fast")]).is_err(),
+            "unknown retry mode"
+        );
+        assert!(
+            resolve(ClientBuilder::default(), &[("AWS_MAX_ATTEMPTS", "0")]).is_err(),
+            "zero attempts"
+        );
+    }
+}
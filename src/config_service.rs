@@ -0,0 +1,450 @@
+//! AWS Config inventory and compliance queries
+//!
+//! Config keeps an inventory of resources across services. Together with the
+//! tag schemas of this crate, it can be used to find resources that violate
+//! tag policies without querying every service separately.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{tags::TagList, Error, RegionClient};
+
+/// Identifies a resource in the Config inventory
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceRef {
+    /// e.g. `AWS::EC2::Instance`
+    pub resource_type: String,
+    pub resource_id: String,
+}
+
+impl fmt::Display for ResourceRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.resource_type, self.resource_id)
+    }
+}
+
+/// Runs an advanced query, returning each result row as a JSON document.
+///
+/// ```text
+/// SELECT resourceId, resourceType, tags WHERE resourceType = 'AWS::EC2::Instance'
+/// ```
+pub async fn select_resource_config(
+    client: &RegionClient,
+    expression: &str,
+) -> Result<Vec<String>, Error> {
+    let mut results = Vec::new();
+    let mut next_token = None;
+
+    loop {
+        let output = client
+            .main
+            .configservice
+            .select_resource_config()
+            .expression(expression)
+            .set_next_token(next_token)
+            .send()
+            .await?;
+
+        results.extend(output.results.unwrap_or_default());
+
+        match output.next_token {
+            Some(token) if !token.is_empty() => next_token = Some(token),
+            _ => break,
+        }
+    }
+
+    Ok(results)
+}
+
+/// Like [`select_resource_config()`], but deserializes each row
+#[cfg(feature = "serde")]
+pub async fn select_resource_config_json<T: DeserializeOwned>(
+    client: &RegionClient,
+    expression: &str,
+) -> Result<Vec<T>, Error> {
+    select_resource_config(client, expression)
+        .await?
+        .iter()
+        .map(|row| {
+            serde_json::from_str(row).map_err(|e| Error::InvalidPayload {
+                message: e.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplianceType {
+    Compliant,
+    NonCompliant,
+    NotApplicable,
+    InsufficientData,
+}
+
+impl From<ComplianceType> for aws_sdk_config::types::ComplianceType {
+    fn from(compliance: ComplianceType) -> Self {
+        match compliance {
+            ComplianceType::Compliant => Self::Compliant,
+            ComplianceType::NonCompliant => Self::NonCompliant,
+            ComplianceType::NotApplicable => Self::NotApplicable,
+            ComplianceType::InsufficientData => Self::InsufficientData,
+        }
+    }
+}
+
+impl TryFrom<aws_sdk_config::types::ComplianceType> for ComplianceType {
+    type Error = Error;
+
+    fn try_from(compliance: aws_sdk_config::types::ComplianceType) -> Result<Self, Self::Error> {
+        use aws_sdk_config::types::ComplianceType;
+
+        Ok(match compliance {
+            ComplianceType::Compliant => Self::Compliant,
+            ComplianceType::NonCompliant => Self::NonCompliant,
+            ComplianceType::NotApplicable => Self::NotApplicable,
+            ComplianceType::InsufficientData => Self::InsufficientData,
+            other => {
+                return Err(Error::InvalidResponseError {
+                    message: format!("unknown compliance type \"{}\"", other.as_str()),
+                })
+            }
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceCompliance {
+    pub resource: ResourceRef,
+    pub compliance: Option<ComplianceType>,
+}
+
+/// Returns the compliance of resources of the given type, optionally limited
+/// to a single resource and to the given compliance types
+pub async fn describe_compliance_by_resource(
+    client: &RegionClient,
+    resource_type: &str,
+    resource_id: Option<&str>,
+    compliance_types: Vec<ComplianceType>,
+) -> Result<Vec<ResourceCompliance>, Error> {
+    let mut results = Vec::new();
+    let mut next_token = None;
+
+    loop {
+        let output = client
+            .main
+            .configservice
+            .describe_compliance_by_resource()
+            .resource_type(resource_type)
+            .set_resource_id(resource_id.map(ToOwned::to_owned))
+            .set_compliance_types(
+                (!compliance_types.is_empty())
+                    .then(|| compliance_types.iter().copied().map(Into::into).collect()),
+            )
+            .set_next_token(next_token)
+            .send()
+            .await?;
+
+        for entry in output.compliance_by_resources.unwrap_or_default() {
+            results.push(ResourceCompliance {
+                resource: ResourceRef {
+                    resource_type: entry.resource_type.ok_or_else(|| {
+                        Error::UnexpectedNoneValue {
+                            entity: "ComplianceByResource.resource_type".to_owned(),
+                        }
+                    })?,
+                    resource_id: entry
+                        .resource_id
+                        .ok_or_else(|| Error::UnexpectedNoneValue {
+                            entity: "ComplianceByResource.resource_id".to_owned(),
+                        })?,
+                },
+                compliance: entry
+                    .compliance
+                    .and_then(|compliance| compliance.compliance_type)
+                    .map(TryInto::try_into)
+                    .transpose()?,
+            });
+        }
+
+        match output.next_token {
+            Some(token) if !token.is_empty() => next_token = Some(token),
+            _ => break,
+        }
+    }
+
+    Ok(results)
+}
+
+/// Returns the tags of the latest recorded configuration of a resource, or
+/// `None` if Config has not recorded the resource
+pub async fn resource_tags(
+    client: &RegionClient,
+    resource: &ResourceRef,
+) -> Result<Option<TagList>, Error> {
+    Ok(client
+        .main
+        .configservice
+        .get_resource_config_history()
+        .resource_type(aws_sdk_config::types::ResourceType::from(
+            resource.resource_type.as_str(),
+        ))
+        .resource_id(&resource.resource_id)
+        .limit(1)
+        .send()
+        .await?
+        .configuration_items
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .map(|item| item.tags.unwrap_or_default().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_ref_display() {
+        let resource = ResourceRef {
+            resource_type: "AWS::EC2::Instance".to_owned(),
+            resource_id: "i-0123456789abcdef0".to_owned(),
+        };
+        assert_eq!(
+            resource.to_string(),
+            "AWS::EC2::Instance i-0123456789abcdef0"
+        );
+    }
+
+    #[test]
+    fn compliance_type_round_trip() {
+        for compliance in [
+            ComplianceType::Compliant,
+            ComplianceType::NonCompliant,
+            ComplianceType::NotApplicable,
+            ComplianceType::InsufficientData,
+        ] {
+            let aws = aws_sdk_config::types::ComplianceType::from(compliance);
+            assert_eq!(ComplianceType::try_from(aws).unwrap(), compliance);
+        }
+
+        assert_eq!(
+            aws_sdk_config::types::ComplianceType::from(ComplianceType::NonCompliant).as_str(),
+            "NON_COMPLIANT"
+        );
+    }
+
+    #[test]
+    fn unknown_compliance_type_is_rejected() {
+        let unknown = aws_sdk_config::types::ComplianceType::from("PARTIALLY_COMPLIANT");
+        assert!(matches!(
+            ComplianceType::try_from(unknown),
+            Err(Error::InvalidResponseError { ref message })
+                if message.contains("PARTIALLY_COMPLIANT")
+        ));
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        #[derive(Debug, Deserialize)]
+        struct Row {
+            #[serde(rename = "resourceId")]
+            resource_id: String,
+        }
+
+        fn instance(id: &str) -> ResourceRef {
+            ResourceRef {
+                resource_type: "AWS::EC2::Instance".to_owned(),
+                resource_id: id.to_owned(),
+            }
+        }
+
+        #[test]
+        fn select_follows_next_tokens() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("NextToken", "page2"),
+                    MockResponse::ok(
+                        r#"{"Results": ["{\"resourceId\": \"i-2\"}"], "NextToken": ""}"#,
+                    ),
+                )
+                .on(
+                    Matcher::action("SelectResourceConfig"),
+                    MockResponse::ok(
+                        r#"{"Results": ["{\"resourceId\": \"i-1\"}"], "NextToken": "page2"}"#,
+                    ),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let rows: Vec<Row> = block_on(select_resource_config_json(
+                &client,
+                "SELECT resourceId WHERE resourceType = 'AWS::EC2::Instance'",
+            ))
+            .unwrap();
+
+            assert_eq!(
+                rows.iter()
+                    .map(|row| row.resource_id.as_str())
+                    .collect::<Vec<_>>(),
+                ["i-1", "i-2"]
+            );
+            assert_eq!(http.requests().unwrap().len(), 2, "an empty token ends");
+        }
+
+        #[test]
+        fn invalid_row_is_rejected() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("SelectResourceConfig"),
+                MockResponse::ok(r#"{"Results": ["{\"id\": \"i-1\"}"]}"#),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            assert!(matches!(
+                block_on(select_resource_config_json::<Row>(
+                    &client,
+                    "SELECT resourceId"
+                )),
+                Err(Error::InvalidPayload { .. })
+            ));
+        }
+
+        #[test]
+        fn invalid_expression_is_an_error() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("SelectResourceConfig"),
+                MockResponse::status(
+                    400,
+                    r#"{"__type": "InvalidExpressionException", "message": "invalid"}"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let error = block_on(select_resource_config(&client, "SELECT")).unwrap_err();
+            assert_eq!(
+                error.request_metadata().unwrap().error_code.as_deref(),
+                Some("InvalidExpressionException")
+            );
+        }
+
+        #[test]
+        fn compliance_is_filtered_and_paginated() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("NextToken", "page2"),
+                    MockResponse::ok(
+                        r#"{"ComplianceByResources": [{"ResourceType": "AWS::EC2::Instance", "ResourceId": "i-2"}]}"#,
+                    ),
+                )
+                .on(
+                    Matcher::action("DescribeComplianceByResource"),
+                    MockResponse::ok(
+                        r#"{"ComplianceByResources": [{"ResourceType": "AWS::EC2::Instance", "ResourceId": "i-1",
+                            "Compliance": {"ComplianceType": "NON_COMPLIANT"}}], "NextToken": "page2"}"#,
+                    ),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let compliance = block_on(describe_compliance_by_resource(
+                &client,
+                "AWS::EC2::Instance",
+                None,
+                vec![ComplianceType::NonCompliant],
+            ))
+            .unwrap();
+
+            assert_eq!(
+                compliance,
+                [
+                    ResourceCompliance {
+                        resource: instance("i-1"),
+                        compliance: Some(ComplianceType::NonCompliant),
+                    },
+                    ResourceCompliance {
+                        resource: instance("i-2"),
+                        compliance: None,
+                    },
+                ]
+            );
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(
+                request
+                    .json_param::<Vec<String>>("ComplianceTypes")
+                    .unwrap(),
+                ["NON_COMPLIANT"]
+            );
+            assert_eq!(request.param("ResourceId"), None);
+        }
+
+        #[test]
+        fn compliance_without_resource_id_is_rejected() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeComplianceByResource"),
+                MockResponse::ok(
+                    r#"{"ComplianceByResources": [{"ResourceType": "AWS::EC2::Instance"}]}"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            assert!(matches!(
+                block_on(describe_compliance_by_resource(
+                    &client,
+                    "AWS::EC2::Instance",
+                    None,
+                    Vec::new(),
+                )),
+                Err(Error::UnexpectedNoneValue { .. })
+            ));
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(
+                request.json_param::<Vec<String>>("ComplianceTypes"),
+                None,
+                "no filter is sent for no compliance types"
+            );
+        }
+
+        #[test]
+        fn resource_tags_of_latest_configuration() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("resourceId", "i-1"),
+                    MockResponse::ok(
+                        r#"{"configurationItems": [{"resourceId": "i-1", "tags": {"team": "infra"}}]}"#,
+                    ),
+                )
+                .on(
+                    Matcher::action("GetResourceConfigHistory"),
+                    MockResponse::ok(r#"{"configurationItems": []}"#),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let tags = block_on(resource_tags(&client, &instance("i-1"))).unwrap();
+            assert_eq!(
+                tags.unwrap().as_slice(),
+                [RawTag::new("team".to_owned(), "infra".to_owned())]
+            );
+            assert_eq!(
+                http.requests()
+                    .unwrap()
+                    .pop()
+                    .unwrap()
+                    .json_param::<i32>("limit"),
+                Some(1)
+            );
+
+            assert!(block_on(resource_tags(&client, &instance("i-2")))
+                .unwrap()
+                .is_none());
+        }
+    }
+}
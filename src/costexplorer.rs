@@ -0,0 +1,389 @@
+//! Cost Explorer cost and usage reports
+//!
+//! Costs can be grouped by tag keys. The keys of each returned group are then
+//! available as a [`TagList`] via [`CostGroup::tags()`], so they can be parsed
+//! with the same tag schemas used for the resources themselves.
+
+use std::{collections::HashMap, fmt};
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{RawTag, TagKey, TagList},
+    Error, RegionClient,
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A range of days. `start` is inclusive, `end` is exclusive.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+impl DateRange {
+    pub fn new(start: NaiveDate, end: NaiveDate) -> Result<Self, Error> {
+        if start >= end {
+            return Err(Error::InvalidDateRange {
+                start: start.to_string(),
+                end: end.to_string(),
+            });
+        }
+        Ok(Self { start, end })
+    }
+
+    pub const fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    pub const fn end(&self) -> NaiveDate {
+        self.end
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.start, self.end)
+    }
+}
+
+impl From<DateRange> for aws_sdk_costexplorer::types::DateInterval {
+    fn from(range: DateRange) -> Self {
+        Self::builder()
+            .start(range.start.format(DATE_FORMAT).to_string())
+            .end(range.end.format(DATE_FORMAT).to_string())
+            .build()
+            .expect("builder misused")
+    }
+}
+
+impl TryFrom<aws_sdk_costexplorer::types::DateInterval> for DateRange {
+    type Error = Error;
+
+    fn try_from(interval: aws_sdk_costexplorer::types::DateInterval) -> Result<Self, Self::Error> {
+        let parse = |value: &str| {
+            // Hourly granularity returns full timestamps, only the date is kept
+            let date = value.get(..10).unwrap_or(value);
+            NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|e| Error::InvalidTimestampError {
+                value: value.to_owned(),
+                message: e.to_string(),
+            })
+        };
+
+        Ok(Self {
+            start: parse(&interval.start)?,
+            end: parse(&interval.end)?,
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    /// Only available for the last 14 days, and has to be enabled for the account
+    Hourly,
+    Daily,
+    Monthly,
+}
+
+impl From<Granularity> for aws_sdk_costexplorer::types::Granularity {
+    fn from(granularity: Granularity) -> Self {
+        match granularity {
+            Granularity::Hourly => Self::Hourly,
+            Granularity::Daily => Self::Daily,
+            Granularity::Monthly => Self::Monthly,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Metric {
+    AmortizedCost,
+    BlendedCost,
+    NetAmortizedCost,
+    NetUnblendedCost,
+    NormalizedUsageAmount,
+    UnblendedCost,
+    UsageQuantity,
+}
+
+impl Metric {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::AmortizedCost => "AmortizedCost",
+            Self::BlendedCost => "BlendedCost",
+            Self::NetAmortizedCost => "NetAmortizedCost",
+            Self::NetUnblendedCost => "NetUnblendedCost",
+            Self::NormalizedUsageAmount => "NormalizedUsageAmount",
+            Self::UnblendedCost => "UnblendedCost",
+            Self::UsageQuantity => "UsageQuantity",
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for Metric {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "AmortizedCost" => Self::AmortizedCost,
+            "BlendedCost" => Self::BlendedCost,
+            "NetAmortizedCost" => Self::NetAmortizedCost,
+            "NetUnblendedCost" => Self::NetUnblendedCost,
+            "NormalizedUsageAmount" => Self::NormalizedUsageAmount,
+            "UnblendedCost" => Self::UnblendedCost,
+            "UsageQuantity" => Self::UsageQuantity,
+            _ => {
+                return Err(Error::InvalidResponseError {
+                    message: format!("unknown cost metric \"{value}\""),
+                })
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupBy {
+    Tag(TagKey),
+    /// A dimension like `SERVICE`, `LINKED_ACCOUNT` or `REGION`
+    Dimension(String),
+    CostCategory(String),
+}
+
+impl From<GroupBy> for aws_sdk_costexplorer::types::GroupDefinition {
+    fn from(group_by: GroupBy) -> Self {
+        use aws_sdk_costexplorer::types::GroupDefinitionType;
+
+        let (kind, key) = match group_by {
+            GroupBy::Tag(key) => (GroupDefinitionType::Tag, key.into_string()),
+            GroupBy::Dimension(key) => (GroupDefinitionType::Dimension, key),
+            GroupBy::CostCategory(key) => (GroupDefinitionType::CostCategory, key),
+        };
+
+        Self::builder().r#type(kind).key(key).build()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CostQuery {
+    pub range: DateRange,
+    pub granularity: Granularity,
+    pub metrics: Vec<Metric>,
+    /// At most two group definitions are allowed
+    pub group_by: Vec<GroupBy>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Amount {
+    pub value: f64,
+    pub unit: CurrencyUnit,
+}
+
+/// The unit of an [`Amount`], usually `USD` for costs
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurrencyUnit {
+    Usd,
+    Other,
+}
+
+fn parse_metrics(
+    metrics: Option<HashMap<String, aws_sdk_costexplorer::types::MetricValue>>,
+) -> Result<HashMap<Metric, Amount>, Error> {
+    metrics
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(metric, value)| {
+            let amount = value.amount.unwrap_or_default();
+            Ok((
+                Metric::try_from(metric.as_str())?,
+                Amount {
+                    value: amount.parse().map_err(|e| Error::InvalidResponseError {
+                        message: format!("invalid amount \"{amount}\": {e}"),
+                    })?,
+                    unit: if value.unit.as_deref() == Some("USD") {
+                        CurrencyUnit::Usd
+                    } else {
+                        CurrencyUnit::Other
+                    },
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct CostGroup {
+    keys: Vec<String>,
+    metrics: HashMap<Metric, Amount>,
+}
+
+impl CostGroup {
+    /// The raw group keys, in the order of [`CostQuery::group_by`]
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn metric(&self, metric: Metric) -> Option<&Amount> {
+        self.metrics.get(&metric)
+    }
+
+    /// The tags of this group when grouping by tag keys.
+    ///
+    /// Tag group keys have the form `<key>$<value>`. Costs of resources
+    /// without the tag have an empty value and are omitted here, so the
+    /// untagged group has an empty list.
+    pub fn tags(&self) -> TagList {
+        TagList::from_vec(
+            self.keys
+                .iter()
+                .filter_map(|key| key.split_once('$'))
+                .filter(|&(_key, value)| !value.is_empty())
+                .map(|(key, value)| RawTag::new(key.to_owned(), value.to_owned()))
+                .collect(),
+        )
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct CostPeriod {
+    range: DateRange,
+    estimated: bool,
+    total: HashMap<Metric, Amount>,
+    groups: Vec<CostGroup>,
+}
+
+impl TryFrom<aws_sdk_costexplorer::types::ResultByTime> for CostPeriod {
+    type Error = Error;
+
+    fn try_from(result: aws_sdk_costexplorer::types::ResultByTime) -> Result<Self, Self::Error> {
+        Ok(Self {
+            range: result
+                .time_period
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "ResultByTime.time_period".to_owned(),
+                })?
+                .try_into()?,
+            estimated: result.estimated,
+            total: parse_metrics(result.total)?,
+            groups: result
+                .groups
+                .unwrap_or_default()
+                .into_iter()
+                .map(|group| {
+                    Ok(CostGroup {
+                        keys: group.keys.unwrap_or_default(),
+                        metrics: parse_metrics(group.metrics)?,
+                    })
+                })
+                .collect::<Result<Vec<CostGroup>, Error>>()?,
+        })
+    }
+}
+
+impl CostPeriod {
+    pub const fn range(&self) -> &DateRange {
+        &self.range
+    }
+
+    /// Costs of periods that are not closed yet may still change
+    pub const fn estimated(&self) -> bool {
+        self.estimated
+    }
+
+    /// Only set when not grouping
+    pub fn total(&self, metric: Metric) -> Option<&Amount> {
+        self.total.get(&metric)
+    }
+
+    pub fn groups(&self) -> &[CostGroup] {
+        &self.groups
+    }
+}
+
+/// Runs the query, following all result pages
+pub async fn get_cost_and_usage(
+    client: &RegionClient,
+    query: CostQuery,
+) -> Result<Vec<CostPeriod>, Error> {
+    let metrics = query
+        .metrics
+        .iter()
+        .map(|metric| metric.as_str().to_owned())
+        .collect::<Vec<String>>();
+    let group_by = query
+        .group_by
+        .into_iter()
+        .map(Into::into)
+        .collect::<Vec<aws_sdk_costexplorer::types::GroupDefinition>>();
+
+    let mut periods = Vec::new();
+    let mut next_page_token = None;
+
+    loop {
+        let output = client
+            .main
+            .costexplorer
+            .get_cost_and_usage()
+            .time_period(query.range.into())
+            .granularity(query.granularity.into())
+            .set_metrics(Some(metrics.clone()))
+            .set_group_by((!group_by.is_empty()).then(|| group_by.clone()))
+            .set_next_page_token(next_page_token)
+            .send()
+            .await?;
+
+        for result in output.results_by_time.unwrap_or_default() {
+            periods.push(result.try_into()?);
+        }
+
+        match output.next_page_token {
+            Some(token) if !token.is_empty() => next_page_token = Some(token),
+            _ => break,
+        }
+    }
+
+    Ok(periods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_tags() {
+        let group = CostGroup {
+            keys: vec!["Environment$prod".to_owned(), "Team$".to_owned()],
+            metrics: HashMap::new(),
+        };
+
+        assert_eq!(
+            group.tags().into_vec(),
+            vec![RawTag::new("Environment".to_owned(), "prod".to_owned())]
+        );
+    }
+
+    #[test]
+    fn date_range_must_not_be_empty() {
+        let day = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        assert!(DateRange::new(day, day).is_err(), "empty range");
+        assert!(
+            DateRange::new(day, day.succ_opt().unwrap()).is_ok(),
+            "one day"
+        );
+    }
+}
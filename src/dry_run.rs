@@ -0,0 +1,185 @@
+//! Previewing mutating EC2 operations
+//!
+//! EC2 checks permissions and parameters of a request with `DryRun` set, but
+//! reports the outcome as an error: `DryRunOperation` if the request would
+//! have succeeded, `UnauthorizedOperation` if not. [`DryRun::send_dry_run()`]
+//! sends a request of the SDK with `DryRun` set and turns these errors into a
+//! [`DryRunResult`]:
+//!
+//! ```no_run
+//! # async fn f(client: &aws_lib::RegionClient) -> Result<(), aws_lib::Error> {
+//! use aws_lib::dry_run::{DryRun as _, DryRunResult};
+//!
+//! let result = client
+//!     .main
+//!     .ec2
+//!     .create_tags()
+//!     .resources("i-1234567890abcdef0")
+//!     .tags(aws_sdk_ec2::types::Tag::builder().key("owner").value("team-a").build())
+//!     .send_dry_run()
+//!     .await?;
+//!
+//! if let DryRunResult::Unauthorized { message } = result {
+//!     println!("tagging would fail: {message:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The Auto Scaling API has no dry run support, so there is nothing
+//! equivalent for its operations.
+
+use std::{fmt, future::Future};
+
+use aws_sdk_ec2::error::{ProvideErrorMetadata, SdkError};
+
+use super::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DryRunResult {
+    /// The caller has the required permissions and the request is valid
+    WouldSucceed,
+    /// The caller is missing permissions for the request
+    Unauthorized {
+        /// May contain an encoded authorization message with details, see
+        /// `sts:DecodeAuthorizationMessage`
+        message: Option<String>,
+    },
+}
+
+impl DryRunResult {
+    pub const fn would_succeed(&self) -> bool {
+        matches!(*self, Self::WouldSucceed)
+    }
+}
+
+impl fmt::Display for DryRunResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::WouldSucceed => write!(f, "would succeed"),
+            Self::Unauthorized { message: None } => write!(f, "unauthorized"),
+            Self::Unauthorized {
+                message: Some(ref message),
+            } => write!(f, "unauthorized: {message}"),
+        }
+    }
+}
+
+/// Maps the error code of a dry run response to its result. Any other code
+/// is a real error, e.g. an invalid parameter.
+fn classify(code: Option<&str>, message: Option<&str>) -> Option<DryRunResult> {
+    match code {
+        Some("DryRunOperation") => Some(DryRunResult::WouldSucceed),
+        Some("UnauthorizedOperation") => Some(DryRunResult::Unauthorized {
+            message: message.map(ToOwned::to_owned),
+        }),
+        _ => None,
+    }
+}
+
+/// Converts the response of a request sent with `DryRun` set
+///
+/// A successful response should not happen for a dry run and is treated as
+/// [`DryRunResult::WouldSucceed`].
+pub fn dry_run_result<T, E, R>(result: Result<T, SdkError<E, R>>) -> Result<DryRunResult, Error>
+where
+    E: ProvideErrorMetadata + std::error::Error + Send + 'static,
+    R: fmt::Debug + Send + 'static,
+{
+    match result {
+        Ok(_output) => Ok(DryRunResult::WouldSucceed),
+        Err(e) => classify(e.code(), e.message()).ok_or_else(|| e.into()),
+    }
+}
+
+/// An EC2 request that supports `DryRun`
+pub trait DryRun {
+    /// Sends the request with `DryRun` set
+    fn send_dry_run(self) -> impl Future<Output = Result<DryRunResult, Error>> + Send;
+}
+
+macro_rules! dry_run {
+    ($(($operation:ident, $builder:ident)),* $(,)?) => {
+        $(
+            impl DryRun for aws_sdk_ec2::operation::$operation::builders::$builder {
+                fn send_dry_run(self) -> impl Future<Output = Result<DryRunResult, Error>> + Send {
+                    async move { dry_run_result(self.dry_run(true).send().await) }
+                }
+            }
+        )*
+    };
+}
+
+dry_run!(
+    (associate_address, AssociateAddressFluentBuilder),
+    (associate_route_table, AssociateRouteTableFluentBuilder),
+    (
+        authorize_security_group_egress,
+        AuthorizeSecurityGroupEgressFluentBuilder
+    ),
+    (
+        authorize_security_group_ingress,
+        AuthorizeSecurityGroupIngressFluentBuilder
+    ),
+    (
+        cancel_spot_instance_requests,
+        CancelSpotInstanceRequestsFluentBuilder
+    ),
+    (copy_snapshot, CopySnapshotFluentBuilder),
+    (create_fleet, CreateFleetFluentBuilder),
+    (create_image, CreateImageFluentBuilder),
+    (create_route, CreateRouteFluentBuilder),
+    (create_route_table, CreateRouteTableFluentBuilder),
+    (create_security_group, CreateSecurityGroupFluentBuilder),
+    (create_snapshot, CreateSnapshotFluentBuilder),
+    (create_subnet, CreateSubnetFluentBuilder),
+    (create_tags, CreateTagsFluentBuilder),
+    (create_vpc, CreateVpcFluentBuilder),
+    (delete_fleets, DeleteFleetsFluentBuilder),
+    (delete_route, DeleteRouteFluentBuilder),
+    (delete_route_table, DeleteRouteTableFluentBuilder),
+    (delete_security_group, DeleteSecurityGroupFluentBuilder),
+    (delete_snapshot, DeleteSnapshotFluentBuilder),
+    (delete_subnet, DeleteSubnetFluentBuilder),
+    (delete_tags, DeleteTagsFluentBuilder),
+    (delete_vpc, DeleteVpcFluentBuilder),
+    (deregister_image, DeregisterImageFluentBuilder),
+    (reboot_instances, RebootInstancesFluentBuilder),
+    (request_spot_instances, RequestSpotInstancesFluentBuilder),
+    (
+        revoke_security_group_egress,
+        RevokeSecurityGroupEgressFluentBuilder
+    ),
+    (
+        revoke_security_group_ingress,
+        RevokeSecurityGroupIngressFluentBuilder
+    ),
+    (run_instances, RunInstancesFluentBuilder),
+    (start_instances, StartInstancesFluentBuilder),
+    (stop_instances, StopInstancesFluentBuilder),
+    (terminate_instances, TerminateInstancesFluentBuilder),
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_dry_run_errors() {
+        assert_eq!(
+            classify(
+                Some("DryRunOperation"),
+                Some("Request would have succeeded")
+            ),
+            Some(DryRunResult::WouldSucceed)
+        );
+        assert_eq!(
+            classify(Some("UnauthorizedOperation"), Some("encoded")),
+            Some(DryRunResult::Unauthorized {
+                message: Some("encoded".to_owned())
+            })
+        );
+        assert_eq!(classify(Some("InvalidInstanceID.NotFound"), None), None);
+        assert_eq!(classify(None, None), None);
+    }
+}
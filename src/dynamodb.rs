@@ -0,0 +1,714 @@
+//! Item-based access to DynamoDB tables
+//!
+//! Items are mapped to and from Rust types via the [`Item`] trait, which can be
+//! derived with [`DynamoItem`]:
+//!
+//! ```rust
+//! # use aws_lib::dynamodb::DynamoItem;
+//! #[derive(DynamoItem)]
+//! struct User {
+//!     id: String,
+//!     #[dynamo(rename = "mail")]
+//!     email: Option<String>,
+//!     logins: u64,
+//! }
+//! ```
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+use std::{collections::HashMap, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use aws_macros::DynamoItem;
+pub use aws_sdk_dynamodb::types::AttributeValue;
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::waiter::Backoff;
+use super::{Error, RegionClient};
+
+/// DynamoDB accepts at most this many write requests per batch
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_BATCH_WRITE_SIZE: usize = 25;
+
+/// How often a batch write is sent, including the first attempt
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_BATCH_WRITE_ATTEMPTS: usize = 5;
+
+/// Delay before unprocessed items are resubmitted. DynamoDB leaves items
+/// unprocessed when it throttles, so retrying right away rarely helps.
+#[cfg(not(target_arch = "wasm32"))]
+const BATCH_WRITE_BACKOFF: Backoff = Backoff {
+    initial: Duration::from_millis(100),
+    max: Duration::from_secs(5),
+    factor: 2,
+};
+
+#[derive(Debug, Clone)]
+pub enum ParseItemError {
+    MissingAttribute { name: String },
+    InvalidAttribute { name: String, message: String },
+}
+
+impl std::error::Error for ParseItemError {}
+
+impl fmt::Display for ParseItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::MissingAttribute { ref name } => write!(f, "attribute \"{name}\" not found"),
+            Self::InvalidAttribute {
+                ref name,
+                ref message,
+            } => write!(f, "invalid value for attribute \"{name}\": {message}"),
+        }
+    }
+}
+
+/// A type that can be stored as a single DynamoDB attribute
+pub trait AttributeType: Sized {
+    fn into_attribute(self) -> AttributeValue;
+    fn from_attribute(value: AttributeValue) -> Result<Self, String>;
+}
+
+/// A type that can be stored as a whole DynamoDB item. Usually derived via
+/// [`DynamoItem`].
+pub trait Item: Sized {
+    fn into_item(self) -> HashMap<String, AttributeValue>;
+    fn from_item(item: HashMap<String, AttributeValue>) -> Result<Self, ParseItemError>;
+}
+
+impl AttributeType for String {
+    fn into_attribute(self) -> AttributeValue {
+        AttributeValue::S(self)
+    }
+
+    fn from_attribute(value: AttributeValue) -> Result<Self, String> {
+        match value {
+            AttributeValue::S(s) => Ok(s),
+            _ => Err("expected attribute of type S".to_owned()),
+        }
+    }
+}
+
+impl AttributeType for bool {
+    fn into_attribute(self) -> AttributeValue {
+        AttributeValue::Bool(self)
+    }
+
+    fn from_attribute(value: AttributeValue) -> Result<Self, String> {
+        match value {
+            AttributeValue::Bool(b) => Ok(b),
+            _ => Err("expected attribute of type BOOL".to_owned()),
+        }
+    }
+}
+
+macro_rules! number_attribute {
+    ($($ty:ty),*) => {
+        $(
+            impl AttributeType for $ty {
+                fn into_attribute(self) -> AttributeValue {
+                    AttributeValue::N(self.to_string())
+                }
+
+                fn from_attribute(value: AttributeValue) -> Result<Self, String> {
+                    match value {
+                        AttributeValue::N(n) => n
+                            .parse()
+                            .map_err(|e| format!("failed parsing \"{n}\" as number: {e}")),
+                        _ => Err("expected attribute of type N".to_owned()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+number_attribute!(i32, i64, u32, u64, f64);
+
+impl<T: AttributeType> AttributeType for Vec<T> {
+    fn into_attribute(self) -> AttributeValue {
+        AttributeValue::L(
+            self.into_iter()
+                .map(AttributeType::into_attribute)
+                .collect(),
+        )
+    }
+
+    fn from_attribute(value: AttributeValue) -> Result<Self, String> {
+        match value {
+            AttributeValue::L(list) => list.into_iter().map(T::from_attribute).collect(),
+            _ => Err("expected attribute of type L".to_owned()),
+        }
+    }
+}
+
+/// The primary key of an item
+#[derive(Debug, Clone)]
+pub struct Key(HashMap<String, AttributeValue>);
+
+impl Key {
+    pub fn partition(name: impl Into<String>, value: impl AttributeType) -> Self {
+        Self(HashMap::from([(name.into(), value.into_attribute())]))
+    }
+
+    #[must_use]
+    pub fn with_sort(mut self, name: impl Into<String>, value: impl AttributeType) -> Self {
+        let _previous = self.0.insert(name.into(), value.into_attribute());
+        self
+    }
+
+    pub fn into_inner(self) -> HashMap<String, AttributeValue> {
+        self.0
+    }
+}
+
+/// The names and values of [`ExpressionAttributes`] as passed to the SDK,
+/// `None` if empty
+type ExpressionAttributeMaps = (
+    Option<HashMap<String, String>>,
+    Option<HashMap<String, AttributeValue>>,
+);
+
+/// Placeholders for attribute names and values of an expression. Names are
+/// always substituted to avoid clashes with reserved words.
+#[derive(Debug, Clone, Default)]
+struct ExpressionAttributes {
+    names: HashMap<String, String>,
+    values: HashMap<String, AttributeValue>,
+}
+
+impl ExpressionAttributes {
+    fn name(&mut self, name: String) -> String {
+        let placeholder = format!("#n{}", self.names.len());
+        let _previous = self.names.insert(placeholder.clone(), name);
+        placeholder
+    }
+
+    fn value(&mut self, value: AttributeValue) -> String {
+        let placeholder = format!(":v{}", self.values.len());
+        let _previous = self.values.insert(placeholder.clone(), value);
+        placeholder
+    }
+
+    fn into_parts(self) -> ExpressionAttributeMaps {
+        (
+            (!self.names.is_empty()).then_some(self.names),
+            (!self.values.is_empty()).then_some(self.values),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SortKeyCondition {
+    Eq(AttributeValue),
+    Lt(AttributeValue),
+    Le(AttributeValue),
+    Gt(AttributeValue),
+    Ge(AttributeValue),
+    Between(AttributeValue, AttributeValue),
+    BeginsWith(AttributeValue),
+}
+
+/// The key condition of a [`Table::query()`]
+#[derive(Debug, Clone)]
+pub struct KeyCondition {
+    partition_key: (String, AttributeValue),
+    sort_key: Option<(String, SortKeyCondition)>,
+}
+
+impl KeyCondition {
+    pub fn partition(name: impl Into<String>, value: impl AttributeType) -> Self {
+        Self {
+            partition_key: (name.into(), value.into_attribute()),
+            sort_key: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_sort(mut self, name: impl Into<String>, condition: SortKeyCondition) -> Self {
+        self.sort_key = Some((name.into(), condition));
+        self
+    }
+
+    fn render(self, attributes: &mut ExpressionAttributes) -> String {
+        let (name, value) = self.partition_key;
+        let mut expression = format!("{} = {}", attributes.name(name), attributes.value(value));
+
+        if let Some((name, condition)) = self.sort_key {
+            let name = attributes.name(name);
+            let condition = match condition {
+                SortKeyCondition::Eq(value) => format!("{name} = {}", attributes.value(value)),
+                SortKeyCondition::Lt(value) => format!("{name} < {}", attributes.value(value)),
+                SortKeyCondition::Le(value) => format!("{name} <= {}", attributes.value(value)),
+                SortKeyCondition::Gt(value) => format!("{name} > {}", attributes.value(value)),
+                SortKeyCondition::Ge(value) => format!("{name} >= {}", attributes.value(value)),
+                SortKeyCondition::Between(low, high) => format!(
+                    "{name} BETWEEN {} AND {}",
+                    attributes.value(low),
+                    attributes.value(high)
+                ),
+                SortKeyCondition::BeginsWith(value) => {
+                    format!("begins_with({name}, {})", attributes.value(value))
+                }
+            };
+            expression = format!("{expression} AND {condition}");
+        }
+
+        expression
+    }
+}
+
+/// Builds an update expression for [`Table::update_item()`]
+///
+/// ```rust
+/// # use aws_lib::dynamodb::UpdateExpression;
+/// let update = UpdateExpression::new()
+///     .set("email", "foo@example.com".to_owned())
+///     .add("logins", 1_u64)
+///     .remove("deactivated_at");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UpdateExpression {
+    set: Vec<String>,
+    remove: Vec<String>,
+    add: Vec<String>,
+    attributes: ExpressionAttributes,
+}
+
+impl UpdateExpression {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn set(mut self, name: impl Into<String>, value: impl AttributeType) -> Self {
+        let name = self.attributes.name(name.into());
+        let value = self.attributes.value(value.into_attribute());
+        self.set.push(format!("{name} = {value}"));
+        self
+    }
+
+    /// Adds `value` to a number attribute, or to a set attribute
+    #[must_use]
+    pub fn add(mut self, name: impl Into<String>, value: impl AttributeType) -> Self {
+        let name = self.attributes.name(name.into());
+        let value = self.attributes.value(value.into_attribute());
+        self.add.push(format!("{name} {value}"));
+        self
+    }
+
+    #[must_use]
+    pub fn remove(mut self, name: impl Into<String>) -> Self {
+        let name = self.attributes.name(name.into());
+        self.remove.push(name);
+        self
+    }
+
+    fn render(&self) -> String {
+        [
+            ("SET", &self.set),
+            ("REMOVE", &self.remove),
+            ("ADD", &self.add),
+        ]
+        .into_iter()
+        .filter(|&(_action, clauses)| !clauses.is_empty())
+        .map(|(action, clauses)| format!("{action} {}", clauses.join(", ")))
+        .collect::<Vec<String>>()
+        .join(" ")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WriteRequest {
+    Put(HashMap<String, AttributeValue>),
+    Delete(Key),
+}
+
+impl WriteRequest {
+    pub fn put(item: impl Item) -> Self {
+        Self::Put(item.into_item())
+    }
+
+    pub const fn delete(key: Key) -> Self {
+        Self::Delete(key)
+    }
+}
+
+impl From<WriteRequest> for aws_sdk_dynamodb::types::WriteRequest {
+    fn from(request: WriteRequest) -> Self {
+        match request {
+            WriteRequest::Put(item) => Self::builder()
+                .put_request(
+                    aws_sdk_dynamodb::types::PutRequest::builder()
+                        .set_item(Some(item))
+                        .build()
+                        .expect("builder has missing fields"),
+                )
+                .build(),
+            WriteRequest::Delete(key) => Self::builder()
+                .delete_request(
+                    aws_sdk_dynamodb::types::DeleteRequest::builder()
+                        .set_key(Some(key.into_inner()))
+                        .build()
+                        .expect("builder has missing fields"),
+                )
+                .build(),
+        }
+    }
+}
+
+string_newtype!(TableName);
+
+impl TableName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Table {
+    name: TableName,
+}
+
+impl Table {
+    pub const fn new(name: TableName) -> Self {
+        Self { name }
+    }
+
+    pub const fn name(&self) -> &TableName {
+        &self.name
+    }
+
+    pub async fn get_item<T: Item>(
+        &self,
+        client: &RegionClient,
+        key: Key,
+        consistent_read: bool,
+    ) -> Result<Option<T>, Error> {
+        Ok(client
+            .main
+            .dynamodb
+            .get_item()
+            .table_name(self.name.as_str())
+            .set_key(Some(key.into_inner()))
+            .consistent_read(consistent_read)
+            .send()
+            .await?
+            .item
+            .map(T::from_item)
+            .transpose()?)
+    }
+
+    pub async fn put_item(&self, client: &RegionClient, item: impl Item) -> Result<(), Error> {
+        let _output = client
+            .main
+            .dynamodb
+            .put_item()
+            .table_name(self.name.as_str())
+            .set_item(Some(item.into_item()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Applies the update and returns the item as it is after the update
+    pub async fn update_item<T: Item>(
+        &self,
+        client: &RegionClient,
+        key: Key,
+        update: UpdateExpression,
+    ) -> Result<T, Error> {
+        let expression = update.render();
+        let (names, values) = update.attributes.into_parts();
+
+        Ok(T::from_item(
+            client
+                .main
+                .dynamodb
+                .update_item()
+                .table_name(self.name.as_str())
+                .set_key(Some(key.into_inner()))
+                .update_expression(expression)
+                .set_expression_attribute_names(names)
+                .set_expression_attribute_values(values)
+                .return_values(aws_sdk_dynamodb::types::ReturnValue::AllNew)
+                .send()
+                .await?
+                .attributes
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "UpdateItemOutput.attributes".to_owned(),
+                })?,
+        )?)
+    }
+
+    /// Returns all items matching the key condition, following pagination.
+    /// Set `index` to query a secondary index instead of the table.
+    pub async fn query<T: Item>(
+        &self,
+        client: &RegionClient,
+        condition: KeyCondition,
+        index: Option<&str>,
+    ) -> Result<Vec<T>, Error> {
+        let mut attributes = ExpressionAttributes::default();
+        let expression = condition.render(&mut attributes);
+        let (names, values) = attributes.into_parts();
+
+        client
+            .main
+            .dynamodb
+            .query()
+            .table_name(self.name.as_str())
+            .set_index_name(index.map(ToOwned::to_owned))
+            .key_condition_expression(expression)
+            .set_expression_attribute_names(names)
+            .set_expression_attribute_values(values)
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(|item| Ok(T::from_item(item)?))
+            .collect()
+    }
+
+    /// Returns all items of the table, following pagination
+    pub async fn scan<T: Item>(&self, client: &RegionClient) -> Result<Vec<T>, Error> {
+        client
+            .main
+            .dynamodb
+            .scan()
+            .table_name(self.name.as_str())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(|item| Ok(T::from_item(item)?))
+            .collect()
+    }
+
+    /// Writes all requests, split into as many batches as necessary.
+    /// Unprocessed items are resubmitted a limited number of times, with
+    /// exponential backoff. If they are still unprocessed after that, the
+    /// requests of later batches are not sent.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn batch_write(
+        &self,
+        client: &RegionClient,
+        requests: Vec<WriteRequest>,
+    ) -> Result<(), Error> {
+        let mut requests = requests.into_iter().peekable();
+        while requests.peek().is_some() {
+            let mut batch: Vec<aws_sdk_dynamodb::types::WriteRequest> = requests
+                .by_ref()
+                .take(MAX_BATCH_WRITE_SIZE)
+                .map(Into::into)
+                .collect();
+
+            let mut attempt = 1;
+            let mut delay = BATCH_WRITE_BACKOFF.initial;
+
+            loop {
+                batch = client
+                    .main
+                    .dynamodb
+                    .batch_write_item()
+                    .request_items(self.name.as_str(), batch)
+                    .send()
+                    .await?
+                    .unprocessed_items
+                    .and_then(|mut unprocessed| unprocessed.remove(self.name.as_str()))
+                    .unwrap_or_default();
+
+                if batch.is_empty() {
+                    break;
+                }
+
+                if attempt >= MAX_BATCH_WRITE_ATTEMPTS {
+                    return Err(Error::UnprocessedItems {
+                        count: batch.len(),
+                        unsent: requests.len(),
+                    });
+                }
+
+                tokio::time::sleep(delay).await;
+                delay = BATCH_WRITE_BACKOFF.next(delay);
+                attempt = attempt.saturating_add(1);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_round_trip() {
+        #[derive(DynamoItem, Debug, PartialEq)]
+        struct MyItem {
+            id: String,
+            #[dynamo(rename = "count")]
+            counter: u64,
+            enabled: Option<bool>,
+            labels: Vec<String>,
+        }
+
+        let item = MyItem {
+            id: "foo".to_owned(),
+            counter: 3,
+            enabled: None,
+            labels: vec!["a".to_owned(), "b".to_owned()],
+        };
+
+        let raw = item.into_item();
+        assert_eq!(raw.get("count"), Some(&AttributeValue::N("3".to_owned())));
+        assert!(!raw.contains_key("enabled"));
+
+        assert_eq!(
+            MyItem::from_item(raw).unwrap(),
+            MyItem {
+                id: "foo".to_owned(),
+                counter: 3,
+                enabled: None,
+                labels: vec!["a".to_owned(), "b".to_owned()],
+            }
+        );
+
+        assert!(matches!(
+            MyItem::from_item(HashMap::new()),
+            Err(ParseItemError::MissingAttribute { .. })
+        ));
+    }
+
+    #[test]
+    fn null_is_none() {
+        #[derive(DynamoItem, Debug, PartialEq)]
+        struct MyItem {
+            id: String,
+            email: Option<String>,
+        }
+
+        let raw = HashMap::from([
+            ("id".to_owned(), AttributeValue::S("foo".to_owned())),
+            ("email".to_owned(), AttributeValue::Null(true)),
+        ]);
+
+        assert_eq!(
+            MyItem::from_item(raw).unwrap(),
+            MyItem {
+                id: "foo".to_owned(),
+                email: None,
+            }
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    mod batch_write {
+        use super::*;
+        use crate::{
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        const UNPROCESSED: &str =
+            r#"{"UnprocessedItems": {"users": [{"PutRequest": {"Item": {"id": {"S": "b"}}}}]}}"#;
+
+        fn table() -> Table {
+            Table::new(TableName::new("users".to_owned()))
+        }
+
+        fn requests() -> Vec<WriteRequest> {
+            ["a", "b"]
+                .into_iter()
+                .map(|id| {
+                    WriteRequest::Put(HashMap::from([(
+                        "id".to_owned(),
+                        AttributeValue::S(id.to_owned()),
+                    )]))
+                })
+                .collect()
+        }
+
+        #[test]
+        fn unprocessed_items_are_retried() {
+            let http = MockHttpClient::new()
+                .once(
+                    Matcher::action("BatchWriteItem"),
+                    MockResponse::ok(UNPROCESSED),
+                )
+                .on(
+                    Matcher::action("BatchWriteItem"),
+                    MockResponse::ok(r#"{"UnprocessedItems": {}}"#),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            block_on(table().batch_write(&client, requests())).unwrap();
+
+            let sizes = http
+                .requests()
+                .unwrap()
+                .into_iter()
+                .map(|request| {
+                    serde_json::from_str::<serde_json::Value>(&request.body)
+                        .unwrap()
+                        .pointer("/RequestItems/users")
+                        .and_then(serde_json::Value::as_array)
+                        .map(Vec::len)
+                })
+                .collect::<Vec<Option<usize>>>();
+            assert_eq!(
+                sizes,
+                vec![Some(2), Some(1)],
+                "only the unprocessed item is resent"
+            );
+        }
+
+        #[test]
+        fn unprocessed_items_give_up() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("BatchWriteItem"),
+                MockResponse::ok(UNPROCESSED),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let error = block_on(table().batch_write(&client, requests())).unwrap_err();
+
+            assert!(
+                matches!(
+                    error,
+                    Error::UnprocessedItems {
+                        count: 1,
+                        unsent: 0
+                    }
+                ),
+                "unexpected error {error:?}"
+            );
+            assert_eq!(http.requests().unwrap().len(), MAX_BATCH_WRITE_ATTEMPTS);
+        }
+    }
+
+    #[test]
+    fn render_update_expression() {
+        let update = UpdateExpression::new()
+            .set("email", "foo@example.com".to_owned())
+            .add("logins", 1_u64)
+            .remove("deactivated_at");
+
+        assert_eq!(update.render(), "SET #n0 = :v0 REMOVE #n2 ADD #n1 :v1");
+        assert_eq!(update.attributes.names.len(), 3);
+        assert_eq!(update.attributes.values.len(), 2);
+    }
+}
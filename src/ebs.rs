@@ -0,0 +1,377 @@
+//! EBS volumes and snapshots
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{ParseTagsError, TagList, Tags},
+    AvailabilityZone, Error, InstanceId, RegionClient, Timestamp,
+};
+
+string_newtype!(VolumeId);
+
+impl VolumeId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(SnapshotId);
+
+impl SnapshotId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+#[expect(
+    clippy::struct_field_names,
+    reason = "the fields are named like in the EC2 API"
+)]
+pub struct Volume {
+    id: VolumeId,
+    size_gib: i32,
+    availability_zone: AvailabilityZone,
+    state: Option<String>,
+    volume_type: Option<String>,
+    attached_instances: Vec<InstanceId>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_ec2::types::Volume> for Volume {
+    type Error = Error;
+
+    fn try_from(volume: aws_sdk_ec2::types::Volume) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                volume.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            id: VolumeId(extract!(volume_id)?),
+            size_gib: extract!(size)?,
+            availability_zone: AvailabilityZone(extract!(availability_zone)?),
+            state: volume.state.map(|state| state.as_str().to_owned()),
+            volume_type: volume
+                .volume_type
+                .map(|volume_type| volume_type.as_str().to_owned()),
+            attached_instances: volume
+                .attachments
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|attachment| attachment.instance_id.map(InstanceId))
+                .collect(),
+            tags: volume.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl Volume {
+    /// Returns all volumes, optionally restricted to volumes that have all
+    /// of the given tags
+    pub async fn list(client: &RegionClient, tags: Option<TagList>) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_volumes()
+            .set_filters(tags.map(Into::into))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub const fn id(&self) -> &VolumeId {
+        &self.id
+    }
+
+    pub const fn size_gib(&self) -> i32 {
+        self.size_gib
+    }
+
+    pub const fn availability_zone(&self) -> &AvailabilityZone {
+        &self.availability_zone
+    }
+
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    pub fn volume_type(&self) -> Option<&str> {
+        self.volume_type.as_deref()
+    }
+
+    pub fn attached_instances(&self) -> &[InstanceId] {
+        &self.attached_instances
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    /// Starts a snapshot of the volume. The snapshot will be in `pending`
+    /// state until it completes.
+    pub async fn create_snapshot(
+        &self,
+        client: &RegionClient,
+        description: &str,
+        tags: TagList,
+    ) -> Result<SnapshotId, Error> {
+        Ok(SnapshotId(
+            client
+                .main
+                .ec2
+                .create_snapshot()
+                .volume_id(self.id.as_str())
+                .description(description)
+                .tag_specifications(
+                    aws_sdk_ec2::types::TagSpecification::builder()
+                        .resource_type(aws_sdk_ec2::types::ResourceType::Snapshot)
+                        .set_tags(Some(tags.into()))
+                        .build(),
+                )
+                .send()
+                .await?
+                .snapshot_id
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "CreateSnapshotOutput.snapshot_id".to_owned(),
+                })?,
+        ))
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    id: SnapshotId,
+    volume_id: Option<VolumeId>,
+    start_time: Timestamp,
+    state: Option<String>,
+    description: Option<String>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_ec2::types::Snapshot> for Snapshot {
+    type Error = Error;
+
+    fn try_from(snapshot: aws_sdk_ec2::types::Snapshot) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                snapshot.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            id: SnapshotId(extract!(snapshot_id)?),
+            volume_id: snapshot.volume_id.map(VolumeId),
+            start_time: extract!(start_time)?.try_into()?,
+            state: snapshot.state.map(|state| state.as_str().to_owned()),
+            description: snapshot.description,
+            tags: snapshot.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl Snapshot {
+    /// Returns all snapshots owned by the account, optionally restricted to
+    /// snapshots that have all of the given tags
+    pub async fn list(client: &RegionClient, tags: Option<TagList>) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_snapshots()
+            .owner_ids("self")
+            .set_filters(tags.map(Into::into))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub const fn id(&self) -> &SnapshotId {
+        &self.id
+    }
+
+    pub const fn volume_id(&self) -> Option<&VolumeId> {
+        self.volume_id.as_ref()
+    }
+
+    pub const fn start_time(&self) -> &Timestamp {
+        &self.start_time
+    }
+
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    pub async fn delete(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .delete_snapshot()
+            .snapshot_id(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Copies the snapshot into the region of `target_client`. `source_client`
+    /// has to be the client of the region the snapshot lives in.
+    pub async fn copy(
+        &self,
+        source_client: &RegionClient,
+        target_client: &RegionClient,
+        description: &str,
+        tags: TagList,
+    ) -> Result<SnapshotId, Error> {
+        Ok(SnapshotId(
+            target_client
+                .main
+                .ec2
+                .copy_snapshot()
+                .source_region(source_client.region.as_str())
+                .source_snapshot_id(self.id.as_str())
+                .description(description)
+                .tag_specifications(
+                    aws_sdk_ec2::types::TagSpecification::builder()
+                        .resource_type(aws_sdk_ec2::types::ResourceType::Snapshot)
+                        .set_tags(Some(tags.into()))
+                        .build(),
+                )
+                .send()
+                .await?
+                .snapshot_id
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "CopySnapshotOutput.snapshot_id".to_owned(),
+                })?,
+        ))
+    }
+}
+
+/// Tags that control how long a snapshot is kept, see [`expired_snapshots()`]
+#[Tags]
+pub struct RetentionTags {
+    #[tag(key = "retain-until")]
+    pub retain_until: Option<Timestamp>,
+}
+
+/// Returns the snapshots whose `retain-until` tag lies before `now`.
+///
+/// Snapshots without a `retain-until` tag are never selected. Fails if any
+/// snapshot has a malformed `retain-until` tag, so that a typo does not lead
+/// to snapshots being kept (or deleted) silently.
+pub fn expired_snapshots(
+    snapshots: &[Snapshot],
+    now: Timestamp,
+) -> Result<Vec<&Snapshot>, ParseTagsError> {
+    let mut expired = Vec::new();
+
+    for snapshot in snapshots {
+        let retention = RetentionTags::from_tags(snapshot.tags.clone())?;
+        if retention
+            .retain_until
+            .is_some_and(|retain_until| retain_until < now)
+        {
+            expired.push(snapshot);
+        }
+    }
+
+    Ok(expired)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::tags::RawTag;
+
+    fn snapshot(id: &str, tags: TagList) -> Snapshot {
+        Snapshot {
+            id: SnapshotId(id.to_owned()),
+            volume_id: None,
+            start_time: Timestamp::new(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+            state: None,
+            description: None,
+            tags,
+        }
+    }
+
+    #[test]
+    fn select_expired_snapshots() {
+        let snapshots = vec![
+            snapshot(
+                "snap-expired",
+                TagList::from_vec(vec![RawTag::new(
+                    "retain-until".to_owned(),
+                    "2024-06-01T00:00:00".to_owned(),
+                )]),
+            ),
+            snapshot(
+                "snap-retained",
+                TagList::from_vec(vec![RawTag::new(
+                    "retain-until".to_owned(),
+                    "2025-06-01T00:00:00".to_owned(),
+                )]),
+            ),
+            snapshot("snap-untagged", TagList::new()),
+        ];
+
+        let now = Timestamp::new(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+
+        let expired = expired_snapshots(&snapshots, now).unwrap();
+        assert_eq!(
+            expired
+                .iter()
+                .map(|snapshot| snapshot.id().as_str())
+                .collect::<Vec<&str>>(),
+            vec!["snap-expired"]
+        );
+
+        let malformed = vec![snapshot(
+            "snap-malformed",
+            TagList::from_vec(vec![RawTag::new(
+                "retain-until".to_owned(),
+                "tomorrow".to_owned(),
+            )]),
+        )];
+        assert!(
+            expired_snapshots(&malformed, now).is_err(),
+            "invalid retention date"
+        );
+    }
+}
@@ -0,0 +1,531 @@
+//! ECR repositories, images and registry authentication
+
+use std::{cmp::Reverse, fmt, time::Duration};
+
+use aws_sdk_ecr::operation::describe_repositories::DescribeRepositoriesError;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{TagKey, TagList},
+    Error, RegionClient, Timestamp,
+};
+
+/// `BatchDeleteImage` accepts at most 100 image IDs per call
+const MAX_DELETE_BATCH_SIZE: usize = 100;
+
+string_newtype!(RepositoryName);
+
+impl RepositoryName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(ImageDigest);
+
+impl ImageDigest {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Credentials for `docker login`, decoded from an ECR authorization token
+#[derive(Clone)]
+pub struct RegistryCredentials {
+    pub username: String,
+    pub password: String,
+    /// The registry URL, e.g. `https://<account>.dkr.ecr.<region>.amazonaws.com`
+    pub endpoint: String,
+    pub expires_at: Option<Timestamp>,
+}
+
+// Do not leak the password into logs
+impl fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegistryCredentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field("endpoint", &self.endpoint)
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// The token is a base64 encoded `<username>:<password>` pair
+fn decode_authorization_token(token: &str) -> Result<(String, String), Error> {
+    let invalid = |message: String| Error::InvalidResponseError {
+        message: format!("invalid ecr authorization token: {message}"),
+    };
+
+    let decoded = String::from_utf8(BASE64.decode(token).map_err(|e| invalid(e.to_string()))?)
+        .map_err(|e| invalid(e.to_string()))?;
+
+    let (username, password) = decoded
+        .split_once(':')
+        .ok_or_else(|| invalid("missing separator".to_owned()))?;
+
+    Ok((username.to_owned(), password.to_owned()))
+}
+
+pub async fn get_registry_credentials(client: &RegionClient) -> Result<RegistryCredentials, Error> {
+    let data = client
+        .main
+        .ecr
+        .get_authorization_token()
+        .send()
+        .await?
+        .authorization_data
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::UnexpectedNoneValue {
+            entity: "GetAuthorizationTokenOutput.authorization_data".to_owned(),
+        })?;
+
+    let (username, password) =
+        decode_authorization_token(data.authorization_token.as_deref().ok_or_else(|| {
+            Error::UnexpectedNoneValue {
+                entity: "AuthorizationData.authorization_token".to_owned(),
+            }
+        })?)?;
+
+    Ok(RegistryCredentials {
+        username,
+        password,
+        endpoint: data
+            .proxy_endpoint
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "AuthorizationData.proxy_endpoint".to_owned(),
+            })?,
+        expires_at: data.expires_at.map(TryInto::try_into).transpose()?,
+    })
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Repository {
+    name: RepositoryName,
+    arn: String,
+    uri: Option<String>,
+    created_at: Option<Timestamp>,
+}
+
+impl TryFrom<aws_sdk_ecr::types::Repository> for Repository {
+    type Error = Error;
+
+    fn try_from(repository: aws_sdk_ecr::types::Repository) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                repository.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            name: RepositoryName(extract!(repository_name)?),
+            arn: extract!(repository_arn)?,
+            uri: repository.repository_uri,
+            created_at: repository.created_at.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl Repository {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ecr
+            .describe_repositories()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_name(
+        client: &RegionClient,
+        name: &RepositoryName,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .ecr
+            .describe_repositories()
+            .repository_names(name.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => {
+                let mut repositories = output.repositories.unwrap_or_default();
+                match repositories.pop() {
+                    None => Ok(None),
+                    Some(repository) => {
+                        if repositories.is_empty() {
+                            Ok(Some(repository.try_into()?))
+                        } else {
+                            Err(Error::MultipleMatches {
+                                entity: format!("repository {name}"),
+                            })
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                if e.as_service_error()
+                    .is_some_and(DescribeRepositoriesError::is_repository_not_found_exception)
+                {
+                    Ok(None)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
+    pub const fn name(&self) -> &RepositoryName {
+        &self.name
+    }
+
+    pub fn arn(&self) -> &str {
+        &self.arn
+    }
+
+    pub fn uri(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
+
+    pub const fn created_at(&self) -> Option<&Timestamp> {
+        self.created_at.as_ref()
+    }
+
+    /// Lists the digests and tags of all images, without further details.
+    /// Each tag of an image is returned as a separate entry.
+    pub async fn list_images(&self, client: &RegionClient) -> Result<Vec<ImageId>, Error> {
+        Ok(client
+            .main
+            .ecr
+            .list_images()
+            .repository_name(self.name.as_str())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    pub async fn describe_images(&self, client: &RegionClient) -> Result<Vec<Image>, Error> {
+        client
+            .main
+            .ecr
+            .describe_images()
+            .repository_name(self.name.as_str())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Deletes the given images, returning the IDs that could not be deleted
+    /// together with the reason
+    pub async fn delete_images(
+        &self,
+        client: &RegionClient,
+        images: Vec<ImageId>,
+    ) -> Result<Vec<(ImageId, String)>, Error> {
+        let mut failures = Vec::new();
+
+        for chunk in images.chunks(MAX_DELETE_BATCH_SIZE) {
+            failures.extend(
+                client
+                    .main
+                    .ecr
+                    .batch_delete_image()
+                    .repository_name(self.name.as_str())
+                    .set_image_ids(Some(chunk.iter().cloned().map(Into::into).collect()))
+                    .send()
+                    .await?
+                    .failures
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|failure| {
+                        failure.image_id.map(|id| {
+                            (
+                                id.into(),
+                                failure.failure_reason.unwrap_or_else(|| {
+                                    failure
+                                        .failure_code
+                                        .map(|code| code.as_str().to_owned())
+                                        .unwrap_or_default()
+                                }),
+                            )
+                        })
+                    }),
+            );
+        }
+
+        Ok(failures)
+    }
+
+    /// Deletes all images selected by [`expired_images()`], returning the
+    /// images that could not be deleted
+    pub async fn expire_images(
+        &self,
+        client: &RegionClient,
+        policy: &RetentionPolicy,
+    ) -> Result<Vec<(ImageId, String)>, Error> {
+        let images = self.describe_images(client).await?;
+
+        let expired = expired_images(&images, policy, Timestamp::now())
+            .into_iter()
+            .map(|image| ImageId {
+                digest: Some(image.digest.clone()),
+                tag: None,
+            })
+            .collect::<Vec<ImageId>>();
+
+        if expired.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.delete_images(client, expired).await
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .main
+            .ecr
+            .list_tags_for_resource()
+            .resource_arn(&self.arn)
+            .send()
+            .await?
+            .tags
+            .unwrap_or_default()
+            .try_into()?)
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ecr
+            .tag_resource()
+            .resource_arn(&self.arn)
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ecr
+            .untag_resource()
+            .resource_arn(&self.arn)
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Identifies an image by digest, tag or both
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageId {
+    pub digest: Option<ImageDigest>,
+    pub tag: Option<String>,
+}
+
+impl From<aws_sdk_ecr::types::ImageIdentifier> for ImageId {
+    fn from(id: aws_sdk_ecr::types::ImageIdentifier) -> Self {
+        Self {
+            digest: id.image_digest.map(ImageDigest),
+            tag: id.image_tag,
+        }
+    }
+}
+
+impl From<ImageId> for aws_sdk_ecr::types::ImageIdentifier {
+    fn from(id: ImageId) -> Self {
+        Self::builder()
+            .set_image_digest(id.digest.map(|digest| digest.0))
+            .set_image_tag(id.tag)
+            .build()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Image {
+    digest: ImageDigest,
+    tags: Vec<String>,
+    pushed_at: Option<Timestamp>,
+    size_in_bytes: Option<i64>,
+}
+
+impl TryFrom<aws_sdk_ecr::types::ImageDetail> for Image {
+    type Error = Error;
+
+    fn try_from(image: aws_sdk_ecr::types::ImageDetail) -> Result<Self, Self::Error> {
+        Ok(Self {
+            digest: ImageDigest(
+                image
+                    .image_digest
+                    .ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: "ImageDetail.image_digest".to_owned(),
+                    })?,
+            ),
+            tags: image.image_tags.unwrap_or_default(),
+            pushed_at: image.image_pushed_at.map(TryInto::try_into).transpose()?,
+            size_in_bytes: image.image_size_in_bytes,
+        })
+    }
+}
+
+impl Image {
+    pub const fn digest(&self) -> &ImageDigest {
+        &self.digest
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub const fn pushed_at(&self) -> Option<&Timestamp> {
+        self.pushed_at.as_ref()
+    }
+
+    pub const fn size_in_bytes(&self) -> Option<i64> {
+        self.size_in_bytes
+    }
+}
+
+/// Which images to expire. An image is expired if it violates any of the
+/// given limits.
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    /// Expire images that were pushed longer ago than this
+    pub max_age: Option<Duration>,
+    /// Keep only this many of the most recently pushed images
+    pub max_count: Option<usize>,
+}
+
+/// Returns the images that are expired according to `policy`, newest first.
+///
+/// Images without a push timestamp are never selected.
+pub fn expired_images<'a>(
+    images: &'a [Image],
+    policy: &RetentionPolicy,
+    now: Timestamp,
+) -> Vec<&'a Image> {
+    let mut images = images
+        .iter()
+        .filter(|image| image.pushed_at.is_some())
+        .collect::<Vec<&Image>>();
+
+    images.sort_by_key(|image| Reverse(image.pushed_at));
+
+    let cutoff = policy.max_age.and_then(|max_age| {
+        chrono::TimeDelta::from_std(max_age)
+            .ok()
+            .and_then(|max_age| now.inner().checked_sub_signed(max_age))
+            .map(Timestamp::new)
+    });
+
+    images
+        .into_iter()
+        .enumerate()
+        .filter(|&(position, image)| {
+            policy
+                .max_count
+                .is_some_and(|max_count| position >= max_count)
+                || cutoff.is_some_and(|cutoff| {
+                    image.pushed_at.is_some_and(|pushed_at| pushed_at < cutoff)
+                })
+        })
+        .map(|(_position, image)| image)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn image(digest: &str, day: u32) -> Image {
+        Image {
+            digest: ImageDigest(digest.to_owned()),
+            tags: Vec::new(),
+            pushed_at: Some(Timestamp::new(
+                Utc.with_ymd_and_hms(2025, 1, day, 0, 0, 0).unwrap(),
+            )),
+            size_in_bytes: None,
+        }
+    }
+
+    fn digests(images: &[&Image]) -> Vec<String> {
+        images
+            .iter()
+            .map(|image| image.digest().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn select_expired_images() {
+        let images = vec![image("old", 1), image("newest", 20), image("middle", 10)];
+        let now = Timestamp::new(Utc.with_ymd_and_hms(2025, 1, 21, 0, 0, 0).unwrap());
+
+        let by_count = RetentionPolicy {
+            max_age: None,
+            max_count: Some(1),
+        };
+        assert_eq!(
+            digests(&expired_images(&images, &by_count, now)),
+            vec!["middle", "old"]
+        );
+
+        let by_age = RetentionPolicy {
+            max_age: Some(Duration::from_secs(15 * 24 * 60 * 60)),
+            max_count: None,
+        };
+        assert_eq!(digests(&expired_images(&images, &by_age, now)), vec!["old"]);
+
+        assert!(expired_images(&images, &RetentionPolicy::default(), now).is_empty());
+    }
+
+    #[test]
+    fn decode_token() {
+        assert_eq!(
+            decode_authorization_token("QVdTOnNlY3JldA==").unwrap(),
+            ("AWS".to_owned(), "secret".to_owned())
+        );
+        assert!(
+            decode_authorization_token("not base64!").is_err(),
+            "invalid base64"
+        );
+    }
+}
@@ -0,0 +1,925 @@
+//! Managing ECS services, tasks and task definitions
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use aws_sdk_ecs::types::{Compatibility, ContainerDefinition, LaunchType, NetworkMode};
+
+use super::{
+    arn::Arn,
+    tags::{TagKey, TagList},
+    Error, RegionClient, SecurityGroupId, SubnetId,
+};
+
+/// `DescribeServices` and `DescribeTasks` accept at most this many entries
+const MAX_DESCRIBE_SIZE: usize = 10;
+
+arn_newtype!(ClusterArn);
+
+arn_newtype!(ServiceArn);
+
+arn_newtype!(TaskArn);
+
+arn_newtype!(TaskDefinitionArn);
+
+fn failures_to_error(failures: Vec<aws_sdk_ecs::types::Failure>) -> Result<(), Error> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    Err(Error::EcsFailures {
+        reasons: failures
+            .into_iter()
+            .map(|failure| {
+                format!(
+                    "{}: {}",
+                    failure.arn.unwrap_or_default(),
+                    failure.reason.unwrap_or_default()
+                )
+            })
+            .collect(),
+    })
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    arn: ClusterArn,
+    name: String,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_ecs::types::Cluster> for Cluster {
+    type Error = Error;
+
+    fn try_from(cluster: aws_sdk_ecs::types::Cluster) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                cluster.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            arn: ClusterArn::parse(&extract!(cluster_arn)?)?,
+            name: extract!(cluster_name)?,
+            tags: cluster.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl Cluster {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        let arns: Vec<String> = client
+            .main
+            .ecs
+            .list_clusters()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?;
+
+        if arns.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let output = client
+            .main
+            .ecs
+            .describe_clusters()
+            .set_clusters(Some(arns))
+            .include(aws_sdk_ecs::types::ClusterField::Tags)
+            .send()
+            .await?;
+
+        failures_to_error(output.failures.unwrap_or_default())?;
+
+        output
+            .clusters
+            .unwrap_or_default()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub const fn arn(&self) -> &ClusterArn {
+        &self.arn
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    pub async fn services(&self, client: &RegionClient) -> Result<Vec<Service>, Error> {
+        let arns: Vec<String> = client
+            .main
+            .ecs
+            .list_services()
+            .cluster(self.arn.to_string())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?;
+
+        let mut services = Vec::with_capacity(arns.len());
+
+        for chunk in arns.chunks(MAX_DESCRIBE_SIZE) {
+            let output = client
+                .main
+                .ecs
+                .describe_services()
+                .cluster(self.arn.to_string())
+                .set_services(Some(chunk.to_vec()))
+                .include(aws_sdk_ecs::types::ServiceField::Tags)
+                .send()
+                .await?;
+
+            failures_to_error(output.failures.unwrap_or_default())?;
+
+            for service in output.services.unwrap_or_default() {
+                services.push(Service::try_from_aws(service, self.arn.clone())?);
+            }
+        }
+
+        Ok(services)
+    }
+
+    /// Returns all tasks of the cluster, optionally restricted to a single
+    /// service
+    pub async fn tasks(
+        &self,
+        client: &RegionClient,
+        service: Option<&Service>,
+    ) -> Result<Vec<Task>, Error> {
+        let arns: Vec<String> = client
+            .main
+            .ecs
+            .list_tasks()
+            .cluster(self.arn.to_string())
+            .set_service_name(service.map(|service| service.name.clone()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?;
+
+        let mut tasks = Vec::with_capacity(arns.len());
+
+        for chunk in arns.chunks(MAX_DESCRIBE_SIZE) {
+            let output = client
+                .main
+                .ecs
+                .describe_tasks()
+                .cluster(self.arn.to_string())
+                .set_tasks(Some(chunk.to_vec()))
+                .include(aws_sdk_ecs::types::TaskField::Tags)
+                .send()
+                .await?;
+
+            failures_to_error(output.failures.unwrap_or_default())?;
+
+            for task in output.tasks.unwrap_or_default() {
+                tasks.push(task.try_into()?);
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "only expect() on builder instances"
+    )]
+    pub async fn run_task(
+        &self,
+        client: &RegionClient,
+        config: RunTaskConfig<'_>,
+    ) -> Result<Vec<Task>, Error> {
+        let output = client
+            .main
+            .ecs
+            .run_task()
+            .cluster(self.arn.to_string())
+            .task_definition(config.task_definition.to_string())
+            .count(config.count)
+            .set_launch_type(config.launch_type)
+            .set_network_configuration(config.subnets.map(|subnets| {
+                aws_sdk_ecs::types::NetworkConfiguration::builder()
+                    .awsvpc_configuration(
+                        aws_sdk_ecs::types::AwsVpcConfiguration::builder()
+                            .set_subnets(Some(
+                                subnets.iter().map(|id| id.as_str().to_owned()).collect(),
+                            ))
+                            .set_security_groups(Some(
+                                config
+                                    .security_groups
+                                    .iter()
+                                    .map(|id| id.as_str().to_owned())
+                                    .collect(),
+                            ))
+                            .build()
+                            .expect("builder has missing fields"),
+                    )
+                    .build()
+            }))
+            .set_tags(Some(config.tags.clone().into()))
+            .send()
+            .await?;
+
+        failures_to_error(output.failures.unwrap_or_default())?;
+
+        output
+            .tasks
+            .unwrap_or_default()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+}
+
+pub struct RunTaskConfig<'a> {
+    pub task_definition: &'a TaskDefinitionArn,
+    pub count: i32,
+    pub launch_type: Option<LaunchType>,
+    /// Required for tasks using the `awsvpc` network mode
+    pub subnets: Option<&'a [SubnetId]>,
+    pub security_groups: &'a [SecurityGroupId],
+    pub tags: &'a TagList,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Service {
+    arn: ServiceArn,
+    name: String,
+    cluster: ClusterArn,
+    status: Option<String>,
+    desired_count: i32,
+    running_count: i32,
+    pending_count: i32,
+    task_definition: Option<TaskDefinitionArn>,
+    tags: TagList,
+}
+
+/// Changes to apply with [`Service::update()`]. Fields that are `None` are
+/// left as they are.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceUpdate {
+    pub desired_count: Option<i32>,
+    pub task_definition: Option<TaskDefinitionArn>,
+    /// Starts a new deployment even if the task definition did not change,
+    /// e.g. to pull a new image for the same tag
+    pub force_new_deployment: bool,
+}
+
+impl Service {
+    fn try_from_aws(
+        service: aws_sdk_ecs::types::Service,
+        cluster: ClusterArn,
+    ) -> Result<Self, Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                service.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            arn: ServiceArn::parse(&extract!(service_arn)?)?,
+            name: extract!(service_name)?,
+            cluster,
+            status: service.status,
+            desired_count: service.desired_count,
+            running_count: service.running_count,
+            pending_count: service.pending_count,
+            task_definition: service
+                .task_definition
+                .map(|arn| TaskDefinitionArn::parse(&arn))
+                .transpose()?,
+            tags: service.tags.unwrap_or_default().try_into()?,
+        })
+    }
+
+    pub const fn arn(&self) -> &ServiceArn {
+        &self.arn
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub const fn cluster(&self) -> &ClusterArn {
+        &self.cluster
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    pub const fn desired_count(&self) -> i32 {
+        self.desired_count
+    }
+
+    pub const fn running_count(&self) -> i32 {
+        self.running_count
+    }
+
+    pub const fn pending_count(&self) -> i32 {
+        self.pending_count
+    }
+
+    pub const fn task_definition(&self) -> Option<&TaskDefinitionArn> {
+        self.task_definition.as_ref()
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    /// Applies the update and returns the updated service
+    pub async fn update(
+        &self,
+        client: &RegionClient,
+        update: ServiceUpdate,
+    ) -> Result<Self, Error> {
+        Self::try_from_aws(
+            client
+                .main
+                .ecs
+                .update_service()
+                .cluster(self.cluster.to_string())
+                .service(self.arn.to_string())
+                .set_desired_count(update.desired_count)
+                .set_task_definition(update.task_definition.map(|arn| arn.to_string()))
+                .force_new_deployment(update.force_new_deployment)
+                .send()
+                .await?
+                .service
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "UpdateServiceOutput.service".to_owned(),
+                })?,
+            self.cluster.clone(),
+        )
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.arn.inner(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.arn.inner(), keys).await
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+#[expect(
+    clippy::struct_field_names,
+    reason = "the fields are named like in the ECS API"
+)]
+pub struct Task {
+    arn: TaskArn,
+    task_definition: TaskDefinitionArn,
+    last_status: Option<String>,
+    desired_status: Option<String>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_ecs::types::Task> for Task {
+    type Error = Error;
+
+    fn try_from(task: aws_sdk_ecs::types::Task) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                task.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            arn: TaskArn::parse(&extract!(task_arn)?)?,
+            task_definition: TaskDefinitionArn::parse(&extract!(task_definition_arn)?)?,
+            last_status: task.last_status,
+            desired_status: task.desired_status,
+            tags: task.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl Task {
+    pub const fn arn(&self) -> &TaskArn {
+        &self.arn
+    }
+
+    pub const fn task_definition(&self) -> &TaskDefinitionArn {
+        &self.task_definition
+    }
+
+    pub fn last_status(&self) -> Option<&str> {
+        self.last_status.as_deref()
+    }
+
+    pub fn desired_status(&self) -> Option<&str> {
+        self.desired_status.as_deref()
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskDefinitionConfig {
+    pub family: String,
+    pub container_definitions: Vec<ContainerDefinition>,
+    pub cpu: Option<String>,
+    pub memory: Option<String>,
+    pub network_mode: Option<NetworkMode>,
+    pub execution_role_arn: Option<String>,
+    pub task_role_arn: Option<String>,
+    pub requires_compatibilities: Vec<Compatibility>,
+    pub tags: TagList,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct TaskDefinition {
+    arn: TaskDefinitionArn,
+    family: String,
+    revision: i32,
+}
+
+impl TaskDefinition {
+    /// Registers a new revision of the task definition family
+    pub async fn register(
+        client: &RegionClient,
+        config: TaskDefinitionConfig,
+    ) -> Result<Self, Error> {
+        let task_definition = client
+            .main
+            .ecs
+            .register_task_definition()
+            .family(config.family)
+            .set_container_definitions(Some(config.container_definitions))
+            .set_cpu(config.cpu)
+            .set_memory(config.memory)
+            .set_network_mode(config.network_mode)
+            .set_execution_role_arn(config.execution_role_arn)
+            .set_task_role_arn(config.task_role_arn)
+            .set_requires_compatibilities(Some(config.requires_compatibilities))
+            .set_tags(Some(config.tags.into()))
+            .send()
+            .await?
+            .task_definition
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "RegisterTaskDefinitionOutput.task_definition".to_owned(),
+            })?;
+
+        macro_rules! extract {
+            ($field:ident) => {
+                task_definition
+                    .$field
+                    .ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: stringify!($field).to_owned(),
+                    })
+            };
+        }
+
+        Ok(Self {
+            arn: TaskDefinitionArn::parse(&extract!(task_definition_arn)?)?,
+            family: extract!(family)?,
+            revision: task_definition.revision,
+        })
+    }
+
+    pub const fn arn(&self) -> &TaskDefinitionArn {
+        &self.arn
+    }
+
+    pub fn family(&self) -> &str {
+        &self.family
+    }
+
+    pub const fn revision(&self) -> i32 {
+        self.revision
+    }
+}
+
+/// Returns the tags of any ECS resource
+pub async fn tags(client: &RegionClient, arn: &Arn) -> Result<TagList, Error> {
+    Ok(client
+        .main
+        .ecs
+        .list_tags_for_resource()
+        .resource_arn(arn.to_string())
+        .send()
+        .await?
+        .tags
+        .unwrap_or_default()
+        .try_into()?)
+}
+
+pub async fn add_tags(client: &RegionClient, arn: &Arn, tags: TagList) -> Result<(), Error> {
+    let _output = client
+        .main
+        .ecs
+        .tag_resource()
+        .resource_arn(arn.to_string())
+        .set_tags(Some(tags.into()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+pub async fn remove_tags(client: &RegionClient, arn: &Arn, keys: Vec<TagKey>) -> Result<(), Error> {
+    let _output = client
+        .main
+        .ecs
+        .untag_resource()
+        .resource_arn(arn.to_string())
+        .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ecs::types::{Failure, Tag};
+
+    use super::*;
+
+    fn tag(key: &str, value: &str) -> Tag {
+        Tag::builder().key(key).value(value).build()
+    }
+
+    #[test]
+    fn failures_are_reported_with_their_arn() {
+        assert!(failures_to_error(Vec::new()).is_ok());
+
+        let error = failures_to_error(vec![Failure::builder()
+            .arn("arn:aws:ecs:eu-central-1:123456789012:service/prod/api")
+            .reason("MISSING")
+            .build()])
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::EcsFailures { ref reasons }
+                if reasons == &["arn:aws:ecs:eu-central-1:123456789012:service/prod/api: MISSING"]
+        ));
+    }
+
+    #[test]
+    fn cluster_from_aws() {
+        let cluster = Cluster::try_from(
+            aws_sdk_ecs::types::Cluster::builder()
+                .cluster_arn("arn:aws:ecs:eu-central-1:123456789012:cluster/prod")
+                .cluster_name("prod")
+                .tags(tag("team", "infra"))
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(cluster.arn().inner().resource(), "cluster/prod");
+        assert_eq!(cluster.name(), "prod");
+        assert_eq!(
+            cluster
+                .tags()
+                .get("team".to_owned())
+                .map(|tag| tag.value().as_str()),
+            Some("infra")
+        );
+
+        assert!(matches!(
+            Cluster::try_from(
+                aws_sdk_ecs::types::Cluster::builder()
+                    .cluster_name("prod")
+                    .build()
+            ),
+            Err(Error::UnexpectedNoneValue { .. })
+        ));
+        assert!(matches!(
+            Cluster::try_from(
+                aws_sdk_ecs::types::Cluster::builder()
+                    .cluster_arn("prod")
+                    .cluster_name("prod")
+                    .build()
+            ),
+            Err(Error::InvalidArn(_))
+        ));
+    }
+
+    #[test]
+    fn service_keeps_its_cluster() {
+        let cluster =
+            ClusterArn::parse("arn:aws:ecs:eu-central-1:123456789012:cluster/prod").unwrap();
+        let service = Service::try_from_aws(
+            aws_sdk_ecs::types::Service::builder()
+                .service_arn("arn:aws:ecs:eu-central-1:123456789012:service/prod/api")
+                .service_name("api")
+                .status("ACTIVE")
+                .desired_count(2)
+                .running_count(1)
+                .task_definition("arn:aws:ecs:eu-central-1:123456789012:task-definition/api:7")
+                .build(),
+            cluster.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(service.name(), "api");
+        assert_eq!(service.cluster(), &cluster);
+        assert_eq!(service.status(), Some("ACTIVE"));
+        assert_eq!(service.desired_count(), 2);
+        assert_eq!(service.running_count(), 1);
+        assert_eq!(service.pending_count(), 0);
+        assert_eq!(
+            service.task_definition().map(|arn| arn.inner().resource()),
+            Some("task-definition/api:7")
+        );
+        assert!(service.tags().as_slice().is_empty());
+    }
+
+    #[test]
+    fn task_with_incomplete_tag_is_rejected() {
+        let task = aws_sdk_ecs::types::Task::builder()
+            .task_arn("arn:aws:ecs:eu-central-1:123456789012:task/prod/0123")
+            .task_definition_arn("arn:aws:ecs:eu-central-1:123456789012:task-definition/api:7");
+
+        let parsed = Task::try_from(task.clone().tags(tag("team", "infra")).build()).unwrap();
+        assert_eq!(
+            parsed.task_definition().inner().resource(),
+            "task-definition/api:7"
+        );
+        assert_eq!(parsed.tags().as_slice().len(), 1);
+
+        assert!(matches!(
+            Task::try_from(task.tags(Tag::builder().key("team").build()).build()),
+            Err(Error::InvalidTags(_))
+        ));
+    }
+
+    #[test]
+    fn tags_round_trip() {
+        let tags: TagList = vec![tag("team", "infra"), tag("env", "prod")]
+            .try_into()
+            .unwrap();
+
+        let aws: Vec<Tag> = tags.clone().into();
+        assert_eq!(aws.len(), 2);
+        assert_eq!(TagList::try_from(aws).unwrap(), tags);
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        const CLUSTER_ARN: &str = "arn:aws:ecs:eu-central-1:123456789012:cluster/prod";
+
+        fn cluster() -> Cluster {
+            Cluster::try_from(
+                aws_sdk_ecs::types::Cluster::builder()
+                    .cluster_arn(CLUSTER_ARN)
+                    .cluster_name("prod")
+                    .build(),
+            )
+            .unwrap()
+        }
+
+        fn service_arn(index: usize) -> String {
+            format!("arn:aws:ecs:eu-central-1:123456789012:service/prod/svc{index}")
+        }
+
+        #[test]
+        fn list_clusters_follows_next_tokens() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("nextToken", "page2"),
+                    MockResponse::ok(
+                        r#"{"clusterArns": ["arn:aws:ecs:eu-central-1:123456789012:cluster/staging"]}"#,
+                    ),
+                )
+                .on(
+                    Matcher::action("ListClusters"),
+                    MockResponse::ok(format!(
+                        r#"{{"clusterArns": ["{CLUSTER_ARN}"], "nextToken": "page2"}}"#
+                    )),
+                )
+                .on(
+                    Matcher::action("DescribeClusters"),
+                    MockResponse::ok(format!(
+                        r#"{{"clusters": [
+                            {{"clusterArn": "{CLUSTER_ARN}", "clusterName": "prod",
+                              "tags": [{{"key": "team", "value": "infra"}}]}},
+                            {{"clusterArn": "arn:aws:ecs:eu-central-1:123456789012:cluster/staging",
+                              "clusterName": "staging"}}
+                        ], "failures": []}}"#
+                    )),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let clusters = block_on(Cluster::list(&client)).unwrap();
+
+            assert_eq!(
+                clusters.iter().map(Cluster::name).collect::<Vec<_>>(),
+                ["prod", "staging"]
+            );
+            assert_eq!(
+                clusters.first().unwrap().tags().as_slice(),
+                [RawTag::new("team".to_owned(), "infra".to_owned())]
+            );
+
+            let describe = http
+                .requests_matching(&Matcher::action("DescribeClusters"))
+                .unwrap()
+                .pop()
+                .unwrap();
+            assert_eq!(
+                describe.json_param::<Vec<String>>("clusters").unwrap(),
+                [
+                    CLUSTER_ARN,
+                    "arn:aws:ecs:eu-central-1:123456789012:cluster/staging"
+                ]
+            );
+            assert_eq!(
+                describe.json_param::<Vec<String>>("include").unwrap(),
+                ["TAGS"]
+            );
+        }
+
+        #[test]
+        fn no_clusters_are_not_described() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("ListClusters"),
+                MockResponse::ok(r#"{"clusterArns": []}"#),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            assert!(block_on(Cluster::list(&client)).unwrap().is_empty());
+            assert_eq!(http.requests().unwrap().len(), 1);
+        }
+
+        #[test]
+        fn describe_failures_are_errors() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::action("ListClusters"),
+                    MockResponse::ok(format!(r#"{{"clusterArns": ["{CLUSTER_ARN}"]}}"#)),
+                )
+                .on(
+                    Matcher::action("DescribeClusters"),
+                    MockResponse::ok(format!(
+                        r#"{{"clusters": [], "failures": [{{"arn": "{CLUSTER_ARN}", "reason": "MISSING"}}]}}"#
+                    )),
+                );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            assert!(matches!(
+                block_on(Cluster::list(&client)),
+                Err(Error::EcsFailures { ref reasons }) if reasons.len() == 1
+            ));
+        }
+
+        #[test]
+        fn error_response_is_returned() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("ListClusters"),
+                MockResponse::status(
+                    400,
+                    r#"{"__type": "AccessDeniedException", "message": "denied"}"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let error = block_on(Cluster::list(&client)).unwrap_err();
+            assert_eq!(error.request_metadata().unwrap().status, Some(400));
+        }
+
+        #[test]
+        fn services_are_described_in_chunks() {
+            let arns = (0..12).map(service_arn).collect::<Vec<_>>();
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::action("ListServices"),
+                    MockResponse::ok(serde_json::json!({ "serviceArns": arns }).to_string()),
+                )
+                .on(
+                    Matcher::action("DescribeServices"),
+                    MockResponse::ok(format!(
+                        r#"{{"services": [{{"serviceArn": "{}", "serviceName": "svc0",
+                            "desiredCount": 2, "runningCount": 2}}]}}"#,
+                        service_arn(0)
+                    )),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let cluster = cluster();
+
+            let services = block_on(cluster.services(&client)).unwrap();
+
+            assert_eq!(services.len(), 2, "one service per describe call");
+            assert!(services
+                .iter()
+                .all(|service| service.cluster() == cluster.arn()));
+            assert_eq!(
+                http.requests_matching(&Matcher::action("DescribeServices"))
+                    .unwrap()
+                    .iter()
+                    .map(|request| request.json_param::<Vec<String>>("services").unwrap().len())
+                    .collect::<Vec<_>>(),
+                [10, 2]
+            );
+        }
+
+        #[test]
+        fn tasks_of_a_service() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::action("ListTasks"),
+                    MockResponse::ok(
+                        r#"{"taskArns": ["arn:aws:ecs:eu-central-1:123456789012:task/prod/0123"]}"#,
+                    ),
+                )
+                .on(
+                    Matcher::action("DescribeTasks"),
+                    MockResponse::ok(
+                        r#"{"tasks": [{"taskArn": "arn:aws:ecs:eu-central-1:123456789012:task/prod/0123",
+                            "taskDefinitionArn": "arn:aws:ecs:eu-central-1:123456789012:task-definition/api:7",
+                            "lastStatus": "RUNNING", "desiredStatus": "RUNNING"}]}"#,
+                    ),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let cluster = cluster();
+            let service = Service::try_from_aws(
+                aws_sdk_ecs::types::Service::builder()
+                    .service_arn(service_arn(0))
+                    .service_name("api")
+                    .build(),
+                cluster.arn().clone(),
+            )
+            .unwrap();
+
+            let tasks = block_on(cluster.tasks(&client, Some(&service))).unwrap();
+
+            assert_eq!(tasks.len(), 1);
+            assert_eq!(tasks.first().unwrap().last_status(), Some("RUNNING"));
+            let list = http
+                .requests_matching(&Matcher::action("ListTasks"))
+                .unwrap()
+                .pop()
+                .unwrap();
+            assert_eq!(list.param("serviceName").as_deref(), Some("api"));
+            assert_eq!(list.param("cluster").as_deref(), Some(CLUSTER_ARN));
+        }
+
+        #[test]
+        fn tags_by_arn() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::action("ListTagsForResource"),
+                    MockResponse::ok(
+                        r#"{"tags": [{"key": "team", "value": "infra"}, {"key": "env", "value": "prod"}]}"#,
+                    ),
+                )
+                .on(Matcher::Any, MockResponse::ok("{}"));
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let arn = Arn::parse(CLUSTER_ARN).unwrap();
+
+            let tags = block_on(tags(&client, &arn)).unwrap();
+            assert_eq!(
+                tags.as_slice(),
+                [
+                    RawTag::new("env".to_owned(), "prod".to_owned()),
+                    RawTag::new("team".to_owned(), "infra".to_owned()),
+                ]
+            );
+
+            block_on(remove_tags(
+                &client,
+                &arn,
+                vec![TagKey::new("team".to_owned())],
+            ))
+            .unwrap();
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(request.action().as_deref(), Some("UntagResource"));
+            assert_eq!(request.param("resourceArn").as_deref(), Some(CLUSTER_ARN));
+            assert_eq!(
+                request.json_param::<Vec<String>>("tagKeys").unwrap(),
+                ["team"]
+            );
+        }
+    }
+}
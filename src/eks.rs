@@ -0,0 +1,392 @@
+//! EKS clusters and authentication tokens for the Kubernetes API
+//!
+//! [`get_token()`] produces the same bearer token as `aws eks get-token`: a
+//! presigned `sts:GetCallerIdentity` URL that the cluster uses to look up the
+//! IAM identity of the caller. It is signed with the credentials of the STS
+//! client of the given [`RegionClient`].
+
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use aws_credential_types::{provider::ProvideCredentials as _, Credentials};
+use aws_sigv4::{
+    http_request::{sign, SignableBody, SignableRequest, SignatureLocation, SigningSettings},
+    sign::v4,
+};
+use aws_smithy_runtime_api::client::identity::Identity;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, TimeDelta, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    arn::Arn,
+    protocol::{url_encode, Operation, Protocol, Shape, Value},
+    tags::{TagKey, TagList},
+    Error, Region, RegionClient, Timestamp,
+};
+
+/// The prefix of all EKS tokens, followed by the base64 encoded URL
+const TOKEN_PREFIX: &str = "k8s-aws-v1.";
+
+/// The header that binds the token to a cluster
+const CLUSTER_ID_HEADER: &str = "x-k8s-aws-id";
+
+/// How long the presigned URL is valid. EKS additionally rejects URLs that
+/// were signed more than 15 minutes ago, regardless of their expiry.
+const PRESIGN_EXPIRY: Duration = Duration::from_secs(60);
+
+/// Tokens are reported as expiring a minute before EKS stops accepting them,
+/// so that clients refresh them in time
+const TOKEN_LIFETIME_MINUTES: i64 = 14;
+
+string_newtype!(ClusterName);
+
+impl ClusterName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    name: ClusterName,
+    arn: Arn,
+    version: Option<String>,
+    status: Option<String>,
+    endpoint: Option<String>,
+    certificate_authority: Option<String>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_eks::types::Cluster> for Cluster {
+    type Error = Error;
+
+    fn try_from(cluster: aws_sdk_eks::types::Cluster) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                cluster.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            name: ClusterName(extract!(name)?),
+            arn: Arn::parse(&extract!(arn)?)?,
+            version: cluster.version,
+            status: cluster.status.map(|status| status.as_str().to_owned()),
+            endpoint: cluster.endpoint,
+            certificate_authority: cluster.certificate_authority.and_then(|ca| ca.data),
+            tags: cluster.tags.unwrap_or_default().into(),
+        })
+    }
+}
+
+impl Cluster {
+    pub async fn list_names(client: &RegionClient) -> Result<Vec<ClusterName>, Error> {
+        Ok(client
+            .main
+            .eks
+            .list_clusters()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(ClusterName)
+            .collect())
+    }
+
+    /// Describes all clusters of the region, one request per cluster
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        let mut clusters = vec![];
+        for name in Self::list_names(client).await? {
+            clusters.push(Self::describe(client, &name).await?);
+        }
+        Ok(clusters)
+    }
+
+    pub async fn describe(client: &RegionClient, name: &ClusterName) -> Result<Self, Error> {
+        client
+            .main
+            .eks
+            .describe_cluster()
+            .name(name.as_str())
+            .send()
+            .await?
+            .cluster
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "DescribeClusterResponse.cluster".to_owned(),
+            })?
+            .try_into()
+    }
+
+    pub const fn name(&self) -> &ClusterName {
+        &self.name
+    }
+
+    pub const fn arn(&self) -> &Arn {
+        &self.arn
+    }
+
+    /// The Kubernetes version, e.g. `1.30`
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    /// The URL of the Kubernetes API server
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    /// The base64 encoded certificate of the cluster CA, as used for
+    /// `certificate-authority-data` in a kubeconfig
+    pub fn certificate_authority(&self) -> Option<&str> {
+        self.certificate_authority.as_deref()
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .eks
+            .tag_resource()
+            .resource_arn(self.arn.to_string())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .eks
+            .untag_resource()
+            .resource_arn(self.arn.to_string())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn token(&self, client: &RegionClient) -> Result<EksToken, Error> {
+        get_token(client, &self.name).await
+    }
+}
+
+/// A bearer token for the Kubernetes API of an EKS cluster
+#[derive(Clone)]
+pub struct EksToken {
+    token: String,
+    expiration: Timestamp,
+}
+
+// Do not leak the token into logs
+impl fmt::Debug for EksToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EksToken")
+            .field("expiration", &self.expiration)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EksToken {
+    /// The value for the `Authorization: Bearer` header
+    pub fn as_str(&self) -> &str {
+        &self.token
+    }
+
+    pub const fn expiration(&self) -> &Timestamp {
+        &self.expiration
+    }
+
+    /// The `ExecCredential` JSON document that kubectl expects from a
+    /// credential plugin configured via `users[].user.exec` in a kubeconfig
+    #[cfg(feature = "serde")]
+    pub fn exec_credential(&self) -> String {
+        serde_json::json!({
+            "kind": "ExecCredential",
+            "apiVersion": "client.authentication.k8s.io/v1beta1",
+            "spec": {},
+            "status": {
+                "expirationTimestamp": self
+                    .expiration
+                    .inner()
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                "token": self.token,
+            },
+        })
+        .to_string()
+    }
+}
+
+struct GetCallerIdentity;
+
+impl Shape for GetCallerIdentity {
+    const NAME: &'static str = "GetCallerIdentityRequest";
+
+    fn to_value(&self) -> Value {
+        Value::structure(Vec::<(String, Value)>::new())
+    }
+}
+
+/// Returns a token for the Kubernetes API of `cluster`, authenticated as the
+/// identity of the credentials of `client`
+pub async fn get_token(client: &RegionClient, cluster: &ClusterName) -> Result<EksToken, Error> {
+    let credentials = client
+        .credentials
+        .as_ref()
+        .ok_or_else(|| Error::Presign {
+            message: "no credentials configured".to_owned(),
+        })?
+        .provide_credentials()
+        .await
+        .map_err(|e| Error::Presign {
+            message: e.to_string(),
+        })?;
+
+    let now = Utc::now();
+
+    Ok(EksToken {
+        token: token(&credentials, client.region, cluster, now)?,
+        expiration: Timestamp::new(
+            now.checked_add_signed(TimeDelta::minutes(TOKEN_LIFETIME_MINUTES))
+                .unwrap_or(now),
+        ),
+    })
+}
+
+fn token(
+    credentials: &Credentials,
+    region: Region,
+    cluster: &ClusterName,
+    time: DateTime<Utc>,
+) -> Result<String, Error> {
+    let presign_error = |message: String| Error::Presign { message };
+
+    let identity: Identity = credentials.clone().into();
+
+    let mut settings = SigningSettings::default();
+    settings.signature_location = SignatureLocation::QueryParams;
+    settings.expires_in = Some(PRESIGN_EXPIRY);
+
+    let params = v4::SigningParams::builder()
+        .identity(&identity)
+        .region(region.as_str())
+        .name("sts")
+        .time(SystemTime::from(time))
+        .settings(settings)
+        .build()
+        .map_err(|e| presign_error(e.to_string()))?
+        .into();
+
+    let query = Protocol::AwsQuery.serialize(
+        &Operation::new("GetCallerIdentity", "2011-06-15"),
+        &GetCallerIdentity,
+    );
+    let url = format!(
+        "{}/?{}",
+        region.partition().endpoint("sts", region.as_str()),
+        query.as_str()
+    );
+
+    let request = SignableRequest::new(
+        "GET",
+        url.as_str(),
+        [(CLUSTER_ID_HEADER, cluster.as_str())].into_iter(),
+        SignableBody::Bytes(&[]),
+    )
+    .map_err(|e| presign_error(e.to_string()))?;
+
+    let (instructions, _signature) = sign(request, &params)
+        .map_err(|e| presign_error(e.to_string()))?
+        .into_parts();
+
+    let signed_url = instructions
+        .params()
+        .iter()
+        .fold(url, |url, &(name, ref value)| {
+            format!("{url}&{}={}", url_encode(name), url_encode(value))
+        });
+
+    Ok(format!(
+        "{TOKEN_PREFIX}{}",
+        URL_SAFE_NO_PAD.encode(signed_url)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn presigned_token() {
+        let credentials = Credentials::new("AKIDEXAMPLE", "secret", None, None, "test");
+        let time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let cluster = ClusterName::new("prod".to_owned());
+
+        let token = token(&credentials, Region::EuCentral1, &cluster, time).unwrap();
+
+        let encoded = token.strip_prefix(TOKEN_PREFIX).unwrap();
+        assert!(!encoded.contains('='));
+
+        let url = String::from_utf8(URL_SAFE_NO_PAD.decode(encoded).unwrap()).unwrap();
+        assert!(url.starts_with(
+            "https://sts.eu-central-1.amazonaws.com/?Action=GetCallerIdentity&Version=2011-06-15&"
+        ));
+        assert!(url.contains("X-Amz-Expires=60"));
+        assert!(url.contains("X-Amz-Date=20240101T120000Z"));
+        assert!(url.contains(
+            "X-Amz-Credential=AKIDEXAMPLE%2F20240101%2Feu-central-1%2Fsts%2Faws4_request"
+        ));
+        assert!(url.contains("X-Amz-SignedHeaders=host%3Bx-k8s-aws-id"));
+        assert!(url.contains("X-Amz-Signature="));
+
+        // Signing is deterministic for the same input
+        assert_eq!(
+            token,
+            super::token(&credentials, Region::EuCentral1, &cluster, time).unwrap()
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn token_from_client_credentials() {
+        use futures_util::FutureExt as _;
+
+        use crate::testing::{mock_region_client, MockHttpClient};
+
+        let client = mock_region_client(Region::EuCentral1, MockHttpClient::new());
+        let cluster = ClusterName::new("prod".to_owned());
+
+        let token = get_token(&client, &cluster)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+
+        let encoded = token.token.strip_prefix(TOKEN_PREFIX).unwrap();
+        let url = String::from_utf8(URL_SAFE_NO_PAD.decode(encoded).unwrap()).unwrap();
+        assert!(url.contains("X-Amz-Credential=AKIDMOCK%2F"));
+    }
+}
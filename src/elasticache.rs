@@ -0,0 +1,525 @@
+//! ElastiCache clusters and replication groups
+//!
+//! ElastiCache tags are managed via the ARN of the resource. The describe calls
+//! do not return tags, use [`ElasticacheArn::tags()`]. Tags are read and written
+//! via the Resource Groups Tagging API, which needs the `tag:*` permissions.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{resource, TagKey, TagList},
+    Error, RegionClient,
+};
+
+string_newtype!(CacheClusterId);
+
+impl CacheClusterId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(ReplicationGroupId);
+
+impl ReplicationGroupId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+arn_newtype!(ElasticacheArn);
+
+impl ElasticacheArn {
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        resource::tags(client, self.inner()).await
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        resource::add_tags(client, self.inner(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        resource::remove_tags(client, self.inner(), keys).await
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct CacheCluster {
+    id: CacheClusterId,
+    arn: ElasticacheArn,
+    node_type: Option<String>,
+    engine: Option<String>,
+    engine_version: Option<String>,
+    status: Option<String>,
+    replication_group: Option<ReplicationGroupId>,
+}
+
+impl TryFrom<aws_sdk_elasticache::types::CacheCluster> for CacheCluster {
+    type Error = Error;
+
+    fn try_from(cluster: aws_sdk_elasticache::types::CacheCluster) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                cluster.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            id: CacheClusterId(extract!(cache_cluster_id)?),
+            arn: ElasticacheArn::parse(&extract!(arn)?)?,
+            node_type: cluster.cache_node_type,
+            engine: cluster.engine,
+            engine_version: cluster.engine_version,
+            status: cluster.cache_cluster_status,
+            replication_group: cluster.replication_group_id.map(ReplicationGroupId),
+        })
+    }
+}
+
+impl CacheCluster {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .elasticache
+            .describe_cache_clusters()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_id(
+        client: &RegionClient,
+        id: &CacheClusterId,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .elasticache
+            .describe_cache_clusters()
+            .cache_cluster_id(id.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => output
+                .cache_clusters
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .map(TryInto::try_into)
+                .transpose(),
+            Err(e) => match e.as_service_error() {
+                Some(service_error) if service_error.is_cache_cluster_not_found_fault() => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    pub const fn id(&self) -> &CacheClusterId {
+        &self.id
+    }
+
+    pub const fn arn(&self) -> &ElasticacheArn {
+        &self.arn
+    }
+
+    pub fn node_type(&self) -> Option<&str> {
+        self.node_type.as_deref()
+    }
+
+    /// `redis`, `valkey` or `memcached`
+    pub fn engine(&self) -> Option<&str> {
+        self.engine.as_deref()
+    }
+
+    pub fn engine_version(&self) -> Option<&str> {
+        self.engine_version.as_deref()
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    /// The replication group the cluster belongs to, if any
+    pub const fn replication_group(&self) -> Option<&ReplicationGroupId> {
+        self.replication_group.as_ref()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ReplicationGroup {
+    id: ReplicationGroupId,
+    arn: ElasticacheArn,
+    description: Option<String>,
+    status: Option<String>,
+    members: Vec<CacheClusterId>,
+}
+
+impl TryFrom<aws_sdk_elasticache::types::ReplicationGroup> for ReplicationGroup {
+    type Error = Error;
+
+    fn try_from(group: aws_sdk_elasticache::types::ReplicationGroup) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                group.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            id: ReplicationGroupId(extract!(replication_group_id)?),
+            arn: ElasticacheArn::parse(&extract!(arn)?)?,
+            description: group.description,
+            status: group.status,
+            members: group
+                .member_clusters
+                .unwrap_or_default()
+                .into_iter()
+                .map(CacheClusterId)
+                .collect(),
+        })
+    }
+}
+
+impl ReplicationGroup {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .elasticache
+            .describe_replication_groups()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_id(
+        client: &RegionClient,
+        id: &ReplicationGroupId,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .elasticache
+            .describe_replication_groups()
+            .replication_group_id(id.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => output
+                .replication_groups
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .map(TryInto::try_into)
+                .transpose(),
+            Err(e) => match e.as_service_error() {
+                Some(service_error) if service_error.is_replication_group_not_found_fault() => {
+                    Ok(None)
+                }
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    pub const fn id(&self) -> &ReplicationGroupId {
+        &self.id
+    }
+
+    pub const fn arn(&self) -> &ElasticacheArn {
+        &self.arn
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    pub fn members(&self) -> &[CacheClusterId] {
+        &self.members
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_from_aws() {
+        let cluster = CacheCluster::try_from(
+            aws_sdk_elasticache::types::CacheCluster::builder()
+                .cache_cluster_id("cache-001")
+                .arn("arn:aws:elasticache:eu-central-1:123456789012:cluster:cache-001")
+                .cache_node_type("cache.t3.micro")
+                .engine("redis")
+                .engine_version("7.1")
+                .cache_cluster_status("available")
+                .replication_group_id("cache")
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(cluster.id().as_str(), "cache-001");
+        assert_eq!(cluster.arn().inner().resource(), "cluster:cache-001");
+        assert_eq!(cluster.node_type(), Some("cache.t3.micro"));
+        assert_eq!(cluster.engine(), Some("redis"));
+        assert_eq!(cluster.engine_version(), Some("7.1"));
+        assert_eq!(cluster.status(), Some("available"));
+        assert_eq!(
+            cluster.replication_group().map(ReplicationGroupId::as_str),
+            Some("cache")
+        );
+
+        assert!(matches!(
+            CacheCluster::try_from(
+                aws_sdk_elasticache::types::CacheCluster::builder()
+                    .cache_cluster_id("cache-001")
+                    .build()
+            ),
+            Err(Error::UnexpectedNoneValue { ref entity }) if entity == "arn"
+        ));
+    }
+
+    #[test]
+    fn replication_group_members() {
+        let group = ReplicationGroup::try_from(
+            aws_sdk_elasticache::types::ReplicationGroup::builder()
+                .replication_group_id("cache")
+                .arn("arn:aws:elasticache:eu-central-1:123456789012:replicationgroup:cache")
+                .member_clusters("cache-001")
+                .member_clusters("cache-002")
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(group.id().as_str(), "cache");
+        assert_eq!(group.description(), None);
+        assert_eq!(
+            group
+                .members()
+                .iter()
+                .map(CacheClusterId::as_str)
+                .collect::<Vec<_>>(),
+            ["cache-001", "cache-002"]
+        );
+    }
+
+    #[test]
+    fn invalid_arn_is_rejected() {
+        let cluster = aws_sdk_elasticache::types::CacheCluster::builder()
+            .cache_cluster_id("cache-001")
+            .arn("cache-001")
+            .build();
+
+        assert!(matches!(
+            CacheCluster::try_from(cluster),
+            Err(Error::InvalidArn(_))
+        ));
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use std::collections::HashMap;
+
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{
+                block_on, mock_region_client, tagging_failure, Matcher, MockHttpClient,
+                MockResponse,
+            },
+            Region,
+        };
+
+        const CLUSTER_ARN: &str = "arn:aws:elasticache:eu-central-1:123456789012:cluster:cache-001";
+
+        fn clusters(marker: Option<&str>, id: &str) -> MockResponse {
+            MockResponse::ok(format!(
+                r#"<DescribeCacheClustersResponse xmlns="http://elasticache.amazonaws.com/doc/2015-02-02/">
+      <DescribeCacheClustersResult>
+        {}
+        <CacheClusters>
+          <CacheCluster>
+            <CacheClusterId>{id}</CacheClusterId>
+            <ARN>arn:aws:elasticache:eu-central-1:123456789012:cluster:{id}</ARN>
+            <CacheNodeType>cache.t3.micro</CacheNodeType>
+            <Engine>redis</Engine>
+            <ReplicationGroupId>cache</ReplicationGroupId>
+          </CacheCluster>
+        </CacheClusters>
+      </DescribeCacheClustersResult>
+    </DescribeCacheClustersResponse>"#,
+                marker.map_or_else(String::new, |marker| format!("<Marker>{marker}</Marker>"))
+            ))
+        }
+
+        fn arn() -> ElasticacheArn {
+            ElasticacheArn::parse(CLUSTER_ARN).unwrap()
+        }
+
+        #[test]
+        fn list_clusters_follows_markers() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("Marker", "page2"),
+                    clusters(None, "cache-002"),
+                )
+                .on(
+                    Matcher::action("DescribeCacheClusters"),
+                    clusters(Some("page2"), "cache-001"),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let clusters = block_on(CacheCluster::list(&client)).unwrap();
+
+            assert_eq!(
+                clusters
+                    .iter()
+                    .map(|cluster| cluster.id().as_str())
+                    .collect::<Vec<_>>(),
+                ["cache-001", "cache-002"]
+            );
+            let first = clusters.first().unwrap();
+            assert_eq!(first.arn(), &arn());
+            assert_eq!(first.engine(), Some("redis"));
+            assert_eq!(first.replication_group().unwrap().as_str(), "cache");
+            assert_eq!(http.requests().unwrap().len(), 2);
+        }
+
+        #[test]
+        fn find_replication_group() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeReplicationGroups"),
+                MockResponse::ok(
+                    r#"<DescribeReplicationGroupsResponse xmlns="http://elasticache.amazonaws.com/doc/2015-02-02/">
+      <DescribeReplicationGroupsResult>
+        <ReplicationGroups>
+          <ReplicationGroup>
+            <ReplicationGroupId>cache</ReplicationGroupId>
+            <ARN>arn:aws:elasticache:eu-central-1:123456789012:replicationgroup:cache</ARN>
+            <MemberClusters>
+              <ClusterId>cache-001</ClusterId>
+              <ClusterId>cache-002</ClusterId>
+            </MemberClusters>
+          </ReplicationGroup>
+        </ReplicationGroups>
+      </DescribeReplicationGroupsResult>
+    </DescribeReplicationGroupsResponse>"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let group = block_on(ReplicationGroup::find_by_id(
+                &client,
+                &ReplicationGroupId::new("cache".to_owned()),
+            ))
+            .unwrap()
+            .unwrap();
+
+            assert_eq!(group.arn().inner().resource(), "replicationgroup:cache");
+            assert_eq!(
+                group
+                    .members()
+                    .iter()
+                    .map(CacheClusterId::as_str)
+                    .collect::<Vec<_>>(),
+                ["cache-001", "cache-002"]
+            );
+        }
+
+        #[test]
+        fn tags_via_tagging_api() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::action("GetResources"),
+                    MockResponse::ok(format!(
+                        r#"{{"ResourceTagMappingList": [{{"ResourceARN": "{CLUSTER_ARN}", "Tags": [
+                            {{"Key": "team", "Value": "infra"}},
+                            {{"Key": "env", "Value": "prod"}}
+                        ]}}]}}"#
+                    )),
+                )
+                .on(Matcher::action("TagResources"), MockResponse::ok("{}"));
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let tags = block_on(arn().tags(&client)).unwrap();
+            assert_eq!(
+                tags.as_slice(),
+                [
+                    RawTag::new("env".to_owned(), "prod".to_owned()),
+                    RawTag::new("team".to_owned(), "infra".to_owned()),
+                ]
+            );
+
+            block_on(arn().add_tags(&client, tags)).unwrap();
+            let request = http
+                .requests_matching(&Matcher::action("TagResources"))
+                .unwrap()
+                .pop()
+                .unwrap();
+            assert_eq!(
+                request
+                    .json_param::<Vec<String>>("ResourceARNList")
+                    .unwrap(),
+                [CLUSTER_ARN]
+            );
+            assert_eq!(
+                request
+                    .json_param::<HashMap<String, String>>("Tags")
+                    .unwrap(),
+                HashMap::from([
+                    ("team".to_owned(), "infra".to_owned()),
+                    ("env".to_owned(), "prod".to_owned()),
+                ])
+            );
+        }
+
+        #[test]
+        fn tagging_failure_is_an_error() {
+            let arn = arn();
+            let http = MockHttpClient::new().on(
+                Matcher::action("UntagResources"),
+                tagging_failure(arn.inner(), 400, "InvalidParameterException"),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let error = block_on(arn.remove_tags(&client, vec![TagKey::new("env".to_owned())]))
+                .unwrap_err();
+
+            assert!(
+                matches!(
+                    error,
+                    Error::TaggingFailed {
+                        ref arn,
+                        error_code: Some(ref code),
+                        ..
+                    } if arn == CLUSTER_ARN && code == "InvalidParameterException"
+                ),
+                "unexpected error {error:?}"
+            );
+        }
+    }
+}
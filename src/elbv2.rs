@@ -0,0 +1,847 @@
+//! Application and network load balancers (ELBv2) and their target groups
+
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+use aws_sdk_elasticloadbalancingv2::operation::describe_load_balancers::DescribeLoadBalancersError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    arn::Arn,
+    tags::{TagKey, TagList},
+    Error, RegionClient,
+};
+
+arn_newtype!(LoadBalancerArn);
+
+arn_newtype!(TargetGroupArn);
+
+/// Load balancers and target groups share the same tagging API, keyed by ARN
+async fn tags(client: &RegionClient, arn: &Arn) -> Result<TagList, Error> {
+    let arn = arn.to_string();
+    Ok(client
+        .main
+        .elbv2
+        .describe_tags()
+        .resource_arns(arn.as_str())
+        .send()
+        .await?
+        .tag_descriptions
+        .unwrap_or_default()
+        .into_iter()
+        .find(|description| description.resource_arn.as_deref() == Some(arn.as_str()))
+        .and_then(|description| description.tags)
+        .unwrap_or_default()
+        .try_into()?)
+}
+
+async fn add_tags(client: &RegionClient, arn: &Arn, tags: TagList) -> Result<(), Error> {
+    let _output = client
+        .main
+        .elbv2
+        .add_tags()
+        .resource_arns(arn.to_string())
+        .set_tags(Some(tags.into()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+async fn remove_tags(client: &RegionClient, arn: &Arn, keys: Vec<TagKey>) -> Result<(), Error> {
+    let _output = client
+        .main
+        .elbv2
+        .remove_tags()
+        .resource_arns(arn.to_string())
+        .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct LoadBalancer {
+    arn: LoadBalancerArn,
+    name: String,
+    dns_name: Option<String>,
+    kind: Option<String>,
+    scheme: Option<String>,
+    state: Option<String>,
+    vpc_id: Option<String>,
+}
+
+impl TryFrom<aws_sdk_elasticloadbalancingv2::types::LoadBalancer> for LoadBalancer {
+    type Error = Error;
+
+    fn try_from(
+        load_balancer: aws_sdk_elasticloadbalancingv2::types::LoadBalancer,
+    ) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                load_balancer
+                    .$field
+                    .ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: stringify!($field).to_owned(),
+                    })
+            };
+        }
+
+        Ok(Self {
+            arn: LoadBalancerArn::parse(&extract!(load_balancer_arn)?)?,
+            name: extract!(load_balancer_name)?,
+            dns_name: load_balancer.dns_name,
+            kind: load_balancer.r#type.map(|kind| kind.as_str().to_owned()),
+            scheme: load_balancer
+                .scheme
+                .map(|scheme| scheme.as_str().to_owned()),
+            state: load_balancer
+                .state
+                .and_then(|state| state.code)
+                .map(|code| code.as_str().to_owned()),
+            vpc_id: load_balancer.vpc_id,
+        })
+    }
+}
+
+impl LoadBalancer {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .elbv2
+            .describe_load_balancers()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_name(client: &RegionClient, name: &str) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .elbv2
+            .describe_load_balancers()
+            .names(name)
+            .send()
+            .await
+        {
+            Ok(output) => {
+                let mut load_balancers = output.load_balancers.unwrap_or_default();
+                match load_balancers.pop() {
+                    None => Ok(None),
+                    Some(load_balancer) => {
+                        if load_balancers.is_empty() {
+                            Ok(Some(load_balancer.try_into()?))
+                        } else {
+                            Err(Error::MultipleMatches {
+                                entity: format!("load balancer {name}"),
+                            })
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                if e.as_service_error()
+                    .is_some_and(DescribeLoadBalancersError::is_load_balancer_not_found_exception)
+                {
+                    Ok(None)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
+    pub const fn arn(&self) -> &LoadBalancerArn {
+        &self.arn
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn dns_name(&self) -> Option<&str> {
+        self.dns_name.as_deref()
+    }
+
+    /// `application`, `network` or `gateway`
+    pub fn kind(&self) -> Option<&str> {
+        self.kind.as_deref()
+    }
+
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    pub fn vpc_id(&self) -> Option<&str> {
+        self.vpc_id.as_deref()
+    }
+
+    pub async fn target_groups(&self, client: &RegionClient) -> Result<Vec<TargetGroup>, Error> {
+        TargetGroup::list_inner(client, Some(&self.arn)).await
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        tags(client, self.arn.inner()).await
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.arn.inner(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.arn.inner(), keys).await
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct TargetGroup {
+    arn: TargetGroupArn,
+    name: String,
+    protocol: Option<String>,
+    port: Option<u16>,
+    target_type: Option<String>,
+    vpc_id: Option<String>,
+    load_balancers: Vec<LoadBalancerArn>,
+}
+
+impl TryFrom<aws_sdk_elasticloadbalancingv2::types::TargetGroup> for TargetGroup {
+    type Error = Error;
+
+    fn try_from(
+        target_group: aws_sdk_elasticloadbalancingv2::types::TargetGroup,
+    ) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                target_group
+                    .$field
+                    .ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: stringify!($field).to_owned(),
+                    })
+            };
+        }
+
+        Ok(Self {
+            arn: TargetGroupArn::parse(&extract!(target_group_arn)?)?,
+            name: extract!(target_group_name)?,
+            protocol: target_group
+                .protocol
+                .map(|protocol| protocol.as_str().to_owned()),
+            port: target_group
+                .port
+                .map(u16::try_from)
+                .transpose()
+                .map_err(|e| Error::InvalidResponseError {
+                    message: format!("invalid target group port: {e}"),
+                })?,
+            target_type: target_group
+                .target_type
+                .map(|target_type| target_type.as_str().to_owned()),
+            vpc_id: target_group.vpc_id,
+            load_balancers: target_group
+                .load_balancer_arns
+                .unwrap_or_default()
+                .into_iter()
+                .map(|arn| LoadBalancerArn::parse(&arn))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl TargetGroup {
+    async fn list_inner(
+        client: &RegionClient,
+        load_balancer: Option<&LoadBalancerArn>,
+    ) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .elbv2
+            .describe_target_groups()
+            .set_load_balancer_arn(load_balancer.map(ToString::to_string))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        Self::list_inner(client, None).await
+    }
+
+    pub const fn arn(&self) -> &TargetGroupArn {
+        &self.arn
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    pub const fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// `instance`, `ip`, `lambda` or `alb`
+    pub fn target_type(&self) -> Option<&str> {
+        self.target_type.as_deref()
+    }
+
+    pub fn vpc_id(&self) -> Option<&str> {
+        self.vpc_id.as_deref()
+    }
+
+    pub fn load_balancers(&self) -> &[LoadBalancerArn] {
+        &self.load_balancers
+    }
+
+    pub async fn register_targets(
+        &self,
+        client: &RegionClient,
+        targets: Vec<Target>,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .elbv2
+            .register_targets()
+            .target_group_arn(self.arn.to_string())
+            .set_targets(Some(targets.into_iter().map(Into::into).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deregistration only starts connection draining. Use
+    /// [`wait_for_drain()`](Self::wait_for_drain()) to wait until the targets
+    /// are actually removed.
+    pub async fn deregister_targets(
+        &self,
+        client: &RegionClient,
+        targets: Vec<Target>,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .elbv2
+            .deregister_targets()
+            .target_group_arn(self.arn.to_string())
+            .set_targets(Some(targets.into_iter().map(Into::into).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the health of the given targets, or of all registered targets
+    /// if `targets` is `None`
+    pub async fn target_health(
+        &self,
+        client: &RegionClient,
+        targets: Option<Vec<Target>>,
+    ) -> Result<Vec<TargetHealth>, Error> {
+        client
+            .main
+            .elbv2
+            .describe_target_health()
+            .target_group_arn(self.arn.to_string())
+            .set_targets(targets.map(|targets| targets.into_iter().map(Into::into).collect()))
+            .send()
+            .await?
+            .target_health_descriptions
+            .unwrap_or_default()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Waits until none of the given targets is draining anymore
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait_for_drain(
+        &self,
+        client: &RegionClient,
+        targets: Vec<Target>,
+        poll_interval: Duration,
+        max_wait: Duration,
+    ) -> Result<(), Error> {
+        let start = Instant::now();
+
+        loop {
+            let health = self.target_health(client, Some(targets.clone())).await?;
+
+            if !health
+                .iter()
+                .any(|health| health.state == TargetHealthState::Draining)
+            {
+                return Ok(());
+            }
+
+            if start.elapsed() >= max_wait {
+                return Err(Error::TargetDrainExceededMaxWait {
+                    target_group: self.arn.to_string(),
+                    max_wait,
+                });
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        tags(client, self.arn.inner()).await
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.arn.inner(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.arn.inner(), keys).await
+    }
+}
+
+/// A target of a target group. `id` is an instance ID, an IP address, a
+/// lambda ARN or an ALB ARN, depending on the target type of the group.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub id: String,
+    pub port: Option<u16>,
+}
+
+impl From<Target> for aws_sdk_elasticloadbalancingv2::types::TargetDescription {
+    fn from(target: Target) -> Self {
+        Self::builder()
+            .id(target.id)
+            .set_port(target.port.map(i32::from))
+            .build()
+    }
+}
+
+impl TryFrom<aws_sdk_elasticloadbalancingv2::types::TargetDescription> for Target {
+    type Error = Error;
+
+    fn try_from(
+        target: aws_sdk_elasticloadbalancingv2::types::TargetDescription,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: target.id.ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "TargetDescription.id".to_owned(),
+            })?,
+            port: target.port.map(u16::try_from).transpose().map_err(|e| {
+                Error::InvalidResponseError {
+                    message: format!("invalid target port: {e}"),
+                }
+            })?,
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetHealthState {
+    Initial,
+    Healthy,
+    Unhealthy,
+    UnhealthyDraining,
+    Unused,
+    Draining,
+    Unavailable,
+    Unknown,
+}
+
+impl From<aws_sdk_elasticloadbalancingv2::types::TargetHealthStateEnum> for TargetHealthState {
+    fn from(state: aws_sdk_elasticloadbalancingv2::types::TargetHealthStateEnum) -> Self {
+        use aws_sdk_elasticloadbalancingv2::types::TargetHealthStateEnum;
+
+        match state {
+            TargetHealthStateEnum::Initial => Self::Initial,
+            TargetHealthStateEnum::Healthy => Self::Healthy,
+            TargetHealthStateEnum::Unhealthy => Self::Unhealthy,
+            TargetHealthStateEnum::UnhealthyDraining => Self::UnhealthyDraining,
+            TargetHealthStateEnum::Unused => Self::Unused,
+            TargetHealthStateEnum::Draining => Self::Draining,
+            TargetHealthStateEnum::Unavailable => Self::Unavailable,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct TargetHealth {
+    pub target: Target,
+    pub state: TargetHealthState,
+    pub reason: Option<String>,
+    pub description: Option<String>,
+}
+
+impl TryFrom<aws_sdk_elasticloadbalancingv2::types::TargetHealthDescription> for TargetHealth {
+    type Error = Error;
+
+    fn try_from(
+        description: aws_sdk_elasticloadbalancingv2::types::TargetHealthDescription,
+    ) -> Result<Self, Self::Error> {
+        let target = description
+            .target
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "TargetHealthDescription.target".to_owned(),
+            })?
+            .try_into()?;
+
+        let health = description.target_health;
+
+        Ok(Self {
+            target,
+            state: health
+                .as_ref()
+                .and_then(|health| health.state.clone())
+                .map_or(TargetHealthState::Unknown, Into::into),
+            reason: health
+                .as_ref()
+                .and_then(|health| health.reason.as_ref())
+                .map(|reason| reason.as_str().to_owned()),
+            description: health.and_then(|health| health.description),
+        })
+    }
+}
+
+impl fmt::Display for TargetHealthState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Self::Initial => "initial",
+                Self::Healthy => "healthy",
+                Self::Unhealthy => "unhealthy",
+                Self::UnhealthyDraining => "unhealthy.draining",
+                Self::Unused => "unused",
+                Self::Draining => "draining",
+                Self::Unavailable => "unavailable",
+                Self::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_elasticloadbalancingv2::types::{
+        LoadBalancerSchemeEnum, LoadBalancerState, LoadBalancerStateEnum, LoadBalancerTypeEnum,
+        TargetDescription, TargetHealthDescription, TargetHealthReasonEnum, TargetHealthStateEnum,
+    };
+
+    use super::*;
+
+    #[test]
+    fn load_balancer_from_aws() {
+        let load_balancer = LoadBalancer::try_from(
+            aws_sdk_elasticloadbalancingv2::types::LoadBalancer::builder()
+                .load_balancer_arn(
+                    "arn:aws:elasticloadbalancing:eu-central-1:123456789012:loadbalancer/app/web/1",
+                )
+                .load_balancer_name("web")
+                .r#type(LoadBalancerTypeEnum::Application)
+                .scheme(LoadBalancerSchemeEnum::InternetFacing)
+                .state(
+                    LoadBalancerState::builder()
+                        .code(LoadBalancerStateEnum::Active)
+                        .build(),
+                )
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(load_balancer.name(), "web");
+        assert_eq!(load_balancer.kind(), Some("application"));
+        assert_eq!(load_balancer.scheme(), Some("internet-facing"));
+        assert_eq!(load_balancer.state(), Some("active"));
+        assert_eq!(load_balancer.dns_name(), None);
+    }
+
+    #[test]
+    fn target_group_port_out_of_range() {
+        let target_group = aws_sdk_elasticloadbalancingv2::types::TargetGroup::builder()
+            .target_group_arn(
+                "arn:aws:elasticloadbalancing:eu-central-1:123456789012:targetgroup/web/1",
+            )
+            .target_group_name("web");
+
+        let parsed = TargetGroup::try_from(target_group.clone().port(8080).build()).unwrap();
+        assert_eq!(parsed.port(), Some(8080));
+
+        assert!(matches!(
+            TargetGroup::try_from(target_group.port(70_000).build()),
+            Err(Error::InvalidResponseError { .. })
+        ));
+    }
+
+    #[test]
+    fn target_round_trip() {
+        let target = Target {
+            id: "i-0123456789abcdef0".to_owned(),
+            port: Some(8080),
+        };
+
+        let description = TargetDescription::from(target.clone());
+        assert_eq!(description.id(), Some("i-0123456789abcdef0"));
+        assert_eq!(description.port(), Some(8080));
+        assert_eq!(Target::try_from(description).unwrap(), target);
+
+        assert!(matches!(
+            Target::try_from(TargetDescription::builder().port(80).build()),
+            Err(Error::UnexpectedNoneValue { .. })
+        ));
+    }
+
+    #[test]
+    fn target_health_from_aws() {
+        let health = TargetHealth::try_from(
+            TargetHealthDescription::builder()
+                .target(TargetDescription::builder().id("10.0.0.1").build())
+                .target_health(
+                    aws_sdk_elasticloadbalancingv2::types::TargetHealth::builder()
+                        .state(TargetHealthStateEnum::Unhealthy)
+                        .reason(TargetHealthReasonEnum::FailedHealthChecks)
+                        .description("Health checks failed")
+                        .build(),
+                )
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(health.target.id, "10.0.0.1");
+        assert_eq!(health.state, TargetHealthState::Unhealthy);
+        assert_eq!(health.state.to_string(), "unhealthy");
+        assert_eq!(health.reason.as_deref(), Some("Target.FailedHealthChecks"));
+        assert_eq!(health.description.as_deref(), Some("Health checks failed"));
+
+        let health = TargetHealth::try_from(
+            TargetHealthDescription::builder()
+                .target(TargetDescription::builder().id("10.0.0.1").build())
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(health.state, TargetHealthState::Unknown);
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        const LOAD_BALANCER_ARN: &str =
+            "arn:aws:elasticloadbalancing:eu-central-1:123456789012:loadbalancer/app/web/50dc6c495c0c9188";
+        const TARGET_GROUP_ARN: &str =
+            "arn:aws:elasticloadbalancing:eu-central-1:123456789012:targetgroup/web/73e2d6bc24d8a067";
+
+        fn load_balancers(next_marker: Option<&str>, name: &str) -> MockResponse {
+            MockResponse::ok(format!(
+                r#"<DescribeLoadBalancersResponse xmlns="http://elasticloadbalancing.amazonaws.com/doc/2015-12-01/">
+      <DescribeLoadBalancersResult>
+        {}
+        <LoadBalancers>
+          <member>
+            <LoadBalancerArn>arn:aws:elasticloadbalancing:eu-central-1:123456789012:loadbalancer/app/{name}/50dc6c495c0c9188</LoadBalancerArn>
+            <LoadBalancerName>{name}</LoadBalancerName>
+            <Type>application</Type>
+            <State><Code>active</Code></State>
+          </member>
+        </LoadBalancers>
+      </DescribeLoadBalancersResult>
+    </DescribeLoadBalancersResponse>"#,
+                next_marker.map_or_else(String::new, |marker| format!(
+                    "<NextMarker>{marker}</NextMarker>"
+                ))
+            ))
+        }
+
+        fn target_health(state: &str) -> MockResponse {
+            MockResponse::ok(format!(
+                r#"<DescribeTargetHealthResponse xmlns="http://elasticloadbalancing.amazonaws.com/doc/2015-12-01/">
+      <DescribeTargetHealthResult>
+        <TargetHealthDescriptions>
+          <member>
+            <Target><Id>i-0123456789abcdef0</Id><Port>80</Port></Target>
+            <TargetHealth><State>{state}</State></TargetHealth>
+          </member>
+        </TargetHealthDescriptions>
+      </DescribeTargetHealthResult>
+    </DescribeTargetHealthResponse>"#
+            ))
+        }
+
+        fn target_group() -> TargetGroup {
+            TargetGroup {
+                arn: TargetGroupArn::parse(TARGET_GROUP_ARN).unwrap(),
+                name: "web".to_owned(),
+                protocol: None,
+                port: None,
+                target_type: None,
+                vpc_id: None,
+                load_balancers: vec![],
+            }
+        }
+
+        #[test]
+        fn list_follows_markers() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("Marker", "page2"),
+                    load_balancers(None, "api"),
+                )
+                .on(
+                    Matcher::action("DescribeLoadBalancers"),
+                    load_balancers(Some("page2"), "web"),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let load_balancers = block_on(LoadBalancer::list(&client)).unwrap();
+
+            assert_eq!(
+                load_balancers
+                    .iter()
+                    .map(LoadBalancer::name)
+                    .collect::<Vec<_>>(),
+                ["web", "api"]
+            );
+            let first = load_balancers.first().unwrap();
+            assert_eq!(first.arn().to_string(), LOAD_BALANCER_ARN);
+            assert_eq!(first.kind(), Some("application"));
+            assert_eq!(first.state(), Some("active"));
+            assert_eq!(http.requests().unwrap().len(), 2);
+        }
+
+        #[test]
+        fn target_groups_of_load_balancer() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeTargetGroups"),
+                MockResponse::ok(format!(
+                    r#"<DescribeTargetGroupsResponse xmlns="http://elasticloadbalancing.amazonaws.com/doc/2015-12-01/">
+      <DescribeTargetGroupsResult>
+        <TargetGroups>
+          <member>
+            <TargetGroupArn>{TARGET_GROUP_ARN}</TargetGroupArn>
+            <TargetGroupName>web</TargetGroupName>
+            <Protocol>HTTP</Protocol>
+            <Port>8080</Port>
+            <LoadBalancerArns><member>{LOAD_BALANCER_ARN}</member></LoadBalancerArns>
+          </member>
+        </TargetGroups>
+      </DescribeTargetGroupsResult>
+    </DescribeTargetGroupsResponse>"#
+                )),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let load_balancer = LoadBalancer::try_from(
+                aws_sdk_elasticloadbalancingv2::types::LoadBalancer::builder()
+                    .load_balancer_arn(LOAD_BALANCER_ARN)
+                    .load_balancer_name("web")
+                    .build(),
+            )
+            .unwrap();
+
+            let target_groups = block_on(load_balancer.target_groups(&client)).unwrap();
+            let target_group = target_groups.first().unwrap();
+
+            assert_eq!(target_group.arn().to_string(), TARGET_GROUP_ARN);
+            assert_eq!(target_group.protocol(), Some("HTTP"));
+            assert_eq!(target_group.port(), Some(8080));
+            assert_eq!(target_group.load_balancers(), [load_balancer.arn().clone()]);
+
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(
+                request.param("LoadBalancerArn").as_deref(),
+                Some(LOAD_BALANCER_ARN)
+            );
+        }
+
+        #[test]
+        fn tags_of_the_requested_arn() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeTags"),
+                MockResponse::ok(format!(
+                    r#"<DescribeTagsResponse xmlns="http://elasticloadbalancing.amazonaws.com/doc/2015-12-01/">
+      <DescribeTagsResult>
+        <TagDescriptions>
+          <member>
+            <ResourceArn>{TARGET_GROUP_ARN}</ResourceArn>
+            <Tags>
+              <member><Key>team</Key><Value>infra</Value></member>
+              <member><Key>env</Key><Value>prod</Value></member>
+            </Tags>
+          </member>
+        </TagDescriptions>
+      </DescribeTagsResult>
+    </DescribeTagsResponse>"#
+                )),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let tags = block_on(target_group().tags(&client)).unwrap();
+
+            assert_eq!(
+                tags.as_slice(),
+                [
+                    RawTag::new("env".to_owned(), "prod".to_owned()),
+                    RawTag::new("team".to_owned(), "infra".to_owned()),
+                ]
+            );
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(
+                request.param("ResourceArns.member.1").as_deref(),
+                Some(TARGET_GROUP_ARN)
+            );
+        }
+
+        #[test]
+        fn wait_for_drain() {
+            let http = MockHttpClient::new()
+                .once(
+                    Matcher::action("DescribeTargetHealth"),
+                    target_health("draining"),
+                )
+                .on(
+                    Matcher::action("DescribeTargetHealth"),
+                    target_health("unused"),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let targets = vec![Target {
+                id: "i-0123456789abcdef0".to_owned(),
+                port: Some(80),
+            }];
+
+            block_on(target_group().wait_for_drain(
+                &client,
+                targets,
+                Duration::ZERO,
+                Duration::from_secs(60),
+            ))
+            .unwrap();
+
+            assert_eq!(http.requests().unwrap().len(), 2);
+        }
+    }
+}
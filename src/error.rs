@@ -1,13 +1,28 @@
-use std::{fmt, net, time::Duration};
+use std::{any::Any, fmt, net, time::Duration};
 
-use crate::tags::{ParseTagError, ParseTagsError};
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+
+use crate::{
+    arn::ParseArnError,
+    dynamodb::ParseItemError,
+    profile::ParseProfileError,
+    protocol::{ParseJsonError, ParseXmlError, RequestMetadata},
+    ssm::ParseConfigError,
+    tags::{ParseTagError, ParseTagsError},
+};
 
 #[derive(Debug)]
 pub enum Error {
     UnexpectedNoneValue {
         entity: String,
     },
-    SdkError(Box<dyn std::error::Error + Send>),
+    /// The error as returned by the SDK, e.g. an `SdkError` of the
+    /// operation, and the metadata of the response if there was one, see
+    /// [`Error::request_metadata()`]
+    SdkError(
+        Box<dyn std::error::Error + Send>,
+        Option<Box<RequestMetadata>>,
+    ),
     InvalidResponseError {
         message: String,
     },
@@ -27,6 +42,118 @@ pub enum Error {
         value: String,
         message: String,
     },
+    InvalidPayload {
+        message: String,
+    },
+    InvalidCidr {
+        value: String,
+        message: String,
+    },
+    InvalidDateRange {
+        start: String,
+        end: String,
+    },
+    InvalidItem(ParseItemError),
+    InvalidConfig(ParseConfigError),
+    InvalidProfile(ParseProfileError),
+    InvalidArn(ParseArnError),
+    InvalidXml(ParseXmlError),
+    InvalidJson(ParseJsonError),
+    InvalidSecret {
+        secret: String,
+        message: String,
+    },
+    Encryption {
+        message: String,
+    },
+    /// `count` items were still unprocessed after the last attempt, and
+    /// `unsent` items of later batches were not sent at all
+    UnprocessedItems {
+        count: usize,
+        unsent: usize,
+    },
+    MultipartUpload {
+        message: String,
+    },
+    Presign {
+        message: String,
+    },
+    Imds {
+        message: String,
+    },
+    EventStream {
+        message: String,
+    },
+    Session {
+        message: String,
+    },
+    Fixture {
+        path: String,
+        message: String,
+    },
+    WaiterTimeout {
+        what: String,
+        timeout: Duration,
+    },
+    WaiterFailure {
+        what: String,
+        reason: String,
+    },
+    EcsFailures {
+        reasons: Vec<String>,
+    },
+    DriftDetectionFailed {
+        stack: String,
+        reason: String,
+    },
+    DriftDetectionExceededMaxWait {
+        stack: String,
+        max_wait: Duration,
+    },
+    TargetDrainExceededMaxWait {
+        target_group: String,
+        max_wait: Duration,
+    },
+    ExecutionExceededMaxWait {
+        execution: String,
+        max_wait: Duration,
+    },
+    QuotaExceeded {
+        quota: String,
+        limit: f64,
+        usage: f64,
+        requested: f64,
+    },
+    InvalidQueryResult {
+        column: String,
+        message: String,
+    },
+    InvalidClientConfig {
+        setting: String,
+        message: String,
+    },
+    Runtime {
+        message: String,
+    },
+    WrongAccount {
+        account: String,
+        partition: String,
+        region: String,
+    },
+    MfaTokenUnavailable {
+        serial: String,
+        message: String,
+    },
+    InvalidBackupSelection {
+        selection: String,
+        message: String,
+    },
+    /// The Resource Groups Tagging API could not tag or untag the resource
+    TaggingFailed {
+        arn: String,
+        error_code: Option<String>,
+        message: Option<String>,
+    },
 }
 
 impl fmt::Display for Error {
@@ -41,7 +168,16 @@ impl fmt::Display for Error {
             Self::UnexpectedNoneValue { ref entity } => {
                 write!(f, "entity \"{entity}\" was empty")
             }
-            Self::SdkError(ref e) => write!(f, "sdk error: {e}"),
+            Self::SdkError(ref e, ref metadata) => {
+                write!(f, "sdk error: {e}")?;
+                if let Some(request_id) = metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.request_id.as_ref())
+                {
+                    write!(f, " (request id {request_id})")?;
+                }
+                Ok(())
+            }
             Self::InvalidResponseError { ref message } => {
                 write!(f, "invalid api response: {message}")
             }
@@ -71,15 +207,219 @@ impl fmt::Display for Error {
             } => {
                 write!(f, "failed parsing \"{value}\" as timestamp: {message}")
             }
+            Self::InvalidCidr {
+                ref value,
+                ref message,
+            } => {
+                write!(f, "failed parsing \"{value}\" as cidr: {message}")
+            }
+            Self::InvalidPayload { ref message } => {
+                write!(f, "invalid payload: {message}")
+            }
+            Self::InvalidDateRange { ref start, ref end } => {
+                write!(f, "invalid date range: {start} is not before {end}")
+            }
+            Self::InvalidItem(ref inner) => {
+                write!(f, "invalid item: {inner}")
+            }
+            Self::InvalidConfig(ref inner) => {
+                write!(f, "invalid config: {inner}")
+            }
+            Self::InvalidProfile(ref inner) => {
+                write!(f, "invalid shared config: {inner}")
+            }
+            Self::InvalidArn(ref inner) => {
+                write!(f, "invalid arn: {inner}")
+            }
+            Self::InvalidXml(ref inner) => {
+                write!(f, "invalid xml response: {inner}")
+            }
+            Self::InvalidJson(ref inner) => {
+                write!(f, "invalid json response: {inner}")
+            }
+            Self::InvalidSecret {
+                ref secret,
+                ref message,
+            } => {
+                write!(f, "invalid value of secret {secret}: {message}")
+            }
+            Self::Encryption { ref message } => {
+                write!(f, "encryption error: {message}")
+            }
+            Self::UnprocessedItems { count, unsent } => {
+                write!(
+                    f,
+                    "{count} items were left unprocessed, {unsent} items were not sent"
+                )
+            }
+            Self::MultipartUpload { ref message } => {
+                write!(f, "multipart upload failed: {message}")
+            }
+            Self::Presign { ref message } => {
+                write!(f, "presigning failed: {message}")
+            }
+            Self::Imds { ref message } => {
+                write!(f, "instance metadata error: {message}")
+            }
+            Self::EventStream { ref message } => {
+                write!(f, "event stream error: {message}")
+            }
+            Self::Session { ref message } => write!(f, "session error: {message}"),
+            Self::Fixture {
+                ref path,
+                ref message,
+            } => {
+                write!(f, "invalid fixture file {path}: {message}")
+            }
+            Self::WaiterTimeout {
+                ref what,
+                ref timeout,
+            } => {
+                write!(
+                    f,
+                    "timed out after {} seconds waiting for {what}",
+                    timeout.as_secs()
+                )
+            }
+            Self::WaiterFailure {
+                ref what,
+                ref reason,
+            } => {
+                write!(f, "waiting for {what} failed: {reason}")
+            }
+            Self::EcsFailures { ref reasons } => {
+                write!(f, "ecs operation failed: {}", reasons.join(", "))
+            }
+            Self::DriftDetectionFailed {
+                ref stack,
+                ref reason,
+            } => {
+                write!(f, "drift detection for stack {stack} failed: {reason}")
+            }
+            Self::DriftDetectionExceededMaxWait {
+                ref stack,
+                ref max_wait,
+            } => {
+                write!(
+                    f,
+                    "drift detection for stack {stack} did not finish in {} seconds",
+                    max_wait.as_secs()
+                )
+            }
+            Self::TargetDrainExceededMaxWait {
+                ref target_group,
+                ref max_wait,
+            } => {
+                write!(
+                    f,
+                    "targets of target group {target_group} did not drain in {} seconds",
+                    max_wait.as_secs()
+                )
+            }
+            Self::ExecutionExceededMaxWait {
+                ref execution,
+                ref max_wait,
+            } => {
+                write!(
+                    f,
+                    "execution {execution} did not finish in {} seconds",
+                    max_wait.as_secs()
+                )
+            }
+            Self::QuotaExceeded {
+                ref quota,
+                limit,
+                usage,
+                requested,
+            } => {
+                write!(
+                    f,
+                    "quota \"{quota}\" of {limit} is not sufficient, {usage} used and {requested} requested"
+                )
+            }
+            Self::InvalidQueryResult {
+                ref column,
+                ref message,
+            } => {
+                write!(f, "invalid value in column \"{column}\": {message}")
+            }
+            Self::InvalidClientConfig {
+                ref setting,
+                ref message,
+            } => {
+                write!(f, "invalid client config for {setting}: {message}")
+            }
+            Self::Runtime { ref message } => {
+                write!(f, "failed to start runtime: {message}")
+            }
+            Self::WrongAccount {
+                ref account,
+                ref partition,
+                ref region,
+            } => {
+                write!(
+                    f,
+                    "refusing to run in account {account} (partition {partition}, region {region}), it is not allowed"
+                )
+            }
+            Self::MfaTokenUnavailable {
+                ref serial,
+                ref message,
+            } => {
+                write!(f, "no MFA code for {serial}: {message}")
+            }
+            Self::InvalidBackupSelection {
+                ref selection,
+                ref message,
+            } => {
+                write!(f, "invalid backup selection {selection}: {message}")
+            }
+            Self::TaggingFailed {
+                ref arn,
+                ref error_code,
+                ref message,
+            } => {
+                write!(
+                    f,
+                    "tagging {arn} failed: {}: {}",
+                    error_code.as_deref().unwrap_or("unknown error"),
+                    message.as_deref().unwrap_or("no message")
+                )
+            }
+        }
+    }
+}
+
+impl Error {
+    /// The request ID and error details of the response that caused the
+    /// error, for errors returned by AWS
+    pub fn request_metadata(&self) -> Option<&RequestMetadata> {
+        match *self {
+            Self::SdkError(_, ref metadata) => metadata.as_deref(),
+            _ => None,
         }
     }
 }
 
 impl std::error::Error for Error {}
 
-impl<T: std::error::Error + Send + 'static> From<aws_sdk_ec2::error::SdkError<T>> for Error {
-    fn from(value: aws_sdk_ec2::error::SdkError<T>) -> Self {
-        Self::SdkError(Box::new(value))
+impl<T, R> From<aws_sdk_ec2::error::SdkError<T, R>> for Error
+where
+    T: std::error::Error + Send + 'static,
+    R: fmt::Debug + Send + 'static,
+{
+    fn from(value: aws_sdk_ec2::error::SdkError<T, R>) -> Self {
+        // All operation errors carry an HTTP response, event stream errors
+        // carry messages instead
+        let metadata = value
+            .raw_response()
+            .and_then(|response| {
+                let response: &dyn Any = response;
+                response.downcast_ref::<HttpResponse>()
+            })
+            .map(RequestMetadata::from_response);
+
+        Self::SdkError(Box::new(value), metadata.map(Box::new))
     }
 }
 
@@ -108,3 +448,39 @@ impl From<ParseTagsError> for Error {
         Self::InvalidTags(value)
     }
 }
+
+impl From<ParseItemError> for Error {
+    fn from(value: ParseItemError) -> Self {
+        Self::InvalidItem(value)
+    }
+}
+
+impl From<ParseConfigError> for Error {
+    fn from(value: ParseConfigError) -> Self {
+        Self::InvalidConfig(value)
+    }
+}
+
+impl From<ParseArnError> for Error {
+    fn from(value: ParseArnError) -> Self {
+        Self::InvalidArn(value)
+    }
+}
+
+impl From<ParseXmlError> for Error {
+    fn from(value: ParseXmlError) -> Self {
+        Self::InvalidXml(value)
+    }
+}
+
+impl From<ParseJsonError> for Error {
+    fn from(value: ParseJsonError) -> Self {
+        Self::InvalidJson(value)
+    }
+}
+
+impl From<ParseProfileError> for Error {
+    fn from(value: ParseProfileError) -> Self {
+        Self::InvalidProfile(value)
+    }
+}
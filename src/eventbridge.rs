@@ -0,0 +1,667 @@
+//! EventBridge events, rules and targets
+//!
+//! Event patterns are built with [`EventPattern`] instead of hand-written
+//! JSON:
+//!
+//! ```
+//! # use aws_lib::eventbridge::{EventPattern, Matcher};
+//! let pattern = EventPattern::new()
+//!     .source(["aws.ec2"])
+//!     .detail_type(["EC2 Instance State-change Notification"])
+//!     .detail(EventPattern::new().field("state", vec![Matcher::exact("running")]));
+//!
+//! assert_eq!(
+//!     pattern.to_string(),
+//!     r#"{"detail":{"state":["running"]},"detail-type":["EC2 Instance State-change Notification"],"source":["aws.ec2"]}"#
+//! );
+//! ```
+
+use std::{collections::BTreeMap, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    batch::batches,
+    tags::{TagKey, TagList},
+    Error, RegionClient, Timestamp,
+};
+
+/// `PutEvents` accepts at most 10 entries per call
+const MAX_BATCH_SIZE: usize = 10;
+
+/// `PutEvents` accepts at most 256 KiB per call, summed over the entries
+const MAX_BATCH_BYTES: usize = 256 * 1024;
+
+string_newtype!(RuleName);
+
+impl RuleName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+arn_newtype!(RuleArn);
+
+string_newtype!(EventId);
+
+/// Writes `value` as a JSON string literal
+fn write_json_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumericOperator {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl NumericOperator {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Equal => "=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// A single condition on a field value. A field matches if any of its
+/// matchers matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Matcher {
+    Exact(String),
+    Prefix(String),
+    Suffix(String),
+    AnythingBut(Vec<String>),
+    Exists(bool),
+    /// All comparisons have to match, e.g. `[(Greater, 0), (LessOrEqual, 5)]`
+    Numeric(Vec<(NumericOperator, i64)>),
+}
+
+impl Matcher {
+    pub fn exact(value: impl Into<String>) -> Self {
+        Self::Exact(value.into())
+    }
+
+    pub fn prefix(value: impl Into<String>) -> Self {
+        Self::Prefix(value.into())
+    }
+
+    pub fn suffix(value: impl Into<String>) -> Self {
+        Self::Suffix(value.into())
+    }
+}
+
+impl fmt::Display for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Exact(ref value) => write_json_string(f, value),
+            Self::Prefix(ref value) => {
+                write!(f, "{{\"prefix\":")?;
+                write_json_string(f, value)?;
+                write!(f, "}}")
+            }
+            Self::Suffix(ref value) => {
+                write!(f, "{{\"suffix\":")?;
+                write_json_string(f, value)?;
+                write!(f, "}}")
+            }
+            Self::AnythingBut(ref values) => {
+                write!(f, "{{\"anything-but\":[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write_json_string(f, value)?;
+                }
+                write!(f, "]}}")
+            }
+            Self::Exists(exists) => write!(f, "{{\"exists\":{exists}}}"),
+            Self::Numeric(ref comparisons) => {
+                write!(f, "{{\"numeric\":[")?;
+                for (i, &(operator, value)) in comparisons.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "\"{}\",{value}", operator.as_str())?;
+                }
+                write!(f, "]}}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternNode {
+    Matchers(Vec<Matcher>),
+    Nested(EventPattern),
+}
+
+/// A typed event pattern. Renders to the JSON expected by EventBridge via
+/// [`Display`](fmt::Display).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventPattern {
+    fields: BTreeMap<String, PatternNode>,
+}
+
+impl EventPattern {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches the field `name` against any of `matchers`
+    #[must_use]
+    pub fn field(mut self, name: impl Into<String>, matchers: Vec<Matcher>) -> Self {
+        let _previous = self
+            .fields
+            .insert(name.into(), PatternNode::Matchers(matchers));
+        self
+    }
+
+    /// Matches the object `name` against another pattern
+    #[must_use]
+    pub fn nested(mut self, name: impl Into<String>, pattern: Self) -> Self {
+        let _previous = self
+            .fields
+            .insert(name.into(), PatternNode::Nested(pattern));
+        self
+    }
+
+    fn exact_values<I, S>(self, name: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.field(name, values.into_iter().map(Matcher::exact).collect())
+    }
+
+    #[must_use]
+    pub fn source<I, S>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exact_values("source", values)
+    }
+
+    #[must_use]
+    pub fn detail_type<I, S>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exact_values("detail-type", values)
+    }
+
+    #[must_use]
+    pub fn account<I, S>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exact_values("account", values)
+    }
+
+    #[must_use]
+    pub fn region<I, S>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exact_values("region", values)
+    }
+
+    #[must_use]
+    pub fn resources<I, S>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exact_values("resources", values)
+    }
+
+    #[must_use]
+    pub fn detail(self, pattern: Self) -> Self {
+        self.nested("detail", pattern)
+    }
+}
+
+impl fmt::Display for EventPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (name, node)) in self.fields.iter().enumerate() {
+            if i != 0 {
+                write!(f, ",")?;
+            }
+            write_json_string(f, name)?;
+            write!(f, ":")?;
+            match *node {
+                PatternNode::Matchers(ref matchers) => {
+                    write!(f, "[")?;
+                    for (j, matcher) in matchers.iter().enumerate() {
+                        if j != 0 {
+                            write!(f, ",")?;
+                        }
+                        write!(f, "{matcher}")?;
+                    }
+                    write!(f, "]")?;
+                }
+                PatternNode::Nested(ref pattern) => write!(f, "{pattern}")?,
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateUnit {
+    Minutes,
+    Hours,
+    Days,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    Rate {
+        value: u32,
+        unit: RateUnit,
+    },
+    /// The six cron fields, e.g. `0 12 * * ? *`
+    Cron(String),
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Rate { value, unit } => {
+                // EventBridge rejects "1 minutes" as well as "5 minute"
+                let unit = match (unit, value == 1) {
+                    (RateUnit::Minutes, true) => "minute",
+                    (RateUnit::Minutes, false) => "minutes",
+                    (RateUnit::Hours, true) => "hour",
+                    (RateUnit::Hours, false) => "hours",
+                    (RateUnit::Days, true) => "day",
+                    (RateUnit::Days, false) => "days",
+                };
+                write!(f, "rate({value} {unit})")
+            }
+            Self::Cron(ref expression) => write!(f, "cron({expression})"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RuleTrigger {
+    Schedule(Schedule),
+    Pattern(EventPattern),
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleConfig {
+    pub name: RuleName,
+    pub description: Option<String>,
+    /// `None` means the default event bus
+    pub event_bus: Option<String>,
+    pub trigger: RuleTrigger,
+    pub enabled: bool,
+    pub tags: TagList,
+}
+
+/// Creates or updates a rule
+pub async fn put_rule(client: &RegionClient, config: RuleConfig) -> Result<RuleArn, Error> {
+    let (schedule, pattern) = match config.trigger {
+        RuleTrigger::Schedule(schedule) => (Some(schedule.to_string()), None),
+        RuleTrigger::Pattern(pattern) => (None, Some(pattern.to_string())),
+    };
+
+    Ok(RuleArn::parse(
+        &client
+            .main
+            .eventbridge
+            .put_rule()
+            .name(config.name.as_str())
+            .set_description(config.description)
+            .set_event_bus_name(config.event_bus)
+            .set_schedule_expression(schedule)
+            .set_event_pattern(pattern)
+            .state(if config.enabled {
+                aws_sdk_eventbridge::types::RuleState::Enabled
+            } else {
+                aws_sdk_eventbridge::types::RuleState::Disabled
+            })
+            .set_tags(Some(config.tags.into()))
+            .send()
+            .await?
+            .rule_arn
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "PutRuleOutput.rule_arn".to_owned(),
+            })?,
+    )?)
+}
+
+#[derive(Debug, Clone)]
+pub struct Target {
+    /// Unique per rule
+    pub id: String,
+    pub arn: String,
+    /// Constant JSON passed to the target instead of the event
+    pub input: Option<String>,
+    pub role_arn: Option<String>,
+}
+
+impl From<Target> for aws_sdk_eventbridge::types::Target {
+    fn from(target: Target) -> Self {
+        Self::builder()
+            .id(target.id)
+            .arn(target.arn)
+            .set_input(target.input)
+            .set_role_arn(target.role_arn)
+            .build()
+            .expect("builder misused")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TargetFailure {
+    pub target_id: String,
+    pub code: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Adds or updates targets of a rule, returning the targets that could not
+/// be added
+pub async fn put_targets(
+    client: &RegionClient,
+    rule: &RuleName,
+    event_bus: Option<&str>,
+    targets: Vec<Target>,
+) -> Result<Vec<TargetFailure>, Error> {
+    Ok(client
+        .main
+        .eventbridge
+        .put_targets()
+        .rule(rule.as_str())
+        .set_event_bus_name(event_bus.map(ToOwned::to_owned))
+        .set_targets(Some(targets.into_iter().map(Into::into).collect()))
+        .send()
+        .await?
+        .failed_entries
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| TargetFailure {
+            target_id: entry.target_id.unwrap_or_default(),
+            code: entry.error_code,
+            message: entry.error_message,
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub source: String,
+    pub detail_type: String,
+    /// JSON object
+    pub detail: String,
+    pub resources: Vec<String>,
+    pub time: Option<Timestamp>,
+    /// `None` means the default event bus
+    pub event_bus: Option<String>,
+}
+
+impl From<Event> for aws_sdk_eventbridge::types::PutEventsRequestEntry {
+    fn from(event: Event) -> Self {
+        Self::builder()
+            .source(event.source)
+            .detail_type(event.detail_type)
+            .detail(event.detail)
+            .set_resources(Some(event.resources))
+            .set_time(event.time.map(Into::into))
+            .set_event_bus_name(event.event_bus)
+            .build()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchFailure {
+    /// Position of the event in the list passed to [`put_events()`]
+    pub index: usize,
+    pub code: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchPutResult {
+    pub sent: Vec<EventId>,
+    pub failed: Vec<BatchFailure>,
+}
+
+/// The size of an entry as counted against [`MAX_BATCH_BYTES`]: the time
+/// counts as 14 bytes, all other fields with the length of their UTF-8
+/// encoding. The event bus is not counted.
+fn entry_size(event: &Event) -> usize {
+    let time = if event.time.is_some() { 14 } else { 0 };
+
+    [
+        time,
+        event.source.len(),
+        event.detail_type.len(),
+        event.detail.len(),
+    ]
+    .into_iter()
+    .chain(event.resources.iter().map(String::len))
+    .fold(0, usize::saturating_add)
+}
+
+/// Sends events in batches of at most 10 events and 256 KiB
+///
+/// Events that EventBridge rejected are reported in
+/// [`BatchPutResult::failed`] instead, as are events larger than 256 KiB,
+/// which cannot be sent at all.
+pub async fn put_events(
+    client: &RegionClient,
+    events: Vec<Event>,
+) -> Result<BatchPutResult, Error> {
+    let mut result = BatchPutResult::default();
+
+    let (events, too_large): (Vec<_>, Vec<_>) = events
+        .into_iter()
+        .map(|event| (entry_size(&event), event))
+        .enumerate()
+        .partition(|&(_, (size, _))| size <= MAX_BATCH_BYTES);
+
+    result.failed.extend(
+        too_large
+            .into_iter()
+            .map(|(index, (size, _))| BatchFailure {
+                index,
+                code: None,
+                message: Some(format!(
+                    "event has {size} bytes, at most {MAX_BATCH_BYTES} are allowed"
+                )),
+            }),
+    );
+
+    let sizes: Vec<usize> = events.iter().map(|&(_, (size, _))| size).collect();
+
+    let mut events = events.into_iter();
+    for batch in batches(&sizes, MAX_BATCH_SIZE, MAX_BATCH_BYTES) {
+        let (indices, entries): (
+            Vec<usize>,
+            Vec<aws_sdk_eventbridge::types::PutEventsRequestEntry>,
+        ) = events
+            .by_ref()
+            .take(batch.len())
+            .map(|(index, (_, event))| (index, event.into()))
+            .unzip();
+
+        let output = client
+            .main
+            .eventbridge
+            .put_events()
+            .set_entries(Some(entries))
+            .send()
+            .await?;
+
+        // Result entries are in the same order as the request entries
+        for (index, entry) in indices.into_iter().zip(output.entries.unwrap_or_default()) {
+            match entry.event_id {
+                Some(id) if entry.error_code.is_none() => result.sent.push(EventId(id)),
+                _ => result.failed.push(BatchFailure {
+                    index,
+                    code: entry.error_code,
+                    message: entry.error_message,
+                }),
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+pub async fn rule_tags(client: &RegionClient, rule: &RuleArn) -> Result<TagList, Error> {
+    Ok(client
+        .main
+        .eventbridge
+        .list_tags_for_resource()
+        .resource_arn(rule.to_string())
+        .send()
+        .await?
+        .tags
+        .unwrap_or_default()
+        .try_into()?)
+}
+
+pub async fn add_rule_tags(
+    client: &RegionClient,
+    rule: &RuleArn,
+    tags: TagList,
+) -> Result<(), Error> {
+    let _output = client
+        .main
+        .eventbridge
+        .tag_resource()
+        .resource_arn(rule.to_string())
+        .set_tags(Some(tags.into()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+pub async fn remove_rule_tags(
+    client: &RegionClient,
+    rule: &RuleArn,
+    keys: Vec<TagKey>,
+) -> Result<(), Error> {
+    let _output = client
+        .main
+        .eventbridge
+        .untag_resource()
+        .resource_arn(rule.to_string())
+        .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_pattern() {
+        let pattern = EventPattern::new().source(["my.app"]).detail(
+            EventPattern::new()
+                .field(
+                    "status",
+                    vec![
+                        Matcher::prefix("fail"),
+                        Matcher::AnythingBut(vec!["ok".to_owned(), "skipped".to_owned()]),
+                    ],
+                )
+                .field(
+                    "count",
+                    vec![Matcher::Numeric(vec![
+                        (NumericOperator::Greater, 0),
+                        (NumericOperator::LessOrEqual, 5),
+                    ])],
+                )
+                .field("error", vec![Matcher::Exists(true)])
+                .field("name", vec![Matcher::exact("quote\"d")]),
+        );
+
+        assert_eq!(
+            pattern.to_string(),
+            concat!(
+                r#"{"detail":{"count":[{"numeric":[">",0,"<=",5]}],"error":[{"exists":true}],"#,
+                r#""name":["quote\"d"],"status":[{"prefix":"fail"},{"anything-but":["ok","skipped"]}]},"#,
+                r#""source":["my.app"]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn render_schedule() {
+        assert_eq!(
+            Schedule::Rate {
+                value: 1,
+                unit: RateUnit::Hours
+            }
+            .to_string(),
+            "rate(1 hour)"
+        );
+        assert_eq!(
+            Schedule::Rate {
+                value: 5,
+                unit: RateUnit::Minutes
+            }
+            .to_string(),
+            "rate(5 minutes)"
+        );
+        assert_eq!(
+            Schedule::Cron("0 12 * * ? *".to_owned()).to_string(),
+            "cron(0 12 * * ? *)"
+        );
+    }
+    #[test]
+    fn event_size() {
+        let event = Event {
+            source: "app".to_owned(),
+            detail_type: "Deployed".to_owned(),
+            detail: "{}".to_owned(),
+            resources: vec!["arn:a".to_owned(), "arn:b".to_owned()],
+            time: None,
+            event_bus: Some("ignored".to_owned()),
+        };
+        assert_eq!(entry_size(&event), 3 + 8 + 2 + 5 + 5);
+
+        let event = Event {
+            time: Some(Timestamp::now()),
+            ..event
+        };
+        assert_eq!(entry_size(&event), 14 + 3 + 8 + 2 + 5 + 5);
+    }
+}
@@ -0,0 +1,517 @@
+//! Decoding of `application/vnd.amazon.eventstream` bodies
+//!
+//! Some operations, e.g. S3 Select, Lambda response streaming and
+//! Transcribe, respond with a stream of binary messages instead of a single
+//! document. Each message consists of a prelude with the lengths, a list of
+//! typed headers, the payload and CRC32 checksums over prelude and message.
+//!
+//! [`MessageDecoder`] splits raw bytes into [`Message`]s, [`EventStream`]
+//! does the same for a response body and turns the messages into typed
+//! events via the [`Event`] trait.
+
+use std::marker::PhantomData;
+
+use aws_smithy_types::byte_stream::ByteStream;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
+
+use super::Error;
+
+/// Total length, headers length and prelude CRC, 4 bytes each
+const PRELUDE_LENGTH: usize = 12;
+
+/// The CRC over the whole message
+const CRC_LENGTH: usize = 4;
+
+/// Messages are limited to 16 MiB payload plus 128 KiB headers
+const MAX_MESSAGE_LENGTH: usize = 16 * 1024 * 1024 + 128 * 1024 + PRELUDE_LENGTH + CRC_LENGTH;
+
+/// CRC32 with the IEEE polynomial, as used by zlib and Ethernet
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8_u8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1_u32) ^ 0xEDB8_8320
+            } else {
+                crc >> 1_u32
+            };
+        }
+    }
+    !crc
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::EventStream {
+        message: message.into(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderValue {
+    Bool(bool),
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    ByteArray(Bytes),
+    String(String),
+    Timestamp(DateTime<Utc>),
+    Uuid([u8; 16]),
+}
+
+impl HeaderValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Self::String(ref value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub name: String,
+    pub value: HeaderValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub headers: Vec<Header>,
+    pub payload: Bytes,
+}
+
+impl Message {
+    pub fn header(&self, name: &str) -> Option<&HeaderValue> {
+        self.headers
+            .iter()
+            .find(|header| header.name == name)
+            .map(|header| &header.value)
+    }
+
+    /// `event`, `exception` or `error`
+    pub fn message_type(&self) -> Option<&str> {
+        self.header(":message-type").and_then(HeaderValue::as_str)
+    }
+
+    /// The name of the event for messages of type `event`
+    pub fn event_type(&self) -> Option<&str> {
+        self.header(":event-type").and_then(HeaderValue::as_str)
+    }
+
+    /// Turns `exception` and `error` messages into errors
+    pub fn into_event(self) -> Result<Self, Error> {
+        match self.message_type() {
+            Some("exception") => Err(invalid(format!(
+                "{}: {}",
+                self.header(":exception-type")
+                    .and_then(HeaderValue::as_str)
+                    .unwrap_or("unknown exception"),
+                String::from_utf8_lossy(&self.payload)
+            ))),
+            Some("error") => Err(invalid(format!(
+                "{}: {}",
+                self.header(":error-code")
+                    .and_then(HeaderValue::as_str)
+                    .unwrap_or("unknown error"),
+                self.header(":error-message")
+                    .and_then(HeaderValue::as_str)
+                    .unwrap_or_default()
+            ))),
+            _ => Ok(self),
+        }
+    }
+
+    /// Decodes a single complete message
+    pub fn decode(frame: &Bytes) -> Result<Self, Error> {
+        let (without_crc, message_crc) = frame
+            .split_last_chunk::<CRC_LENGTH>()
+            .ok_or_else(|| invalid("message is too short"))?;
+        if crc32(without_crc) != u32::from_be_bytes(*message_crc) {
+            return Err(invalid("message checksum mismatch"));
+        }
+
+        let mut reader = Reader { data: without_crc };
+        let prelude = reader.array::<8>()?;
+        let prelude_crc = reader.u32()?;
+        if crc32(&prelude) != prelude_crc {
+            return Err(invalid("prelude checksum mismatch"));
+        }
+
+        let [t0, t1, t2, t3, h0, h1, h2, h3] = prelude;
+        let total_length = length(u32::from_be_bytes([t0, t1, t2, t3]))?;
+        let headers_length = length(u32::from_be_bytes([h0, h1, h2, h3]))?;
+        if total_length != frame.len() {
+            return Err(invalid(format!(
+                "message length is {}, but prelude says {total_length}",
+                frame.len()
+            )));
+        }
+
+        let mut headers_reader = Reader {
+            data: reader.slice(headers_length)?,
+        };
+        let mut headers = Vec::new();
+        while !headers_reader.data.is_empty() {
+            headers.push(headers_reader.header()?);
+        }
+
+        Ok(Self {
+            headers,
+            payload: frame.slice_ref(reader.data),
+        })
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut headers = Vec::new();
+        for header in &self.headers {
+            headers.push(
+                u8::try_from(header.name.len())
+                    .map_err(|e| invalid(format!("header name too long: {e}")))?,
+            );
+            headers.extend_from_slice(header.name.as_bytes());
+            match header.value {
+                HeaderValue::Bool(true) => headers.push(0),
+                HeaderValue::Bool(false) => headers.push(1),
+                HeaderValue::Byte(value) => {
+                    headers.push(2);
+                    headers.extend_from_slice(&value.to_be_bytes());
+                }
+                HeaderValue::Short(value) => {
+                    headers.push(3);
+                    headers.extend_from_slice(&value.to_be_bytes());
+                }
+                HeaderValue::Int(value) => {
+                    headers.push(4);
+                    headers.extend_from_slice(&value.to_be_bytes());
+                }
+                HeaderValue::Long(value) => {
+                    headers.push(5);
+                    headers.extend_from_slice(&value.to_be_bytes());
+                }
+                HeaderValue::ByteArray(ref value) => {
+                    headers.push(6);
+                    headers.extend_from_slice(&short_length(value.len())?);
+                    headers.extend_from_slice(value);
+                }
+                HeaderValue::String(ref value) => {
+                    headers.push(7);
+                    headers.extend_from_slice(&short_length(value.len())?);
+                    headers.extend_from_slice(value.as_bytes());
+                }
+                HeaderValue::Timestamp(ref value) => {
+                    headers.push(8);
+                    headers.extend_from_slice(&value.timestamp_millis().to_be_bytes());
+                }
+                HeaderValue::Uuid(ref value) => {
+                    headers.push(9);
+                    headers.extend_from_slice(value);
+                }
+            }
+        }
+
+        let total_length = PRELUDE_LENGTH
+            .checked_add(headers.len())
+            .and_then(|length| length.checked_add(self.payload.len()))
+            .and_then(|length| length.checked_add(CRC_LENGTH))
+            .filter(|&length| length <= MAX_MESSAGE_LENGTH)
+            .ok_or_else(|| invalid("message too long"))?;
+
+        let mut message = Vec::with_capacity(total_length);
+        message.extend_from_slice(
+            &u32::try_from(total_length)
+                .map_err(|e| invalid(format!("message too long: {e}")))?
+                .to_be_bytes(),
+        );
+        message.extend_from_slice(
+            &u32::try_from(headers.len())
+                .map_err(|e| invalid(format!("headers too long: {e}")))?
+                .to_be_bytes(),
+        );
+        message.extend_from_slice(&crc32(&message).to_be_bytes());
+        message.extend_from_slice(&headers);
+        message.extend_from_slice(&self.payload);
+        message.extend_from_slice(&crc32(&message).to_be_bytes());
+
+        Ok(message)
+    }
+}
+
+fn length(value: u32) -> Result<usize, Error> {
+    usize::try_from(value).map_err(|e| invalid(format!("invalid length {value}: {e}")))
+}
+
+fn short_length(value: usize) -> Result<[u8; 2], Error> {
+    u16::try_from(value)
+        .map(u16::to_be_bytes)
+        .map_err(|e| invalid(format!("header value too long: {e}")))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let (head, rest) = self
+            .data
+            .split_first_chunk::<N>()
+            .ok_or_else(|| invalid("unexpected end of message"))?;
+        self.data = rest;
+        Ok(*head)
+    }
+
+    fn slice(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let (head, rest) = self
+            .data
+            .split_at_checked(length)
+            .ok_or_else(|| invalid("unexpected end of message"))?;
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        self.array::<1>().map(u8::from_be_bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        self.array::<2>().map(u16::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        self.array::<4>().map(u32::from_be_bytes)
+    }
+
+    fn header(&mut self) -> Result<Header, Error> {
+        let name_length = usize::from(self.u8()?);
+        let name = String::from_utf8(self.slice(name_length)?.to_vec())
+            .map_err(|e| invalid(format!("invalid header name: {e}")))?;
+
+        let value = match self.u8()? {
+            0 => HeaderValue::Bool(true),
+            1 => HeaderValue::Bool(false),
+            2 => HeaderValue::Byte(i8::from_be_bytes(self.array()?)),
+            3 => HeaderValue::Short(i16::from_be_bytes(self.array()?)),
+            4 => HeaderValue::Int(i32::from_be_bytes(self.array()?)),
+            5 => HeaderValue::Long(i64::from_be_bytes(self.array()?)),
+            6 => {
+                let length = usize::from(self.u16()?);
+                HeaderValue::ByteArray(Bytes::copy_from_slice(self.slice(length)?))
+            }
+            7 => {
+                let length = usize::from(self.u16()?);
+                HeaderValue::String(
+                    String::from_utf8(self.slice(length)?.to_vec())
+                        .map_err(|e| invalid(format!("invalid value of header {name}: {e}")))?,
+                )
+            }
+            8 => HeaderValue::Timestamp(
+                DateTime::from_timestamp_millis(i64::from_be_bytes(self.array()?))
+                    .ok_or_else(|| invalid(format!("timestamp of header {name} out of range")))?,
+            ),
+            9 => HeaderValue::Uuid(self.array()?),
+            value_type => {
+                return Err(invalid(format!(
+                    "unknown type {value_type} of header {name}"
+                )))
+            }
+        };
+
+        Ok(Header { name, value })
+    }
+}
+
+/// Splits a byte stream into messages. Data can be pushed in chunks of any
+/// size.
+#[derive(Debug, Default)]
+pub struct MessageDecoder {
+    buffer: BytesMut,
+}
+
+impl MessageDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next complete message, or `None` if more data is needed
+    pub fn next_message(&mut self) -> Result<Option<Message>, Error> {
+        let Some(&total_length) = self.buffer.first_chunk::<4>() else {
+            return Ok(None);
+        };
+        let total_length = length(u32::from_be_bytes(total_length))?;
+
+        if !(PRELUDE_LENGTH.saturating_add(CRC_LENGTH)..=MAX_MESSAGE_LENGTH).contains(&total_length)
+        {
+            return Err(invalid(format!("invalid message length {total_length}")));
+        }
+
+        if self.buffer.len() < total_length {
+            return Ok(None);
+        }
+
+        Message::decode(&self.buffer.split_to(total_length).freeze()).map(Some)
+    }
+
+    /// Whether there is no partial message left
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+/// A typed event decoded from a message
+pub trait Event: Sized {
+    fn from_message(message: Message) -> Result<Self, Error>;
+}
+
+impl Event for Message {
+    fn from_message(message: Message) -> Result<Self, Error> {
+        Ok(message)
+    }
+}
+
+/// The events of a response body. `exception` and `error` messages are
+/// returned as [`Error::EventStream`].
+#[derive(Debug)]
+pub struct EventStream<T> {
+    body: ByteStream,
+    decoder: MessageDecoder,
+    event: PhantomData<T>,
+}
+
+impl<T: Event> EventStream<T> {
+    pub fn new(body: ByteStream) -> Self {
+        Self {
+            body,
+            decoder: MessageDecoder::new(),
+            event: PhantomData,
+        }
+    }
+
+    /// Returns the next event, or `None` when the stream has ended
+    pub async fn next_event(&mut self) -> Option<Result<T, Error>> {
+        loop {
+            match self.decoder.next_message() {
+                Ok(Some(message)) => return Some(message.into_event().and_then(T::from_message)),
+                Ok(None) => (),
+                Err(e) => return Some(Err(e)),
+            }
+
+            match self.body.next().await {
+                Some(Ok(chunk)) => self.decoder.push(&chunk),
+                Some(Err(e)) => return Some(Err(Error::SdkError(Box::new(e), None))),
+                None if self.decoder.is_empty() => return None,
+                None => {
+                    self.decoder = MessageDecoder::new();
+                    return Some(Err(invalid("stream ended in the middle of a message")));
+                }
+            }
+        }
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<T, Error>> {
+        futures_util::stream::unfold(self, |mut stream| async move {
+            stream.next_event().await.map(|event| (event, stream))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> Message {
+        Message {
+            headers: vec![
+                Header {
+                    name: ":message-type".to_owned(),
+                    value: HeaderValue::String("event".to_owned()),
+                },
+                Header {
+                    name: ":event-type".to_owned(),
+                    value: HeaderValue::String("Records".to_owned()),
+                },
+                Header {
+                    name: "flag".to_owned(),
+                    value: HeaderValue::Bool(false),
+                },
+                Header {
+                    name: "count".to_owned(),
+                    value: HeaderValue::Long(-3),
+                },
+                Header {
+                    name: "at".to_owned(),
+                    value: HeaderValue::Timestamp(
+                        DateTime::from_timestamp_millis(1_700_000_000_123).unwrap(),
+                    ),
+                },
+            ],
+            payload: Bytes::from_static(b"a,b\n"),
+        }
+    }
+
+    #[test]
+    fn checksum() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn roundtrip_in_chunks() {
+        let encoded = [message().encode().unwrap(), message().encode().unwrap()].concat();
+
+        let mut decoder = MessageDecoder::new();
+        let mut messages = Vec::new();
+        for chunk in encoded.chunks(7) {
+            decoder.push(chunk);
+            while let Some(message) = decoder.next_message().unwrap() {
+                messages.push(message);
+            }
+        }
+
+        assert!(decoder.is_empty(), "all data must be consumed");
+        assert_eq!(messages, vec![message(), message()]);
+        assert_eq!(messages.first().unwrap().event_type(), Some("Records"));
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut encoded = message().encode().unwrap();
+        if let Some(byte) = encoded.get_mut(20) {
+            *byte ^= 0xFF;
+        }
+
+        let mut decoder = MessageDecoder::new();
+        decoder.push(&encoded);
+        assert!(
+            decoder.next_message().is_err(),
+            "corrupted message must be rejected"
+        );
+    }
+
+    #[test]
+    fn exception() {
+        let exception = Message {
+            headers: vec![
+                Header {
+                    name: ":message-type".to_owned(),
+                    value: HeaderValue::String("exception".to_owned()),
+                },
+                Header {
+                    name: ":exception-type".to_owned(),
+                    value: HeaderValue::String("ThrottlingException".to_owned()),
+                },
+            ],
+            payload: Bytes::from_static(b"slow down"),
+        };
+
+        assert!(matches!(
+            exception.into_event(),
+            Err(Error::EventStream { ref message }) if message == "ThrottlingException: slow down"
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+//! Typed filters for EC2 and Auto Scaling describe calls
+//!
+//! ```rust
+//! use aws_lib::{export::ec2::InstanceStateName, filter::Filter, tags::Tags};
+//!
+//! #[Tags]
+//! struct MyTags {
+//!     env: String,
+//!     team: Option<String>,
+//! }
+//!
+//! let filters = [
+//!     Filter::tag("env").eq("prod"),
+//!     Filter::tag("team").any_of(["infra", "platform"]),
+//!     Filter::tag_keys(MyTags::KEYS),
+//!     Filter::instance_state(&InstanceStateName::Running),
+//! ];
+//! ```
+
+use super::{tags::TagKey, InstanceId};
+
+/// A filter on the value of a single tag. Created by [`Filter::tag()`].
+#[derive(Debug, Clone)]
+pub struct TagFilter {
+    key: String,
+}
+
+impl TagFilter {
+    pub fn eq(self, value: impl Into<String>) -> Filter {
+        Filter::new(format!("tag:{}", self.key), [value.into()])
+    }
+
+    /// Matches if the tag has any of the values
+    pub fn any_of(self, values: impl IntoIterator<Item = impl Into<String>>) -> Filter {
+        Filter::new(format!("tag:{}", self.key), values)
+    }
+}
+
+/// A filter for describe calls. Multiple values of one filter are combined
+/// with OR, multiple filters with AND.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    name: String,
+    values: Vec<String>,
+}
+
+impl Filter {
+    /// For filters that have no typed constructor. See the AWS documentation
+    /// of the describe call for the valid names.
+    pub fn new(
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn tag(key: impl Into<String>) -> TagFilter {
+        TagFilter { key: key.into() }
+    }
+
+    /// Matches resources that have a tag with the key, regardless of its value
+    pub fn tag_key(key: &TagKey) -> Self {
+        Self::new("tag-key", [key.as_str()])
+    }
+
+    /// Matches resources that have a tag with any of the keys. Use with the
+    /// `KEYS` constant generated by [`Tags`](super::tags::Tags).
+    pub fn tag_keys(keys: &[&str]) -> Self {
+        Self::new("tag-key", keys.iter().copied())
+    }
+
+    /// Matches resources that have a tag with the value, regardless of its key
+    pub fn tag_value(value: impl Into<String>) -> Self {
+        Self::new("tag-value", [value.into()])
+    }
+
+    /// EC2 only
+    pub fn instance_state(state: &aws_sdk_ec2::types::InstanceStateName) -> Self {
+        Self::new("instance-state-name", [state.as_str()])
+    }
+
+    /// EC2 only
+    pub fn instance_id(id: &InstanceId) -> Self {
+        Self::new("instance-id", [id.as_str()])
+    }
+
+    /// EC2 only
+    pub fn availability_zone(zone: impl Into<String>) -> Self {
+        Self::new("availability-zone", [zone.into()])
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+}
+
+impl From<Filter> for aws_sdk_ec2::types::Filter {
+    fn from(filter: Filter) -> Self {
+        Self::builder()
+            .name(filter.name)
+            .set_values(Some(filter.values))
+            .build()
+    }
+}
+
+impl From<Filter> for aws_sdk_autoscaling::types::Filter {
+    fn from(filter: Filter) -> Self {
+        Self::builder()
+            .name(filter.name)
+            .set_values(Some(filter.values))
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            Filter::tag("env").eq("prod"),
+            Filter::new("tag:env", ["prod"])
+        );
+        assert_eq!(
+            Filter::tag("env").any_of(["dev", "test"]).values(),
+            ["dev".to_owned(), "test".to_owned()]
+        );
+        assert_eq!(
+            Filter::instance_state(&aws_sdk_ec2::types::InstanceStateName::Running),
+            Filter::new("instance-state-name", ["running"])
+        );
+        assert_eq!(
+            Filter::tag_keys(&["env", "team"]),
+            Filter::new("tag-key", ["env", "team"])
+        );
+    }
+}
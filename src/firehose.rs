@@ -0,0 +1,358 @@
+//! Firehose delivery streams
+//!
+//! Batching and retries work like for Kinesis, see [`super::kinesis`].
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::kinesis::{put_batched, PutConfig, PutReport, Rejection};
+use super::{
+    tags::{TagKey, TagList},
+    Error, RegionClient,
+};
+
+/// Limits of `PutRecordBatch`
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_RECORDS: usize = 500;
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_REQUEST_BYTES: usize = 4 * 1024 * 1024;
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_RECORD_BYTES: usize = 1000 * 1024;
+
+string_newtype!(DeliveryStreamName);
+
+impl DeliveryStreamName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct DeliveryStream {
+    name: DeliveryStreamName,
+}
+
+impl DeliveryStream {
+    pub const fn new(name: DeliveryStreamName) -> Self {
+        Self { name }
+    }
+
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        let mut streams = vec![];
+        let mut start: Option<String> = None;
+
+        loop {
+            let output = client
+                .main
+                .firehose
+                .list_delivery_streams()
+                .set_exclusive_start_delivery_stream_name(start)
+                .send()
+                .await?;
+
+            start = output.delivery_stream_names.last().cloned();
+            streams.extend(
+                output
+                    .delivery_stream_names
+                    .into_iter()
+                    .map(|name| Self::new(DeliveryStreamName(name))),
+            );
+
+            if !output.has_more_delivery_streams || start.is_none() {
+                break;
+            }
+        }
+
+        Ok(streams)
+    }
+
+    pub const fn name(&self) -> &DeliveryStreamName {
+        &self.name
+    }
+
+    /// Returns the ID of the record
+    pub async fn put_record(&self, client: &RegionClient, data: Vec<u8>) -> Result<String, Error> {
+        Ok(client
+            .main
+            .firehose
+            .put_record()
+            .delivery_stream_name(self.name.as_str())
+            .record(record(data))
+            .send()
+            .await?
+            .record_id)
+    }
+
+    /// Puts any number of records, splitting them into requests below the
+    /// limits of `PutRecordBatch` and retrying rejected records
+    ///
+    /// Firehose does not add delimiters between records, so e.g. for JSON
+    /// lines each record has to end with a newline.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn put_records(
+        &self,
+        client: &RegionClient,
+        records: Vec<Vec<u8>>,
+        config: &PutConfig,
+    ) -> Result<PutReport<Vec<u8>>, Error> {
+        put_batched(
+            records,
+            Vec::len,
+            (MAX_RECORDS, MAX_REQUEST_BYTES, MAX_RECORD_BYTES),
+            config,
+            |batch| {
+                // Built outside of the future, which must not borrow the batch
+                let records: Vec<_> = batch.iter().cloned().map(record).collect();
+
+                async move {
+                    Ok(client
+                        .main
+                        .firehose
+                        .put_record_batch()
+                        .delivery_stream_name(self.name.as_str())
+                        .set_records(Some(records))
+                        .send()
+                        .await?
+                        .request_responses
+                        .into_iter()
+                        .map(|entry| {
+                            entry.error_code.map(|error_code| Rejection {
+                                error_code,
+                                error_message: entry.error_message.unwrap_or_default(),
+                            })
+                        })
+                        .collect())
+                }
+            },
+        )
+        .await
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        let mut tags = vec![];
+        let mut start = None;
+
+        loop {
+            let output = client
+                .main
+                .firehose
+                .list_tags_for_delivery_stream()
+                .delivery_stream_name(self.name.as_str())
+                .set_exclusive_start_tag_key(start)
+                .send()
+                .await?;
+
+            start = output.tags.last().map(|tag| tag.key.clone());
+            tags.extend(output.tags);
+
+            if !output.has_more_tags || start.is_none() {
+                break;
+            }
+        }
+
+        Ok(tags.try_into()?)
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .firehose
+            .tag_delivery_stream()
+            .delivery_stream_name(self.name.as_str())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .firehose
+            .untag_delivery_stream()
+            .delivery_stream_name(self.name.as_str())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn record(data: Vec<u8>) -> aws_sdk_firehose::types::Record {
+    aws_sdk_firehose::types::Record::builder()
+        .data(aws_sdk_firehose::primitives::Blob::new(data))
+        .build()
+        .expect("builder misused")
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use super::*;
+    use crate::{
+        kinesis::FailedRecord,
+        tags::RawTag,
+        testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse, Request},
+        waiter::Backoff,
+        Region,
+    };
+
+    const REJECTED: &str = r#"{"FailedPutCount": 1, "RequestResponses": [
+        {"RecordId": "record-a"},
+        {"ErrorCode": "ServiceUnavailableException", "ErrorMessage": "slow down"}
+    ]}"#;
+
+    fn stream() -> DeliveryStream {
+        DeliveryStream::new(DeliveryStreamName::new("events".to_owned()))
+    }
+
+    fn config(max_attempts: u32) -> PutConfig {
+        PutConfig {
+            max_attempts,
+            backoff: Backoff::constant(Duration::ZERO),
+        }
+    }
+
+    fn sent_data(request: &Request) -> Vec<String> {
+        request
+            .json_param::<Vec<HashMap<String, String>>>("Records")
+            .unwrap()
+            .into_iter()
+            .map(|mut record| record.remove("Data").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn list_follows_start_names() {
+        let http = MockHttpClient::new()
+            .on(
+                Matcher::param("ExclusiveStartDeliveryStreamName", "events"),
+                MockResponse::ok(
+                    r#"{"DeliveryStreamNames": ["metrics"], "HasMoreDeliveryStreams": false}"#,
+                ),
+            )
+            .on(
+                Matcher::action("ListDeliveryStreams"),
+                MockResponse::ok(
+                    r#"{"DeliveryStreamNames": ["audit", "events"], "HasMoreDeliveryStreams": true}"#,
+                ),
+            );
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let streams = block_on(DeliveryStream::list(&client)).unwrap();
+
+        assert_eq!(
+            streams
+                .iter()
+                .map(|stream| stream.name().as_str())
+                .collect::<Vec<_>>(),
+            ["audit", "events", "metrics"]
+        );
+        assert_eq!(http.requests().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rejected_records_are_retried() {
+        let http = MockHttpClient::new()
+            .once(
+                Matcher::action("PutRecordBatch"),
+                MockResponse::ok(REJECTED),
+            )
+            .on(
+                Matcher::action("PutRecordBatch"),
+                MockResponse::ok(
+                    r#"{"FailedPutCount": 0, "RequestResponses": [{"RecordId": "record-b"}]}"#,
+                ),
+            );
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let report =
+            block_on(stream().put_records(&client, vec![b"a".to_vec(), b"b".to_vec()], &config(3)))
+                .unwrap();
+
+        assert!(report.is_success());
+        assert_eq!(report.succeeded, 2);
+        let requests = http.requests().unwrap();
+        assert_eq!(
+            requests.iter().map(sent_data).collect::<Vec<_>>(),
+            [vec!["YQ==", "Yg=="], vec!["Yg=="]]
+        );
+        assert!(requests
+            .iter()
+            .all(|request| request.param("DeliveryStreamName").as_deref() == Some("events")));
+    }
+
+    #[test]
+    fn records_fail_after_last_attempt() {
+        let http = MockHttpClient::new().on(Matcher::Any, MockResponse::ok(REJECTED));
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let report =
+            block_on(stream().put_records(&client, vec![b"a".to_vec(), b"b".to_vec()], &config(1)))
+                .unwrap();
+
+        assert_eq!(report.succeeded, 1);
+        assert_eq!(
+            report.failed,
+            [FailedRecord {
+                record: b"b".to_vec(),
+                error_code: "ServiceUnavailableException".to_owned(),
+                error_message: "slow down".to_owned(),
+            }]
+        );
+        assert_eq!(http.requests().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn tags_follow_start_keys() {
+        let http = MockHttpClient::new()
+            .on(
+                Matcher::param("ExclusiveStartTagKey", "team"),
+                MockResponse::ok(
+                    r#"{"Tags": [{"Key": "env", "Value": "prod"}], "HasMoreTags": false}"#,
+                ),
+            )
+            .on(
+                Matcher::action("ListTagsForDeliveryStream"),
+                MockResponse::ok(
+                    r#"{"Tags": [{"Key": "team", "Value": "infra"}], "HasMoreTags": true}"#,
+                ),
+            )
+            .on(
+                Matcher::action("UntagDeliveryStream"),
+                MockResponse::ok("{}"),
+            );
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let tags = block_on(stream().tags(&client)).unwrap();
+        assert_eq!(
+            tags.as_slice(),
+            [
+                RawTag::new("env".to_owned(), "prod".to_owned()),
+                RawTag::new("team".to_owned(), "infra".to_owned()),
+            ]
+        );
+
+        block_on(stream().remove_tags(&client, vec![TagKey::new("env".to_owned())])).unwrap();
+        let request = http
+            .requests_matching(&Matcher::action("UntagDeliveryStream"))
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(
+            request.json_param::<Vec<String>>("TagKeys").unwrap(),
+            ["env"]
+        );
+    }
+}
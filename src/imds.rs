@@ -0,0 +1,483 @@
+//! Instance metadata service (IMDS)
+//!
+//! Only IMDSv2 (session tokens) is used by default. IMDSv1 can be enabled
+//! explicitly with [`ImdsConfig::allow_imdsv1`] for instances that do not
+//! support tokens.
+//!
+//! With instance metadata tags enabled on the instance, [`Imds::tags()`]
+//! returns the instance tags as a [`TagList`], which can then be parsed with
+//! the tag schemas of this crate without any EC2 API calls.
+//!
+//! Spot instances get a two minute notice before they are interrupted,
+//! which [`Imds::spot_interruption()`] and
+//! [`Imds::wait_for_spot_interruption()`] expose.
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    net::TcpStream,
+};
+
+use super::{
+    tags::{RawTag, TagList},
+    Error,
+};
+#[cfg(feature = "serde")]
+use super::{InstanceId, Timestamp};
+
+const TOKEN_PATH: &str = "/latest/api/token";
+const TOKEN_TTL_HEADER: &str = "X-aws-ec2-metadata-token-ttl-seconds";
+const TOKEN_HEADER: &str = "X-aws-ec2-metadata-token";
+
+/// Tokens are refreshed this long before they expire
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct ImdsConfig {
+    pub endpoint: SocketAddr,
+    /// Lifetime of session tokens, at most 6 hours
+    pub token_ttl: Duration,
+    /// Fall back to IMDSv1 if no token can be acquired
+    pub allow_imdsv1: bool,
+    /// Timeout for each request, including connecting
+    pub timeout: Duration,
+}
+
+impl Default for ImdsConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(169, 254, 169, 254), 80)),
+            token_ttl: Duration::from_secs(6 * 60 * 60),
+            allow_imdsv1: false,
+            timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+/// Parses a HTTP/1.1 response. IMDS always sends a `Content-Length`, so
+/// chunked encoding is not supported.
+fn parse_response(raw: &[u8]) -> Result<Response, Error> {
+    let invalid = |message: &str| Error::Imds {
+        message: format!("invalid http response: {message}"),
+    };
+
+    let raw = std::str::from_utf8(raw).map_err(|_e| invalid("not utf-8"))?;
+    let (head, body) = raw
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| invalid("missing header terminator"))?;
+
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| invalid("malformed status line"))?;
+
+    if lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.eq_ignore_ascii_case("transfer-encoding") && value.trim() == "chunked"
+        })
+    }) {
+        return Err(invalid("chunked encoding is not supported"));
+    }
+
+    Ok(Response {
+        status,
+        body: body.to_owned(),
+    })
+}
+
+/// The instance identity document
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityDocument {
+    pub account_id: String,
+    pub instance_id: InstanceId,
+    pub instance_type: String,
+    pub image_id: String,
+    pub region: String,
+    pub availability_zone: String,
+    pub private_ip: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    token: String,
+    expiration: String,
+}
+
+/// Temporary credentials of the instance profile role
+#[cfg(feature = "serde")]
+#[derive(Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub token: String,
+    pub expiration: Timestamp,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawCredentials> for Credentials {
+    type Error = Error;
+
+    fn try_from(raw: RawCredentials) -> Result<Self, Self::Error> {
+        Ok(Self {
+            expiration: Timestamp::new(
+                chrono::DateTime::parse_from_rfc3339(&raw.expiration)
+                    .map_err(|e| Error::InvalidTimestampError {
+                        value: raw.expiration.clone(),
+                        message: e.to_string(),
+                    })?
+                    .with_timezone(&chrono::Utc),
+            ),
+            access_key_id: raw.access_key_id,
+            secret_access_key: raw.secret_access_key,
+            token: raw.token,
+        })
+    }
+}
+
+// Do not leak the secrets into logs
+#[cfg(feature = "serde")]
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"<redacted>")
+            .field("token", &"<redacted>")
+            .field("expiration", &self.expiration)
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpotInterruptionAction {
+    Terminate,
+    Stop,
+    Hibernate,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RawSpotInterruption {
+    action: SpotInterruptionAction,
+    time: String,
+}
+
+/// A pending interruption of the spot instance
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpotInterruption {
+    pub action: SpotInterruptionAction,
+    /// When the instance is interrupted, usually two minutes after the
+    /// notice
+    pub time: Timestamp,
+}
+
+#[cfg(feature = "serde")]
+fn parse_spot_interruption(document: &str) -> Result<SpotInterruption, Error> {
+    let raw: RawSpotInterruption = serde_json::from_str(document).map_err(|e| Error::Imds {
+        message: format!("invalid spot instance action: {e}"),
+    })?;
+
+    Ok(SpotInterruption {
+        action: raw.action,
+        time: Timestamp::new(
+            chrono::DateTime::parse_from_rfc3339(&raw.time)
+                .map_err(|e| Error::InvalidTimestampError {
+                    value: raw.time.clone(),
+                    message: e.to_string(),
+                })?
+                .with_timezone(&chrono::Utc),
+        ),
+    })
+}
+
+#[derive(Debug)]
+pub struct Imds {
+    config: ImdsConfig,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl Imds {
+    pub const fn new(config: ImdsConfig) -> Self {
+        Self {
+            config,
+            token: Mutex::new(None),
+        }
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<Response, Error> {
+        let io_error = |e: std::io::Error| Error::Imds {
+            message: e.to_string(),
+        };
+
+        let exchange = async {
+            let mut stream = TcpStream::connect(self.config.endpoint)
+                .await
+                .map_err(io_error)?;
+
+            let mut request = format!(
+                "{method} {path} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: 0\r\n",
+                self.config.endpoint.ip()
+            );
+            for &(name, value) in headers {
+                request.push_str(name);
+                request.push_str(": ");
+                request.push_str(value);
+                request.push_str("\r\n");
+            }
+            request.push_str("\r\n");
+
+            stream
+                .write_all(request.as_bytes())
+                .await
+                .map_err(io_error)?;
+
+            let mut raw = Vec::new();
+            let _read = stream.read_to_end(&mut raw).await.map_err(io_error)?;
+
+            parse_response(&raw)
+        };
+
+        tokio::time::timeout(self.config.timeout, exchange)
+            .await
+            .map_err(|_e| Error::Imds {
+                message: format!("request to {path} timed out"),
+            })?
+    }
+
+    /// Returns a valid session token, or `None` if IMDSv1 is allowed and no
+    /// token could be acquired
+    async fn token(&self) -> Result<Option<String>, Error> {
+        let cached = self
+            .token
+            .lock()
+            .map_err(|_e| Error::Imds {
+                message: "token cache poisoned".to_owned(),
+            })?
+            .clone();
+
+        if let Some((token, expires_at)) = cached {
+            if Instant::now() < expires_at {
+                return Ok(Some(token));
+            }
+        }
+
+        let ttl = self.config.token_ttl.as_secs().to_string();
+        let result = self
+            .request("PUT", TOKEN_PATH, &[(TOKEN_TTL_HEADER, &ttl)])
+            .await
+            .and_then(|response| {
+                if response.status == 200 {
+                    Ok(response.body)
+                } else {
+                    Err(Error::Imds {
+                        message: format!("token request failed with status {}", response.status),
+                    })
+                }
+            });
+
+        match result {
+            Ok(token) => {
+                let expires_at = Instant::now()
+                    .checked_add(self.config.token_ttl.saturating_sub(TOKEN_REFRESH_MARGIN))
+                    .unwrap_or_else(Instant::now);
+
+                *self.token.lock().map_err(|_e| Error::Imds {
+                    message: "token cache poisoned".to_owned(),
+                })? = Some((token.clone(), expires_at));
+
+                Ok(Some(token))
+            }
+            Err(_e) if self.config.allow_imdsv1 => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Fetches a metadata path, e.g. `/latest/meta-data/instance-id`.
+    /// Returns `None` if the path does not exist.
+    pub async fn get(&self, path: &str) -> Result<Option<String>, Error> {
+        let token = self.token().await?;
+
+        let headers = token
+            .as_deref()
+            .map(|token| vec![(TOKEN_HEADER, token)])
+            .unwrap_or_default();
+
+        let response = self.request("GET", path, &headers).await?;
+
+        match response.status {
+            200 => Ok(Some(response.body)),
+            404 => Ok(None),
+            status => Err(Error::Imds {
+                message: format!("request to {path} failed with status {status}"),
+            }),
+        }
+    }
+
+    async fn get_required(&self, path: &str) -> Result<String, Error> {
+        self.get(path).await?.ok_or_else(|| Error::Imds {
+            message: format!("{path} not found"),
+        })
+    }
+
+    pub async fn region(&self) -> Result<String, Error> {
+        self.get_required("/latest/meta-data/placement/region")
+            .await
+    }
+
+    pub async fn instance_id(&self) -> Result<String, Error> {
+        self.get_required("/latest/meta-data/instance-id").await
+    }
+
+    #[cfg(feature = "serde")]
+    pub async fn identity_document(&self) -> Result<IdentityDocument, Error> {
+        let document = self
+            .get_required("/latest/dynamic/instance-identity/document")
+            .await?;
+
+        serde_json::from_str(&document).map_err(|e| Error::Imds {
+            message: format!("invalid identity document: {e}"),
+        })
+    }
+
+    /// The tags of the instance. Requires instance metadata tags to be
+    /// enabled for the instance, otherwise an empty list is returned.
+    pub async fn tags(&self) -> Result<TagList, Error> {
+        let Some(keys) = self.get("/latest/meta-data/tags/instance").await? else {
+            return Ok(TagList::new());
+        };
+
+        let mut tags = TagList::new();
+        for key in keys.lines().filter(|key| !key.is_empty()) {
+            let value = self
+                .get_required(&format!("/latest/meta-data/tags/instance/{key}"))
+                .await?;
+            tags.push(RawTag::new(key.to_owned(), value));
+        }
+
+        Ok(tags)
+    }
+
+    /// Credentials of the role of the instance profile, or `None` if the
+    /// instance has no instance profile
+    #[cfg(feature = "serde")]
+    pub async fn credentials(&self) -> Result<Option<Credentials>, Error> {
+        const PATH: &str = "/latest/meta-data/iam/security-credentials/";
+
+        let Some(roles) = self.get(PATH).await? else {
+            return Ok(None);
+        };
+
+        let Some(role) = roles.lines().next() else {
+            return Ok(None);
+        };
+
+        let credentials: RawCredentials = serde_json::from_str(
+            &self.get_required(&format!("{PATH}{role}")).await?,
+        )
+        .map_err(|e| Error::Imds {
+            message: format!("invalid credentials: {e}"),
+        })?;
+
+        Ok(Some(credentials.try_into()?))
+    }
+
+    /// The pending interruption of the spot instance, or `None` if there is
+    /// none or the instance is not a spot instance
+    #[cfg(feature = "serde")]
+    pub async fn spot_interruption(&self) -> Result<Option<SpotInterruption>, Error> {
+        self.get("/latest/meta-data/spot/instance-action")
+            .await?
+            .as_deref()
+            .map(parse_spot_interruption)
+            .transpose()
+    }
+
+    /// Polls for an interruption every `interval` until there is one. AWS
+    /// recommends polling every five seconds.
+    ///
+    /// Cancel the future by dropping it, e.g. when the work is done.
+    #[cfg(feature = "serde")]
+    pub async fn wait_for_spot_interruption(
+        &self,
+        interval: Duration,
+    ) -> Result<SpotInterruption, Error> {
+        loop {
+            if let Some(interruption) = self.spot_interruption().await? {
+                return Ok(interruption);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+impl Default for Imds {
+    fn default() -> Self {
+        Self::new(ImdsConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_http_response() {
+        let response =
+            parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\ni-12345678").unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "i-12345678");
+
+        assert!(
+            parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n").is_err(),
+            "chunked encoding"
+        );
+        assert!(parse_response(b"garbage").is_err(), "no status line");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn spot_interruption() {
+        let interruption =
+            parse_spot_interruption(r#"{"action": "terminate", "time": "2017-09-18T08:22:00Z"}"#)
+                .unwrap();
+        assert_eq!(interruption.action, SpotInterruptionAction::Terminate);
+        assert_eq!(
+            interruption.time.to_string(),
+            "2017-09-18 08:22:00 UTC",
+            "time of the interruption"
+        );
+
+        assert!(
+            parse_spot_interruption(r#"{"action": "explode"}"#).is_err(),
+            "unknown action"
+        );
+    }
+}
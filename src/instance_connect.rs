@@ -0,0 +1,227 @@
+//! EC2 Instance Connect
+//!
+//! [`send_ssh_public_key()`] pushes an SSH public key to an instance. The SSH
+//! daemon of the instance accepts the key for [`KEY_LIFETIME`], so the
+//! connection has to be opened right after. Combined with a tag query, this
+//! replaces `aws ec2-instance-connect send-ssh-public-key`:
+//!
+//! ```no_run
+//! # async fn f(client: &aws_lib::RegionClient) -> Result<(), aws_lib::Error> {
+//! use aws_lib::{filter::Filter, instance_connect, Instance};
+//!
+//! let public_key = std::fs::read_to_string("/home/alice/.ssh/id_ed25519.pub").unwrap();
+//!
+//! for instance in Instance::list(client, vec![Filter::tag("role").eq("bastion")]).await? {
+//!     instance_connect::send_ssh_public_key(
+//!         client,
+//!         instance.instance_id(),
+//!         "ec2-user",
+//!         &public_key,
+//!     )
+//!     .await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use super::{Error, InstanceId, RegionClient};
+
+/// How long a pushed key is accepted by the instance
+pub const KEY_LIFETIME: Duration = Duration::from_secs(60);
+
+fn check_success(success: bool, instance: &InstanceId) -> Result<(), Error> {
+    if success {
+        Ok(())
+    } else {
+        Err(Error::InvalidResponseError {
+            message: format!("instance connect did not accept the key for {instance}"),
+        })
+    }
+}
+
+/// Pushes `public_key` (in OpenSSH format) for `os_user` to the instance
+///
+/// The instance needs the Instance Connect package installed, which is the
+/// default on Amazon Linux and Ubuntu AMIs.
+pub async fn send_ssh_public_key(
+    client: &RegionClient,
+    instance: &InstanceId,
+    os_user: &str,
+    public_key: &str,
+) -> Result<(), Error> {
+    let output = client
+        .main
+        .ec2instanceconnect
+        .send_ssh_public_key()
+        .instance_id(instance.as_str())
+        .instance_os_user(os_user)
+        .ssh_public_key(public_key.trim())
+        .send()
+        .await?;
+
+    check_success(output.success(), instance)
+}
+
+/// Pushes `public_key` for the serial console of the instance
+///
+/// The serial console is reachable even if the network or the SSH daemon of
+/// the instance is broken. Only supported on Nitro instances, and the serial
+/// console access has to be enabled for the account.
+pub async fn send_serial_console_ssh_public_key(
+    client: &RegionClient,
+    instance: &InstanceId,
+    serial_port: i32,
+    public_key: &str,
+) -> Result<(), Error> {
+    let output = client
+        .main
+        .ec2instanceconnect
+        .send_serial_console_ssh_public_key()
+        .instance_id(instance.as_str())
+        .serial_port(serial_port)
+        .ssh_public_key(public_key.trim())
+        .send()
+        .await?;
+
+    check_success(output.success(), instance)
+}
+
+/// The SSH endpoint of the serial console of the instance, connect as
+/// `<instance id>.port<port>@<endpoint>`
+pub fn serial_console_endpoint(client: &RegionClient) -> String {
+    format!(
+        "serial-console.ec2-instance-connect.{}.{}",
+        client.region.as_str(),
+        client.region.partition().dns_suffix()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejected_key_is_an_error() {
+        let instance = InstanceId("i-0123456789abcdef0".to_owned());
+
+        assert!(check_success(true, &instance).is_ok());
+        assert!(matches!(
+            check_success(false, &instance),
+            Err(Error::InvalidResponseError { ref message })
+                if message.contains("i-0123456789abcdef0")
+        ));
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        fn instance() -> InstanceId {
+            InstanceId("i-0123456789abcdef0".to_owned())
+        }
+
+        #[test]
+        fn key_is_sent_trimmed() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("SendSSHPublicKey"),
+                MockResponse::ok(r#"{"RequestId": "8f7e9a3c", "Success": true}"#),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            block_on(send_ssh_public_key(
+                &client,
+                &instance(),
+                "ec2-user",
+                "ssh-ed25519 AAAA alice\n",
+            ))
+            .unwrap();
+
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(
+                request.param("InstanceId").as_deref(),
+                Some("i-0123456789abcdef0")
+            );
+            assert_eq!(request.param("InstanceOSUser").as_deref(), Some("ec2-user"));
+            assert_eq!(
+                request.param("SSHPublicKey").as_deref(),
+                Some("ssh-ed25519 AAAA alice")
+            );
+        }
+
+        #[test]
+        fn unsuccessful_push_is_an_error() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("SendSerialConsoleSSHPublicKey"),
+                MockResponse::ok(r#"{"RequestId": "8f7e9a3c", "Success": false}"#),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            assert!(matches!(
+                block_on(send_serial_console_ssh_public_key(
+                    &client,
+                    &instance(),
+                    0,
+                    "ssh-ed25519 AAAA alice",
+                )),
+                Err(Error::InvalidResponseError { .. })
+            ));
+            assert_eq!(
+                http.requests()
+                    .unwrap()
+                    .pop()
+                    .unwrap()
+                    .param("SerialPort")
+                    .as_deref(),
+                Some("0")
+            );
+        }
+
+        #[test]
+        fn error_response_is_returned() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("SendSSHPublicKey"),
+                MockResponse::status(
+                    400,
+                    r#"{"__type": "EC2InstanceNotFoundException", "Message": "not found"}"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let error = block_on(send_ssh_public_key(
+                &client,
+                &instance(),
+                "ec2-user",
+                "ssh-ed25519 AAAA alice",
+            ))
+            .unwrap_err();
+            assert_eq!(
+                error.request_metadata().unwrap().error_code.as_deref(),
+                Some("EC2InstanceNotFoundException")
+            );
+        }
+
+        #[test]
+        fn serial_console_endpoint_of_partition() {
+            assert_eq!(
+                serial_console_endpoint(&mock_region_client(
+                    Region::EuCentral1,
+                    MockHttpClient::new()
+                )),
+                "serial-console.ec2-instance-connect.eu-central-1.amazonaws.com"
+            );
+            assert_eq!(
+                serial_console_endpoint(&mock_region_client(
+                    Region::CnNorth1,
+                    MockHttpClient::new()
+                )),
+                "serial-console.ec2-instance-connect.cn-north-1.amazonaws.com.cn"
+            );
+        }
+    }
+}
@@ -0,0 +1,393 @@
+//! Kinesis data streams
+//!
+//! [`KinesisStream::put_records()`] splits any number of records into
+//! requests below the limits of `PutRecords`, and retries records that were
+//! rejected, e.g. due to throttling of a shard. The same is done for Firehose
+//! in [`super::firehose`].
+
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::future::Future;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::batch::batches;
+use super::{
+    tags::{TagKey, TagList},
+    waiter::Backoff,
+    Error, RegionClient,
+};
+
+/// Limits of `PutRecords`. The size of a record is the size of its data plus
+/// its partition key.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_RECORDS: usize = 500;
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_REQUEST_BYTES: usize = 5 * 1024 * 1024;
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_RECORD_BYTES: usize = 1024 * 1024;
+
+string_newtype!(StreamName);
+
+impl StreamName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub partition_key: String,
+    pub data: Vec<u8>,
+    /// Overrides the shard selection via the hash of the partition key
+    pub explicit_hash_key: Option<String>,
+}
+
+impl Record {
+    pub fn new(partition_key: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            partition_key: partition_key.into(),
+            data: data.into(),
+            explicit_hash_key: None,
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn size(&self) -> usize {
+        self.data.len().saturating_add(self.partition_key.len())
+    }
+}
+
+/// How often rejected records are sent again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PutConfig {
+    /// Including the first attempt
+    pub max_attempts: u32,
+    pub backoff: Backoff,
+}
+
+impl Default for PutConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Backoff::default(),
+        }
+    }
+}
+
+/// A record that was still rejected after the last attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedRecord<R> {
+    pub record: R,
+    pub error_code: String,
+    pub error_message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PutReport<R> {
+    pub succeeded: usize,
+    pub failed: Vec<FailedRecord<R>>,
+}
+
+impl<R> PutReport<R> {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// The rejection of a single record in a batch request
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub(crate) struct Rejection {
+    pub(crate) error_code: String,
+    pub(crate) error_message: String,
+}
+
+/// Sends `records` in batches via `send`, and retries rejected records
+/// according to `config`
+///
+/// `send` returns one entry per record of the batch, in the same order,
+/// which is `Some` if the record was rejected.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn put_batched<R, F, Fut>(
+    records: Vec<R>,
+    size: impl Fn(&R) -> usize,
+    (max_records, max_request_bytes, max_record_bytes): (usize, usize, usize),
+    config: &PutConfig,
+    send: F,
+) -> Result<PutReport<R>, Error>
+where
+    F: Fn(&[R]) -> Fut,
+    Fut: Future<Output = Result<Vec<Option<Rejection>>, Error>>,
+{
+    let sizes: Vec<usize> = records.iter().map(&size).collect();
+
+    if let Some((index, record_size)) = sizes
+        .iter()
+        .enumerate()
+        .find(|&(_, &record_size)| record_size > max_record_bytes)
+    {
+        return Err(Error::InvalidPayload {
+            message: format!(
+                "record {index} has {record_size} bytes, at most {max_record_bytes} are allowed"
+            ),
+        });
+    }
+
+    let mut report = PutReport {
+        succeeded: 0,
+        failed: vec![],
+    };
+
+    let mut records = records.into_iter();
+    for batch in batches(&sizes, max_records, max_request_bytes) {
+        let mut pending: Vec<R> = records.by_ref().take(batch.len()).collect();
+        let mut attempt = 1;
+        let mut delay = config.backoff.initial;
+
+        loop {
+            let results = send(&pending).await?;
+            let rejected = settle(pending, results, &mut report.succeeded);
+
+            if rejected.is_empty() {
+                break;
+            }
+
+            if attempt >= config.max_attempts {
+                report
+                    .failed
+                    .extend(
+                        rejected
+                            .into_iter()
+                            .map(|(record, rejection)| FailedRecord {
+                                record,
+                                error_code: rejection.error_code,
+                                error_message: rejection.error_message,
+                            }),
+                    );
+                break;
+            }
+
+            tokio::time::sleep(delay).await;
+            delay = config.backoff.next(delay);
+            attempt = attempt.saturating_add(1);
+            pending = rejected.into_iter().map(|(record, _)| record).collect();
+        }
+    }
+
+    Ok(report)
+}
+
+/// Counts the accepted records and returns the rejected ones
+#[cfg(not(target_arch = "wasm32"))]
+fn settle<R>(
+    records: Vec<R>,
+    results: Vec<Option<Rejection>>,
+    succeeded: &mut usize,
+) -> Vec<(R, Rejection)> {
+    let mut rejected = vec![];
+    for (record, result) in records.into_iter().zip(results) {
+        match result {
+            None => *succeeded = succeeded.saturating_add(1),
+            Some(rejection) => rejected.push((record, rejection)),
+        }
+    }
+    rejected
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct KinesisStream {
+    name: StreamName,
+}
+
+impl KinesisStream {
+    pub const fn new(name: StreamName) -> Self {
+        Self { name }
+    }
+
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        Ok(client
+            .main
+            .kinesis
+            .list_streams()
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.stream_names)
+            .map(|name| Self::new(StreamName(name)))
+            .collect())
+    }
+
+    pub const fn name(&self) -> &StreamName {
+        &self.name
+    }
+
+    /// Returns the shard ID and sequence number of the record
+    pub async fn put_record(
+        &self,
+        client: &RegionClient,
+        record: Record,
+    ) -> Result<(String, String), Error> {
+        let output = client
+            .main
+            .kinesis
+            .put_record()
+            .stream_name(self.name.as_str())
+            .partition_key(record.partition_key)
+            .set_explicit_hash_key(record.explicit_hash_key)
+            .data(aws_sdk_kinesis::primitives::Blob::new(record.data))
+            .send()
+            .await?;
+
+        Ok((output.shard_id, output.sequence_number))
+    }
+
+    /// Puts any number of records, see the [module documentation](self)
+    ///
+    /// Records keep their order within a batch, but rejected records are
+    /// retried after the rest of their batch.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "only expect() on builder instances"
+    )]
+    pub async fn put_records(
+        &self,
+        client: &RegionClient,
+        records: Vec<Record>,
+        config: &PutConfig,
+    ) -> Result<PutReport<Record>, Error> {
+        put_batched(
+            records,
+            Record::size,
+            (MAX_RECORDS, MAX_REQUEST_BYTES, MAX_RECORD_BYTES),
+            config,
+            |batch| {
+                // Built outside of the future, which must not borrow the batch
+                let entries: Vec<_> = batch
+                    .iter()
+                    .map(|record| {
+                        aws_sdk_kinesis::types::PutRecordsRequestEntry::builder()
+                            .partition_key(record.partition_key.clone())
+                            .set_explicit_hash_key(record.explicit_hash_key.clone())
+                            .data(aws_sdk_kinesis::primitives::Blob::new(record.data.clone()))
+                            .build()
+                            .expect("builder misused")
+                    })
+                    .collect();
+
+                async move {
+                    Ok(client
+                        .main
+                        .kinesis
+                        .put_records()
+                        .stream_name(self.name.as_str())
+                        .set_records(Some(entries))
+                        .send()
+                        .await?
+                        .records
+                        .into_iter()
+                        .map(|entry| {
+                            entry.error_code.map(|error_code| Rejection {
+                                error_code,
+                                error_message: entry.error_message.unwrap_or_default(),
+                            })
+                        })
+                        .collect())
+                }
+            },
+        )
+        .await
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        let mut tags = vec![];
+        let mut start = None;
+
+        loop {
+            let output = client
+                .main
+                .kinesis
+                .list_tags_for_stream()
+                .stream_name(self.name.as_str())
+                .set_exclusive_start_tag_key(start)
+                .send()
+                .await?;
+
+            start = output.tags.last().map(|tag| tag.key.clone());
+            tags.extend(output.tags);
+
+            if !output.has_more_tags || start.is_none() {
+                break;
+            }
+        }
+
+        Ok(tags.try_into()?)
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .kinesis
+            .add_tags_to_stream()
+            .stream_name(self.name.as_str())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .kinesis
+            .remove_tags_from_stream()
+            .stream_name(self.name.as_str())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settle_batch_results() {
+        let rejection = || {
+            Some(Rejection {
+                error_code: "ProvisionedThroughputExceededException".to_owned(),
+                error_message: String::new(),
+            })
+        };
+
+        let mut succeeded = 1;
+        let rejected = settle(
+            vec![1, 2, 3],
+            vec![None, rejection(), rejection()],
+            &mut succeeded,
+        );
+
+        assert_eq!(succeeded, 2);
+        assert_eq!(
+            rejected
+                .into_iter()
+                .map(|(record, _)| record)
+                .collect::<Vec<u32>>(),
+            vec![2, 3]
+        );
+    }
+}
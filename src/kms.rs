@@ -0,0 +1,380 @@
+//! KMS keys, encryption and envelope encryption
+//!
+//! KMS can only encrypt up to 4KB of data directly. For larger payloads, the
+//! `envelope` feature provides [`seal()`] and [`open()`]: The data is
+//! encrypted locally with AES-256-GCM using a fresh data key, and only the
+//! data key is encrypted by KMS.
+
+use std::{collections::HashMap, fmt};
+
+#[cfg(feature = "envelope")]
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use aws_sdk_kms::primitives::Blob;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{TagKey, TagList},
+    Error, RegionClient,
+};
+
+string_newtype!(KeyId);
+
+impl KeyId {
+    /// Accepts a key ID, key ARN, alias name (`alias/...`) or alias ARN
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Additional authenticated data for encryption. The same context has to be
+/// passed for decryption.
+pub type EncryptionContext = HashMap<String, String>;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Key {
+    id: KeyId,
+    arn: Option<String>,
+}
+
+impl Key {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .kms
+            .list_keys()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(|key| {
+                Ok(Self {
+                    id: KeyId(key.key_id.ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: "KeyListEntry.key_id".to_owned(),
+                    })?),
+                    arn: key.key_arn,
+                })
+            })
+            .collect()
+    }
+
+    pub const fn id(&self) -> &KeyId {
+        &self.id
+    }
+
+    pub fn arn(&self) -> Option<&str> {
+        self.arn.as_deref()
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .main
+            .kms
+            .list_resource_tags()
+            .key_id(self.id.as_str())
+            .send()
+            .await?
+            .tags
+            .unwrap_or_default()
+            .try_into()?)
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .kms
+            .tag_resource()
+            .key_id(self.id.as_str())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .kms
+            .untag_resource()
+            .key_id(self.id.as_str())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Alias {
+    name: String,
+    target_key: Option<KeyId>,
+}
+
+impl Alias {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .kms
+            .list_aliases()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(|alias| {
+                Ok(Self {
+                    name: alias.alias_name.ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: "AliasListEntry.alias_name".to_owned(),
+                    })?,
+                    target_key: alias.target_key_id.map(KeyId),
+                })
+            })
+            .collect()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `None` for AWS managed aliases that are not yet in use
+    pub const fn target_key(&self) -> Option<&KeyId> {
+        self.target_key.as_ref()
+    }
+}
+
+pub async fn encrypt(
+    client: &RegionClient,
+    key: &KeyId,
+    plaintext: Vec<u8>,
+    context: Option<EncryptionContext>,
+) -> Result<Vec<u8>, Error> {
+    Ok(client
+        .main
+        .kms
+        .encrypt()
+        .key_id(key.as_str())
+        .plaintext(Blob::new(plaintext))
+        .set_encryption_context(context)
+        .send()
+        .await?
+        .ciphertext_blob
+        .ok_or_else(|| Error::UnexpectedNoneValue {
+            entity: "EncryptOutput.ciphertext_blob".to_owned(),
+        })?
+        .into_inner())
+}
+
+/// The key does not need to be given for symmetric keys, as it is part of the
+/// ciphertext
+pub async fn decrypt(
+    client: &RegionClient,
+    key: Option<&KeyId>,
+    ciphertext: Vec<u8>,
+    context: Option<EncryptionContext>,
+) -> Result<Vec<u8>, Error> {
+    Ok(client
+        .main
+        .kms
+        .decrypt()
+        .set_key_id(key.map(|key| key.as_str().to_owned()))
+        .ciphertext_blob(Blob::new(ciphertext))
+        .set_encryption_context(context)
+        .send()
+        .await?
+        .plaintext
+        .ok_or_else(|| Error::UnexpectedNoneValue {
+            entity: "DecryptOutput.plaintext".to_owned(),
+        })?
+        .into_inner())
+}
+
+/// A 256 bit data key, both in plaintext and encrypted under a KMS key
+pub struct DataKey {
+    pub plaintext: Vec<u8>,
+    pub encrypted: Vec<u8>,
+}
+
+// Do not leak key material into logs
+impl fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataKey")
+            .field("plaintext", &"<redacted>")
+            .field("encrypted", &self.encrypted)
+            .finish()
+    }
+}
+
+pub async fn generate_data_key(
+    client: &RegionClient,
+    key: &KeyId,
+    context: Option<EncryptionContext>,
+) -> Result<DataKey, Error> {
+    let output = client
+        .main
+        .kms
+        .generate_data_key()
+        .key_id(key.as_str())
+        .key_spec(aws_sdk_kms::types::DataKeySpec::Aes256)
+        .set_encryption_context(context)
+        .send()
+        .await?;
+
+    Ok(DataKey {
+        plaintext: output
+            .plaintext
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "GenerateDataKeyOutput.plaintext".to_owned(),
+            })?
+            .into_inner(),
+        encrypted: output
+            .ciphertext_blob
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "GenerateDataKeyOutput.ciphertext_blob".to_owned(),
+            })?
+            .into_inner(),
+    })
+}
+
+/// Data encrypted by [`seal()`]
+#[cfg(feature = "envelope")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    encrypted_key: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+#[cfg(feature = "envelope")]
+impl Envelope {
+    /// Serializes the envelope as `<key length (u32, big endian)><key><nonce><ciphertext>`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let key_length = u32::try_from(self.encrypted_key.len()).unwrap_or(u32::MAX);
+        [
+            key_length.to_be_bytes().as_slice(),
+            &self.encrypted_key,
+            &self.nonce,
+            &self.ciphertext,
+        ]
+        .concat()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = || Error::Encryption {
+            message: "envelope is truncated".to_owned(),
+        };
+
+        let (key_length, rest) = bytes.split_first_chunk::<4>().ok_or_else(invalid)?;
+        let key_length =
+            usize::try_from(u32::from_be_bytes(*key_length)).map_err(|_e| invalid())?;
+
+        let (encrypted_key, rest) = rest.split_at_checked(key_length).ok_or_else(invalid)?;
+        let (nonce, ciphertext) = rest.split_at_checked(NONCE_LENGTH).ok_or_else(invalid)?;
+
+        Ok(Self {
+            encrypted_key: encrypted_key.to_vec(),
+            nonce: nonce.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+}
+
+/// Length of the AES-GCM nonce in bytes
+#[cfg(feature = "envelope")]
+const NONCE_LENGTH: usize = 12;
+
+/// Encrypts `plaintext` with a new data key generated under `key`
+#[cfg(feature = "envelope")]
+pub async fn seal(
+    client: &RegionClient,
+    key: &KeyId,
+    plaintext: &[u8],
+    context: Option<EncryptionContext>,
+) -> Result<Envelope, Error> {
+    let data_key = generate_data_key(client, key, context).await?;
+
+    let cipher = Aes256Gcm::new_from_slice(&data_key.plaintext).map_err(|e| Error::Encryption {
+        message: e.to_string(),
+    })?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| Error::Encryption {
+            message: e.to_string(),
+        })?;
+
+    Ok(Envelope {
+        encrypted_key: data_key.encrypted,
+        nonce: nonce.to_vec(),
+        ciphertext,
+    })
+}
+
+/// Decrypts an envelope created by [`seal()`]. `context` has to match the
+/// context used for sealing.
+#[cfg(feature = "envelope")]
+pub async fn open(
+    client: &RegionClient,
+    envelope: &Envelope,
+    context: Option<EncryptionContext>,
+) -> Result<Vec<u8>, Error> {
+    let data_key = decrypt(client, None, envelope.encrypted_key.clone(), context).await?;
+
+    let cipher = Aes256Gcm::new_from_slice(&data_key).map_err(|e| Error::Encryption {
+        message: e.to_string(),
+    })?;
+
+    if envelope.nonce.len() != NONCE_LENGTH {
+        return Err(Error::Encryption {
+            message: "invalid nonce length".to_owned(),
+        });
+    }
+
+    cipher
+        .decrypt(
+            Nonce::from_slice(&envelope.nonce),
+            envelope.ciphertext.as_slice(),
+        )
+        .map_err(|e| Error::Encryption {
+            message: e.to_string(),
+        })
+}
+
+#[cfg(test)]
+#[cfg(feature = "envelope")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_bytes_round_trip() {
+        let envelope = Envelope {
+            encrypted_key: vec![1, 2, 3],
+            nonce: vec![4; NONCE_LENGTH],
+            ciphertext: vec![5, 6],
+        };
+
+        assert_eq!(
+            Envelope::from_bytes(&envelope.to_bytes()).unwrap(),
+            envelope
+        );
+        assert!(
+            Envelope::from_bytes(&[0, 0, 0, 9, 1]).is_err(),
+            "truncated key"
+        );
+    }
+}
@@ -0,0 +1,602 @@
+//! Invocation and management of Lambda functions
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use aws_sdk_lambda::{
+    primitives::{event_stream::EventReceiver, Blob},
+    types::{
+        error::InvokeWithResponseStreamResponseEventError, InvokeWithResponseStreamResponseEvent,
+    },
+};
+
+use super::{tags::TagList, Error, RegionClient};
+
+string_newtype!(FunctionName);
+
+impl FunctionName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+arn_newtype!(FunctionArn);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvocationType {
+    /// Wait for the function to finish and return its response
+    RequestResponse,
+    /// Queue the invocation and return immediately
+    Event,
+}
+
+impl From<InvocationType> for aws_sdk_lambda::types::InvocationType {
+    fn from(value: InvocationType) -> Self {
+        match value {
+            InvocationType::RequestResponse => Self::RequestResponse,
+            InvocationType::Event => Self::Event,
+        }
+    }
+}
+
+/// The raw bytes sent to or received from a function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Payload(Vec<u8>);
+
+impl Payload {
+    pub const fn new(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+
+    pub const fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_json<T: Serialize>(value: &T) -> Result<Self, Error> {
+        Ok(Self(serde_json::to_vec(value).map_err(|e| {
+            Error::InvalidPayload {
+                message: e.to_string(),
+            }
+        })?))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn parse_json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_slice(&self.0).map_err(|e| Error::InvalidPayload {
+            message: e.to_string(),
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Blob> for Payload {
+    fn from(value: Blob) -> Self {
+        Self(value.into_inner())
+    }
+}
+
+/// An error raised by the function code itself, as opposed to an error when
+/// talking to the Lambda API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionError {
+    kind: String,
+    payload: Payload,
+}
+
+impl FunctionError {
+    /// `Handled` or `Unhandled` for regular invocations, the error code for
+    /// streaming invocations
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub const fn payload(&self) -> &Payload {
+        &self.payload
+    }
+}
+
+impl fmt::Display for FunctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "function error ({}): {}",
+            self.kind,
+            String::from_utf8_lossy(self.payload.as_bytes())
+        )
+    }
+}
+
+impl std::error::Error for FunctionError {}
+
+#[derive(Debug)]
+pub struct Invocation {
+    status_code: i32,
+    executed_version: Option<String>,
+    log_result: Option<String>,
+    outcome: Result<Payload, FunctionError>,
+}
+
+impl Invocation {
+    pub const fn status_code(&self) -> i32 {
+        self.status_code
+    }
+
+    pub fn executed_version(&self) -> Option<&str> {
+        self.executed_version.as_deref()
+    }
+
+    /// Base64-encoded tail of the execution log, if requested
+    pub fn log_result(&self) -> Option<&str> {
+        self.log_result.as_deref()
+    }
+
+    pub const fn outcome(&self) -> Result<&Payload, &FunctionError> {
+        self.outcome.as_ref()
+    }
+
+    pub fn into_outcome(self) -> Result<Payload, FunctionError> {
+        self.outcome
+    }
+}
+
+impl From<aws_sdk_lambda::operation::invoke::InvokeOutput> for Invocation {
+    fn from(output: aws_sdk_lambda::operation::invoke::InvokeOutput) -> Self {
+        let payload = output.payload.map_or_else(Payload::empty, Into::into);
+        Self {
+            status_code: output.status_code,
+            executed_version: output.executed_version,
+            log_result: output.log_result,
+            outcome: match output.function_error {
+                Some(kind) => Err(FunctionError { kind, payload }),
+                None => Ok(payload),
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StreamEvent {
+    Chunk(Payload),
+    Complete {
+        log_result: Option<String>,
+        outcome: Result<(), FunctionError>,
+    },
+}
+
+/// The response of a streaming invocation, see [`Function::invoke_streaming()`].
+#[derive(Debug)]
+pub struct InvocationStream {
+    status_code: i32,
+    executed_version: Option<String>,
+    receiver: EventReceiver<
+        InvokeWithResponseStreamResponseEvent,
+        InvokeWithResponseStreamResponseEventError,
+    >,
+}
+
+impl InvocationStream {
+    pub const fn status_code(&self) -> i32 {
+        self.status_code
+    }
+
+    pub fn executed_version(&self) -> Option<&str> {
+        self.executed_version.as_deref()
+    }
+
+    /// Returns the next event of the stream, or `None` when the stream has ended.
+    ///
+    /// Unknown event types are skipped.
+    pub async fn next_event(&mut self) -> Result<Option<StreamEvent>, Error> {
+        loop {
+            match self.receiver.recv().await? {
+                None => return Ok(None),
+                Some(InvokeWithResponseStreamResponseEvent::PayloadChunk(update)) => {
+                    return Ok(Some(StreamEvent::Chunk(
+                        update.payload.map_or_else(Payload::empty, Into::into),
+                    )))
+                }
+                Some(InvokeWithResponseStreamResponseEvent::InvokeComplete(complete)) => {
+                    return Ok(Some(StreamEvent::Complete {
+                        log_result: complete.log_result,
+                        outcome: match complete.error_code {
+                            Some(kind) => Err(FunctionError {
+                                kind,
+                                payload: complete
+                                    .error_details
+                                    .map_or_else(Payload::empty, |details| {
+                                        Payload(details.into_bytes())
+                                    }),
+                            }),
+                            None => Ok(()),
+                        },
+                    }))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Function {
+    name: FunctionName,
+    arn: FunctionArn,
+    runtime: Option<String>,
+    handler: Option<String>,
+    memory_size: Option<i32>,
+    timeout: Option<i32>,
+    version: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl TryFrom<aws_sdk_lambda::types::FunctionConfiguration> for Function {
+    type Error = Error;
+
+    fn try_from(
+        function: aws_sdk_lambda::types::FunctionConfiguration,
+    ) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                function.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            name: FunctionName(extract!(function_name)?),
+            arn: FunctionArn::parse(&extract!(function_arn)?)?,
+            runtime: function.runtime.map(|runtime| runtime.as_str().to_owned()),
+            handler: function.handler,
+            memory_size: function.memory_size,
+            timeout: function.timeout,
+            version: function.version,
+            last_modified: function.last_modified,
+        })
+    }
+}
+
+impl Function {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .lambda
+            .list_functions()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_name(
+        client: &RegionClient,
+        name: &FunctionName,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .lambda
+            .get_function_configuration()
+            .function_name(name.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(
+                aws_sdk_lambda::types::FunctionConfiguration::builder()
+                    .set_function_name(output.function_name)
+                    .set_function_arn(output.function_arn)
+                    .set_runtime(output.runtime)
+                    .set_handler(output.handler)
+                    .set_memory_size(output.memory_size)
+                    .set_timeout(output.timeout)
+                    .set_version(output.version)
+                    .set_last_modified(output.last_modified)
+                    .build()
+                    .try_into()?,
+            )),
+            Err(e) => match e.as_service_error() {
+                Some(service_error) if service_error.is_resource_not_found_exception() => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    pub const fn name(&self) -> &FunctionName {
+        &self.name
+    }
+
+    pub const fn arn(&self) -> &FunctionArn {
+        &self.arn
+    }
+
+    pub fn runtime(&self) -> Option<&str> {
+        self.runtime.as_deref()
+    }
+
+    pub fn handler(&self) -> Option<&str> {
+        self.handler.as_deref()
+    }
+
+    pub const fn memory_size(&self) -> Option<i32> {
+        self.memory_size
+    }
+
+    pub const fn timeout(&self) -> Option<i32> {
+        self.timeout
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    pub fn last_modified(&self) -> Option<&str> {
+        self.last_modified.as_deref()
+    }
+
+    /// Invokes the function.
+    ///
+    /// An `Err` is only returned if the invocation itself failed. Errors raised
+    /// by the function are reported via [`Invocation::outcome()`].
+    pub async fn invoke(
+        &self,
+        client: &RegionClient,
+        invocation_type: InvocationType,
+        payload: Payload,
+    ) -> Result<Invocation, Error> {
+        Ok(client
+            .main
+            .lambda
+            .invoke()
+            .function_name(self.arn.to_string())
+            .invocation_type(invocation_type.into())
+            .payload(Blob::new(payload.into_bytes()))
+            .send()
+            .await?
+            .into())
+    }
+
+    /// Invokes the function with response streaming. The function has to be
+    /// configured for streaming responses.
+    pub async fn invoke_streaming(
+        &self,
+        client: &RegionClient,
+        payload: Payload,
+    ) -> Result<InvocationStream, Error> {
+        let output = client
+            .main
+            .lambda
+            .invoke_with_response_stream()
+            .function_name(self.arn.to_string())
+            .payload(Blob::new(payload.into_bytes()))
+            .send()
+            .await?;
+
+        Ok(InvocationStream {
+            status_code: output.status_code,
+            executed_version: output.executed_version,
+            receiver: output.event_stream,
+        })
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .main
+            .lambda
+            .list_tags()
+            .resource(self.arn.to_string())
+            .send()
+            .await?
+            .tags
+            .unwrap_or_default()
+            .into())
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .lambda
+            .tag_resource()
+            .resource(self.arn.to_string())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use aws_sdk_lambda::{operation::invoke::InvokeOutput, types::FunctionConfiguration};
+
+    use super::*;
+
+    #[test]
+    fn invocation_outcome() {
+        let invocation = Invocation::from(
+            InvokeOutput::builder()
+                .status_code(200)
+                .payload(Blob::new(r#"{"ok":true}"#))
+                .build(),
+        );
+        assert_eq!(
+            invocation.outcome(),
+            Ok(&Payload::new(br#"{"ok":true}"#.to_vec()))
+        );
+
+        let invocation = Invocation::from(
+            InvokeOutput::builder()
+                .status_code(200)
+                .function_error("Unhandled")
+                .payload(Blob::new(r#"{"errorMessage":"boom"}"#))
+                .build(),
+        );
+        let error = invocation.into_outcome().unwrap_err();
+        assert_eq!(error.kind(), "Unhandled");
+        assert_eq!(error.payload().as_bytes(), br#"{"errorMessage":"boom"}"#);
+
+        let invocation = Invocation::from(InvokeOutput::builder().status_code(202).build());
+        assert_eq!(invocation.outcome(), Ok(&Payload::empty()));
+    }
+
+    #[test]
+    fn function_from_configuration() {
+        let function = Function::try_from(
+            FunctionConfiguration::builder()
+                .function_name("worker")
+                .function_arn("arn:aws:lambda:eu-central-1:123456789012:function:worker")
+                .runtime(aws_sdk_lambda::types::Runtime::Python312)
+                .memory_size(256)
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(function.name().as_str(), "worker");
+        assert_eq!(function.arn().inner().region(), Some("eu-central-1"));
+        assert_eq!(function.runtime(), Some("python3.12"));
+        assert_eq!(function.memory_size(), Some(256));
+
+        assert!(matches!(
+            Function::try_from(
+                FunctionConfiguration::builder()
+                    .function_name("worker")
+                    .build()
+            ),
+            Err(Error::UnexpectedNoneValue { .. })
+        ));
+        assert!(matches!(
+            Function::try_from(
+                FunctionConfiguration::builder()
+                    .function_name("worker")
+                    .function_arn("worker")
+                    .build()
+            ),
+            Err(Error::InvalidArn(_))
+        ));
+    }
+
+    #[test]
+    fn tags_as_map() {
+        let map = HashMap::from([
+            ("team".to_owned(), "infra".to_owned()),
+            ("env".to_owned(), "prod".to_owned()),
+        ]);
+
+        let tags = TagList::from(map.clone());
+        assert_eq!(
+            tags.get("team".to_owned()).map(|tag| tag.value().as_str()),
+            Some("infra")
+        );
+
+        assert_eq!(HashMap::<String, String>::from(tags), map);
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        const FUNCTION_ARN: &str = "arn:aws:lambda:eu-central-1:123456789012:function:worker";
+
+        #[test]
+        fn list_follows_markers() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("Marker", "page2"),
+                    MockResponse::ok(
+                        r#"{"Functions": [{"FunctionName": "api", "FunctionArn": "arn:aws:lambda:eu-central-1:123456789012:function:api"}]}"#,
+                    ),
+                )
+                .on(
+                    Matcher::predicate(|request| request.uri.contains("/functions")),
+                    MockResponse::ok(
+                        r#"{"Functions": [{"FunctionName": "worker", "FunctionArn": "arn:aws:lambda:eu-central-1:123456789012:function:worker", "Runtime": "python3.12"}], "NextMarker": "page2"}"#,
+                    ),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let functions = block_on(Function::list(&client)).unwrap();
+
+            assert_eq!(
+                functions
+                    .iter()
+                    .map(|function| function.name().as_str())
+                    .collect::<Vec<_>>(),
+                ["worker", "api"]
+            );
+            let first = functions.first().unwrap();
+            assert_eq!(first.arn().to_string(), FUNCTION_ARN);
+            assert_eq!(first.runtime(), Some("python3.12"));
+            assert_eq!(http.requests().unwrap().len(), 2);
+        }
+
+        #[test]
+        fn tags() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::predicate(|request| request.method == "GET"),
+                    MockResponse::ok(r#"{"Tags": {"team": "infra", "env": "prod"}}"#),
+                )
+                .on(Matcher::Any, MockResponse::ok("{}"));
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let function = Function::try_from(
+                FunctionConfiguration::builder()
+                    .function_name("worker")
+                    .function_arn(FUNCTION_ARN)
+                    .build(),
+            )
+            .unwrap();
+
+            let tags = block_on(function.tags(&client)).unwrap();
+            assert_eq!(
+                tags.as_slice(),
+                [
+                    RawTag::new("env".to_owned(), "prod".to_owned()),
+                    RawTag::new("team".to_owned(), "infra".to_owned()),
+                ]
+            );
+
+            block_on(function.add_tags(&client, tags)).unwrap();
+            let request = http.requests().unwrap().pop().unwrap();
+            assert!(request.uri.contains("function%3Aworker"));
+            assert_eq!(
+                request
+                    .json_param::<HashMap<String, String>>("Tags")
+                    .unwrap(),
+                HashMap::from([
+                    ("team".to_owned(), "infra".to_owned()),
+                    ("env".to_owned(), "prod".to_owned()),
+                ])
+            );
+        }
+    }
+}
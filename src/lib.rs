@@ -1,8 +1,24 @@
 //! Provides an opinionated interface to the AWS API
+//!
+//! # Cancellation
+//!
+//! All async functions can be cancelled by dropping their future, e.g. with
+//! [`tokio::time::timeout`] or [`tokio::select!`]. Cancelling never leaves
+//! the clients in an inconsistent state, but requests that were already sent
+//! may still take effect on the AWS side. Retrying after cancellation is
+//! therefore safe for reads (`list`, `describe`, `get`, tag lookups and
+//! waiters), and for operations that set a state idempotently (`set_tags`,
+//! `add_tags`, `remove_tags`, stopping instances). Functions that create
+//! resources (e.g. [`start_ec2_instance()`], [`create_cloudformation_stack()`])
+//! or send messages may have completed without the caller knowing, so check
+//! for the resource before retrying. A cancelled
+//! [`MultipartUploader::upload()`](s3::MultipartUploader::upload)
+//! leaves an incomplete upload behind, see its documentation.
 
 extern crate self as aws_lib;
 
 use std::{
+    collections::HashMap,
     fmt::{self, Debug},
     net,
     time::Duration,
@@ -10,10 +26,14 @@ use std::{
 
 use aws_config::retry::RetryConfig;
 use aws_sdk_ec2::client::Waiters;
+use aws_sdk_ec2::config::{
+    SharedCredentialsProvider, SharedHttpClient, SharedIdentityCache, SharedInterceptor,
+};
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+mod batch;
 mod error;
 pub use error::Error;
 
@@ -73,6 +93,10 @@ wrap_aws_enum!(InstanceStateName);
 wrap_aws_enum!(InstanceType);
 
 #[derive(Debug)]
+#[expect(
+    clippy::struct_field_names,
+    reason = "the fields are named like in the EC2 API"
+)]
 pub struct Instance {
     tags: TagList,
     instance_type: InstanceType,
@@ -114,6 +138,27 @@ impl Instance {
         })
     }
 
+    /// Returns all instances matching all `filters`
+    pub async fn list(
+        client: &RegionClient,
+        filters: Vec<filter::Filter>,
+    ) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_instances()
+            .set_filters(Some(filters.into_iter().map(Into::into).collect()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|reservation| reservation.instances.unwrap_or_default())
+            .map(Self::try_from_aws)
+            .collect()
+    }
+
     pub fn get_tag(&self, key: TagKey) -> Option<&RawTag> {
         self.tags.get(key)
     }
@@ -158,6 +203,22 @@ impl Instance {
         Ok(())
     }
 
+    /// Checks whether [`Instance::stop()`] would succeed
+    pub async fn stop_dry_run(
+        &self,
+        client: &RegionClient,
+    ) -> Result<dry_run::DryRunResult, Error> {
+        use dry_run::DryRun as _;
+
+        client
+            .main
+            .ec2
+            .stop_instances()
+            .instance_ids(self.instance_id().as_str())
+            .send_dry_run()
+            .await
+    }
+
     pub async fn wait_for_stop(
         &self,
         client: &RegionClient,
@@ -206,6 +267,14 @@ pub enum Region {
     EuCentral1,
     #[cfg_attr(feature = "serde", serde(rename = "us-east-1"))]
     UsEast1,
+    #[cfg_attr(feature = "serde", serde(rename = "cn-north-1"))]
+    CnNorth1,
+    #[cfg_attr(feature = "serde", serde(rename = "cn-northwest-1"))]
+    CnNorthwest1,
+    #[cfg_attr(feature = "serde", serde(rename = "us-gov-west-1"))]
+    UsGovWest1,
+    #[cfg_attr(feature = "serde", serde(rename = "us-gov-east-1"))]
+    UsGovEast1,
 }
 
 impl fmt::Display for Region {
@@ -228,17 +297,41 @@ impl Region {
         match self {
             Self::EuCentral1 => "eu-central-1",
             Self::UsEast1 => "us-east-1",
+            Self::CnNorth1 => "cn-north-1",
+            Self::CnNorthwest1 => "cn-northwest-1",
+            Self::UsGovWest1 => "us-gov-west-1",
+            Self::UsGovEast1 => "us-gov-east-1",
+        }
+    }
+
+    /// The inverse of [`Self::as_str()`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "eu-central-1" => Some(Self::EuCentral1),
+            "us-east-1" => Some(Self::UsEast1),
+            "cn-north-1" => Some(Self::CnNorth1),
+            "cn-northwest-1" => Some(Self::CnNorthwest1),
+            "us-gov-west-1" => Some(Self::UsGovWest1),
+            "us-gov-east-1" => Some(Self::UsGovEast1),
+            _ => None,
         }
     }
 
+    /// All regions of the standard partition. Regions in China and
+    /// GovCloud need separate accounts and are therefore not included.
     pub const fn all() -> [Self; 2] {
         [Self::EuCentral1, Self::UsEast1]
     }
 
     const fn name(self) -> &'static str {
+        self.as_str()
+    }
+
+    pub const fn partition(self) -> partition::Partition {
         match self {
-            Self::EuCentral1 => "eu-central-1",
-            Self::UsEast1 => "us-east-1",
+            Self::EuCentral1 | Self::UsEast1 => partition::Partition::Aws,
+            Self::CnNorth1 | Self::CnNorthwest1 => partition::Partition::AwsCn,
+            Self::UsGovWest1 | Self::UsGovEast1 => partition::Partition::AwsUsGov,
         }
     }
 
@@ -246,7 +339,11 @@ impl Region {
         ShieldPop(
             match self {
                 Self::EuCentral1 => "eu-central-1",
-                Self::UsEast1 => "us-east-1",
+                // Origin Shield is not available outside of the standard
+                // partition, the closest standard region is used instead
+                Self::UsEast1 | Self::UsGovEast1 => "us-east-1",
+                Self::CnNorth1 | Self::CnNorthwest1 => "ap-northeast-1",
+                Self::UsGovWest1 => "us-west-2",
             }
             .to_owned(),
         )
@@ -256,8 +353,39 @@ impl Region {
 #[derive(Debug, Clone)]
 pub struct RegionClientMain {
     pub ec2: aws_sdk_ec2::Client,
+    pub ec2instanceconnect: aws_sdk_ec2instanceconnect::Client,
     pub efs: aws_sdk_efs::Client,
     pub route53: aws_sdk_route53::Client,
+    pub lambda: aws_sdk_lambda::Client,
+    pub sqs: aws_sdk_sqs::Client,
+    pub dynamodb: aws_sdk_dynamodb::Client,
+    pub cloudwatch: aws_sdk_cloudwatch::Client,
+    pub logs: aws_sdk_cloudwatchlogs::Client,
+    pub ecs: aws_sdk_ecs::Client,
+    pub autoscaling: aws_sdk_autoscaling::Client,
+    pub rds: aws_sdk_rds::Client,
+    pub ssm: aws_sdk_ssm::Client,
+    pub secretsmanager: aws_sdk_secretsmanager::Client,
+    pub kms: aws_sdk_kms::Client,
+    pub elbv2: aws_sdk_elasticloadbalancingv2::Client,
+    pub ecr: aws_sdk_ecr::Client,
+    pub eventbridge: aws_sdk_eventbridge::Client,
+    pub sfn: aws_sdk_sfn::Client,
+    pub costexplorer: aws_sdk_costexplorer::Client,
+    pub configservice: aws_sdk_config::Client,
+    pub organizations: aws_sdk_organizations::Client,
+    pub cloudtrail: aws_sdk_cloudtrail::Client,
+    pub s3: aws_sdk_s3::Client,
+    pub tagging: aws_sdk_resourcegroupstagging::Client,
+    pub sts: aws_sdk_sts::Client,
+    pub servicequotas: aws_sdk_servicequotas::Client,
+    pub elasticache: aws_sdk_elasticache::Client,
+    pub opensearch: aws_sdk_opensearch::Client,
+    pub eks: aws_sdk_eks::Client,
+    pub athena: aws_sdk_athena::Client,
+    pub backup: aws_sdk_backup::Client,
+    pub kinesis: aws_sdk_kinesis::Client,
+    pub firehose: aws_sdk_firehose::Client,
 }
 
 #[derive(Debug, Clone)]
@@ -266,13 +394,171 @@ pub struct RegionClientCdn {
     pub cloudformation: aws_sdk_cloudformation::Client,
 }
 
+/// The service clients of one region
+///
+/// Cloning is cheap: every service client is a handle to reference counted
+/// state, so a clone only increments reference counts. All clones, and all
+/// region clients returned by one call of [`load_sdk_clients()`], share the
+/// same connection pool and credentials cache. Clone the client into each
+/// task instead of loading new clients.
 #[derive(Debug, Clone)]
 pub struct RegionClient {
     pub region: Region,
     pub main: RegionClientMain,
     pub cdn: RegionClientCdn,
+    /// `None` if requests to buckets in other regions are not redirected,
+    /// see [`ClientConfig::disable_s3_redirects`]
+    pub s3_bucket_regions: Option<s3::BucketRegions>,
+    /// The credentials provider the service clients were built with, for
+    /// signing requests outside of the SDK (e.g. [`eks::get_token()`])
+    pub credentials: Option<SharedCredentialsProvider>,
+    /// Set if audit is enabled, to also look up the caller identity of
+    /// credentials from [`Self::with_config()`]
+    identity_lookup: Option<audit::IdentityLookup>,
+}
+
+/// Changes to the config of a [`RegionClient`], see
+/// [`RegionClient::with_config()`]. Fields that are `None` keep the value of
+/// the original client.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverride {
+    /// Services that are only reachable in a single region (CloudFormation,
+    /// Cost Explorer, Organizations) use the global region of the partition
+    /// of this region
+    pub region: Option<Region>,
+    /// Used by all service clients, including the CDN ones, e.g. the
+    /// credentials of an assumed role in another account. With audit
+    /// enabled, their caller identity is looked up like for the original
+    /// credentials.
+    pub credentials: Option<SharedCredentialsProvider>,
+    pub retry: Option<RetryConfig>,
+}
+
+impl RegionClient {
+    /// Returns a copy of the client with `config_override` applied, for single
+    /// calls against another region or with other credentials
+    ///
+    /// The service clients are rebuilt from their current config, so
+    /// timeouts, metrics, rate limits and the connection pool are kept.
+    /// This is much cheaper than loading new clients, but still more expensive
+    /// than [`Clone`], so reuse the returned client for multiple calls.
+    #[must_use]
+    pub fn with_config(&self, config_override: &ConfigOverride) -> Self {
+        self.rebuild(config_override, None)
+    }
+
+    /// Like [`Self::with_config()`], optionally adding `interceptor` to all
+    /// service clients
+    pub(crate) fn rebuild(
+        &self,
+        config_override: &ConfigOverride,
+        interceptor: Option<&SharedInterceptor>,
+    ) -> Self {
+        let region = config_override.region.unwrap_or(self.region);
+        let identity_lookup = self
+            .identity_lookup
+            .as_ref()
+            .map(|lookup| lookup.with_region(region.name()));
+        let credentials = config_override.credentials.as_ref().map(|credentials| {
+            identity_lookup.as_ref().map_or_else(
+                || credentials.clone(),
+                |lookup| lookup.wrap(credentials.clone()),
+            )
+        });
+
+        macro_rules! client {
+            ($client:expr, $sdk:ident, $region:expr) => {{
+                let mut builder = $client.config().to_builder();
+                if config_override.region.is_some() {
+                    builder = builder.region($sdk::config::Region::new($region));
+                }
+                if let Some(ref credentials) = credentials {
+                    builder = builder.credentials_provider(credentials.clone());
+                }
+                if let Some(ref retry) = config_override.retry {
+                    builder = builder.retry_config(retry.clone());
+                }
+                if let Some(interceptor) = interceptor {
+                    builder = builder.interceptor(interceptor.clone());
+                }
+                $sdk::Client::from_conf(builder.build())
+            }};
+            ($client:expr, $sdk:ident) => {
+                client!($client, $sdk, region.name())
+            };
+        }
+
+        let global_region = region.partition().global_region();
+
+        Self {
+            region,
+            main: RegionClientMain {
+                ec2: client!(self.main.ec2, aws_sdk_ec2),
+                ec2instanceconnect: client!(
+                    self.main.ec2instanceconnect,
+                    aws_sdk_ec2instanceconnect
+                ),
+                efs: client!(self.main.efs, aws_sdk_efs),
+                route53: client!(self.main.route53, aws_sdk_route53),
+                lambda: client!(self.main.lambda, aws_sdk_lambda),
+                sqs: client!(self.main.sqs, aws_sdk_sqs),
+                dynamodb: client!(self.main.dynamodb, aws_sdk_dynamodb),
+                cloudwatch: client!(self.main.cloudwatch, aws_sdk_cloudwatch),
+                logs: client!(self.main.logs, aws_sdk_cloudwatchlogs),
+                ecs: client!(self.main.ecs, aws_sdk_ecs),
+                autoscaling: client!(self.main.autoscaling, aws_sdk_autoscaling),
+                rds: client!(self.main.rds, aws_sdk_rds),
+                ssm: client!(self.main.ssm, aws_sdk_ssm),
+                secretsmanager: client!(self.main.secretsmanager, aws_sdk_secretsmanager),
+                kms: client!(self.main.kms, aws_sdk_kms),
+                elbv2: client!(self.main.elbv2, aws_sdk_elasticloadbalancingv2),
+                ecr: client!(self.main.ecr, aws_sdk_ecr),
+                eventbridge: client!(self.main.eventbridge, aws_sdk_eventbridge),
+                sfn: client!(self.main.sfn, aws_sdk_sfn),
+                costexplorer: client!(self.main.costexplorer, aws_sdk_costexplorer, global_region),
+                configservice: client!(self.main.configservice, aws_sdk_config),
+                organizations: client!(
+                    self.main.organizations,
+                    aws_sdk_organizations,
+                    global_region
+                ),
+                cloudtrail: client!(self.main.cloudtrail, aws_sdk_cloudtrail),
+                s3: client!(self.main.s3, aws_sdk_s3),
+                tagging: client!(self.main.tagging, aws_sdk_resourcegroupstagging),
+                sts: client!(self.main.sts, aws_sdk_sts),
+                servicequotas: client!(self.main.servicequotas, aws_sdk_servicequotas),
+                elasticache: client!(self.main.elasticache, aws_sdk_elasticache),
+                opensearch: client!(self.main.opensearch, aws_sdk_opensearch),
+                eks: client!(self.main.eks, aws_sdk_eks),
+                athena: client!(self.main.athena, aws_sdk_athena),
+                backup: client!(self.main.backup, aws_sdk_backup),
+                kinesis: client!(self.main.kinesis, aws_sdk_kinesis),
+                firehose: client!(self.main.firehose, aws_sdk_firehose),
+            },
+            cdn: RegionClientCdn {
+                cloudfront: client!(self.cdn.cloudfront, aws_sdk_cloudfront),
+                cloudformation: client!(
+                    self.cdn.cloudformation,
+                    aws_sdk_cloudformation,
+                    global_region
+                ),
+            },
+            s3_bucket_regions: self.s3_bucket_regions.clone(),
+            credentials: config_override
+                .credentials
+                .clone()
+                .or_else(|| self.credentials.clone()),
+            identity_lookup,
+        }
+    }
 }
 
+// Clients are moved into spawned tasks, so they must stay thread safe
+const _: fn() = || {
+    const fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+    assert_shareable::<RegionClient>();
+};
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct InstanceProfileName(String);
@@ -294,19 +580,27 @@ impl InstanceKeypairName {
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SecurityGroupId(String);
 
 impl SecurityGroupId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
 }
 
+/// Operations are in the [`vpc`] module
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct SecurityGroup {
     id: SecurityGroupId,
+    name: Option<String>,
+    vpc_id: Option<vpc::VpcId>,
+    tags: TagList,
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -350,6 +644,106 @@ macro_rules! string_newtype {
     };
 }
 
+/// The [`Arn`](arn::Arn) of one kind of resource, so that partition, region
+/// and account are validated when the ARN is parsed
+macro_rules! arn_newtype {
+    ($name:ident) => {
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+        pub struct $name($crate::arn::Arn);
+
+        impl $name {
+            pub const fn new(arn: $crate::arn::Arn) -> Self {
+                Self(arn)
+            }
+
+            pub fn parse(value: &str) -> Result<Self, $crate::arn::ParseArnError> {
+                $crate::arn::Arn::parse(value).map(Self)
+            }
+
+            pub const fn inner(&self) -> &$crate::arn::Arn {
+                &self.0
+            }
+
+            pub fn into_inner(self) -> $crate::arn::Arn {
+                self.0
+            }
+        }
+
+        impl From<$name> for $crate::arn::Arn {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    };
+}
+
+pub mod ami;
+pub mod arn;
+pub mod athena;
+pub mod audit;
+pub mod autoscaling;
+pub mod backup;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+pub mod cloudformation;
+pub mod cloudfront;
+pub mod cloudtrail;
+pub mod cloudwatch;
+pub mod config;
+pub mod config_service;
+pub mod costexplorer;
+pub mod dry_run;
+pub mod dynamodb;
+pub mod ebs;
+pub mod ecr;
+pub mod ecs;
+pub mod eks;
+pub mod elasticache;
+pub mod elbv2;
+pub mod eventbridge;
+pub mod eventstream;
+pub mod filter;
+pub mod firehose;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod imds;
+pub mod instance_connect;
+pub mod kinesis;
+pub mod kms;
+pub mod lambda;
+pub mod logs;
+pub mod metrics;
+pub mod opensearch;
+pub mod organizations;
+pub mod partition;
+pub mod profile;
+pub mod protocol;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ratelimit;
+pub mod rds;
+pub mod s3;
+pub mod secretsmanager;
+pub mod service_quotas;
+#[cfg(all(feature = "session", not(target_arch = "wasm32")))]
+pub mod session;
+pub mod sfn;
+pub mod spot;
+pub mod sqs;
+pub mod ssm;
+pub mod sts;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod vpc;
+pub mod waiter;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
 string_newtype!(AvailabilityZone);
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -357,6 +751,8 @@ string_newtype!(AvailabilityZone);
 pub struct Subnet {
     pub id: SubnetId,
     pub availability_zone: AvailabilityZone,
+    pub vpc_id: Option<vpc::VpcId>,
+    pub tags: TagList,
 }
 
 impl TryFrom<aws_sdk_ec2::types::Subnet> for Subnet {
@@ -374,6 +770,8 @@ impl TryFrom<aws_sdk_ec2::types::Subnet> for Subnet {
         Ok(Self {
             id: SubnetId(extract!(subnet_id)?),
             availability_zone: AvailabilityZone(extract!(availability_zone)?),
+            vpc_id: subnet.vpc_id.map(vpc::VpcId::new),
+            tags: subnet.tags.unwrap_or_default().try_into()?,
         })
     }
 }
@@ -400,6 +798,8 @@ pub struct Ami {
     pub id: AmiId,
     pub tags: TagList,
     pub creation_date: Timestamp,
+    /// Snapshots of the EBS volumes of the AMI
+    pub snapshots: Vec<ebs::SnapshotId>,
 }
 
 impl TryFrom<aws_sdk_ec2::types::Image> for Ami {
@@ -416,8 +816,15 @@ impl TryFrom<aws_sdk_ec2::types::Image> for Ami {
 
         Ok(Self {
             id: AmiId(extract!(image_id)?),
-            tags: extract!(tags)?.try_into()?,
+            tags: image.tags.unwrap_or_default().try_into()?,
             creation_date: RawImageCreationDate(extract!(creation_date)?).try_into()?,
+            snapshots: image
+                .block_device_mappings
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|mapping| mapping.ebs.and_then(|ebs| ebs.snapshot_id))
+                .map(ebs::SnapshotId::new)
+                .collect(),
         })
     }
 }
@@ -432,6 +839,10 @@ impl Timestamp {
         Self(value)
     }
 
+    pub fn now() -> Self {
+        Self(Utc::now())
+    }
+
     pub const fn inner(&self) -> &DateTime<Utc> {
         &self.0
     }
@@ -464,6 +875,27 @@ impl From<Timestamp> for RawTagValue {
     }
 }
 
+impl TryFrom<aws_sdk_ec2::primitives::DateTime> for Timestamp {
+    type Error = Error;
+
+    fn try_from(value: aws_sdk_ec2::primitives::DateTime) -> Result<Self, Self::Error> {
+        Ok(Self(
+            DateTime::from_timestamp(value.secs(), value.subsec_nanos()).ok_or_else(|| {
+                Error::InvalidTimestampError {
+                    value: value.to_string(),
+                    message: "timestamp out of range".to_owned(),
+                }
+            })?,
+        ))
+    }
+}
+
+impl From<Timestamp> for aws_sdk_ec2::primitives::DateTime {
+    fn from(value: Timestamp) -> Self {
+        Self::from_secs_and_nanos(value.0.timestamp(), value.0.timestamp_subsec_nanos())
+    }
+}
+
 struct RawImageCreationDate(String);
 
 impl TryFrom<RawImageCreationDate> for Timestamp {
@@ -702,6 +1134,7 @@ impl From<aws_sdk_cloudfront::types::Origin> for CloudfrontOrigin {
 #[derive(Debug, Clone)]
 pub struct CloudfrontDistribution {
     pub id: CloudfrontDistributionId,
+    pub arn: String,
     pub status: CloudfrontDistributionStatus,
     pub domain: CloudfrontDistributionDomain,
     pub origins: Vec<CloudfrontOrigin>,
@@ -715,6 +1148,7 @@ impl TryFrom<aws_sdk_cloudfront::types::DistributionSummary> for CloudfrontDistr
     ) -> Result<Self, Self::Error> {
         Ok(Self {
             id: CloudfrontDistributionId(distribution.id),
+            arn: distribution.arn,
             status: distribution.status.into(),
             domain: distribution.domain_name.into(),
             origins: distribution.origins.map_or_else(Vec::new, |origins| {
@@ -724,43 +1158,140 @@ impl TryFrom<aws_sdk_cloudfront::types::DistributionSummary> for CloudfrontDistr
     }
 }
 
-impl CloudfrontDistribution {
-    pub fn origins(&self) -> &[CloudfrontOrigin] {
-        &self.origins
-    }
-
-    pub const fn domain(&self) -> &CloudfrontDistributionDomain {
-        &self.domain
-    }
-
-    pub const fn status(&self) -> &CloudfrontDistributionStatus {
-        &self.status
-    }
-}
-
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct ProfileName(String);
 
 impl ProfileName {
     pub const fn new(value: String) -> Self {
         Self(value)
     }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct ProfileConfig {
     pub profile_name_main: ProfileName,
     pub profile_name_cdn: ProfileName,
 }
 
+/// Timeouts of requests. `None` disables the timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Establishing a connection, including the TLS handshake
+    pub connect: Option<Duration>,
+    /// Waiting for data on an established connection
+    pub read: Option<Duration>,
+    /// A single attempt of a request
+    pub attempt: Option<Duration>,
+    /// A whole operation, including all retries
+    pub operation: Option<Duration>,
+}
+
+impl Timeouts {
+    fn to_sdk(self) -> aws_config::timeout::TimeoutConfig {
+        let mut builder = aws_config::timeout::TimeoutConfig::builder();
+        let _builder = builder
+            .set_connect_timeout(self.connect)
+            .set_read_timeout(self.read)
+            .set_operation_attempt_timeout(self.attempt)
+            .set_operation_timeout(self.operation);
+        builder.build()
+    }
+}
+
+/// Without a read timeout, a hung endpoint blocks callers forever, so the
+/// defaults set both connect and read timeouts. The total time of an
+/// operation is not limited, as it depends on the number of retries.
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Some(Duration::from_secs(5)),
+            read: Some(Duration::from_secs(60)),
+            attempt: None,
+            operation: None,
+        }
+    }
+}
+
+/// Settings that apply to each service client
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    pub timeouts: Timeouts,
+    pub metrics: Option<std::sync::Arc<dyn metrics::MetricsSink>>,
+    /// Every service client gets its own rate limiter, as API rate limits
+    /// are per service
+    #[cfg(not(target_arch = "wasm32"))]
+    pub rate_limit: Option<ratelimit::RateLimitConfig>,
+    /// Records all mutating operations
+    pub audit: Option<std::sync::Arc<dyn audit::AuditSink>>,
+    /// Return the redirect errors of S3 for buckets in other regions instead
+    /// of following them, see [`s3::BucketRegions`]
+    pub disable_s3_redirects: bool,
+}
+
 pub async fn load_sdk_clients<const C: usize>(
     regions: [Region; C],
     profile_config: ProfileConfig,
 ) -> Vec<RegionClient> {
-    let mut region_clients = vec![];
+    load_sdk_clients_with_config(&regions, &profile_config, &ClientConfig::default()).await
+}
+
+pub async fn load_sdk_clients_with_config(
+    regions: &[Region],
+    profile_config: &ProfileConfig,
+    client_config: &ClientConfig,
+) -> Vec<RegionClient> {
+    load_sdk_clients_with(
+        regions,
+        profile_config,
+        |loader, _profile| loader,
+        client_config,
+    )
+    .await
+}
+
+/// Connection pool and credentials, shared by the clients of all regions
+///
+/// Each config loader would otherwise create its own HTTP client and
+/// credentials provider, so every region and profile would open separate
+/// connections and fetch separate credentials.
+#[derive(Default)]
+struct SharedComponents {
+    http_client: Option<SharedHttpClient>,
+    identity_cache: Option<SharedIdentityCache>,
+    /// By profile name
+    credentials: HashMap<String, SharedCredentialsProvider>,
+}
+
+impl SharedComponents {
+    fn apply(
+        &self,
+        loader: aws_config::ConfigLoader,
+        profile: &ProfileName,
+    ) -> aws_config::ConfigLoader {
+        let mut loader = loader.profile_name(&profile.0);
+        if let Some(ref http_client) = self.http_client {
+            loader = loader.http_client(http_client.clone());
+        }
+        if let Some(ref identity_cache) = self.identity_cache {
+            loader = loader.identity_cache(identity_cache.clone());
+        }
+        if let Some(credentials) = self.credentials.get(&profile.0) {
+            loader = loader.credentials_provider(credentials.clone());
+        }
+        loader
+    }
 
-    for region in regions {
-        let base_config = || {
+    async fn load(
+        &mut self,
+        customize: impl Fn(aws_config::ConfigLoader, &ProfileName) -> aws_config::ConfigLoader + Sync,
+        profile: &ProfileName,
+        region: &'static str,
+    ) -> aws_config::SdkConfig {
+        let loader = self.apply(
             aws_config::ConfigLoader::default()
                 .retry_config(RetryConfig::standard())
                 .stalled_stream_protection(
@@ -768,51 +1299,230 @@ pub async fn load_sdk_clients<const C: usize>(
                         .grace_period(Duration::from_secs(5))
                         .build(),
                 )
-                .behavior_version(aws_config::BehaviorVersion::latest())
-        };
+                .behavior_version(aws_config::BehaviorVersion::latest()),
+            profile,
+        );
+        let config = customize(loader.region(region), profile).load().await;
+        self.update(&config, profile);
+        config
+    }
+
+    /// Keeps the components of the first loaded config for all later ones
+    fn update(&mut self, config: &aws_config::SdkConfig, profile: &ProfileName) {
+        if self.http_client.is_none() {
+            self.http_client = config.http_client();
+        }
+        if self.identity_cache.is_none() {
+            self.identity_cache = config.identity_cache();
+        }
+        if let Some(credentials) = config.credentials_provider() {
+            let _entry = self
+                .credentials
+                .entry(profile.0.clone())
+                .or_insert(credentials);
+        }
+    }
+}
 
-        let config = base_config()
-            .profile_name(&profile_config.profile_name_main.0)
-            .region(region.name())
-            .load()
-            .await;
+/// Like [`load_sdk_clients()`], but `customize` can modify each config
+/// loader, e.g. to set a different HTTP client. It also gets the profile the
+/// loader is for.
+pub(crate) async fn load_sdk_clients_with(
+    regions: &[Region],
+    profile_config: &ProfileConfig,
+    customize: impl Fn(aws_config::ConfigLoader, &ProfileName) -> aws_config::ConfigLoader + Sync,
+    client_config: &ClientConfig,
+) -> Vec<RegionClient> {
+    let mut region_clients = vec![];
+    let mut shared = SharedComponents::default();
 
-        let config_cdn = base_config()
-            .profile_name(&profile_config.profile_name_cdn.0)
-            .region(region.name())
-            .load()
+    for &region in regions {
+        let config = shared
+            .load(&customize, &profile_config.profile_name_main, region.name())
+            .await;
+        let config_cdn = shared
+            .load(&customize, &profile_config.profile_name_cdn, region.name())
             .await;
 
-        // Cloudformation needs always be run in us-east-1
-        let config_cloudformation = base_config()
-            .profile_name(&profile_config.profile_name_cdn.0)
-            .region(Region::UsEast1.as_str())
-            .load()
+        // Cloudformation needs always be run in us-east-1 (or the
+        // equivalent region of other partitions)
+        let config_cloudformation = shared
+            .load(
+                &customize,
+                &profile_config.profile_name_cdn,
+                region.partition().global_region(),
+            )
             .await;
 
-        let ec2_client = aws_sdk_ec2::Client::new(&config);
-        let cloudfront_client = aws_sdk_cloudfront::Client::new(&config_cdn);
-        let efs_client = aws_sdk_efs::Client::new(&config);
-        let route53_client = aws_sdk_route53::Client::new(&config);
-        let cloudformation_client = aws_sdk_cloudformation::Client::new(&config_cloudformation);
+        // Global services like Cost Explorer and Organizations are only
+        // reachable in a single region per partition
+        let config_global = shared
+            .load(
+                &customize,
+                &profile_config.profile_name_main,
+                region.partition().global_region(),
+            )
+            .await;
 
-        region_clients.push(RegionClient {
+        region_clients.push(region_client_from_configs(
             region,
-            main: RegionClientMain {
-                ec2: ec2_client,
-                efs: efs_client,
-                route53: route53_client,
-            },
-            cdn: RegionClientCdn {
-                cloudfront: cloudfront_client,
-                cloudformation: cloudformation_client,
-            },
-        });
+            &config,
+            &config_cdn,
+            &config_cloudformation,
+            &config_global,
+            client_config,
+        ));
     }
 
     region_clients
 }
 
+#[expect(
+    clippy::similar_names,
+    reason = "the client bindings are named after their services"
+)]
+pub(crate) fn region_client_from_configs(
+    region: Region,
+    config: &aws_config::SdkConfig,
+    config_cdn: &aws_config::SdkConfig,
+    config_cloudformation: &aws_config::SdkConfig,
+    config_global: &aws_config::SdkConfig,
+    client_config: &ClientConfig,
+) -> RegionClient {
+    // The audit log needs the caller identity of each access key, which is
+    // looked up when the credentials are loaded
+    let identities = std::sync::Arc::new(audit::Identities::default());
+    let identity_lookup = client_config
+        .audit
+        .is_some()
+        .then(|| audit::IdentityLookup::new(config, &identities));
+    let audited = |config: &aws_config::SdkConfig| {
+        if client_config.audit.is_some() {
+            audit::IdentityLookup::new(config, &identities).wrap_config(config)
+        } else {
+            config.clone()
+        }
+    };
+    let config = &audited(config);
+    let config_cdn = &audited(config_cdn);
+    let config_cloudformation = &audited(config_cloudformation);
+    let config_global = &audited(config_global);
+
+    // Clients are built from their service config instead of the shared
+    // config, as interceptors can only be set per service
+    macro_rules! client {
+        ($sdk:ident, $config:expr) => {{
+            let mut builder = $sdk::config::Builder::from($config)
+                .timeout_config(client_config.timeouts.to_sdk());
+            if let Some(ref sink) = client_config.metrics {
+                builder = builder.interceptor(metrics::MetricsInterceptor::new(
+                    std::sync::Arc::clone(sink),
+                ));
+            }
+            if let Some(ref sink) = client_config.audit {
+                builder = builder.interceptor(audit::AuditInterceptor::new(
+                    std::sync::Arc::clone(sink),
+                    std::sync::Arc::clone(&identities),
+                ));
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(rate_limit) = client_config.rate_limit {
+                let rate_limit = ratelimit::RateLimit::new(rate_limit);
+                if let Some(http_client) = rate_limit.http_client($config) {
+                    builder = builder
+                        .http_client(http_client)
+                        .interceptor(rate_limit.interceptor());
+                }
+            }
+            $sdk::Client::from_conf(builder.build())
+        }};
+    }
+
+    let ec2_client = client!(aws_sdk_ec2, config);
+    let ec2instanceconnect_client = client!(aws_sdk_ec2instanceconnect, config);
+    let cloudfront_client = client!(aws_sdk_cloudfront, config_cdn);
+    let efs_client = client!(aws_sdk_efs, config);
+    let route53_client = client!(aws_sdk_route53, config);
+    let lambda_client = client!(aws_sdk_lambda, config);
+    let sqs_client = client!(aws_sdk_sqs, config);
+    let dynamodb_client = client!(aws_sdk_dynamodb, config);
+    let cloudwatch_client = client!(aws_sdk_cloudwatch, config);
+    let logs_client = client!(aws_sdk_cloudwatchlogs, config);
+    let ecs_client = client!(aws_sdk_ecs, config);
+    let autoscaling_client = client!(aws_sdk_autoscaling, config);
+    let rds_client = client!(aws_sdk_rds, config);
+    let ssm_client = client!(aws_sdk_ssm, config);
+    let secretsmanager_client = client!(aws_sdk_secretsmanager, config);
+    let kms_client = client!(aws_sdk_kms, config);
+    let elbv2_client = client!(aws_sdk_elasticloadbalancingv2, config);
+    let ecr_client = client!(aws_sdk_ecr, config);
+    let eventbridge_client = client!(aws_sdk_eventbridge, config);
+    let sfn_client = client!(aws_sdk_sfn, config);
+    let costexplorer_client = client!(aws_sdk_costexplorer, config_global);
+    let configservice_client = client!(aws_sdk_config, config);
+    let organizations_client = client!(aws_sdk_organizations, config_global);
+    let cloudtrail_client = client!(aws_sdk_cloudtrail, config);
+    let s3_client = client!(aws_sdk_s3, config);
+    let tagging_client = client!(aws_sdk_resourcegroupstagging, config);
+    let sts_client = client!(aws_sdk_sts, config);
+    let servicequotas_client = client!(aws_sdk_servicequotas, config);
+    let elasticache_client = client!(aws_sdk_elasticache, config);
+    let opensearch_client = client!(aws_sdk_opensearch, config);
+    let eks_client = client!(aws_sdk_eks, config);
+    let athena_client = client!(aws_sdk_athena, config);
+    let backup_client = client!(aws_sdk_backup, config);
+    let kinesis_client = client!(aws_sdk_kinesis, config);
+    let firehose_client = client!(aws_sdk_firehose, config);
+    let cloudformation_client = client!(aws_sdk_cloudformation, config_cloudformation);
+
+    RegionClient {
+        region,
+        main: RegionClientMain {
+            ec2: ec2_client,
+            ec2instanceconnect: ec2instanceconnect_client,
+            efs: efs_client,
+            route53: route53_client,
+            lambda: lambda_client,
+            sqs: sqs_client,
+            dynamodb: dynamodb_client,
+            cloudwatch: cloudwatch_client,
+            logs: logs_client,
+            ecs: ecs_client,
+            autoscaling: autoscaling_client,
+            rds: rds_client,
+            ssm: ssm_client,
+            secretsmanager: secretsmanager_client,
+            kms: kms_client,
+            elbv2: elbv2_client,
+            ecr: ecr_client,
+            eventbridge: eventbridge_client,
+            sfn: sfn_client,
+            costexplorer: costexplorer_client,
+            configservice: configservice_client,
+            organizations: organizations_client,
+            cloudtrail: cloudtrail_client,
+            s3: s3_client,
+            tagging: tagging_client,
+            sts: sts_client,
+            servicequotas: servicequotas_client,
+            elasticache: elasticache_client,
+            opensearch: opensearch_client,
+            eks: eks_client,
+            athena: athena_client,
+            backup: backup_client,
+            kinesis: kinesis_client,
+            firehose: firehose_client,
+        },
+        cdn: RegionClientCdn {
+            cloudfront: cloudfront_client,
+            cloudformation: cloudformation_client,
+        },
+        s3_bucket_regions: (!client_config.disable_s3_redirects).then(s3::BucketRegions::default),
+        credentials: config.credentials_provider(),
+        identity_lookup,
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub struct Account {
@@ -970,18 +1680,7 @@ pub async fn create_cloudformation_stack(
         .create_stack()
         .stack_name(name)
         .template_body(template)
-        .set_parameters(Some(
-            parameters
-                .0
-                .iter()
-                .map(|param| {
-                    aws_sdk_cloudformation::types::Parameter::builder()
-                        .parameter_key(param.key.as_str())
-                        .parameter_value(param.value.as_str())
-                        .build()
-                })
-                .collect(),
-        ))
+        .set_parameters(Some(parameters.into()))
         .disable_rollback(true)
         .capabilities(aws_sdk_cloudformation::types::Capability::CapabilityAutoExpand)
         .set_tags(Some(tags.clone().into()))
@@ -1010,6 +1709,21 @@ impl CloudformationParameters {
     }
 }
 
+impl From<&CloudformationParameters> for Vec<aws_sdk_cloudformation::types::Parameter> {
+    fn from(parameters: &CloudformationParameters) -> Self {
+        parameters
+            .0
+            .iter()
+            .map(|param| {
+                aws_sdk_cloudformation::types::Parameter::builder()
+                    .parameter_key(param.key.as_str())
+                    .parameter_value(param.value.as_str())
+                    .build()
+            })
+            .collect()
+    }
+}
+
 #[expect(
     clippy::missing_panics_doc,
     reason = "only expect() on builder instances"
@@ -1079,3 +1793,35 @@ pub async fn find_efs(client: &RegionClient, tag: &RawTag) -> Result<Option<Efs>
         }),
     }
 }
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use testing::{mock_region_client, MockHttpClient};
+
+    #[test]
+    fn config_override() {
+        let client = mock_region_client(Region::EuCentral1, MockHttpClient::new());
+        let overridden = client.with_config(&ConfigOverride {
+            region: Some(Region::CnNorth1),
+            ..Default::default()
+        });
+
+        let region = |config: Option<&aws_sdk_ec2::config::Region>| {
+            config.map(|region| region.as_ref().to_owned())
+        };
+
+        assert_eq!(
+            region(overridden.main.ec2.config().region()).as_deref(),
+            Some("cn-north-1")
+        );
+        assert_eq!(
+            region(overridden.main.costexplorer.config().region()).as_deref(),
+            Some("cn-northwest-1")
+        );
+        assert_eq!(
+            region(client.main.ec2.config().region()).as_deref(),
+            Some("eu-central-1")
+        );
+    }
+}
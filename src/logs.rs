@@ -0,0 +1,592 @@
+//! Writing to and reading from CloudWatch Logs
+
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{collections::VecDeque, time::Duration};
+
+use chrono::DateTime;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{TagKey, TagList},
+    Error, RegionClient, Timestamp,
+};
+
+string_newtype!(LogGroupName);
+
+impl LogGroupName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(LogStreamName);
+
+impl LogStreamName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn timestamp_from_millis(millis: i64) -> Result<Timestamp, Error> {
+    DateTime::from_timestamp_millis(millis)
+        .map(Timestamp::new)
+        .ok_or_else(|| Error::InvalidTimestampError {
+            value: millis.to_string(),
+            message: "timestamp out of range".to_owned(),
+        })
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEvent {
+    timestamp: Timestamp,
+    message: String,
+    stream: Option<LogStreamName>,
+    id: Option<String>,
+}
+
+impl LogEvent {
+    pub const fn new(timestamp: Timestamp, message: String) -> Self {
+        Self {
+            timestamp,
+            message,
+            stream: None,
+            id: None,
+        }
+    }
+
+    pub const fn timestamp(&self) -> &Timestamp {
+        &self.timestamp
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Only set for events read from a log group
+    pub const fn stream(&self) -> Option<&LogStreamName> {
+        self.stream.as_ref()
+    }
+}
+
+impl TryFrom<aws_sdk_cloudwatchlogs::types::FilteredLogEvent> for LogEvent {
+    type Error = Error;
+
+    fn try_from(
+        event: aws_sdk_cloudwatchlogs::types::FilteredLogEvent,
+    ) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                event.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            timestamp: timestamp_from_millis(extract!(timestamp)?)?,
+            message: extract!(message)?,
+            stream: event.log_stream_name.map(LogStreamName),
+            id: event.event_id,
+        })
+    }
+}
+
+impl From<LogEvent> for aws_sdk_cloudwatchlogs::types::InputLogEvent {
+    fn from(event: LogEvent) -> Self {
+        Self::builder()
+            .timestamp(event.timestamp.inner().timestamp_millis())
+            .message(event.message)
+            .build()
+            .expect("builder has missing fields")
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct LogGroup {
+    name: LogGroupName,
+    arn: String,
+}
+
+impl LogGroup {
+    pub async fn create(
+        client: &RegionClient,
+        name: LogGroupName,
+        tags: TagList,
+    ) -> Result<Self, Error> {
+        let _output = client
+            .main
+            .logs
+            .create_log_group()
+            .log_group_name(name.as_str())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Self::find_by_name(client, &name)
+            .await?
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: format!("log group {name}"),
+            })
+    }
+
+    pub async fn find_by_name(
+        client: &RegionClient,
+        name: &LogGroupName,
+    ) -> Result<Option<Self>, Error> {
+        let groups = client
+            .main
+            .logs
+            .describe_log_groups()
+            .log_group_name_prefix(name.as_str())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?;
+
+        groups
+            .into_iter()
+            .find(|group| group.log_group_name.as_deref() == Some(name.as_str()))
+            .map(|group| {
+                Ok(Self {
+                    name: name.clone(),
+                    // the ARN of a log group contains a trailing `:*`, which
+                    // the tagging API does not accept
+                    arn: group
+                        .arn
+                        .ok_or_else(|| Error::UnexpectedNoneValue {
+                            entity: "LogGroup.arn".to_owned(),
+                        })?
+                        .trim_end_matches(":*")
+                        .to_owned(),
+                })
+            })
+            .transpose()
+    }
+
+    pub const fn name(&self) -> &LogGroupName {
+        &self.name
+    }
+
+    pub fn arn(&self) -> &str {
+        &self.arn
+    }
+
+    pub async fn create_stream(
+        &self,
+        client: &RegionClient,
+        name: LogStreamName,
+    ) -> Result<LogStream, Error> {
+        let _output = client
+            .main
+            .logs
+            .create_log_stream()
+            .log_group_name(self.name.as_str())
+            .log_stream_name(name.as_str())
+            .send()
+            .await?;
+
+        Ok(LogStream {
+            group: self.name.clone(),
+            name,
+            sequence_token: None,
+        })
+    }
+
+    /// Returns all events matching `pattern` (or all events if `None`) in the
+    /// given time range, following pagination
+    pub async fn filter_events(
+        &self,
+        client: &RegionClient,
+        pattern: Option<&str>,
+        start: Option<Timestamp>,
+        end: Option<Timestamp>,
+    ) -> Result<Vec<LogEvent>, Error> {
+        client
+            .main
+            .logs
+            .filter_log_events()
+            .log_group_name(self.name.as_str())
+            .set_filter_pattern(pattern.map(ToOwned::to_owned))
+            .set_start_time(start.map(|start| start.inner().timestamp_millis()))
+            .set_end_time(end.map(|end| end.inner().timestamp_millis()))
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.events.unwrap_or_default())
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Follows the log group, returning new events as they arrive. See
+    /// [`Tail`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tail<'a>(
+        &'a self,
+        client: &'a RegionClient,
+        pattern: Option<String>,
+        poll_interval: Duration,
+    ) -> Tail<'a> {
+        Tail {
+            client,
+            group: self,
+            pattern,
+            poll_interval,
+            start: Timestamp::now(),
+            seen: Vec::new(),
+            buffer: VecDeque::new(),
+        }
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .main
+            .logs
+            .list_tags_for_resource()
+            .resource_arn(&self.arn)
+            .send()
+            .await?
+            .tags
+            .unwrap_or_default()
+            .into())
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .logs
+            .tag_resource()
+            .resource_arn(&self.arn)
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .logs
+            .untag_resource()
+            .resource_arn(&self.arn)
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogStream {
+    group: LogGroupName,
+    name: LogStreamName,
+    sequence_token: Option<String>,
+}
+
+impl LogStream {
+    pub const fn new(group: LogGroupName, name: LogStreamName) -> Self {
+        Self {
+            group,
+            name,
+            sequence_token: None,
+        }
+    }
+
+    pub const fn group(&self) -> &LogGroupName {
+        &self.group
+    }
+
+    pub const fn name(&self) -> &LogStreamName {
+        &self.name
+    }
+
+    /// Writes events to the stream. Events have to be in chronological order.
+    ///
+    /// The sequence token returned by each call is passed on to the next one.
+    /// If the token is rejected (e.g. because another writer used the
+    /// stream), the request is retried once with the expected token.
+    pub async fn put_events(
+        &mut self,
+        client: &RegionClient,
+        events: Vec<LogEvent>,
+    ) -> Result<(), Error> {
+        let events: Vec<aws_sdk_cloudwatchlogs::types::InputLogEvent> =
+            events.into_iter().map(Into::into).collect();
+
+        let request = || {
+            client
+                .main
+                .logs
+                .put_log_events()
+                .log_group_name(self.group.as_str())
+                .log_stream_name(self.name.as_str())
+                .set_log_events(Some(events.clone()))
+        };
+
+        let output = match request()
+            .set_sequence_token(self.sequence_token.clone())
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e) => match e.as_service_error() {
+                Some(
+                    &aws_sdk_cloudwatchlogs::operation::put_log_events::PutLogEventsError::InvalidSequenceTokenException(
+                        ref inner,
+                    ),
+                ) => {
+                    request()
+                        .set_sequence_token(inner.expected_sequence_token.clone())
+                        .send()
+                        .await?
+                }
+                _ => return Err(e.into()),
+            },
+        };
+
+        self.sequence_token = output.next_sequence_token;
+
+        Ok(())
+    }
+}
+
+/// Polls a log group for new events, see [`LogGroup::tail()`].
+///
+/// Only events newer than the creation of the `Tail` are returned. As events
+/// can arrive out of order, events of the most recent timestamp are tracked to
+/// not return them twice.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct Tail<'a> {
+    client: &'a RegionClient,
+    group: &'a LogGroup,
+    pattern: Option<String>,
+    poll_interval: Duration,
+    start: Timestamp,
+    seen: Vec<String>,
+    buffer: VecDeque<LogEvent>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Tail<'_> {
+    /// Waits for and returns the next event
+    pub async fn next(&mut self) -> Result<LogEvent, Error> {
+        loop {
+            if let Some(event) = self.buffer.pop_front() {
+                return Ok(event);
+            }
+
+            let events = self
+                .group
+                .filter_events(self.client, self.pattern.as_deref(), Some(self.start), None)
+                .await?;
+
+            for event in events {
+                if event.timestamp > self.start {
+                    self.start = event.timestamp;
+                    self.seen.clear();
+                }
+
+                if let Some(ref id) = event.id {
+                    if self.seen.contains(id) {
+                        continue;
+                    }
+                    self.seen.push(id.clone());
+                }
+
+                self.buffer.push_back(event);
+            }
+
+            if self.buffer.is_empty() {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+}
+
+impl fmt::Display for LogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.timestamp, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_cloudwatchlogs::types::{FilteredLogEvent, InputLogEvent};
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn event_conversion() {
+        let event = LogEvent::try_from(
+            FilteredLogEvent::builder()
+                .timestamp(1_704_067_200_123)
+                .message("started")
+                .log_stream_name("worker/1")
+                .event_id("1")
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            event.timestamp(),
+            &Timestamp::new(Utc.timestamp_millis_opt(1_704_067_200_123).unwrap())
+        );
+        assert_eq!(event.message(), "started");
+        assert_eq!(event.stream().map(LogStreamName::as_str), Some("worker/1"));
+
+        let input = InputLogEvent::from(event);
+        assert_eq!(input.timestamp, 1_704_067_200_123);
+        assert_eq!(input.message, "started");
+    }
+
+    #[test]
+    fn event_without_timestamp_is_rejected() {
+        assert!(matches!(
+            LogEvent::try_from(FilteredLogEvent::builder().message("started").build()),
+            Err(Error::UnexpectedNoneValue { .. })
+        ));
+        assert!(matches!(
+            LogEvent::try_from(
+                FilteredLogEvent::builder()
+                    .timestamp(i64::MAX)
+                    .message("started")
+                    .build()
+            ),
+            Err(Error::InvalidTimestampError { .. })
+        ));
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        const GROUP_ARN: &str = "arn:aws:logs:eu-central-1:123456789012:log-group:app";
+
+        fn group() -> LogGroup {
+            LogGroup {
+                name: LogGroupName::new("app".to_owned()),
+                arn: GROUP_ARN.to_owned(),
+            }
+        }
+
+        #[test]
+        fn find_by_name_follows_pages() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("nextToken", "page2"),
+                    MockResponse::ok(
+                        r#"{"logGroups": [{"logGroupName": "app", "arn": "arn:aws:logs:eu-central-1:123456789012:log-group:app:*"}]}"#,
+                    ),
+                )
+                .on(
+                    Matcher::action("DescribeLogGroups"),
+                    MockResponse::ok(
+                        r#"{"logGroups": [{"logGroupName": "app-old", "arn": "arn:aws:logs:eu-central-1:123456789012:log-group:app-old:*"}], "nextToken": "page2"}"#,
+                    ),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let group = block_on(LogGroup::find_by_name(
+                &client,
+                &LogGroupName::new("app".to_owned()),
+            ))
+            .unwrap()
+            .unwrap();
+
+            assert_eq!(group.name().as_str(), "app");
+            assert_eq!(group.arn(), GROUP_ARN, "the trailing :* is removed");
+            assert_eq!(http.requests().unwrap().len(), 2);
+        }
+
+        #[test]
+        fn filter_events_follows_pages() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("nextToken", "page2"),
+                    MockResponse::ok(
+                        r#"{"events": [{"timestamp": 1704110400000, "message": "second", "logStreamName": "web", "eventId": "2"}]}"#,
+                    ),
+                )
+                .on(
+                    Matcher::action("FilterLogEvents"),
+                    MockResponse::ok(
+                        r#"{"events": [{"timestamp": 1704110400000, "message": "first", "logStreamName": "web", "eventId": "1"}], "nextToken": "page2"}"#,
+                    ),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let events =
+                block_on(group().filter_events(&client, Some("ERROR"), None, None)).unwrap();
+
+            assert_eq!(
+                events.iter().map(LogEvent::message).collect::<Vec<_>>(),
+                ["first", "second"]
+            );
+            let first = events.first().unwrap();
+            assert_eq!(first.stream().unwrap().as_str(), "web");
+            assert_eq!(
+                first.timestamp().inner().to_rfc3339(),
+                "2024-01-01T12:00:00+00:00"
+            );
+
+            let requests = http.requests().unwrap();
+            assert_eq!(requests.len(), 2);
+            assert!(requests
+                .iter()
+                .all(|request| request.param("filterPattern").as_deref() == Some("ERROR")));
+        }
+
+        #[test]
+        fn tags() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::action("ListTagsForResource"),
+                    MockResponse::ok(r#"{"tags": {"team": "infra", "env": "prod"}}"#),
+                )
+                .on(Matcher::action("UntagResource"), MockResponse::ok("{}"));
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let tags = block_on(group().tags(&client)).unwrap();
+            assert_eq!(
+                tags.as_slice(),
+                [
+                    RawTag::new("env".to_owned(), "prod".to_owned()),
+                    RawTag::new("team".to_owned(), "infra".to_owned()),
+                ]
+            );
+
+            block_on(group().remove_tags(&client, vec![TagKey::new("env".to_owned())])).unwrap();
+            let untag = http
+                .requests_matching(&Matcher::action("UntagResource"))
+                .unwrap();
+            let request = untag.first().unwrap();
+            assert_eq!(request.param("resourceArn").as_deref(), Some(GROUP_ARN));
+            assert_eq!(
+                request.json_param::<Vec<String>>("tagKeys").unwrap(),
+                ["env"]
+            );
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! Client-side metrics
+//!
+//! A [`MetricsSink`] is called after every attempt of every request,
+//! including retries, with the service, operation, latency and a
+//! classification of the outcome. Use [`load_sdk_clients()`] to get clients
+//! that report to a sink.
+//!
+//! [`NoopMetrics`] discards everything, [`InMemoryMetrics`] aggregates per
+//! operation and is mainly meant for tests.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use aws_smithy_runtime_api::{
+    box_error::BoxError,
+    client::{
+        interceptors::{
+            context::{BeforeTransmitInterceptorContextRef, FinalizerInterceptorContextRef},
+            Intercept,
+        },
+        orchestrator::{HttpResponse, Metadata},
+        runtime_components::RuntimeComponents,
+    },
+};
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+
+use super::{protocol, ClientConfig, Error, ProfileConfig, Region, RegionClient};
+
+/// Error codes that signal throttling, across all services
+const THROTTLING_ERROR_CODES: &[&str] = &[
+    "Throttling",
+    "ThrottlingException",
+    "ThrottledException",
+    "RequestThrottledException",
+    "TooManyRequestsException",
+    "ProvisionedThroughputExceededException",
+    "TransactionInProgressException",
+    "RequestLimitExceeded",
+    "BandwidthLimitExceeded",
+    "LimitExceededException",
+    "RequestThrottled",
+    "SlowDown",
+    "PriorRequestNotComplete",
+    "EC2ThrottledException",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    /// The request was rejected because of rate limits
+    Throttled,
+    /// Any other 4xx response
+    ClientError,
+    /// Any other 5xx response
+    ServerError,
+    /// No response was received, e.g. because of timeouts or connection
+    /// errors
+    TransportError,
+}
+
+/// A single attempt of a request
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    /// The service, e.g. `ec2`
+    pub service: String,
+    /// The operation, e.g. `DescribeInstances`
+    pub operation: String,
+    /// Starts at 1, higher numbers are retries
+    pub attempt: u32,
+    pub latency: Duration,
+    pub status: Option<u16>,
+    pub error_code: Option<String>,
+    pub outcome: Outcome,
+}
+
+impl Attempt {
+    pub const fn is_retry(&self) -> bool {
+        self.attempt > 1
+    }
+}
+
+/// Receives metrics for each request attempt.
+///
+/// Called synchronously on the request path, so implementations should only
+/// do cheap bookkeeping, e.g. update counters of a Prometheus registry or
+/// buffer datapoints to be sent to CloudWatch in the background.
+pub trait MetricsSink: fmt::Debug + Send + Sync {
+    fn record(&self, attempt: &Attempt);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl MetricsSink for NoopMetrics {
+    fn record(&self, _attempt: &Attempt) {}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperationStats {
+    pub attempts: u64,
+    pub retries: u64,
+    pub throttles: u64,
+    /// Client, server and transport errors. Throttles are not included.
+    pub errors: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+}
+
+impl OperationStats {
+    fn add(&mut self, attempt: &Attempt) {
+        self.attempts = self.attempts.saturating_add(1);
+        if attempt.is_retry() {
+            self.retries = self.retries.saturating_add(1);
+        }
+        match attempt.outcome {
+            Outcome::Success => {}
+            Outcome::Throttled => self.throttles = self.throttles.saturating_add(1),
+            Outcome::ClientError | Outcome::ServerError | Outcome::TransportError => {
+                self.errors = self.errors.saturating_add(1);
+            }
+        }
+        self.total_latency = self.total_latency.saturating_add(attempt.latency);
+        self.max_latency = self.max_latency.max(attempt.latency);
+    }
+}
+
+/// Aggregates attempts per service and operation
+#[derive(Debug, Default)]
+pub struct InMemoryMetrics {
+    stats: Mutex<BTreeMap<(String, String), OperationStats>>,
+}
+
+impl InMemoryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, service: &str, operation: &str) -> Result<OperationStats, Error> {
+        Ok(self
+            .snapshot()?
+            .get(&(service.to_owned(), operation.to_owned()))
+            .copied()
+            .unwrap_or_default())
+    }
+
+    /// Stats of all operations, keyed by service and operation
+    pub fn snapshot(&self) -> Result<BTreeMap<(String, String), OperationStats>, Error> {
+        Ok(self
+            .stats
+            .lock()
+            .map_err(|_e| Error::InvalidResponseError {
+                message: "metrics poisoned".to_owned(),
+            })?
+            .clone())
+    }
+}
+
+impl MetricsSink for InMemoryMetrics {
+    fn record(&self, attempt: &Attempt) {
+        if let Ok(mut stats) = self.stats.lock() {
+            stats
+                .entry((attempt.service.clone(), attempt.operation.clone()))
+                .or_default()
+                .add(attempt);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AttemptStart {
+    at: Instant,
+    attempt: u32,
+}
+
+impl Storable for AttemptStart {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The SDK sets `amz-sdk-request: attempt=2; max=3` on every request
+fn attempt_number(header: &str) -> Option<u32> {
+    header
+        .split(';')
+        .find_map(|part| part.trim().strip_prefix("attempt="))
+        .and_then(|attempt| attempt.parse().ok())
+}
+
+/// The error type of the JSON protocols, e.g. "ThrottlingException:http://..."
+/// or "aws.protocoljson#ThrottlingException"
+fn json_error_code(error_type: &str) -> String {
+    let error_type = error_type.split(':').next().unwrap_or(error_type);
+    error_type
+        .rsplit('#')
+        .next()
+        .unwrap_or(error_type)
+        .to_owned()
+}
+
+pub(crate) fn error_code(response: &HttpResponse) -> Option<String> {
+    if let Some(error_type) = response.headers().get("x-amzn-errortype") {
+        return Some(json_error_code(error_type));
+    }
+
+    let body = std::str::from_utf8(response.body().bytes()?).ok()?;
+
+    // JSON protocols without the header carry the type in the body
+    if let Some(error_type) = protocol::parse_json(body)
+        .ok()
+        .as_ref()
+        .and_then(|value| value.get("__type"))
+        .and_then(protocol::Value::as_str)
+    {
+        return Some(json_error_code(error_type));
+    }
+
+    // Query and REST-XML protocols
+    protocol::parse_error_details(body).map(|details| details.code)
+}
+
+/// Whether a failed call may succeed when it is sent again: throttling,
+/// server errors and calls without a response, e.g. because of timeouts
+pub(crate) fn is_transient(error: &Error) -> bool {
+    match error.request_metadata() {
+        Some(metadata) => matches!(
+            classify(metadata.status, metadata.error_code.as_deref()),
+            Outcome::Throttled | Outcome::ServerError | Outcome::TransportError
+        ),
+        None => matches!(*error, Error::SdkError(..)),
+    }
+}
+
+fn classify(status: Option<u16>, error_code: Option<&str>) -> Outcome {
+    let Some(status) = status else {
+        return Outcome::TransportError;
+    };
+
+    if status == 429 || error_code.is_some_and(|code| THROTTLING_ERROR_CODES.contains(&code)) {
+        Outcome::Throttled
+    } else if (500..600).contains(&status) {
+        Outcome::ServerError
+    } else if (400..500).contains(&status) {
+        Outcome::ClientError
+    } else {
+        Outcome::Success
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct MetricsInterceptor {
+    sink: Arc<dyn MetricsSink>,
+}
+
+impl MetricsInterceptor {
+    pub(crate) fn new(sink: Arc<dyn MetricsSink>) -> Self {
+        Self { sink }
+    }
+}
+
+impl Intercept for MetricsInterceptor {
+    fn name(&self) -> &'static str {
+        "MetricsInterceptor"
+    }
+
+    fn read_before_transmit(
+        &self,
+        context: &BeforeTransmitInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let attempt = context
+            .request()
+            .headers()
+            .get("amz-sdk-request")
+            .and_then(attempt_number)
+            .unwrap_or(1);
+
+        let _layer = cfg.interceptor_state().store_put(AttemptStart {
+            at: Instant::now(),
+            attempt,
+        });
+
+        Ok(())
+    }
+
+    fn read_after_attempt(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        // Attempts that fail before transmission (e.g. signing errors) are
+        // not requests and therefore not recorded
+        let Some(start) = cfg.load::<AttemptStart>().copied() else {
+            return Ok(());
+        };
+
+        let (service, operation) = cfg
+            .load::<Metadata>()
+            .map(|metadata| (metadata.service().to_owned(), metadata.name().to_owned()))
+            .unwrap_or_default();
+
+        let status = context
+            .response()
+            .map(|response| response.status().as_u16());
+        let error_code = context.response().and_then(error_code);
+
+        self.sink.record(&Attempt {
+            service,
+            operation,
+            attempt: start.attempt,
+            latency: start.at.elapsed(),
+            status,
+            outcome: classify(status, error_code.as_deref()),
+            error_code,
+        });
+
+        Ok(())
+    }
+}
+
+/// Like [`load_sdk_clients()`](crate::load_sdk_clients), but all clients
+/// report to `sink`
+///
+/// Use [`load_sdk_clients_with_config()`](crate::load_sdk_clients_with_config)
+/// to combine metrics with other settings.
+pub async fn load_sdk_clients(
+    regions: &[Region],
+    profile_config: &ProfileConfig,
+    sink: Arc<dyn MetricsSink>,
+) -> Vec<RegionClient> {
+    super::load_sdk_clients_with_config(
+        regions,
+        profile_config,
+        &ClientConfig {
+            metrics: Some(sink),
+            ..ClientConfig::default()
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_outcomes() {
+        assert_eq!(classify(Some(200), None), Outcome::Success);
+        assert_eq!(classify(Some(429), None), Outcome::Throttled);
+        assert_eq!(
+            classify(Some(400), Some("RequestLimitExceeded")),
+            Outcome::Throttled
+        );
+        assert_eq!(
+            classify(Some(400), Some("InvalidParameterValue")),
+            Outcome::ClientError
+        );
+        assert_eq!(classify(Some(503), Some("SlowDown")), Outcome::Throttled);
+        assert_eq!(classify(Some(500), None), Outcome::ServerError);
+        assert_eq!(classify(None, None), Outcome::TransportError);
+
+        assert_eq!(
+            json_error_code("ThrottlingException:http://internal.amazon.com/"),
+            "ThrottlingException"
+        );
+        assert_eq!(
+            json_error_code("com.amazonaws.tagging#ThrottledException"),
+            "ThrottledException"
+        );
+
+        assert_eq!(attempt_number("attempt=2; max=3"), Some(2));
+        assert_eq!(attempt_number("ttl=20240101T000000Z; attempt=1"), Some(1));
+    }
+
+    #[test]
+    fn aggregate_in_memory() {
+        let metrics = InMemoryMetrics::new();
+        let attempt = |attempt, outcome, millis| Attempt {
+            service: "ec2".to_owned(),
+            operation: "DescribeInstances".to_owned(),
+            attempt,
+            latency: Duration::from_millis(millis),
+            status: None,
+            error_code: None,
+            outcome,
+        };
+
+        metrics.record(&attempt(1, Outcome::Throttled, 10));
+        metrics.record(&attempt(2, Outcome::ServerError, 30));
+        metrics.record(&attempt(3, Outcome::Success, 20));
+
+        assert_eq!(
+            metrics.get("ec2", "DescribeInstances").unwrap(),
+            OperationStats {
+                attempts: 3,
+                retries: 2,
+                throttles: 1,
+                errors: 1,
+                total_latency: Duration::from_millis(60),
+                max_latency: Duration::from_millis(30),
+            }
+        );
+        assert_eq!(metrics.get("ec2", "RunInstances").unwrap().attempts, 0);
+    }
+}
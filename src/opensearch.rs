@@ -0,0 +1,380 @@
+//! OpenSearch Service domains
+//!
+//! Tags are managed via the ARN of the domain, see [`DomainArn`]. They are read
+//! and written via the Resource Groups Tagging API, which needs the `tag:*`
+//! permissions.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{resource, TagKey, TagList},
+    Error, RegionClient,
+};
+
+/// `DescribeDomains` accepts at most this many domain names
+const DESCRIBE_DOMAINS_LIMIT: usize = 5;
+
+string_newtype!(DomainName);
+
+impl DomainName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+arn_newtype!(DomainArn);
+
+impl DomainArn {
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        resource::tags(client, self.inner()).await
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        resource::add_tags(client, self.inner(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        resource::remove_tags(client, self.inner(), keys).await
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Domain {
+    id: String,
+    name: DomainName,
+    arn: DomainArn,
+    engine_version: Option<String>,
+    endpoint: Option<String>,
+    instance_type: Option<String>,
+    instance_count: Option<i32>,
+    processing: bool,
+    deleted: bool,
+}
+
+impl TryFrom<aws_sdk_opensearch::types::DomainStatus> for Domain {
+    type Error = Error;
+
+    fn try_from(domain: aws_sdk_opensearch::types::DomainStatus) -> Result<Self, Self::Error> {
+        let (instance_type, instance_count) = domain
+            .cluster_config
+            .map(|config| {
+                (
+                    config.instance_type.map(|t| t.as_str().to_owned()),
+                    config.instance_count,
+                )
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            id: domain.domain_id,
+            name: DomainName(domain.domain_name),
+            arn: DomainArn::parse(&domain.arn)?,
+            engine_version: domain.engine_version,
+            endpoint: domain.endpoint,
+            instance_type,
+            instance_count,
+            processing: domain.processing.unwrap_or(false),
+            deleted: domain.deleted.unwrap_or(false),
+        })
+    }
+}
+
+impl Domain {
+    /// Lists all domains of the region
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        let names: Vec<String> = client
+            .main
+            .opensearch
+            .list_domain_names()
+            .send()
+            .await?
+            .domain_names
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|info| info.domain_name)
+            .collect();
+
+        let mut domains = vec![];
+        for chunk in names.chunks(DESCRIBE_DOMAINS_LIMIT) {
+            for domain in client
+                .main
+                .opensearch
+                .describe_domains()
+                .set_domain_names(Some(chunk.to_vec()))
+                .send()
+                .await?
+                .domain_status_list
+            {
+                domains.push(domain.try_into()?);
+            }
+        }
+
+        Ok(domains)
+    }
+
+    pub async fn find_by_name(
+        client: &RegionClient,
+        name: &DomainName,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .opensearch
+            .describe_domain()
+            .domain_name(name.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => output.domain_status.map(TryInto::try_into).transpose(),
+            Err(e) => match e.as_service_error() {
+                Some(service_error) if service_error.is_resource_not_found_exception() => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub const fn name(&self) -> &DomainName {
+        &self.name
+    }
+
+    pub const fn arn(&self) -> &DomainArn {
+        &self.arn
+    }
+
+    /// E.g. `OpenSearch_2.11` or `Elasticsearch_7.10`
+    pub fn engine_version(&self) -> Option<&str> {
+        self.engine_version.as_deref()
+    }
+
+    /// `None` for domains in a VPC and for domains that are still being
+    /// created
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    pub fn instance_type(&self) -> Option<&str> {
+        self.instance_type.as_deref()
+    }
+
+    pub const fn instance_count(&self) -> Option<i32> {
+        self.instance_count
+    }
+
+    /// Whether a configuration change is in progress
+    pub const fn processing(&self) -> bool {
+        self.processing
+    }
+
+    /// Whether the domain is being deleted
+    pub const fn deleted(&self) -> bool {
+        self.deleted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_opensearch::types::{ClusterConfig, DomainStatus, OpenSearchPartitionInstanceType};
+
+    use super::*;
+
+    fn domain_status() -> aws_sdk_opensearch::types::builders::DomainStatusBuilder {
+        DomainStatus::builder()
+            .domain_id("123456789012/search")
+            .domain_name("search")
+            .arn("arn:aws:es:eu-central-1:123456789012:domain/search")
+    }
+
+    #[test]
+    fn domain_from_status() {
+        let domain = Domain::try_from(
+            domain_status()
+                .engine_version("OpenSearch_2.11")
+                .endpoint("search-abc.eu-central-1.es.amazonaws.com")
+                .cluster_config(
+                    ClusterConfig::builder()
+                        .instance_type(OpenSearchPartitionInstanceType::T3SmallSearch)
+                        .instance_count(2)
+                        .build(),
+                )
+                .processing(true)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(domain.id(), "123456789012/search");
+        assert_eq!(domain.name().as_str(), "search");
+        assert_eq!(domain.arn().inner().resource(), "domain/search");
+        assert_eq!(domain.engine_version(), Some("OpenSearch_2.11"));
+        assert_eq!(
+            domain.endpoint(),
+            Some("search-abc.eu-central-1.es.amazonaws.com")
+        );
+        assert_eq!(domain.instance_type(), Some("t3.small.search"));
+        assert_eq!(domain.instance_count(), Some(2));
+        assert!(domain.processing());
+        assert!(!domain.deleted());
+    }
+
+    #[test]
+    fn domain_without_cluster_config() {
+        let domain = Domain::try_from(domain_status().deleted(true).build().unwrap()).unwrap();
+
+        assert_eq!(domain.instance_type(), None);
+        assert_eq!(domain.instance_count(), None);
+        assert!(!domain.processing());
+        assert!(domain.deleted());
+    }
+
+    #[test]
+    fn invalid_arn_is_rejected() {
+        assert!(matches!(
+            Domain::try_from(domain_status().arn("search").build().unwrap()),
+            Err(Error::InvalidArn(_))
+        ));
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        const DOMAIN_ARN: &str = "arn:aws:es:eu-central-1:123456789012:domain/search";
+
+        fn domain_status() -> String {
+            format!(
+                r#"{{
+                    "DomainId": "123456789012/search",
+                    "DomainName": "search",
+                    "ARN": "{DOMAIN_ARN}",
+                    "EngineVersion": "OpenSearch_2.11",
+                    "Endpoint": "search-abc.eu-central-1.es.amazonaws.com",
+                    "ClusterConfig": {{"InstanceType": "t3.small.search", "InstanceCount": 2}},
+                    "Processing": true
+                }}"#
+            )
+        }
+
+        #[test]
+        fn list_describes_domains_in_chunks() {
+            let names = (0..7)
+                .map(|i| format!(r#"{{"DomainName": "search-{i}"}}"#))
+                .collect::<Vec<_>>()
+                .join(",");
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::predicate(|request| request.uri.contains("/domain-info")),
+                    MockResponse::ok(format!(r#"{{"DomainStatusList": [{}]}}"#, domain_status())),
+                )
+                .on(
+                    Matcher::predicate(|request| request.method == "GET"),
+                    MockResponse::ok(format!(r#"{{"DomainNames": [{names}]}}"#)),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let domains = block_on(Domain::list(&client)).unwrap();
+
+            assert_eq!(domains.len(), 2);
+            let requests = http
+                .requests_matching(&Matcher::predicate(|request| {
+                    request.uri.contains("/domain-info")
+                }))
+                .unwrap();
+            assert_eq!(
+                requests
+                    .iter()
+                    .map(|request| request.json_param::<Vec<String>>("DomainNames").unwrap())
+                    .collect::<Vec<_>>(),
+                [
+                    (0..5).map(|i| format!("search-{i}")).collect::<Vec<_>>(),
+                    (5..7).map(|i| format!("search-{i}")).collect::<Vec<_>>(),
+                ]
+            );
+        }
+
+        #[test]
+        fn find_by_name_converts_domain() {
+            let http = MockHttpClient::new().on(
+                Matcher::Any,
+                MockResponse::ok(format!(r#"{{"DomainStatus": {}}}"#, domain_status())),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let domain = block_on(Domain::find_by_name(
+                &client,
+                &DomainName::new("search".to_owned()),
+            ))
+            .unwrap()
+            .unwrap();
+
+            assert_eq!(domain.name().as_str(), "search");
+            assert_eq!(domain.arn().inner().resource(), "domain/search");
+            assert_eq!(domain.engine_version(), Some("OpenSearch_2.11"));
+            assert_eq!(domain.instance_type(), Some("t3.small.search"));
+            assert_eq!(domain.instance_count(), Some(2));
+            assert!(domain.processing());
+            assert!(!domain.deleted());
+        }
+
+        #[test]
+        fn missing_domain_is_none() {
+            let http = MockHttpClient::new().on(
+                Matcher::Any,
+                MockResponse::status(404, r#"{"message": "Domain not found: search"}"#)
+                    .with_header("x-amzn-errortype", "ResourceNotFoundException"),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            assert!(block_on(Domain::find_by_name(
+                &client,
+                &DomainName::new("search".to_owned()),
+            ))
+            .unwrap()
+            .is_none());
+        }
+
+        #[test]
+        fn tags_via_tagging_api() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("GetResources"),
+                MockResponse::ok(format!(
+                    r#"{{"ResourceTagMappingList": [
+                        {{"ResourceARN": "{DOMAIN_ARN}/other", "Tags": [{{"Key": "env", "Value": "dev"}}]}},
+                        {{"ResourceARN": "{DOMAIN_ARN}", "Tags": [{{"Key": "env", "Value": "prod"}}]}}
+                    ]}}"#
+                )),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let arn = DomainArn::parse(DOMAIN_ARN).unwrap();
+
+            let tags = block_on(arn.tags(&client)).unwrap();
+
+            assert_eq!(
+                tags.as_slice(),
+                [RawTag::new("env".to_owned(), "prod".to_owned())]
+            );
+            assert_eq!(
+                http.requests()
+                    .unwrap()
+                    .first()
+                    .unwrap()
+                    .json_param::<Vec<String>>("ResourceARNList")
+                    .unwrap(),
+                [DOMAIN_ARN]
+            );
+        }
+    }
+}
@@ -0,0 +1,583 @@
+//! AWS Organizations accounts, organizational units and their tags
+//!
+//! Organizations is a global service, the client always talks to
+//! `us-east-1`.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{TagKey, TagList},
+    Error, RegionClient, Timestamp,
+};
+
+string_newtype!(AccountId);
+
+impl AccountId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// The ID of a root (`r-...`) or an organizational unit (`ou-...`)
+string_newtype!(ParentId);
+
+impl ParentId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Account {
+    id: AccountId,
+    arn: Option<String>,
+    name: Option<String>,
+    email: Option<String>,
+    status: Option<String>,
+    joined_at: Option<Timestamp>,
+}
+
+impl TryFrom<aws_sdk_organizations::types::Account> for Account {
+    type Error = Error;
+
+    fn try_from(account: aws_sdk_organizations::types::Account) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: AccountId(account.id.ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "Account.id".to_owned(),
+            })?),
+            arn: account.arn,
+            name: account.name,
+            email: account.email,
+            status: account.status.map(|status| status.as_str().to_owned()),
+            joined_at: account
+                .joined_timestamp
+                .map(TryInto::try_into)
+                .transpose()?,
+        })
+    }
+}
+
+impl Account {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .organizations
+            .list_accounts()
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.accounts.unwrap_or_default())
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Only the accounts directly below `parent`
+    pub async fn list_for_parent(
+        client: &RegionClient,
+        parent: &ParentId,
+    ) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .organizations
+            .list_accounts_for_parent()
+            .parent_id(parent.as_str())
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.accounts.unwrap_or_default())
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub const fn id(&self) -> &AccountId {
+        &self.id
+    }
+
+    pub fn arn(&self) -> Option<&str> {
+        self.arn.as_deref()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// `ACTIVE`, `SUSPENDED` or `PENDING_CLOSURE`
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    pub const fn joined_at(&self) -> Option<&Timestamp> {
+        self.joined_at.as_ref()
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        tags(client, self.id.as_str()).await
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.id.as_str(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.id.as_str(), keys).await
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct OrganizationalUnit {
+    id: ParentId,
+    name: Option<String>,
+}
+
+impl OrganizationalUnit {
+    /// The roots of the organization. There is currently always exactly one.
+    pub async fn roots(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .organizations
+            .list_roots()
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.roots.unwrap_or_default())
+            .map(|root| {
+                Ok(Self {
+                    id: ParentId(root.id.ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: "Root.id".to_owned(),
+                    })?),
+                    name: root.name,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn children(&self, client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .organizations
+            .list_organizational_units_for_parent()
+            .parent_id(self.id.as_str())
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.organizational_units.unwrap_or_default())
+            .map(|ou| {
+                Ok(Self {
+                    id: ParentId(ou.id.ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: "OrganizationalUnit.id".to_owned(),
+                    })?),
+                    name: ou.name,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn accounts(&self, client: &RegionClient) -> Result<Vec<Account>, Error> {
+        Account::list_for_parent(client, &self.id).await
+    }
+
+    /// Walks the whole subtree below this unit, returning every account
+    /// together with the path of units leading to it (starting with this
+    /// unit)
+    pub async fn descendant_accounts(
+        &self,
+        client: &RegionClient,
+    ) -> Result<Vec<(OuPath, Account)>, Error> {
+        let mut result = Vec::new();
+        let mut pending = vec![OuPath(vec![self.clone()])];
+
+        while let Some(path) = pending.pop() {
+            let Some(unit) = path.0.last() else {
+                continue;
+            };
+
+            for account in unit.accounts(client).await? {
+                result.push((path.clone(), account));
+            }
+
+            for child in unit.children(client).await? {
+                let mut child_path = path.clone();
+                child_path.0.push(child);
+                pending.push(child_path);
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub const fn id(&self) -> &ParentId {
+        &self.id
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        tags(client, self.id.as_str()).await
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.id.as_str(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.id.as_str(), keys).await
+    }
+}
+
+/// The units from a root or starting unit down to an account
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct OuPath(Vec<OrganizationalUnit>);
+
+impl OuPath {
+    pub fn units(&self) -> &[OrganizationalUnit] {
+        &self.0
+    }
+}
+
+impl fmt::Display for OuPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for unit in &self.0 {
+            write!(f, "/{}", unit.name().unwrap_or_else(|| unit.id().as_str()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Accounts, units, roots and policies are all tagged by their ID
+async fn tags(client: &RegionClient, resource_id: &str) -> Result<TagList, Error> {
+    let mut tags = Vec::new();
+    let mut next_token = None;
+
+    loop {
+        let output = client
+            .main
+            .organizations
+            .list_tags_for_resource()
+            .resource_id(resource_id)
+            .set_next_token(next_token)
+            .send()
+            .await?;
+
+        tags.extend(output.tags.unwrap_or_default());
+
+        match output.next_token {
+            Some(token) if !token.is_empty() => next_token = Some(token),
+            _ => break,
+        }
+    }
+
+    Ok(tags.try_into()?)
+}
+
+async fn add_tags(client: &RegionClient, resource_id: &str, tags: TagList) -> Result<(), Error> {
+    let _output = client
+        .main
+        .organizations
+        .tag_resource()
+        .resource_id(resource_id)
+        .set_tags(Some(tags.into()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+async fn remove_tags(
+    client: &RegionClient,
+    resource_id: &str,
+    keys: Vec<TagKey>,
+) -> Result<(), Error> {
+    let _output = client
+        .main
+        .organizations
+        .untag_resource()
+        .resource_id(resource_id)
+        .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_organizations::{primitives::DateTime, types::AccountStatus};
+
+    use super::*;
+    use crate::tags::RawTag;
+
+    #[test]
+    fn account_from_aws() {
+        let account = Account::try_from(
+            aws_sdk_organizations::types::Account::builder()
+                .id("123456789012")
+                .name("production")
+                .email("aws-prod@example.com")
+                .status(AccountStatus::Active)
+                .joined_timestamp(DateTime::from_secs(1_704_067_200))
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(account.id().as_str(), "123456789012");
+        assert_eq!(account.name(), Some("production"));
+        assert_eq!(account.email(), Some("aws-prod@example.com"));
+        assert_eq!(account.status(), Some("ACTIVE"));
+        assert_eq!(
+            account.joined_at().map(|joined| joined.inner().timestamp()),
+            Some(1_704_067_200)
+        );
+
+        assert!(matches!(
+            Account::try_from(
+                aws_sdk_organizations::types::Account::builder()
+                    .name("production")
+                    .build()
+            ),
+            Err(Error::UnexpectedNoneValue { ref entity }) if entity == "Account.id"
+        ));
+    }
+
+    #[test]
+    fn ou_path_display() {
+        let path = OuPath(vec![
+            OrganizationalUnit {
+                id: ParentId::new("r-ab12".to_owned()),
+                name: None,
+            },
+            OrganizationalUnit {
+                id: ParentId::new("ou-ab12-11111111".to_owned()),
+                name: Some("workloads".to_owned()),
+            },
+            OrganizationalUnit {
+                id: ParentId::new("ou-ab12-22222222".to_owned()),
+                name: Some("prod".to_owned()),
+            },
+        ]);
+        assert_eq!(path.to_string(), "/r-ab12/workloads/prod");
+        assert_eq!(path.units().len(), 3);
+
+        assert_eq!(OuPath(Vec::new()).to_string(), "");
+    }
+
+    #[test]
+    fn tags_round_trip() {
+        let tags = TagList::from_vec(vec![
+            RawTag::new("cost-center".to_owned(), "1234".to_owned()),
+            RawTag::new("team".to_owned(), "infra".to_owned()),
+        ]);
+
+        let aws: Vec<aws_sdk_organizations::types::Tag> = tags.clone().into();
+        assert_eq!(
+            aws.iter()
+                .map(aws_sdk_organizations::types::Tag::key)
+                .collect::<Vec<_>>(),
+            ["cost-center", "team"]
+        );
+        assert_eq!(TagList::try_from(aws).unwrap(), tags);
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        fn for_parent(action: &str, parent: &str) -> Matcher {
+            Matcher::All(vec![
+                Matcher::action(action),
+                Matcher::param("ParentId", parent),
+            ])
+        }
+
+        fn accounts(ids: &[&str]) -> MockResponse {
+            MockResponse::ok(
+                serde_json::json!({
+                    "Accounts": ids
+                        .iter()
+                        .map(|id| serde_json::json!({"Id": id, "Status": "ACTIVE"}))
+                        .collect::<Vec<_>>()
+                })
+                .to_string(),
+            )
+        }
+
+        fn root() -> OrganizationalUnit {
+            OrganizationalUnit {
+                id: ParentId::new("r-ab12".to_owned()),
+                name: Some("Root".to_owned()),
+            }
+        }
+
+        #[test]
+        fn list_follows_next_tokens() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("NextToken", "page2"),
+                    accounts(&["222222222222"]),
+                )
+                .on(
+                    Matcher::action("ListAccounts"),
+                    MockResponse::ok(
+                        r#"{"Accounts": [{"Id": "111111111111", "Name": "production"}], "NextToken": "page2"}"#,
+                    ),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let accounts = block_on(Account::list(&client)).unwrap();
+
+            assert_eq!(
+                accounts
+                    .iter()
+                    .map(|account| account.id().as_str())
+                    .collect::<Vec<_>>(),
+                ["111111111111", "222222222222"]
+            );
+            assert_eq!(accounts.first().unwrap().name(), Some("production"));
+            assert_eq!(http.requests().unwrap().len(), 2);
+        }
+
+        #[test]
+        fn roots() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("ListRoots"),
+                MockResponse::ok(r#"{"Roots": [{"Id": "r-ab12", "Name": "Root"}]}"#),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let roots = block_on(OrganizationalUnit::roots(&client)).unwrap();
+
+            assert_eq!(
+                roots
+                    .iter()
+                    .map(|root| root.id().as_str())
+                    .collect::<Vec<_>>(),
+                ["r-ab12"]
+            );
+        }
+
+        #[test]
+        fn organization_not_in_use_is_an_error() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("ListRoots"),
+                MockResponse::status(
+                    400,
+                    r#"{"__type": "AWSOrganizationsNotInUseException", "Message": "not in use"}"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let error = block_on(OrganizationalUnit::roots(&client)).unwrap_err();
+            assert_eq!(
+                error.request_metadata().unwrap().error_code.as_deref(),
+                Some("AWSOrganizationsNotInUseException")
+            );
+        }
+
+        #[test]
+        fn descendant_accounts_walk_the_tree() {
+            let http = MockHttpClient::new()
+                .on(
+                    for_parent("ListAccountsForParent", "r-ab12"),
+                    accounts(&["111111111111"]),
+                )
+                .on(
+                    for_parent("ListOrganizationalUnitsForParent", "r-ab12"),
+                    MockResponse::ok(
+                        r#"{"OrganizationalUnits": [{"Id": "ou-ab12-11111111", "Name": "workloads"}]}"#,
+                    ),
+                )
+                .on(
+                    for_parent("ListAccountsForParent", "ou-ab12-11111111"),
+                    accounts(&["222222222222", "333333333333"]),
+                )
+                .on(
+                    for_parent("ListOrganizationalUnitsForParent", "ou-ab12-11111111"),
+                    MockResponse::ok(r#"{"OrganizationalUnits": []}"#),
+                );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let mut accounts = block_on(root().descendant_accounts(&client))
+                .unwrap()
+                .into_iter()
+                .map(|(path, account)| (path.to_string(), account.id().as_str().to_owned()))
+                .collect::<Vec<_>>();
+            accounts.sort();
+
+            assert_eq!(
+                accounts,
+                [
+                    ("/Root".to_owned(), "111111111111".to_owned()),
+                    ("/Root/workloads".to_owned(), "222222222222".to_owned()),
+                    ("/Root/workloads".to_owned(), "333333333333".to_owned()),
+                ]
+            );
+        }
+
+        #[test]
+        fn tags_follow_next_tokens() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("NextToken", "page2"),
+                    MockResponse::ok(r#"{"Tags": [{"Key": "cost-center", "Value": "1234"}]}"#),
+                )
+                .on(
+                    Matcher::action("ListTagsForResource"),
+                    MockResponse::ok(
+                        r#"{"Tags": [{"Key": "team", "Value": "infra"}], "NextToken": "page2"}"#,
+                    ),
+                )
+                .on(Matcher::Any, MockResponse::ok("{}"));
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let unit = root();
+
+            let tags = block_on(unit.tags(&client)).unwrap();
+            assert_eq!(
+                tags.as_slice(),
+                [
+                    RawTag::new("cost-center".to_owned(), "1234".to_owned()),
+                    RawTag::new("team".to_owned(), "infra".to_owned()),
+                ]
+            );
+
+            block_on(unit.remove_tags(&client, vec![TagKey::new("team".to_owned())])).unwrap();
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(request.action().as_deref(), Some("UntagResource"));
+            assert_eq!(request.param("ResourceId").as_deref(), Some("r-ab12"));
+            assert_eq!(
+                request.json_param::<Vec<String>>("TagKeys").unwrap(),
+                ["team"]
+            );
+        }
+    }
+}
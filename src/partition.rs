@@ -0,0 +1,120 @@
+//! AWS partitions
+//!
+//! Regions are grouped into partitions that are isolated from each other:
+//! the standard partition (`aws`), China (`aws-cn`) and GovCloud
+//! (`aws-us-gov`). Each partition has its own domain suffix, its own ARN
+//! prefix and its own region for global services, and not every service is
+//! available everywhere.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Partition {
+    #[cfg_attr(feature = "serde", serde(rename = "aws"))]
+    Aws,
+    #[cfg_attr(feature = "serde", serde(rename = "aws-cn"))]
+    AwsCn,
+    #[cfg_attr(feature = "serde", serde(rename = "aws-us-gov"))]
+    AwsUsGov,
+}
+
+impl Partition {
+    pub const ALL: [Self; 3] = [Self::Aws, Self::AwsCn, Self::AwsUsGov];
+
+    /// The partition as used in ARNs
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Aws => "aws",
+            Self::AwsCn => "aws-cn",
+            Self::AwsUsGov => "aws-us-gov",
+        }
+    }
+
+    /// The partition of a region, based on its name. Unknown regions are
+    /// assumed to be in the standard partition.
+    pub fn from_region(region: &str) -> Self {
+        if region.starts_with("cn-") {
+            Self::AwsCn
+        } else if region.starts_with("us-gov-") {
+            Self::AwsUsGov
+        } else {
+            Self::Aws
+        }
+    }
+
+    pub const fn dns_suffix(self) -> &'static str {
+        match self {
+            Self::Aws | Self::AwsUsGov => "amazonaws.com",
+            Self::AwsCn => "amazonaws.com.cn",
+        }
+    }
+
+    /// The region that hosts global services like Organizations and Cost
+    /// Explorer, and that is used to sign requests to them
+    pub const fn global_region(self) -> &'static str {
+        match self {
+            Self::Aws => "us-east-1",
+            Self::AwsCn => "cn-northwest-1",
+            Self::AwsUsGov => "us-gov-west-1",
+        }
+    }
+
+    /// Endpoint prefixes of the services used by this crate that do not
+    /// exist in the partition
+    const fn unavailable_services(self) -> &'static [&'static str] {
+        match self {
+            Self::Aws | Self::AwsCn => &[],
+            Self::AwsUsGov => &["cloudfront", "ce"],
+        }
+    }
+
+    /// Whether a service, given by its endpoint prefix (e.g. `ec2` or `ce`),
+    /// is available in the partition
+    pub fn is_service_available(self, service: &str) -> bool {
+        !self.unavailable_services().contains(&service)
+    }
+
+    /// The regional endpoint of a service, e.g.
+    /// `https://ec2.cn-north-1.amazonaws.com.cn`
+    pub fn endpoint(self, service: &str, region: &str) -> String {
+        format!("https://{service}.{region}.{}", self.dns_suffix())
+    }
+}
+
+impl fmt::Display for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for Partition {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|partition| partition.as_str() == value)
+            .ok_or_else(|| format!("unknown partition \"{value}\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partition_of_region() {
+        assert_eq!(Partition::from_region("eu-central-1"), Partition::Aws);
+        assert_eq!(Partition::from_region("cn-north-1"), Partition::AwsCn);
+        assert_eq!(Partition::from_region("us-gov-west-1"), Partition::AwsUsGov);
+        assert_eq!(
+            Partition::AwsCn.endpoint("ec2", "cn-north-1"),
+            "https://ec2.cn-north-1.amazonaws.com.cn"
+        );
+        assert!(!Partition::AwsUsGov.is_service_available("cloudfront"));
+    }
+}
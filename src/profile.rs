@@ -0,0 +1,746 @@
+//! Parsing of the shared config files (`~/.aws/config` and
+//! `~/.aws/credentials`)
+//!
+//! [`ProfileSet::load()`] reads both files, honoring `AWS_CONFIG_FILE` and
+//! `AWS_SHARED_CREDENTIALS_FILE`. Properties from the credentials file take
+//! precedence over the config file, like in the CLI and the SDKs.
+//!
+//! ```
+//! use aws_lib::profile::ProfileSet;
+//!
+//! let profiles = ProfileSet::parse(
+//!     r"
+//! [profile admin]
+//! role_arn = arn:aws:iam::123456789012:role/admin
+//! source_profile = base
+//! region = eu-central-1
+//! ",
+//!     r"
+//! [base]
+//! aws_access_key_id = AKIAEXAMPLE
+//! aws_secret_access_key = secret
+//! ",
+//! )
+//! .unwrap();
+//!
+//! let chain = profiles.source_chain("admin").unwrap();
+//! assert_eq!(chain.len(), 2);
+//! assert!(chain[1].static_credentials().is_some());
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseProfileError {
+    ReadFile {
+        path: String,
+        message: String,
+    },
+    InvalidLine {
+        line: usize,
+        content: String,
+    },
+    PropertyOutsideSection {
+        line: usize,
+    },
+    InvalidValue {
+        profile: String,
+        key: String,
+        value: String,
+    },
+    MissingProfile {
+        name: String,
+    },
+    MissingSsoSession {
+        profile: String,
+        session: String,
+    },
+    SourceProfileCycle {
+        profile: String,
+    },
+}
+
+impl std::error::Error for ParseProfileError {}
+
+impl fmt::Display for ParseProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::ReadFile {
+                ref path,
+                ref message,
+            } => write!(f, "could not read {path}: {message}"),
+            Self::InvalidLine { line, ref content } => {
+                write!(f, "invalid line {line}: \"{content}\"")
+            }
+            Self::PropertyOutsideSection { line } => {
+                write!(f, "property on line {line} is not part of a section")
+            }
+            Self::InvalidValue {
+                ref profile,
+                ref key,
+                ref value,
+            } => write!(
+                f,
+                "invalid value \"{value}\" for {key} in profile {profile}"
+            ),
+            Self::MissingProfile { ref name } => write!(f, "profile {name} not found"),
+            Self::MissingSsoSession {
+                ref profile,
+                ref session,
+            } => write!(
+                f,
+                "sso-session {session} referenced by profile {profile} not found"
+            ),
+            Self::SourceProfileCycle { ref profile } => {
+                write!(f, "source_profile chain of profile {profile} is cyclic")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryMode {
+    Legacy,
+    Standard,
+    Adaptive,
+}
+
+impl TryFrom<&str> for RetryMode {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "legacy" => Ok(Self::Legacy),
+            "standard" => Ok(Self::Standard),
+            "adaptive" => Ok(Self::Adaptive),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct StaticCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+// Do not leak the secrets into logs
+impl fmt::Debug for StaticCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"<redacted>")
+            .field(
+                "session_token",
+                &self.session_token.as_ref().map(|_token| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+/// An `[sso-session name]` section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsoSession {
+    pub name: String,
+    pub start_url: Option<String>,
+    pub region: Option<String>,
+    pub registration_scopes: Vec<String>,
+}
+
+type Properties = BTreeMap<String, String>;
+
+impl SsoSession {
+    fn from_properties(name: String, properties: &Properties) -> Self {
+        Self {
+            start_url: properties.get("sso_start_url").cloned(),
+            region: properties.get("sso_region").cloned(),
+            registration_scopes: properties
+                .get("sso_registration_scopes")
+                .map(|scopes| {
+                    scopes
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|scope| !scope.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[expect(
+    clippy::struct_field_names,
+    reason = "the fields are named like the settings of the config file"
+)]
+pub struct Profile {
+    name: String,
+    region: Option<String>,
+    static_credentials: Option<StaticCredentials>,
+    role_arn: Option<String>,
+    source_profile: Option<String>,
+    credential_source: Option<String>,
+    external_id: Option<String>,
+    mfa_serial: Option<String>,
+    role_session_name: Option<String>,
+    duration_seconds: Option<u32>,
+    sso_session: Option<String>,
+    sso_account_id: Option<String>,
+    sso_role_name: Option<String>,
+    retry_mode: Option<RetryMode>,
+    max_attempts: Option<u32>,
+    properties: Properties,
+}
+
+impl Profile {
+    fn from_properties(name: String, properties: Properties) -> Result<Self, ParseProfileError> {
+        let get = |key: &str| properties.get(key).cloned();
+        let invalid = |key: &str, value: &str| ParseProfileError::InvalidValue {
+            profile: name.clone(),
+            key: key.to_owned(),
+            value: value.to_owned(),
+        };
+
+        let static_credentials = match (
+            properties.get("aws_access_key_id"),
+            properties.get("aws_secret_access_key"),
+        ) {
+            (Some(access_key_id), Some(secret_access_key)) => Some(StaticCredentials {
+                access_key_id: access_key_id.clone(),
+                secret_access_key: secret_access_key.clone(),
+                session_token: get("aws_session_token"),
+            }),
+            _ => None,
+        };
+
+        let retry_mode = properties
+            .get("retry_mode")
+            .map(|mode| {
+                RetryMode::try_from(mode.as_str()).map_err(|()| invalid("retry_mode", mode))
+            })
+            .transpose()?;
+
+        let max_attempts = properties
+            .get("max_attempts")
+            .map(|attempts| {
+                attempts
+                    .parse::<u32>()
+                    .map_err(|_e| invalid("max_attempts", attempts))
+            })
+            .transpose()?;
+
+        let duration_seconds = properties
+            .get("duration_seconds")
+            .map(|seconds| {
+                seconds
+                    .parse::<u32>()
+                    .map_err(|_e| invalid("duration_seconds", seconds))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            region: get("region"),
+            static_credentials,
+            role_arn: get("role_arn"),
+            source_profile: get("source_profile"),
+            credential_source: get("credential_source"),
+            external_id: get("external_id"),
+            mfa_serial: get("mfa_serial"),
+            role_session_name: get("role_session_name"),
+            duration_seconds,
+            sso_session: get("sso_session"),
+            sso_account_id: get("sso_account_id"),
+            sso_role_name: get("sso_role_name"),
+            retry_mode,
+            max_attempts,
+            name,
+            properties,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub const fn static_credentials(&self) -> Option<&StaticCredentials> {
+        self.static_credentials.as_ref()
+    }
+
+    pub fn role_arn(&self) -> Option<&str> {
+        self.role_arn.as_deref()
+    }
+
+    pub fn source_profile(&self) -> Option<&str> {
+        self.source_profile.as_deref()
+    }
+
+    /// `Environment`, `Ec2InstanceMetadata` or `EcsContainer`
+    pub fn credential_source(&self) -> Option<&str> {
+        self.credential_source.as_deref()
+    }
+
+    pub fn external_id(&self) -> Option<&str> {
+        self.external_id.as_deref()
+    }
+
+    pub fn mfa_serial(&self) -> Option<&str> {
+        self.mfa_serial.as_deref()
+    }
+
+    pub fn role_session_name(&self) -> Option<&str> {
+        self.role_session_name.as_deref()
+    }
+
+    /// How long credentials of the assumed `role_arn` are valid
+    pub const fn duration_seconds(&self) -> Option<u32> {
+        self.duration_seconds
+    }
+
+    /// Name of the `[sso-session]` section, see [`ProfileSet::sso_session_for()`]
+    pub fn sso_session(&self) -> Option<&str> {
+        self.sso_session.as_deref()
+    }
+
+    pub fn sso_account_id(&self) -> Option<&str> {
+        self.sso_account_id.as_deref()
+    }
+
+    pub fn sso_role_name(&self) -> Option<&str> {
+        self.sso_role_name.as_deref()
+    }
+
+    pub const fn retry_mode(&self) -> Option<RetryMode> {
+        self.retry_mode
+    }
+
+    pub const fn max_attempts(&self) -> Option<u32> {
+        self.max_attempts
+    }
+
+    /// Any property by its lowercase name. Nested properties are joined with
+    /// a dot, e.g. `s3.max_concurrent_requests`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Config,
+    Credentials,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Section {
+    Profile(String),
+    SsoSession(String),
+    /// e.g. `[services ...]`, which is not supported
+    Ignored,
+}
+
+#[derive(Debug, Default)]
+struct RawSections {
+    profiles: BTreeMap<String, Properties>,
+    sso_sessions: BTreeMap<String, Properties>,
+}
+
+/// Comments start with `#` or `;`, inline comments must be preceded by
+/// whitespace
+fn strip_comment(line: &str) -> &str {
+    line.char_indices()
+        .find(|&(i, c)| {
+            (c == '#' || c == ';')
+                && (i == 0
+                    || line
+                        .get(..i)
+                        .and_then(|before| before.chars().last())
+                        .is_some_and(char::is_whitespace))
+        })
+        .and_then(|(i, _)| line.get(..i))
+        .unwrap_or(line)
+}
+
+fn parse_section(header: &str, kind: FileKind) -> Section {
+    let header = header.trim();
+    match kind {
+        FileKind::Credentials => Section::Profile(header.to_owned()),
+        FileKind::Config => {
+            if header == "default" {
+                return Section::Profile(header.to_owned());
+            }
+            match header.split_once(char::is_whitespace) {
+                Some(("profile", name)) => Section::Profile(name.trim().to_owned()),
+                Some(("sso-session", name)) => Section::SsoSession(name.trim().to_owned()),
+                _ => Section::Ignored,
+            }
+        }
+    }
+}
+
+fn parse_file(
+    content: &str,
+    kind: FileKind,
+    sections: &mut RawSections,
+) -> Result<(), ParseProfileError> {
+    let mut current: Option<Section> = None;
+    let mut last_key: Option<String> = None;
+
+    for (i, raw_line) in content.lines().enumerate() {
+        let line_number = i.saturating_add(1);
+        let is_continuation = raw_line.starts_with(char::is_whitespace);
+        let line = raw_line.trim_end_matches('\r');
+
+        if line.trim().is_empty() || line.trim_start().starts_with(['#', ';']) {
+            continue;
+        }
+
+        let invalid_line = || ParseProfileError::InvalidLine {
+            line: line_number,
+            content: line.to_owned(),
+        };
+
+        if let Some(header) = line.trim().strip_prefix('[') {
+            let header = strip_comment(header)
+                .trim_end()
+                .strip_suffix(']')
+                .ok_or_else(invalid_line)?;
+            let section = parse_section(header, kind);
+            match section {
+                Section::Profile(ref name) => {
+                    let _properties = sections.profiles.entry(name.clone()).or_default();
+                }
+                Section::SsoSession(ref name) => {
+                    let _properties = sections.sso_sessions.entry(name.clone()).or_default();
+                }
+                Section::Ignored => {}
+            }
+            current = Some(section);
+            last_key = None;
+            continue;
+        }
+
+        let properties = match current {
+            None => return Err(ParseProfileError::PropertyOutsideSection { line: line_number }),
+            Some(Section::Ignored) => continue,
+            Some(Section::Profile(ref name)) => sections.profiles.entry(name.clone()),
+            Some(Section::SsoSession(ref name)) => sections.sso_sessions.entry(name.clone()),
+        }
+        .or_default();
+
+        let (key, value) = strip_comment(line)
+            .split_once('=')
+            .ok_or_else(invalid_line)?;
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_owned();
+
+        if key.is_empty() {
+            return Err(invalid_line());
+        }
+
+        match (is_continuation, &last_key) {
+            // Nested property, e.g. "  max_concurrent_requests = 10" below "s3 ="
+            (true, &Some(ref parent)) => {
+                let _previous = properties.insert(format!("{parent}.{key}"), value);
+            }
+            (true, &None) => return Err(invalid_line()),
+            (false, _) => {
+                let _previous = properties.insert(key.clone(), value);
+                last_key = Some(key);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_optional(path: &Path) -> Result<String, ParseProfileError> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(ParseProfileError::ReadFile {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        }),
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfileSet {
+    profiles: BTreeMap<String, Profile>,
+    sso_sessions: BTreeMap<String, SsoSession>,
+}
+
+impl ProfileSet {
+    pub fn parse(config: &str, credentials: &str) -> Result<Self, ParseProfileError> {
+        let mut sections = RawSections::default();
+        parse_file(config, FileKind::Config, &mut sections)?;
+        // Parsed second so its properties overwrite the ones from the config
+        parse_file(credentials, FileKind::Credentials, &mut sections)?;
+
+        Ok(Self {
+            profiles: sections
+                .profiles
+                .into_iter()
+                .map(|(name, properties)| {
+                    Profile::from_properties(name.clone(), properties)
+                        .map(|profile| (name, profile))
+                })
+                .collect::<Result<_, _>>()?,
+            sso_sessions: sections
+                .sso_sessions
+                .into_iter()
+                .map(|(name, properties)| {
+                    let session = SsoSession::from_properties(name.clone(), &properties);
+                    (name, session)
+                })
+                .collect(),
+        })
+    }
+
+    /// Loads the shared config files. Missing files are treated as empty.
+    pub fn load() -> Result<Self, Error> {
+        let aws_dir = home_dir().unwrap_or_default().join(".aws");
+
+        let config_path =
+            env::var_os("AWS_CONFIG_FILE").map_or_else(|| aws_dir.join("config"), PathBuf::from);
+        let credentials_path = env::var_os("AWS_SHARED_CREDENTIALS_FILE")
+            .map_or_else(|| aws_dir.join("credentials"), PathBuf::from);
+
+        Ok(Self::parse(
+            &read_optional(&config_path)?,
+            &read_optional(&credentials_path)?,
+        )?)
+    }
+
+    /// The profile selected by `AWS_PROFILE`, or `default`
+    pub fn selected_profile_name() -> String {
+        env::var("AWS_PROFILE").unwrap_or_else(|_e| "default".to_owned())
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    pub fn profiles(&self) -> impl Iterator<Item = &Profile> {
+        self.profiles.values()
+    }
+
+    pub fn sso_session(&self, name: &str) -> Option<&SsoSession> {
+        self.sso_sessions.get(name)
+    }
+
+    /// The `[sso-session]` referenced by a profile, if any
+    pub fn sso_session_for(
+        &self,
+        profile: &Profile,
+    ) -> Result<Option<&SsoSession>, ParseProfileError> {
+        profile
+            .sso_session()
+            .map(|session| {
+                self.sso_session(session)
+                    .ok_or_else(|| ParseProfileError::MissingSsoSession {
+                        profile: profile.name.clone(),
+                        session: session.to_owned(),
+                    })
+            })
+            .transpose()
+    }
+
+    /// Follows the `source_profile` references, starting with `name`. The
+    /// last profile of the chain is the one that provides the initial
+    /// credentials; all others assume their `role_arn` in order, starting
+    /// from the end.
+    ///
+    /// Like in the SDKs, a profile may name itself as `source_profile` if it
+    /// has static credentials, which ends the chain.
+    pub fn source_chain(&self, name: &str) -> Result<Vec<&Profile>, ParseProfileError> {
+        let mut chain: Vec<&Profile> = Vec::new();
+        let mut next = Some(name);
+
+        while let Some(name) = next {
+            let profile = self
+                .profile(name)
+                .ok_or_else(|| ParseProfileError::MissingProfile {
+                    name: name.to_owned(),
+                })?;
+
+            if chain.iter().any(|seen| seen.name == profile.name) {
+                return Err(ParseProfileError::SourceProfileCycle {
+                    profile: chain
+                        .first()
+                        .map_or_else(|| name.to_owned(), |first| first.name.clone()),
+                });
+            }
+
+            chain.push(profile);
+
+            next = match profile.source_profile() {
+                Some(source) if source == profile.name && profile.static_credentials.is_some() => {
+                    None
+                }
+                source => source,
+            };
+        }
+
+        Ok(chain)
+    }
+
+    /// Resolves the region like the SDKs: `AWS_REGION`, then
+    /// `AWS_DEFAULT_REGION`, then the `region` of the profile
+    pub fn resolve_region(&self, profile: &str) -> Option<String> {
+        self.resolve_region_with(|name| env::var(name).ok(), profile)
+    }
+
+    /// Like [`Self::resolve_region()`], reading the variables with `env`
+    pub(crate) fn resolve_region_with(
+        &self,
+        env: impl Fn(&str) -> Option<String>,
+        profile: &str,
+    ) -> Option<String> {
+        let env = |name: &str| env(name).filter(|value| !value.is_empty());
+
+        env("AWS_REGION")
+            .or_else(|| env("AWS_DEFAULT_REGION"))
+            .or_else(|| {
+                self.profile(profile)
+                    .and_then(Profile::region)
+                    .map(ToOwned::to_owned)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+# a comment
+[default]
+region = eu-central-1 ; inline comment
+retry_mode = adaptive
+max_attempts = 5
+s3 =
+  max_concurrent_requests = 10
+
+[profile dev]
+sso_session = corp
+sso_account_id = 123456789012
+sso_role_name = Developer
+
+[sso-session corp]
+sso_start_url = https://corp.awsapps.com/start#/
+sso_region = eu-west-1
+sso_registration_scopes = sso:account:access
+
+[services ignored]
+ec2 =
+  endpoint_url = http://localhost
+
+[profile admin]
+role_arn = arn:aws:iam::123456789012:role/admin
+source_profile = default
+mfa_serial = arn:aws:iam::123456789012:mfa/alice
+external_id = corp
+duration_seconds = 7200
+
+[profile loop-a]
+role_arn = arn:aws:iam::123456789012:role/a
+source_profile = loop-b
+
+[profile loop-b]
+role_arn = arn:aws:iam::123456789012:role/b
+source_profile = loop-a
+";
+
+    const CREDENTIALS: &str = "
+[default]
+aws_access_key_id = AKIAEXAMPLE
+aws_secret_access_key = secret
+region = us-east-1
+";
+
+    #[test]
+    fn parse_config_and_credentials() {
+        let profiles = ProfileSet::parse(CONFIG, CREDENTIALS).unwrap();
+
+        let default = profiles.profile("default").unwrap();
+        assert_eq!(default.region(), Some("us-east-1"));
+        assert_eq!(default.retry_mode(), Some(RetryMode::Adaptive));
+        assert_eq!(default.max_attempts(), Some(5));
+        assert_eq!(default.get("s3.max_concurrent_requests"), Some("10"));
+        assert_eq!(
+            default.static_credentials().unwrap().access_key_id,
+            "AKIAEXAMPLE"
+        );
+
+        let dev = profiles.profile("dev").unwrap();
+        let session = profiles.sso_session_for(dev).unwrap().unwrap();
+        assert_eq!(
+            session.start_url.as_deref(),
+            Some("https://corp.awsapps.com/start#/")
+        );
+        assert_eq!(session.registration_scopes, vec!["sso:account:access"]);
+
+        let admin = profiles.profile("admin").unwrap();
+        assert_eq!(
+            admin.mfa_serial(),
+            Some("arn:aws:iam::123456789012:mfa/alice")
+        );
+        assert_eq!(admin.external_id(), Some("corp"));
+        assert_eq!(admin.duration_seconds(), Some(7200));
+
+        assert!(profiles.profile("ignored").is_none());
+    }
+
+    #[test]
+    fn source_profile_cycles() {
+        let profiles = ProfileSet::parse(CONFIG, CREDENTIALS).unwrap();
+
+        assert_eq!(
+            profiles.source_chain("loop-a").unwrap_err(),
+            ParseProfileError::SourceProfileCycle {
+                profile: "loop-a".to_owned()
+            }
+        );
+        assert_eq!(profiles.source_chain("default").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn invalid_files() {
+        assert_eq!(
+            ProfileSet::parse("region = eu-central-1", "").unwrap_err(),
+            ParseProfileError::PropertyOutsideSection { line: 1 }
+        );
+        assert!(
+            ProfileSet::parse("[default]\nmax_attempts = many", "").is_err(),
+            "invalid number"
+        );
+        assert!(
+            ProfileSet::parse("[default]\nduration_seconds = 1h", "").is_err(),
+            "invalid duration"
+        );
+        assert!(
+            ProfileSet::parse("[default\n", "").is_err(),
+            "unterminated section"
+        );
+    }
+}
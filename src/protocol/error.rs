@@ -0,0 +1,118 @@
+//! Error responses of the query and XML protocols
+//!
+//! The error code and message are wrapped differently depending on the
+//! protocol:
+//!
+//! * awsQuery and restXml: `<ErrorResponse><Error><Code>` (CloudFront,
+//!   Route 53, IAM, STS, ...)
+//! * ec2Query: `<Response><Errors><Error><Code>`
+//! * S3: `<Error><Code>` directly
+
+use super::{parse_xml, FromXml};
+
+/// The code and message of an error response
+#[derive(FromXml, Debug, Clone, PartialEq, Eq)]
+#[xml(rename_all = "PascalCase")]
+pub(crate) struct ErrorDetails {
+    pub(crate) code: String,
+    pub(crate) message: Option<String>,
+}
+
+#[derive(FromXml)]
+#[xml(rename_all = "PascalCase")]
+struct ErrorResponse {
+    error: ErrorDetails,
+}
+
+#[derive(FromXml)]
+#[xml(rename_all = "PascalCase")]
+struct Ec2ErrorResponse {
+    errors: Vec<ErrorDetails>,
+}
+
+/// The details of an XML error response. `None` if `body` is not one, e.g.
+/// for JSON protocols or empty bodies of `HEAD` requests.
+pub(crate) fn parse_error_details(body: &str) -> Option<ErrorDetails> {
+    let root = parse_xml(body).ok()?;
+
+    match root.name() {
+        "Error" => ErrorDetails::from_xml(&root).ok(),
+        "ErrorResponse" => ErrorResponse::from_xml(&root)
+            .ok()
+            .map(|response| response.error),
+        "Response" => Ec2ErrorResponse::from_xml(&root)
+            .ok()
+            .and_then(|response| response.errors.into_iter().next()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(code: &str, message: Option<&str>) -> Option<ErrorDetails> {
+        Some(ErrorDetails {
+            code: code.to_owned(),
+            message: message.map(ToOwned::to_owned),
+        })
+    }
+
+    #[test]
+    fn query() {
+        assert_eq!(
+            parse_error_details(
+                r#"<ErrorResponse xmlns="http://elasticache.amazonaws.com/doc/2015-02-02/">
+                  <Error>
+                    <Type>Sender</Type>
+                    <Code>CacheClusterNotFound</Code>
+                    <Message>CacheCluster not found: redis-1</Message>
+                  </Error>
+                  <RequestId>4d9c6d0e-2d1a-4f5b-9b3e-0c2a1f7e8d9a</RequestId>
+                </ErrorResponse>"#
+            ),
+            details(
+                "CacheClusterNotFound",
+                Some("CacheCluster not found: redis-1")
+            )
+        );
+    }
+
+    #[test]
+    fn ec2() {
+        assert_eq!(
+            parse_error_details(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <Response><Errors><Error>\
+                 <Code>InvalidAllocationID.NotFound</Code>\
+                 <Message/>\
+                 </Error></Errors><RequestID>ea966190-f9aa-478e-9ede-cb5432daacc0</RequestID></Response>"
+            ),
+            details("InvalidAllocationID.NotFound", Some(""))
+        );
+    }
+
+    #[test]
+    fn s3() {
+        assert_eq!(
+            parse_error_details(
+                "<Error><Code>NoSuchKey</Code><Key>a.txt</Key><RequestId>4442587FB7D0A2F9</RequestId></Error>"
+            ),
+            details("NoSuchKey", None)
+        );
+    }
+
+    #[test]
+    fn not_an_error() {
+        assert_eq!(parse_error_details(""), None);
+        assert_eq!(
+            parse_error_details(r#"{"__type":"ResourceNotFoundException"}"#),
+            None
+        );
+        assert_eq!(
+            parse_error_details("<DescribeTagsResponse><tagSet/></DescribeTagsResponse>"),
+            None
+        );
+        assert_eq!(parse_error_details("<Response><Errors/></Response>"), None);
+    }
+}
@@ -0,0 +1,523 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseXmlError {
+    Syntax {
+        position: usize,
+        message: String,
+    },
+    MissingElement {
+        parent: String,
+        name: String,
+    },
+    InvalidValue {
+        element: String,
+        value: String,
+        message: String,
+    },
+}
+
+impl std::error::Error for ParseXmlError {}
+
+impl fmt::Display for ParseXmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Syntax {
+                position,
+                ref message,
+            } => write!(f, "invalid xml at character {position}: {message}"),
+            Self::MissingElement {
+                ref parent,
+                ref name,
+            } => write!(f, "element <{parent}> has no child <{name}>"),
+            Self::InvalidValue {
+                ref element,
+                ref value,
+                ref message,
+            } => write!(f, "invalid value \"{value}\" of <{element}>: {message}"),
+        }
+    }
+}
+
+/// A parsed XML element. Namespace prefixes are removed from the names, and
+/// attributes are dropped, as AWS responses carry all data in elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlElement {
+    name: String,
+    text: String,
+    children: Vec<Self>,
+}
+
+impl XmlElement {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The text content, with entities and CDATA sections resolved. Empty for
+    /// empty elements like `<tagSet/>`.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn children(&self) -> &[Self] {
+        &self.children
+    }
+
+    /// The first child with the given name
+    pub fn child(&self, name: &str) -> Option<&Self> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Parses all children with the given name. Used for flattened lists,
+    /// where the list items are direct children of the parent instead of
+    /// being wrapped in a list element.
+    pub fn flattened<T: FromXml>(&self, name: &str) -> Result<Vec<T>, ParseXmlError> {
+        self.children
+            .iter()
+            .filter(|child| child.name == name)
+            .map(T::from_xml)
+            .collect()
+    }
+
+    /// Parses the first child with the given name. If there is none,
+    /// [`FromXml::from_missing()`] decides whether that is an error.
+    pub fn required<T: FromXml>(&self, name: &str) -> Result<T, ParseXmlError> {
+        match self.child(name) {
+            Some(child) => T::from_xml(child),
+            None => T::from_missing().ok_or_else(|| ParseXmlError::MissingElement {
+                parent: self.name.clone(),
+                name: name.to_owned(),
+            }),
+        }
+    }
+
+    /// Parses the first child with the given name, if there is one
+    pub fn optional<T: FromXml>(&self, name: &str) -> Result<Option<T>, ParseXmlError> {
+        self.child(name).map(T::from_xml).transpose()
+    }
+
+    fn invalid_value(&self, message: impl fmt::Display) -> ParseXmlError {
+        ParseXmlError::InvalidValue {
+            element: self.name.clone(),
+            value: self.text.clone(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Conversion from an XML element. Can be derived with [`FromXml`](macro@FromXml).
+pub trait FromXml: Sized {
+    fn from_xml(element: &XmlElement) -> Result<Self, ParseXmlError>;
+
+    /// The value used when the element is missing completely. By default,
+    /// missing elements are an error.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+impl FromXml for String {
+    fn from_xml(element: &XmlElement) -> Result<Self, ParseXmlError> {
+        Ok(element.text.clone())
+    }
+}
+
+impl FromXml for bool {
+    fn from_xml(element: &XmlElement) -> Result<Self, ParseXmlError> {
+        match element.text.trim() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(element.invalid_value("expected true or false")),
+        }
+    }
+}
+
+macro_rules! impl_from_xml_parse {
+    ($($ty:ty),*) => {
+        $(
+            impl FromXml for $ty {
+                fn from_xml(element: &XmlElement) -> Result<Self, ParseXmlError> {
+                    element
+                        .text
+                        .trim()
+                        .parse()
+                        .map_err(|e| element.invalid_value(e))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_xml_parse!(i32, i64, u32, u64, f64);
+
+impl FromXml for DateTime<Utc> {
+    fn from_xml(element: &XmlElement) -> Result<Self, ParseXmlError> {
+        DateTime::parse_from_rfc3339(element.text.trim())
+            .map(|timestamp| timestamp.with_timezone(&Utc))
+            .map_err(|e| element.invalid_value(e))
+    }
+}
+
+/// A wrapped list: every child is an item, regardless of its name (`member`
+/// for the query protocol, `item` for EC2)
+impl<T: FromXml> FromXml for Vec<T> {
+    fn from_xml(element: &XmlElement) -> Result<Self, ParseXmlError> {
+        element.children.iter().map(T::from_xml).collect()
+    }
+
+    /// EC2 leaves out empty lists
+    fn from_missing() -> Option<Self> {
+        Some(Self::new())
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars
+            .get(self.position.saturating_add(offset))
+            .copied()
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        prefix
+            .chars()
+            .enumerate()
+            .all(|(offset, c)| self.peek(offset) == Some(c))
+    }
+
+    fn advance(&mut self, count: usize) {
+        self.position = self.position.saturating_add(count);
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseXmlError {
+        ParseXmlError::Syntax {
+            position: self.position,
+            message: message.into(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek(0).is_some_and(char::is_whitespace) {
+            self.advance(1);
+        }
+    }
+
+    /// Consumes everything up to and including `end`, returning the skipped
+    /// part
+    fn take_until(&mut self, end: &str) -> Result<String, ParseXmlError> {
+        let mut taken = String::new();
+        loop {
+            if self.starts_with(end) {
+                self.advance(end.chars().count());
+                return Ok(taken);
+            }
+            match self.peek(0) {
+                Some(c) => {
+                    taken.push(c);
+                    self.advance(1);
+                }
+                None => return Err(self.error(format!("expected \"{end}\""))),
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseXmlError> {
+        if self.peek(0) == Some(c) {
+            self.advance(1);
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{c}'")))
+        }
+    }
+
+    /// Skips the declaration, processing instructions, comments and doctypes
+    fn skip_misc(&mut self) -> Result<(), ParseXmlError> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with("<?") {
+                let _instruction = self.take_until("?>")?;
+            } else if self.starts_with("<!--") {
+                let _comment = self.take_until("-->")?;
+            } else if self.starts_with("<!DOCTYPE") {
+                let _doctype = self.take_until(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, ParseXmlError> {
+        let mut name = String::new();
+        while let Some(c) = self.peek(0) {
+            if c.is_whitespace() || matches!(c, '/' | '>' | '=') {
+                break;
+            }
+            name.push(c);
+            self.advance(1);
+        }
+        if name.is_empty() {
+            return Err(self.error("expected a name"));
+        }
+        Ok(name)
+    }
+
+    fn entity(&mut self) -> Result<char, ParseXmlError> {
+        self.expect('&')?;
+        let entity = self.take_until(";")?;
+        let resolved = match entity.as_str() {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        resolved.ok_or_else(|| self.error(format!("unknown entity \"&{entity};\"")))
+    }
+
+    fn skip_attributes(&mut self) -> Result<(), ParseXmlError> {
+        loop {
+            self.skip_whitespace();
+            match self.peek(0) {
+                Some('/' | '>') => return Ok(()),
+                Some(_) => {
+                    let _name = self.name()?;
+                    self.skip_whitespace();
+                    self.expect('=')?;
+                    self.skip_whitespace();
+                    match self.peek(0) {
+                        Some(quote @ ('"' | '\'')) => {
+                            self.advance(1);
+                            let _value = self.take_until(&quote.to_string())?;
+                        }
+                        _ => return Err(self.error("expected a quoted attribute value")),
+                    }
+                }
+                None => return Err(self.error("unexpected end of input")),
+            }
+        }
+    }
+
+    fn element(&mut self) -> Result<XmlElement, ParseXmlError> {
+        self.expect('<')?;
+        let raw_name = self.name()?;
+        let name = raw_name
+            .rsplit_once(':')
+            .map_or(raw_name.as_str(), |(_prefix, local)| local)
+            .to_owned();
+
+        self.skip_attributes()?;
+
+        let mut element = XmlElement {
+            name,
+            text: String::new(),
+            children: Vec::new(),
+        };
+
+        if self.starts_with("/>") {
+            self.advance(2);
+            return Ok(element);
+        }
+        self.expect('>')?;
+
+        loop {
+            if self.starts_with("</") {
+                self.advance(2);
+                let closing = self.name()?;
+                if closing != raw_name {
+                    return Err(self.error(format!(
+                        "closing tag </{closing}> does not match <{raw_name}>"
+                    )));
+                }
+                self.skip_whitespace();
+                self.expect('>')?;
+                return Ok(element);
+            } else if self.starts_with("<!--") {
+                let _comment = self.take_until("-->")?;
+            } else if self.starts_with("<![CDATA[") {
+                self.advance("<![CDATA[".len());
+                let cdata = self.take_until("]]>")?;
+                element.text.push_str(&cdata);
+            } else if self.starts_with("<?") {
+                let _instruction = self.take_until("?>")?;
+            } else {
+                match self.peek(0) {
+                    Some('<') => element.children.push(self.element()?),
+                    Some('&') => element.text.push(self.entity()?),
+                    Some(c) => {
+                        element.text.push(c);
+                        self.advance(1);
+                    }
+                    None => return Err(self.error(format!("<{raw_name}> is not closed"))),
+                }
+            }
+        }
+    }
+}
+
+/// Parses a document and returns its root element
+pub fn parse_xml(input: &str) -> Result<XmlElement, ParseXmlError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        position: 0,
+    };
+
+    parser.skip_misc()?;
+    let mut root = parser.element()?;
+    parser.skip_misc()?;
+
+    if parser.peek(0).is_some() {
+        return Err(parser.error("content after the root element"));
+    }
+
+    trim_containers(&mut root);
+    Ok(root)
+}
+
+/// Whitespace between child elements is formatting, not content
+fn trim_containers(element: &mut XmlElement) {
+    if !element.children.is_empty() {
+        element.text.clear();
+        element.children.iter_mut().for_each(trim_containers);
+    }
+}
+
+/// Parses a document into `T`, starting at the root element
+pub fn from_xml_str<T: FromXml>(input: &str) -> Result<T, ParseXmlError> {
+    T::from_xml(&parse_xml(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let root = parse_xml(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <DescribeTagsResponse xmlns="http://ec2.amazonaws.com/doc/2016-11-15/">
+                <!-- comment -->
+                <requestId>7a62c49f</requestId>
+                <tagSet>
+                    <item><key>a &amp; b</key><value><![CDATA[<x>]]></value></item>
+                    <item><key>&#x41;&#66;</key><value/></item>
+                </tagSet>
+                <s3:Empty xmlns:s3="urn:s3"></s3:Empty>
+            </DescribeTagsResponse>"#,
+        )
+        .unwrap();
+
+        assert_eq!(root.name(), "DescribeTagsResponse");
+        assert_eq!(root.text(), "");
+        assert_eq!(root.child("requestId").unwrap().text(), "7a62c49f");
+
+        let items = root.child("tagSet").unwrap().children();
+        assert_eq!(items.len(), 2);
+        let mut items = items.iter();
+        let first = items.next().unwrap();
+        assert_eq!(first.child("key").unwrap().text(), "a & b");
+        assert_eq!(first.child("value").unwrap().text(), "<x>");
+        let second = items.next().unwrap();
+        assert_eq!(second.child("key").unwrap().text(), "AB");
+        assert_eq!(second.child("value").unwrap().text(), "");
+
+        assert_eq!(root.child("Empty").unwrap().text(), "");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            parse_xml("<a><b></a>"),
+            Err(ParseXmlError::Syntax { .. })
+        ));
+        assert!(matches!(
+            parse_xml("<a>"),
+            Err(ParseXmlError::Syntax { .. })
+        ));
+        assert!(matches!(
+            parse_xml("<a/><b/>"),
+            Err(ParseXmlError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn derive() {
+        #[derive(crate::protocol::FromXml, Debug, PartialEq)]
+        struct Tag {
+            key: String,
+            value: Option<String>,
+        }
+
+        #[derive(crate::protocol::FromXml, Debug, PartialEq)]
+        struct Response {
+            request_id: String,
+            tag_set: Vec<Tag>,
+            #[xml(name = "nextToken")]
+            token: Option<String>,
+            #[xml(flatten)]
+            #[xml(name = "resource")]
+            resources: Vec<String>,
+        }
+
+        #[derive(crate::protocol::FromXml, Debug, PartialEq)]
+        #[xml(rename_all = "PascalCase")]
+        struct Owner {
+            display_name: String,
+        }
+
+        let response: Response = from_xml_str(
+            "<Response><requestId>r</requestId>\
+             <tagSet><item><key>a</key><value>b</value></item><item><key>c</key></item></tagSet>\
+             <resource>x</resource><resource>y</resource></Response>",
+        )
+        .unwrap();
+
+        assert_eq!(
+            response,
+            Response {
+                request_id: "r".to_owned(),
+                tag_set: vec![
+                    Tag {
+                        key: "a".to_owned(),
+                        value: Some("b".to_owned()),
+                    },
+                    Tag {
+                        key: "c".to_owned(),
+                        value: None,
+                    },
+                ],
+                token: None,
+                resources: vec!["x".to_owned(), "y".to_owned()],
+            }
+        );
+
+        let owner: Owner = from_xml_str("<Owner><DisplayName>me</DisplayName></Owner>").unwrap();
+        assert_eq!(owner.display_name, "me");
+    }
+
+    #[test]
+    fn values() {
+        let root = parse_xml("<r><n> 42 </n><b>maybe</b></r>").unwrap();
+
+        assert_eq!(root.required::<i64>("n"), Ok(42));
+        assert!(root.required::<bool>("b").is_err(), "not a boolean");
+        assert_eq!(root.optional::<String>("missing"), Ok(None));
+        assert_eq!(root.required::<Vec<String>>("missing"), Ok(Vec::new()));
+        assert!(matches!(
+            root.required::<String>("missing"),
+            Err(ParseXmlError::MissingElement { .. })
+        ));
+    }
+}
@@ -0,0 +1,385 @@
+use std::fmt::{self, Write as _};
+
+use super::{base64, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseJsonError {
+    position: usize,
+    message: String,
+}
+
+impl std::error::Error for ParseJsonError {}
+
+impl fmt::Display for ParseJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid json at character {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                write!(out, "\\u{:04x}", u32::from(c)).expect("writing to a string never fails");
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_members(out: &mut String, members: &[(String, Value)]) {
+    out.push('{');
+    for (index, member) in members.iter().enumerate() {
+        let (ref name, ref value) = *member;
+        if index > 0 {
+            out.push(',');
+        }
+        write_string(out, name);
+        out.push(':');
+        write_value(out, value);
+    }
+    out.push('}');
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match *value {
+        Value::String(ref value) => write_string(out, value),
+        Value::Integer(value) => out.push_str(&value.to_string()),
+        // JSON has no representation for NaN and infinity, AWS expects them
+        // as strings
+        Value::Float(value) if value.is_finite() => out.push_str(&value.to_string()),
+        Value::Float(value) => write_string(out, &value.to_string()),
+        Value::Boolean(value) => out.push_str(if value { "true" } else { "false" }),
+        // Epoch seconds
+        Value::Timestamp(ref value) => out.push_str(&value.timestamp().to_string()),
+        Value::Blob(ref value) => write_string(out, &base64(value)),
+        Value::List(ref items) | Value::NamedList(_, ref items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Map(ref members) | Value::Structure(ref members) => write_members(out, members),
+    }
+}
+
+pub(super) fn serialize(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn advance(&mut self) {
+        self.position = self.position.saturating_add(1);
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseJsonError {
+        ParseJsonError {
+            position: self.position,
+            message: message.into(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseJsonError> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{c}'")))
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), ParseJsonError> {
+        for c in keyword.chars() {
+            if self.peek() != Some(c) {
+                return Err(self.error(format!("expected \"{keyword}\"")));
+            }
+            self.advance();
+        }
+        Ok(())
+    }
+
+    fn hex_escape(&mut self) -> Result<u32, ParseJsonError> {
+        let mut code = 0_u32;
+        for _ in 0..4_u8 {
+            let digit = self
+                .peek()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("expected a hex digit"))?;
+            code = code.saturating_mul(16).saturating_add(digit);
+            self.advance();
+        }
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, ParseJsonError> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("string is not closed"))?;
+            self.advance();
+            match c {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("string is not closed"))?;
+                    self.advance();
+                    value.push(match escaped {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let mut code = self.hex_escape()?;
+                            // Characters outside of the BMP are escaped as a
+                            // surrogate pair
+                            if (0xD800..0xDC00).contains(&code) {
+                                self.keyword("\\u")?;
+                                let low = self.hex_escape()?;
+                                code = 0x1_0000_u32
+                                    .saturating_add(
+                                        code.saturating_sub(0xD800).saturating_mul(0x400),
+                                    )
+                                    .saturating_add(low.saturating_sub(0xDC00));
+                            }
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        other => return Err(self.error(format!("invalid escape '\\{other}'"))),
+                    });
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ParseJsonError> {
+        let mut number = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|&c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            number.push(c);
+            self.advance();
+        }
+
+        // Integers that do not fit into an i64 are kept as floats
+        if let Ok(value) = number.parse::<i64>() {
+            return Ok(Value::Integer(value));
+        }
+        number
+            .parse::<f64>()
+            .map(Value::Float)
+            .map_err(|e| self.error(format!("invalid number \"{number}\": {e}")))
+    }
+
+    /// `None` for `null`, which AWS uses like a missing member
+    fn value(&mut self) -> Result<Option<Value>, ParseJsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.advance();
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.advance();
+                    return Ok(Some(Value::Structure(members)));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let name = self.string()?;
+                    self.expect(':')?;
+                    if let Some(value) = self.value()? {
+                        members.push((name, value));
+                    }
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.advance(),
+                        Some('}') => {
+                            self.advance();
+                            return Ok(Some(Value::Structure(members)));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some('[') => {
+                self.advance();
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.advance();
+                    return Ok(Some(Value::List(items)));
+                }
+                loop {
+                    if let Some(item) = self.value()? {
+                        items.push(item);
+                    }
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.advance(),
+                        Some(']') => {
+                            self.advance();
+                            return Ok(Some(Value::List(items)));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some('"') => self.string().map(|value| Some(Value::String(value))),
+            Some('t') => self.keyword("true").map(|()| Some(Value::Boolean(true))),
+            Some('f') => self.keyword("false").map(|()| Some(Value::Boolean(false))),
+            Some('n') => self.keyword("null").map(|()| None),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number().map(Some),
+            Some(c) => Err(self.error(format!("unexpected character '{c}'"))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+}
+
+/// Parses a JSON document. Objects become [`Value::Structure`], as the JSON
+/// protocols do not distinguish them from maps. `null` members and items
+/// are left out, and timestamps stay numbers of epoch seconds.
+pub fn parse_json(input: &str) -> Result<Value, ParseJsonError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        position: 0,
+    };
+
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("content after the value"));
+    }
+
+    Ok(value.unwrap_or_else(|| Value::Structure(Vec::new())))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+
+    #[test]
+    fn serialize_structure() {
+        let value = Value::structure([
+            ("Name", Value::from("quote \" and \u{1}")),
+            ("Count", Value::from(3_i64)),
+            ("Enabled", Value::from(false)),
+            (
+                "Since",
+                Value::from(DateTime::from_timestamp(1_700_000_000, 0).unwrap()),
+            ),
+            ("Payload", Value::Blob(b"hi".to_vec())),
+            ("Tags", Value::map([("env", Value::from("prod"))])),
+            ("Ids", Value::list([Value::from(1_i64), Value::from(2_i64)])),
+        ]);
+
+        assert_eq!(
+            serialize(&value),
+            r#"{"Name":"quote \" and \u0001","Count":3,"Enabled":false,"Since":1700000000,"Payload":"aGk=","Tags":{"env":"prod"},"Ids":[1,2]}"#
+        );
+    }
+
+    #[test]
+    fn parse_document() {
+        let value = parse_json(
+            r#" {"DomainStatus": {"DomainName": "logs \"a\" \u00e9\ud83d\ude00", "Processing": false,
+                "ClusterConfig": {"InstanceCount": 3, "Ratio": 0.5, "Big": 1e3},
+                "Endpoint": null, "Ids": [1, null, -2], "Empty": {}, "None": []}} "#,
+        )
+        .unwrap();
+
+        let status = value.get("DomainStatus").unwrap();
+        assert_eq!(
+            status.get("DomainName").and_then(Value::as_str),
+            Some("logs \"a\" \u{e9}\u{1f600}")
+        );
+        assert_eq!(
+            status.get("Processing").and_then(Value::as_bool),
+            Some(false)
+        );
+        let config = status.get("ClusterConfig").unwrap();
+        assert_eq!(config.get("InstanceCount").and_then(Value::as_i64), Some(3));
+        assert_eq!(config.get("Ratio"), Some(&Value::Float(0.5)));
+        assert_eq!(config.get("Big"), Some(&Value::Float(1000.0)));
+        assert_eq!(status.get("Endpoint"), None);
+        assert_eq!(
+            status.get("Ids").map(Value::items),
+            Some([Value::Integer(1), Value::Integer(-2)].as_slice())
+        );
+        assert_eq!(status.get("Empty"), Some(&Value::Structure(Vec::new())));
+        assert_eq!(status.get("None").map(Value::items), Some([].as_slice()));
+
+        assert_eq!(parse_json("null"), Ok(Value::Structure(Vec::new())));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for input in [
+            "",
+            "{",
+            r#"{"a" 1}"#,
+            "[1,]",
+            r#""\x""#,
+            "tru",
+            "{} {}",
+            "1.2.3",
+        ] {
+            assert!(parse_json(input).is_err(), "{input} is not valid json");
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let value = Value::structure([
+            ("Name", Value::from("tab\t")),
+            (
+                "Tags",
+                Value::list([Value::structure([("Key", Value::from("k"))])]),
+            ),
+        ]);
+
+        assert_eq!(parse_json(&serialize(&value)), Ok(value));
+    }
+}
@@ -0,0 +1,316 @@
+//! Request IDs and error details of responses
+//!
+//! AWS support and CloudTrail identify requests by their request ID, and S3
+//! additionally by its extended request ID (`x-amz-id-2`). Failed operations
+//! carry the [`RequestMetadata`] of their last response in the error, see
+//! [`Error::request_metadata()`](crate::Error::request_metadata).
+//!
+//! The results of successful operations do not carry any metadata, as that
+//! would change the return type of every operation of the service clients.
+//! To get the metadata of successful operations, run them with the client of
+//! [`with_request_metadata()`], which collects the metadata of every request
+//! they send:
+//!
+//! ```no_run
+//! # async fn f(client: &aws_lib::RegionClient) -> Result<(), aws_lib::Error> {
+//! use aws_lib::{protocol::with_request_metadata, Instance};
+//!
+//! let result = with_request_metadata(client, |client| async move {
+//!     Instance::list(&client, vec![]).await
+//! })
+//! .await;
+//! for request in result.requests() {
+//!     println!("{request}");
+//! }
+//! let instances = result.into_inner()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    future::Future,
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use aws_sdk_ec2::config::SharedInterceptor;
+use aws_smithy_runtime_api::{
+    box_error::BoxError,
+    client::{
+        interceptors::{context::FinalizerInterceptorContextRef, Intercept},
+        orchestrator::{HttpResponse, Metadata},
+        runtime_components::RuntimeComponents,
+    },
+};
+use aws_smithy_types::config_bag::ConfigBag;
+
+use crate::{ConfigOverride, RegionClient};
+
+/// Services use different headers for the request ID
+const REQUEST_ID_HEADERS: &[&str] = &["x-amzn-requestid", "x-amz-request-id", "x-amzn-request-id"];
+const EXTENDED_REQUEST_ID_HEADER: &str = "x-amz-id-2";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestMetadata {
+    /// The service, e.g. `ec2`. Only known for requests collected with
+    /// [`with_request_metadata()`].
+    pub service: Option<String>,
+    /// The operation, e.g. `DescribeInstances`. Only known for requests
+    /// collected with [`with_request_metadata()`].
+    pub operation: Option<String>,
+    pub status: Option<u16>,
+    pub request_id: Option<String>,
+    /// Only sent by S3
+    pub extended_request_id: Option<String>,
+    /// The error code of failed requests, e.g. `InvalidInstanceID.NotFound`
+    pub error_code: Option<String>,
+}
+
+impl RequestMetadata {
+    pub fn from_response(response: &HttpResponse) -> Self {
+        Self {
+            service: None,
+            operation: None,
+            status: Some(response.status().as_u16()),
+            request_id: REQUEST_ID_HEADERS
+                .iter()
+                .find_map(|&header| response.headers().get(header))
+                .map(ToOwned::to_owned),
+            extended_request_id: response
+                .headers()
+                .get(EXTENDED_REQUEST_ID_HEADER)
+                .map(ToOwned::to_owned),
+            error_code: if response.status().is_success() {
+                None
+            } else {
+                crate::metrics::error_code(response)
+            },
+        }
+    }
+}
+
+impl fmt::Display for RequestMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(service), Some(operation)) =
+            (self.service.as_deref(), self.operation.as_deref())
+        {
+            write!(f, "{service}:{operation} ")?;
+        }
+        write!(
+            f,
+            "request id {}",
+            self.request_id.as_deref().unwrap_or("unknown")
+        )?;
+        if let Some(ref extended_request_id) = self.extended_request_id {
+            write!(f, ", extended request id {extended_request_id}")?;
+        }
+        if let Some(status) = self.status {
+            write!(f, ", status {status}")?;
+        }
+        if let Some(ref error_code) = self.error_code {
+            write!(f, ", error {error_code}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of an operation together with the metadata of all requests it
+/// sent, in the order they completed
+#[derive(Debug, Clone)]
+pub struct WithMetadata<T> {
+    value: T,
+    requests: Vec<RequestMetadata>,
+}
+
+impl<T> WithMetadata<T> {
+    pub const fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    pub fn requests(&self) -> &[RequestMetadata] {
+        &self.requests
+    }
+
+    /// The metadata of the last request, which is usually the one to quote
+    /// in a support ticket
+    pub fn request_metadata(&self) -> Option<&RequestMetadata> {
+        self.requests.last()
+    }
+}
+
+/// Collects the [`RequestMetadata`] of all requests sent with the client
+/// that `f` gets
+///
+/// The client is a copy of `client` with an additional interceptor, so
+/// requests are collected no matter where they are sent, including spawned
+/// tasks and other threads. Requests that are still in flight when the
+/// future of `f` completes are not included. Building the copy is as
+/// expensive as [`RegionClient::with_config()`].
+pub async fn with_request_metadata<F, Fut>(client: &RegionClient, f: F) -> WithMetadata<Fut::Output>
+where
+    F: FnOnce(RegionClient) -> Fut,
+    Fut: Future,
+{
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let interceptor = SharedInterceptor::new(RequestMetadataInterceptor {
+        requests: Arc::clone(&requests),
+    });
+
+    let value = f(client.rebuild(&ConfigOverride::default(), Some(&interceptor))).await;
+
+    WithMetadata {
+        value,
+        requests: requests
+            .lock()
+            .map(|mut requests| mem::take(&mut *requests))
+            .unwrap_or_default(),
+    }
+}
+
+/// Appends the metadata of each response to `requests`
+#[derive(Debug)]
+struct RequestMetadataInterceptor {
+    requests: Arc<Mutex<Vec<RequestMetadata>>>,
+}
+
+impl Intercept for RequestMetadataInterceptor {
+    fn name(&self) -> &'static str {
+        "RequestMetadataInterceptor"
+    }
+
+    fn read_after_execution(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let Some(response) = context.response() else {
+            return Ok(());
+        };
+
+        let metadata = cfg.load::<Metadata>();
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(RequestMetadata {
+                service: metadata.map(|metadata| metadata.service().to_owned()),
+                operation: metadata.map(|metadata| metadata.name().to_owned()),
+                ..RequestMetadata::from_response(response)
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let metadata = RequestMetadata {
+            service: Some("s3".to_owned()),
+            operation: Some("GetObject".to_owned()),
+            status: Some(404),
+            request_id: Some("4442587FB7D0A2F9".to_owned()),
+            extended_request_id: Some(
+                "vlR7PnpV2Ce81l0PRw6jlUpck7Jo5ZsQjryTjKlc5aLWGVHPZLj5NeC6qMa0emYBDXOo6QBU0Wo="
+                    .to_owned(),
+            ),
+            error_code: Some("NoSuchKey".to_owned()),
+        };
+
+        assert_eq!(
+            metadata.to_string(),
+            "s3:GetObject request id 4442587FB7D0A2F9, extended request id vlR7PnpV2Ce81l0PRw6jlUpck7Jo5ZsQjryTjKlc5aLWGVHPZLj5NeC6qMa0emYBDXOo6QBU0Wo=, status 404, error NoSuchKey"
+        );
+        assert_eq!(RequestMetadata::default().to_string(), "request id unknown");
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn error_metadata() {
+        use crate::{
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Error, Region,
+        };
+
+        let http = MockHttpClient::new().on(
+            Matcher::action("DescribeInstances"),
+            MockResponse::status(
+                400,
+                "<Response><Errors><Error><Code>UnauthorizedOperation</Code>\
+                 <Message>denied</Message></Error></Errors></Response>",
+            )
+            .with_header("x-amzn-requestid", "8f7e9a3c"),
+        );
+        let client = mock_region_client(Region::EuCentral1, http);
+
+        let error = Error::from(block_on(client.main.ec2.describe_instances().send()).unwrap_err());
+
+        let Error::SdkError(ref sdk_error, _) = error else {
+            panic!("unexpected error {error:?}");
+        };
+        assert!(
+            sdk_error
+                .downcast_ref::<aws_sdk_ec2::error::SdkError<
+                    aws_sdk_ec2::operation::describe_instances::DescribeInstancesError,
+                    HttpResponse,
+                >>()
+                .is_some(),
+            "the SDK error is kept as is"
+        );
+        let metadata = error.request_metadata().unwrap();
+        assert_eq!(metadata.request_id.as_deref(), Some("8f7e9a3c"));
+        assert_eq!(metadata.status, Some(400));
+        assert!(error.to_string().ends_with("(request id 8f7e9a3c)"));
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn spawned_requests() {
+        use crate::{
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        let http = MockHttpClient::new().on(
+            Matcher::action("DescribeInstances"),
+            MockResponse::ok(
+                "<DescribeInstancesResponse><reservationSet/></DescribeInstancesResponse>",
+            )
+            .with_header("x-amzn-requestid", "5d1a3b7e"),
+        );
+        let client = mock_region_client(Region::EuCentral1, http);
+
+        let result = block_on(with_request_metadata(&client, |client| async move {
+            let task = tokio::spawn({
+                let client = client.clone();
+                async move { client.main.ec2.describe_instances().send().await }
+            });
+            let thread =
+                std::thread::spawn(move || block_on(client.main.ec2.describe_instances().send()));
+            (task.await.unwrap().is_ok(), thread.join().unwrap().is_ok())
+        }));
+
+        assert_eq!(*result.value(), (true, true));
+        assert_eq!(result.requests().len(), 2, "task and thread are collected");
+        for request in result.requests() {
+            assert_eq!(request.operation.as_deref(), Some("DescribeInstances"));
+            assert_eq!(request.request_id.as_deref(), Some("5d1a3b7e"));
+        }
+
+        // Requests with the original client are not collected
+        let result = block_on(with_request_metadata(&client, |_client| async {
+            client.main.ec2.describe_instances().send().await.is_ok()
+        }));
+        assert!(
+            result.requests().is_empty(),
+            "sent with the original client"
+        );
+    }
+}
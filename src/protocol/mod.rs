@@ -0,0 +1,311 @@
+//! Wire encoding of request bodies for the AWS protocols
+//!
+//! A service client describes its input as a [`Shape`], which turns itself
+//! into a protocol-independent [`Value`] tree. [`Protocol::serialize()`] then
+//! produces the request body for the protocol the service speaks:
+//!
+//! * [`Protocol::Ec2Query`]: form-encoded, used only by EC2
+//! * [`Protocol::AwsQuery`]: form-encoded, used by e.g. IAM, STS, SQS and
+//!   CloudFormation
+//! * [`Protocol::RestJson1`]: JSON body, used by e.g. Lambda and EKS
+//! * [`Protocol::RestXml`]: XML body, used by S3, CloudFront and Route 53
+//!
+//! Responses of the query and XML protocols are parsed with [`from_xml_str()`]
+//! into types implementing [`FromXml`](trait@FromXml), usually via the derive
+//! macro of the same name. Responses of the JSON protocols are parsed into a
+//! [`Value`] with [`parse_json()`].
+//!
+//! The [`RequestMetadata`] of responses, most importantly the request ID, is
+//! kept in errors and can be collected for successful operations with
+//! [`with_request_metadata()`].
+//!
+//! For the REST protocols, only the body is handled. Members bound to the
+//! URI, the query string or headers must be left out of the shape and added
+//! by the caller.
+//!
+//! ```rust
+//! use aws_lib::protocol::{Operation, Protocol, Shape, Value};
+//!
+//! struct DescribeTags {
+//!     resource_ids: Vec<String>,
+//! }
+//!
+//! impl Shape for DescribeTags {
+//!     const NAME: &'static str = "DescribeTagsRequest";
+//!
+//!     fn to_value(&self) -> Value {
+//!         Value::structure([(
+//!             "ResourceId",
+//!             Value::list(self.resource_ids.iter().cloned().map(Value::String)),
+//!         )])
+//!     }
+//! }
+//!
+//! let operation = Operation::new("DescribeTags", "2016-11-15");
+//! let input = DescribeTags {
+//!     resource_ids: vec!["i-1".to_owned(), "i-2".to_owned()],
+//! };
+//!
+//! let body = Protocol::Ec2Query.serialize(&operation, &input);
+//! assert_eq!(
+//!     body.as_str(),
+//!     "Action=DescribeTags&Version=2016-11-15&ResourceId.1=i-1&ResourceId.2=i-2"
+//! );
+//! ```
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+
+mod error;
+mod from_xml;
+mod json;
+mod metadata;
+mod query;
+mod xml;
+
+pub use aws_macros::FromXml;
+pub(crate) use error::parse_error_details;
+pub use from_xml::{from_xml_str, parse_xml, FromXml, ParseXmlError, XmlElement};
+pub use json::{parse_json, ParseJsonError};
+pub use metadata::{with_request_metadata, RequestMetadata, WithMetadata};
+pub(crate) use query::url_encode;
+
+/// A protocol-independent value of a request member
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Timestamp(DateTime<Utc>),
+    /// Encoded as base64
+    Blob(Vec<u8>),
+    List(Vec<Self>),
+    /// A list whose items are named after the given member name instead of
+    /// `member` by the awsQuery and XML protocols, e.g. `Tag` for
+    /// `Tags.Tag.1.Key`. The other protocols handle it like a [`Value::List`].
+    NamedList(String, Vec<Self>),
+    /// Keys are always strings in AWS APIs
+    Map(Vec<(String, Self)>),
+    /// Members in declaration order
+    Structure(Vec<(String, Self)>),
+}
+
+impl Value {
+    pub fn list(items: impl IntoIterator<Item = Self>) -> Self {
+        Self::List(items.into_iter().collect())
+    }
+
+    pub fn named_list(member: impl Into<String>, items: impl IntoIterator<Item = Self>) -> Self {
+        Self::NamedList(member.into(), items.into_iter().collect())
+    }
+
+    pub fn map(entries: impl IntoIterator<Item = (impl Into<String>, Self)>) -> Self {
+        Self::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    pub fn structure(members: impl IntoIterator<Item = (impl Into<String>, Self)>) -> Self {
+        Self::Structure(
+            members
+                .into_iter()
+                .map(|(name, value)| (name.into(), value))
+                .collect(),
+        )
+    }
+
+    /// Serializes the value like the JSON protocols, e.g. for logging
+    pub fn to_json(&self) -> String {
+        json::serialize(self)
+    }
+
+    /// A member of a structure or an entry of a map
+    pub fn get(&self, name: &str) -> Option<&Self> {
+        match *self {
+            Self::Map(ref members) | Self::Structure(ref members) => members
+                .iter()
+                .find(|member| member.0 == name)
+                .map(|member| &member.1),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Self::String(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub const fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::Integer(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub const fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Self::Boolean(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The items of a list, empty for all other values
+    pub fn items(&self) -> &[Self] {
+        match *self {
+            Self::List(ref items) | Self::NamedList(_, ref items) => items,
+            _ => &[],
+        }
+    }
+
+    /// Optional members that are `None` are left out of a structure. Use
+    /// this together with [`Option::map()`] for those members.
+    pub fn optional(members: impl IntoIterator<Item = (impl Into<String>, Option<Self>)>) -> Self {
+        Self::Structure(
+            members
+                .into_iter()
+                .filter_map(|(name, value)| value.map(|value| (name.into(), value)))
+                .collect(),
+        )
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::Timestamp(value)
+    }
+}
+
+/// The input of an operation
+pub trait Shape {
+    /// The name of the shape, used as the root element for XML
+    const NAME: &'static str;
+
+    /// The XML namespace of the root element, required by some restXml
+    /// services like CloudFront
+    const NAMESPACE: Option<&'static str> = None;
+
+    /// Must return a [`Value::Structure`]
+    fn to_value(&self) -> Value;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operation {
+    name: &'static str,
+    version: &'static str,
+}
+
+impl Operation {
+    /// `version` is the API version of the service, e.g. `2016-11-15` for
+    /// EC2. It is only sent with the query protocols.
+    pub const fn new(name: &'static str, version: &'static str) -> Self {
+        Self { name, version }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub const fn version(&self) -> &'static str {
+        self.version
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Ec2Query,
+    AwsQuery,
+    RestJson1,
+    RestXml,
+}
+
+/// A serialized request body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Body {
+    content_type: &'static str,
+    content: String,
+}
+
+impl Body {
+    pub const fn content_type(&self) -> &'static str {
+        self.content_type
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.content
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.content.into_bytes()
+    }
+}
+
+impl Protocol {
+    pub const fn content_type(self) -> &'static str {
+        match self {
+            Self::Ec2Query | Self::AwsQuery => "application/x-www-form-urlencoded; charset=utf-8",
+            Self::RestJson1 => "application/json",
+            Self::RestXml => "application/xml",
+        }
+    }
+
+    pub fn serialize<S: Shape>(self, operation: &Operation, input: &S) -> Body {
+        let value = input.to_value();
+
+        let content = match self {
+            Self::Ec2Query => query::serialize(operation, &value, query::Flavor::Ec2),
+            Self::AwsQuery => query::serialize(operation, &value, query::Flavor::Aws),
+            Self::RestJson1 => json::serialize(&value),
+            Self::RestXml => xml::serialize(S::NAME, S::NAMESPACE, &value),
+        };
+
+        Body {
+            content_type: self.content_type(),
+            content,
+        }
+    }
+}
+
+/// Timestamps are sent as ISO 8601 by the query and XML protocols
+fn iso8601(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn base64(value: &[u8]) -> String {
+    BASE64.encode(value)
+}
@@ -0,0 +1,170 @@
+use super::{base64, iso8601, Operation, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Flavor {
+    /// Lists are `Name.1`, member names are capitalized
+    Ec2,
+    /// Lists are `Name.member.1`, maps are `Name.entry.1.key`
+    Aws,
+}
+
+/// Percent-encodes everything except unreserved characters (RFC 3986)
+pub(crate) fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push('%');
+            for nibble in [byte >> 4_u8, byte & 0x0F] {
+                if let Some(digit) = char::from_digit(u32::from(nibble), 16) {
+                    encoded.push(digit.to_ascii_uppercase());
+                }
+            }
+        }
+    }
+    encoded
+}
+
+fn member_name(name: &str, flavor: Flavor) -> String {
+    match flavor {
+        Flavor::Ec2 => {
+            let mut chars = name.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }
+        Flavor::Aws => name.to_owned(),
+    }
+}
+
+fn flatten(params: &mut Vec<(String, String)>, prefix: &str, value: &Value, flavor: Flavor) {
+    let scalar = match *value {
+        Value::String(ref value) => value.clone(),
+        Value::Integer(value) => value.to_string(),
+        Value::Float(value) => value.to_string(),
+        Value::Boolean(value) => value.to_string(),
+        Value::Timestamp(ref value) => iso8601(value),
+        Value::Blob(ref value) => base64(value),
+        Value::List(ref items) => {
+            flatten_list(params, prefix, "member", items, flavor);
+            return;
+        }
+        Value::NamedList(ref member, ref items) => {
+            flatten_list(params, prefix, member, items, flavor);
+            return;
+        }
+        Value::Map(ref entries) => {
+            for (index, entry) in (1_usize..).zip(entries) {
+                let (ref key, ref value) = *entry;
+                let prefix = match flavor {
+                    Flavor::Ec2 => format!("{prefix}.{index}"),
+                    Flavor::Aws => format!("{prefix}.entry.{index}"),
+                };
+                params.push((
+                    format!("{prefix}.{}", member_name("key", flavor)),
+                    key.clone(),
+                ));
+                flatten(
+                    params,
+                    &format!("{prefix}.{}", member_name("value", flavor)),
+                    value,
+                    flavor,
+                );
+            }
+            return;
+        }
+        Value::Structure(ref members) => {
+            for entry in members {
+                let (ref name, ref member) = *entry;
+                flatten(
+                    params,
+                    &format!("{prefix}.{}", member_name(name, flavor)),
+                    member,
+                    flavor,
+                );
+            }
+            return;
+        }
+    };
+
+    params.push((prefix.to_owned(), scalar));
+}
+
+fn flatten_list(
+    params: &mut Vec<(String, String)>,
+    prefix: &str,
+    member: &str,
+    items: &[Value],
+    flavor: Flavor,
+) {
+    // awsQuery distinguishes an empty list from a missing one, EC2 does not
+    if items.is_empty() && flavor == Flavor::Aws {
+        params.push((prefix.to_owned(), String::new()));
+    }
+    for (index, item) in (1_usize..).zip(items) {
+        let prefix = match flavor {
+            Flavor::Ec2 => format!("{prefix}.{index}"),
+            Flavor::Aws => format!("{prefix}.{member}.{index}"),
+        };
+        flatten(params, &prefix, item, flavor);
+    }
+}
+
+pub(super) fn serialize(operation: &Operation, value: &Value, flavor: Flavor) -> String {
+    let mut params = vec![
+        ("Action".to_owned(), operation.name().to_owned()),
+        ("Version".to_owned(), operation.version().to_owned()),
+    ];
+
+    if let Value::Structure(ref members) = *value {
+        for entry in members {
+            let (ref name, ref member) = *entry;
+            flatten(&mut params, &member_name(name, flavor), member, flavor);
+        }
+    }
+
+    params
+        .iter()
+        .map(|param| format!("{}={}", url_encode(&param.0), url_encode(&param.1)))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input() -> Value {
+        Value::structure([
+            ("name", Value::from("a b")),
+            ("ids", Value::list([Value::from("x"), Value::from("y")])),
+            ("attributes", Value::map([("k", Value::from(true))])),
+            ("empty", Value::list([])),
+            (
+                "tags",
+                Value::named_list("Tag", [Value::structure([("Key", Value::from("k"))])]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn ec2() {
+        assert_eq!(
+            serialize(&Operation::new("Op", "1"), &input(), Flavor::Ec2),
+            "Action=Op&Version=1&Name=a%20b&Ids.1=x&Ids.2=y\
+             &Attributes.1.Key=k&Attributes.1.Value=true&Tags.1.Key=k"
+        );
+    }
+
+    #[test]
+    fn aws() {
+        assert_eq!(
+            serialize(&Operation::new("Op", "1"), &input(), Flavor::Aws),
+            "Action=Op&Version=1&name=a%20b&ids.member.1=x&ids.member.2=y\
+             &attributes.entry.1.key=k&attributes.entry.1.value=true&empty=\
+             &tags.Tag.1.Key=k"
+        );
+    }
+}
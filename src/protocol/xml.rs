@@ -0,0 +1,121 @@
+use super::{base64, iso8601, Value};
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_element(out: &mut String, name: &str, value: &Value) {
+    out.push('<');
+    out.push_str(name);
+    out.push('>');
+    write_content(out, value);
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+}
+
+fn write_content(out: &mut String, value: &Value) {
+    match *value {
+        Value::String(ref value) => out.push_str(&escape(value)),
+        Value::Integer(value) => out.push_str(&value.to_string()),
+        Value::Float(value) => out.push_str(&value.to_string()),
+        Value::Boolean(value) => out.push_str(if value { "true" } else { "false" }),
+        Value::Timestamp(ref value) => out.push_str(&iso8601(value)),
+        Value::Blob(ref value) => out.push_str(&base64(value)),
+        // Only wrapped lists are supported, flattened lists must be modelled
+        // as repeated structure members
+        Value::List(ref items) => {
+            for item in items {
+                write_element(out, "member", item);
+            }
+        }
+        Value::NamedList(ref member, ref items) => {
+            for item in items {
+                write_element(out, member, item);
+            }
+        }
+        Value::Map(ref entries) => {
+            for entry in entries {
+                let (ref key, ref value) = *entry;
+                out.push_str("<entry>");
+                write_element(out, "key", &Value::String(key.clone()));
+                write_element(out, "value", value);
+                out.push_str("</entry>");
+            }
+        }
+        Value::Structure(ref members) => {
+            for member in members {
+                write_element(out, &member.0, &member.1);
+            }
+        }
+    }
+}
+
+pub(super) fn serialize(root: &str, namespace: Option<&str>, value: &Value) -> String {
+    let mut out = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    out.push('<');
+    out.push_str(root);
+    if let Some(namespace) = namespace {
+        out.push_str(" xmlns=\"");
+        out.push_str(&escape(namespace));
+        out.push('"');
+    }
+    out.push('>');
+    write_content(&mut out, value);
+    out.push_str("</");
+    out.push_str(root);
+    out.push('>');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_structure() {
+        let value = Value::structure([
+            ("Comment", Value::from("a < b & c")),
+            ("Paths", Value::list([Value::from("/a"), Value::from("/b")])),
+            ("Quiet", Value::from(true)),
+        ]);
+
+        assert_eq!(
+            serialize("InvalidationBatch", None, &value),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><InvalidationBatch>\
+             <Comment>a &lt; b &amp; c</Comment>\
+             <Paths><member>/a</member><member>/b</member></Paths>\
+             <Quiet>true</Quiet></InvalidationBatch>"
+        );
+    }
+
+    #[test]
+    fn serialize_namespace() {
+        let value = Value::structure([(
+            "Items",
+            Value::named_list("Key", [Value::from("a"), Value::from("b")]),
+        )]);
+
+        assert_eq!(
+            serialize(
+                "TagKeys",
+                Some("http://cloudfront.amazonaws.com/doc/2020-05-31/"),
+                &value
+            ),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <TagKeys xmlns=\"http://cloudfront.amazonaws.com/doc/2020-05-31/\">\
+             <Items><Key>a</Key><Key>b</Key></Items></TagKeys>"
+        );
+    }
+}
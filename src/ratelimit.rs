@@ -0,0 +1,301 @@
+//! Adaptive client-side rate limiting
+//!
+//! Each request has to take a token from a bucket before it is sent. The
+//! refill rate of the bucket adapts to the responses, similar to the
+//! adaptive retry mode of the SDK: it is cut on every throttled attempt and
+//! slowly raised again on successes (additive increase, multiplicative
+//! decrease). This keeps bursts of describe and tag calls from running into
+//! the API rate limits, which are shared by the whole account.
+//!
+//! Attempts are classified by the same logic as the [metrics](super::metrics),
+//! so everything counted as [`Outcome::Throttled`] slows down the client.
+//!
+//! Enable it with [`ClientConfig::rate_limit`](super::ClientConfig::rate_limit).
+//! Waiting for tokens needs tokio timers, so it is not available on wasm.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use aws_smithy_runtime_api::client::{
+    http::{
+        HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpClient,
+        SharedHttpConnector,
+    },
+    orchestrator::HttpRequest,
+    runtime_components::RuntimeComponents,
+};
+
+use super::metrics::{Attempt, MetricsInterceptor, MetricsSink, Outcome};
+
+/// Rates are tracked in thousandths of requests per second and tokens in
+/// thousandths of a request, to avoid floating point math
+const MILLI: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Requests per second when there is no throttling. This is also the
+    /// starting rate.
+    pub max_rate: u32,
+    /// The rate is never reduced below this
+    pub min_rate: u32,
+    /// How many requests can be sent at once after a pause
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_rate: 20,
+            min_rate: 1,
+            burst: 10,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    /// milli-requests per second
+    rate: u64,
+    /// milli-tokens
+    tokens: u64,
+    last_refill: Instant,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State {
+                rate: u64::from(config.max_rate).saturating_mul(MILLI),
+                tokens: u64::from(config.burst).saturating_mul(MILLI),
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    fn max_rate(&self) -> u64 {
+        u64::from(self.config.max_rate).saturating_mul(MILLI).max(1)
+    }
+
+    fn min_rate(&self) -> u64 {
+        u64::from(self.config.min_rate)
+            .saturating_mul(MILLI)
+            .clamp(1, self.max_rate())
+    }
+
+    /// The current rate in requests per second, rounded down
+    pub fn current_rate(&self) -> u32 {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|state| state.rate.checked_div(MILLI))
+            .and_then(|rate| u32::try_from(rate).ok())
+            .unwrap_or(self.config.max_rate)
+    }
+
+    /// Takes a token, or returns how long to wait until one is available
+    fn try_acquire(&self, now: Instant) -> Result<(), Duration> {
+        let Ok(mut state) = self.state.lock() else {
+            // Rather not limit at all than block forever
+            return Ok(());
+        };
+
+        let elapsed = u64::try_from(now.saturating_duration_since(state.last_refill).as_millis())
+            .unwrap_or(u64::MAX);
+        let refill = elapsed
+            .saturating_mul(state.rate)
+            .checked_div(MILLI)
+            .unwrap_or(0);
+        state.tokens = state
+            .tokens
+            .saturating_add(refill)
+            .min(u64::from(self.config.burst.max(1)).saturating_mul(MILLI));
+        state.last_refill = now;
+
+        if state.tokens >= MILLI {
+            state.tokens = state.tokens.saturating_sub(MILLI);
+            Ok(())
+        } else {
+            let missing = MILLI.saturating_sub(state.tokens);
+            let wait_millis = missing
+                .saturating_mul(MILLI)
+                .checked_div(state.rate)
+                .unwrap_or(MILLI);
+            Err(Duration::from_millis(wait_millis.max(1)))
+        }
+    }
+
+    pub async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire(Instant::now()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Cuts the rate by 30%
+    pub fn on_throttle(&self) {
+        let min_rate = self.min_rate();
+        if let Ok(mut state) = self.state.lock() {
+            state.rate = state
+                .rate
+                .saturating_mul(7)
+                .checked_div(10)
+                .unwrap_or(min_rate)
+                .max(min_rate);
+        }
+    }
+
+    /// Raises the rate by 1% of the maximum rate
+    pub fn on_success(&self) {
+        let max_rate = self.max_rate();
+        if let Ok(mut state) = self.state.lock() {
+            state.rate = state
+                .rate
+                .saturating_add(max_rate.checked_div(100).unwrap_or(0).max(1))
+                .min(max_rate);
+        }
+    }
+}
+
+impl MetricsSink for RateLimiter {
+    fn record(&self, attempt: &Attempt) {
+        match attempt.outcome {
+            Outcome::Throttled => self.on_throttle(),
+            Outcome::Success => self.on_success(),
+            // Other errors say nothing about the rate limits
+            Outcome::ClientError | Outcome::ServerError | Outcome::TransportError => {}
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RateLimitedConnector {
+    inner: SharedHttpConnector,
+    limiter: Arc<RateLimiter>,
+}
+
+impl HttpConnector for RateLimitedConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let inner = self.inner.clone();
+        let limiter = Arc::clone(&self.limiter);
+
+        HttpConnectorFuture::new(async move {
+            limiter.acquire().await;
+            inner.call(request).await
+        })
+    }
+}
+
+#[derive(Debug)]
+struct RateLimitedHttpClient {
+    inner: SharedHttpClient,
+    limiter: Arc<RateLimiter>,
+}
+
+impl HttpClient for RateLimitedHttpClient {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(RateLimitedConnector {
+            inner: self.inner.http_connector(settings, components),
+            limiter: Arc::clone(&self.limiter),
+        })
+    }
+}
+
+/// The rate limiter of one service client
+///
+/// Every attempt (including retries) has to pass the limiter in the HTTP
+/// client, and the outcome of each attempt is fed back into it by an
+/// interceptor.
+#[derive(Debug)]
+pub(crate) struct RateLimit {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimit {
+    pub(crate) fn new(config: RateLimitConfig) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(config)),
+        }
+    }
+
+    /// Wraps the HTTP client of `config`, or the default client of the SDK
+    /// if `config` has none. `None` if there is no client to wrap.
+    pub(crate) fn http_client(&self, config: &aws_config::SdkConfig) -> Option<SharedHttpClient> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let inner = config
+            .http_client()
+            .or_else(aws_smithy_http_client::hyper_014::default_client);
+        #[cfg(target_arch = "wasm32")]
+        let inner = config.http_client();
+
+        inner.map(|inner| {
+            SharedHttpClient::new(RateLimitedHttpClient {
+                inner,
+                limiter: Arc::clone(&self.limiter),
+            })
+        })
+    }
+
+    pub(crate) fn interceptor(&self) -> MetricsInterceptor {
+        let limiter = Arc::clone(&self.limiter);
+        let sink: Arc<dyn MetricsSink> = limiter;
+        MetricsInterceptor::new(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_refills_at_rate() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_rate: 10,
+            min_rate: 1,
+            burst: 2,
+        });
+        let start = Instant::now();
+
+        assert_eq!(limiter.try_acquire(start), Ok(()));
+        assert_eq!(limiter.try_acquire(start), Ok(()));
+        assert_eq!(limiter.try_acquire(start), Err(Duration::from_millis(100)));
+
+        assert_eq!(
+            limiter.try_acquire(start.checked_add(Duration::from_millis(100)).unwrap()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rate_adapts_to_throttling() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_rate: 100,
+            min_rate: 10,
+            burst: 1,
+        });
+
+        limiter.on_throttle();
+        assert_eq!(limiter.current_rate(), 70);
+
+        for _ in 0..10_u32 {
+            limiter.on_throttle();
+        }
+        assert_eq!(limiter.current_rate(), 10);
+
+        for _ in 0..200_u32 {
+            limiter.on_success();
+        }
+        assert_eq!(limiter.current_rate(), 100);
+    }
+}
@@ -0,0 +1,625 @@
+//! RDS database instances and clusters
+//!
+//! Contrary to EC2, RDS tags are not managed via resource IDs, but via the
+//! ARN of the resource.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    tags::{TagKey, TagList},
+    Error, RegionClient,
+};
+
+string_newtype!(DbInstanceIdentifier);
+
+impl DbInstanceIdentifier {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(DbClusterIdentifier);
+
+impl DbClusterIdentifier {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+arn_newtype!(RdsArn);
+
+impl RdsArn {
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .main
+            .rds
+            .list_tags_for_resource()
+            .resource_name(self.to_string())
+            .send()
+            .await?
+            .tag_list
+            .unwrap_or_default()
+            .try_into()?)
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .rds
+            .add_tags_to_resource()
+            .resource_name(self.to_string())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .rds
+            .remove_tags_from_resource()
+            .resource_name(self.to_string())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct DbInstance {
+    identifier: DbInstanceIdentifier,
+    arn: RdsArn,
+    class: Option<String>,
+    engine: Option<String>,
+    status: Option<String>,
+    cluster: Option<DbClusterIdentifier>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_rds::types::DbInstance> for DbInstance {
+    type Error = Error;
+
+    fn try_from(instance: aws_sdk_rds::types::DbInstance) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                instance.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            identifier: DbInstanceIdentifier(extract!(db_instance_identifier)?),
+            arn: RdsArn::parse(&extract!(db_instance_arn)?)?,
+            class: instance.db_instance_class,
+            engine: instance.engine,
+            status: instance.db_instance_status,
+            cluster: instance.db_cluster_identifier.map(DbClusterIdentifier),
+            tags: instance.tag_list.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl DbInstance {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .rds
+            .describe_db_instances()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_identifier(
+        client: &RegionClient,
+        identifier: &DbInstanceIdentifier,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .rds
+            .describe_db_instances()
+            .db_instance_identifier(identifier.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => output
+                .db_instances
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .map(TryInto::try_into)
+                .transpose(),
+            Err(e) => match e.as_service_error() {
+                Some(service_error) if service_error.is_db_instance_not_found_fault() => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    pub const fn identifier(&self) -> &DbInstanceIdentifier {
+        &self.identifier
+    }
+
+    pub const fn arn(&self) -> &RdsArn {
+        &self.arn
+    }
+
+    pub fn class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    pub fn engine(&self) -> Option<&str> {
+        self.engine.as_deref()
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    /// The cluster the instance belongs to, if any
+    pub const fn cluster(&self) -> Option<&DbClusterIdentifier> {
+        self.cluster.as_ref()
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    pub async fn start(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .rds
+            .start_db_instance()
+            .db_instance_identifier(self.identifier.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Note that RDS automatically starts stopped instances again after seven
+    /// days.
+    pub async fn stop(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .rds
+            .stop_db_instance()
+            .db_instance_identifier(self.identifier.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn reboot(&self, client: &RegionClient, force_failover: bool) -> Result<(), Error> {
+        let _output = client
+            .main
+            .rds
+            .reboot_db_instance()
+            .db_instance_identifier(self.identifier.as_str())
+            .force_failover(force_failover)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct DbCluster {
+    identifier: DbClusterIdentifier,
+    arn: RdsArn,
+    engine: Option<String>,
+    status: Option<String>,
+    members: Vec<DbInstanceIdentifier>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_rds::types::DbCluster> for DbCluster {
+    type Error = Error;
+
+    fn try_from(cluster: aws_sdk_rds::types::DbCluster) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                cluster.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            identifier: DbClusterIdentifier(extract!(db_cluster_identifier)?),
+            arn: RdsArn::parse(&extract!(db_cluster_arn)?)?,
+            engine: cluster.engine,
+            status: cluster.status,
+            members: cluster
+                .db_cluster_members
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|member| member.db_instance_identifier.map(DbInstanceIdentifier))
+                .collect(),
+            tags: cluster.tag_list.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl DbCluster {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .rds
+            .describe_db_clusters()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn find_by_identifier(
+        client: &RegionClient,
+        identifier: &DbClusterIdentifier,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .rds
+            .describe_db_clusters()
+            .db_cluster_identifier(identifier.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => output
+                .db_clusters
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .map(TryInto::try_into)
+                .transpose(),
+            Err(e) => match e.as_service_error() {
+                Some(service_error) if service_error.is_db_cluster_not_found_fault() => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    pub const fn identifier(&self) -> &DbClusterIdentifier {
+        &self.identifier
+    }
+
+    pub const fn arn(&self) -> &RdsArn {
+        &self.arn
+    }
+
+    pub fn engine(&self) -> Option<&str> {
+        self.engine.as_deref()
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    pub fn members(&self) -> &[DbInstanceIdentifier] {
+        &self.members
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    pub async fn start(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .rds
+            .start_db_cluster()
+            .db_cluster_identifier(self.identifier.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn stop(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .rds
+            .stop_db_cluster()
+            .db_cluster_identifier(self.identifier.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Reboots all instances of the cluster
+    pub async fn reboot(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .rds
+            .reboot_db_cluster()
+            .db_cluster_identifier(self.identifier.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_rds::types::{DbClusterMember, Tag};
+
+    use super::*;
+
+    #[test]
+    fn instance_from_aws() {
+        let instance = DbInstance::try_from(
+            aws_sdk_rds::types::DbInstance::builder()
+                .db_instance_identifier("db-1")
+                .db_instance_arn("arn:aws:rds:eu-central-1:123456789012:db:db-1")
+                .db_instance_class("db.t4g.micro")
+                .engine("postgres")
+                .db_instance_status("available")
+                .db_cluster_identifier("main")
+                .tag_list(Tag::builder().key("team").value("infra").build())
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(instance.identifier().as_str(), "db-1");
+        assert_eq!(instance.arn().inner().resource(), "db:db-1");
+        assert_eq!(instance.class(), Some("db.t4g.micro"));
+        assert_eq!(instance.engine(), Some("postgres"));
+        assert_eq!(instance.status(), Some("available"));
+        assert_eq!(
+            instance.cluster().map(DbClusterIdentifier::as_str),
+            Some("main")
+        );
+        assert_eq!(
+            instance
+                .tags()
+                .get("team".to_owned())
+                .map(|tag| tag.value().as_str()),
+            Some("infra")
+        );
+    }
+
+    #[test]
+    fn instance_without_arn_is_rejected() {
+        assert!(matches!(
+            DbInstance::try_from(
+                aws_sdk_rds::types::DbInstance::builder()
+                    .db_instance_identifier("db-1")
+                    .build()
+            ),
+            Err(Error::UnexpectedNoneValue { ref entity }) if entity == "db_instance_arn"
+        ));
+        assert!(matches!(
+            DbInstance::try_from(
+                aws_sdk_rds::types::DbInstance::builder()
+                    .db_instance_identifier("db-1")
+                    .db_instance_arn("db-1")
+                    .build()
+            ),
+            Err(Error::InvalidArn(_))
+        ));
+
+        assert!(matches!(
+            DbInstance::try_from(
+                aws_sdk_rds::types::DbInstance::builder()
+                    .db_instance_identifier("db-1")
+                    .db_instance_arn("arn:aws:rds:eu-central-1:123456789012:db:db-1")
+                    .tag_list(Tag::builder().value("infra").build())
+                    .build()
+            ),
+            Err(Error::InvalidTags(_))
+        ));
+    }
+
+    #[test]
+    fn cluster_members() {
+        let cluster = DbCluster::try_from(
+            aws_sdk_rds::types::DbCluster::builder()
+                .db_cluster_identifier("main")
+                .db_cluster_arn("arn:aws:rds:eu-central-1:123456789012:cluster:main")
+                .db_cluster_members(
+                    DbClusterMember::builder()
+                        .db_instance_identifier("db-1")
+                        .build(),
+                )
+                .db_cluster_members(DbClusterMember::builder().build())
+                .db_cluster_members(
+                    DbClusterMember::builder()
+                        .db_instance_identifier("db-2")
+                        .build(),
+                )
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(cluster.identifier().as_str(), "main");
+        assert_eq!(
+            cluster
+                .members()
+                .iter()
+                .map(DbInstanceIdentifier::as_str)
+                .collect::<Vec<_>>(),
+            ["db-1", "db-2"]
+        );
+        assert!(cluster.tags().as_slice().is_empty());
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        const INSTANCE_ARN: &str = "arn:aws:rds:eu-central-1:123456789012:db:db1";
+
+        fn instances(marker: Option<&str>, identifier: &str) -> MockResponse {
+            MockResponse::ok(format!(
+                r#"<DescribeDBInstancesResponse xmlns="http://rds.amazonaws.com/doc/2014-10-31/">
+      <DescribeDBInstancesResult>
+        {}
+        <DBInstances>
+          <DBInstance>
+            <DBInstanceIdentifier>{identifier}</DBInstanceIdentifier>
+            <DBInstanceArn>arn:aws:rds:eu-central-1:123456789012:db:{identifier}</DBInstanceArn>
+            <DBInstanceClass>db.t3.micro</DBInstanceClass>
+            <DBClusterIdentifier>cluster1</DBClusterIdentifier>
+            <TagList>
+              <Tag><Key>team</Key><Value>infra</Value></Tag>
+              <Tag><Key>env</Key><Value>prod</Value></Tag>
+            </TagList>
+          </DBInstance>
+        </DBInstances>
+      </DescribeDBInstancesResult>
+    </DescribeDBInstancesResponse>"#,
+                marker.map_or_else(String::new, |marker| format!("<Marker>{marker}</Marker>"))
+            ))
+        }
+
+        #[test]
+        fn list_instances_follows_markers() {
+            let http = MockHttpClient::new()
+                .on(Matcher::param("Marker", "page2"), instances(None, "db2"))
+                .on(
+                    Matcher::action("DescribeDBInstances"),
+                    instances(Some("page2"), "db1"),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let instances = block_on(DbInstance::list(&client)).unwrap();
+
+            assert_eq!(
+                instances
+                    .iter()
+                    .map(|instance| instance.identifier().as_str())
+                    .collect::<Vec<_>>(),
+                ["db1", "db2"]
+            );
+            assert_eq!(http.requests().unwrap().len(), 2);
+
+            let first = instances.first().unwrap();
+            assert_eq!(first.arn().to_string(), INSTANCE_ARN);
+            assert_eq!(first.class(), Some("db.t3.micro"));
+            assert_eq!(first.cluster().unwrap().as_str(), "cluster1");
+            assert_eq!(
+                first.tags().as_slice(),
+                [
+                    RawTag::new("env".to_owned(), "prod".to_owned()),
+                    RawTag::new("team".to_owned(), "infra".to_owned()),
+                ]
+            );
+        }
+
+        #[test]
+        fn list_clusters() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeDBClusters"),
+                MockResponse::ok(
+                    r#"<DescribeDBClustersResponse xmlns="http://rds.amazonaws.com/doc/2014-10-31/">
+      <DescribeDBClustersResult>
+        <DBClusters>
+          <DBCluster>
+            <DBClusterIdentifier>cluster1</DBClusterIdentifier>
+            <DBClusterArn>arn:aws:rds:eu-central-1:123456789012:cluster:cluster1</DBClusterArn>
+            <Engine>aurora-postgresql</Engine>
+            <DBClusterMembers>
+              <DBClusterMember><DBInstanceIdentifier>db1</DBInstanceIdentifier></DBClusterMember>
+              <DBClusterMember><DBInstanceIdentifier>db2</DBInstanceIdentifier></DBClusterMember>
+            </DBClusterMembers>
+          </DBCluster>
+        </DBClusters>
+      </DescribeDBClustersResult>
+    </DescribeDBClustersResponse>"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let clusters = block_on(DbCluster::list(&client)).unwrap();
+            let cluster = clusters.first().unwrap();
+
+            assert_eq!(cluster.identifier().as_str(), "cluster1");
+            assert_eq!(cluster.arn().inner().resource(), "cluster:cluster1");
+            assert_eq!(
+                cluster
+                    .members()
+                    .iter()
+                    .map(DbInstanceIdentifier::as_str)
+                    .collect::<Vec<_>>(),
+                ["db1", "db2"]
+            );
+            assert!(cluster.tags().as_slice().is_empty());
+        }
+
+        #[test]
+        fn missing_cluster_is_none() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeDBClusters"),
+                MockResponse::status(
+                    404,
+                    r#"<ErrorResponse xmlns="http://rds.amazonaws.com/doc/2014-10-31/">
+      <Error><Type>Sender</Type><Code>DBClusterNotFoundFault</Code><Message>not found</Message></Error>
+    </ErrorResponse>"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let cluster = block_on(DbCluster::find_by_identifier(
+                &client,
+                &DbClusterIdentifier::new("missing".to_owned()),
+            ))
+            .unwrap();
+            assert!(cluster.is_none());
+        }
+
+        #[test]
+        fn tags_by_arn() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::action("RemoveTagsFromResource"),
+                    MockResponse::ok(
+                        "<RemoveTagsFromResourceResponse><ResponseMetadata/></RemoveTagsFromResourceResponse>",
+                    ),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let arn = RdsArn::parse(INSTANCE_ARN).unwrap();
+
+            block_on(arn.remove_tags(&client, vec![TagKey::new("env".to_owned())])).unwrap();
+
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(request.param("ResourceName").as_deref(), Some(INSTANCE_ARN));
+            assert_eq!(request.param("TagKeys.member.1").as_deref(), Some("env"));
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use aws_sdk_s3::primitives::ByteStream;
+use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::io::AsyncRead;
+
+use super::{ChecksumAlgorithm, ObjectChecksum};
+use crate::Error;
+
+/// A byte range for partial downloads, see [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#name-byte-ranges)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// From `start` to `end`, both inclusive
+    Inclusive { start: u64, end: u64 },
+    /// From `start` to the end of the object
+    From { start: u64 },
+    /// The last `length` bytes of the object
+    Suffix { length: u64 },
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Inclusive { start, end } => write!(f, "bytes={start}-{end}"),
+            Self::From { start } => write!(f, "bytes={start}-"),
+            Self::Suffix { length } => write!(f, "bytes=-{length}"),
+        }
+    }
+}
+
+/// The body of a downloaded object. Data is streamed from S3 as it is
+/// consumed, nothing is buffered up front.
+#[derive(Debug)]
+pub struct ObjectBody {
+    stream: ByteStream,
+    content_length: Option<i64>,
+    content_type: Option<String>,
+    e_tag: Option<String>,
+    checksum: Option<ObjectChecksum>,
+}
+
+impl ObjectBody {
+    pub(super) const fn new(
+        stream: ByteStream,
+        content_length: Option<i64>,
+        content_type: Option<String>,
+        e_tag: Option<String>,
+        checksum: Option<ObjectChecksum>,
+    ) -> Self {
+        Self {
+            stream,
+            content_length,
+            content_type,
+            e_tag,
+            checksum,
+        }
+    }
+
+    /// Length of this body, i.e. of the requested range for range requests
+    pub const fn content_length(&self) -> Option<i64> {
+        self.content_length
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn e_tag(&self) -> Option<&str> {
+        self.e_tag.as_deref()
+    }
+
+    /// The checksum stored with the object. Unless it is
+    /// [composite](ObjectChecksum::is_composite()), the body is validated
+    /// against it while reading, and the last chunk fails on a mismatch.
+    pub const fn checksum(&self) -> Option<&ObjectChecksum> {
+        self.checksum.as_ref()
+    }
+
+    /// Returns the next chunk, or `None` when the body is exhausted
+    pub async fn next_chunk(&mut self) -> Option<Result<Bytes, Error>> {
+        self.stream
+            .next()
+            .await
+            .map(|chunk| chunk.map_err(|e| Error::SdkError(Box::new(e), None)))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn into_async_read(self) -> impl AsyncRead + Unpin {
+        self.stream.into_async_read()
+    }
+
+    /// Buffers the whole remaining body in memory
+    pub async fn collect(self) -> Result<Vec<u8>, Error> {
+        Ok(self
+            .stream
+            .collect()
+            .await
+            .map_err(|e| Error::SdkError(Box::new(e), None))?
+            .to_vec())
+    }
+}
+
+/// The body of an upload. S3 requires the length to be known up front.
+#[derive(Debug)]
+pub struct PutBody {
+    stream: ByteStream,
+    content_length: Option<i64>,
+    checksum: Option<ChecksumAlgorithm>,
+}
+
+impl PutBody {
+    /// Streams the file from disk instead of reading it into memory
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let stream = ByteStream::from_path(path)
+            .await
+            .map_err(|e| Error::SdkError(Box::new(e), None))?;
+
+        Ok(Self {
+            stream,
+            // Length is determined from the file metadata by the stream itself
+            content_length: None,
+            checksum: None,
+        })
+    }
+
+    /// A stream that produces exactly `content_length` bytes
+    pub const fn from_stream(stream: ByteStream, content_length: i64) -> Self {
+        Self {
+            stream,
+            content_length: Some(content_length),
+            checksum: None,
+        }
+    }
+
+    /// Sends a checksum of the body computed with `algorithm`, which S3
+    /// verifies and stores with the object
+    #[must_use]
+    pub fn with_checksum(self, algorithm: ChecksumAlgorithm) -> Self {
+        Self {
+            checksum: Some(algorithm),
+            ..self
+        }
+    }
+
+    pub(super) fn into_parts(self) -> (ByteStream, Option<i64>, Option<ChecksumAlgorithm>) {
+        (self.stream, self.content_length, self.checksum)
+    }
+}
+
+impl From<Vec<u8>> for PutBody {
+    fn from(value: Vec<u8>) -> Self {
+        Self {
+            stream: ByteStream::from(value),
+            content_length: None,
+            checksum: None,
+        }
+    }
+}
+
+impl From<Bytes> for PutBody {
+    fn from(value: Bytes) -> Self {
+        Self {
+            stream: ByteStream::from(value),
+            content_length: None,
+            checksum: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_ranges() {
+        assert_eq!(
+            ByteRange::Inclusive { start: 0, end: 99 }.to_string(),
+            "bytes=0-99"
+        );
+        assert_eq!(ByteRange::From { start: 100 }.to_string(), "bytes=100-");
+        assert_eq!(ByteRange::Suffix { length: 500 }.to_string(), "bytes=-500");
+    }
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The additional checksums supported by S3. The SDK computes the checksum
+/// while sending the body, and S3 rejects the upload if its own computation
+/// differs.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Crc32,
+    Crc32C,
+    Sha1,
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Crc32 => "CRC32",
+            Self::Crc32C => "CRC32C",
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<ChecksumAlgorithm> for aws_sdk_s3::types::ChecksumAlgorithm {
+    fn from(value: ChecksumAlgorithm) -> Self {
+        match value {
+            ChecksumAlgorithm::Crc32 => Self::Crc32,
+            ChecksumAlgorithm::Crc32C => Self::Crc32C,
+            ChecksumAlgorithm::Sha1 => Self::Sha1,
+            ChecksumAlgorithm::Sha256 => Self::Sha256,
+        }
+    }
+}
+
+/// The checksum stored with an object
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectChecksum {
+    pub algorithm: ChecksumAlgorithm,
+    /// Base64 encoded
+    pub value: String,
+}
+
+impl ObjectChecksum {
+    /// Takes the first checksum that is set, S3 only stores one per object
+    pub(super) fn from_fields(
+        crc32: Option<String>,
+        crc32c: Option<String>,
+        sha1: Option<String>,
+        sha256: Option<String>,
+    ) -> Option<Self> {
+        [
+            (ChecksumAlgorithm::Crc32, crc32),
+            (ChecksumAlgorithm::Crc32C, crc32c),
+            (ChecksumAlgorithm::Sha1, sha1),
+            (ChecksumAlgorithm::Sha256, sha256),
+        ]
+        .into_iter()
+        .find_map(|(algorithm, value)| value.map(|value| Self { algorithm, value }))
+    }
+
+    /// Objects uploaded in parts have a checksum of the part checksums,
+    /// suffixed with `-<number of parts>`. It cannot be compared to a
+    /// checksum of the whole data, and is not validated on download.
+    pub fn is_composite(&self) -> bool {
+        self.value.contains('-')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_fields() {
+        let checksum =
+            ObjectChecksum::from_fields(None, Some("yZRlqg==".to_owned()), None, None).unwrap();
+        assert_eq!(checksum.algorithm, ChecksumAlgorithm::Crc32C);
+        assert!(!checksum.is_composite(), "single part checksum");
+
+        let composite =
+            ObjectChecksum::from_fields(None, None, None, Some("abc=-3".to_owned())).unwrap();
+        assert!(composite.is_composite(), "multipart checksum");
+
+        assert_eq!(ObjectChecksum::from_fields(None, None, None, None), None);
+    }
+}
@@ -0,0 +1,520 @@
+//! S3 buckets and objects
+//!
+//! Object bodies are streamed in both directions, see [`ObjectBody`] and
+//! [`PutBody`]. Objects larger than 5 GB cannot be uploaded with a single
+//! `PutObject`, use [`MultipartUploader`] for those.
+//!
+//! Uploads can carry an additional checksum, see [`PutBody::with_checksum()`]
+//! and [`MultipartConfig::checksum`]. Downloads of whole objects are
+//! validated against their checksum, if the object has one.
+//!
+//! [`Bucket::presign_get()`] and [`Bucket::presign_put()`] create URLs that
+//! allow clients without AWS credentials to download or upload single
+//! objects.
+//!
+//! Buckets do not have to be in the region of the client. The operations of
+//! [`Bucket`] follow the redirects of S3 to the region of the bucket, unless
+//! disabled with [`ClientConfig::disable_s3_redirects`](crate::ClientConfig).
+//! Multipart uploads and presigned requests use the region of the client, use
+//! [`Bucket::region()`] to find the right one for those.
+
+use std::{fmt, time::Duration};
+
+use aws_sdk_s3::{primitives::ByteStream, types::BucketLocationConstraint};
+use aws_smithy_types::body::SdkBody;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{tags::TagList, Error, RegionClient, Timestamp};
+
+mod body;
+mod checksum;
+#[cfg(not(target_arch = "wasm32"))]
+mod multipart;
+mod presign;
+mod redirect;
+
+pub use body::{ByteRange, ObjectBody, PutBody};
+pub use checksum::{ChecksumAlgorithm, ObjectChecksum};
+#[cfg(not(target_arch = "wasm32"))]
+pub use multipart::{MultipartConfig, MultipartUploader};
+pub use presign::{PresignPutOptions, PresignedRequest};
+pub use redirect::BucketRegions;
+use redirect::{customized, send_to_bucket_region};
+
+string_newtype!(BucketName);
+
+impl BucketName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(ObjectKey);
+
+impl ObjectKey {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Object {
+    key: ObjectKey,
+    size: Option<i64>,
+    e_tag: Option<String>,
+    last_modified: Option<Timestamp>,
+}
+
+impl TryFrom<aws_sdk_s3::types::Object> for Object {
+    type Error = Error;
+
+    fn try_from(object: aws_sdk_s3::types::Object) -> Result<Self, Self::Error> {
+        Ok(Self {
+            key: ObjectKey(object.key.ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "Object.key".to_owned(),
+            })?),
+            size: object.size,
+            e_tag: object.e_tag,
+            last_modified: object.last_modified.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl Object {
+    pub const fn key(&self) -> &ObjectKey {
+        &self.key
+    }
+
+    pub const fn size(&self) -> Option<i64> {
+        self.size
+    }
+
+    pub fn e_tag(&self) -> Option<&str> {
+        self.e_tag.as_deref()
+    }
+
+    pub const fn last_modified(&self) -> Option<&Timestamp> {
+        self.last_modified.as_ref()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Bucket {
+    name: BucketName,
+}
+
+impl Bucket {
+    pub const fn new(name: BucketName) -> Self {
+        Self { name }
+    }
+
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .s3
+            .list_buckets()
+            .send()
+            .await?
+            .buckets
+            .unwrap_or_default()
+            .into_iter()
+            .map(|bucket| {
+                Ok(Self {
+                    name: BucketName(bucket.name.ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: "Bucket.name".to_owned(),
+                    })?),
+                })
+            })
+            .collect()
+    }
+
+    pub const fn name(&self) -> &BucketName {
+        &self.name
+    }
+
+    /// The region of the bucket, e.g. `eu-west-1`
+    ///
+    /// Uses the `x-amz-bucket-region` header of `HeadBucket`, which works
+    /// from any region, and falls back to `GetBucketLocation` if the header
+    /// is missing.
+    pub async fn region(&self, client: &RegionClient) -> Result<String, Error> {
+        if let Some(region) = client
+            .s3_bucket_regions
+            .as_ref()
+            .and_then(|regions| regions.get(&self.name))
+        {
+            return Ok(region);
+        }
+
+        let region = match client
+            .main
+            .s3
+            .head_bucket()
+            .bucket(self.name.as_str())
+            .send()
+            .await
+        {
+            Ok(output) => output.bucket_region,
+            Err(e) => Some(
+                e.raw_response()
+                    .and_then(redirect::redirect_region)
+                    .ok_or(e)?,
+            ),
+        };
+
+        let region = match region {
+            Some(region) => region,
+            None => self.location(client).await?,
+        };
+
+        if let Some(ref regions) = client.s3_bucket_regions {
+            regions.insert(&self.name, region.clone());
+        }
+
+        Ok(region)
+    }
+
+    /// The region of the bucket via `GetBucketLocation`, which only works
+    /// for the owner of the bucket. Prefer [`Self::region()`].
+    pub async fn location(&self, client: &RegionClient) -> Result<String, Error> {
+        let constraint = client
+            .main
+            .s3
+            .get_bucket_location()
+            .bucket(self.name.as_str())
+            .send()
+            .await?
+            .location_constraint;
+
+        // Buckets in us-east-1 have no constraint, and very old buckets in
+        // eu-west-1 have the legacy constraint `EU`
+        Ok(
+            match constraint.as_ref().map(BucketLocationConstraint::as_str) {
+                None | Some("") => "us-east-1".to_owned(),
+                Some("EU") => "eu-west-1".to_owned(),
+                Some(region) => region.to_owned(),
+            },
+        )
+    }
+
+    pub async fn list_objects(
+        &self,
+        client: &RegionClient,
+        prefix: Option<&str>,
+    ) -> Result<Vec<Object>, Error> {
+        // Paginators do not support config overrides, so the pages are
+        // fetched by hand
+        send_to_bucket_region(client, &self.name, move |config| async move {
+            let mut objects = Vec::new();
+            let mut continuation_token = None;
+
+            loop {
+                let output = customized!(
+                    client
+                        .main
+                        .s3
+                        .list_objects_v2()
+                        .bucket(self.name.as_str())
+                        .set_prefix(prefix.map(ToOwned::to_owned))
+                        .set_continuation_token(continuation_token),
+                    config.clone()
+                )
+                .send()
+                .await?;
+
+                objects.extend(output.contents.unwrap_or_default());
+
+                match output.next_continuation_token {
+                    Some(token) if output.is_truncated == Some(true) => {
+                        continuation_token = Some(token);
+                    }
+                    _ => break,
+                }
+            }
+
+            Ok(objects)
+        })
+        .await?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+
+    /// Uploads `body` in a single request. Limited to 5 GB.
+    ///
+    /// Redirects to the region of the bucket are only followed for bodies
+    /// that can be sent twice, i.e. in-memory bodies and files.
+    pub async fn put_object(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+        body: impl Into<PutBody>,
+    ) -> Result<(), Error> {
+        let (stream, content_length, checksum) = body.into().into_parts();
+        let mut body = Some(stream.into_inner());
+        let replayable = body.as_ref().is_some_and(|body| body.try_clone().is_some());
+
+        let mut send = |config: Option<aws_sdk_s3::config::Builder>| {
+            // Sending takes the body, so a redirect needs a copy of it
+            let stream = match body.as_ref().and_then(SdkBody::try_clone) {
+                Some(copy) => copy,
+                None => body.take().unwrap_or_else(SdkBody::empty),
+            };
+
+            customized!(
+                client
+                    .main
+                    .s3
+                    .put_object()
+                    .bucket(self.name.as_str())
+                    .key(key.as_str())
+                    .body(ByteStream::new(stream))
+                    .set_content_length(content_length)
+                    .set_checksum_algorithm(checksum.map(Into::into)),
+                config
+            )
+            .send()
+        };
+
+        let _output = if replayable {
+            send_to_bucket_region(client, &self.name, send).await?
+        } else {
+            send(redirect::known_region(client, &self.name)).await?
+        };
+
+        Ok(())
+    }
+
+    async fn get_object_inner(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+        range: Option<ByteRange>,
+    ) -> Result<ObjectBody, Error> {
+        let output = send_to_bucket_region(client, &self.name, |config| {
+            customized!(
+                client
+                    .main
+                    .s3
+                    .get_object()
+                    .bucket(self.name.as_str())
+                    .key(key.as_str())
+                    .set_range(range.map(|range| range.to_string()))
+                    .checksum_mode(aws_sdk_s3::types::ChecksumMode::Enabled),
+                config
+            )
+            .send()
+        })
+        .await?;
+
+        let checksum = ObjectChecksum::from_fields(
+            output.checksum_crc32,
+            output.checksum_crc32_c,
+            output.checksum_sha1,
+            output.checksum_sha256,
+        );
+
+        Ok(ObjectBody::new(
+            output.body,
+            output.content_length,
+            output.content_type,
+            output.e_tag,
+            checksum,
+        ))
+    }
+
+    /// Returns the object body as a stream. Use [`ObjectBody::collect()`] to
+    /// read it into memory.
+    pub async fn get_object(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+    ) -> Result<ObjectBody, Error> {
+        self.get_object_inner(client, key, None).await
+    }
+
+    /// Like [`get_object()`](Self::get_object()), but only returns the
+    /// given range of the object
+    pub async fn get_object_range(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+        range: ByteRange,
+    ) -> Result<ObjectBody, Error> {
+        self.get_object_inner(client, key, Some(range)).await
+    }
+
+    pub async fn delete_object(&self, client: &RegionClient, key: &ObjectKey) -> Result<(), Error> {
+        let _output = send_to_bucket_region(client, &self.name, |config| {
+            customized!(
+                client
+                    .main
+                    .s3
+                    .delete_object()
+                    .bucket(self.name.as_str())
+                    .key(key.as_str()),
+                config
+            )
+            .send()
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(send_to_bucket_region(client, &self.name, |config| {
+            customized!(
+                client
+                    .main
+                    .s3
+                    .get_bucket_tagging()
+                    .bucket(self.name.as_str()),
+                config
+            )
+            .send()
+        })
+        .await?
+        .tag_set
+        .try_into()?)
+    }
+
+    /// Replaces all tags of the bucket, S3 does not support adding single
+    /// tags
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "only expect() on builder instances"
+    )]
+    pub async fn set_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let tagging = aws_sdk_s3::types::Tagging::builder()
+            .set_tag_set(Some(tags.into()))
+            .build()
+            .expect("builder misused");
+
+        let _output = send_to_bucket_region(client, &self.name, |config| {
+            customized!(
+                client
+                    .main
+                    .s3
+                    .put_bucket_tagging()
+                    .bucket(self.name.as_str())
+                    .tagging(tagging.clone()),
+                config
+            )
+            .send()
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn object_tags(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+    ) -> Result<TagList, Error> {
+        Ok(send_to_bucket_region(client, &self.name, |config| {
+            customized!(
+                client
+                    .main
+                    .s3
+                    .get_object_tagging()
+                    .bucket(self.name.as_str())
+                    .key(key.as_str()),
+                config
+            )
+            .send()
+        })
+        .await?
+        .tag_set
+        .try_into()?)
+    }
+
+    /// Replaces all tags of the object
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "only expect() on builder instances"
+    )]
+    pub async fn set_object_tags(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+        tags: TagList,
+    ) -> Result<(), Error> {
+        let tagging = aws_sdk_s3::types::Tagging::builder()
+            .set_tag_set(Some(tags.into()))
+            .build()
+            .expect("builder misused");
+
+        let _output = send_to_bucket_region(client, &self.name, |config| {
+            customized!(
+                client
+                    .main
+                    .s3
+                    .put_object_tagging()
+                    .bucket(self.name.as_str())
+                    .key(key.as_str())
+                    .tagging(tagging.clone()),
+                config
+            )
+            .send()
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn multipart_uploader<'a>(
+        &'a self,
+        client: &'a RegionClient,
+        key: ObjectKey,
+        config: MultipartConfig,
+    ) -> MultipartUploader<'a> {
+        MultipartUploader::new(client, &self.name, key, config)
+    }
+
+    /// A presigned `GetObject` request, valid for `expires_in` (at most 7
+    /// days)
+    pub async fn presign_get(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest, Error> {
+        presign::get(client, &self.name, key, expires_in).await
+    }
+
+    pub async fn presign_head(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest, Error> {
+        presign::head(client, &self.name, key, expires_in).await
+    }
+
+    /// A presigned `PutObject` request. Content type and tags are part of
+    /// the signature and have to be sent as the returned headers.
+    pub async fn presign_put(
+        &self,
+        client: &RegionClient,
+        key: &ObjectKey,
+        expires_in: Duration,
+        options: PresignPutOptions,
+    ) -> Result<PresignedRequest, Error> {
+        presign::put(client, &self.name, key, expires_in, options).await
+    }
+}
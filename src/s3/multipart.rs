@@ -0,0 +1,466 @@
+use std::{future::Future, pin::pin, time::Duration};
+
+use aws_sdk_s3::{primitives::ByteStream, types::CompletedPart};
+use futures_util::{
+    future::{self, Either},
+    stream::{FuturesUnordered, StreamExt as _},
+};
+use tokio::io::{AsyncRead, AsyncReadExt as _};
+
+use super::{BucketName, ChecksumAlgorithm, ObjectKey};
+use crate::{metrics, Error, RegionClient};
+
+/// S3 rejects parts smaller than 5 MiB, except for the last one
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+const MAX_PARTS: i32 = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MultipartConfig {
+    /// Size of each part. Raised to 5 MiB if smaller.
+    pub part_size: usize,
+    /// Maximum number of parts uploaded at the same time
+    pub concurrency: usize,
+    /// How often each part is tried before the whole upload is aborted.
+    /// Only transient errors (throttling, server errors) are retried.
+    pub max_attempts: u32,
+    /// Delay before the first retry of a part, doubled for each further retry
+    pub retry_delay: Duration,
+    /// Checksum sent with every part. S3 stores a checksum of the part
+    /// checksums for the object.
+    pub checksum: Option<ChecksumAlgorithm>,
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        Self {
+            part_size: 8 * 1024 * 1024,
+            concurrency: 4,
+            max_attempts: 3,
+            retry_delay: Duration::from_millis(200),
+            checksum: None,
+        }
+    }
+}
+
+/// Uploads an object in parts.
+///
+/// Parts are read from the reader sequentially, but up to
+/// [`MultipartConfig::concurrency`] of them are uploaded in parallel, so at
+/// most `concurrency` parts are buffered in memory at any time. If any part
+/// fails after all retries, the upload is aborted so that no orphaned parts
+/// are left behind (and billed).
+pub struct MultipartUploader<'a> {
+    client: &'a RegionClient,
+    bucket: &'a BucketName,
+    key: ObjectKey,
+    config: MultipartConfig,
+}
+
+impl<'a> MultipartUploader<'a> {
+    pub fn new(
+        client: &'a RegionClient,
+        bucket: &'a BucketName,
+        key: ObjectKey,
+        config: MultipartConfig,
+    ) -> Self {
+        Self {
+            client,
+            bucket,
+            key,
+            config: MultipartConfig {
+                part_size: config.part_size.max(MIN_PART_SIZE),
+                concurrency: config.concurrency.max(1),
+                max_attempts: config.max_attempts.max(1),
+                ..config
+            },
+        }
+    }
+
+    async fn read_part<R: AsyncRead + Unpin>(&self, reader: &mut R) -> Result<Vec<u8>, Error> {
+        let limit = u64::try_from(self.config.part_size).map_err(|e| Error::MultipartUpload {
+            message: format!("invalid part size: {e}"),
+        })?;
+
+        let mut buffer = Vec::with_capacity(self.config.part_size);
+        let _read = reader
+            .take(limit)
+            .read_to_end(&mut buffer)
+            .await
+            .map_err(|e| Error::MultipartUpload {
+                message: format!("failed reading part: {e}"),
+            })?;
+
+        Ok(buffer)
+    }
+
+    async fn upload_part(
+        &self,
+        upload_id: &str,
+        part_number: i32,
+        body: Vec<u8>,
+    ) -> Result<CompletedPart, Error> {
+        let mut attempt: u32 = 1;
+        let mut delay = self.config.retry_delay;
+
+        loop {
+            match self
+                .client
+                .main
+                .s3
+                .upload_part()
+                .bucket(self.bucket.as_str())
+                .key(self.key.as_str())
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(body.clone()))
+                .set_checksum_algorithm(self.config.checksum.map(Into::into))
+                .send()
+                .await
+            {
+                // Completing the upload requires the checksum of each part
+                Ok(output) => {
+                    return Ok(CompletedPart::builder()
+                        .part_number(part_number)
+                        .set_e_tag(output.e_tag)
+                        .set_checksum_crc32(output.checksum_crc32)
+                        .set_checksum_crc32_c(output.checksum_crc32_c)
+                        .set_checksum_sha1(output.checksum_sha1)
+                        .set_checksum_sha256(output.checksum_sha256)
+                        .build())
+                }
+                Err(e) => {
+                    // Errors like missing permissions or an aborted upload
+                    // will not go away on retry
+                    let e = Error::from(e);
+                    if attempt >= self.config.max_attempts || !metrics::is_transient(&e) {
+                        return Err(e);
+                    }
+                    tokio::time::sleep(delay).await;
+                    attempt = attempt.saturating_add(1);
+                    delay = delay.saturating_mul(2);
+                }
+            }
+        }
+    }
+
+    /// Reads the next part while the uploads in flight keep being polled, so
+    /// that they progress (and fail early) while the reader is slow
+    async fn read_part_polling<R, F>(
+        &self,
+        reader: &mut R,
+        in_flight: &mut FuturesUnordered<F>,
+        completed: &mut Vec<CompletedPart>,
+    ) -> Result<Vec<u8>, Error>
+    where
+        R: AsyncRead + Unpin,
+        F: Future<Output = Result<CompletedPart, Error>>,
+    {
+        let mut read = pin!(self.read_part(reader));
+
+        loop {
+            if in_flight.is_empty() {
+                return read.await;
+            }
+
+            match future::select(read.as_mut(), in_flight.next()).await {
+                Either::Left((part, _)) => return part,
+                Either::Right((Some(result), _)) => completed.push(result?),
+                Either::Right((None, _)) => {}
+            }
+        }
+    }
+
+    /// Uploads parts until the reader is exhausted. Does not abort on error.
+    async fn upload_parts<R: AsyncRead + Unpin>(
+        &self,
+        upload_id: &str,
+        first_part: Vec<u8>,
+        reader: &mut R,
+    ) -> Result<Vec<CompletedPart>, Error> {
+        let mut completed = Vec::new();
+        let mut in_flight = FuturesUnordered::new();
+
+        let mut part_number: i32 = 1;
+        in_flight.push(self.upload_part(upload_id, part_number, first_part));
+
+        loop {
+            // The part that is read counts against the concurrency as well,
+            // as it is buffered in memory
+            while in_flight.len() >= self.config.concurrency {
+                if let Some(result) = in_flight.next().await {
+                    completed.push(result?);
+                }
+            }
+
+            let part = self
+                .read_part_polling(reader, &mut in_flight, &mut completed)
+                .await?;
+            if part.is_empty() {
+                break;
+            }
+
+            if part_number >= MAX_PARTS {
+                return Err(Error::MultipartUpload {
+                    message: format!(
+                        "object needs more than {MAX_PARTS} parts, increase the part size"
+                    ),
+                });
+            }
+            part_number = part_number.saturating_add(1);
+
+            in_flight.push(self.upload_part(upload_id, part_number, part));
+        }
+
+        while let Some(result) = in_flight.next().await {
+            completed.push(result?);
+        }
+
+        completed.sort_by_key(|part| part.part_number);
+
+        Ok(completed)
+    }
+
+    /// Uploads everything read from `reader`.
+    ///
+    /// If the data fits into a single part, a plain `PutObject` is used
+    /// instead of a multipart upload.
+    ///
+    /// # Cancellation
+    ///
+    /// When the future is dropped, parts that are in flight are cancelled,
+    /// but the multipart upload is not aborted, as that would require another
+    /// request. The already uploaded parts are kept (and billed) until the
+    /// upload is aborted, so buckets used with this should have a lifecycle
+    /// rule that aborts incomplete multipart uploads. Retrying starts a new
+    /// upload and is safe.
+    pub async fn upload<R: AsyncRead + Unpin>(&self, mut reader: R) -> Result<(), Error> {
+        let first_part = self.read_part(&mut reader).await?;
+
+        if first_part.len() < self.config.part_size {
+            let _output = self
+                .client
+                .main
+                .s3
+                .put_object()
+                .bucket(self.bucket.as_str())
+                .key(self.key.as_str())
+                .body(ByteStream::from(first_part))
+                .set_checksum_algorithm(self.config.checksum.map(Into::into))
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        let upload_id = self
+            .client
+            .main
+            .s3
+            .create_multipart_upload()
+            .bucket(self.bucket.as_str())
+            .key(self.key.as_str())
+            .set_checksum_algorithm(self.config.checksum.map(Into::into))
+            .send()
+            .await?
+            .upload_id
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "CreateMultipartUploadOutput.upload_id".to_owned(),
+            })?;
+
+        let result = match self.upload_parts(&upload_id, first_part, &mut reader).await {
+            Ok(parts) => self
+                .client
+                .main
+                .s3
+                .complete_multipart_upload()
+                .bucket(self.bucket.as_str())
+                .key(self.key.as_str())
+                .upload_id(&upload_id)
+                .multipart_upload(
+                    aws_sdk_s3::types::CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .send()
+                .await
+                .map(|_output| ())
+                .map_err(Into::into),
+            Err(e) => Err(e),
+        };
+
+        if result.is_err() {
+            // The original error is more interesting than a failure to abort
+            let _abort = self
+                .client
+                .main
+                .s3
+                .abort_multipart_upload()
+                .bucket(self.bucket.as_str())
+                .key(self.key.as_str())
+                .upload_id(&upload_id)
+                .send()
+                .await;
+        }
+
+        result
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse, Request},
+        Region,
+    };
+
+    const CREATED: &str = "<InitiateMultipartUploadResult><Bucket>bucket</Bucket>\
+        <Key>key</Key><UploadId>upload-1</UploadId></InitiateMultipartUploadResult>";
+
+    const COMPLETED: &str = "<CompleteMultipartUploadResult><Bucket>bucket</Bucket>\
+        <Key>key</Key><ETag>object</ETag></CompleteMultipartUploadResult>";
+
+    fn is_create(request: &Request) -> bool {
+        request.method == "POST" && request.uri.contains("?uploads")
+    }
+
+    fn is_complete(request: &Request) -> bool {
+        request.method == "POST" && request.uri.contains("uploadId=upload-1")
+    }
+
+    fn is_abort(request: &Request) -> bool {
+        request.method == "DELETE"
+    }
+
+    /// Only valid for part numbers below 10, which is all these tests use
+    fn is_part(request: &Request, part_number: i32) -> bool {
+        request.method == "PUT" && request.uri.contains(&format!("partNumber={part_number}"))
+    }
+
+    fn part_failure() -> MockResponse {
+        MockResponse::status(
+            500,
+            "<Error><Code>InternalError</Code><Message>try again</Message></Error>",
+        )
+    }
+
+    /// Mocks a successful upload, with an ETag per part. `rules` are matched
+    /// before the defaults.
+    fn http(rules: impl FnOnce(MockHttpClient) -> MockHttpClient) -> MockHttpClient {
+        (1..=3)
+            .fold(rules(MockHttpClient::new()), |http, part_number| {
+                http.on(
+                    Matcher::predicate(move |request| is_part(request, part_number)),
+                    MockResponse::ok("").with_header("ETag", &format!("etag-{part_number}")),
+                )
+            })
+            .on(Matcher::predicate(is_create), MockResponse::ok(CREATED))
+            .on(Matcher::predicate(is_complete), MockResponse::ok(COMPLETED))
+            .on(Matcher::predicate(is_abort), MockResponse::status(204, ""))
+    }
+
+    /// Two full parts and a short last one
+    fn upload(http: &MockHttpClient) -> Result<(), Error> {
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+        let bucket = BucketName::new("bucket".to_owned());
+        let config = MultipartConfig {
+            max_attempts: 2,
+            retry_delay: Duration::from_millis(10),
+            ..MultipartConfig::default()
+        };
+        let uploader =
+            MultipartUploader::new(&client, &bucket, ObjectKey::new("key".to_owned()), config);
+
+        let data = vec![0_u8; MIN_PART_SIZE.saturating_mul(2).saturating_add(10)];
+        block_on(uploader.upload(data.as_slice()))
+    }
+
+    fn count(
+        http: &MockHttpClient,
+        predicate: impl Fn(&Request) -> bool + Send + Sync + 'static,
+    ) -> usize {
+        http.requests_matching(&Matcher::predicate(predicate))
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn failed_part_is_retried() {
+        let http = http(|http| {
+            http.once(
+                Matcher::predicate(|request| is_part(request, 2)),
+                part_failure(),
+            )
+        });
+
+        upload(&http).unwrap();
+
+        assert_eq!(count(&http, |request| is_part(request, 2)), 2);
+        assert_eq!(count(&http, is_complete), 1);
+        assert_eq!(count(&http, is_abort), 0, "the upload succeeded");
+    }
+
+    #[test]
+    fn permanent_failure_aborts() {
+        let http = http(|http| {
+            http.on(
+                Matcher::predicate(|request| is_part(request, 2)),
+                part_failure(),
+            )
+        });
+
+        assert!(upload(&http).is_err(), "the upload must fail");
+
+        assert_eq!(count(&http, |request| is_part(request, 2)), 2);
+        assert_eq!(count(&http, is_complete), 0);
+        assert_eq!(count(&http, is_abort), 1, "the upload has to be aborted");
+    }
+
+    #[test]
+    fn client_error_is_not_retried() {
+        let http = http(|http| {
+            http.on(
+                Matcher::predicate(|request| is_part(request, 2)),
+                MockResponse::status(
+                    403,
+                    "<Error><Code>AccessDenied</Code><Message>denied</Message></Error>",
+                ),
+            )
+        });
+
+        assert!(upload(&http).is_err(), "the upload must fail");
+
+        assert_eq!(count(&http, |request| is_part(request, 2)), 1);
+        assert_eq!(count(&http, is_abort), 1, "the upload has to be aborted");
+    }
+
+    #[test]
+    fn parts_are_completed_in_order() {
+        // the retry makes the first part finish last
+        let http = http(|http| {
+            http.once(
+                Matcher::predicate(|request| is_part(request, 1)),
+                part_failure(),
+            )
+        });
+
+        upload(&http).unwrap();
+
+        let body = http
+            .requests()
+            .unwrap()
+            .into_iter()
+            .find(is_complete)
+            .unwrap()
+            .body;
+        let values = |tag: &str| -> Vec<String> {
+            body.split(&format!("<{tag}>"))
+                .skip(1)
+                .filter_map(|rest| rest.split_once('<'))
+                .map(|(value, _)| value.to_owned())
+                .collect()
+        };
+        assert_eq!(values("PartNumber"), vec!["1", "2", "3"]);
+        assert_eq!(values("ETag"), vec!["etag-1", "etag-2", "etag-3"]);
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use aws_sdk_s3::presigning::PresigningConfig;
+
+use super::{BucketName, ObjectKey};
+use crate::{protocol::url_encode, tags::TagList, Error, RegionClient};
+
+/// A presigned request that can be executed without AWS credentials
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedRequest {
+    pub method: String,
+    pub url: String,
+    /// Headers that are part of the signature. The caller must send them
+    /// exactly as given, otherwise S3 rejects the request.
+    pub headers: Vec<(String, String)>,
+}
+
+impl From<aws_sdk_s3::presigning::PresignedRequest> for PresignedRequest {
+    fn from(request: aws_sdk_s3::presigning::PresignedRequest) -> Self {
+        Self {
+            method: request.method().to_owned(),
+            url: request.uri().to_owned(),
+            headers: request
+                .headers()
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PresignPutOptions {
+    pub content_type: Option<String>,
+    /// Tags that are set on the uploaded object
+    pub tags: Option<TagList>,
+}
+
+fn config(expires_in: Duration) -> Result<PresigningConfig, Error> {
+    PresigningConfig::expires_in(expires_in).map_err(|e| Error::Presign {
+        message: e.to_string(),
+    })
+}
+
+/// The `x-amz-tagging` header value, a URL-encoded query string
+fn tagging_header(tags: &TagList) -> String {
+    tags.as_slice()
+        .iter()
+        .map(|tag| {
+            format!(
+                "{}={}",
+                url_encode(tag.key().as_str()),
+                url_encode(tag.value().as_str())
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+pub(super) async fn get(
+    client: &RegionClient,
+    bucket: &BucketName,
+    key: &ObjectKey,
+    expires_in: Duration,
+) -> Result<PresignedRequest, Error> {
+    Ok(client
+        .main
+        .s3
+        .get_object()
+        .bucket(bucket.as_str())
+        .key(key.as_str())
+        .presigned(config(expires_in)?)
+        .await?
+        .into())
+}
+
+pub(super) async fn head(
+    client: &RegionClient,
+    bucket: &BucketName,
+    key: &ObjectKey,
+    expires_in: Duration,
+) -> Result<PresignedRequest, Error> {
+    Ok(client
+        .main
+        .s3
+        .head_object()
+        .bucket(bucket.as_str())
+        .key(key.as_str())
+        .presigned(config(expires_in)?)
+        .await?
+        .into())
+}
+
+pub(super) async fn put(
+    client: &RegionClient,
+    bucket: &BucketName,
+    key: &ObjectKey,
+    expires_in: Duration,
+    options: PresignPutOptions,
+) -> Result<PresignedRequest, Error> {
+    Ok(client
+        .main
+        .s3
+        .put_object()
+        .bucket(bucket.as_str())
+        .key(key.as_str())
+        .set_content_type(options.content_type)
+        .set_tagging(options.tags.as_ref().map(tagging_header))
+        .presigned(config(expires_in)?)
+        .await?
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::RawTag;
+
+    #[test]
+    fn encode_tagging_header() {
+        let tags = TagList::from_vec(vec![
+            RawTag::new("env".to_owned(), "prod".to_owned()),
+            RawTag::new("owner".to_owned(), "team a&b".to_owned()),
+        ]);
+
+        assert_eq!(tagging_header(&tags), "env=prod&owner=team%20a%26b");
+    }
+}
@@ -0,0 +1,167 @@
+//! Requests to buckets in other regions than the one of the client
+//!
+//! S3 rejects those with a `301 PermanentRedirect` (or a `400` for requests
+//! signed for the wrong region) and names the region of the bucket in the
+//! `x-amz-bucket-region` header. The bucket operations of this module then
+//! send the request again, signed for and addressed to that region, and
+//! remember the region of the bucket for later requests.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use aws_sdk_s3::error::SdkError;
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+
+use super::BucketName;
+use crate::{Error, RegionClient};
+
+const BUCKET_REGION_HEADER: &str = "x-amz-bucket-region";
+
+/// Statuses of responses to requests that went to the wrong region
+const REDIRECT_STATUSES: &[u16] = &[301, 307, 400];
+
+/// The regions of buckets, learned from redirects. Shared by all clones of
+/// a [`RegionClient`], as the region of a bucket does not depend on the
+/// region of the client.
+#[derive(Debug, Clone, Default)]
+pub struct BucketRegions(Arc<Mutex<HashMap<String, String>>>);
+
+impl BucketRegions {
+    pub fn get(&self, bucket: &BucketName) -> Option<String> {
+        self.0.lock().ok()?.get(bucket.as_str()).cloned()
+    }
+
+    pub fn insert(&self, bucket: &BucketName, region: String) {
+        if let Ok(mut regions) = self.0.lock() {
+            let _previous = regions.insert(bucket.as_str().to_owned(), region);
+        }
+    }
+}
+
+/// The region of the bucket, if the response is a redirect to it
+pub(super) fn redirect_region(response: &HttpResponse) -> Option<String> {
+    if !REDIRECT_STATUSES.contains(&response.status().as_u16()) {
+        return None;
+    }
+
+    response
+        .headers()
+        .get(BUCKET_REGION_HEADER)
+        .filter(|region| !region.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+/// A region override for requests to the bucket, if its region is known
+pub(super) fn known_region(
+    client: &RegionClient,
+    bucket: &BucketName,
+) -> Option<aws_sdk_s3::config::Builder> {
+    client
+        .s3_bucket_regions
+        .as_ref()?
+        .get(bucket)
+        .map(|region| region_override(&region))
+}
+
+pub(super) fn region_override(region: &str) -> aws_sdk_s3::config::Builder {
+    aws_sdk_s3::config::Builder::default()
+        .region(aws_sdk_s3::config::Region::new(region.to_owned()))
+}
+
+/// Applies the region override of [`send_to_bucket_region()`] to a request
+macro_rules! customized {
+    ($request:expr, $config:expr) => {{
+        let operation = $request.customize();
+        match $config {
+            Some(config) => operation.config_override(config),
+            None => operation,
+        }
+    }};
+}
+
+pub(super) use customized;
+
+/// Runs `send` with a region override for the bucket if its region is
+/// known, and once more with the region of a redirect
+///
+/// `send` must build a new request on every call, applying the override
+/// with [`customized!`].
+pub(super) async fn send_to_bucket_region<T, E, F, Fut>(
+    client: &RegionClient,
+    bucket: &BucketName,
+    mut send: F,
+) -> Result<T, Error>
+where
+    F: FnMut(Option<aws_sdk_s3::config::Builder>) -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E, HttpResponse>>>,
+    E: std::error::Error + Send + 'static,
+{
+    let Some(ref regions) = client.s3_bucket_regions else {
+        return Ok(send(None).await?);
+    };
+
+    let known = regions.get(bucket);
+
+    match send(known.as_deref().map(region_override)).await {
+        Ok(output) => Ok(output),
+        Err(e) => match e.raw_response().and_then(redirect_region) {
+            Some(region)
+                if known.as_deref() != Some(region.as_str())
+                    && region != client.region.as_str() =>
+            {
+                regions.insert(bucket, region.clone());
+                Ok(send(Some(region_override(&region))).await?)
+            }
+            _ => Err(e.into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_smithy_runtime_api::http::StatusCode;
+    use aws_smithy_types::body::SdkBody;
+
+    use super::*;
+
+    fn response(status: u16, region: Option<&str>) -> HttpResponse {
+        let mut response =
+            HttpResponse::new(StatusCode::try_from(status).unwrap(), SdkBody::empty());
+        if let Some(region) = region {
+            let _previous = response
+                .headers_mut()
+                .insert(BUCKET_REGION_HEADER, region.to_owned());
+        }
+        response
+    }
+
+    #[test]
+    fn redirects() {
+        assert_eq!(
+            redirect_region(&response(301, Some("eu-west-1"))).as_deref(),
+            Some("eu-west-1")
+        );
+        assert_eq!(
+            redirect_region(&response(400, Some("us-east-2"))).as_deref(),
+            Some("us-east-2")
+        );
+        assert_eq!(redirect_region(&response(301, None)), None);
+        // HeadBucket and GetObject of existing buckets also carry the header
+        assert_eq!(redirect_region(&response(200, Some("eu-west-1"))), None);
+        assert_eq!(redirect_region(&response(403, Some("eu-west-1"))), None);
+    }
+
+    #[test]
+    fn bucket_regions() {
+        let regions = BucketRegions::default();
+        let bucket = BucketName::new("logs".to_owned());
+
+        assert_eq!(regions.get(&bucket), None);
+        let shared = regions.clone();
+        shared.insert(&bucket, "eu-west-1".to_owned());
+        assert_eq!(regions.get(&bucket).as_deref(), Some("eu-west-1"));
+    }
+}
@@ -0,0 +1,480 @@
+//! Secrets Manager
+//!
+//! With the `serde` feature, [`CachedSecret`] provides a typed view of a JSON
+//! secret that is only fetched again when it may have changed. It needs a
+//! clock for its TTL, so it is not available on wasm.
+
+use std::fmt;
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+use std::time::{Duration, Instant};
+
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{tags::TagList, Error, RegionClient};
+
+/// The version stage that points to the current version of a secret
+pub const STAGE_CURRENT: &str = "AWSCURRENT";
+
+string_newtype!(SecretId);
+
+impl SecretId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the version of the secret that has the given version stage
+    /// attached, usually [`STAGE_CURRENT`]
+    pub async fn get_value(
+        &self,
+        client: &RegionClient,
+        stage: &str,
+    ) -> Result<SecretVersion, Error> {
+        let output = client
+            .main
+            .secretsmanager
+            .get_secret_value()
+            .secret_id(self.as_str())
+            .version_stage(stage)
+            .send()
+            .await?;
+
+        Ok(SecretVersion {
+            version_id: SecretVersionId(output.version_id.ok_or_else(|| {
+                Error::UnexpectedNoneValue {
+                    entity: "GetSecretValueOutput.version_id".to_owned(),
+                }
+            })?),
+            stages: output.version_stages.unwrap_or_default(),
+            value: match (output.secret_string, output.secret_binary) {
+                (Some(value), _) => SecretValue::String(value),
+                (None, Some(value)) => SecretValue::Binary(value.into_inner()),
+                (None, None) => {
+                    return Err(Error::UnexpectedNoneValue {
+                        entity: "GetSecretValueOutput.secret_string".to_owned(),
+                    })
+                }
+            },
+        })
+    }
+
+    /// Stores a new string value. The new version becomes
+    /// [`STAGE_CURRENT`].
+    pub async fn put_value(
+        &self,
+        client: &RegionClient,
+        value: String,
+    ) -> Result<SecretVersionId, Error> {
+        Ok(SecretVersionId(
+            client
+                .main
+                .secretsmanager
+                .put_secret_value()
+                .secret_id(self.as_str())
+                .secret_string(value)
+                .send()
+                .await?
+                .version_id
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "PutSecretValueOutput.version_id".to_owned(),
+                })?,
+        ))
+    }
+
+    /// Returns the version that currently has `stage` attached, without
+    /// fetching the secret value
+    pub async fn version_for_stage(
+        &self,
+        client: &RegionClient,
+        stage: &str,
+    ) -> Result<Option<SecretVersionId>, Error> {
+        Ok(client
+            .main
+            .secretsmanager
+            .describe_secret()
+            .secret_id(self.as_str())
+            .send()
+            .await?
+            .version_ids_to_stages
+            .unwrap_or_default()
+            .into_iter()
+            .find(|version| version.1.iter().any(|s| s == stage))
+            .map(|(version, _stages)| SecretVersionId(version)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretVersionId(String);
+
+impl SecretVersionId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The value of a secret. Secrets can either hold a string or binary data.
+#[derive(Clone, PartialEq, Eq)]
+pub enum SecretValue {
+    String(String),
+    Binary(Vec<u8>),
+}
+
+// Do not leak secret values into logs
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::String(_) => write!(f, "SecretValue::String(<redacted>)"),
+            Self::Binary(_) => write!(f, "SecretValue::Binary(<redacted>)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SecretVersion {
+    version_id: SecretVersionId,
+    stages: Vec<String>,
+    value: SecretValue,
+}
+
+impl SecretVersion {
+    pub const fn version_id(&self) -> &SecretVersionId {
+        &self.version_id
+    }
+
+    pub fn stages(&self) -> &[String] {
+        &self.stages
+    }
+
+    pub const fn value(&self) -> &SecretValue {
+        &self.value
+    }
+
+    pub fn into_value(self) -> SecretValue {
+        self.value
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Secret {
+    id: SecretId,
+    name: String,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_secretsmanager::types::SecretListEntry> for Secret {
+    type Error = Error;
+
+    fn try_from(
+        secret: aws_sdk_secretsmanager::types::SecretListEntry,
+    ) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                secret.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            id: SecretId(extract!(arn)?),
+            name: extract!(name)?,
+            tags: secret.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl Secret {
+    /// Returns all secrets, optionally restricted to secrets that have all of
+    /// the given tags
+    pub async fn list(client: &RegionClient, tags: Option<&TagList>) -> Result<Vec<Self>, Error> {
+        let secrets: Vec<Self> = client
+            .main
+            .secretsmanager
+            .list_secrets()
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.secret_list.unwrap_or_default())
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<Self>, Error>>()?;
+
+        // The tag filters of ListSecrets match keys and values independently
+        // of each other, so we filter on our side instead
+        Ok(match tags {
+            Some(tags) => secrets
+                .into_iter()
+                .filter(|secret| {
+                    tags.as_slice()
+                        .iter()
+                        .all(|tag| secret.tags.get(tag.key().clone()) == Some(tag))
+                })
+                .collect(),
+            None => secrets,
+        })
+    }
+
+    /// Creates a secret with a string value
+    pub async fn create(
+        client: &RegionClient,
+        name: &str,
+        value: String,
+        tags: TagList,
+    ) -> Result<Self, Error> {
+        let output = client
+            .main
+            .secretsmanager
+            .create_secret()
+            .name(name)
+            .secret_string(value)
+            .set_tags(Some(tags.clone().into()))
+            .send()
+            .await?;
+
+        Ok(Self {
+            id: SecretId(output.arn.ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "CreateSecretOutput.arn".to_owned(),
+            })?),
+            name: name.to_owned(),
+            tags,
+        })
+    }
+
+    pub const fn id(&self) -> &SecretId {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+}
+
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+struct CacheEntry<T> {
+    value: T,
+    version_id: SecretVersionId,
+    checked_at: Instant,
+}
+
+/// A JSON secret that is cached in memory.
+///
+/// After `ttl` has passed, the version attached to the configured stage is
+/// checked. Only if it changed (e.g. because the secret was rotated), the
+/// value is fetched again. This keeps calls to `GetSecretValue` to a minimum
+/// while picking up rotations within `ttl`.
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+pub struct CachedSecret<T: DeserializeOwned> {
+    id: SecretId,
+    stage: String,
+    ttl: Duration,
+    entry: Option<CacheEntry<T>>,
+}
+
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+impl<T: DeserializeOwned + Send + Sync> CachedSecret<T> {
+    pub fn new(id: SecretId, ttl: Duration) -> Self {
+        Self {
+            id,
+            stage: STAGE_CURRENT.to_owned(),
+            ttl,
+            entry: None,
+        }
+    }
+
+    /// Follows a version stage other than [`STAGE_CURRENT`], e.g.
+    /// `AWSPENDING` during rotation
+    #[must_use]
+    pub fn with_stage(mut self, stage: String) -> Self {
+        self.stage = stage;
+        self.entry = None;
+        self
+    }
+
+    /// Drops the cached value, so the next [`get()`](Self::get()) fetches
+    /// the secret
+    pub fn invalidate(&mut self) {
+        self.entry = None;
+    }
+
+    async fn fetch(&self, client: &RegionClient) -> Result<CacheEntry<T>, Error> {
+        let version = self.id.get_value(client, &self.stage).await?;
+
+        let value = match version.value {
+            SecretValue::String(ref value) => serde_json::from_str(value),
+            SecretValue::Binary(ref value) => serde_json::from_slice(value),
+        }
+        .map_err(|e| Error::InvalidSecret {
+            secret: self.id.to_string(),
+            message: e.to_string(),
+        })?;
+
+        Ok(CacheEntry {
+            value,
+            version_id: version.version_id,
+            checked_at: Instant::now(),
+        })
+    }
+
+    pub async fn get(&mut self, client: &RegionClient) -> Result<&T, Error> {
+        let refresh = match self.entry {
+            None => true,
+            Some(ref entry) if entry.checked_at.elapsed() >= self.ttl => {
+                self.id
+                    .version_for_stage(client, &self.stage)
+                    .await?
+                    .as_ref()
+                    != Some(&entry.version_id)
+            }
+            Some(_) => false,
+        };
+
+        if refresh {
+            self.entry = Some(self.fetch(client).await?);
+        }
+
+        let entry = self
+            .entry
+            .as_mut()
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "cached secret".to_owned(),
+            })?;
+        if entry.checked_at.elapsed() >= self.ttl {
+            entry.checked_at = Instant::now();
+        }
+
+        Ok(&entry.value)
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{
+        testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+        Region,
+    };
+
+    #[derive(Debug, Deserialize)]
+    struct Credentials {
+        user: String,
+    }
+
+    fn value(version: &str, user: &str) -> MockResponse {
+        MockResponse::ok(format!(
+            r#"{{"Name": "db", "VersionId": "{version}", "VersionStages": ["AWSCURRENT"],
+                "SecretString": "{{\"user\": \"{user}\"}}"}}"#
+        ))
+    }
+
+    fn current(version: &str) -> MockResponse {
+        MockResponse::ok(format!(
+            r#"{{"Name": "db", "VersionIdsToStages": {{"{version}": ["AWSCURRENT"]}}}}"#
+        ))
+    }
+
+    fn cached(ttl: Duration) -> CachedSecret<Credentials> {
+        CachedSecret::new(SecretId::new("db".to_owned()), ttl)
+    }
+
+    fn get(secret: &mut CachedSecret<Credentials>, client: &RegionClient) -> Result<String, Error> {
+        block_on(secret.get(client)).map(|credentials| credentials.user.clone())
+    }
+
+    fn calls(http: &MockHttpClient, action: &str) -> usize {
+        http.requests_matching(&Matcher::action(action))
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn value_is_cached_within_ttl() {
+        let http =
+            MockHttpClient::new().on(Matcher::action("GetSecretValue"), value("v1", "admin"));
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+        let mut secret = cached(Duration::from_secs(3600));
+
+        assert_eq!(get(&mut secret, &client).unwrap(), "admin");
+        assert_eq!(get(&mut secret, &client).unwrap(), "admin");
+
+        assert_eq!(calls(&http, "GetSecretValue"), 1);
+        assert_eq!(calls(&http, "DescribeSecret"), 0, "the ttl has not passed");
+    }
+
+    #[test]
+    fn unchanged_version_is_not_fetched_again() {
+        let http = MockHttpClient::new()
+            .on(Matcher::action("GetSecretValue"), value("v1", "admin"))
+            .on(Matcher::action("DescribeSecret"), current("v1"));
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+        let mut secret = cached(Duration::ZERO);
+
+        assert_eq!(get(&mut secret, &client).unwrap(), "admin");
+        assert_eq!(get(&mut secret, &client).unwrap(), "admin");
+
+        assert_eq!(calls(&http, "DescribeSecret"), 1);
+        assert_eq!(calls(&http, "GetSecretValue"), 1);
+    }
+
+    #[test]
+    fn rotated_version_is_fetched() {
+        let http = MockHttpClient::new()
+            .once(Matcher::action("GetSecretValue"), value("v1", "admin"))
+            .on(Matcher::action("GetSecretValue"), value("v2", "rotated"))
+            .on(Matcher::action("DescribeSecret"), current("v2"));
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+        let mut secret = cached(Duration::ZERO);
+
+        assert_eq!(get(&mut secret, &client).unwrap(), "admin");
+        assert_eq!(get(&mut secret, &client).unwrap(), "rotated");
+
+        let requests = http
+            .requests_matching(&Matcher::action("GetSecretValue"))
+            .unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .all(|request| request.param("VersionStage").as_deref() == Some(STAGE_CURRENT)));
+    }
+
+    #[test]
+    fn failed_check_keeps_cached_value() {
+        let http = MockHttpClient::new()
+            .on(Matcher::action("GetSecretValue"), value("v1", "admin"))
+            .once(
+                Matcher::action("DescribeSecret"),
+                MockResponse::status(
+                    400,
+                    r#"{"__type": "AccessDeniedException", "message": "denied"}"#,
+                ),
+            )
+            .on(Matcher::action("DescribeSecret"), current("v1"));
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+        let mut secret = cached(Duration::ZERO);
+
+        assert_eq!(get(&mut secret, &client).unwrap(), "admin");
+        assert!(get(&mut secret, &client).is_err());
+        assert_eq!(get(&mut secret, &client).unwrap(), "admin");
+
+        assert_eq!(
+            calls(&http, "GetSecretValue"),
+            1,
+            "the cached value was kept"
+        );
+    }
+}
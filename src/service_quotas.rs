@@ -0,0 +1,440 @@
+//! Service Quotas and preflight checks against them
+//!
+//! Quotas are identified by the code of the service (e.g. `ec2`) and the
+//! code of the quota (e.g. `L-1216C47A`), which are listed in the Service
+//! Quotas console or by [`ServiceQuota::list()`].
+//!
+//! Preflight checks compare the current usage plus the requested amount to
+//! the quota before provisioning anything, so a bulk operation fails early
+//! instead of halfway through:
+//!
+//! ```no_run
+//! # async fn f(client: &aws_lib::RegionClient, instance_type: &aws_lib::InstanceType) -> Result<(), aws_lib::Error> {
+//! use aws_lib::service_quotas::check_running_instances;
+//!
+//! let check = check_running_instances(client, instance_type, 20).await?.ensure()?;
+//! println!("{} vCPUs left after launching", check.headroom());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{filter::Filter, Error, InstanceType, RegionClient};
+
+pub const EC2_SERVICE_CODE: &str = "ec2";
+
+/// Running On-Demand Standard (A, C, D, H, I, M, R, T, Z) instances, in
+/// vCPUs
+pub const EC2_RUNNING_ON_DEMAND_STANDARD_VCPUS: &str = "L-1216C47A";
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceQuota {
+    service_code: String,
+    quota_code: String,
+    name: Option<String>,
+    value: Option<f64>,
+    unit: Option<String>,
+    adjustable: bool,
+    global: bool,
+}
+
+impl TryFrom<aws_sdk_servicequotas::types::ServiceQuota> for ServiceQuota {
+    type Error = Error;
+
+    fn try_from(quota: aws_sdk_servicequotas::types::ServiceQuota) -> Result<Self, Self::Error> {
+        Ok(Self {
+            service_code: quota
+                .service_code
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "ServiceQuota.service_code".to_owned(),
+                })?,
+            quota_code: quota.quota_code.ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "ServiceQuota.quota_code".to_owned(),
+            })?,
+            name: quota.quota_name,
+            value: quota.value,
+            unit: quota.unit,
+            adjustable: quota.adjustable,
+            global: quota.global_quota,
+        })
+    }
+}
+
+impl ServiceQuota {
+    /// The applied value of the quota in the region of `client`
+    pub async fn get(
+        client: &RegionClient,
+        service_code: &str,
+        quota_code: &str,
+    ) -> Result<Self, Error> {
+        client
+            .main
+            .servicequotas
+            .get_service_quota()
+            .service_code(service_code)
+            .quota_code(quota_code)
+            .send()
+            .await?
+            .quota
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "GetServiceQuotaOutput.quota".to_owned(),
+            })?
+            .try_into()
+    }
+
+    /// All quotas of a service that have an applied value. Quotas that were
+    /// never changed only have a default value and are not listed.
+    pub async fn list(client: &RegionClient, service_code: &str) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .servicequotas
+            .list_service_quotas()
+            .service_code(service_code)
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Requests raising the quota to `desired_value`. Increases are reviewed
+    /// by AWS and may take days, check the returned request for its status.
+    pub async fn request_increase(
+        &self,
+        client: &RegionClient,
+        desired_value: f64,
+    ) -> Result<QuotaIncreaseRequest, Error> {
+        let request = client
+            .main
+            .servicequotas
+            .request_service_quota_increase()
+            .service_code(&self.service_code)
+            .quota_code(&self.quota_code)
+            .desired_value(desired_value)
+            .send()
+            .await?
+            .requested_quota
+            .ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "RequestServiceQuotaIncreaseOutput.requested_quota".to_owned(),
+            })?;
+
+        Ok(QuotaIncreaseRequest {
+            id: request.id.ok_or_else(|| Error::UnexpectedNoneValue {
+                entity: "RequestedServiceQuotaChange.id".to_owned(),
+            })?,
+            status: request.status.map(|status| status.as_str().to_owned()),
+            desired_value: request.desired_value,
+        })
+    }
+
+    pub fn service_code(&self) -> &str {
+        &self.service_code
+    }
+
+    pub fn quota_code(&self) -> &str {
+        &self.quota_code
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub const fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    pub fn unit(&self) -> Option<&str> {
+        self.unit.as_deref()
+    }
+
+    /// Whether an increase can be requested
+    pub const fn adjustable(&self) -> bool {
+        self.adjustable
+    }
+
+    /// Whether the quota applies to all regions together
+    pub const fn global(&self) -> bool {
+        self.global
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaIncreaseRequest {
+    id: String,
+    status: Option<String>,
+    desired_value: Option<f64>,
+}
+
+impl QuotaIncreaseRequest {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// e.g. `PENDING`, `CASE_OPENED`, `APPROVED`, `DENIED`
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    pub const fn desired_value(&self) -> Option<f64> {
+        self.desired_value
+    }
+}
+
+/// The result of a preflight check
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaCheck {
+    quota: ServiceQuota,
+    usage: f64,
+    requested: f64,
+}
+
+impl QuotaCheck {
+    pub const fn new(quota: ServiceQuota, usage: f64, requested: f64) -> Self {
+        Self {
+            quota,
+            usage,
+            requested,
+        }
+    }
+
+    pub const fn quota(&self) -> &ServiceQuota {
+        &self.quota
+    }
+
+    pub const fn usage(&self) -> f64 {
+        self.usage
+    }
+
+    pub const fn requested(&self) -> f64 {
+        self.requested
+    }
+
+    /// What is left of the quota after the requested amount is used. A
+    /// quota without value is treated as unlimited.
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "quota values are floating point numbers in the API"
+    )]
+    pub fn headroom(&self) -> f64 {
+        self.quota
+            .value
+            .map_or(f64::INFINITY, |limit| limit - self.usage - self.requested)
+    }
+
+    pub fn is_sufficient(&self) -> bool {
+        self.headroom() >= 0.0
+    }
+
+    /// Fails with [`Error::QuotaExceeded`] if the quota is not sufficient
+    pub fn ensure(self) -> Result<Self, Error> {
+        if self.is_sufficient() {
+            Ok(self)
+        } else {
+            Err(Error::QuotaExceeded {
+                limit: self.quota.value.unwrap_or(f64::INFINITY),
+                quota: self.quota.name.unwrap_or(self.quota.quota_code),
+                usage: self.usage,
+                requested: self.requested,
+            })
+        }
+    }
+}
+
+/// Checks a quota against a usage that was determined by the caller
+pub async fn check(
+    client: &RegionClient,
+    service_code: &str,
+    quota_code: &str,
+    usage: f64,
+    requested: f64,
+) -> Result<QuotaCheck, Error> {
+    Ok(QuotaCheck::new(
+        ServiceQuota::get(client, service_code, quota_code).await?,
+        usage,
+        requested,
+    ))
+}
+
+/// Whether instances of the family count against the Running On-Demand
+/// Standard quota. Accelerated and HPC families have quotas of their own.
+fn is_standard_family(instance_type: &str) -> bool {
+    let family = instance_type
+        .split_once('.')
+        .map_or(instance_type, |(family, _)| family);
+
+    family.starts_with(['a', 'c', 'd', 'h', 'i', 'm', 'r', 't', 'z'])
+        && !family.starts_with("inf")
+        && !family.starts_with("hpc")
+        && !family.starts_with("dl")
+}
+
+/// vCPUs of pending and running On-Demand Standard instances
+#[expect(
+    clippy::float_arithmetic,
+    reason = "quota values are floating point numbers in the API"
+)]
+pub async fn running_on_demand_standard_vcpus(client: &RegionClient) -> Result<f64, Error> {
+    let mut counts: BTreeMap<String, i32> = BTreeMap::new();
+
+    let reservations: Vec<_> = client
+        .main
+        .ec2
+        .describe_instances()
+        .filters(Filter::new("instance-state-name", ["pending", "running"]).into())
+        .into_paginator()
+        .items()
+        .send()
+        .try_collect()
+        .await?;
+
+    for instance in reservations
+        .into_iter()
+        .flat_map(|reservation| reservation.instances.unwrap_or_default())
+        // Spot instances have a quota of their own
+        .filter(|instance| instance.instance_lifecycle.is_none())
+    {
+        if let Some(instance_type) = instance.instance_type {
+            if is_standard_family(instance_type.as_str()) {
+                let count = counts
+                    .entry(instance_type.as_str().to_owned())
+                    .or_insert(0_i32);
+                *count = count.saturating_add(1);
+            }
+        }
+    }
+
+    let vcpus = instance_type_vcpus(client, &counts.keys().cloned().collect()).await?;
+
+    Ok(counts
+        .iter()
+        .map(|(instance_type, &count)| {
+            f64::from(vcpus.get(instance_type).copied().unwrap_or(0_i32)) * f64::from(count)
+        })
+        .sum())
+}
+
+/// Default vCPUs of each of the instance types
+async fn instance_type_vcpus(
+    client: &RegionClient,
+    instance_types: &BTreeSet<String>,
+) -> Result<BTreeMap<String, i32>, Error> {
+    if instance_types.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let instance_types: Vec<_> = client
+        .main
+        .ec2
+        .describe_instance_types()
+        .set_instance_types(Some(
+            instance_types
+                .iter()
+                .map(|instance_type| instance_type.as_str().into())
+                .collect(),
+        ))
+        .into_paginator()
+        .items()
+        .send()
+        .try_collect()
+        .await?;
+
+    Ok(instance_types
+        .into_iter()
+        .filter_map(|info| {
+            Some((
+                info.instance_type?.as_str().to_owned(),
+                info.v_cpu_info?.default_v_cpus?,
+            ))
+        })
+        .collect())
+}
+
+/// Checks whether `count` more instances of `instance_type` fit into the
+/// Running On-Demand Standard quota
+///
+/// Instance types outside of the standard families are not checked, the
+/// returned check then requests nothing.
+#[expect(
+    clippy::float_arithmetic,
+    reason = "quota values are floating point numbers in the API"
+)]
+pub async fn check_running_instances(
+    client: &RegionClient,
+    instance_type: &InstanceType,
+    count: u32,
+) -> Result<QuotaCheck, Error> {
+    let requested = if is_standard_family(instance_type.inner().as_str()) {
+        let vcpus = instance_type_vcpus(
+            client,
+            &BTreeSet::from([instance_type.inner().as_str().to_owned()]),
+        )
+        .await?;
+
+        f64::from(
+            vcpus
+                .get(instance_type.inner().as_str())
+                .copied()
+                .unwrap_or(0_i32),
+        ) * f64::from(count)
+    } else {
+        0.0_f64
+    };
+
+    check(
+        client,
+        EC2_SERVICE_CODE,
+        EC2_RUNNING_ON_DEMAND_STANDARD_VCPUS,
+        running_on_demand_standard_vcpus(client).await?,
+        requested,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_families() {
+        assert!(is_standard_family("m5.large"), "general purpose");
+        assert!(is_standard_family("t4g.micro"), "burstable");
+        assert!(is_standard_family("i4i.xlarge"), "storage optimized");
+        assert!(!is_standard_family("inf2.xlarge"), "inferentia");
+        assert!(!is_standard_family("hpc7g.4xlarge"), "hpc");
+        assert!(!is_standard_family("p4d.24xlarge"), "gpu");
+        assert!(!is_standard_family("x2idn.16xlarge"), "memory");
+    }
+
+    #[test]
+    fn quota_check() {
+        let quota = ServiceQuota {
+            service_code: EC2_SERVICE_CODE.to_owned(),
+            quota_code: EC2_RUNNING_ON_DEMAND_STANDARD_VCPUS.to_owned(),
+            name: None,
+            value: Some(64.0_f64),
+            unit: None,
+            adjustable: true,
+            global: false,
+        };
+
+        let check = QuotaCheck::new(quota.clone(), 48.0, 16.0);
+        assert!(check.is_sufficient(), "exactly at the limit");
+        assert!(check.ensure().is_ok(), "exactly at the limit");
+
+        let check = QuotaCheck::new(quota, 48.0, 32.0);
+        assert!(!check.is_sufficient(), "over the limit");
+        assert!(
+            matches!(check.ensure(), Err(Error::QuotaExceeded { .. })),
+            "over the limit"
+        );
+    }
+}
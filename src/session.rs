@@ -0,0 +1,727 @@
+//! SSM Session Manager sessions
+//!
+//! [`Session::start()`] calls `StartSession` and connects to the WebSocket
+//! stream of the session, like the `session-manager-plugin` of the AWS CLI.
+//! The stream carries binary messages with a fixed header, a SHA-256 digest
+//! and the payload. Output of the instance is read with
+//! [`Session::next_event()`], which also acknowledges the messages and
+//! answers the handshake of the agent. Input is sent with [`Session::send()`].
+//!
+//! Requires the `session` feature.
+//!
+//! ```no_run
+//! # async fn f(client: &aws_lib::RegionClient, instance: &aws_lib::InstanceId) -> Result<(), aws_lib::Error> {
+//! use aws_lib::session::{Session, SessionEvent, SessionOptions};
+//!
+//! let mut session = Session::start(client, instance, SessionOptions::default()).await?;
+//! session.send(b"uptime; exit\n").await?;
+//! while let Some(event) = session.next_event().await? {
+//!     if let SessionEvent::Output(output) = event {
+//!         print!("{}", String::from_utf8_lossy(&output));
+//!     }
+//! }
+//! session.terminate(client).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Port forwarding and other session documents are selected via
+//! [`SessionOptions::document()`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use bytes::{Buf as _, Bytes};
+use chrono::Utc;
+use futures_util::{SinkExt as _, StreamExt as _};
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+use super::{protocol::Value, Error, InstanceId, RegionClient};
+
+/// Sent to the agent in the handshake
+const CLIENT_VERSION: &str = "1.2.0.0";
+
+/// Length of all header fields after the header length, up to the payload
+/// length
+const HEADER_LENGTH: u32 = 116;
+const MESSAGE_TYPE_LENGTH: usize = 32;
+const SCHEMA_VERSION: u32 = 1;
+
+/// Input is split into messages of at most this size, like the plugin does
+const MAX_INPUT_PAYLOAD: usize = 1024;
+
+mod message_type {
+    pub(super) const INPUT_STREAM_DATA: &str = "input_stream_data";
+    pub(super) const OUTPUT_STREAM_DATA: &str = "output_stream_data";
+    pub(super) const ACKNOWLEDGE: &str = "acknowledge";
+    pub(super) const CHANNEL_CLOSED: &str = "channel_closed";
+}
+
+mod payload_type {
+    pub(super) const OUTPUT: u32 = 1;
+    pub(super) const SIZE: u32 = 3;
+    pub(super) const HANDSHAKE_REQUEST: u32 = 5;
+    pub(super) const HANDSHAKE_RESPONSE: u32 = 6;
+    pub(super) const STDERR: u32 = 11;
+    pub(super) const EXIT_CODE: u32 = 12;
+}
+
+mod action_status {
+    pub(super) const SUCCESS: i64 = 1;
+    pub(super) const UNSUPPORTED: i64 = 3;
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::Session {
+        message: message.into(),
+    }
+}
+
+/// A message on the stream of a session, called `ClientMessage` by the
+/// plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AgentMessage {
+    message_type: String,
+    /// Milliseconds since the epoch
+    created: u64,
+    sequence_number: i64,
+    flags: u64,
+    message_id: Uuid,
+    payload_type: u32,
+    payload: Bytes,
+}
+
+impl AgentMessage {
+    fn new(message_type: &str, sequence_number: i64, payload_type: u32, payload: Bytes) -> Self {
+        Self {
+            message_type: message_type.to_owned(),
+            created: u64::try_from(Utc::now().timestamp_millis()).unwrap_or_default(),
+            sequence_number,
+            flags: 0,
+            message_id: Uuid::new_v4(),
+            payload_type,
+            payload,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let payload_length = u32::try_from(self.payload.len()).unwrap_or(u32::MAX);
+
+        let mut message_type = self.message_type.as_bytes().to_vec();
+        message_type.resize(MESSAGE_TYPE_LENGTH, b' ');
+
+        // The plugin writes the less significant half of the UUID first
+        let (most, least) = self.message_id.as_bytes().split_at(8);
+
+        let mut out = Vec::new();
+        out.extend_from_slice(&HEADER_LENGTH.to_be_bytes());
+        out.extend_from_slice(&message_type);
+        out.extend_from_slice(&SCHEMA_VERSION.to_be_bytes());
+        out.extend_from_slice(&self.created.to_be_bytes());
+        out.extend_from_slice(&self.sequence_number.to_be_bytes());
+        out.extend_from_slice(&self.flags.to_be_bytes());
+        out.extend_from_slice(least);
+        out.extend_from_slice(most);
+        out.extend_from_slice(&Sha256::digest(&self.payload));
+        out.extend_from_slice(&self.payload_type.to_be_bytes());
+        out.extend_from_slice(&payload_length.to_be_bytes());
+        out.extend_from_slice(&self.payload);
+        out
+    }
+
+    fn decode(mut data: &[u8]) -> Result<Self, Error> {
+        let header_length = usize::try_from(HEADER_LENGTH).unwrap_or(usize::MAX);
+        if data.len() < header_length.saturating_add(4) {
+            return Err(invalid("message is truncated"));
+        }
+
+        let actual_header_length = data.get_u32();
+        if actual_header_length < HEADER_LENGTH {
+            return Err(invalid(format!(
+                "invalid header length {actual_header_length}"
+            )));
+        }
+
+        let message_type =
+            String::from_utf8_lossy(data.get(..MESSAGE_TYPE_LENGTH).unwrap_or_default())
+                .trim_end_matches([' ', '\0'])
+                .to_owned();
+        data.advance(MESSAGE_TYPE_LENGTH);
+        let _schema_version = data.get_u32();
+        let created = data.get_u64();
+        let sequence_number = data.get_i64();
+        let flags = data.get_u64();
+
+        let mut message_id = [0_u8; 16];
+        data.copy_to_slice(&mut message_id);
+        let (least, most) = message_id.split_at(8);
+        let message_id = Uuid::from_slice(&[most, least].concat())
+            .map_err(|e| invalid(format!("invalid message id: {e}")))?;
+
+        let mut digest = [0_u8; 32];
+        data.copy_to_slice(&mut digest);
+        let payload_type = data.get_u32();
+
+        // Fields added by later schema versions are skipped
+        let extra = usize::try_from(actual_header_length.saturating_sub(HEADER_LENGTH))
+            .unwrap_or(usize::MAX);
+        if data.len() < extra.saturating_add(4) {
+            return Err(invalid("message is truncated"));
+        }
+        data.advance(extra);
+
+        let payload_length = usize::try_from(data.get_u32()).unwrap_or(usize::MAX);
+        let payload = data
+            .get(..payload_length)
+            .ok_or_else(|| invalid("payload is truncated"))?;
+
+        if Sha256::digest(payload).as_slice() != digest.as_slice() {
+            return Err(invalid("payload digest mismatch"));
+        }
+
+        Ok(Self {
+            message_type,
+            created,
+            sequence_number,
+            flags,
+            message_id,
+            payload_type,
+            payload: Bytes::copy_from_slice(payload),
+        })
+    }
+
+    /// The acknowledgement the agent expects for every stream message
+    fn acknowledgement(&self) -> Self {
+        let payload = Value::structure([
+            (
+                "AcknowledgedMessageType",
+                Value::from(self.message_type.as_str()),
+            ),
+            (
+                "AcknowledgedMessageId",
+                Value::from(self.message_id.hyphenated().to_string()),
+            ),
+            (
+                "AcknowledgedMessageSequenceNumber",
+                Value::from(self.sequence_number),
+            ),
+            ("IsSequentialMessage", Value::from(true)),
+        ])
+        .to_json();
+
+        Self {
+            flags: 3,
+            ..Self::new(message_type::ACKNOWLEDGE, 0, 0, Bytes::from(payload))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HandshakeRequest {
+    #[serde(default)]
+    requested_client_actions: Vec<RequestedClientAction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RequestedClientAction {
+    action_type: String,
+}
+
+/// Accepts the session type and refuses everything else, e.g. KMS
+/// encryption of the stream
+fn handshake_response(request: &[u8]) -> Result<Bytes, Error> {
+    let request: HandshakeRequest = serde_json::from_slice(request)
+        .map_err(|e| invalid(format!("invalid handshake request: {e}")))?;
+
+    let actions = request.requested_client_actions.iter().map(|action| {
+        let supported = action.action_type == "SessionType";
+        Value::structure([
+            ("ActionType", Value::from(action.action_type.as_str())),
+            (
+                "ActionStatus",
+                Value::from(if supported {
+                    action_status::SUCCESS
+                } else {
+                    action_status::UNSUPPORTED
+                }),
+            ),
+            (
+                "Error",
+                Value::from(if supported {
+                    String::new()
+                } else {
+                    format!("{} is not supported", action.action_type)
+                }),
+            ),
+        ])
+    });
+
+    Ok(Bytes::from(
+        Value::structure([
+            ("ClientVersion", Value::from(CLIENT_VERSION)),
+            ("ProcessedClientActions", Value::list(actions)),
+            ("Errors", Value::list([])),
+        ])
+        .to_json(),
+    ))
+}
+
+string_newtype!(SessionId);
+
+impl SessionId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Parameters of `StartSession`. The default starts an interactive shell.
+#[derive(Debug, Clone, Default)]
+pub struct SessionOptions {
+    document: Option<String>,
+    parameters: HashMap<String, Vec<String>>,
+    reason: Option<String>,
+}
+
+impl SessionOptions {
+    /// The session document, e.g. `AWS-StartPortForwardingSession` or
+    /// `AWS-StartNonInteractiveCommand`
+    #[must_use]
+    pub fn document(mut self, document: impl Into<String>) -> Self {
+        self.document = Some(document.into());
+        self
+    }
+
+    #[must_use]
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.parameters
+            .entry(name.into())
+            .or_default()
+            .push(value.into());
+        self
+    }
+
+    /// Recorded in CloudTrail and the session history
+    #[must_use]
+    pub fn reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// Standard output, or all output of interactive sessions
+    Output(Bytes),
+    /// Standard error of non-interactive sessions
+    Stderr(Bytes),
+    /// The exit code of the command of non-interactive sessions
+    ExitCode(i32),
+}
+
+pub struct Session {
+    id: SessionId,
+    target: InstanceId,
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    /// Sequence number of the next input message
+    input_sequence_number: i64,
+    /// Sequence number of the next output message. Output that arrives
+    /// early is kept in `pending`, output that is resent is dropped.
+    output_sequence_number: i64,
+    pending: BTreeMap<i64, AgentMessage>,
+    closed: bool,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("id", &self.id)
+            .field("target", &self.target)
+            .field("closed", &self.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Session {
+    /// Starts a session on the instance and opens its stream
+    ///
+    /// The instance needs a running SSM agent and an instance profile that
+    /// allows it to use Session Manager.
+    pub async fn start(
+        client: &RegionClient,
+        target: &InstanceId,
+        options: SessionOptions,
+    ) -> Result<Self, Error> {
+        let output = client
+            .main
+            .ssm
+            .start_session()
+            .target(target.as_str())
+            .set_document_name(options.document)
+            .set_parameters((!options.parameters.is_empty()).then_some(options.parameters))
+            .set_reason(options.reason)
+            .send()
+            .await?;
+
+        let missing = |entity: &str| Error::UnexpectedNoneValue {
+            entity: entity.to_owned(),
+        };
+        let id = SessionId(output.session_id.ok_or_else(|| missing("session_id"))?);
+        let stream_url = output.stream_url.ok_or_else(|| missing("stream_url"))?;
+        let token = output.token_value.ok_or_else(|| missing("token_value"))?;
+
+        let (mut socket, _response) = tokio_tungstenite::connect_async(stream_url)
+            .await
+            .map_err(|e| invalid(format!("cannot connect to the session stream: {e}")))?;
+
+        let open = Value::structure([
+            ("MessageSchemaVersion", Value::from("1.0")),
+            ("RequestId", Value::from(Uuid::new_v4().to_string())),
+            ("TokenValue", Value::from(token)),
+            ("ClientId", Value::from(Uuid::new_v4().to_string())),
+            ("ClientVersion", Value::from(CLIENT_VERSION)),
+        ])
+        .to_json();
+        socket
+            .send(Message::text(open))
+            .await
+            .map_err(|e| invalid(format!("cannot open the session stream: {e}")))?;
+
+        Ok(Self {
+            id,
+            target: target.clone(),
+            socket,
+            input_sequence_number: 0,
+            output_sequence_number: 0,
+            pending: BTreeMap::new(),
+            closed: false,
+        })
+    }
+
+    pub const fn id(&self) -> &SessionId {
+        &self.id
+    }
+
+    pub const fn target(&self) -> &InstanceId {
+        &self.target
+    }
+
+    async fn send_message(&mut self, message: &AgentMessage) -> Result<(), Error> {
+        self.socket
+            .send(Message::binary(message.encode()))
+            .await
+            .map_err(|e| invalid(format!("cannot send to the session stream: {e}")))
+    }
+
+    async fn send_input(&mut self, payload_type: u32, payload: Bytes) -> Result<(), Error> {
+        let message = AgentMessage::new(
+            message_type::INPUT_STREAM_DATA,
+            self.input_sequence_number,
+            payload_type,
+            payload,
+        );
+        self.send_message(&message).await?;
+        self.input_sequence_number = self.input_sequence_number.saturating_add(1);
+        Ok(())
+    }
+
+    /// Sends input to the session, e.g. keystrokes of an interactive shell
+    /// or the data of a forwarded port
+    pub async fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        for chunk in data.chunks(MAX_INPUT_PAYLOAD) {
+            self.send_input(payload_type::OUTPUT, Bytes::copy_from_slice(chunk))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Sets the terminal size of an interactive session
+    pub async fn resize(&mut self, columns: u32, rows: u32) -> Result<(), Error> {
+        let size = Value::structure([
+            ("cols", Value::from(i64::from(columns))),
+            ("rows", Value::from(i64::from(rows))),
+        ])
+        .to_json();
+        self.send_input(payload_type::SIZE, Bytes::from(size)).await
+    }
+
+    /// Handles an output message in sequence. Returns the event for the
+    /// caller, if there is one.
+    async fn handle_output(
+        &mut self,
+        message: AgentMessage,
+    ) -> Result<Option<SessionEvent>, Error> {
+        match message.payload_type {
+            payload_type::OUTPUT => Ok(Some(SessionEvent::Output(message.payload))),
+            payload_type::STDERR => Ok(Some(SessionEvent::Stderr(message.payload))),
+            payload_type::EXIT_CODE => {
+                let code = std::str::from_utf8(&message.payload)
+                    .ok()
+                    .and_then(|code| code.trim().parse().ok())
+                    .ok_or_else(|| invalid("invalid exit code"))?;
+                Ok(Some(SessionEvent::ExitCode(code)))
+            }
+            payload_type::HANDSHAKE_REQUEST => {
+                let response = handshake_response(&message.payload)?;
+                self.send_input(payload_type::HANDSHAKE_RESPONSE, response)
+                    .await?;
+                Ok(None)
+            }
+            // Handshake completion, flags and encryption challenges
+            _ => Ok(None),
+        }
+    }
+
+    /// Waits for the next event of the session. Returns `None` once the
+    /// session is closed, e.g. because the shell exited.
+    ///
+    /// Must be polled regularly, even if the output is not needed, as the
+    /// agent waits for acknowledgements.
+    pub async fn next_event(&mut self) -> Result<Option<SessionEvent>, Error> {
+        loop {
+            if let Some(message) = self.pending.remove(&self.output_sequence_number) {
+                self.output_sequence_number = self.output_sequence_number.saturating_add(1);
+                if let Some(event) = self.handle_output(message).await? {
+                    return Ok(Some(event));
+                }
+                continue;
+            }
+
+            if self.closed {
+                return Ok(None);
+            }
+
+            let Some(frame) = self.socket.next().await else {
+                self.closed = true;
+                continue;
+            };
+            let frame =
+                frame.map_err(|e| invalid(format!("cannot read the session stream: {e}")))?;
+
+            let data = match frame {
+                Message::Binary(data) => data,
+                Message::Close(_) => {
+                    self.closed = true;
+                    continue;
+                }
+                Message::Text(_) | Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {
+                    continue
+                }
+            };
+
+            let message = AgentMessage::decode(&data)?;
+            match message.message_type.as_str() {
+                message_type::OUTPUT_STREAM_DATA => {
+                    self.send_message(&message.acknowledgement()).await?;
+                    if message.sequence_number >= self.output_sequence_number {
+                        let _previous = self.pending.insert(message.sequence_number, message);
+                    }
+                }
+                message_type::CHANNEL_CLOSED => self.closed = true,
+                // Acknowledgements of input and publication control. Input
+                // is not resent, the WebSocket connection is reliable.
+                _ => {}
+            }
+        }
+    }
+
+    /// Closes the stream and terminates the session
+    pub async fn terminate(mut self, client: &RegionClient) -> Result<(), Error> {
+        if !self.closed {
+            // The session is terminated below either way
+            let _closed = self.socket.close(None).await;
+        }
+
+        let _output = client
+            .main
+            .ssm
+            .terminate_session()
+            .session_id(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_roundtrip() {
+        let message = AgentMessage::new(
+            message_type::INPUT_STREAM_DATA,
+            7,
+            payload_type::OUTPUT,
+            Bytes::from_static(b"ls -l\n"),
+        );
+
+        let encoded = message.encode();
+        assert_eq!(encoded.len(), 120 + 6, "header and payload");
+        assert_eq!(
+            encoded.get(4..21),
+            Some(b"input_stream_data".as_slice()),
+            "message type"
+        );
+        assert_eq!(
+            encoded.get(64..72),
+            message.message_id.as_bytes().get(8..),
+            "less significant half of the message id first"
+        );
+        assert_eq!(AgentMessage::decode(&encoded).unwrap(), message);
+    }
+
+    #[test]
+    fn corrupted_message() {
+        let mut encoded = AgentMessage::new(
+            message_type::OUTPUT_STREAM_DATA,
+            0,
+            payload_type::OUTPUT,
+            Bytes::from_static(b"hello"),
+        )
+        .encode();
+
+        assert!(
+            AgentMessage::decode(encoded.split_at(100).0).is_err(),
+            "truncated message"
+        );
+
+        if let Some(last) = encoded.last_mut() {
+            *last = b'!';
+        }
+        assert!(
+            AgentMessage::decode(&encoded).is_err(),
+            "payload does not match the digest"
+        );
+    }
+
+    #[test]
+    fn acknowledgement() {
+        let message = AgentMessage::new(
+            message_type::OUTPUT_STREAM_DATA,
+            3,
+            payload_type::OUTPUT,
+            Bytes::new(),
+        );
+        let ack = message.acknowledgement();
+
+        assert_eq!(ack.message_type, message_type::ACKNOWLEDGE);
+        assert_eq!(
+            std::str::from_utf8(&ack.payload).unwrap(),
+            format!(
+                r#"{{"AcknowledgedMessageType":"output_stream_data","AcknowledgedMessageId":"{}","AcknowledgedMessageSequenceNumber":3,"IsSequentialMessage":true}}"#,
+                message.message_id.hyphenated()
+            )
+        );
+    }
+
+    #[test]
+    fn handshake() {
+        let response = handshake_response(
+            br#"{"AgentVersion":"3.3.0.0","RequestedClientActions":[
+                {"ActionType":"SessionType","ActionParameters":{"SessionType":"Standard_Stream"}},
+                {"ActionType":"KMSEncryption","ActionParameters":{"KMSKeyId":"alias/ssm"}}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::str::from_utf8(&response).unwrap(),
+            concat!(
+                r#"{"ClientVersion":"1.2.0.0","ProcessedClientActions":["#,
+                r#"{"ActionType":"SessionType","ActionStatus":1,"Error":""},"#,
+                r#"{"ActionType":"KMSEncryption","ActionStatus":3,"Error":"KMSEncryption is not supported"}"#,
+                r#"],"Errors":[]}"#
+            )
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        fn instance() -> InstanceId {
+            InstanceId("i-0123456789abcdef0".to_owned())
+        }
+
+        #[test]
+        fn options_are_sent() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("StartSession"),
+                MockResponse::ok(r#"{"SessionId": "alice-0123", "TokenValue": "token"}"#),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let result = block_on(Session::start(
+                &client,
+                &instance(),
+                SessionOptions::default()
+                    .document("AWS-StartPortForwardingSession")
+                    .parameter("portNumber", "80")
+                    .reason("debugging"),
+            ));
+
+            assert!(matches!(
+                result,
+                Err(Error::UnexpectedNoneValue { ref entity }) if entity == "stream_url"
+            ));
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(
+                request.param("Target").as_deref(),
+                Some("i-0123456789abcdef0")
+            );
+            assert_eq!(
+                request.param("DocumentName").as_deref(),
+                Some("AWS-StartPortForwardingSession")
+            );
+            assert_eq!(
+                request
+                    .json_param::<HashMap<String, Vec<String>>>("Parameters")
+                    .unwrap(),
+                HashMap::from([("portNumber".to_owned(), vec!["80".to_owned()])])
+            );
+            assert_eq!(request.param("Reason").as_deref(), Some("debugging"));
+        }
+
+        #[test]
+        fn unreachable_target_is_an_error() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("StartSession"),
+                MockResponse::status(
+                    400,
+                    r#"{"__type": "TargetNotConnected", "Message": "not connected"}"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let error = block_on(Session::start(
+                &client,
+                &instance(),
+                SessionOptions::default(),
+            ))
+            .unwrap_err();
+
+            assert_eq!(
+                error.request_metadata().unwrap().error_code.as_deref(),
+                Some("TargetNotConnected")
+            );
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(request.param("DocumentName"), None);
+            assert_eq!(request.param("Parameters"), None, "no empty parameters");
+        }
+    }
+}
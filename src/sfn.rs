@@ -0,0 +1,452 @@
+//! Step Functions state machines and executions
+
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    arn::Arn,
+    tags::{TagKey, TagList},
+    Error, RegionClient, Timestamp,
+};
+
+arn_newtype!(StateMachineArn);
+
+arn_newtype!(ExecutionArn);
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct StateMachine {
+    arn: StateMachineArn,
+    name: String,
+    kind: String,
+    created_at: Timestamp,
+}
+
+impl TryFrom<aws_sdk_sfn::types::StateMachineListItem> for StateMachine {
+    type Error = Error;
+
+    fn try_from(item: aws_sdk_sfn::types::StateMachineListItem) -> Result<Self, Self::Error> {
+        Ok(Self {
+            arn: StateMachineArn::parse(&item.state_machine_arn)?,
+            name: item.name,
+            kind: item.r#type.as_str().to_owned(),
+            created_at: item.creation_date.try_into()?,
+        })
+    }
+}
+
+impl StateMachine {
+    pub async fn list(client: &RegionClient) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .sfn
+            .list_state_machines()
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub const fn arn(&self) -> &StateMachineArn {
+        &self.arn
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `STANDARD` or `EXPRESS`
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub const fn created_at(&self) -> &Timestamp {
+        &self.created_at
+    }
+
+    /// `input` has to be a JSON document. If `name` is `None`, a unique name
+    /// is generated.
+    pub async fn start_execution(
+        &self,
+        client: &RegionClient,
+        name: Option<String>,
+        input: Option<String>,
+    ) -> Result<ExecutionArn, Error> {
+        start_execution(client, &self.arn, name, input).await
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        tags(client, self.arn.inner()).await
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.arn.inner(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.arn.inner(), keys).await
+    }
+}
+
+pub async fn start_execution(
+    client: &RegionClient,
+    state_machine: &StateMachineArn,
+    name: Option<String>,
+    input: Option<String>,
+) -> Result<ExecutionArn, Error> {
+    Ok(ExecutionArn::parse(
+        &client
+            .main
+            .sfn
+            .start_execution()
+            .state_machine_arn(state_machine.to_string())
+            .set_name(name)
+            .set_input(input)
+            .send()
+            .await?
+            .execution_arn,
+    )?)
+}
+
+#[cfg(feature = "serde")]
+pub async fn start_execution_json<T: Serialize + Sync>(
+    client: &RegionClient,
+    state_machine: &StateMachineArn,
+    name: Option<String>,
+    input: &T,
+) -> Result<ExecutionArn, Error> {
+    let input = serde_json::to_string(input).map_err(|e| Error::InvalidPayload {
+        message: e.to_string(),
+    })?;
+    start_execution(client, state_machine, name, Some(input)).await
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    Running,
+    Succeeded,
+    Failed,
+    TimedOut,
+    Aborted,
+    PendingRedrive,
+    Unknown,
+}
+
+impl ExecutionStatus {
+    pub const fn is_terminal(self) -> bool {
+        !matches!(self, Self::Running | Self::PendingRedrive)
+    }
+}
+
+impl From<aws_sdk_sfn::types::ExecutionStatus> for ExecutionStatus {
+    fn from(status: aws_sdk_sfn::types::ExecutionStatus) -> Self {
+        use aws_sdk_sfn::types::ExecutionStatus;
+
+        match status {
+            ExecutionStatus::Running => Self::Running,
+            ExecutionStatus::Succeeded => Self::Succeeded,
+            ExecutionStatus::Failed => Self::Failed,
+            ExecutionStatus::TimedOut => Self::TimedOut,
+            ExecutionStatus::Aborted => Self::Aborted,
+            ExecutionStatus::PendingRedrive => Self::PendingRedrive,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for ExecutionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Self::Running => "RUNNING",
+                Self::Succeeded => "SUCCEEDED",
+                Self::Failed => "FAILED",
+                Self::TimedOut => "TIMED_OUT",
+                Self::Aborted => "ABORTED",
+                Self::PendingRedrive => "PENDING_REDRIVE",
+                Self::Unknown => "UNKNOWN",
+            }
+        )
+    }
+}
+
+/// The JSON output of a successful execution
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionOutput(String);
+
+impl ExecutionOutput {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn parse_json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_str(&self.0).map_err(|e| Error::InvalidPayload {
+            message: e.to_string(),
+        })
+    }
+}
+
+/// An execution that terminated without succeeding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionFailure {
+    pub status: ExecutionStatus,
+    pub error: Option<String>,
+    pub cause: Option<String>,
+}
+
+impl fmt::Display for ExecutionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "execution {}", self.status)?;
+        if let Some(ref error) = self.error {
+            write!(f, ": {error}")?;
+        }
+        if let Some(ref cause) = self.cause {
+            write!(f, " ({cause})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ExecutionFailure {}
+
+#[derive(Debug, Clone)]
+pub struct Execution {
+    arn: ExecutionArn,
+    state_machine: StateMachineArn,
+    name: Option<String>,
+    status: ExecutionStatus,
+    started_at: Timestamp,
+    stopped_at: Option<Timestamp>,
+    output: Option<String>,
+    error: Option<String>,
+    cause: Option<String>,
+}
+
+impl Execution {
+    pub async fn describe(client: &RegionClient, arn: &ExecutionArn) -> Result<Self, Error> {
+        let output = client
+            .main
+            .sfn
+            .describe_execution()
+            .execution_arn(arn.to_string())
+            .send()
+            .await?;
+
+        Ok(Self {
+            arn: ExecutionArn::parse(&output.execution_arn)?,
+            state_machine: StateMachineArn::parse(&output.state_machine_arn)?,
+            name: output.name,
+            status: output.status.into(),
+            started_at: output.start_date.try_into()?,
+            stopped_at: output.stop_date.map(TryInto::try_into).transpose()?,
+            output: output.output,
+            error: output.error,
+            cause: output.cause,
+        })
+    }
+
+    pub const fn arn(&self) -> &ExecutionArn {
+        &self.arn
+    }
+
+    pub const fn state_machine(&self) -> &StateMachineArn {
+        &self.state_machine
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub const fn status(&self) -> ExecutionStatus {
+        self.status
+    }
+
+    pub const fn started_at(&self) -> &Timestamp {
+        &self.started_at
+    }
+
+    pub const fn stopped_at(&self) -> Option<&Timestamp> {
+        self.stopped_at.as_ref()
+    }
+
+    /// `None` if the execution has not terminated yet
+    pub fn outcome(self) -> Option<Result<ExecutionOutput, ExecutionFailure>> {
+        match self.status {
+            ExecutionStatus::Succeeded => {
+                Some(Ok(ExecutionOutput(self.output.unwrap_or_default())))
+            }
+            status if status.is_terminal() => Some(Err(ExecutionFailure {
+                status,
+                error: self.error,
+                cause: self.cause,
+            })),
+            _ => None,
+        }
+    }
+
+    pub async fn history(&self, client: &RegionClient) -> Result<Vec<HistoryEvent>, Error> {
+        client
+            .main
+            .sfn
+            .get_execution_history()
+            .execution_arn(self.arn.to_string())
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+}
+
+/// Polls the execution until it terminates.
+///
+/// The outer `Result` fails on API errors or when `max_wait` is exceeded, the
+/// inner one reflects the outcome of the execution itself.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn wait_for_completion(
+    client: &RegionClient,
+    execution: &ExecutionArn,
+    poll_interval: Duration,
+    max_wait: Duration,
+) -> Result<Result<ExecutionOutput, ExecutionFailure>, Error> {
+    let start = Instant::now();
+
+    loop {
+        if let Some(outcome) = Execution::describe(client, execution).await?.outcome() {
+            return Ok(outcome);
+        }
+
+        if start.elapsed() >= max_wait {
+            return Err(Error::ExecutionExceededMaxWait {
+                execution: execution.to_string(),
+                max_wait,
+            });
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEvent {
+    pub id: i64,
+    pub previous_id: Option<i64>,
+    pub kind: String,
+    pub timestamp: Timestamp,
+}
+
+impl TryFrom<aws_sdk_sfn::types::HistoryEvent> for HistoryEvent {
+    type Error = Error;
+
+    fn try_from(event: aws_sdk_sfn::types::HistoryEvent) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: event.id,
+            previous_id: (event.previous_event_id != 0).then_some(event.previous_event_id),
+            kind: event.r#type.as_str().to_owned(),
+            timestamp: event.timestamp.try_into()?,
+        })
+    }
+}
+
+async fn tags(client: &RegionClient, arn: &Arn) -> Result<TagList, Error> {
+    Ok(client
+        .main
+        .sfn
+        .list_tags_for_resource()
+        .resource_arn(arn.to_string())
+        .send()
+        .await?
+        .tags
+        .unwrap_or_default()
+        .try_into()?)
+}
+
+async fn add_tags(client: &RegionClient, arn: &Arn, tags: TagList) -> Result<(), Error> {
+    let _output = client
+        .main
+        .sfn
+        .tag_resource()
+        .resource_arn(arn.to_string())
+        .set_tags(Some(tags.into()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+async fn remove_tags(client: &RegionClient, arn: &Arn, keys: Vec<TagKey>) -> Result<(), Error> {
+    let _output = client
+        .main
+        .sfn
+        .untag_resource()
+        .resource_arn(arn.to_string())
+        .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn execution(status: ExecutionStatus) -> Execution {
+        Execution {
+            arn: ExecutionArn::parse(
+                "arn:aws:states:eu-central-1:123456789012:execution:machine:run",
+            )
+            .unwrap(),
+            state_machine: StateMachineArn::parse(
+                "arn:aws:states:eu-central-1:123456789012:stateMachine:machine",
+            )
+            .unwrap(),
+            name: None,
+            status,
+            started_at: Timestamp::new(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()),
+            stopped_at: None,
+            output: Some("{\"ok\":true}".to_owned()),
+            error: Some("States.TaskFailed".to_owned()),
+            cause: Some("boom".to_owned()),
+        }
+    }
+
+    #[test]
+    fn execution_outcome() {
+        assert!(execution(ExecutionStatus::Running).outcome().is_none());
+
+        assert_eq!(
+            execution(ExecutionStatus::Succeeded).outcome(),
+            Some(Ok(ExecutionOutput("{\"ok\":true}".to_owned())))
+        );
+
+        assert_eq!(
+            execution(ExecutionStatus::Failed).outcome(),
+            Some(Err(ExecutionFailure {
+                status: ExecutionStatus::Failed,
+                error: Some("States.TaskFailed".to_owned()),
+                cause: Some("boom".to_owned()),
+            }))
+        );
+    }
+}
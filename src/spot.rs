@@ -0,0 +1,784 @@
+//! Spot instances, EC2 Fleet and spot prices
+//!
+//! [`request_spot_instances()`] sends one-time spot requests, which are
+//! tagged on creation. [`create_fleet()`] launches a mix of on-demand and
+//! spot capacity from a launch template, optionally spread over several
+//! instance types and subnets.
+//!
+//! An instance can watch for its own interruption with
+//! [`Imds::wait_for_spot_interruption()`](super::imds::Imds::wait_for_spot_interruption).
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    filter::Filter,
+    tags::{Tag, TagList, TagValue},
+    Ami, AvailabilityZone, Error, InstanceId, InstanceKeypairName, InstanceProfileName,
+    InstanceType, RegionClient, SecurityGroup, SubnetId, Timestamp,
+};
+
+string_newtype!(SpotInstanceRequestId);
+
+impl SpotInstanceRequestId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(FleetId);
+
+impl FleetId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(LaunchTemplateId);
+
+impl LaunchTemplateId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct SpotInstanceRequest {
+    id: SpotInstanceRequestId,
+    state: Option<String>,
+    status_code: Option<String>,
+    instance_id: Option<InstanceId>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_ec2::types::SpotInstanceRequest> for SpotInstanceRequest {
+    type Error = Error;
+
+    fn try_from(request: aws_sdk_ec2::types::SpotInstanceRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: SpotInstanceRequestId(request.spot_instance_request_id.ok_or_else(|| {
+                Error::UnexpectedNoneValue {
+                    entity: "SpotInstanceRequest.spot_instance_request_id".to_owned(),
+                }
+            })?),
+            state: request.state.map(|state| state.as_str().to_owned()),
+            status_code: request.status.and_then(|status| status.code),
+            instance_id: request.instance_id.map(InstanceId),
+            tags: request.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl SpotInstanceRequest {
+    /// Returns all spot requests matching all `filters`
+    pub async fn list(client: &RegionClient, filters: Vec<Filter>) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_spot_instance_requests()
+            .set_filters(Some(filters.into_iter().map(Into::into).collect()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub const fn id(&self) -> &SpotInstanceRequestId {
+        &self.id
+    }
+
+    /// e.g. `open`, `active`, `closed`, `cancelled`
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    /// e.g. `fulfilled`, `capacity-not-available`, `price-too-low`
+    pub fn status_code(&self) -> Option<&str> {
+        self.status_code.as_deref()
+    }
+
+    /// Set once the request is fulfilled
+    pub const fn instance_id(&self) -> Option<&InstanceId> {
+        self.instance_id.as_ref()
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    /// Cancels the request. An instance that was already launched keeps
+    /// running and has to be terminated separately.
+    pub async fn cancel(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .cancel_spot_instance_requests()
+            .spot_instance_request_ids(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_tag<T>(&self, client: &RegionClient, tag: Tag<T>) -> Result<(), Error>
+    where
+        T: fmt::Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        let _output = client
+            .main
+            .ec2
+            .create_tags()
+            .resources(self.id.as_str())
+            .tags(tag.into())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .create_tags()
+            .resources(self.id.as_str())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub struct SpotRequestConfig<'a> {
+    pub ami: &'a Ami,
+    pub instance_type: &'a InstanceType,
+    pub security_group: &'a SecurityGroup,
+    pub instance_profile_name: &'a InstanceProfileName,
+    pub instance_keypair_name: &'a InstanceKeypairName,
+    pub subnet_id: &'a SubnetId,
+    pub user_data: &'a str,
+    pub count: i32,
+    /// Maximum price per instance hour in USD, defaults to the on-demand
+    /// price
+    pub max_price: Option<&'a str>,
+    /// Set on the spot requests, not on the instances
+    pub tags: &'a TagList,
+}
+
+/// Sends one-time spot requests. The requests are fulfilled asynchronously,
+/// use [`SpotInstanceRequest::list()`] to check for the instances.
+pub async fn request_spot_instances(
+    client: &RegionClient,
+    config: SpotRequestConfig<'_>,
+) -> Result<Vec<SpotInstanceRequest>, Error> {
+    client
+        .main
+        .ec2
+        .request_spot_instances()
+        .instance_count(config.count)
+        .r#type(aws_sdk_ec2::types::SpotInstanceType::OneTime)
+        .set_spot_price(config.max_price.map(ToOwned::to_owned))
+        .launch_specification(
+            aws_sdk_ec2::types::RequestSpotLaunchSpecification::builder()
+                .image_id(config.ami.id.as_str())
+                .instance_type(config.instance_type.clone().into_inner())
+                .key_name(config.instance_keypair_name.as_str())
+                .security_group_ids(config.security_group.id.as_str())
+                .subnet_id(config.subnet_id.as_str())
+                .user_data(config.user_data)
+                .iam_instance_profile(
+                    aws_sdk_ec2::types::IamInstanceProfileSpecification::builder()
+                        .name(config.instance_profile_name.as_str())
+                        .build(),
+                )
+                .build(),
+        )
+        .tag_specifications(
+            aws_sdk_ec2::types::TagSpecification::builder()
+                .resource_type(aws_sdk_ec2::types::ResourceType::SpotInstancesRequest)
+                .set_tags(Some(config.tags.clone().into()))
+                .build(),
+        )
+        .send()
+        .await?
+        .spot_instance_requests
+        .unwrap_or_default()
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+}
+
+pub struct FleetConfig<'a> {
+    pub launch_template: &'a LaunchTemplateId,
+    /// Defaults to the default version of the template
+    pub launch_template_version: Option<&'a str>,
+    /// Overrides the instance type of the template. Every combination of
+    /// instance type and subnet is a separate launch option.
+    pub instance_types: &'a [InstanceType],
+    /// Overrides the subnet of the template
+    pub subnet_ids: &'a [SubnetId],
+    /// Total number of instances
+    pub target_capacity: i32,
+    /// How many of the instances are launched on-demand, the rest are spot
+    pub on_demand_capacity: i32,
+    pub spot_allocation_strategy: aws_sdk_ec2::types::SpotAllocationStrategy,
+    /// Set on the fleet and on all launched instances
+    pub tags: &'a TagList,
+}
+
+/// A fleet of type `instant`, whose instances are launched synchronously
+#[derive(Debug, Clone)]
+pub struct Fleet {
+    id: FleetId,
+    instances: Vec<InstanceId>,
+    errors: Vec<String>,
+}
+
+impl Fleet {
+    pub const fn id(&self) -> &FleetId {
+        &self.id
+    }
+
+    pub fn instances(&self) -> &[InstanceId] {
+        &self.instances
+    }
+
+    /// Launch errors, e.g. for instance types without capacity. The fleet
+    /// may be partially fulfilled.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// Deletes the fleet and terminates its instances
+    pub async fn delete(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .delete_fleets()
+            .fleet_ids(self.id.as_str())
+            .terminate_instances(true)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Launches an instant fleet from a launch template
+pub async fn create_fleet(client: &RegionClient, config: FleetConfig<'_>) -> Result<Fleet, Error> {
+    // `None` keeps the value of the template
+    let instance_types: Vec<Option<&InstanceType>> = if config.instance_types.is_empty() {
+        vec![None]
+    } else {
+        config.instance_types.iter().map(Some).collect()
+    };
+    let subnet_ids: Vec<Option<&SubnetId>> = if config.subnet_ids.is_empty() {
+        vec![None]
+    } else {
+        config.subnet_ids.iter().map(Some).collect()
+    };
+
+    let overrides: Vec<_> = instance_types
+        .iter()
+        .flat_map(|&instance_type| {
+            subnet_ids.iter().map(move |&subnet_id| {
+                aws_sdk_ec2::types::FleetLaunchTemplateOverridesRequest::builder()
+                    .set_instance_type(
+                        instance_type.map(|instance_type| instance_type.clone().into_inner()),
+                    )
+                    .set_subnet_id(subnet_id.map(|subnet_id| subnet_id.as_str().to_owned()))
+                    .build()
+            })
+        })
+        .collect();
+    let has_overrides = !config.instance_types.is_empty() || !config.subnet_ids.is_empty();
+
+    let tags: Vec<aws_sdk_ec2::types::Tag> = config.tags.clone().into();
+
+    let output = client
+        .main
+        .ec2
+        .create_fleet()
+        .r#type(aws_sdk_ec2::types::FleetType::Instant)
+        .launch_template_configs(
+            aws_sdk_ec2::types::FleetLaunchTemplateConfigRequest::builder()
+                .launch_template_specification(
+                    aws_sdk_ec2::types::FleetLaunchTemplateSpecificationRequest::builder()
+                        .launch_template_id(config.launch_template.as_str())
+                        .version(config.launch_template_version.unwrap_or("$Default"))
+                        .build(),
+                )
+                .set_overrides(has_overrides.then_some(overrides))
+                .build(),
+        )
+        .target_capacity_specification(
+            aws_sdk_ec2::types::TargetCapacitySpecificationRequest::builder()
+                .total_target_capacity(config.target_capacity)
+                .on_demand_target_capacity(config.on_demand_capacity)
+                .spot_target_capacity(
+                    config
+                        .target_capacity
+                        .saturating_sub(config.on_demand_capacity),
+                )
+                .default_target_capacity_type(aws_sdk_ec2::types::DefaultTargetCapacityType::Spot)
+                .build(),
+        )
+        .spot_options(
+            aws_sdk_ec2::types::SpotOptionsRequest::builder()
+                .allocation_strategy(config.spot_allocation_strategy)
+                .build(),
+        )
+        .tag_specifications(
+            aws_sdk_ec2::types::TagSpecification::builder()
+                .resource_type(aws_sdk_ec2::types::ResourceType::Fleet)
+                .set_tags(Some(tags.clone()))
+                .build(),
+        )
+        .tag_specifications(
+            aws_sdk_ec2::types::TagSpecification::builder()
+                .resource_type(aws_sdk_ec2::types::ResourceType::Instance)
+                .set_tags(Some(tags))
+                .build(),
+        )
+        .send()
+        .await?;
+
+    Ok(Fleet {
+        id: FleetId(output.fleet_id.ok_or_else(|| Error::UnexpectedNoneValue {
+            entity: "CreateFleetOutput.fleet_id".to_owned(),
+        })?),
+        instances: output
+            .instances
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|instances| instances.instance_ids.unwrap_or_default())
+            .map(InstanceId)
+            .collect(),
+        errors: output
+            .errors
+            .unwrap_or_default()
+            .into_iter()
+            .map(|error| {
+                format!(
+                    "{}: {}",
+                    error.error_code.unwrap_or_default(),
+                    error.error_message.unwrap_or_default()
+                )
+            })
+            .collect(),
+    })
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct SpotPrice {
+    pub instance_type: String,
+    pub availability_zone: AvailabilityZone,
+    /// USD per instance hour
+    pub price: String,
+    pub timestamp: Timestamp,
+}
+
+/// Linux spot prices of the instance types since `start`, newest first.
+/// Without `start`, only the current price of each type and zone is
+/// returned.
+pub async fn spot_price_history(
+    client: &RegionClient,
+    instance_types: &[InstanceType],
+    start: Option<Timestamp>,
+) -> Result<Vec<SpotPrice>, Error> {
+    client
+        .main
+        .ec2
+        .describe_spot_price_history()
+        .set_instance_types(Some(
+            instance_types
+                .iter()
+                .map(|instance_type| instance_type.clone().into_inner())
+                .collect(),
+        ))
+        .product_descriptions("Linux/UNIX")
+        .set_start_time(start.map(Into::into))
+        .into_paginator()
+        .items()
+        .send()
+        .try_collect()
+        .await?
+        .into_iter()
+        .map(|price| {
+            macro_rules! extract {
+                ($field:ident) => {
+                    price.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                        entity: concat!("SpotPrice.", stringify!($field)).to_owned(),
+                    })
+                };
+            }
+
+            Ok(SpotPrice {
+                instance_type: extract!(instance_type)?.as_str().to_owned(),
+                availability_zone: AvailabilityZone(extract!(availability_zone)?),
+                price: extract!(spot_price)?,
+                timestamp: extract!(timestamp)?.try_into()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{SpotInstanceState, SpotInstanceStatus};
+
+    use super::*;
+
+    #[test]
+    fn request_from_aws() {
+        let request = SpotInstanceRequest::try_from(
+            aws_sdk_ec2::types::SpotInstanceRequest::builder()
+                .spot_instance_request_id("sir-0123abcd")
+                .state(SpotInstanceState::Active)
+                .status(SpotInstanceStatus::builder().code("fulfilled").build())
+                .instance_id("i-0123456789abcdef0")
+                .tags(
+                    aws_sdk_ec2::types::Tag::builder()
+                        .key("team")
+                        .value("infra")
+                        .build(),
+                )
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(request.id().as_str(), "sir-0123abcd");
+        assert_eq!(request.state(), Some("active"));
+        assert_eq!(request.status_code(), Some("fulfilled"));
+        assert_eq!(
+            request.instance_id().map(InstanceId::as_str),
+            Some("i-0123456789abcdef0")
+        );
+        assert_eq!(
+            request
+                .tags()
+                .get("team".to_owned())
+                .map(|tag| tag.value().as_str()),
+            Some("infra")
+        );
+    }
+
+    #[test]
+    fn open_request_has_no_instance() {
+        let request = SpotInstanceRequest::try_from(
+            aws_sdk_ec2::types::SpotInstanceRequest::builder()
+                .spot_instance_request_id("sir-0123abcd")
+                .state(SpotInstanceState::Open)
+                .build(),
+        )
+        .unwrap();
+
+        assert_eq!(request.state(), Some("open"));
+        assert_eq!(request.status_code(), None);
+        assert_eq!(request.instance_id(), None);
+        assert!(request.tags().as_slice().is_empty());
+    }
+
+    #[test]
+    fn invalid_request_is_rejected() {
+        assert!(matches!(
+            SpotInstanceRequest::try_from(
+                aws_sdk_ec2::types::SpotInstanceRequest::builder()
+                    .state(SpotInstanceState::Open)
+                    .build()
+            ),
+            Err(Error::UnexpectedNoneValue { .. })
+        ));
+
+        assert!(matches!(
+            SpotInstanceRequest::try_from(
+                aws_sdk_ec2::types::SpotInstanceRequest::builder()
+                    .spot_instance_request_id("sir-0123abcd")
+                    .tags(aws_sdk_ec2::types::Tag::builder().key("team").build())
+                    .build()
+            ),
+            Err(Error::InvalidTags(_))
+        ));
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            tags::RawTag,
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        fn requests(next_token: Option<&str>, id: &str) -> MockResponse {
+            MockResponse::ok(format!(
+                r#"<DescribeSpotInstanceRequestsResponse xmlns="http://ec2.amazonaws.com/doc/2016-11-15/">
+      <spotInstanceRequestSet>
+        <item>
+          <spotInstanceRequestId>{id}</spotInstanceRequestId>
+          <state>active</state>
+          <status><code>fulfilled</code></status>
+          <instanceId>i-0123456789abcdef0</instanceId>
+          <tagSet>
+            <item><key>team</key><value>infra</value></item>
+          </tagSet>
+        </item>
+      </spotInstanceRequestSet>
+      {}
+    </DescribeSpotInstanceRequestsResponse>"#,
+                next_token.map_or_else(String::new, |token| format!(
+                    "<nextToken>{token}</nextToken>"
+                ))
+            ))
+        }
+
+        fn instance_type(instance_type: aws_sdk_ec2::types::InstanceType) -> InstanceType {
+            InstanceType::new(instance_type)
+        }
+
+        #[test]
+        fn list_follows_next_tokens() {
+            let http = MockHttpClient::new()
+                .on(
+                    Matcher::param("NextToken", "page2"),
+                    requests(None, "sir-2"),
+                )
+                .on(
+                    Matcher::action("DescribeSpotInstanceRequests"),
+                    requests(Some("page2"), "sir-1"),
+                );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let requests = block_on(SpotInstanceRequest::list(
+                &client,
+                vec![Filter::new("state", ["active"])],
+            ))
+            .unwrap();
+
+            assert_eq!(
+                requests
+                    .iter()
+                    .map(|request| request.id().as_str())
+                    .collect::<Vec<_>>(),
+                ["sir-1", "sir-2"]
+            );
+            let first = requests.first().unwrap();
+            assert_eq!(first.status_code(), Some("fulfilled"));
+            assert_eq!(
+                first.tags().as_slice(),
+                [RawTag::new("team".to_owned(), "infra".to_owned())]
+            );
+
+            let sent = http.requests().unwrap();
+            assert_eq!(sent.len(), 2);
+            assert!(sent.iter().all(|request| {
+                request.param("Filter.1.Name").as_deref() == Some("state")
+                    && request.param("Filter.1.Value.1").as_deref() == Some("active")
+            }));
+        }
+
+        #[test]
+        fn unknown_request_is_an_error() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeSpotInstanceRequests"),
+                MockResponse::status(
+                    400,
+                    "<Response><Errors><Error>\
+                     <Code>InvalidSpotInstanceRequestID.NotFound</Code>\
+                     <Message>not found</Message>\
+                     </Error></Errors><RequestID>8f7e9a3c</RequestID></Response>",
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http);
+
+            let error = block_on(SpotInstanceRequest::list(&client, Vec::new())).unwrap_err();
+            assert_eq!(
+                error.request_metadata().unwrap().error_code.as_deref(),
+                Some("InvalidSpotInstanceRequestID.NotFound")
+            );
+        }
+
+        #[test]
+        fn fleet_overrides_every_type_and_subnet() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("CreateFleet"),
+                MockResponse::ok(
+                    r#"<CreateFleetResponse xmlns="http://ec2.amazonaws.com/doc/2016-11-15/">
+      <fleetId>fleet-0123</fleetId>
+      <errorSet>
+        <item>
+          <errorCode>InsufficientInstanceCapacity</errorCode>
+          <errorMessage>no capacity</errorMessage>
+        </item>
+      </errorSet>
+      <fleetInstanceSet>
+        <item>
+          <instanceIds><item>i-0123456789abcdef0</item><item>i-0123456789abcdef1</item></instanceIds>
+        </item>
+      </fleetInstanceSet>
+    </CreateFleetResponse>"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+            let tags = TagList::from_vec(vec![RawTag::new("team".to_owned(), "infra".to_owned())]);
+
+            let fleet = block_on(create_fleet(
+                &client,
+                FleetConfig {
+                    launch_template: &LaunchTemplateId::new("lt-0123".to_owned()),
+                    launch_template_version: None,
+                    instance_types: &[
+                        instance_type(aws_sdk_ec2::types::InstanceType::T3Micro),
+                        instance_type(aws_sdk_ec2::types::InstanceType::T3Small),
+                    ],
+                    subnet_ids: &[SubnetId("subnet-0123".to_owned())],
+                    target_capacity: 3,
+                    on_demand_capacity: 1,
+                    spot_allocation_strategy:
+                        aws_sdk_ec2::types::SpotAllocationStrategy::PriceCapacityOptimized,
+                    tags: &tags,
+                },
+            ))
+            .unwrap();
+
+            assert_eq!(fleet.id().as_str(), "fleet-0123");
+            assert_eq!(fleet.instances().len(), 2);
+            assert_eq!(
+                fleet.errors(),
+                ["InsufficientInstanceCapacity: no capacity"]
+            );
+
+            let request = http.requests().unwrap().pop().unwrap();
+            let param = |name: &str| request.param(name);
+            assert_eq!(param("Type").as_deref(), Some("instant"));
+            assert_eq!(
+                param("LaunchTemplateConfigs.1.LaunchTemplateSpecification.Version").as_deref(),
+                Some("$Default")
+            );
+            assert_eq!(
+                [1, 2].map(|index| param(&format!(
+                    "LaunchTemplateConfigs.1.Overrides.{index}.InstanceType"
+                ))),
+                [Some("t3.micro".to_owned()), Some("t3.small".to_owned())]
+            );
+            assert_eq!(
+                param("LaunchTemplateConfigs.1.Overrides.2.SubnetId").as_deref(),
+                Some("subnet-0123")
+            );
+            assert_eq!(
+                param("TargetCapacitySpecification.SpotTargetCapacity").as_deref(),
+                Some("2")
+            );
+            assert_eq!(
+                [1, 2].map(|index| param(&format!("TagSpecification.{index}.ResourceType"))),
+                [Some("fleet".to_owned()), Some("instance".to_owned())]
+            );
+        }
+
+        #[test]
+        fn fleet_without_overrides_keeps_the_template() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("CreateFleet"),
+                MockResponse::ok(
+                    r#"<CreateFleetResponse xmlns="http://ec2.amazonaws.com/doc/2016-11-15/">
+      <fleetId>fleet-0123</fleetId>
+    </CreateFleetResponse>"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let fleet = block_on(create_fleet(
+                &client,
+                FleetConfig {
+                    launch_template: &LaunchTemplateId::new("lt-0123".to_owned()),
+                    launch_template_version: Some("3"),
+                    instance_types: &[],
+                    subnet_ids: &[],
+                    target_capacity: 1,
+                    on_demand_capacity: 0,
+                    spot_allocation_strategy:
+                        aws_sdk_ec2::types::SpotAllocationStrategy::PriceCapacityOptimized,
+                    tags: &TagList::new(),
+                },
+            ))
+            .unwrap();
+
+            assert!(fleet.instances().is_empty());
+            assert!(fleet.errors().is_empty());
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(
+                request
+                    .param("LaunchTemplateConfigs.1.LaunchTemplateSpecification.Version")
+                    .as_deref(),
+                Some("3")
+            );
+            assert_eq!(
+                request.param("LaunchTemplateConfigs.1.Overrides.1.InstanceType"),
+                None
+            );
+        }
+
+        #[test]
+        fn spot_prices() {
+            let http = MockHttpClient::new().on(
+                Matcher::action("DescribeSpotPriceHistory"),
+                MockResponse::ok(
+                    r#"<DescribeSpotPriceHistoryResponse xmlns="http://ec2.amazonaws.com/doc/2016-11-15/">
+      <spotPriceHistorySet>
+        <item>
+          <instanceType>t3.micro</instanceType>
+          <productDescription>Linux/UNIX</productDescription>
+          <spotPrice>0.0036</spotPrice>
+          <timestamp>2024-01-01T00:00:00Z</timestamp>
+          <availabilityZone>eu-central-1a</availabilityZone>
+        </item>
+      </spotPriceHistorySet>
+    </DescribeSpotPriceHistoryResponse>"#,
+                ),
+            );
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let prices = block_on(spot_price_history(
+                &client,
+                &[instance_type(aws_sdk_ec2::types::InstanceType::T3Micro)],
+                None,
+            ))
+            .unwrap();
+
+            let price = prices.first().unwrap();
+            assert_eq!(price.instance_type, "t3.micro");
+            assert_eq!(price.availability_zone.to_string(), "eu-central-1a");
+            assert_eq!(price.price, "0.0036");
+
+            let request = http.requests().unwrap().pop().unwrap();
+            assert_eq!(request.param("InstanceType.1").as_deref(), Some("t3.micro"));
+            assert_eq!(
+                request.param("ProductDescription.1").as_deref(),
+                Some("Linux/UNIX")
+            );
+            assert_eq!(request.param("StartTime"), None);
+        }
+    }
+}
@@ -0,0 +1,904 @@
+//! Sending to and consuming from SQS queues
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    time::{Duration, Instant},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{future::Future, pin::pin};
+
+#[cfg(not(target_arch = "wasm32"))]
+use futures_util::{
+    future::{self, Either},
+    stream, Stream,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    batch::batches,
+    metrics,
+    tags::{TagKey, TagList},
+    waiter::Backoff,
+    Error, RegionClient,
+};
+
+/// SQS accepts at most this many entries per batch request
+const MAX_BATCH_SIZE: usize = 10;
+
+/// Limit for the sum of the message sizes of a batch request, which is also
+/// the limit for a single message
+const MAX_BATCH_BYTES: usize = 256 * 1024;
+
+/// Lower bound for the interval of [`Queue::with_heartbeat()`], for very
+/// short visibility timeouts
+#[cfg(not(target_arch = "wasm32"))]
+const MIN_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+string_newtype!(QueueUrl);
+
+impl QueueUrl {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(MessageId);
+
+impl MessageId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptHandle(String);
+
+impl ReceiptHandle {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn duration_to_seconds(duration: Duration) -> i32 {
+    i32::try_from(duration.as_secs()).unwrap_or(i32::MAX)
+}
+
+/// Visibility is extended after half of the visibility timeout, which leaves
+/// the other half for the request to succeed
+#[cfg(not(target_arch = "wasm32"))]
+fn heartbeat_interval(visibility_timeout: Duration) -> Duration {
+    visibility_timeout
+        .checked_div(2)
+        .unwrap_or_default()
+        .max(MIN_HEARTBEAT_INTERVAL)
+}
+
+/// A message to send with [`Queue::send()`] or [`Queue::send_batch()`]
+#[derive(Debug, Clone)]
+pub struct OutgoingMessage {
+    pub body: String,
+    pub delay: Option<Duration>,
+    /// Required for FIFO queues
+    pub group_id: Option<String>,
+    pub deduplication_id: Option<String>,
+}
+
+impl OutgoingMessage {
+    pub const fn new(body: String) -> Self {
+        Self {
+            body,
+            delay: None,
+            group_id: None,
+            deduplication_id: None,
+        }
+    }
+}
+
+/// A message failed to be sent as part of a batch. `index` refers to the
+/// position of the message in the input of [`Queue::send_batch()`].
+#[derive(Debug, Clone)]
+pub struct BatchFailure {
+    pub index: usize,
+    pub code: String,
+    pub message: Option<String>,
+    pub sender_fault: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchSendResult {
+    pub sent: Vec<MessageId>,
+    pub failed: Vec<BatchFailure>,
+}
+
+/// A batch request of [`Queue::send_batch()`] failed as a whole
+///
+/// The messages before `unsent` were handled by earlier requests, with the
+/// outcome in `result`. The messages from `unsent` on may not have been sent.
+#[derive(Debug)]
+pub struct BatchSendError {
+    pub result: BatchSendResult,
+    pub unsent: usize,
+    pub error: Error,
+}
+
+impl fmt::Display for BatchSendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "batch starting at message {} failed: {}",
+            self.unsent, self.error
+        )
+    }
+}
+
+impl std::error::Error for BatchSendError {}
+
+impl From<BatchSendError> for Error {
+    fn from(value: BatchSendError) -> Self {
+        value.error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReceivedMessage {
+    id: MessageId,
+    receipt_handle: ReceiptHandle,
+    body: String,
+    received_at: Instant,
+}
+
+impl ReceivedMessage {
+    fn try_from_aws(
+        message: aws_sdk_sqs::types::Message,
+        received_at: Instant,
+    ) -> Result<Self, Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                message.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            id: MessageId(extract!(message_id)?),
+            receipt_handle: ReceiptHandle(extract!(receipt_handle)?),
+            body: message.body.unwrap_or_default(),
+            received_at,
+        })
+    }
+
+    pub const fn id(&self) -> &MessageId {
+        &self.id
+    }
+
+    pub const fn receipt_handle(&self) -> &ReceiptHandle {
+        &self.receipt_handle
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn into_body(self) -> String {
+        self.body
+    }
+
+    /// The time the message was received, or its visibility was last extended
+    pub const fn received_at(&self) -> Instant {
+        self.received_at
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Queue {
+    url: QueueUrl,
+}
+
+impl Queue {
+    pub const fn new(url: QueueUrl) -> Self {
+        Self { url }
+    }
+
+    pub async fn find_by_name(client: &RegionClient, name: &str) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .sqs
+            .get_queue_url()
+            .queue_name(name)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(Self {
+                url: QueueUrl(output.queue_url.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "GetQueueUrlOutput.queue_url".to_owned(),
+                })?),
+            })),
+            Err(e) => match e.as_service_error() {
+                Some(service_error) if service_error.is_queue_does_not_exist() => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    pub const fn url(&self) -> &QueueUrl {
+        &self.url
+    }
+
+    pub async fn send(
+        &self,
+        client: &RegionClient,
+        message: OutgoingMessage,
+    ) -> Result<MessageId, Error> {
+        Ok(MessageId(
+            client
+                .main
+                .sqs
+                .send_message()
+                .queue_url(self.url.as_str())
+                .message_body(message.body)
+                .set_delay_seconds(message.delay.map(duration_to_seconds))
+                .set_message_group_id(message.group_id)
+                .set_message_deduplication_id(message.deduplication_id)
+                .send()
+                .await?
+                .message_id
+                .ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: "SendMessageOutput.message_id".to_owned(),
+                })?,
+        ))
+    }
+
+    /// Sends all messages, split into as many requests as necessary to
+    /// stay below the entry count and size limits of a batch.
+    ///
+    /// Failures of single messages do not fail the whole operation, they are
+    /// reported in [`BatchSendResult::failed`] instead. That includes
+    /// messages that are too large to be sent at all.
+    pub async fn send_batch(
+        &self,
+        client: &RegionClient,
+        messages: Vec<OutgoingMessage>,
+    ) -> Result<BatchSendResult, BatchSendError> {
+        let mut result = BatchSendResult::default();
+
+        let (messages, too_large): (Vec<_>, Vec<_>) = messages
+            .into_iter()
+            .enumerate()
+            .partition(|entry| entry.1.body.len() <= MAX_BATCH_BYTES);
+
+        result
+            .failed
+            .extend(too_large.into_iter().map(|(index, message)| BatchFailure {
+                index,
+                code: "MessageTooLong".to_owned(),
+                message: Some(format!(
+                    "message has {} bytes, at most {MAX_BATCH_BYTES} are allowed",
+                    message.body.len()
+                )),
+                sender_fault: true,
+            }));
+
+        let sizes: Vec<usize> = messages.iter().map(|entry| entry.1.body.len()).collect();
+
+        let mut messages = messages.into_iter();
+        for batch in batches(&sizes, MAX_BATCH_SIZE, MAX_BATCH_BYTES) {
+            let batch: Vec<(usize, OutgoingMessage)> =
+                messages.by_ref().take(batch.len()).collect();
+            let first = batch.first().map_or(0, |&(index, _)| index);
+
+            if let Err(error) = self.send_entries(client, batch, &mut result).await {
+                return Err(BatchSendError {
+                    result,
+                    unsent: first,
+                    error,
+                });
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn send_entries(
+        &self,
+        client: &RegionClient,
+        batch: Vec<(usize, OutgoingMessage)>,
+        result: &mut BatchSendResult,
+    ) -> Result<(), Error> {
+        let entries = batch
+            .into_iter()
+            .map(|(index, message)| {
+                aws_sdk_sqs::types::SendMessageBatchRequestEntry::builder()
+                    .id(index.to_string())
+                    .message_body(message.body)
+                    .set_delay_seconds(message.delay.map(duration_to_seconds))
+                    .set_message_group_id(message.group_id)
+                    .set_message_deduplication_id(message.deduplication_id)
+                    .build()
+                    .expect("builder has missing fields")
+            })
+            .collect();
+
+        let output = client
+            .main
+            .sqs
+            .send_message_batch()
+            .queue_url(self.url.as_str())
+            .set_entries(Some(entries))
+            .send()
+            .await?;
+
+        result.sent.extend(
+            output
+                .successful
+                .into_iter()
+                .map(|entry| MessageId(entry.message_id)),
+        );
+
+        for failure in output.failed {
+            result.failed.push(BatchFailure {
+                index: failure
+                    .id
+                    .parse()
+                    .map_err(|e| Error::InvalidResponseError {
+                        message: format!("invalid batch entry id \"{}\": {e}", failure.id),
+                    })?,
+                code: failure.code,
+                message: failure.message,
+                sender_fault: failure.sender_fault,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Receives up to `max_messages` (at most 10) messages, waiting for up to
+    /// `wait_time` (at most 20 seconds) for messages to arrive (long polling).
+    pub async fn receive(
+        &self,
+        client: &RegionClient,
+        max_messages: i32,
+        wait_time: Duration,
+        visibility_timeout: Option<Duration>,
+    ) -> Result<Vec<ReceivedMessage>, Error> {
+        let output = client
+            .main
+            .sqs
+            .receive_message()
+            .queue_url(self.url.as_str())
+            .max_number_of_messages(max_messages)
+            .wait_time_seconds(duration_to_seconds(wait_time))
+            .set_visibility_timeout(visibility_timeout.map(duration_to_seconds))
+            .send()
+            .await?;
+
+        let received_at = Instant::now();
+
+        output
+            .messages
+            .unwrap_or_default()
+            .into_iter()
+            .map(|message| ReceivedMessage::try_from_aws(message, received_at))
+            .collect()
+    }
+
+    pub async fn delete(
+        &self,
+        client: &RegionClient,
+        message: &ReceivedMessage,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .sqs
+            .delete_message()
+            .queue_url(self.url.as_str())
+            .receipt_handle(message.receipt_handle.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Makes the message invisible for `timeout`, counting from now.
+    pub async fn change_visibility(
+        &self,
+        client: &RegionClient,
+        message: &mut ReceivedMessage,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .sqs
+            .change_message_visibility()
+            .queue_url(self.url.as_str())
+            .receipt_handle(message.receipt_handle.as_str())
+            .visibility_timeout(duration_to_seconds(timeout))
+            .send()
+            .await?;
+
+        message.received_at = Instant::now();
+
+        Ok(())
+    }
+
+    /// Runs `processing` and extends the visibility of `message` by
+    /// `visibility_timeout` every half of it until `processing` is done, so
+    /// the message does not become visible to other consumers in between.
+    ///
+    /// If the visibility cannot be extended, `processing` is cancelled and
+    /// the error is returned.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_heartbeat<F: Future>(
+        &self,
+        client: &RegionClient,
+        message: &ReceivedMessage,
+        visibility_timeout: Duration,
+        processing: F,
+    ) -> Result<F::Output, Error> {
+        let heartbeat = async {
+            let mut message = message.clone();
+            loop {
+                tokio::time::sleep(heartbeat_interval(visibility_timeout)).await;
+                if let Err(e) = self
+                    .change_visibility(client, &mut message, visibility_timeout)
+                    .await
+                {
+                    return e;
+                }
+            }
+        };
+
+        match future::select(pin!(processing), pin!(heartbeat)).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right((error, _)) => Err(error),
+        }
+    }
+
+    /// Returns all queue attributes, keyed by their attribute name (e.g.
+    /// `VisibilityTimeout`)
+    pub async fn attributes(
+        &self,
+        client: &RegionClient,
+    ) -> Result<HashMap<String, String>, Error> {
+        Ok(client
+            .main
+            .sqs
+            .get_queue_attributes()
+            .queue_url(self.url.as_str())
+            .attribute_names(aws_sdk_sqs::types::QueueAttributeName::All)
+            .send()
+            .await?
+            .attributes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| (name.as_str().to_owned(), value))
+            .collect())
+    }
+
+    pub async fn set_attributes(
+        &self,
+        client: &RegionClient,
+        attributes: HashMap<String, String>,
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .sqs
+            .set_queue_attributes()
+            .queue_url(self.url.as_str())
+            .set_attributes(Some(
+                attributes
+                    .into_iter()
+                    .map(|(name, value)| (name.as_str().into(), value))
+                    .collect(),
+            ))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .main
+            .sqs
+            .list_queue_tags()
+            .queue_url(self.url.as_str())
+            .send()
+            .await?
+            .tags
+            .unwrap_or_default()
+            .into())
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .sqs
+            .tag_queue()
+            .queue_url(self.url.as_str())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .sqs
+            .untag_queue()
+            .queue_url(self.url.as_str())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsumerConfig {
+    /// Long polling wait time for each receive call, at most 20 seconds
+    pub wait_time: Duration,
+    pub visibility_timeout: Duration,
+    /// Number of messages to fetch per receive call, at most 10
+    pub batch_size: i32,
+    /// Delay of [`Consumer::into_stream()`] after an error, growing with
+    /// each consecutive error
+    pub error_backoff: Backoff,
+    /// [`Consumer::into_stream()`] ends after this many consecutive errors
+    pub max_consecutive_errors: usize,
+}
+
+impl Default for ConsumerConfig {
+    fn default() -> Self {
+        Self {
+            wait_time: Duration::from_secs(20),
+            visibility_timeout: Duration::from_secs(30),
+            batch_size: 10,
+            error_backoff: Backoff {
+                initial: Duration::from_secs(1),
+                max: Duration::from_secs(60),
+                factor: 2,
+            },
+            max_consecutive_errors: 10,
+        }
+    }
+}
+
+/// Continuously receives messages from a queue using long polling.
+///
+/// Messages are fetched in batches and handed out one by one via
+/// [`next()`](Self::next()), or as a [`Stream`] via
+/// [`into_stream()`](Self::into_stream()). Messages that were buffered for
+/// more than half of their visibility timeout get their visibility extended
+/// before they are returned. While a message is processed,
+/// [`with_heartbeat()`](Self::with_heartbeat()) keeps extending it. Processed
+/// messages have to be acknowledged with [`ack()`](Self::ack()), otherwise
+/// they become visible again after the visibility timeout.
+///
+/// ```no_run
+/// # async fn f(client: &aws_lib::RegionClient, queue: &aws_lib::sqs::Queue) -> Result<(), aws_lib::Error> {
+/// use std::pin::pin;
+///
+/// use aws_lib::sqs::{Consumer, ConsumerConfig};
+/// use futures_util::StreamExt as _;
+///
+/// let config = ConsumerConfig::default();
+/// let timeout = config.visibility_timeout;
+/// let mut messages = pin!(Consumer::new(client, queue, config).into_stream());
+///
+/// while let Some(message) = messages.next().await {
+///     let message = message?;
+///     queue
+///         .with_heartbeat(client, &message, timeout, async {
+///             println!("{}", message.body());
+///         })
+///         .await?;
+///     queue.delete(client, &message).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Consumer<'a> {
+    client: &'a RegionClient,
+    queue: &'a Queue,
+    config: ConsumerConfig,
+    buffer: VecDeque<ReceivedMessage>,
+}
+
+impl<'a> Consumer<'a> {
+    pub const fn new(client: &'a RegionClient, queue: &'a Queue, config: ConsumerConfig) -> Self {
+        Self {
+            client,
+            queue,
+            config,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    fn needs_extension(&self, message: &ReceivedMessage) -> bool {
+        message.received_at.elapsed()
+            >= self
+                .config
+                .visibility_timeout
+                .checked_div(2)
+                .unwrap_or_default()
+    }
+
+    /// Whether the visibility timeout of a message has passed, so it may
+    /// already have been received again and its receipt handle is stale
+    fn visibility_lapsed(&self, message: &ReceivedMessage) -> bool {
+        message.received_at.elapsed() >= self.config.visibility_timeout
+    }
+
+    /// Delay before the next receive after `errors` consecutive errors
+    #[cfg(not(target_arch = "wasm32"))]
+    fn error_delay(&self, errors: usize) -> Duration {
+        let backoff = self.config.error_backoff;
+        (1..errors).fold(backoff.initial, |delay, _| backoff.next(delay))
+    }
+
+    /// Waits for and returns the next message. Empty polls are retried
+    /// transparently. If the visibility of a buffered message cannot be
+    /// extended, the error is returned. The message stays buffered if the
+    /// error is transient, otherwise or if its visibility already lapsed it
+    /// is dropped and becomes visible in the queue again.
+    pub async fn next(&mut self) -> Result<ReceivedMessage, Error> {
+        loop {
+            if let Some(mut message) = self.buffer.pop_front() {
+                if self.needs_extension(&message) {
+                    if let Err(e) = self.extend(&mut message).await {
+                        if metrics::is_transient(&e) && !self.visibility_lapsed(&message) {
+                            self.buffer.push_front(message);
+                        }
+                        return Err(e);
+                    }
+                }
+                return Ok(message);
+            }
+
+            self.buffer.extend(
+                self.queue
+                    .receive(
+                        self.client,
+                        self.config.batch_size,
+                        self.config.wait_time,
+                        Some(self.config.visibility_timeout),
+                    )
+                    .await?,
+            );
+        }
+    }
+
+    /// Returns the messages of [`next()`](Self::next()) as a stream. Errors
+    /// are passed on as items, the stream continues after them with
+    /// [`ConsumerConfig::error_backoff`]. It ends after
+    /// [`ConsumerConfig::max_consecutive_errors`] errors in a row.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn into_stream(self) -> impl Stream<Item = Result<ReceivedMessage, Error>> + 'a {
+        stream::unfold((self, 0_usize), |(mut consumer, errors)| async move {
+            if errors >= consumer.config.max_consecutive_errors {
+                return None;
+            }
+            if errors > 0 {
+                tokio::time::sleep(consumer.error_delay(errors)).await;
+            }
+
+            let message = consumer.next().await;
+            let errors = if message.is_ok() {
+                0
+            } else {
+                errors.saturating_add(1)
+            };
+            Some((message, (consumer, errors)))
+        })
+    }
+
+    /// Runs `processing` while extending the visibility of `message`, see
+    /// [`Queue::with_heartbeat()`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_heartbeat<F: Future>(
+        &self,
+        message: &ReceivedMessage,
+        processing: F,
+    ) -> Result<F::Output, Error> {
+        self.queue
+            .with_heartbeat(
+                self.client,
+                message,
+                self.config.visibility_timeout,
+                processing,
+            )
+            .await
+    }
+
+    /// Extends the visibility of a message by the configured visibility
+    /// timeout. Use this for long-running processing.
+    pub async fn extend(&self, message: &mut ReceivedMessage) -> Result<(), Error> {
+        self.queue
+            .change_visibility(self.client, message, self.config.visibility_timeout)
+            .await
+    }
+
+    /// Deletes the message from the queue after successful processing
+    pub async fn ack(&self, message: ReceivedMessage) -> Result<(), Error> {
+        self.queue.delete(self.client, &message).await
+    }
+
+    /// Makes the message visible again immediately so it can be retried
+    pub async fn nack(&self, mut message: ReceivedMessage) -> Result<(), Error> {
+        self.queue
+            .change_visibility(self.client, &mut message, Duration::ZERO)
+            .await
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use futures_util::StreamExt as _;
+
+    use super::*;
+    use crate::{
+        testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+        Region,
+    };
+
+    const MESSAGES: &str = r#"{"Messages": [
+        {"MessageId": "m1", "ReceiptHandle": "r1", "Body": "first"},
+        {"MessageId": "m2", "ReceiptHandle": "r2", "Body": "second"}
+    ]}"#;
+
+    fn queue() -> Queue {
+        Queue::new(QueueUrl::new(
+            "https://sqs.eu-central-1.amazonaws.com/123456789012/jobs".to_owned(),
+        ))
+    }
+
+    fn failure() -> MockResponse {
+        MockResponse::status(
+            400,
+            r#"{"__type": "com.amazonaws.sqs#InvalidAddress", "message": "invalid"}"#,
+        )
+    }
+
+    #[test]
+    fn buffer_is_drained() {
+        let http = MockHttpClient::new()
+            .once(
+                Matcher::action("ReceiveMessage"),
+                MockResponse::ok(MESSAGES),
+            )
+            .on(Matcher::action("ReceiveMessage"), MockResponse::ok("{}"));
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+        let queue = queue();
+        let mut consumer = Consumer::new(&client, &queue, ConsumerConfig::default());
+
+        let bodies = block_on(async {
+            vec![
+                consumer.next().await.unwrap().body().to_owned(),
+                consumer.next().await.unwrap().body().to_owned(),
+            ]
+        });
+
+        assert_eq!(bodies, vec!["first", "second"]);
+        assert_eq!(
+            http.requests_matching(&Matcher::action("ReceiveMessage"))
+                .unwrap()
+                .len(),
+            1,
+            "both come from one receive"
+        );
+    }
+
+    fn buffered(body: &str, received_ago: Duration) -> ReceivedMessage {
+        ReceivedMessage {
+            id: MessageId(body.to_owned()),
+            receipt_handle: ReceiptHandle(body.to_owned()),
+            body: body.to_owned(),
+            received_at: Instant::now().checked_sub(received_ago).unwrap(),
+        }
+    }
+
+    /// Fails the extension of the first of two messages buffered
+    /// `received_ago` with `response`, and returns the body of the message
+    /// returned after the error
+    fn next_after_failed_extension(response: MockResponse, received_ago: Duration) -> String {
+        let http = MockHttpClient::new()
+            .once(Matcher::action("ChangeMessageVisibility"), response)
+            .on(
+                Matcher::action("ChangeMessageVisibility"),
+                MockResponse::ok("{}"),
+            );
+        let client = mock_region_client(Region::EuCentral1, http);
+        let queue = queue();
+        let config = ConsumerConfig {
+            visibility_timeout: Duration::from_secs(60),
+            ..ConsumerConfig::default()
+        };
+        let mut consumer = Consumer::new(&client, &queue, config);
+        consumer.buffer.extend([
+            buffered("first", received_ago),
+            buffered("second", received_ago),
+        ]);
+
+        let (failed, next) = block_on(async { (consumer.next().await, consumer.next().await) });
+
+        assert!(failed.is_err(), "the failed extension is returned");
+        next.unwrap().into_body()
+    }
+
+    #[test]
+    fn message_is_kept_when_extension_fails_transiently() {
+        let body = next_after_failed_extension(
+            MockResponse::status(500, r#"{"__type": "InternalError"}"#),
+            Duration::from_secs(40),
+        );
+
+        assert_eq!(body, "first");
+    }
+
+    #[test]
+    fn message_is_dropped_when_extension_is_rejected() {
+        let body = next_after_failed_extension(failure(), Duration::from_secs(40));
+
+        assert_eq!(body, "second");
+    }
+
+    #[test]
+    fn message_is_dropped_when_visibility_lapsed() {
+        let body = next_after_failed_extension(
+            MockResponse::status(500, r#"{"__type": "InternalError"}"#),
+            Duration::from_secs(90),
+        );
+
+        assert_eq!(body, "second", "the message may be received again already");
+    }
+
+    #[test]
+    fn stream_backs_off_and_ends_after_errors() {
+        let http = MockHttpClient::new().on(Matcher::action("ReceiveMessage"), failure());
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+        let queue = queue();
+        let config = ConsumerConfig {
+            error_backoff: Backoff {
+                initial: Duration::from_millis(20),
+                max: Duration::from_secs(1),
+                factor: 2,
+            },
+            max_consecutive_errors: 3,
+            ..ConsumerConfig::default()
+        };
+
+        let start = Instant::now();
+        let results = block_on(
+            Consumer::new(&client, &queue, config)
+                .into_stream()
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(Result::is_err), "all receives failed");
+        assert_eq!(
+            http.requests_matching(&Matcher::action("ReceiveMessage"))
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(
+            // 20ms after the first error, 40ms after the second
+            start.elapsed() >= Duration::from_millis(60),
+            "waited {:?}",
+            start.elapsed()
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! SSM Parameter Store
+//!
+//! Besides reading and writing single parameters, whole configuration structs
+//! can be loaded from a parameter path via the [`SsmConfig`] derive macro.
+//! Each field maps to the parameter `<prefix>/<field name>`, and the values
+//! are parsed the same way as tag values:
+//!
+//! ```rust
+//! # use aws_lib::ssm::SsmConfig;
+//! #[derive(SsmConfig)]
+//! struct DatabaseConfig {
+//!     host: String,
+//!     #[ssm(name = "use-tls")]
+//!     tls: bool,
+//!     replica_host: Option<String>,
+//! }
+//! ```
+//!
+//! `DatabaseConfig` can then be loaded with [`load_config()`].
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use aws_macros::SsmConfig;
+pub use aws_sdk_ssm::types::ParameterType;
+
+use super::{
+    tags::{ParseTagValueError, TagKey, TagList},
+    Error, RegionClient,
+};
+
+/// `GetParameters` accepts at most this many names per request
+const MAX_GET_PARAMETERS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub enum ParseConfigError {
+    MissingParameter {
+        name: String,
+    },
+    InvalidParameter {
+        name: String,
+        inner: ParseTagValueError,
+    },
+}
+
+impl std::error::Error for ParseConfigError {}
+
+impl fmt::Display for ParseConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::MissingParameter { ref name } => write!(f, "parameter \"{name}\" not found"),
+            Self::InvalidParameter {
+                ref name,
+                ref inner,
+            } => write!(f, "invalid value for parameter \"{name}\": {inner}"),
+        }
+    }
+}
+
+/// A struct that can be loaded from the parameters below a path. Usually
+/// derived via [`SsmConfig`].
+pub trait SsmConfig: Sized {
+    /// The full names of all parameters the struct is built from
+    fn parameter_names(prefix: &str) -> Vec<String>;
+    fn from_parameters(prefix: &str, parameters: &[Parameter]) -> Result<Self, ParseConfigError>;
+}
+
+/// Joins a path prefix and a parameter name
+pub fn parameter_path(prefix: &str, name: &str) -> String {
+    format!("{}/{name}", prefix.trim_end_matches('/'))
+}
+
+string_newtype!(ParameterName);
+
+impl ParameterName {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<String> for ParameterName {
+    fn eq(&self, other: &String) -> bool {
+        &self.0 == other
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    name: ParameterName,
+    value: String,
+    version: i64,
+    secure: bool,
+}
+
+impl TryFrom<aws_sdk_ssm::types::Parameter> for Parameter {
+    type Error = Error;
+
+    fn try_from(parameter: aws_sdk_ssm::types::Parameter) -> Result<Self, Self::Error> {
+        macro_rules! extract {
+            ($field:ident) => {
+                parameter.$field.ok_or_else(|| Error::UnexpectedNoneValue {
+                    entity: stringify!($field).to_owned(),
+                })
+            };
+        }
+
+        Ok(Self {
+            secure: parameter.r#type == Some(ParameterType::SecureString),
+            name: ParameterName(extract!(name)?),
+            value: extract!(value)?,
+            version: parameter.version,
+        })
+    }
+}
+
+impl Parameter {
+    pub async fn get(
+        client: &RegionClient,
+        name: &ParameterName,
+        with_decryption: bool,
+    ) -> Result<Option<Self>, Error> {
+        match client
+            .main
+            .ssm
+            .get_parameter()
+            .name(name.as_str())
+            .with_decryption(with_decryption)
+            .send()
+            .await
+        {
+            Ok(output) => output.parameter.map(TryInto::try_into).transpose(),
+            Err(e) => match e.as_service_error() {
+                Some(service_error) if service_error.is_parameter_not_found() => Ok(None),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    /// Returns all parameters that exist. Names that do not exist are
+    /// ignored.
+    pub async fn get_many(
+        client: &RegionClient,
+        names: &[ParameterName],
+        with_decryption: bool,
+    ) -> Result<Vec<Self>, Error> {
+        let mut parameters = Vec::with_capacity(names.len());
+
+        for chunk in names.chunks(MAX_GET_PARAMETERS) {
+            for parameter in client
+                .main
+                .ssm
+                .get_parameters()
+                .set_names(Some(chunk.iter().map(|name| name.0.clone()).collect()))
+                .with_decryption(with_decryption)
+                .send()
+                .await?
+                .parameters
+                .unwrap_or_default()
+            {
+                parameters.push(parameter.try_into()?);
+            }
+        }
+
+        Ok(parameters)
+    }
+
+    pub async fn get_by_path(
+        client: &RegionClient,
+        path: &str,
+        recursive: bool,
+        with_decryption: bool,
+    ) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ssm
+            .get_parameters_by_path()
+            .path(path)
+            .recursive(recursive)
+            .with_decryption(with_decryption)
+            .into_paginator()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .flat_map(|page| page.parameters.unwrap_or_default())
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Creates or (if `overwrite` is set) updates a parameter, returning the
+    /// new version. Use [`add_tags()`](Self::add_tags()) to tag existing
+    /// parameters, as tags can only be set here when creating a parameter.
+    pub async fn put(
+        client: &RegionClient,
+        name: &ParameterName,
+        value: String,
+        parameter_type: ParameterType,
+        overwrite: bool,
+        tags: Option<TagList>,
+    ) -> Result<i64, Error> {
+        Ok(client
+            .main
+            .ssm
+            .put_parameter()
+            .name(name.as_str())
+            .value(value)
+            .r#type(parameter_type)
+            .overwrite(overwrite)
+            .set_tags(tags.map(Into::into))
+            .send()
+            .await?
+            .version)
+    }
+
+    pub const fn name(&self) -> &ParameterName {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub const fn version(&self) -> i64 {
+        self.version
+    }
+
+    pub const fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    pub async fn tags(&self, client: &RegionClient) -> Result<TagList, Error> {
+        Ok(client
+            .main
+            .ssm
+            .list_tags_for_resource()
+            .resource_type(aws_sdk_ssm::types::ResourceTypeForTagging::Parameter)
+            .resource_id(self.name.as_str())
+            .send()
+            .await?
+            .tag_list
+            .unwrap_or_default()
+            .try_into()?)
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ssm
+            .add_tags_to_resource()
+            .resource_type(aws_sdk_ssm::types::ResourceTypeForTagging::Parameter)
+            .resource_id(self.name.as_str())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ssm
+            .remove_tags_from_resource()
+            .resource_type(aws_sdk_ssm::types::ResourceTypeForTagging::Parameter)
+            .resource_id(self.name.as_str())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Loads a configuration struct from the parameters below `prefix`.
+/// `SecureString` parameters are decrypted.
+pub async fn load_config<T: SsmConfig>(client: &RegionClient, prefix: &str) -> Result<T, Error> {
+    let names: Vec<ParameterName> = T::parameter_names(prefix)
+        .into_iter()
+        .map(ParameterName)
+        .collect();
+
+    let parameters = Parameter::get_many(client, &names, true).await?;
+
+    Ok(T::from_parameters(prefix, &parameters)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameter(name: &str, value: &str) -> Parameter {
+        Parameter {
+            name: ParameterName(name.to_owned()),
+            value: value.to_owned(),
+            version: 1,
+            secure: false,
+        }
+    }
+
+    #[test]
+    fn derive_config() {
+        #[derive(SsmConfig, Debug)]
+        struct MyConfig {
+            host: String,
+            #[ssm(name = "use-tls")]
+            tls: bool,
+            port: Option<String>,
+        }
+
+        assert_eq!(
+            MyConfig::parameter_names("/app/"),
+            vec!["/app/host", "/app/use-tls", "/app/port"]
+        );
+
+        let config = MyConfig::from_parameters(
+            "/app",
+            &[
+                parameter("/app/host", "db"),
+                parameter("/app/use-tls", "true"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.host, "db");
+        assert!(config.tls);
+        assert!(config.port.is_none());
+
+        assert!(matches!(
+            MyConfig::from_parameters("/app", &[parameter("/app/host", "db")]),
+            Err(ParseConfigError::MissingParameter { .. })
+        ));
+
+        assert!(matches!(
+            MyConfig::from_parameters(
+                "/app",
+                &[
+                    parameter("/app/host", "db"),
+                    parameter("/app/use-tls", "yes")
+                ]
+            ),
+            Err(ParseConfigError::InvalidParameter { .. })
+        ));
+    }
+}
@@ -0,0 +1,905 @@
+//! Assuming roles with STS, and running operations across many accounts
+//!
+//! [`multi_account()`] assumes a role of the same name in every account and
+//! passes an [`AccountClients`] to the operation, which hands out clients
+//! with the credentials of that account:
+//!
+//! ```no_run
+//! # async fn f(client: &aws_lib::RegionClient) -> Result<(), aws_lib::Error> {
+//! use aws_lib::{
+//!     organizations::AccountId,
+//!     sts::{multi_account, MultiAccountConfig},
+//!     Instance, Region,
+//! };
+//!
+//! let accounts = [
+//!     AccountId::new("111111111111".to_owned()),
+//!     AccountId::new("222222222222".to_owned()),
+//! ];
+//!
+//! let report = multi_account(
+//!     client,
+//!     &accounts,
+//!     &MultiAccountConfig::new("inventory"),
+//!     |clients| async move {
+//!         let client = clients.client(Region::EuCentral1);
+//!         Ok(Instance::list(&client, vec![]).await?.len())
+//!     },
+//! )
+//! .await;
+//!
+//! for (account, count) in report.succeeded() {
+//!     println!("{account}: {count} instances");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`assume_role_with()`] supports external IDs, MFA, session tags and
+//! session durations. [`AssumeRoleProvider`] assumes the role of a profile
+//! with `mfa_serial`, which the SDK does not support.
+//!
+//! An [`AccountGuard`] makes sure that tooling only runs in the accounts it
+//! is meant for.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    io::{self, Write as _},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use aws_credential_types::provider::{error::CredentialsError, future, ProvideCredentials};
+use aws_sdk_ec2::config::{Credentials, SharedCredentialsProvider};
+use futures_util::stream::{FuturesUnordered, StreamExt as _};
+
+use super::{
+    arn::Arn,
+    organizations::AccountId,
+    partition::Partition,
+    profile::{Profile, ProfileSet},
+    tags::TagList,
+    ConfigOverride, Error, Region, RegionClient, Timestamp,
+};
+
+/// Assumed credentials are refreshed this long before they expire, so that
+/// requests in flight do not fail
+const CREDENTIALS_REFRESH_BEFORE_EXPIRY: Duration = Duration::from_secs(5 * 60);
+
+/// Temporary credentials of an assumed role
+#[derive(Clone)]
+pub struct AssumedRole {
+    role: Arn,
+    credentials: SharedCredentialsProvider,
+    expiration: Timestamp,
+}
+
+// Do not leak the credentials into logs
+impl fmt::Debug for AssumedRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssumedRole")
+            .field("role", &self.role)
+            .field("expiration", &self.expiration)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AssumedRole {
+    pub const fn role(&self) -> &Arn {
+        &self.role
+    }
+
+    /// The credentials are not refreshed, clients using them fail after
+    /// this point in time
+    pub const fn expiration(&self) -> &Timestamp {
+        &self.expiration
+    }
+
+    pub fn credentials(&self) -> SharedCredentialsProvider {
+        self.credentials.clone()
+    }
+
+    /// Returns a copy of `client` that uses the credentials of the role,
+    /// optionally in another region
+    pub fn client(&self, client: &RegionClient, region: Option<Region>) -> RegionClient {
+        client.with_config(&ConfigOverride {
+            region,
+            credentials: Some(self.credentials()),
+            retry: None,
+        })
+    }
+}
+
+/// The future returned by [`MfaTokenProvider::token()`]
+pub type MfaTokenFuture<'a> = Pin<Box<dyn Future<Output = Result<String, Error>> + Send + 'a>>;
+
+/// Asks for the current code of an MFA device when assuming a role that
+/// requires MFA
+///
+/// The future is polled on the async runtime. Implementations that wait for
+/// user input must not block while doing so, see [`TerminalMfaPrompt`].
+pub trait MfaTokenProvider: fmt::Debug + Send + Sync {
+    /// `serial` is the ARN (or serial number) of the device
+    fn token<'a>(&'a self, serial: &'a str) -> MfaTokenFuture<'a>;
+}
+
+/// Prompts on stderr and reads the code from stdin, like the AWS CLI
+///
+/// The terminal is read on the blocking thread pool of tokio, so the
+/// runtime keeps running while the prompt waits for input.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalMfaPrompt;
+
+impl TerminalMfaPrompt {
+    fn prompt(serial: &str) -> Result<String, Error> {
+        let io_error = |e: io::Error| Error::MfaTokenUnavailable {
+            serial: serial.to_owned(),
+            message: e.to_string(),
+        };
+
+        let mut stderr = io::stderr();
+        write!(stderr, "Enter MFA code for {serial}: ").map_err(io_error)?;
+        stderr.flush().map_err(io_error)?;
+
+        let mut token = String::new();
+        let _read = io::stdin().read_line(&mut token).map_err(io_error)?;
+        Ok(token.trim().to_owned())
+    }
+}
+
+impl MfaTokenProvider for TerminalMfaPrompt {
+    fn token<'a>(&'a self, serial: &'a str) -> MfaTokenFuture<'a> {
+        Box::pin(async move {
+            let device = serial.to_owned();
+            match tokio::task::spawn_blocking(move || Self::prompt(&device)).await {
+                Ok(token) => token,
+                Err(e) => Err(Error::MfaTokenUnavailable {
+                    serial: serial.to_owned(),
+                    message: e.to_string(),
+                }),
+            }
+        })
+    }
+}
+
+/// Optional parameters of `AssumeRole`
+#[derive(Debug, Clone)]
+pub struct AssumeRoleOptions {
+    /// Shows up in CloudTrail as the name of the session and must match
+    /// `[\w+=,.@-]{2,64}`
+    pub session_name: String,
+    /// Required by roles that are meant to be assumed by third parties
+    pub external_id: Option<String>,
+    /// The MFA device whose code is sent along, see [`MfaTokenProvider`]
+    pub mfa_serial: Option<String>,
+    /// Defaults to one hour. May not exceed the maximum session duration of
+    /// the role.
+    pub duration: Option<Duration>,
+    /// Tags of the session, which can be used in the policies of the role
+    pub session_tags: TagList,
+}
+
+impl AssumeRoleOptions {
+    pub const fn new(session_name: String) -> Self {
+        Self {
+            session_name,
+            external_id: None,
+            mfa_serial: None,
+            duration: None,
+            session_tags: TagList::new(),
+        }
+    }
+
+    /// Takes `role_session_name`, `external_id`, `mfa_serial` and
+    /// `duration_seconds` of the profile. Profiles without
+    /// `role_session_name` get a session name with the current time, like in
+    /// the SDKs.
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            session_name: profile.role_session_name().map_or_else(
+                || format!("aws-lib-{}", Timestamp::now().inner().timestamp_millis()),
+                ToOwned::to_owned,
+            ),
+            external_id: profile.external_id().map(ToOwned::to_owned),
+            mfa_serial: profile.mfa_serial().map(ToOwned::to_owned),
+            duration: profile
+                .duration_seconds()
+                .map(|seconds| Duration::from_secs(seconds.into())),
+            session_tags: TagList::new(),
+        }
+    }
+}
+
+async fn send_assume_role(
+    sts: &aws_sdk_sts::Client,
+    role: &Arn,
+    options: &AssumeRoleOptions,
+    mfa: Option<&dyn MfaTokenProvider>,
+) -> Result<(Credentials, Timestamp), Error> {
+    let token_code = match (options.mfa_serial.as_deref(), mfa) {
+        (Some(serial), Some(mfa)) => Some(mfa.token(serial).await?),
+        (Some(serial), None) => {
+            return Err(Error::MfaTokenUnavailable {
+                serial: serial.to_owned(),
+                message: "no MFA token provider given".to_owned(),
+            })
+        }
+        (None, _) => None,
+    };
+
+    let duration_seconds = options
+        .duration
+        .map(|duration| i32::try_from(duration.as_secs()).unwrap_or(i32::MAX));
+
+    let output = sts
+        .assume_role()
+        .role_arn(role.to_string())
+        .role_session_name(&options.session_name)
+        .set_external_id(options.external_id.clone())
+        .set_serial_number(options.mfa_serial.clone())
+        .set_token_code(token_code)
+        .set_duration_seconds(duration_seconds)
+        .set_tags(
+            (!options.session_tags.as_slice().is_empty())
+                .then(|| options.session_tags.clone().into()),
+        )
+        .send()
+        .await?;
+
+    let credentials = output
+        .credentials
+        .ok_or_else(|| Error::UnexpectedNoneValue {
+            entity: "AssumeRoleResponse.Credentials".to_owned(),
+        })?;
+
+    let expiration = Timestamp::try_from(credentials.expiration)?;
+
+    Ok((
+        Credentials::new(
+            credentials.access_key_id,
+            credentials.secret_access_key,
+            Some(credentials.session_token),
+            Some(SystemTime::from(*expiration.inner())),
+            "AssumeRole",
+        ),
+        expiration,
+    ))
+}
+
+/// Assumes `role` with the credentials of `client`
+///
+/// `session_name` shows up in CloudTrail as the name of the session and must
+/// match `[\w+=,.@-]{2,64}`.
+pub async fn assume_role(
+    client: &RegionClient,
+    role: &Arn,
+    session_name: &str,
+) -> Result<AssumedRole, Error> {
+    assume_role_with(
+        client,
+        role,
+        &AssumeRoleOptions::new(session_name.to_owned()),
+        None,
+    )
+    .await
+}
+
+/// Like [`assume_role()`], with an external ID, MFA, session tags or a
+/// session duration. `mfa` is required if [`AssumeRoleOptions::mfa_serial`]
+/// is set.
+pub async fn assume_role_with(
+    client: &RegionClient,
+    role: &Arn,
+    options: &AssumeRoleOptions,
+    mfa: Option<&dyn MfaTokenProvider>,
+) -> Result<AssumedRole, Error> {
+    let (credentials, expiration) = send_assume_role(&client.main.sts, role, options, mfa).await?;
+
+    Ok(AssumedRole {
+        role: role.clone(),
+        credentials: SharedCredentialsProvider::new(credentials),
+        expiration,
+    })
+}
+
+/// Credentials of a role assumed with the credentials of another profile,
+/// for use as the credentials provider of all clients
+///
+/// The SDK cannot assume roles that require MFA on its own.
+/// [`ClientBuilder`](crate::config::ClientBuilder) uses this provider for
+/// profiles with `mfa_serial`. The credentials are shared by all clients and
+/// only refreshed shortly before they expire, so the MFA code is only asked
+/// for once per session. Requests that need credentials while the code is
+/// being entered wait for it without blocking the runtime.
+#[derive(Debug)]
+pub struct AssumeRoleProvider {
+    role: Arn,
+    options: AssumeRoleOptions,
+    /// Roles assumed in order before `role`, for chained source profiles
+    via: Vec<(Arn, AssumeRoleOptions)>,
+    source: SourceCredentials,
+    region: Region,
+    mfa: Arc<dyn MfaTokenProvider>,
+    cached: futures_util::lock::Mutex<Option<Credentials>>,
+}
+
+/// The credentials the first role of an [`AssumeRoleProvider`] is assumed
+/// with
+#[derive(Debug)]
+enum SourceCredentials {
+    Static(Credentials),
+    /// Loaded by the SDK, for the given profile or with the default
+    /// credentials chain
+    Sdk(Option<String>),
+}
+
+impl AssumeRoleProvider {
+    /// Assumes `role` with the credentials of `source_profile`, or the
+    /// default credentials chain if `None`, calling STS in `region`
+    pub fn new(
+        role: Arn,
+        options: AssumeRoleOptions,
+        source_profile: Option<String>,
+        region: Region,
+        mfa: Arc<dyn MfaTokenProvider>,
+    ) -> Self {
+        Self {
+            role,
+            options,
+            via: Vec::new(),
+            source: SourceCredentials::Sdk(source_profile),
+            region,
+            mfa,
+            cached: futures_util::lock::Mutex::new(None),
+        }
+    }
+
+    /// The provider for the `role_arn` of the profile `name`, or `None` if
+    /// the profile does not assume a role
+    ///
+    /// Follows the [source chain](ProfileSet::source_chain) of the profile:
+    /// the static credentials at its end are used directly, and the roles of
+    /// all profiles in between are assumed in order. Any other credentials
+    /// at the end of the chain, e.g. SSO, are left to the SDK.
+    pub fn from_profile(
+        profiles: &ProfileSet,
+        name: &str,
+        region: Region,
+        mfa: Arc<dyn MfaTokenProvider>,
+    ) -> Result<Option<Self>, Error> {
+        let chain = profiles.source_chain(name)?;
+        let (Some(&target), Some(&base)) = (chain.first(), chain.last()) else {
+            return Ok(None);
+        };
+        let Some(role) = target.role_arn() else {
+            return Ok(None);
+        };
+
+        let source = match base.static_credentials() {
+            Some(credentials) => SourceCredentials::Static(Credentials::new(
+                credentials.access_key_id.clone(),
+                credentials.secret_access_key.clone(),
+                credentials.session_token.clone(),
+                None,
+                "ProfileFile",
+            )),
+            // A role at the end of the chain uses its `credential_source`,
+            // which the default credentials chain covers
+            None if base.role_arn().is_some() => SourceCredentials::Sdk(None),
+            None => SourceCredentials::Sdk(Some(base.name().to_owned())),
+        };
+
+        let via = chain
+            .iter()
+            .skip(1)
+            .rev()
+            .filter_map(|profile| {
+                profile.role_arn().map(|role| {
+                    Ok::<_, Error>((Arn::parse(role)?, AssumeRoleOptions::from_profile(profile)))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(Self {
+            via,
+            source,
+            ..Self::new(
+                Arn::parse(role)?,
+                AssumeRoleOptions::from_profile(target),
+                None,
+                region,
+                mfa,
+            )
+        }))
+    }
+
+    async fn credentials(&self) -> Result<Credentials, Error> {
+        let mut cached = self.cached.lock().await;
+
+        if let Some(ref credentials) = *cached {
+            let valid = credentials.expiry().map_or(true, |expiry| {
+                SystemTime::now()
+                    .checked_add(CREDENTIALS_REFRESH_BEFORE_EXPIRY)
+                    .is_some_and(|refresh_at| refresh_at < expiry)
+            });
+            if valid {
+                return Ok(credentials.clone());
+            }
+        }
+
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(aws_config::Region::new(self.region.as_str()));
+        match self.source {
+            SourceCredentials::Static(ref credentials) => {
+                loader = loader.credentials_provider(credentials.clone());
+            }
+            SourceCredentials::Sdk(Some(ref profile)) => loader = loader.profile_name(profile),
+            SourceCredentials::Sdk(None) => {}
+        }
+        let mut config = loader.load().await;
+
+        for hop in &self.via {
+            let (ref role, ref options) = *hop;
+            let (credentials, _expiration) = send_assume_role(
+                &aws_sdk_sts::Client::new(&config),
+                role,
+                options,
+                Some(self.mfa.as_ref()),
+            )
+            .await?;
+            config = config
+                .to_builder()
+                .credentials_provider(SharedCredentialsProvider::new(credentials))
+                .build();
+        }
+
+        let (credentials, _expiration) = send_assume_role(
+            &aws_sdk_sts::Client::new(&config),
+            &self.role,
+            &self.options,
+            Some(self.mfa.as_ref()),
+        )
+        .await?;
+
+        *cached = Some(credentials.clone());
+        drop(cached);
+        Ok(credentials)
+    }
+}
+
+impl ProvideCredentials for AssumeRoleProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(async move {
+            self.credentials()
+                .await
+                .map_err(|e| CredentialsError::provider_error(e.to_string()))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MultiAccountConfig {
+    /// The name of the role assumed in every account, without path
+    pub role_name: String,
+    pub session_name: String,
+    /// Maximum number of accounts processed at once
+    pub concurrency: usize,
+}
+
+impl MultiAccountConfig {
+    pub fn new(role_name: impl Into<String>) -> Self {
+        Self {
+            role_name: role_name.into(),
+            session_name: "aws-lib".to_owned(),
+            concurrency: 8,
+        }
+    }
+}
+
+/// Hands out clients for a single account, see [`multi_account()`]
+#[derive(Debug, Clone)]
+pub struct AccountClients<'a> {
+    account: AccountId,
+    role: AssumedRole,
+    base: &'a RegionClient,
+}
+
+impl AccountClients<'_> {
+    pub const fn account(&self) -> &AccountId {
+        &self.account
+    }
+
+    pub const fn role(&self) -> &AssumedRole {
+        &self.role
+    }
+
+    /// A client for `region` in the account. Each call builds a new client,
+    /// so reuse it for all calls to the same region.
+    pub fn client(&self, region: Region) -> RegionClient {
+        self.role.client(self.base, Some(region))
+    }
+}
+
+/// The result of [`multi_account()`], in the order of the given accounts
+#[derive(Debug)]
+pub struct MultiAccountReport<T> {
+    pub results: Vec<(AccountId, Result<T, Error>)>,
+}
+
+impl<T> MultiAccountReport<T> {
+    pub fn succeeded(&self) -> impl Iterator<Item = (&AccountId, &T)> {
+        self.results
+            .iter()
+            .filter_map(|entry| entry.1.as_ref().ok().map(|value| (&entry.0, value)))
+    }
+
+    /// Includes accounts where the role could not be assumed
+    pub fn failed(&self) -> impl Iterator<Item = (&AccountId, &Error)> {
+        self.results
+            .iter()
+            .filter_map(|entry| entry.1.as_ref().err().map(|e| (&entry.0, e)))
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|entry| entry.1.is_ok())
+    }
+}
+
+/// Assumes the role of `config` in each of `accounts` with the credentials
+/// of `client`, and runs `operation` for each account
+///
+/// Failing to assume the role or a failing operation only fail that
+/// account, all other accounts are still processed.
+pub async fn multi_account<'a, T, F, Fut>(
+    client: &'a RegionClient,
+    accounts: &[AccountId],
+    config: &MultiAccountConfig,
+    operation: F,
+) -> MultiAccountReport<T>
+where
+    F: Fn(AccountClients<'a>) -> Fut + Sync,
+    Fut: Future<Output = Result<T, Error>> + Send,
+    T: Send,
+{
+    let partition = client.region.partition();
+    let operation = &operation;
+
+    let run = |index: usize, account: AccountId| async move {
+        let result = async {
+            let role = Arn::builder()
+                .partition(partition.as_str())
+                .service("iam")
+                .account(account.as_str())
+                .resource(format!("role/{}", config.role_name))
+                .build()?;
+
+            let role = assume_role(client, &role, &config.session_name).await?;
+
+            operation(AccountClients {
+                account: account.clone(),
+                role,
+                base: client,
+            })
+            .await
+        }
+        .await;
+
+        (index, account, result)
+    };
+
+    let mut pending = accounts.iter().cloned().enumerate();
+    let mut in_flight = FuturesUnordered::new();
+    let mut results = vec![];
+
+    loop {
+        while in_flight.len() < config.concurrency.max(1) {
+            match pending.next() {
+                Some((index, account)) => in_flight.push(run(index, account)),
+                None => break,
+            }
+        }
+
+        match in_flight.next().await {
+            Some(result) => results.push(result),
+            None => break,
+        }
+    }
+
+    results.sort_by_key(|&(index, _, _)| index);
+
+    MultiAccountReport {
+        results: results
+            .into_iter()
+            .map(|(_, account, result)| (account, result))
+            .collect(),
+    }
+}
+
+/// The result of `GetCallerIdentity`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallerIdentity {
+    pub account: AccountId,
+    /// The user or the assumed role session
+    pub arn: Arn,
+    pub user_id: String,
+}
+
+/// Returns the identity of the credentials of `client`
+pub async fn caller_identity(client: &RegionClient) -> Result<CallerIdentity, Error> {
+    identity_of(&client.main.sts).await
+}
+
+pub(crate) async fn identity_of(sts: &aws_sdk_sts::Client) -> Result<CallerIdentity, Error> {
+    let output = sts.get_caller_identity().send().await?;
+
+    let missing = |field: &str| Error::UnexpectedNoneValue {
+        entity: format!("GetCallerIdentityResponse.{field}"),
+    };
+
+    Ok(CallerIdentity {
+        account: AccountId::new(output.account.ok_or_else(|| missing("Account"))?),
+        arn: Arn::parse(&output.arn.ok_or_else(|| missing("Arn"))?)?,
+        user_id: output.user_id.ok_or_else(|| missing("UserId"))?,
+    })
+}
+
+/// Refuses to run operations outside of an allow-list of accounts
+///
+/// The caller identity is resolved on the first check of each set of
+/// credentials (by access key ID of [`RegionClient::credentials`]) and reused
+/// afterwards, so one guard can check clients of several accounts, e.g. from
+/// [`multi_account()`]. Clients without credentials are resolved on every
+/// check. Partitions and regions are only restricted if set.
+///
+/// ```no_run
+/// # async fn f(client: &aws_lib::RegionClient, resources: &[aws_lib::arn::Arn], tags: aws_lib::tags::TagList) -> Result<(), aws_lib::Error> {
+/// use aws_lib::{organizations::AccountId, sts::AccountGuard, tags::bulk, Region};
+///
+/// let guard = AccountGuard::new(vec![AccountId::new("111111111111".to_owned())])
+///     .regions(vec![Region::EuCentral1]);
+///
+/// let report = guard
+///     .run(client, bulk::apply_tags(client, resources, tags, &bulk::BulkConfig::default()))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct AccountGuard {
+    accounts: Vec<AccountId>,
+    partitions: Vec<Partition>,
+    regions: Vec<Region>,
+    identities: Mutex<HashMap<String, CallerIdentity>>,
+}
+
+impl AccountGuard {
+    pub fn new(accounts: Vec<AccountId>) -> Self {
+        Self {
+            accounts,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn partitions(mut self, partitions: Vec<Partition>) -> Self {
+        self.partitions = partitions;
+        self
+    }
+
+    #[must_use]
+    pub fn regions(mut self, regions: Vec<Region>) -> Self {
+        self.regions = regions;
+        self
+    }
+
+    /// The identity of the credentials of `client`, fetched once per access
+    /// key
+    pub async fn identity(&self, client: &RegionClient) -> Result<CallerIdentity, Error> {
+        // Errors are left to GetCallerIdentity, which resolves the same
+        // credentials
+        let key = match client.credentials {
+            Some(ref provider) => provider
+                .provide_credentials()
+                .await
+                .ok()
+                .map(|credentials| credentials.access_key_id().to_owned()),
+            None => None,
+        };
+
+        if let Some(ref key) = key {
+            if let Some(identity) = self
+                .identities
+                .lock()
+                .ok()
+                .and_then(|identities| identities.get(key).cloned())
+            {
+                return Ok(identity);
+            }
+        }
+
+        let identity = caller_identity(client).await?;
+        if let Some(key) = key {
+            if let Ok(mut identities) = self.identities.lock() {
+                let _previous = identities.insert(key, identity.clone());
+            }
+        }
+        Ok(identity)
+    }
+
+    fn allows(&self, identity: &CallerIdentity, region: Region) -> bool {
+        let partition = identity.arn.partition();
+
+        self.accounts.contains(&identity.account)
+            && (self.partitions.is_empty()
+                || self
+                    .partitions
+                    .iter()
+                    .any(|allowed| allowed.as_str() == partition))
+            && (self.regions.is_empty()
+                || self
+                    .regions
+                    .iter()
+                    .any(|allowed| allowed.as_str() == region.as_str()))
+    }
+
+    /// Returns [`Error::WrongAccount`] if `client` is not in an allowed
+    /// account, partition and region
+    pub async fn check(&self, client: &RegionClient) -> Result<(), Error> {
+        let identity = self.identity(client).await?;
+
+        if self.allows(&identity, client.region) {
+            Ok(())
+        } else {
+            Err(Error::WrongAccount {
+                account: identity.account.to_string(),
+                partition: identity.arn.partition().to_owned(),
+                region: client.region.as_str().to_owned(),
+            })
+        }
+    }
+
+    /// Runs `operation` only after [`Self::check()`] succeeded for `client`
+    pub async fn run<T, Fut>(&self, client: &RegionClient, operation: Fut) -> Result<T, Error>
+    where
+        Fut: Future<Output = T>,
+    {
+        self.check(client).await?;
+        Ok(operation.await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(arn: &str) -> CallerIdentity {
+        let arn = Arn::parse(arn).unwrap();
+        CallerIdentity {
+            account: AccountId::new(arn.account().unwrap().to_owned()),
+            arn,
+            user_id: "AROAEXAMPLE:session".to_owned(),
+        }
+    }
+
+    #[test]
+    fn options_from_profile() {
+        let profiles = crate::profile::ProfileSet::parse(
+            "
+[profile admin]
+role_arn = arn:aws:iam::123456789012:role/admin
+role_session_name = alice
+mfa_serial = arn:aws:iam::123456789012:mfa/alice
+external_id = corp
+duration_seconds = 7200
+",
+            "",
+        )
+        .unwrap();
+
+        let options = AssumeRoleOptions::from_profile(profiles.profile("admin").unwrap());
+        assert_eq!(options.session_name, "alice");
+        assert_eq!(options.external_id.as_deref(), Some("corp"));
+        assert_eq!(
+            options.mfa_serial.as_deref(),
+            Some("arn:aws:iam::123456789012:mfa/alice")
+        );
+        assert_eq!(options.duration, Some(Duration::from_secs(7200)));
+    }
+
+    #[test]
+    fn provider_from_source_chain() {
+        let profiles = ProfileSet::parse(
+            "
+[profile admin]
+role_arn = arn:aws:iam::123456789012:role/admin
+source_profile = ops
+mfa_serial = arn:aws:iam::123456789012:mfa/alice
+
+[profile ops]
+role_arn = arn:aws:iam::123456789012:role/ops
+source_profile = base
+
+[profile sso]
+sso_session = corp
+sso_account_id = 123456789012
+sso_role_name = Developer
+
+[sso-session corp]
+sso_start_url = https://corp.awsapps.com/start
+sso_region = eu-west-1
+
+[profile dev]
+role_arn = arn:aws:iam::123456789012:role/dev
+source_profile = sso
+",
+            "
+[base]
+aws_access_key_id = AKIAEXAMPLE
+aws_secret_access_key = secret
+",
+        )
+        .unwrap();
+        let provider = |name| {
+            AssumeRoleProvider::from_profile(
+                &profiles,
+                name,
+                Region::EuCentral1,
+                Arc::new(TerminalMfaPrompt),
+            )
+        };
+
+        let admin = provider("admin").unwrap().unwrap();
+        assert_eq!(
+            admin.role.to_string(),
+            "arn:aws:iam::123456789012:role/admin"
+        );
+        assert_eq!(
+            admin
+                .via
+                .iter()
+                .map(|hop| hop.0.to_string())
+                .collect::<Vec<_>>(),
+            vec!["arn:aws:iam::123456789012:role/ops"]
+        );
+        assert!(matches!(
+            admin.source,
+            SourceCredentials::Static(ref credentials) if credentials.access_key_id() == "AKIAEXAMPLE"
+        ));
+
+        let dev = provider("dev").unwrap().unwrap();
+        assert!(dev.via.is_empty());
+        assert!(matches!(
+            dev.source,
+            SourceCredentials::Sdk(Some(ref profile)) if profile == "sso"
+        ));
+
+        assert!(provider("base").unwrap().is_none());
+        assert!(provider("missing").is_err());
+    }
+
+    #[test]
+    fn guard_allows() {
+        let guard = AccountGuard::new(vec![AccountId::new("111111111111".to_owned())]);
+        let staging = identity("arn:aws:sts::111111111111:assumed-role/deploy/session");
+        let production = identity("arn:aws:sts::222222222222:assumed-role/deploy/session");
+
+        assert!(guard.allows(&staging, Region::EuCentral1));
+        assert!(!guard.allows(&production, Region::EuCentral1));
+
+        let guard = guard
+            .partitions(vec![Partition::Aws])
+            .regions(vec![Region::UsEast1]);
+        assert!(guard.allows(&staging, Region::UsEast1));
+        assert!(!guard.allows(&staging, Region::EuCentral1));
+        assert!(!guard.allows(
+            &identity("arn:aws-cn:sts::111111111111:assumed-role/deploy/session"),
+            Region::UsEast1
+        ));
+    }
+}
@@ -0,0 +1,59 @@
+//! Property-based tests of tag structs
+
+use std::fmt::Debug;
+
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    prop_assert_eq,
+    test_runner::{TestCaseError, TestRunner},
+};
+
+use super::{ParseTagsError, TagList};
+
+/// Asserts that every value of a tag struct survives the conversion to tags
+/// and back
+///
+/// This makes sure that no [`TagValue`](super::TagValue) of its fields loses
+/// information. Pass the functions generated by
+/// [`Tags`](macro@super::Tags):
+///
+/// ```
+/// use aws_lib::tags::{assert_roundtrip, Tags};
+///
+/// #[Tags(arbitrary)]
+/// #[derive(Debug, Clone, PartialEq, Eq)]
+/// struct MyTags {
+///     owner: String,
+///     production: Option<bool>,
+/// }
+///
+/// assert_roundtrip(MyTags::into_tags, MyTags::from_tags);
+/// ```
+///
+/// The values are generated with `proptest`, so every field type has to
+/// implement `proptest::arbitrary::Arbitrary`. `#[Tags(arbitrary)]`
+/// implements it for the struct.
+///
+/// # Panics
+///
+/// If a value does not round-trip, with the smallest such value found.
+#[expect(clippy::panic, reason = "this is an assertion for tests")]
+pub fn assert_roundtrip<T>(
+    into_tags: impl Fn(T) -> TagList,
+    from_tags: impl Fn(TagList) -> Result<T, ParseTagsError>,
+) where
+    T: Arbitrary + Clone + PartialEq + Debug,
+{
+    let mut runner = TestRunner::default();
+
+    let result = runner.run(&any::<T>(), |value| {
+        let parsed = from_tags(into_tags(value.clone()))
+            .map_err(|e| TestCaseError::fail(format!("cannot parse the tags: {e}")))?;
+        prop_assert_eq!(parsed, value);
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        panic!("tags do not round-trip: {e}");
+    }
+}
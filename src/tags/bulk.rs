@@ -0,0 +1,531 @@
+//! Applying and removing tags on many resources at once
+//!
+//! Uses the Resource Groups Tagging API, which works for all taggable
+//! resources and accepts up to 20 ARNs per call. Calls are sent with bounded
+//! concurrency. Only transient errors (throttling, server errors) are
+//! retried, either for the single resource that failed or for all resources
+//! of a call that failed as a whole. The [`BulkReport`] lists which
+//! resources succeeded, failed or were skipped.
+//!
+//! The tags usually come from a struct generated with
+//! [`Tags`](super::Tags), via its `into_tags()` method.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    time::Duration,
+};
+
+use aws_sdk_resourcegroupstagging::types::{ErrorCode, FailureInfo};
+use futures_util::stream::{FuturesUnordered, StreamExt as _};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{TagKey, TagList};
+use crate::{arn::Arn, metrics, Error, RegionClient};
+
+/// `TagResources` and `UntagResources` accept at most this many ARNs
+const MAX_RESOURCES_PER_CALL: usize = 20;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BulkConfig {
+    /// Maximum number of API calls in flight
+    pub concurrency: usize,
+    /// How often each resource is tried, including the first attempt
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub retry_delay: Duration,
+}
+
+impl Default for BulkConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            max_attempts: 3,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Operation {
+    Apply(HashMap<String, String>),
+    Remove(Vec<String>),
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkFailure {
+    pub arn: Arn,
+    pub error_code: Option<String>,
+    pub message: Option<String>,
+    pub attempts: u32,
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The resource is in a different region than the client
+    OtherRegion { region: String },
+    /// There are no tags to apply or remove
+    NothingToDo,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::OtherRegion { ref region } => write!(f, "resource is in region {region}"),
+            Self::NothingToDo => write!(f, "nothing to do"),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub arn: Arn,
+    pub reason: SkipReason,
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BulkReport {
+    pub succeeded: Vec<Arn>,
+    pub failed: Vec<BulkFailure>,
+    pub skipped: Vec<Skipped>,
+}
+
+impl BulkReport {
+    /// Whether no resource failed. Skipped resources do not count as
+    /// failures.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Adds the results of another report, e.g. from another region
+    pub fn merge(&mut self, other: Self) {
+        self.succeeded.extend(other.succeeded);
+        self.failed.extend(other.failed);
+        self.skipped.extend(other.skipped);
+    }
+}
+
+/// Server-side errors may go away on retry, invalid parameters will not
+fn is_retriable(failure: &FailureInfo) -> bool {
+    matches!(
+        failure.error_code(),
+        Some(&ErrorCode::InternalServiceException)
+    ) || failure.status_code() >= 500
+        || failure.status_code() == 429
+}
+
+async fn send(
+    client: &RegionClient,
+    operation: &Operation,
+    arns: &[Arn],
+) -> Result<HashMap<String, FailureInfo>, Error> {
+    let arns = arns
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>();
+
+    Ok(match *operation {
+        Operation::Apply(ref tags) => {
+            client
+                .main
+                .tagging
+                .tag_resources()
+                .set_resource_arn_list(Some(arns))
+                .set_tags(Some(tags.clone()))
+                .send()
+                .await?
+                .failed_resources_map
+        }
+        Operation::Remove(ref keys) => {
+            client
+                .main
+                .tagging
+                .untag_resources()
+                .set_resource_arn_list(Some(arns))
+                .set_tag_keys(Some(keys.clone()))
+                .send()
+                .await?
+                .failed_resources_map
+        }
+    }
+    .unwrap_or_default())
+}
+
+/// Processes one batch of ARNs, retrying failed resources
+async fn process_batch(
+    client: &RegionClient,
+    operation: &Operation,
+    arns: Vec<Arn>,
+    config: &BulkConfig,
+) -> BulkReport {
+    let mut report = BulkReport::default();
+    let mut pending = arns;
+    let mut attempt: u32 = 0;
+    let mut delay = config.retry_delay;
+
+    loop {
+        attempt = attempt.saturating_add(1);
+        let last_attempt = attempt >= config.max_attempts;
+        let mut retry = Vec::new();
+
+        match send(client, operation, &pending).await {
+            Ok(failures) => {
+                for arn in pending {
+                    match failures.get(&arn.to_string()) {
+                        None => report.succeeded.push(arn),
+                        Some(failure) if is_retriable(failure) && !last_attempt => retry.push(arn),
+                        Some(failure) => report.failed.push(BulkFailure {
+                            arn,
+                            error_code: failure.error_code().map(|code| code.as_str().to_owned()),
+                            message: failure.error_message().map(ToOwned::to_owned),
+                            attempts: attempt,
+                        }),
+                    }
+                }
+            }
+            // The whole call failed. Only throttling and transient errors
+            // may go away on retry, others like missing permissions or
+            // invalid ARNs fail the whole batch right away.
+            Err(e) if last_attempt || !metrics::is_transient(&e) => {
+                let message = e.to_string();
+                report
+                    .failed
+                    .extend(pending.into_iter().map(|arn| BulkFailure {
+                        arn,
+                        error_code: None,
+                        message: Some(message.clone()),
+                        attempts: attempt,
+                    }));
+            }
+            Err(_) => retry = pending,
+        }
+
+        if retry.is_empty() {
+            return report;
+        }
+
+        pending = retry;
+        tokio::time::sleep(delay).await;
+        delay = delay.saturating_mul(2);
+    }
+}
+
+async fn run(
+    client: &RegionClient,
+    resources: &[Arn],
+    operation: Operation,
+    nothing_to_do: bool,
+    config: &BulkConfig,
+) -> BulkReport {
+    let mut report = BulkReport::default();
+    let mut targets = Vec::new();
+
+    // Duplicates would only cause redundant calls
+    let unique: BTreeSet<&Arn> = resources.iter().collect();
+    for arn in unique {
+        let reason = if nothing_to_do {
+            Some(SkipReason::NothingToDo)
+        } else {
+            arn.region()
+                .filter(|&region| region != client.region.as_str())
+                .map(|region| SkipReason::OtherRegion {
+                    region: region.to_owned(),
+                })
+        };
+
+        match reason {
+            Some(reason) => report.skipped.push(Skipped {
+                arn: arn.clone(),
+                reason,
+            }),
+            None => targets.push(arn.clone()),
+        }
+    }
+
+    let mut batches = targets.chunks(MAX_RESOURCES_PER_CALL).map(<[Arn]>::to_vec);
+    let mut in_flight = FuturesUnordered::new();
+
+    loop {
+        while in_flight.len() < config.concurrency.max(1) {
+            match batches.next() {
+                Some(batch) => in_flight.push(process_batch(client, &operation, batch, config)),
+                None => break,
+            }
+        }
+
+        match in_flight.next().await {
+            Some(batch_report) => report.merge(batch_report),
+            None => break,
+        }
+    }
+
+    report.succeeded.sort();
+    report.failed.sort_by(|a, b| a.arn.cmp(&b.arn));
+
+    report
+}
+
+/// Sets `tags` on all `resources`, overwriting existing values of the same
+/// keys. Other tags of the resources are left untouched.
+///
+/// Resources in other regions than the one of `client` are skipped.
+pub async fn apply_tags(
+    client: &RegionClient,
+    resources: &[Arn],
+    tags: TagList,
+    config: &BulkConfig,
+) -> BulkReport {
+    let nothing_to_do = tags.as_slice().is_empty();
+    run(
+        client,
+        resources,
+        Operation::Apply(tags.into()),
+        nothing_to_do,
+        config,
+    )
+    .await
+}
+
+/// Removes the tags with the given keys from all `resources`. Removing keys
+/// that a resource does not have is not an error.
+///
+/// Resources in other regions than the one of `client` are skipped.
+pub async fn remove_tags(
+    client: &RegionClient,
+    resources: &[Arn],
+    keys: &[TagKey],
+    config: &BulkConfig,
+) -> BulkReport {
+    run(
+        client,
+        resources,
+        Operation::Remove(keys.iter().map(|key| key.as_str().to_owned()).collect()),
+        keys.is_empty(),
+        config,
+    )
+    .await
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::{
+        tags::RawTag,
+        testing::{
+            block_on, instance_arn, mock_region_client, tagging_failure, DelayedHttpClient,
+            Matcher, MockHttpClient, MockResponse, Request,
+        },
+        Region,
+    };
+
+    const NO_DELAY: BulkConfig = BulkConfig {
+        concurrency: 4,
+        max_attempts: 3,
+        retry_delay: Duration::ZERO,
+    };
+
+    fn tags() -> TagList {
+        TagList::from_vec(vec![RawTag::new("env".to_owned(), "prod".to_owned())])
+    }
+
+    fn requested_arns(request: &Request) -> Vec<String> {
+        request.json_param("ResourceARNList").unwrap()
+    }
+
+    #[test]
+    fn retriable_failures() {
+        let failure = |status, code| {
+            FailureInfo::builder()
+                .status_code(status)
+                .error_code(code)
+                .build()
+        };
+
+        assert!(is_retriable(&failure(
+            500_i32,
+            ErrorCode::InternalServiceException
+        )));
+        assert!(!is_retriable(&failure(
+            400_i32,
+            ErrorCode::InvalidParameterException
+        )));
+    }
+
+    #[test]
+    fn chunks_of_twenty() {
+        let http =
+            MockHttpClient::new().on(Matcher::action("TagResources"), MockResponse::ok("{}"));
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let mut resources: Vec<Arn> = (0..45).map(instance_arn).collect();
+        resources.push(instance_arn(0));
+
+        let report = block_on(apply_tags(&client, &resources, tags(), &NO_DELAY));
+        assert!(report.is_success());
+        assert_eq!(report.succeeded.len(), 45);
+
+        let mut sizes: Vec<usize> = http
+            .requests()
+            .unwrap()
+            .iter()
+            .map(|request| requested_arns(request).len())
+            .collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, [5, 20, 20]);
+    }
+
+    #[test]
+    fn concurrency_limit() {
+        let http = DelayedHttpClient::new(
+            MockHttpClient::new().on(Matcher::action("TagResources"), MockResponse::ok("{}")),
+            Duration::from_millis(10),
+        );
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let resources: Vec<Arn> = (0..100).map(instance_arn).collect();
+        let config = BulkConfig {
+            concurrency: 2,
+            ..NO_DELAY
+        };
+
+        let report = block_on(apply_tags(&client, &resources, tags(), &config));
+        assert_eq!(report.succeeded.len(), 100);
+        assert_eq!(http.inner().requests().unwrap().len(), 5);
+        assert_eq!(http.max_in_flight(), 2);
+    }
+
+    #[test]
+    fn retry_failed_resources() {
+        let http = MockHttpClient::new()
+            .once(
+                Matcher::action("TagResources"),
+                tagging_failure(&instance_arn(1), 500, "InternalServiceException"),
+            )
+            .on(Matcher::action("TagResources"), MockResponse::ok("{}"));
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let report = block_on(apply_tags(
+            &client,
+            &[instance_arn(0), instance_arn(1)],
+            tags(),
+            &NO_DELAY,
+        ));
+        assert!(report.is_success());
+        assert_eq!(report.succeeded, [instance_arn(0), instance_arn(1)]);
+
+        // Only the failed resource is sent again
+        let requests = http.requests().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requested_arns(requests.last().unwrap()),
+            [instance_arn(1).to_string()]
+        );
+    }
+
+    #[test]
+    fn retries_exhausted() {
+        let http = MockHttpClient::new().on(
+            Matcher::action("UntagResources"),
+            MockResponse::status(
+                400,
+                r#"{"__type":"ThrottledException","Message":"slow down"}"#,
+            ),
+        );
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let key = TagKey::new("env".to_owned());
+        let report = block_on(remove_tags(&client, &[instance_arn(0)], &[key], &NO_DELAY));
+
+        assert_eq!(http.requests().unwrap().len(), 3);
+        assert_eq!(report.failed.len(), 1);
+        let failure = report.failed.first().unwrap();
+        assert_eq!(failure.arn, instance_arn(0));
+        assert_eq!(failure.attempts, 3);
+        assert_eq!(failure.error_code, None);
+    }
+
+    #[test]
+    fn permanent_errors_are_not_retried() {
+        let http = MockHttpClient::new().on(
+            Matcher::action("TagResources"),
+            MockResponse::status(
+                400,
+                r#"{"__type":"AccessDeniedException","Message":"not allowed"}"#,
+            ),
+        );
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let report = block_on(apply_tags(
+            &client,
+            &[instance_arn(0), instance_arn(1)],
+            tags(),
+            &NO_DELAY,
+        ));
+
+        assert_eq!(http.requests().unwrap().len(), 1);
+        assert!(report.succeeded.is_empty());
+        assert_eq!(
+            report
+                .failed
+                .iter()
+                .map(|failure| (&failure.arn, failure.attempts))
+                .collect::<Vec<_>>(),
+            [(&instance_arn(0), 1), (&instance_arn(1), 1)]
+        );
+    }
+
+    #[test]
+    fn report() {
+        let other_region =
+            Arn::parse("arn:aws:ec2:us-east-1:123456789012:instance/i-0123456789abcdef0").unwrap();
+        let http = MockHttpClient::new().on(
+            Matcher::action("TagResources"),
+            tagging_failure(&instance_arn(1), 400, "InvalidParameterException"),
+        );
+        let client = mock_region_client(Region::EuCentral1, http.clone());
+
+        let report = block_on(apply_tags(
+            &client,
+            &[instance_arn(0), instance_arn(1), other_region.clone()],
+            tags(),
+            &NO_DELAY,
+        ));
+
+        assert!(!report.is_success());
+        assert_eq!(report.succeeded, [instance_arn(0)]);
+        assert_eq!(
+            report.failed,
+            [BulkFailure {
+                arn: instance_arn(1),
+                error_code: Some("InvalidParameterException".to_owned()),
+                message: Some("failed".to_owned()),
+                attempts: 1,
+            }]
+        );
+        assert_eq!(
+            report.skipped,
+            [Skipped {
+                arn: other_region,
+                reason: SkipReason::OtherRegion {
+                    region: "us-east-1".to_owned()
+                },
+            }]
+        );
+
+        // Nothing to remove, so no calls at all
+        let report = block_on(remove_tags(&client, &[instance_arn(0)], &[], &NO_DELAY));
+        assert_eq!(
+            report.skipped,
+            [Skipped {
+                arn: instance_arn(0),
+                reason: SkipReason::NothingToDo,
+            }]
+        );
+        assert_eq!(http.requests().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,135 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{RawTag, RawTagValue, TagKey, TagList};
+
+/// Tags with this prefix are managed by AWS and cannot be removed
+const AWS_PREFIX: &str = "aws:";
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagChange {
+    pub key: TagKey,
+    pub from: RawTagValue,
+    pub to: RawTagValue,
+}
+
+/// The changes needed to turn one [`TagList`] into another
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagDiff {
+    /// Tags that are only in the desired list
+    pub added: Vec<RawTag>,
+    /// Tags that are in both lists, but with different values
+    pub changed: Vec<TagChange>,
+    /// Tags that are only in the current list. Tags with the reserved `aws:`
+    /// prefix are never included.
+    pub removed: Vec<RawTag>,
+}
+
+impl TagDiff {
+    /// Both lists are compared in their canonical form, so the order of the
+    /// tags does not matter
+    pub fn between(current: &TagList, desired: &TagList) -> Self {
+        let mut diff = Self::default();
+
+        for tag in desired.canonical() {
+            match current.get(tag.key.clone()) {
+                None => diff.added.push(tag.clone()),
+                Some(existing) if existing.value != tag.value => diff.changed.push(TagChange {
+                    key: tag.key.clone(),
+                    from: existing.value.clone(),
+                    to: tag.value.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+
+        diff.removed = current
+            .canonical()
+            .into_iter()
+            .filter(|tag| {
+                !tag.key.as_str().starts_with(AWS_PREFIX) && desired.get(tag.key.clone()).is_none()
+            })
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// The tags that have to be set, i.e. all added and changed tags, sorted
+    /// by key
+    pub fn to_set(&self) -> TagList {
+        TagList::from_vec(
+            self.added
+                .iter()
+                .cloned()
+                .chain(self.changed.iter().map(|change| RawTag {
+                    key: change.key.clone(),
+                    value: change.to.clone(),
+                }))
+                .collect(),
+        )
+        .canonicalize()
+    }
+
+    /// The keys of all removed tags
+    pub fn removed_keys(&self) -> Vec<TagKey> {
+        self.removed.iter().map(|tag| tag.key.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(tags: &[(&str, &str)]) -> TagList {
+        TagList::from_vec(
+            tags.iter()
+                .map(|&(key, value)| RawTag::new(key.to_owned(), value.to_owned()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn diff() {
+        let current = list(&[
+            ("env", "dev"),
+            ("team", "infra"),
+            ("owner", "alice"),
+            ("aws:cloudformation:stack-name", "stack"),
+        ]);
+        let desired = list(&[("env", "prod"), ("team", "infra"), ("cost-center", "42")]);
+
+        let diff = current.diff(&desired);
+
+        assert_eq!(diff.added, list(&[("cost-center", "42")]).into_vec());
+        assert_eq!(
+            diff.changed,
+            vec![TagChange {
+                key: "env".to_owned().into(),
+                from: RawTagValue::new("dev".to_owned()),
+                to: RawTagValue::new("prod".to_owned()),
+            }]
+        );
+        assert_eq!(diff.removed, list(&[("owner", "alice")]).into_vec());
+        assert_eq!(
+            diff.to_set(),
+            list(&[("cost-center", "42"), ("env", "prod")])
+        );
+        assert_eq!(diff.removed_keys(), vec![TagKey::new("owner".to_owned())]);
+    }
+
+    #[test]
+    fn no_diff() {
+        let tags = list(&[("env", "prod")]);
+        assert!(
+            tags.diff(&tags).is_empty(),
+            "identical lists must not differ"
+        );
+    }
+}
@@ -1,5 +1,8 @@
 #![doc = include_str!("README.md")]
-use std::fmt::{self, Debug};
+use std::{
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+};
 
 #[cfg(feature = "serde-tags")]
 use serde::de::DeserializeOwned;
@@ -8,13 +11,32 @@ use serde::Deserialize;
 #[cfg(any(feature = "serde-tags", feature = "serde"))]
 use serde::Serialize;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod bulk;
+mod diff;
 mod error;
 mod helpers;
 mod predefined_types;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod reconcile;
+pub mod report;
+pub(crate) mod resource;
+mod schema;
+mod selector;
 mod svc;
 
+#[cfg(feature = "arbitrary")]
+pub use arbitrary::assert_roundtrip;
 pub use aws_macros::{Tag, Tags};
+pub use diff::{TagChange, TagDiff};
 pub use error::{ParseTagAwsError, ParseTagError, ParseTagValueError, ParseTagsError};
+/// Used by the `Arbitrary` implementations of `#[Tags(arbitrary)]`
+#[cfg(feature = "arbitrary")]
+pub use proptest;
+pub use schema::{Schema, SchemaKey, ValueType};
+pub use selector::{Selector, SelectorTag};
 
 #[derive(Debug, PartialEq, Eq)]
 struct InnerTagValue<T>(T)
@@ -48,7 +70,7 @@ where
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct RawTagValue(String);
 helpers::impl_string_wrapper!(RawTagValue);
@@ -82,6 +104,11 @@ pub trait TagValue<V> {
     fn into_raw_tag(value: V) -> RawTagValue {
         Self::Translator::into_raw_tag(value)
     }
+
+    /// The values the tag accepts, for the [`Schema`] of a tags struct
+    fn value_type() -> ValueType {
+        ValueType::String
+    }
 }
 
 #[cfg(feature = "serde-tags")]
@@ -128,7 +155,7 @@ where
 }
 
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct RawTag {
     key: TagKey,
     value: RawTagValue,
@@ -166,7 +193,7 @@ where
 }
 
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct TagKey(String);
 helpers::impl_string_wrapper!(TagKey);
 
@@ -243,13 +270,7 @@ where
             },
         })
     }
-}
 
-impl<T> Tag<T>
-where
-    T: Debug + Clone + PartialEq + Eq + Send,
-    T: TagValue<T>,
-{
     fn into_parts(self) -> (TagKey, InnerTagValue<T>) {
         (self.key, self.value)
     }
@@ -263,8 +284,12 @@ where
     }
 }
 
+/// A list of tags as returned by AWS
+///
+/// Equality and hashing use the [canonical form](Self::canonicalize()), so
+/// they do not depend on the order of the tags.
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq)]
 pub struct TagList(Vec<RawTag>);
 
 impl TagList {
@@ -300,6 +325,39 @@ impl TagList {
     pub fn as_slice(&self) -> &[RawTag] {
         &self.0
     }
+
+    /// Shorthand for [`TagDiff::between()`] with `self` as the current tags
+    pub fn diff(&self, desired: &Self) -> TagDiff {
+        TagDiff::between(self, desired)
+    }
+
+    /// Sorts the tags by key and removes tags with duplicate keys. Of those,
+    /// the first one is kept, like [`get()`](Self::get()) does.
+    #[must_use]
+    pub fn canonicalize(mut self) -> Self {
+        self.0.sort_by(|a, b| a.key.cmp(&b.key));
+        self.0.dedup_by(|a, b| a.key == b.key);
+        self
+    }
+
+    fn canonical(&self) -> Vec<&RawTag> {
+        let mut tags = self.0.iter().collect::<Vec<&RawTag>>();
+        tags.sort_by(|a, b| a.key.cmp(&b.key));
+        tags.dedup_by(|a, b| a.key == b.key);
+        tags
+    }
+}
+
+impl PartialEq for TagList {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Hash for TagList {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
 }
 
 #[cfg(test)]
@@ -424,6 +482,162 @@ mod tests {
         );
     }
 
+    #[test]
+    fn keys_in_field_order() {
+        #[Tags]
+        struct MyKeyedTags {
+            tag1: String,
+            tag2: Option<bool>,
+            #[tag(key = "myname")]
+            tag3: MyTag,
+            #[tag(key = "anothername")]
+            tag4: Option<MyTag>,
+        }
+
+        assert_eq!(MyKeyedTags::KEYS, ["tag1", "tag2", "myname", "anothername"]);
+    }
+
+    #[test]
+    fn key_from_const() {
+        mod keys {
+            pub(super) const OWNER: &str = "acme:owner";
+            pub(super) const TEAM: &str = "acme:team";
+        }
+
+        #[Tags]
+        struct MyKeyTags {
+            #[tag(key_from = keys::OWNER)]
+            owner: String,
+            #[tag(key_from = keys::TEAM)]
+            team: Option<String>,
+        }
+
+        assert_eq!(MyKeyTags::KEYS, ["acme:owner", "acme:team"]);
+
+        let tags = MyKeyTags::from_tags(TagList::from_vec(vec![RawTag::new(
+            "acme:owner".to_owned(),
+            "alice".to_owned(),
+        )]))
+        .unwrap();
+        assert_eq!(tags.owner, "alice");
+        assert!(tags.team.is_none());
+        assert_eq!(
+            MyKeyTags::schema().keys.first().map(|key| key.key.as_str()),
+            Some("acme:owner")
+        );
+    }
+
+    #[test]
+    fn cfg_key_fallback() {
+        #[Tags]
+        struct MigratedTags {
+            #[tag(
+                key = "owner",
+                cfg_key(test, key = "Owner"),
+                cfg_key(not(test), key = "owner-name")
+            )]
+            owner: String,
+        }
+
+        let parse = |key: &str| {
+            MigratedTags::from_tags(TagList::from_vec(vec![RawTag::new(
+                key.to_owned(),
+                "alice".to_owned(),
+            )]))
+            .map(|tags| tags.owner)
+        };
+
+        assert_eq!(parse("owner").unwrap(), "alice");
+        assert_eq!(parse("Owner").unwrap(), "alice");
+        assert!(parse("owner-name").is_err(), "inactive fallback key");
+
+        let both = TagList::from_vec(vec![
+            RawTag::new("Owner".to_owned(), "bob".to_owned()),
+            RawTag::new("owner".to_owned(), "alice".to_owned()),
+        ]);
+        assert_eq!(
+            MigratedTags::from_tags(both).unwrap().owner,
+            "alice",
+            "new key is preferred"
+        );
+
+        assert_eq!(
+            MigratedTags::from_values("alice".to_owned()).into_tags(),
+            TagList::from_vec(vec![RawTag::new("owner".to_owned(), "alice".to_owned())])
+        );
+        assert_eq!(MigratedTags::KEYS, ["owner"]);
+    }
+
+    /// Run with and without the `testing` feature to cover both defaults
+    #[test]
+    fn cfg_default_value() {
+        #[Tags]
+        struct DefaultTags {
+            #[tag(
+                key = "team",
+                cfg_default(feature = "testing", value = "qa"),
+                cfg_default(not(feature = "testing"), value = "platform")
+            )]
+            team: String,
+            #[tag(cfg_default(any(), value = "never"))]
+            owner: Option<String>,
+        }
+
+        #[cfg(feature = "testing")]
+        const TEAM: &str = "qa";
+        #[cfg(not(feature = "testing"))]
+        const TEAM: &str = "platform";
+
+        let tags = DefaultTags::from_tags(TagList::new()).unwrap();
+        assert_eq!(tags.team, TEAM);
+        assert_eq!(tags.owner, None, "inactive default");
+
+        let tags = DefaultTags::from_tags(TagList::from_vec(vec![RawTag::new(
+            "team".to_owned(),
+            "infra".to_owned(),
+        )]))
+        .unwrap();
+        assert_eq!(tags.team, "infra", "a set tag wins over the default");
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_roundtrip() {
+        #[Tags(arbitrary)]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        struct ArbitraryTags {
+            name: String,
+            #[tag(key = "enabled")]
+            flag: bool,
+            optional: Option<bool>,
+            #[cfg(not(test))]
+            removed: String,
+        }
+
+        assert_roundtrip(ArbitraryTags::into_tags, ArbitraryTags::from_tags);
+    }
+
+    #[test]
+    fn struct_attributes_are_kept() {
+        // None of these traits are needed by the generated code, so they
+        // only exist if the derive survives the macro
+        #[Tags]
+        #[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        struct DerivedTags {
+            owner: String,
+        }
+
+        let empty = DerivedTags::default();
+        let alice = DerivedTags::from_values("alice".to_owned());
+
+        assert!(empty.owner.is_empty());
+        assert!(empty < alice);
+        assert_eq!(
+            std::collections::HashSet::from([empty, DerivedTags::default()]).len(),
+            1
+        );
+    }
+
     #[test]
     fn test_transparent_tag() {
         #[derive(Tag, PartialEq, Debug)]
@@ -468,4 +682,80 @@ mod tests {
             MyCoolioTag::B
         );
     }
+
+    #[test]
+    fn into_tags_sorted() {
+        #[Tags]
+        struct UnsortedTags {
+            zone: String,
+            app: String,
+            #[tag(key = "env")]
+            environment: String,
+        }
+
+        let tags = UnsortedTags {
+            zone: "a".to_owned(),
+            app: "b".to_owned(),
+            environment: "c".to_owned(),
+        }
+        .into_tags();
+
+        assert_eq!(
+            tags.as_slice()
+                .iter()
+                .map(|tag| tag.key().as_str())
+                .collect::<Vec<&str>>(),
+            ["app", "env", "zone"]
+        );
+    }
+
+    #[test]
+    fn canonical_form() {
+        use std::hash::DefaultHasher;
+
+        let tags = TagList::from_vec(vec![
+            RawTag::new("owner".to_owned(), "alice".to_owned()),
+            RawTag::new("env".to_owned(), "prod".to_owned()),
+        ]);
+        let reordered = TagList::from_vec(vec![
+            RawTag::new("env".to_owned(), "prod".to_owned()),
+            RawTag::new("owner".to_owned(), "alice".to_owned()),
+        ]);
+
+        assert_eq!(
+            tags.clone().canonicalize().into_vec(),
+            reordered.clone().into_vec()
+        );
+        assert_eq!(tags, reordered);
+        assert_ne!(
+            reordered,
+            TagList::from_vec(vec![RawTag::new("env".to_owned(), "prod".to_owned())])
+        );
+
+        let hash = |tags: &TagList| {
+            let mut hasher = DefaultHasher::new();
+            tags.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&tags), hash(&reordered));
+    }
+
+    #[test]
+    fn canonical_form_duplicate_keys() {
+        let alice = || RawTag::new("owner".to_owned(), "alice".to_owned());
+        let bob = || RawTag::new("owner".to_owned(), "bob".to_owned());
+        let env = || RawTag::new("env".to_owned(), "prod".to_owned());
+
+        let tags = TagList::from_vec(vec![alice(), env(), bob()]);
+        assert_eq!(tags.as_slice(), [alice(), env(), bob()], "kept as added");
+        assert_eq!(tags.get("owner".to_owned()), Some(&alice()));
+        assert_eq!(tags.clone().canonicalize().into_vec(), [env(), alice()]);
+        assert_eq!(tags, TagList::from_vec(vec![env(), alice()]));
+        assert_ne!(tags, TagList::from_vec(vec![bob(), env()]));
+
+        let mut pushed = TagList::from_vec(vec![env(), alice()]);
+        pushed.push(bob());
+        assert_eq!(pushed.as_slice(), [env(), alice(), bob()]);
+        assert_eq!(pushed, tags);
+    }
 }
@@ -1,4 +1,6 @@
-use super::{ParseTagValueError, RawTagValue, TagValue, TranslatableManual, TranslateManual};
+use super::{
+    ParseTagValueError, RawTagValue, TagValue, TranslatableManual, TranslateManual, ValueType,
+};
 
 impl TranslatableManual for bool {}
 
@@ -8,6 +10,10 @@ const FALSE_STR: &str = "false";
 impl TagValue<Self> for bool {
     type Error = ParseTagValueError;
     type Translator = TranslateManual;
+
+    fn value_type() -> ValueType {
+        ValueType::Boolean
+    }
 }
 
 impl TryFrom<RawTagValue> for bool {
@@ -0,0 +1,514 @@
+//! Enforcing a desired set of tags on many resources
+//!
+//! A [`Reconciler`] fetches the current tags of resources via the Resource
+//! Groups Tagging API, computes a [`TagDiff`] against the desired tags and
+//! applies only the differences with the [bulk](super::bulk) engine. In dry
+//! run mode, nothing is changed and the [`ReconcileReport`] only shows the
+//! drift. The report implements `Display` for humans and, with the `serde`
+//! feature, `Serialize` for JSON output.
+//!
+//! ```no_run
+//! # use aws_lib::{arn::Arn, tags::{Tags, reconcile::Reconciler}, RegionClient};
+//! #[Tags]
+//! struct Required {
+//!     team: String,
+//!     production: bool,
+//! }
+//!
+//! # async fn f(client: &RegionClient, resources: &[Arn]) -> Result<(), aws_lib::Error> {
+//! let desired = Required {
+//!     team: "infra".to_owned(),
+//!     production: true,
+//! };
+//!
+//! let report = Reconciler::new(client)
+//!     .dry_run(true)
+//!     .reconcile(resources, &desired.into_tags())
+//!     .await?;
+//!
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bulk::{self, BulkConfig, BulkReport, SkipReason, Skipped},
+    TagDiff, TagKey, TagList,
+};
+use crate::{arn::Arn, Error, RegionClient};
+
+/// `GetResources` accepts at most this many ARNs
+const MAX_RESOURCES_PER_LOOKUP: usize = 100;
+
+/// Changes of the same kind keyed by a sortable form of the change, with the
+/// resources that need it
+type Groups<K, C> = BTreeMap<K, (C, Vec<Arn>)>;
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceDrift {
+    pub arn: Arn,
+    pub diff: TagDiff,
+}
+
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconcileReport {
+    pub dry_run: bool,
+    /// Resources whose tags already match
+    pub in_sync: Vec<Arn>,
+    /// Resources whose tags differ, with the changes that were (or in dry run
+    /// mode, would be) made
+    pub drifted: Vec<ResourceDrift>,
+    /// Resources that were not looked at
+    pub skipped: Vec<Skipped>,
+    /// The outcome of the tag changes, `None` in dry run mode
+    pub applied: Option<BulkReport>,
+}
+
+impl ReconcileReport {
+    /// Whether all changes were applied. Always `true` in dry run mode.
+    pub fn is_success(&self) -> bool {
+        self.applied.as_ref().map_or(true, BulkReport::is_success)
+    }
+}
+
+impl fmt::Display for ReconcileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for drift in &self.drifted {
+            writeln!(f, "{}", drift.arn)?;
+            for tag in &drift.diff.added {
+                writeln!(f, "  + {} = {}", tag.key(), tag.value())?;
+            }
+            for change in &drift.diff.changed {
+                writeln!(f, "  ~ {} = {} -> {}", change.key, change.from, change.to)?;
+            }
+            for tag in &drift.diff.removed {
+                writeln!(f, "  - {} = {}", tag.key(), tag.value())?;
+            }
+        }
+
+        for skipped in &self.skipped {
+            writeln!(f, "skipped {}: {}", skipped.arn, skipped.reason)?;
+        }
+
+        if let Some(ref applied) = self.applied {
+            for failure in &applied.failed {
+                writeln!(
+                    f,
+                    "failed {}: {}",
+                    failure.arn,
+                    failure.message.as_deref().unwrap_or("unknown error")
+                )?;
+            }
+        }
+
+        write!(
+            f,
+            "{} in sync, {} drifted, {} skipped",
+            self.in_sync.len(),
+            self.drifted.len(),
+            self.skipped.len()
+        )?;
+
+        match self.applied {
+            Some(ref applied) => write!(f, ", {} failed", applied.failed.len()),
+            None => write!(f, " (dry run)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Reconciler<'a> {
+    client: &'a RegionClient,
+    bulk: BulkConfig,
+    prune: bool,
+    dry_run: bool,
+}
+
+impl<'a> Reconciler<'a> {
+    pub fn new(client: &'a RegionClient) -> Self {
+        Self {
+            client,
+            bulk: BulkConfig::default(),
+            prune: false,
+            dry_run: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_bulk_config(self, bulk: BulkConfig) -> Self {
+        Self { bulk, ..self }
+    }
+
+    /// Also remove all tags that are not in the desired tags. Off by default,
+    /// so tags managed by others are left alone.
+    #[must_use]
+    pub const fn prune(self, prune: bool) -> Self {
+        Self { prune, ..self }
+    }
+
+    /// Only report the drift, do not change any tags
+    #[must_use]
+    pub const fn dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run, ..self }
+    }
+
+    /// Brings the tags of all `resources` in line with `desired`, which
+    /// usually comes from the `into_tags()` method of a [`Tags`](super::Tags)
+    /// struct.
+    ///
+    /// Resources in other regions than the one of the client are skipped.
+    /// Failures to change tags of individual resources are reported in
+    /// [`ReconcileReport::applied`]; only failing to fetch the current tags
+    /// returns an error.
+    pub async fn reconcile(
+        &self,
+        resources: &[Arn],
+        desired: &TagList,
+    ) -> Result<ReconcileReport, Error> {
+        let mut report = ReconcileReport {
+            dry_run: self.dry_run,
+            ..ReconcileReport::default()
+        };
+
+        let mut targets = Vec::new();
+        for arn in resources {
+            match arn
+                .region()
+                .filter(|&region| region != self.client.region.as_str())
+            {
+                Some(region) => report.skipped.push(Skipped {
+                    arn: arn.clone(),
+                    reason: SkipReason::OtherRegion {
+                        region: region.to_owned(),
+                    },
+                }),
+                None => targets.push(arn.clone()),
+            }
+        }
+        targets.sort();
+        targets.dedup();
+
+        let mut current = self.current_tags(&targets).await?;
+
+        for arn in targets {
+            let mut diff = current
+                .remove(&arn.to_string())
+                .unwrap_or_else(TagList::new)
+                .diff(desired);
+            if !self.prune {
+                diff.removed.clear();
+            }
+
+            if diff.is_empty() {
+                report.in_sync.push(arn);
+            } else {
+                report.drifted.push(ResourceDrift { arn, diff });
+            }
+        }
+
+        if !self.dry_run {
+            report.applied = Some(self.apply(&report.drifted).await);
+        }
+
+        Ok(report)
+    }
+
+    /// Resources that were never tagged are not returned by the API and get
+    /// an empty list
+    async fn current_tags(&self, resources: &[Arn]) -> Result<HashMap<String, TagList>, Error> {
+        let mut tags = HashMap::new();
+
+        for chunk in resources.chunks(MAX_RESOURCES_PER_LOOKUP) {
+            let arns = chunk
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>();
+            let mut pagination_token = None;
+
+            loop {
+                let output = self
+                    .client
+                    .main
+                    .tagging
+                    .get_resources()
+                    .set_resource_arn_list(Some(arns.clone()))
+                    .set_pagination_token(pagination_token)
+                    .send()
+                    .await?;
+
+                for mapping in output.resource_tag_mapping_list.unwrap_or_default() {
+                    if let Some(arn) = mapping.resource_arn {
+                        let _previous: Option<TagList> =
+                            tags.insert(arn, mapping.tags.unwrap_or_default().try_into()?);
+                    }
+                }
+
+                match output.pagination_token {
+                    Some(token) if !token.is_empty() => pagination_token = Some(token),
+                    _ => break,
+                }
+            }
+        }
+
+        Ok(tags)
+    }
+
+    /// Resources that need the same changes are sent together, so that the
+    /// bulk engine can batch them
+    async fn apply(&self, drifted: &[ResourceDrift]) -> BulkReport {
+        let mut to_set: Groups<Vec<(String, String)>, TagList> = BTreeMap::new();
+        let mut to_remove: Groups<Vec<String>, Vec<TagKey>> = BTreeMap::new();
+
+        for drift in drifted {
+            let tags = drift.diff.to_set();
+            if !tags.as_slice().is_empty() {
+                let group = tags
+                    .as_slice()
+                    .iter()
+                    .map(|tag| (tag.key().to_string(), tag.value().to_string()))
+                    .collect();
+                to_set
+                    .entry(group)
+                    .or_insert_with(|| (tags, Vec::new()))
+                    .1
+                    .push(drift.arn.clone());
+            }
+
+            let keys = drift.diff.removed_keys();
+            if !keys.is_empty() {
+                let group = keys.iter().map(ToString::to_string).collect();
+                to_remove
+                    .entry(group)
+                    .or_insert_with(|| (keys, Vec::new()))
+                    .1
+                    .push(drift.arn.clone());
+            }
+        }
+
+        let mut report = BulkReport::default();
+
+        for (tags, arns) in to_set.into_values() {
+            report.merge(bulk::apply_tags(self.client, &arns, tags, &self.bulk).await);
+        }
+
+        for (keys, arns) in to_remove.into_values() {
+            report.merge(bulk::remove_tags(self.client, &arns, &keys, &self.bulk).await);
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::RawTag;
+
+    #[test]
+    fn display() {
+        let current = TagList::from_vec(vec![RawTag::new("env".to_owned(), "dev".to_owned())]);
+        let desired = TagList::from_vec(vec![
+            RawTag::new("env".to_owned(), "prod".to_owned()),
+            RawTag::new("team".to_owned(), "infra".to_owned()),
+        ]);
+
+        let report = ReconcileReport {
+            dry_run: true,
+            in_sync: vec![Arn::parse("arn:aws:sqs:eu-central-1:123456789012:other").unwrap()],
+            drifted: vec![ResourceDrift {
+                arn: Arn::parse("arn:aws:sqs:eu-central-1:123456789012:queue").unwrap(),
+                diff: current.diff(&desired),
+            }],
+            skipped: Vec::new(),
+            applied: None,
+        };
+
+        assert_eq!(
+            report.to_string(),
+            "arn:aws:sqs:eu-central-1:123456789012:queue\n  \
+             + team = infra\n  \
+             ~ env = dev -> prod\n\
+             1 in sync, 1 drifted, 0 skipped (dry run)"
+        );
+        assert!(report.is_success(), "dry run cannot fail");
+    }
+
+    #[cfg(feature = "testing")]
+    mod mocked {
+        use super::*;
+        use crate::{
+            testing::{block_on, mock_region_client, Matcher, MockHttpClient, MockResponse},
+            Region,
+        };
+
+        fn queue(name: &str) -> Arn {
+            Arn::parse(&format!("arn:aws:sqs:eu-central-1:123456789012:{name}")).unwrap()
+        }
+
+        /// `in-sync` has the desired tags, `drifted` has the wrong `env` and an
+        /// extra tag, `untagged` has no tags and is therefore not returned
+        fn mock() -> MockHttpClient {
+            MockHttpClient::new()
+                .on(
+                    Matcher::action("GetResources"),
+                    MockResponse::ok(
+                        r#"{"ResourceTagMappingList":[
+                            {"ResourceARN":"arn:aws:sqs:eu-central-1:123456789012:in-sync",
+                             "Tags":[{"Key":"env","Value":"prod"},{"Key":"team","Value":"infra"}]},
+                            {"ResourceARN":"arn:aws:sqs:eu-central-1:123456789012:drifted",
+                             "Tags":[{"Key":"env","Value":"dev"},{"Key":"team","Value":"infra"},
+                                     {"Key":"extra","Value":"x"}]}
+                        ],"PaginationToken":""}"#,
+                    ),
+                )
+                .on(Matcher::action("TagResources"), MockResponse::ok("{}"))
+                .on(Matcher::action("UntagResources"), MockResponse::ok("{}"))
+        }
+
+        fn desired() -> TagList {
+            TagList::from_vec(vec![
+                RawTag::new("env".to_owned(), "prod".to_owned()),
+                RawTag::new("team".to_owned(), "infra".to_owned()),
+            ])
+        }
+
+        fn resources() -> Vec<Arn> {
+            vec![queue("in-sync"), queue("drifted"), queue("untagged")]
+        }
+
+        #[test]
+        fn dry_run() {
+            let http = mock();
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let report = block_on(
+                Reconciler::new(&client)
+                    .dry_run(true)
+                    .reconcile(&resources(), &desired()),
+            )
+            .unwrap();
+
+            assert!(report.dry_run);
+            assert_eq!(report.in_sync, [queue("in-sync")]);
+            assert_eq!(
+                report
+                    .drifted
+                    .iter()
+                    .map(|drift| drift.arn.clone())
+                    .collect::<Vec<Arn>>(),
+                [queue("drifted"), queue("untagged")]
+            );
+            assert_eq!(report.applied, None);
+
+            let requests = http.requests().unwrap();
+            assert_eq!(requests.len(), 1);
+            assert_eq!(
+                http.requests_matching(&Matcher::action("GetResources"))
+                    .unwrap()
+                    .len(),
+                1
+            );
+        }
+
+        #[test]
+        fn apply_only_changes() {
+            let http = mock();
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let report =
+                block_on(Reconciler::new(&client).reconcile(&resources(), &desired())).unwrap();
+            assert!(report.is_success());
+            assert_eq!(
+                report.applied.map(|applied| applied.succeeded),
+                Some(vec![queue("drifted"), queue("untagged")])
+            );
+
+            // Without pruning, the extra tag is neither reported nor removed
+            let drifted = report.drifted.first().unwrap();
+            assert_eq!(drifted.arn, queue("drifted"));
+            assert!(drifted.diff.removed.is_empty());
+            assert!(http
+                .requests_matching(&Matcher::action("UntagResources"))
+                .unwrap()
+                .is_empty());
+
+            // Resources in sync are left alone, and only the changed tags are
+            // sent, grouped by the changes a resource needs
+            let mut tagged: Vec<(Vec<String>, BTreeMap<String, String>)> = http
+                .requests_matching(&Matcher::action("TagResources"))
+                .unwrap()
+                .iter()
+                .map(|request| {
+                    (
+                        request.json_param("ResourceARNList").unwrap(),
+                        request.json_param("Tags").unwrap(),
+                    )
+                })
+                .collect();
+            tagged.sort();
+
+            assert_eq!(
+                tagged,
+                [
+                    (
+                        vec![queue("drifted").to_string()],
+                        BTreeMap::from([("env".to_owned(), "prod".to_owned())])
+                    ),
+                    (
+                        vec![queue("untagged").to_string()],
+                        BTreeMap::from([
+                            ("env".to_owned(), "prod".to_owned()),
+                            ("team".to_owned(), "infra".to_owned())
+                        ])
+                    ),
+                ]
+            );
+        }
+
+        #[test]
+        fn prune() {
+            let http = mock();
+            let client = mock_region_client(Region::EuCentral1, http.clone());
+
+            let report = block_on(
+                Reconciler::new(&client)
+                    .prune(true)
+                    .reconcile(&resources(), &desired()),
+            )
+            .unwrap();
+            assert!(report.is_success());
+
+            let drifted = report.drifted.first().unwrap();
+            assert_eq!(
+                drifted.diff.removed,
+                [RawTag::new("extra".to_owned(), "x".to_owned())]
+            );
+
+            let untagged = http
+                .requests_matching(&Matcher::action("UntagResources"))
+                .unwrap();
+            assert_eq!(untagged.len(), 1);
+            let request = untagged.first().unwrap();
+            assert_eq!(
+                request
+                    .json_param::<Vec<String>>("ResourceARNList")
+                    .unwrap(),
+                [queue("drifted").to_string()]
+            );
+            assert_eq!(
+                request.json_param::<Vec<String>>("TagKeys").unwrap(),
+                ["extra"]
+            );
+        }
+    }
+}
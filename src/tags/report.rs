@@ -0,0 +1,354 @@
+//! Inventory and compliance reports over the tags of many resources
+//!
+//! An [`Inventory`] is a list of resources with their tags, usually fetched
+//! via the Resource Groups Tagging API. It is turned into a [`Report`] that
+//! groups the resources by tag key, by tag value or, given the [`Schema`] of
+//! a [`Tags`](super::Tags) struct, by violation of that schema. Reports can be
+//! written as CSV or JSON:
+//!
+//! ```no_run
+//! # use aws_lib::{tags::{Tags, report::{GroupBy, Inventory}}, RegionClient};
+//! #[Tags]
+//! struct Required {
+//!     team: String,
+//!     production: bool,
+//! }
+//!
+//! # async fn f(client: &RegionClient) -> Result<(), aws_lib::Error> {
+//! let inventory = Inventory::fetch(client, &["ec2:instance", "s3"]).await?;
+//!
+//! let schema = Required::schema();
+//! let report = inventory.report(GroupBy::Violation(&schema));
+//! std::fs::write("violations.csv", report.to_csv()).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use super::{Schema, TagList};
+use crate::{arn::Arn, protocol::Value, Error, RegionClient};
+
+const CSV_HEADER: &str = "group,resource,key,value";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedResource {
+    pub arn: Arn,
+    pub tags: TagList,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A required key is not set
+    Missing { key: String },
+    /// The value is not one of the values the schema allows for the key
+    InvalidValue { key: String, value: String },
+}
+
+impl Violation {
+    /// The group of the violation in a [`Report`]
+    pub const fn kind(&self) -> &'static str {
+        match *self {
+            Self::Missing { .. } => "missing",
+            Self::InvalidValue { .. } => "invalid_value",
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match *self {
+            Self::Missing { ref key } | Self::InvalidValue { ref key, .. } => key,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GroupBy<'a> {
+    /// One group per tag key, containing every resource that has the key
+    Key,
+    /// One group per key and value, named `key=value`
+    Value,
+    /// One group per [`Violation::kind()`], containing every resource that
+    /// violates the schema
+    Violation(&'a Schema),
+}
+
+/// A resource in a [`Report`] group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub arn: Arn,
+    pub key: String,
+    /// `None` for missing keys
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Number of resources that were looked at, including those that do not
+    /// appear in any group
+    pub resources: usize,
+    pub groups: BTreeMap<String, Vec<Entry>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inventory {
+    pub resources: Vec<TaggedResource>,
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// All resources of the given types that match all `tag_filters`
+pub(super) async fn get_resources(
+    client: &RegionClient,
+    resource_types: &[&str],
+    tag_filters: Vec<aws_sdk_resourcegroupstagging::types::TagFilter>,
+) -> Result<Vec<TaggedResource>, Error> {
+    let resource_types = resource_types
+        .iter()
+        .map(|&resource_type| resource_type.to_owned())
+        .collect::<Vec<String>>();
+
+    let mut resources = Vec::new();
+    let mut pagination_token = None;
+
+    loop {
+        let output = client
+            .main
+            .tagging
+            .get_resources()
+            .set_resource_type_filters((!resource_types.is_empty()).then(|| resource_types.clone()))
+            .set_tag_filters((!tag_filters.is_empty()).then(|| tag_filters.clone()))
+            .set_pagination_token(pagination_token)
+            .send()
+            .await?;
+
+        for mapping in output.resource_tag_mapping_list.unwrap_or_default() {
+            if let Some(arn) = mapping.resource_arn {
+                resources.push(TaggedResource {
+                    arn: Arn::parse(&arn)?,
+                    tags: mapping.tags.unwrap_or_default().try_into()?,
+                });
+            }
+        }
+
+        match output.pagination_token {
+            Some(token) if !token.is_empty() => pagination_token = Some(token),
+            _ => break,
+        }
+    }
+
+    Ok(resources)
+}
+
+fn count(value: usize) -> Value {
+    Value::from(i64::try_from(value).unwrap_or(i64::MAX))
+}
+
+impl Report {
+    /// One line per entry with the columns `group`, `resource`, `key` and
+    /// `value`, sorted by group
+    pub fn to_csv(&self) -> String {
+        let mut out = format!("{CSV_HEADER}\r\n");
+        for (group, entries) in &self.groups {
+            for entry in entries {
+                out.push_str(&csv_field(group));
+                out.push(',');
+                out.push_str(&csv_field(&entry.arn.to_string()));
+                out.push(',');
+                out.push_str(&csv_field(&entry.key));
+                out.push(',');
+                out.push_str(&csv_field(entry.value.as_deref().unwrap_or_default()));
+                out.push_str("\r\n");
+            }
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        Value::structure([
+            ("resources", count(self.resources)),
+            (
+                "groups",
+                Value::map(self.groups.iter().map(|(group, entries)| {
+                    (
+                        group.as_str(),
+                        Value::structure([
+                            ("count", count(entries.len())),
+                            (
+                                "resources",
+                                Value::list(entries.iter().map(|entry| {
+                                    Value::optional([
+                                        ("resource", Some(Value::from(entry.arn.to_string()))),
+                                        ("key", Some(Value::from(entry.key.as_str()))),
+                                        ("value", entry.value.as_deref().map(Value::from)),
+                                    ])
+                                })),
+                            ),
+                        ]),
+                    )
+                })),
+            ),
+        ])
+        .to_json()
+    }
+}
+
+impl Inventory {
+    pub const fn new(resources: Vec<TaggedResource>) -> Self {
+        Self { resources }
+    }
+
+    /// Fetches all resources of the given types in the region of the client,
+    /// e.g. `ec2:instance` or `s3`. An empty list fetches all types.
+    ///
+    /// The Resource Groups Tagging API only returns resources that have or
+    /// had tags, so resources that were never tagged are missing.
+    pub async fn fetch(client: &RegionClient, resource_types: &[&str]) -> Result<Self, Error> {
+        Ok(Self {
+            resources: get_resources(client, resource_types, Vec::new()).await?,
+        })
+    }
+
+    /// All violations of `schema` by the tags of `resource`
+    pub fn violations(resource: &TaggedResource, schema: &Schema) -> Vec<Violation> {
+        schema
+            .keys
+            .iter()
+            .filter_map(|key| match resource.tags.get(key.key.clone()) {
+                None if key.required => Some(Violation::Missing {
+                    key: key.key.clone(),
+                }),
+                None => None,
+                Some(tag) => (!key.value_type.accepts(tag.value().as_str())).then(|| {
+                    Violation::InvalidValue {
+                        key: key.key.clone(),
+                        value: tag.value().to_string(),
+                    }
+                }),
+            })
+            .collect()
+    }
+
+    pub fn report(&self, group_by: GroupBy<'_>) -> Report {
+        let mut groups: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+
+        for resource in &self.resources {
+            match group_by {
+                GroupBy::Key | GroupBy::Value => {
+                    for tag in resource.tags.as_slice() {
+                        let group = match group_by {
+                            GroupBy::Value => format!("{}={}", tag.key(), tag.value()),
+                            GroupBy::Key | GroupBy::Violation(_) => tag.key().to_string(),
+                        };
+                        groups.entry(group).or_default().push(Entry {
+                            arn: resource.arn.clone(),
+                            key: tag.key().to_string(),
+                            value: Some(tag.value().to_string()),
+                        });
+                    }
+                }
+                GroupBy::Violation(schema) => {
+                    for violation in Self::violations(resource, schema) {
+                        let value = match violation {
+                            Violation::Missing { .. } => None,
+                            Violation::InvalidValue { ref value, .. } => Some(value.clone()),
+                        };
+                        groups
+                            .entry(violation.kind().to_owned())
+                            .or_default()
+                            .push(Entry {
+                                arn: resource.arn.clone(),
+                                key: violation.key().to_owned(),
+                                value,
+                            });
+                    }
+                }
+            }
+        }
+
+        Report {
+            resources: self.resources.len(),
+            groups,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::{RawTag, SchemaKey, ValueType};
+
+    fn resource(arn: &str, tags: &[(&str, &str)]) -> TaggedResource {
+        TaggedResource {
+            arn: Arn::parse(arn).unwrap(),
+            tags: TagList::from_vec(
+                tags.iter()
+                    .map(|&(key, value)| RawTag::new(key.to_owned(), value.to_owned()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn inventory() -> Inventory {
+        Inventory::new(vec![
+            resource(
+                "arn:aws:ec2:eu-central-1:123456789012:instance/i-1",
+                &[("team", "infra"), ("production", "yes")],
+            ),
+            resource(
+                "arn:aws:ec2:eu-central-1:123456789012:instance/i-2",
+                &[("team", "web, frontend")],
+            ),
+        ])
+    }
+
+    #[test]
+    fn group_by_key() {
+        let report = inventory().report(GroupBy::Key);
+
+        assert_eq!(report.resources, 2);
+        assert_eq!(report.groups.get("team").map(Vec::len), Some(2));
+        assert_eq!(report.groups.get("production").map(Vec::len), Some(1));
+        assert_eq!(
+            report.to_csv(),
+            "group,resource,key,value\r\n\
+             production,arn:aws:ec2:eu-central-1:123456789012:instance/i-1,production,yes\r\n\
+             team,arn:aws:ec2:eu-central-1:123456789012:instance/i-1,team,infra\r\n\
+             team,arn:aws:ec2:eu-central-1:123456789012:instance/i-2,team,\"web, frontend\"\r\n"
+        );
+    }
+
+    #[test]
+    fn group_by_violation() {
+        let schema = Schema::new(
+            "Required",
+            vec![
+                SchemaKey::new("team", ValueType::String, true),
+                SchemaKey::new("production", ValueType::Boolean, true),
+            ],
+        );
+
+        let report = inventory().report(GroupBy::Violation(&schema));
+
+        assert_eq!(
+            report.groups.get("invalid_value"),
+            Some(&vec![Entry {
+                arn: Arn::parse("arn:aws:ec2:eu-central-1:123456789012:instance/i-1").unwrap(),
+                key: "production".to_owned(),
+                value: Some("yes".to_owned()),
+            }])
+        );
+        assert_eq!(
+            report.to_json(),
+            concat!(
+                r#"{"resources":2,"groups":{"invalid_value":{"count":1,"resources":[{"resource":"arn:aws:ec2:eu-central-1:123456789012:instance/i-1","key":"production","value":"yes"}]},"#,
+                r#""missing":{"count":1,"resources":[{"resource":"arn:aws:ec2:eu-central-1:123456789012:instance/i-2","key":"production"}]}}}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Tags of single resources by ARN, via the Resource Groups Tagging API
+//!
+//! For services whose tags are only managed by ARN, like ElastiCache and
+//! OpenSearch, so that they need no tag conversions of their own. Like
+//! [bulk](super::bulk) tagging, this needs the `tag:*` permissions in
+//! addition to the tagging permissions of the service.
+
+use std::collections::HashMap;
+
+use aws_sdk_resourcegroupstagging::types::FailureInfo;
+
+use super::{TagKey, TagList};
+use crate::{arn::Arn, Error, RegionClient};
+
+fn check_failures(arn: &Arn, failures: Option<HashMap<String, FailureInfo>>) -> Result<(), Error> {
+    match failures.unwrap_or_default().remove(&arn.to_string()) {
+        None => Ok(()),
+        Some(failure) => Err(Error::TaggingFailed {
+            arn: arn.to_string(),
+            error_code: failure.error_code().map(|code| code.as_str().to_owned()),
+            message: failure.error_message().map(ToOwned::to_owned),
+        }),
+    }
+}
+
+/// Resources that were never tagged are not known to the tagging API, their
+/// tag list is empty
+pub(crate) async fn tags(client: &RegionClient, arn: &Arn) -> Result<TagList, Error> {
+    let arn = arn.to_string();
+    Ok(client
+        .main
+        .tagging
+        .get_resources()
+        .resource_arn_list(arn.as_str())
+        .send()
+        .await?
+        .resource_tag_mapping_list
+        .unwrap_or_default()
+        .into_iter()
+        .find(|mapping| mapping.resource_arn.as_deref() == Some(arn.as_str()))
+        .and_then(|mapping| mapping.tags)
+        .unwrap_or_default()
+        .try_into()?)
+}
+
+pub(crate) async fn add_tags(client: &RegionClient, arn: &Arn, tags: TagList) -> Result<(), Error> {
+    check_failures(
+        arn,
+        client
+            .main
+            .tagging
+            .tag_resources()
+            .resource_arn_list(arn.to_string())
+            .set_tags(Some(tags.into()))
+            .send()
+            .await?
+            .failed_resources_map,
+    )
+}
+
+pub(crate) async fn remove_tags(
+    client: &RegionClient,
+    arn: &Arn,
+    keys: Vec<TagKey>,
+) -> Result<(), Error> {
+    check_failures(
+        arn,
+        client
+            .main
+            .tagging
+            .untag_resources()
+            .resource_arn_list(arn.to_string())
+            .set_tag_keys(Some(keys.into_iter().map(TagKey::into_string).collect()))
+            .send()
+            .await?
+            .failed_resources_map,
+    )
+}
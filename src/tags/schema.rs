@@ -0,0 +1,292 @@
+//! Descriptions of [`macro@Tags`](super::Tags) structs for other tooling
+
+use crate::protocol::Value;
+
+const JSON_SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// The values a tag accepts. Tag values are always strings on the AWS side,
+/// this describes which strings parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueType {
+    /// Any string
+    String,
+    /// `true` or `false`
+    Boolean,
+    /// One of the given strings
+    Enum(Vec<String>),
+    /// A JSON document, for types translated with serde
+    Json,
+}
+
+impl ValueType {
+    /// Whether `value` is valid. JSON documents are not parsed, so every
+    /// value is accepted for them.
+    pub fn accepts(&self, value: &str) -> bool {
+        self.allowed_values()
+            .map_or(true, |values| values.contains(&value))
+    }
+
+    fn allowed_values(&self) -> Option<Vec<&str>> {
+        match *self {
+            Self::String | Self::Json => None,
+            Self::Boolean => Some(vec!["true", "false"]),
+            Self::Enum(ref values) => Some(values.iter().map(String::as_str).collect()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaKey {
+    pub key: String,
+    pub value_type: ValueType,
+    pub required: bool,
+}
+
+impl SchemaKey {
+    pub fn new(key: impl Into<String>, value_type: ValueType, required: bool) -> Self {
+        Self {
+            key: key.into(),
+            value_type,
+            required,
+        }
+    }
+}
+
+/// The tags of a [`macro@Tags`](super::Tags) struct, returned by its
+/// generated `schema()` function
+///
+/// ```
+/// use aws_lib::tags::{Tag, Tags};
+///
+/// #[derive(Tag, Debug, Clone, PartialEq, Eq)]
+/// #[tag(translate = transparent)]
+/// enum Environment {
+///     #[tag(rename = "prod")]
+///     Production,
+///     #[tag(rename = "dev")]
+///     Development,
+/// }
+///
+/// #[Tags]
+/// struct ResourceTags {
+///     owner: String,
+///     #[tag(key = "env")]
+///     environment: Option<Environment>,
+/// }
+///
+/// let schema = ResourceTags::schema();
+/// assert!(schema.to_json_schema().contains(r#""enum":["prod","dev"]"#));
+/// println!("{}", schema.to_terraform("default_tags"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    pub name: String,
+    pub keys: Vec<SchemaKey>,
+}
+
+/// Escapes a string for a quoted HCL string, including template sequences
+fn hcl_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            // Only the template openers `${` and `%{` are escaped, a single
+            // `$` or `%` is taken literally
+            '$' | '%' if chars.peek() == Some(&'{') => {
+                out.push(c);
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn hcl_list(values: &[&str]) -> String {
+    format!(
+        "[{}]",
+        values
+            .iter()
+            .map(|value| hcl_string(value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+impl Schema {
+    pub fn new(name: impl Into<String>, keys: Vec<SchemaKey>) -> Self {
+        Self {
+            name: name.into(),
+            keys,
+        }
+    }
+
+    pub fn required_keys(&self) -> impl Iterator<Item = &str> {
+        self.keys
+            .iter()
+            .filter(|key| key.required)
+            .map(|key| key.key.as_str())
+    }
+
+    /// A JSON Schema for an object of tag keys and values, e.g. the `Tags`
+    /// of a CloudFormation resource in map form. Unknown keys are allowed.
+    pub fn to_json_schema(&self) -> String {
+        Value::structure([
+            ("$schema", Value::from(JSON_SCHEMA_DRAFT)),
+            ("title", Value::from(self.name.as_str())),
+            ("type", Value::from("object")),
+            (
+                "properties",
+                Value::map(self.keys.iter().map(|key| {
+                    let mut property = vec![("type".to_owned(), Value::from("string"))];
+                    if let Some(values) = key.value_type.allowed_values() {
+                        property.push((
+                            "enum".to_owned(),
+                            Value::list(values.into_iter().map(Value::from)),
+                        ));
+                    }
+                    if key.value_type == ValueType::Json {
+                        property.push((
+                            "contentMediaType".to_owned(),
+                            Value::from("application/json"),
+                        ));
+                    }
+                    (key.key.as_str(), Value::Structure(property))
+                })),
+            ),
+            (
+                "required",
+                Value::list(self.required_keys().map(Value::from)),
+            ),
+            ("additionalProperties", Value::from(true)),
+        ])
+        .to_json()
+    }
+
+    /// A Terraform variable named `variable` that validates tags against the
+    /// schema, used as the `default_tags` of the AWS provider
+    pub fn to_terraform(&self, variable: &str) -> String {
+        let var = format!("var.{variable}");
+
+        let mut lines = vec![
+            format!("variable {} {{", hcl_string(variable)),
+            format!(
+                "  description = {}",
+                hcl_string(&format!("Tags of {}", self.name))
+            ),
+            "  type        = map(string)".to_owned(),
+        ];
+
+        let required: Vec<&str> = self.required_keys().collect();
+        if !required.is_empty() {
+            lines.extend([
+                String::new(),
+                "  validation {".to_owned(),
+                format!(
+                    "    condition     = alltrue([for key in {} : contains(keys({var}), key)])",
+                    hcl_list(&required)
+                ),
+                format!(
+                    "    error_message = {}",
+                    hcl_string(&format!("Required tags: {}", required.join(", ")))
+                ),
+                "  }".to_owned(),
+            ]);
+        }
+
+        for key in &self.keys {
+            let Some(values) = key.value_type.allowed_values() else {
+                continue;
+            };
+            let lookup = format!("{var}[{}]", hcl_string(&key.key));
+            lines.extend([
+                String::new(),
+                "  validation {".to_owned(),
+                format!(
+                    "    condition     = !contains(keys({var}), {}) || contains({}, {lookup})",
+                    hcl_string(&key.key),
+                    hcl_list(&values)
+                ),
+                format!(
+                    "    error_message = {}",
+                    hcl_string(&format!(
+                        "Tag {} must be one of: {}",
+                        key.key,
+                        values.join(", ")
+                    ))
+                ),
+                "  }".to_owned(),
+            ]);
+        }
+
+        lines.extend([
+            "}".to_owned(),
+            String::new(),
+            "locals {".to_owned(),
+            format!("  default_tags = {var}"),
+            "}".to_owned(),
+            String::new(),
+            "provider \"aws\" {".to_owned(),
+            "  default_tags {".to_owned(),
+            "    tags = local.default_tags".to_owned(),
+            "  }".to_owned(),
+            "}".to_owned(),
+            String::new(),
+        ]);
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::new(
+            "ResourceTags",
+            vec![
+                SchemaKey::new("owner", ValueType::String, true),
+                SchemaKey::new(
+                    "env",
+                    ValueType::Enum(vec!["prod".to_owned(), "dev".to_owned()]),
+                    false,
+                ),
+                SchemaKey::new("backup", ValueType::Boolean, false),
+            ],
+        )
+    }
+
+    #[test]
+    fn json_schema() {
+        assert_eq!(
+            schema().to_json_schema(),
+            concat!(
+                r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","title":"ResourceTags","type":"object","#,
+                r#""properties":{"owner":{"type":"string"},"env":{"type":"string","enum":["prod","dev"]},"#,
+                r#""backup":{"type":"string","enum":["true","false"]}},"required":["owner"],"additionalProperties":true}"#
+            )
+        );
+    }
+
+    #[test]
+    fn terraform() {
+        let terraform = schema().to_terraform("default_tags");
+
+        assert!(terraform.starts_with("variable \"default_tags\" {\n"));
+        assert!(terraform.contains(
+            "condition     = alltrue([for key in [\"owner\"] : contains(keys(var.default_tags), key)])"
+        ));
+        assert!(terraform.contains(
+            "condition     = !contains(keys(var.default_tags), \"env\") || contains([\"prod\", \"dev\"], var.default_tags[\"env\"])"
+        ));
+        assert!(terraform.contains("tags = local.default_tags"));
+
+        assert_eq!(hcl_string("${var} 100%"), "\"$${var} 100%\"");
+        assert_eq!(hcl_string("%{if} $5 %"), "\"%%{if} $5 %\"");
+    }
+}
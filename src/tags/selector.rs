@@ -0,0 +1,252 @@
+//! Selection of resources by their tags, independent of the service
+
+use super::{
+    report::{self, TaggedResource},
+    TagList,
+};
+use crate::{filter::Filter, Error, RegionClient};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    /// The tag has one of the values
+    Values { key: String, values: Vec<String> },
+    /// The tag is set, regardless of its value
+    Exists { key: String },
+}
+
+impl Condition {
+    fn key(&self) -> &str {
+        match *self {
+            Self::Values { ref key, .. } | Self::Exists { ref key } => key,
+        }
+    }
+
+    fn matches(&self, tags: &TagList) -> bool {
+        let Some(tag) = tags.get(self.key().to_owned()) else {
+            return false;
+        };
+        match *self {
+            Self::Values { ref values, .. } => values.iter().any(|value| tag.value() == value),
+            Self::Exists { .. } => true,
+        }
+    }
+}
+
+/// A query over tags, built from conditions that all have to match
+///
+/// The same selector compiles to filters of the Resource Groups Tagging API
+/// and of EC2 describe calls, and can be evaluated against a [`TagList`]
+/// that was fetched some other way:
+///
+/// ```
+/// use aws_lib::tags::{RawTag, Selector, TagList};
+///
+/// let selector = Selector::new()
+///     .tag("env")
+///     .eq("prod")
+///     .and()
+///     .tag("owner")
+///     .exists();
+///
+/// let tags = TagList::from_vec(vec![
+///     RawTag::new("env".to_owned(), "prod".to_owned()),
+///     RawTag::new("owner".to_owned(), "alice".to_owned()),
+/// ]);
+/// assert!(selector.matches(&tags));
+///
+/// let filters = selector.to_ec2_filters();
+/// assert_eq!(filters[0].name(), "tag:env");
+/// assert_eq!(filters[1].name(), "tag-key");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selector {
+    conditions: Vec<Condition>,
+}
+
+/// A condition on a single tag that is being built. Created by
+/// [`Selector::tag()`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct SelectorTag {
+    selector: Selector,
+    key: String,
+}
+
+impl SelectorTag {
+    fn condition(self, condition: impl FnOnce(String) -> Condition) -> Selector {
+        let mut selector = self.selector;
+        selector.conditions.push(condition(self.key));
+        selector
+    }
+
+    pub fn eq(self, value: impl Into<String>) -> Selector {
+        self.condition(|key| Condition::Values {
+            key,
+            values: vec![value.into()],
+        })
+    }
+
+    /// Matches if the tag has any of the values
+    pub fn any_of(self, values: impl IntoIterator<Item = impl Into<String>>) -> Selector {
+        self.condition(|key| Condition::Values {
+            key,
+            values: values.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// Matches if the tag is set, regardless of its value
+    pub fn exists(self) -> Selector {
+        self.condition(|key| Condition::Exists { key })
+    }
+}
+
+impl Selector {
+    /// A selector without conditions, which matches every resource
+    pub const fn new() -> Self {
+        Self {
+            conditions: Vec::new(),
+        }
+    }
+
+    pub fn tag(self, key: impl Into<String>) -> SelectorTag {
+        SelectorTag {
+            selector: self,
+            key: key.into(),
+        }
+    }
+
+    /// Only for readability, all conditions of a selector have to match
+    #[must_use]
+    pub const fn and(self) -> Self {
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// The conditions as keys and their accepted values. `None` accepts any
+    /// value.
+    pub(crate) fn conditions(&self) -> impl Iterator<Item = (&str, Option<&[String]>)> {
+        self.conditions.iter().map(|condition| match *condition {
+            Condition::Values {
+                ref key,
+                ref values,
+            } => (key.as_str(), Some(values.as_slice())),
+            Condition::Exists { ref key } => (key.as_str(), None),
+        })
+    }
+
+    /// Whether all conditions match the tags
+    pub fn matches(&self, tags: &TagList) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.matches(tags))
+    }
+
+    /// Filters for describe calls of EC2 and Auto Scaling
+    pub fn to_ec2_filters(&self) -> Vec<Filter> {
+        self.conditions
+            .iter()
+            .map(|condition| match *condition {
+                Condition::Values {
+                    ref key,
+                    ref values,
+                } => Filter::tag(key.as_str()).any_of(values.iter().map(String::as_str)),
+                Condition::Exists { ref key } => Filter::new("tag-key", [key.as_str()]),
+            })
+            .collect()
+    }
+
+    /// Filters for `GetResources` of the Resource Groups Tagging API
+    pub fn to_tag_filters(&self) -> Vec<aws_sdk_resourcegroupstagging::types::TagFilter> {
+        self.conditions
+            .iter()
+            .map(|condition| {
+                let filter =
+                    aws_sdk_resourcegroupstagging::types::TagFilter::builder().key(condition.key());
+                match *condition {
+                    Condition::Values { ref values, .. } => {
+                        filter.set_values(Some(values.clone())).build()
+                    }
+                    Condition::Exists { .. } => filter.build(),
+                }
+            })
+            .collect()
+    }
+
+    /// Finds all resources of the given types in the region of the client
+    /// that match the selector, see [`report::Inventory::fetch()`] for the
+    /// resource types
+    pub async fn find(
+        &self,
+        client: &RegionClient,
+        resource_types: &[&str],
+    ) -> Result<Vec<TaggedResource>, Error> {
+        Ok(
+            report::get_resources(client, resource_types, self.to_tag_filters())
+                .await?
+                .into_iter()
+                .filter(|resource| self.matches(&resource.tags))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::RawTag;
+
+    fn tags(tags: &[(&str, &str)]) -> TagList {
+        TagList::from_vec(
+            tags.iter()
+                .map(|&(key, value)| RawTag::new(key.to_owned(), value.to_owned()))
+                .collect(),
+        )
+    }
+
+    fn selector() -> Selector {
+        Selector::new()
+            .tag("env")
+            .any_of(["prod", "staging"])
+            .and()
+            .tag("owner")
+            .exists()
+    }
+
+    #[test]
+    fn matches() {
+        assert!(selector().matches(&tags(&[("env", "prod"), ("owner", "alice")])));
+        assert!(!selector().matches(&tags(&[("env", "dev"), ("owner", "alice")])));
+        assert!(!selector().matches(&tags(&[("env", "staging")])));
+        assert!(Selector::new().matches(&TagList::new()));
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            selector().to_ec2_filters(),
+            vec![
+                Filter::new("tag:env", ["prod", "staging"]),
+                Filter::new("tag-key", ["owner"]),
+            ]
+        );
+
+        let filters = selector().to_tag_filters();
+        assert_eq!(filters.len(), 2);
+        assert_eq!(filters.first().and_then(|filter| filter.key()), Some("env"));
+        assert_eq!(
+            filters
+                .first()
+                .map(aws_sdk_resourcegroupstagging::types::TagFilter::values),
+            Some(["prod".to_owned(), "staging".to_owned()].as_slice())
+        );
+        assert_eq!(
+            filters
+                .get(1)
+                .map(aws_sdk_resourcegroupstagging::types::TagFilter::values),
+            Some([].as_slice())
+        );
+    }
+}
@@ -27,11 +27,12 @@ mod ec2 {
         type Error = ParseTagsError;
 
         fn try_from(list: Vec<aws_sdk_ec2::types::Tag>) -> Result<Self, Self::Error> {
-            Ok(Self(
+            Ok(Self::from_vec(
                 list.into_iter()
                     .map(TryInto::try_into)
                     .collect::<Result<Vec<_>, ParseTagError>>()?,
-            ))
+            )
+            .canonicalize())
         }
     }
 
@@ -119,11 +120,12 @@ mod cloudformation {
         type Error = ParseTagsError;
 
         fn try_from(list: Vec<aws_sdk_cloudformation::types::Tag>) -> Result<Self, Self::Error> {
-            Ok(Self(
+            Ok(Self::from_vec(
                 list.into_iter()
                     .map(TryInto::try_into)
                     .collect::<Result<Vec<_>, ParseTagError>>()?,
-            ))
+            )
+            .canonicalize())
         }
     }
 
@@ -195,11 +197,12 @@ mod efs {
         type Error = ParseTagsError;
 
         fn try_from(list: Vec<aws_sdk_efs::types::Tag>) -> Result<Self, Self::Error> {
-            Ok(Self(
+            Ok(Self::from_vec(
                 list.into_iter()
                     .map(TryInto::try_into)
                     .collect::<Result<Vec<_>, ParseTagError>>()?,
-            ))
+            )
+            .canonicalize())
         }
     }
 
@@ -231,3 +234,1358 @@ mod efs {
         }
     }
 }
+
+/// Some services (e.g. Lambda) represent tags as a plain key-value map
+mod map {
+    use std::{collections::HashMap, hash::BuildHasher};
+
+    use super::super::{RawTag, RawTagValue, TagKey, TagList};
+
+    impl<S> From<HashMap<String, String, S>> for TagList {
+        fn from(map: HashMap<String, String, S>) -> Self {
+            Self::from_vec(
+                map.into_iter()
+                    .map(|(key, value)| RawTag {
+                        key: TagKey(key),
+                        value: RawTagValue(value),
+                    })
+                    .collect(),
+            )
+            .canonicalize()
+        }
+    }
+
+    impl<S: BuildHasher + Default> From<TagList> for HashMap<String, String, S> {
+        fn from(tags: TagList) -> Self {
+            tags.0
+                .into_iter()
+                .map(|tag| (tag.key.0, tag.value.0))
+                .collect()
+        }
+    }
+}
+
+mod ecs {
+    use std::fmt::Debug;
+
+    use super::super::{
+        error::ParseTagAwsError, ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey,
+        TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_ecs::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder().key(key).value(value.0).build()
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_ecs::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder().key(tag.key).value(tag.value.0).build()
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_ecs::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_ecs::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_ecs::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_ecs::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_ecs::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key.ok_or(ParseTagAwsError::AwsKeyNone)?);
+            let value = RawTagValue(
+                tag.value
+                    .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?,
+            );
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_ecs::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_ecs::types::Tag) -> bool {
+            Some(&self.key.0) == other.key.as_ref() && Some(&self.value.0) == other.value.as_ref()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_ecs::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod rds {
+    use std::fmt::Debug;
+
+    use super::super::{
+        error::ParseTagAwsError, ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey,
+        TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_rds::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder().key(key).value(value.0).build()
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_rds::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder().key(tag.key).value(tag.value.0).build()
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_rds::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_rds::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_rds::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_rds::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_rds::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key.ok_or(ParseTagAwsError::AwsKeyNone)?);
+            let value = RawTagValue(
+                tag.value
+                    .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?,
+            );
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_rds::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_rds::types::Tag) -> bool {
+            Some(&self.key.0) == other.key.as_ref() && Some(&self.value.0) == other.value.as_ref()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_rds::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod ssm {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_ssm::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .key(key)
+                .value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_ssm::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_ssm::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_ssm::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_ssm::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_ssm::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_ssm::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value);
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_ssm::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_ssm::types::Tag) -> bool {
+            self.key.0 == other.key && self.value.0 == other.value
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_ssm::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod secretsmanager {
+    use std::fmt::Debug;
+
+    use super::super::{
+        error::ParseTagAwsError, ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey,
+        TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_secretsmanager::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder().key(key).value(value.0).build()
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_secretsmanager::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder().key(tag.key).value(tag.value.0).build()
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_secretsmanager::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_secretsmanager::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_secretsmanager::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_secretsmanager::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_secretsmanager::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key.ok_or(ParseTagAwsError::AwsKeyNone)?);
+            let value = RawTagValue(
+                tag.value
+                    .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?,
+            );
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_secretsmanager::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_secretsmanager::types::Tag) -> bool {
+            Some(&self.key.0) == other.key.as_ref() && Some(&self.value.0) == other.value.as_ref()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_secretsmanager::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+/// KMS uses `tag_key` and `tag_value` instead of `key` and `value`
+mod kms {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_kms::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .tag_key(key)
+                .tag_value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_kms::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .tag_key(tag.key)
+                .tag_value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_kms::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_kms::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_kms::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_kms::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_kms::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.tag_key);
+            let value = RawTagValue(tag.tag_value);
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_kms::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_kms::types::Tag) -> bool {
+            self.key.0 == other.tag_key && self.value.0 == other.tag_value
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_kms::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+/// ELBv2 tags have an optional key and value, like EC2 tags
+mod elbv2 {
+    use std::fmt::Debug;
+
+    use super::super::{
+        error::ParseTagAwsError, ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey,
+        TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_elasticloadbalancingv2::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder().key(key).value(value.0).build()
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_elasticloadbalancingv2::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder().key(tag.key).value(tag.value.0).build()
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_elasticloadbalancingv2::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(
+            list: Vec<aws_sdk_elasticloadbalancingv2::types::Tag>,
+        ) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_elasticloadbalancingv2::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_elasticloadbalancingv2::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_elasticloadbalancingv2::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key.ok_or(ParseTagAwsError::AwsKeyNone)?);
+            let value = RawTagValue(
+                tag.value
+                    .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?,
+            );
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_elasticloadbalancingv2::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_elasticloadbalancingv2::types::Tag) -> bool {
+            Some(&self.key.0) == other.key.as_ref() && Some(&self.value.0) == other.value.as_ref()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_elasticloadbalancingv2::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod ecr {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_ecr::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .key(key)
+                .value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_ecr::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_ecr::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_ecr::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_ecr::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_ecr::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_ecr::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value);
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_ecr::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_ecr::types::Tag) -> bool {
+            self.key.0 == other.key && self.value.0 == other.value
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_ecr::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod eventbridge {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_eventbridge::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .key(key)
+                .value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_eventbridge::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_eventbridge::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_eventbridge::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_eventbridge::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_eventbridge::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_eventbridge::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value);
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_eventbridge::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_eventbridge::types::Tag) -> bool {
+            self.key.0 == other.key && self.value.0 == other.value
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_eventbridge::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod sfn {
+    use std::fmt::Debug;
+
+    use super::super::{
+        error::ParseTagAwsError, ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey,
+        TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_sfn::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder().key(key).value(value.0).build()
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_sfn::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder().key(tag.key).value(tag.value.0).build()
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_sfn::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_sfn::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_sfn::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_sfn::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_sfn::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key.ok_or(ParseTagAwsError::AwsKeyNone)?);
+            let value = RawTagValue(
+                tag.value
+                    .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?,
+            );
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_sfn::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_sfn::types::Tag) -> bool {
+            Some(&self.key.0) == other.key.as_ref() && Some(&self.value.0) == other.value.as_ref()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_sfn::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod organizations {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_organizations::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .key(key)
+                .value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_organizations::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_organizations::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_organizations::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_organizations::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_organizations::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_organizations::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value);
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_organizations::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_organizations::types::Tag) -> bool {
+            self.key.0 == other.key && self.value.0 == other.value
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_organizations::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod s3 {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_s3::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .key(key)
+                .value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_s3::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_s3::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_s3::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_s3::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_s3::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_s3::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value);
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_s3::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_s3::types::Tag) -> bool {
+            self.key.0 == other.key && self.value.0 == other.value
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_s3::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod resourcegroupstagging {
+    use super::super::{ParseTagError, ParseTagsError, RawTag, RawTagValue, TagKey, TagList};
+
+    impl TryFrom<Vec<aws_sdk_resourcegroupstagging::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(
+            list: Vec<aws_sdk_resourcegroupstagging::types::Tag>,
+        ) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl TryFrom<aws_sdk_resourcegroupstagging::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_resourcegroupstagging::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value);
+            Ok(Self { key, value })
+        }
+    }
+}
+
+mod cloudfront {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_cloudfront::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .key(key)
+                .value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_cloudfront::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_cloudfront::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_cloudfront::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_cloudfront::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    // CloudFront tag values are optional, a missing value is the same as an
+    // empty one
+    impl TryFrom<aws_sdk_cloudfront::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_cloudfront::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value.unwrap_or_default());
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_cloudfront::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_cloudfront::types::Tag) -> bool {
+            self.key.0 == other.key && self.value.0 == other.value.as_deref().unwrap_or_default()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_cloudfront::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod athena {
+    use std::fmt::Debug;
+
+    use super::super::{
+        error::ParseTagAwsError, ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey,
+        TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_athena::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder().key(key).value(value.0).build()
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_athena::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder().key(tag.key).value(tag.value.0).build()
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_athena::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_athena::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_athena::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_athena::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_athena::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key.ok_or(ParseTagAwsError::AwsKeyNone)?);
+            let value = RawTagValue(
+                tag.value
+                    .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?,
+            );
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_athena::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_athena::types::Tag) -> bool {
+            Some(&self.key.0) == other.key.as_ref() && Some(&self.value.0) == other.value.as_ref()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_athena::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod kinesis {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_kinesis::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .key(key)
+                .value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_kinesis::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_kinesis::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_kinesis::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_kinesis::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    // Tag values are optional, a missing value is the same as an empty one
+    impl TryFrom<aws_sdk_kinesis::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_kinesis::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value.unwrap_or_default());
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_kinesis::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_kinesis::types::Tag) -> bool {
+            self.key.0 == other.key && self.value.0 == other.value.as_deref().unwrap_or_default()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_kinesis::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+mod firehose {
+    use std::fmt::Debug;
+
+    use super::super::{
+        ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey, TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_firehose::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder()
+                .key(key)
+                .value(value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_firehose::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_firehose::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_firehose::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_firehose::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    // Tag values are optional, a missing value is the same as an empty one
+    impl TryFrom<aws_sdk_firehose::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_firehose::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key);
+            let value = RawTagValue(tag.value.unwrap_or_default());
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_firehose::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_firehose::types::Tag) -> bool {
+            self.key.0 == other.key && self.value.0 == other.value.as_deref().unwrap_or_default()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_firehose::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+/// Session tags are only ever sent to STS, never returned
+mod sts {
+    use super::super::{RawTag, TagList};
+
+    impl From<RawTag> for aws_sdk_sts::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder()
+                .key(tag.key)
+                .value(tag.value.0)
+                .build()
+                .expect("builder misused")
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_sts::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+}
+
+mod cloudwatch {
+    use std::fmt::Debug;
+
+    use super::super::{
+        error::ParseTagAwsError, ParseTagError, ParseTagsError, RawTag, RawTagValue, Tag, TagKey,
+        TagList, TagValue,
+    };
+
+    impl<T> From<Tag<T>> for aws_sdk_cloudwatch::types::Tag
+    where
+        T: Debug + Clone + PartialEq + Eq + Into<String> + Send,
+        T: TagValue<T>,
+    {
+        fn from(tag: Tag<T>) -> Self {
+            let (key, value) = tag.into_parts();
+            Self::builder().key(key).value(value.0).build()
+        }
+    }
+
+    impl From<RawTag> for aws_sdk_cloudwatch::types::Tag {
+        fn from(tag: RawTag) -> Self {
+            Self::builder().key(tag.key).value(tag.value.0).build()
+        }
+    }
+
+    impl TryFrom<Vec<aws_sdk_cloudwatch::types::Tag>> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: Vec<aws_sdk_cloudwatch::types::Tag>) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+
+    impl From<TagList> for Vec<aws_sdk_cloudwatch::types::Tag> {
+        fn from(tags: TagList) -> Self {
+            tags.0.into_iter().map(Into::into).collect()
+        }
+    }
+
+    impl TryFrom<aws_sdk_cloudwatch::types::Tag> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: aws_sdk_cloudwatch::types::Tag) -> Result<Self, Self::Error> {
+            let key = TagKey(tag.key.ok_or(ParseTagAwsError::AwsKeyNone)?);
+            let value = RawTagValue(
+                tag.value
+                    .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?,
+            );
+            Ok(Self { key, value })
+        }
+    }
+
+    impl PartialEq<aws_sdk_cloudwatch::types::Tag> for RawTag {
+        fn eq(&self, other: &aws_sdk_cloudwatch::types::Tag) -> bool {
+            Some(&self.key.0) == other.key.as_ref() && Some(&self.value.0) == other.value.as_ref()
+        }
+    }
+
+    impl PartialEq<RawTag> for aws_sdk_cloudwatch::types::Tag {
+        fn eq(&self, other: &RawTag) -> bool {
+            other.eq(self)
+        }
+    }
+}
+
+/// Tags of services without an SDK client, as `Key` and `Value` members
+mod protocol {
+    use super::super::{
+        error::ParseTagAwsError, ParseTagError, ParseTagsError, RawTag, RawTagValue, TagKey,
+        TagList,
+    };
+    use crate::protocol::{FromXml, ParseXmlError, Value, XmlElement};
+
+    impl From<RawTag> for Value {
+        fn from(tag: RawTag) -> Self {
+            Self::structure([("Key", tag.key.0.into()), ("Value", tag.value.0.into())])
+        }
+    }
+
+    /// The list items are named `Tag`, as expected by the awsQuery and
+    /// restXml services. EC2 and the JSON services ignore the name.
+    impl From<TagList> for Value {
+        fn from(tags: TagList) -> Self {
+            Self::named_list("Tag", tags.0.into_iter().map(Into::into))
+        }
+    }
+
+    // A missing value is the same as an empty one, like for CloudFront
+    impl FromXml for RawTag {
+        fn from_xml(element: &XmlElement) -> Result<Self, ParseXmlError> {
+            Ok(Self {
+                key: TagKey(element.required("Key")?),
+                value: RawTagValue(element.optional("Value")?.unwrap_or_default()),
+            })
+        }
+    }
+
+    impl FromXml for TagList {
+        fn from_xml(element: &XmlElement) -> Result<Self, ParseXmlError> {
+            Ok(Self::from_vec(Vec::from_xml(element)?).canonicalize())
+        }
+
+        fn from_missing() -> Option<Self> {
+            Some(Self::new())
+        }
+    }
+
+    impl TryFrom<&Value> for RawTag {
+        type Error = ParseTagError;
+
+        fn try_from(tag: &Value) -> Result<Self, Self::Error> {
+            let member = |name| tag.get(name).and_then(Value::as_str).map(ToOwned::to_owned);
+            let key = TagKey(member("Key").ok_or(ParseTagAwsError::AwsKeyNone)?);
+            let value = RawTagValue(
+                member("Value")
+                    .ok_or_else(|| ParseTagAwsError::AwsValueNone { key: key.clone() })?,
+            );
+            Ok(Self { key, value })
+        }
+    }
+
+    impl TryFrom<&Value> for TagList {
+        type Error = ParseTagsError;
+
+        fn try_from(list: &Value) -> Result<Self, Self::Error> {
+            Ok(Self::from_vec(
+                list.items()
+                    .iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, ParseTagError>>()?,
+            )
+            .canonicalize())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{RawTag, TagList};
+
+    #[test]
+    fn sdk_tags_are_canonical() {
+        let tag = |key: &str, value: &str| {
+            aws_sdk_ec2::types::Tag::builder()
+                .key(key)
+                .value(value)
+                .build()
+        };
+        let raw = |key: &str, value: &str| RawTag::new(key.to_owned(), value.to_owned());
+
+        let tags: TagList = vec![
+            tag("team", "infra"),
+            tag("env", "prod"),
+            tag("team", "web"),
+            tag("app", "api"),
+        ]
+        .try_into()
+        .unwrap();
+
+        assert_eq!(
+            tags.as_slice(),
+            [raw("app", "api"), raw("env", "prod"), raw("team", "infra")]
+        );
+        assert_eq!(tags.get("team".to_owned()), Some(&raw("team", "infra")));
+        assert_eq!(tags.get("app".to_owned()), Some(&raw("app", "api")));
+
+        let desired = TagList::from_vec(vec![
+            raw("env", "prod"),
+            raw("team", "infra"),
+            raw("app", "api"),
+        ]);
+        assert_eq!(tags, desired);
+        assert!(tags.diff(&desired).is_empty());
+    }
+}
@@ -0,0 +1,751 @@
+//! Testing code that uses the clients of this crate without hitting AWS
+//!
+//! [`MockHttpClient`] answers requests with canned responses. Requests are
+//! matched by their API action, by parameters or by a custom predicate:
+//!
+//! ```no_run
+//! # async fn f() -> Result<(), aws_lib::Error> {
+//! use aws_lib::{testing::{mock_region_client, Matcher, MockHttpClient, MockResponse}, Region};
+//!
+//! let http = MockHttpClient::new().on(
+//!     Matcher::action("DescribeInstances"),
+//!     MockResponse::ok(r#"<DescribeInstancesResponse><reservationSet/></DescribeInstancesResponse>"#),
+//! );
+//!
+//! let client = mock_region_client(Region::EuCentral1, http.clone());
+//! // ... run the code under test against `client` ...
+//! assert_eq!(http.requests()?.len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To avoid writing responses by hand, [`Recorder`] wraps a real HTTP client,
+//! captures all traffic and saves it as fixture file, which can then be
+//! replayed with [`MockHttpClient::from_fixtures()`].
+
+use std::{
+    fmt, fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::Duration,
+};
+
+use aws_sdk_ec2::config::{BehaviorVersion, Credentials, SharedCredentialsProvider};
+use aws_smithy_runtime_api::{
+    client::{
+        http::{
+            HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings,
+            SharedHttpClient, SharedHttpConnector,
+        },
+        orchestrator::{HttpRequest, HttpResponse},
+        result::ConnectorError,
+        runtime_components::RuntimeComponents,
+    },
+    http::StatusCode,
+};
+use aws_smithy_types::{body::SdkBody, byte_stream::ByteStream};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{ClientConfig, Error, ProfileConfig, Region, RegionClient};
+
+/// Headers of the request signature. They contain the access key ID and the
+/// session token of the credentials, so they are never recorded.
+const SIGNING_HEADERS: [&str; 3] = ["authorization", "x-amz-security-token", "x-amz-date"];
+
+/// A request as seen by the HTTP client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Request {
+    pub method: String,
+    pub uri: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl From<&HttpRequest> for Request {
+    fn from(request: &HttpRequest) -> Self {
+        Self {
+            method: request.method().to_owned(),
+            uri: request.uri().to_owned(),
+            headers: request
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect(),
+            body: request
+                .body()
+                .bytes()
+                .map(|body| String::from_utf8_lossy(body).into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Request {
+    fn without_signature(mut self) -> Self {
+        self.headers.retain(|entry| {
+            !SIGNING_HEADERS
+                .iter()
+                .any(|header| entry.0.eq_ignore_ascii_case(header))
+        });
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.0.eq_ignore_ascii_case(name))
+            .map(|header| header.1.as_str())
+    }
+
+    /// The API action, taken from the `X-Amz-Target` header for JSON
+    /// protocols or from the `Action` parameter for query protocols. REST
+    /// protocols (e.g. S3) do not encode the action and return `None`.
+    pub fn action(&self) -> Option<String> {
+        if let Some(target) = self.header("x-amz-target") {
+            return target.rsplit_once('.').map(|(_, action)| action.to_owned());
+        }
+
+        form_param(&self.body, "Action")
+    }
+
+    /// A request parameter, from the form encoded body (query protocols),
+    /// the top level of the JSON body (JSON protocols) or the query string
+    pub fn param(&self, name: &str) -> Option<String> {
+        if let Ok(serde_json::Value::Object(body)) =
+            serde_json::from_str::<serde_json::Value>(&self.body)
+        {
+            return body.get(name).map(|value| match *value {
+                serde_json::Value::String(ref value) => value.clone(),
+                ref value => value.to_string(),
+            });
+        }
+
+        form_param(&self.body, name).or_else(|| {
+            self.uri
+                .split_once('?')
+                .and_then(|(_, query)| form_param(query, name))
+        })
+    }
+
+    /// Like [`param()`](Self::param()), but deserializes the value, e.g. for
+    /// lists and maps in JSON bodies
+    pub fn json_param<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        serde_json::from_str(&self.param(name)?).ok()
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next(), input.next()];
+                let decoded = match hex {
+                    [Some(high), Some(low)] => std::str::from_utf8(&[high, low])
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                    _ => None,
+                };
+                if let Some(decoded) = decoded {
+                    bytes.push(decoded);
+                } else {
+                    bytes.push(b'%');
+                    bytes.extend(hex.into_iter().flatten());
+                }
+            }
+            byte => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn form_param(form: &str, name: &str) -> Option<String> {
+    form.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        (percent_decode(key) == name).then(|| percent_decode(value))
+    })
+}
+
+/// Decides whether a mock response applies to a request
+#[derive(Clone)]
+pub enum Matcher {
+    Any,
+    Action(String),
+    Param { name: String, value: String },
+    Predicate(Arc<dyn Fn(&Request) -> bool + Send + Sync>),
+    All(Vec<Self>),
+}
+
+impl fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Any => write!(f, "Any"),
+            Self::Action(ref action) => f.debug_tuple("Action").field(action).finish(),
+            Self::Param {
+                ref name,
+                ref value,
+            } => f
+                .debug_struct("Param")
+                .field("name", name)
+                .field("value", value)
+                .finish(),
+            Self::Predicate(_) => write!(f, "Predicate(..)"),
+            Self::All(ref matchers) => f.debug_tuple("All").field(matchers).finish(),
+        }
+    }
+}
+
+impl Matcher {
+    pub fn action(action: &str) -> Self {
+        Self::Action(action.to_owned())
+    }
+
+    pub fn param(name: &str, value: &str) -> Self {
+        Self::Param {
+            name: name.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    pub fn predicate(predicate: impl Fn(&Request) -> bool + Send + Sync + 'static) -> Self {
+        Self::Predicate(Arc::new(predicate))
+    }
+
+    pub fn matches(&self, request: &Request) -> bool {
+        match *self {
+            Self::Any => true,
+            Self::Action(ref action) => request.action().as_ref() == Some(action),
+            Self::Param {
+                ref name,
+                ref value,
+            } => request.param(name).as_ref() == Some(value),
+            Self::Predicate(ref predicate) => predicate(request),
+            Self::All(ref matchers) => matchers.iter().all(|matcher| matcher.matches(request)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self::status(200, body)
+    }
+
+    pub fn status(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    fn into_http(self) -> Result<HttpResponse, ConnectorError> {
+        let status = StatusCode::try_from(self.status)
+            .map_err(|e| ConnectorError::other(Box::new(e), None))?;
+
+        let mut response = HttpResponse::new(status, SdkBody::from(self.body));
+        for (name, value) in self.headers {
+            let _previous = response.headers_mut().insert(name, value);
+        }
+
+        Ok(response)
+    }
+}
+
+/// A recorded request together with its response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub request: Request,
+    pub response: MockResponse,
+}
+
+#[derive(Debug)]
+struct Rule {
+    matcher: Matcher,
+    response: MockResponse,
+    /// `None` means the rule applies any number of times
+    remaining: Option<usize>,
+}
+
+#[derive(Debug)]
+struct MockError {
+    message: String,
+}
+
+impl fmt::Display for MockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for MockError {}
+
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>, Error> {
+    mutex.lock().map_err(|_e| Error::InvalidResponseError {
+        message: "mock state poisoned".to_owned(),
+    })
+}
+
+/// A HTTP client that answers with canned responses.
+///
+/// Rules are checked in the order they were added, the first matching rule
+/// wins. Requests without matching rule fail with a connector error. Clones
+/// share their rules and recorded requests.
+#[derive(Debug, Clone, Default)]
+pub struct MockHttpClient {
+    rules: Arc<Mutex<Vec<Rule>>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push_rule(self, matcher: Matcher, response: MockResponse, remaining: Option<usize>) -> Self {
+        if let Ok(mut rules) = self.rules.lock() {
+            rules.push(Rule {
+                matcher,
+                response,
+                remaining,
+            });
+        }
+        self
+    }
+
+    /// Answers all matching requests with `response`
+    #[must_use]
+    pub fn on(self, matcher: Matcher, response: MockResponse) -> Self {
+        self.push_rule(matcher, response, None)
+    }
+
+    /// Answers only the next matching request with `response`. Useful to
+    /// return different responses for consecutive calls, e.g. for
+    /// pagination or waiters.
+    #[must_use]
+    pub fn once(self, matcher: Matcher, response: MockResponse) -> Self {
+        self.push_rule(matcher, response, Some(1))
+    }
+
+    /// Replays a fixture file written by [`Recorder::save()`]. Each fixture
+    /// answers one request with the same action (or, for REST protocols, the
+    /// same method and URI), in recording order.
+    pub fn from_fixtures(path: &Path) -> Result<Self, Error> {
+        let fixture_error = |message: String| Error::Fixture {
+            path: path.display().to_string(),
+            message,
+        };
+
+        let fixtures: Vec<Fixture> = serde_json::from_str(
+            &fs::read_to_string(path).map_err(|e| fixture_error(e.to_string()))?,
+        )
+        .map_err(|e| fixture_error(e.to_string()))?;
+
+        Ok(fixtures.into_iter().fold(Self::new(), |client, fixture| {
+            let matcher = if let Some(action) = fixture.request.action() {
+                Matcher::Action(action)
+            } else {
+                let method = fixture.request.method.clone();
+                let uri = fixture.request.uri.clone();
+                Matcher::predicate(move |request| request.method == method && request.uri == uri)
+            };
+            client.once(matcher, fixture.response)
+        }))
+    }
+
+    /// All requests received so far
+    pub fn requests(&self) -> Result<Vec<Request>, Error> {
+        Ok(lock(&self.requests)?.clone())
+    }
+
+    /// The requests received so far that match `matcher`
+    pub fn requests_matching(&self, matcher: &Matcher) -> Result<Vec<Request>, Error> {
+        Ok(lock(&self.requests)?
+            .iter()
+            .filter(|request| matcher.matches(request))
+            .cloned()
+            .collect())
+    }
+
+    fn respond(&self, request: &Request) -> Result<MockResponse, Error> {
+        lock(&self.requests)?.push(request.clone());
+
+        let response = lock(&self.rules)?.iter_mut().find_map(|rule| {
+            if rule.remaining == Some(0) || !rule.matcher.matches(request) {
+                return None;
+            }
+            rule.remaining = rule.remaining.map(|remaining| remaining.saturating_sub(1));
+            Some(rule.response.clone())
+        });
+
+        response.ok_or_else(|| Error::InvalidResponseError {
+            message: format!(
+                "no mock response for {} {} (action {})",
+                request.method,
+                request.uri,
+                request.action().as_deref().unwrap_or("unknown")
+            ),
+        })
+    }
+}
+
+impl HttpConnector for MockHttpClient {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let response = self
+            .respond(&Request::from(&request))
+            .map_err(|e| {
+                ConnectorError::other(
+                    Box::new(MockError {
+                        message: e.to_string(),
+                    }),
+                    None,
+                )
+            })
+            .and_then(MockResponse::into_http);
+
+        HttpConnectorFuture::ready(response)
+    }
+}
+
+impl HttpClient for MockHttpClient {
+    fn http_connector(
+        &self,
+        _settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(self.clone())
+    }
+}
+
+/// Delays every response of a [`MockHttpClient`] and records the highest
+/// number of requests in flight at the same time, to test concurrency limits
+#[derive(Debug, Clone)]
+pub struct DelayedHttpClient {
+    inner: MockHttpClient,
+    delay: Duration,
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl DelayedHttpClient {
+    pub fn new(inner: MockHttpClient, delay: Duration) -> Self {
+        Self {
+            inner,
+            delay,
+            in_flight: Arc::default(),
+            max_in_flight: Arc::default(),
+        }
+    }
+
+    pub const fn inner(&self) -> &MockHttpClient {
+        &self.inner
+    }
+
+    /// The highest number of requests that were in flight at the same time
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+impl HttpConnector for DelayedHttpClient {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let response = self.inner.call(request);
+        let delay = self.delay;
+        let in_flight = Arc::clone(&self.in_flight);
+        let max_in_flight = Arc::clone(&self.max_in_flight);
+
+        HttpConnectorFuture::new(async move {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst).saturating_add(1);
+            let _previous = max_in_flight.fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(delay).await;
+            let _previous = in_flight.fetch_sub(1, Ordering::SeqCst);
+            response.await
+        })
+    }
+}
+
+impl HttpClient for DelayedHttpClient {
+    fn http_connector(
+        &self,
+        _settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(self.clone())
+    }
+}
+
+/// Passes requests through to a real HTTP client and records them as
+/// fixtures
+///
+/// The headers of the request signature (`Authorization`,
+/// `X-Amz-Security-Token` and `X-Amz-Date`) are left out, so fixture files
+/// can be committed.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    inner: SharedHttpClient,
+    fixtures: Arc<Mutex<Vec<Fixture>>>,
+}
+
+#[derive(Debug)]
+struct RecordingConnector {
+    inner: SharedHttpConnector,
+    fixtures: Arc<Mutex<Vec<Fixture>>>,
+}
+
+impl HttpConnector for RecordingConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let captured = Request::from(&request).without_signature();
+        let call = self.inner.call(request);
+        let fixtures = Arc::clone(&self.fixtures);
+
+        HttpConnectorFuture::new(async move {
+            let mut response = call.await?;
+
+            // The body is a stream and can only be read once, so it is
+            // buffered and put back into the response
+            let body = ByteStream::new(response.take_body())
+                .collect()
+                .await
+                .map_err(|e| ConnectorError::io(Box::new(e)))?
+                .into_bytes();
+
+            let recorded = MockResponse {
+                status: response.status().as_u16(),
+                headers: response
+                    .headers()
+                    .iter()
+                    .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                    .collect(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            };
+
+            *response.body_mut() = SdkBody::from(body);
+
+            if let Ok(mut fixtures) = fixtures.lock() {
+                fixtures.push(Fixture {
+                    request: captured,
+                    response: recorded,
+                });
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+impl HttpClient for Recorder {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(RecordingConnector {
+            inner: self.inner.http_connector(settings, components),
+            fixtures: Arc::clone(&self.fixtures),
+        })
+    }
+}
+
+impl Recorder {
+    /// `inner` is the HTTP client that actually talks to AWS
+    pub fn new(inner: impl HttpClient + 'static) -> Self {
+        Self {
+            inner: SharedHttpClient::new(inner),
+            fixtures: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn fixtures(&self) -> Result<Vec<Fixture>, Error> {
+        Ok(lock(&self.fixtures)?.clone())
+    }
+
+    /// Writes all recorded fixtures to `path` as JSON
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let fixture_error = |message: String| Error::Fixture {
+            path: path.display().to_string(),
+            message,
+        };
+
+        let json = serde_json::to_string_pretty(&self.fixtures()?)
+            .map_err(|e| fixture_error(e.to_string()))?;
+
+        fs::write(path, json).map_err(|e| fixture_error(e.to_string()))
+    }
+
+    /// Loads clients with the given profiles like
+    /// [`load_sdk_clients()`](crate::load_sdk_clients), but records all
+    /// traffic
+    pub async fn load_sdk_clients(
+        &self,
+        regions: &[Region],
+        profile_config: &ProfileConfig,
+    ) -> Vec<RegionClient> {
+        super::load_sdk_clients_with(
+            regions,
+            profile_config,
+            |loader, _profile| loader.http_client(self.clone()),
+            &ClientConfig::default(),
+        )
+        .await
+    }
+}
+
+/// A client for `region` that sends all requests to `http_client`, using
+/// static dummy credentials
+pub fn mock_region_client(region: Region, http_client: impl HttpClient + 'static) -> RegionClient {
+    mock_region_client_with(region, http_client, &ClientConfig::default())
+}
+
+/// Like [`mock_region_client()`], e.g. to test with a metrics or audit sink
+pub fn mock_region_client_with(
+    region: Region,
+    http_client: impl HttpClient + 'static,
+    client_config: &ClientConfig,
+) -> RegionClient {
+    let config = aws_config::SdkConfig::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(aws_config::Region::new(region.as_str()))
+        .credentials_provider(SharedCredentialsProvider::new(Credentials::new(
+            "AKIDMOCK", "mock", None, None, "mock",
+        )))
+        .retry_config(aws_config::retry::RetryConfig::disabled())
+        .http_client(http_client)
+        .build();
+
+    super::region_client_from_configs(region, &config, &config, &config, &config, client_config)
+}
+
+/// Runs `future` on a new single-threaded runtime with timers enabled, for
+/// tests of code that sleeps, e.g. between retries
+#[cfg(test)]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("failed to build test runtime")
+        .block_on(future)
+}
+
+/// The ARN of a made-up EC2 instance, distinct for each `index`
+#[cfg(test)]
+pub(crate) fn instance_arn(index: usize) -> crate::arn::Arn {
+    crate::arn::Arn::parse(&format!(
+        "arn:aws:ec2:eu-central-1:123456789012:instance/i-{index:017}"
+    ))
+    .expect("invalid test ARN")
+}
+
+/// A `TagResources` or `UntagResources` response in which `arn` failed
+#[cfg(test)]
+pub(crate) fn tagging_failure(arn: &crate::arn::Arn, status: u16, code: &str) -> MockResponse {
+    MockResponse::ok(format!(
+        r#"{{"FailedResourcesMap":{{"{arn}":{{"StatusCode":{status},"ErrorCode":"{code}","ErrorMessage":"failed"}}}}}}"#
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&str, &str)], body: &str) -> Request {
+        Request {
+            method: "POST".to_owned(),
+            uri: "https://example.com/".to_owned(),
+            headers: headers
+                .iter()
+                .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+                .collect(),
+            body: body.to_owned(),
+        }
+    }
+
+    #[test]
+    fn query_protocol() {
+        let request = request(
+            &[],
+            "Action=DescribeInstances&Version=2016-11-15&InstanceId.1=i-123&Filter.1.Value.1=a+b%2Fc",
+        );
+
+        assert_eq!(request.action().as_deref(), Some("DescribeInstances"));
+        assert_eq!(request.param("InstanceId.1").as_deref(), Some("i-123"));
+        assert_eq!(request.param("Filter.1.Value.1").as_deref(), Some("a b/c"));
+        assert!(Matcher::All(vec![
+            Matcher::action("DescribeInstances"),
+            Matcher::param("InstanceId.1", "i-123"),
+        ])
+        .matches(&request));
+    }
+
+    #[test]
+    fn json_protocol() {
+        let request = request(
+            &[("X-Amz-Target", "AmazonSSM.GetParameter")],
+            r#"{"Name": "/app/config", "WithDecryption": true}"#,
+        );
+
+        assert_eq!(request.action().as_deref(), Some("GetParameter"));
+        assert_eq!(request.param("Name").as_deref(), Some("/app/config"));
+        assert_eq!(request.param("WithDecryption").as_deref(), Some("true"));
+    }
+
+    #[test]
+    fn once_rules_are_consumed() {
+        let client = MockHttpClient::new()
+            .once(Matcher::Any, MockResponse::ok("first"))
+            .on(Matcher::Any, MockResponse::ok("rest"));
+
+        let request = request(&[], "");
+        let bodies = (0..3_u32)
+            .map(|_| client.respond(&request).map(|response| response.body))
+            .collect::<Result<Vec<String>, Error>>()
+            .unwrap();
+
+        assert_eq!(bodies, vec!["first", "rest", "rest"]);
+        assert_eq!(client.requests().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn signature_is_not_recorded() {
+        let request = request(
+            &[
+                (
+                    "Authorization",
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240101/eu-central-1/ec2/aws4_request, SignedHeaders=host, Signature=abc",
+                ),
+                ("X-Amz-Security-Token", "token"),
+                ("x-amz-date", "20240101T000000Z"),
+                ("content-type", "application/x-www-form-urlencoded"),
+            ],
+            "Action=DescribeInstances",
+        )
+        .without_signature();
+
+        assert_eq!(
+            request.headers,
+            vec![(
+                "content-type".to_owned(),
+                "application/x-www-form-urlencoded".to_owned()
+            )]
+        );
+        assert_eq!(request.action().as_deref(), Some("DescribeInstances"));
+    }
+}
@@ -0,0 +1,955 @@
+//! VPCs, subnets, security groups and route tables
+//!
+//! Security group rules are built from the protocol and ports first, then
+//! the source (or destination, for egress rules):
+//!
+//! ```rust
+//! use aws_lib::vpc::{Cidr, SecurityGroupRule};
+//!
+//! let rules = [
+//!     SecurityGroupRule::tcp(443).from_cidr("0.0.0.0/0".parse().unwrap()),
+//!     SecurityGroupRule::tcp_range(8000, 8080)
+//!         .from_cidr("10.0.0.0/16".parse().unwrap())
+//!         .with_description("internal services"),
+//!     SecurityGroupRule::icmp().from_cidr("::/0".parse().unwrap()),
+//! ];
+//!
+//! assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+//! ```
+
+use std::{fmt, net::IpAddr, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{
+    filter::Filter,
+    tags::{Tag, TagKey, TagList},
+    AvailabilityZone, Error, InstanceId, RegionClient, SecurityGroup, SecurityGroupId, Subnet,
+    SubnetId,
+};
+
+string_newtype!(VpcId);
+
+impl VpcId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+string_newtype!(RouteTableId);
+
+impl RouteTableId {
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// An IPv4 or IPv6 network in CIDR notation, e.g. `10.0.0.0/16`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl Cidr {
+    pub fn new(address: IpAddr, prefix_len: u8) -> Result<Self, Error> {
+        let max = if address.is_ipv4() { 32 } else { 128 };
+        if prefix_len > max {
+            return Err(Error::InvalidCidr {
+                value: format!("{address}/{prefix_len}"),
+                message: format!("prefix length must be at most {max}"),
+            });
+        }
+
+        Ok(Self {
+            address,
+            prefix_len,
+        })
+    }
+
+    pub const fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub const fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub const fn is_ipv4(&self) -> bool {
+        self.address.is_ipv4()
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix_len)
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| Error::InvalidCidr {
+            value: value.to_owned(),
+            message,
+        };
+
+        let (address, prefix_len) = value
+            .split_once('/')
+            .ok_or_else(|| invalid("missing prefix length".to_owned()))?;
+
+        Self::new(
+            address
+                .parse()
+                .map_err(|e| invalid(format!("invalid address: {e}")))?,
+            prefix_len
+                .parse()
+                .map_err(|e| invalid(format!("invalid prefix length: {e}")))?,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Cidr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Cidr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn tag_specification(
+    resource_type: aws_sdk_ec2::types::ResourceType,
+    tags: TagList,
+) -> aws_sdk_ec2::types::TagSpecification {
+    aws_sdk_ec2::types::TagSpecification::builder()
+        .resource_type(resource_type)
+        .set_tags(Some(tags.into()))
+        .build()
+}
+
+async fn add_tags(client: &RegionClient, resource: &str, tags: TagList) -> Result<(), Error> {
+    let _output = client
+        .main
+        .ec2
+        .create_tags()
+        .resources(resource)
+        .set_tags(Some(tags.into()))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+async fn remove_tags(
+    client: &RegionClient,
+    resource: &str,
+    keys: Vec<TagKey>,
+) -> Result<(), Error> {
+    let _output = client
+        .main
+        .ec2
+        .delete_tags()
+        .resources(resource)
+        .set_tags(Some(
+            keys.into_iter()
+                .map(|key| {
+                    aws_sdk_ec2::types::Tag::builder()
+                        .key(key.into_string())
+                        .build()
+                })
+                .collect(),
+        ))
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+fn extract<T>(value: Option<T>, entity: &str) -> Result<T, Error> {
+    value.ok_or_else(|| Error::UnexpectedNoneValue {
+        entity: entity.to_owned(),
+    })
+}
+
+fn parse_cidr(value: Option<&str>) -> Result<Option<Cidr>, Error> {
+    value.map(str::parse).transpose()
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Vpc {
+    id: VpcId,
+    cidr_block: Option<Cidr>,
+    is_default: bool,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_ec2::types::Vpc> for Vpc {
+    type Error = Error;
+
+    fn try_from(vpc: aws_sdk_ec2::types::Vpc) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: VpcId(extract(vpc.vpc_id, "Vpc.vpc_id")?),
+            cidr_block: parse_cidr(vpc.cidr_block.as_deref())?,
+            is_default: vpc.is_default.unwrap_or(false),
+            tags: vpc.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl Vpc {
+    /// Returns all VPCs matching all `filters`
+    pub async fn list(client: &RegionClient, filters: Vec<Filter>) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_vpcs()
+            .set_filters(Some(filters.into_iter().map(Into::into).collect()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// `cidr` must be an IPv4 network, IPv6 networks can only be
+    /// associated after creation
+    pub async fn create(client: &RegionClient, cidr: Cidr, tags: TagList) -> Result<Self, Error> {
+        extract(
+            client
+                .main
+                .ec2
+                .create_vpc()
+                .cidr_block(cidr.to_string())
+                .tag_specifications(tag_specification(
+                    aws_sdk_ec2::types::ResourceType::Vpc,
+                    tags,
+                ))
+                .send()
+                .await?
+                .vpc,
+            "CreateVpcOutput.vpc",
+        )?
+        .try_into()
+    }
+
+    /// Fails if the VPC still contains subnets, security groups or other
+    /// resources
+    pub async fn delete(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .delete_vpc()
+            .vpc_id(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub const fn id(&self) -> &VpcId {
+        &self.id
+    }
+
+    pub const fn cidr_block(&self) -> Option<&Cidr> {
+        self.cidr_block.as_ref()
+    }
+
+    pub const fn is_default(&self) -> bool {
+        self.is_default
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.id.as_str(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.id.as_str(), keys).await
+    }
+}
+
+impl Subnet {
+    /// Returns all subnets matching all `filters`
+    pub async fn list(client: &RegionClient, filters: Vec<Filter>) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_subnets()
+            .set_filters(Some(filters.into_iter().map(Into::into).collect()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn create(
+        client: &RegionClient,
+        vpc: &VpcId,
+        cidr: Cidr,
+        availability_zone: &AvailabilityZone,
+        tags: TagList,
+    ) -> Result<Self, Error> {
+        let builder = client.main.ec2.create_subnet().vpc_id(vpc.as_str());
+        let builder = if cidr.is_ipv4() {
+            builder.cidr_block(cidr.to_string())
+        } else {
+            builder.ipv6_cidr_block(cidr.to_string()).ipv6_native(true)
+        };
+
+        extract(
+            builder
+                .availability_zone(availability_zone.to_string())
+                .tag_specifications(tag_specification(
+                    aws_sdk_ec2::types::ResourceType::Subnet,
+                    tags,
+                ))
+                .send()
+                .await?
+                .subnet,
+            "CreateSubnetOutput.subnet",
+        )?
+        .try_into()
+    }
+
+    pub async fn delete(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .delete_subnet()
+            .subnet_id(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.id.as_str(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.id.as_str(), keys).await
+    }
+}
+
+impl TryFrom<aws_sdk_ec2::types::SecurityGroup> for SecurityGroup {
+    type Error = Error;
+
+    fn try_from(group: aws_sdk_ec2::types::SecurityGroup) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: SecurityGroupId(extract(group.group_id, "SecurityGroup.group_id")?),
+            name: group.group_name,
+            vpc_id: group.vpc_id.map(VpcId),
+            tags: group.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl SecurityGroup {
+    /// Returns all security groups matching all `filters`
+    pub async fn list(client: &RegionClient, filters: Vec<Filter>) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_security_groups()
+            .set_filters(Some(filters.into_iter().map(Into::into).collect()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    /// Creates a group without ingress rules. Egress to everywhere is
+    /// allowed by default.
+    pub async fn create(
+        client: &RegionClient,
+        vpc: &VpcId,
+        name: &str,
+        description: &str,
+        tags: TagList,
+    ) -> Result<Self, Error> {
+        let output = client
+            .main
+            .ec2
+            .create_security_group()
+            .vpc_id(vpc.as_str())
+            .group_name(name)
+            .description(description)
+            .tag_specifications(tag_specification(
+                aws_sdk_ec2::types::ResourceType::SecurityGroup,
+                tags.clone(),
+            ))
+            .send()
+            .await?;
+
+        Ok(Self {
+            id: SecurityGroupId(extract(
+                output.group_id,
+                "CreateSecurityGroupOutput.group_id",
+            )?),
+            name: Some(name.to_owned()),
+            vpc_id: Some(vpc.clone()),
+            tags,
+        })
+    }
+
+    pub async fn delete(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .delete_security_group()
+            .group_id(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub const fn id(&self) -> &SecurityGroupId {
+        &self.id
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub const fn vpc_id(&self) -> Option<&VpcId> {
+        self.vpc_id.as_ref()
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    pub async fn authorize_ingress(
+        &self,
+        client: &RegionClient,
+        rules: &[SecurityGroupRule],
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .authorize_security_group_ingress()
+            .group_id(self.id.as_str())
+            .set_ip_permissions(Some(rules.iter().map(SecurityGroupRule::to_sdk).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn revoke_ingress(
+        &self,
+        client: &RegionClient,
+        rules: &[SecurityGroupRule],
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .revoke_security_group_ingress()
+            .group_id(self.id.as_str())
+            .set_ip_permissions(Some(rules.iter().map(SecurityGroupRule::to_sdk).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    /// For egress rules, the source of the rule is the destination
+    pub async fn authorize_egress(
+        &self,
+        client: &RegionClient,
+        rules: &[SecurityGroupRule],
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .authorize_security_group_egress()
+            .group_id(self.id.as_str())
+            .set_ip_permissions(Some(rules.iter().map(SecurityGroupRule::to_sdk).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn revoke_egress(
+        &self,
+        client: &RegionClient,
+        rules: &[SecurityGroupRule],
+    ) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .revoke_security_group_egress()
+            .group_id(self.id.as_str())
+            .set_ip_permissions(Some(rules.iter().map(SecurityGroupRule::to_sdk).collect()))
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.id.as_str(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.id.as_str(), keys).await
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpProtocol {
+    Tcp,
+    Udp,
+    Icmp,
+    Icmpv6,
+    /// All protocols and ports
+    All,
+}
+
+impl IpProtocol {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+            Self::Icmp => "icmp",
+            Self::Icmpv6 => "icmpv6",
+            Self::All => "-1",
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleSource {
+    Cidr(Cidr),
+    SecurityGroup(SecurityGroupId),
+    PrefixList(String),
+}
+
+/// Protocol and ports of a rule, without a source yet. Created by the
+/// constructors of [`SecurityGroupRule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RulePorts {
+    protocol: IpProtocol,
+    from_port: i32,
+    to_port: i32,
+}
+
+impl RulePorts {
+    const fn source(self, source: RuleSource) -> SecurityGroupRule {
+        SecurityGroupRule {
+            protocol: self.protocol,
+            from_port: self.from_port,
+            to_port: self.to_port,
+            source,
+            description: None,
+        }
+    }
+
+    pub const fn from_cidr(self, cidr: Cidr) -> SecurityGroupRule {
+        self.source(RuleSource::Cidr(cidr))
+    }
+
+    pub fn from_security_group(self, group: &SecurityGroupId) -> SecurityGroupRule {
+        self.source(RuleSource::SecurityGroup(group.clone()))
+    }
+
+    pub fn from_prefix_list(self, prefix_list_id: impl Into<String>) -> SecurityGroupRule {
+        self.source(RuleSource::PrefixList(prefix_list_id.into()))
+    }
+}
+
+/// A single ingress or egress rule of a security group
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityGroupRule {
+    protocol: IpProtocol,
+    from_port: i32,
+    to_port: i32,
+    source: RuleSource,
+    description: Option<String>,
+}
+
+impl SecurityGroupRule {
+    pub fn tcp(port: u16) -> RulePorts {
+        Self::tcp_range(port, port)
+    }
+
+    pub fn tcp_range(from: u16, to: u16) -> RulePorts {
+        RulePorts {
+            protocol: IpProtocol::Tcp,
+            from_port: from.into(),
+            to_port: to.into(),
+        }
+    }
+
+    pub fn udp(port: u16) -> RulePorts {
+        Self::udp_range(port, port)
+    }
+
+    pub fn udp_range(from: u16, to: u16) -> RulePorts {
+        RulePorts {
+            protocol: IpProtocol::Udp,
+            from_port: from.into(),
+            to_port: to.into(),
+        }
+    }
+
+    /// All ICMP types. Use [`icmpv6()`](Self::icmpv6()) for IPv6 sources.
+    pub const fn icmp() -> RulePorts {
+        RulePorts {
+            protocol: IpProtocol::Icmp,
+            from_port: -1,
+            to_port: -1,
+        }
+    }
+
+    pub const fn icmpv6() -> RulePorts {
+        RulePorts {
+            protocol: IpProtocol::Icmpv6,
+            from_port: -1,
+            to_port: -1,
+        }
+    }
+
+    /// All traffic
+    pub const fn all() -> RulePorts {
+        RulePorts {
+            protocol: IpProtocol::All,
+            from_port: -1,
+            to_port: -1,
+        }
+    }
+
+    #[must_use]
+    pub fn with_description(self, description: impl Into<String>) -> Self {
+        Self {
+            description: Some(description.into()),
+            ..self
+        }
+    }
+
+    pub const fn protocol(&self) -> IpProtocol {
+        self.protocol
+    }
+
+    /// `None` for rules that are not limited to ports
+    pub const fn ports(&self) -> Option<(i32, i32)> {
+        match self.protocol {
+            IpProtocol::Tcp | IpProtocol::Udp => Some((self.from_port, self.to_port)),
+            IpProtocol::Icmp | IpProtocol::Icmpv6 | IpProtocol::All => None,
+        }
+    }
+
+    pub const fn source(&self) -> &RuleSource {
+        &self.source
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn to_sdk(&self) -> aws_sdk_ec2::types::IpPermission {
+        let builder = aws_sdk_ec2::types::IpPermission::builder()
+            .ip_protocol(self.protocol.as_str())
+            .from_port(self.from_port)
+            .to_port(self.to_port);
+
+        let description = self.description.clone();
+
+        match self.source {
+            RuleSource::Cidr(ref cidr) if cidr.is_ipv4() => builder.ip_ranges(
+                aws_sdk_ec2::types::IpRange::builder()
+                    .cidr_ip(cidr.to_string())
+                    .set_description(description)
+                    .build(),
+            ),
+            RuleSource::Cidr(ref cidr) => builder.ipv6_ranges(
+                aws_sdk_ec2::types::Ipv6Range::builder()
+                    .cidr_ipv6(cidr.to_string())
+                    .set_description(description)
+                    .build(),
+            ),
+            RuleSource::SecurityGroup(ref group) => builder.user_id_group_pairs(
+                aws_sdk_ec2::types::UserIdGroupPair::builder()
+                    .group_id(group.as_str())
+                    .set_description(description)
+                    .build(),
+            ),
+            RuleSource::PrefixList(ref prefix_list_id) => builder.prefix_list_ids(
+                aws_sdk_ec2::types::PrefixListId::builder()
+                    .prefix_list_id(prefix_list_id)
+                    .set_description(description)
+                    .build(),
+            ),
+        }
+        .build()
+    }
+}
+
+/// Where a route sends its traffic
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteTarget {
+    InternetGateway(String),
+    NatGateway(String),
+    TransitGateway(String),
+    VpcPeeringConnection(String),
+    Instance(InstanceId),
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct RouteTable {
+    id: RouteTableId,
+    vpc_id: Option<VpcId>,
+    associated_subnets: Vec<SubnetId>,
+    tags: TagList,
+}
+
+impl TryFrom<aws_sdk_ec2::types::RouteTable> for RouteTable {
+    type Error = Error;
+
+    fn try_from(table: aws_sdk_ec2::types::RouteTable) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: RouteTableId(extract(table.route_table_id, "RouteTable.route_table_id")?),
+            vpc_id: table.vpc_id.map(VpcId),
+            associated_subnets: table
+                .associations
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|association| association.subnet_id.map(SubnetId))
+                .collect(),
+            tags: table.tags.unwrap_or_default().try_into()?,
+        })
+    }
+}
+
+impl RouteTable {
+    /// Returns all route tables matching all `filters`
+    pub async fn list(client: &RegionClient, filters: Vec<Filter>) -> Result<Vec<Self>, Error> {
+        client
+            .main
+            .ec2
+            .describe_route_tables()
+            .set_filters(Some(filters.into_iter().map(Into::into).collect()))
+            .into_paginator()
+            .items()
+            .send()
+            .try_collect()
+            .await?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    pub async fn create(client: &RegionClient, vpc: &VpcId, tags: TagList) -> Result<Self, Error> {
+        extract(
+            client
+                .main
+                .ec2
+                .create_route_table()
+                .vpc_id(vpc.as_str())
+                .tag_specifications(tag_specification(
+                    aws_sdk_ec2::types::ResourceType::RouteTable,
+                    tags,
+                ))
+                .send()
+                .await?
+                .route_table,
+            "CreateRouteTableOutput.route_table",
+        )?
+        .try_into()
+    }
+
+    /// Fails if the table is still associated with subnets
+    pub async fn delete(&self, client: &RegionClient) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .delete_route_table()
+            .route_table_id(self.id.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_route(
+        &self,
+        client: &RegionClient,
+        destination: Cidr,
+        target: &RouteTarget,
+    ) -> Result<(), Error> {
+        let builder = client
+            .main
+            .ec2
+            .create_route()
+            .route_table_id(self.id.as_str());
+
+        let builder = if destination.is_ipv4() {
+            builder.destination_cidr_block(destination.to_string())
+        } else {
+            builder.destination_ipv6_cidr_block(destination.to_string())
+        };
+
+        let builder = match *target {
+            RouteTarget::InternetGateway(ref id) => builder.gateway_id(id),
+            RouteTarget::NatGateway(ref id) => builder.nat_gateway_id(id),
+            RouteTarget::TransitGateway(ref id) => builder.transit_gateway_id(id),
+            RouteTarget::VpcPeeringConnection(ref id) => builder.vpc_peering_connection_id(id),
+            RouteTarget::Instance(ref id) => builder.instance_id(id.as_str()),
+        };
+
+        let _output = builder.send().await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_route(
+        &self,
+        client: &RegionClient,
+        destination: Cidr,
+    ) -> Result<(), Error> {
+        let builder = client
+            .main
+            .ec2
+            .delete_route()
+            .route_table_id(self.id.as_str());
+
+        let builder = if destination.is_ipv4() {
+            builder.destination_cidr_block(destination.to_string())
+        } else {
+            builder.destination_ipv6_cidr_block(destination.to_string())
+        };
+
+        let _output = builder.send().await?;
+
+        Ok(())
+    }
+
+    /// Associates the table with a subnet, replacing the main route table
+    /// of the VPC for that subnet
+    pub async fn associate(&self, client: &RegionClient, subnet: &SubnetId) -> Result<(), Error> {
+        let _output = client
+            .main
+            .ec2
+            .associate_route_table()
+            .route_table_id(self.id.as_str())
+            .subnet_id(subnet.as_str())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    pub const fn id(&self) -> &RouteTableId {
+        &self.id
+    }
+
+    pub const fn vpc_id(&self) -> Option<&VpcId> {
+        self.vpc_id.as_ref()
+    }
+
+    pub fn associated_subnets(&self) -> &[SubnetId] {
+        &self.associated_subnets
+    }
+
+    pub const fn tags(&self) -> &TagList {
+        &self.tags
+    }
+
+    pub async fn add_tags(&self, client: &RegionClient, tags: TagList) -> Result<(), Error> {
+        add_tags(client, self.id.as_str(), tags).await
+    }
+
+    pub async fn remove_tags(&self, client: &RegionClient, keys: Vec<TagKey>) -> Result<(), Error> {
+        remove_tags(client, self.id.as_str(), keys).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cidr() {
+        let cidr: Cidr = "10.0.0.0/16".parse().unwrap();
+        assert_eq!(cidr.prefix_len(), 16);
+        assert_eq!(cidr.to_string(), "10.0.0.0/16");
+        assert!(cidr.is_ipv4(), "ipv4 network");
+
+        assert!(
+            !"2001:db8::/32".parse::<Cidr>().unwrap().is_ipv4(),
+            "ipv6 network"
+        );
+        assert!("10.0.0.0".parse::<Cidr>().is_err(), "missing prefix");
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err(), "prefix too long");
+        assert!("10.0.0/8".parse::<Cidr>().is_err(), "invalid address");
+    }
+
+    #[test]
+    fn security_group_rules() {
+        let rule = SecurityGroupRule::tcp(443)
+            .from_cidr("0.0.0.0/0".parse().unwrap())
+            .with_description("https");
+        let permission = rule.to_sdk();
+        assert_eq!(permission.ip_protocol(), Some("tcp"));
+        assert_eq!(permission.from_port(), Some(443_i32));
+        assert_eq!(permission.to_port(), Some(443_i32));
+        assert_eq!(
+            permission
+                .ip_ranges()
+                .first()
+                .and_then(|range| range.cidr_ip()),
+            Some("0.0.0.0/0")
+        );
+
+        let rule = SecurityGroupRule::all().from_cidr("::/0".parse().unwrap());
+        assert_eq!(rule.ports(), None);
+        assert_eq!(rule.to_sdk().ipv6_ranges().len(), 1);
+    }
+}
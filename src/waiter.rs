@@ -0,0 +1,360 @@
+//! Polling until a resource reaches a desired state
+//!
+//! Most AWS APIs are eventually consistent, and long-running operations only
+//! report their progress via describe calls. [`Waiter`] repeatedly calls a
+//! poll function, passes the result to a matcher and backs off between
+//! attempts until the matcher reports success or failure, or the timeout is
+//! reached.
+//!
+//! Ready-made waiters for common cases are [`instance_running()`],
+//! [`snapshot_completed()`], [`stack_update_complete()`],
+//! [`nat_gateway_available()`] and [`invalidations_completed()`].
+//!
+//! Waiting needs tokio timers, so it is not available on wasm.
+
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{future::Future, time::Instant};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::{
+    cloudformation::StackName,
+    cloudfront::{InvalidationId, InvalidationStatus},
+    ebs::SnapshotId,
+    CloudfrontDistribution, Error, InstanceId, RegionClient,
+};
+
+/// The decision of a matcher about a polled state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Match<T> {
+    /// The desired state was reached
+    Done(T),
+    /// Not there yet, poll again
+    Retry,
+    /// The desired state can no longer be reached
+    Failure(String),
+}
+
+/// Exponential backoff between polls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub factor: u32,
+}
+
+impl Backoff {
+    pub const fn constant(delay: Duration) -> Self {
+        Self {
+            initial: delay,
+            max: delay,
+            factor: 1,
+        }
+    }
+
+    pub(crate) fn next(&self, current: Duration) -> Duration {
+        current.saturating_mul(self.factor).min(self.max)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(2),
+            max: Duration::from_secs(30),
+            factor: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Waiter {
+    pub backoff: Backoff,
+    pub timeout: Duration,
+}
+
+impl Waiter {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            backoff: Backoff::default(),
+            timeout,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_backoff(self, backoff: Backoff) -> Self {
+        Self { backoff, ..self }
+    }
+
+    /// Polls until `matcher` returns [`Match::Done`] or [`Match::Failure`].
+    ///
+    /// `what` describes the awaited state for error messages, e.g.
+    /// `"instance i-123 running"`. Errors of `poll` are returned immediately.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait<S, T, P, F, M>(&self, what: &str, mut poll: P, matcher: M) -> Result<T, Error>
+    where
+        P: FnMut() -> F + Send,
+        F: Future<Output = Result<S, Error>> + Send,
+        M: Fn(S) -> Match<T> + Sync,
+        S: Send,
+        T: Send,
+    {
+        let start = Instant::now();
+        let mut delay = self.backoff.initial;
+
+        loop {
+            match matcher(poll().await?) {
+                Match::Done(value) => return Ok(value),
+                Match::Failure(reason) => {
+                    return Err(Error::WaiterFailure {
+                        what: what.to_owned(),
+                        reason,
+                    })
+                }
+                Match::Retry => {}
+            }
+
+            let elapsed = start.elapsed();
+            if elapsed >= self.timeout {
+                return Err(Error::WaiterTimeout {
+                    what: what.to_owned(),
+                    timeout: self.timeout,
+                });
+            }
+
+            // Do not sleep past the timeout
+            tokio::time::sleep(delay.min(self.timeout.saturating_sub(elapsed))).await;
+            delay = self.backoff.next(delay);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn instance_running(
+    client: &RegionClient,
+    instance: &InstanceId,
+    waiter: &Waiter,
+) -> Result<(), Error> {
+    use aws_sdk_ec2::types::InstanceStateName;
+
+    waiter
+        .wait(
+            &format!("instance {instance} running"),
+            || async {
+                Ok(client
+                    .main
+                    .ec2
+                    .describe_instances()
+                    .instance_ids(instance.as_str())
+                    .send()
+                    .await?
+                    .reservations
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flat_map(|reservation| reservation.instances.unwrap_or_default())
+                    .next()
+                    .and_then(|instance| instance.state)
+                    .and_then(|state| state.name))
+            },
+            |state| match state {
+                Some(InstanceStateName::Running) => Match::Done(()),
+                Some(
+                    InstanceStateName::ShuttingDown
+                    | InstanceStateName::Terminated
+                    | InstanceStateName::Stopping
+                    | InstanceStateName::Stopped,
+                ) => Match::Failure("instance is not starting".to_owned()),
+                // Describe calls right after launch may not find the instance yet
+                _ => Match::Retry,
+            },
+        )
+        .await
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn snapshot_completed(
+    client: &RegionClient,
+    snapshot: &SnapshotId,
+    waiter: &Waiter,
+) -> Result<(), Error> {
+    use aws_sdk_ec2::types::SnapshotState;
+
+    waiter
+        .wait(
+            &format!("snapshot {snapshot} completed"),
+            || async {
+                Ok(client
+                    .main
+                    .ec2
+                    .describe_snapshots()
+                    .snapshot_ids(snapshot.as_str())
+                    .send()
+                    .await?
+                    .snapshots
+                    .unwrap_or_default()
+                    .into_iter()
+                    .next()
+                    .map(|snapshot| (snapshot.state, snapshot.state_message)))
+            },
+            |state| match state {
+                Some((Some(SnapshotState::Completed), _)) => Match::Done(()),
+                Some((Some(SnapshotState::Error), message)) => {
+                    Match::Failure(message.unwrap_or_else(|| "snapshot failed".to_owned()))
+                }
+                _ => Match::Retry,
+            },
+        )
+        .await
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn stack_update_complete(
+    client: &RegionClient,
+    stack: &StackName,
+    waiter: &Waiter,
+) -> Result<(), Error> {
+    use aws_sdk_cloudformation::types::StackStatus;
+
+    waiter
+        .wait(
+            &format!("stack {stack} update complete"),
+            || async {
+                Ok(client
+                    .cdn
+                    .cloudformation
+                    .describe_stacks()
+                    .stack_name(stack.as_str())
+                    .send()
+                    .await?
+                    .stacks
+                    .unwrap_or_default()
+                    .into_iter()
+                    .next()
+                    .map(|stack| (stack.stack_status, stack.stack_status_reason)))
+            },
+            |state| match state {
+                Some((Some(StackStatus::UpdateComplete), _)) => Match::Done(()),
+                Some((
+                    Some(
+                        ref status @ (StackStatus::UpdateFailed
+                        | StackStatus::UpdateRollbackComplete
+                        | StackStatus::UpdateRollbackFailed),
+                    ),
+                    ref reason,
+                )) => Match::Failure(format!(
+                    "stack is in state {}: {}",
+                    status.as_str(),
+                    reason.as_deref().unwrap_or("no reason given")
+                )),
+                None => Match::Failure("stack does not exist".to_owned()),
+                _ => Match::Retry,
+            },
+        )
+        .await
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn nat_gateway_available(
+    client: &RegionClient,
+    nat_gateway_id: &str,
+    waiter: &Waiter,
+) -> Result<(), Error> {
+    use aws_sdk_ec2::types::NatGatewayState;
+
+    waiter
+        .wait(
+            &format!("nat gateway {nat_gateway_id} available"),
+            || async {
+                Ok(client
+                    .main
+                    .ec2
+                    .describe_nat_gateways()
+                    .nat_gateway_ids(nat_gateway_id)
+                    .send()
+                    .await?
+                    .nat_gateways
+                    .unwrap_or_default()
+                    .into_iter()
+                    .next()
+                    .map(|gateway| (gateway.state, gateway.failure_message)))
+            },
+            |state| match state {
+                Some((Some(NatGatewayState::Available), _)) => Match::Done(()),
+                Some((
+                    Some(
+                        NatGatewayState::Failed
+                        | NatGatewayState::Deleting
+                        | NatGatewayState::Deleted,
+                    ),
+                    message,
+                )) => Match::Failure(
+                    message.unwrap_or_else(|| "nat gateway is not being created".to_owned()),
+                ),
+                _ => Match::Retry,
+            },
+        )
+        .await
+}
+
+/// Waits until all `invalidations` of `distribution` are completed
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn invalidations_completed(
+    client: &RegionClient,
+    distribution: &CloudfrontDistribution,
+    invalidations: &[InvalidationId],
+    waiter: &Waiter,
+) -> Result<(), Error> {
+    waiter
+        .wait(
+            &format!(
+                "{} invalidations of distribution {} completed",
+                invalidations.len(),
+                distribution.id
+            ),
+            || async {
+                let mut statuses = vec![];
+                for invalidation in invalidations {
+                    statuses.push(
+                        distribution
+                            .invalidation(client, invalidation)
+                            .await?
+                            .status,
+                    );
+                }
+                Ok(statuses)
+            },
+            |statuses| {
+                if statuses
+                    .iter()
+                    .all(|status| *status == InvalidationStatus::Completed)
+                {
+                    Match::Done(())
+                } else {
+                    Match::Retry
+                }
+            },
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_is_capped() {
+        let backoff = Backoff {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(5),
+            factor: 2,
+        };
+
+        let delays =
+            std::iter::successors(Some(backoff.initial), |&delay| Some(backoff.next(delay)))
+                .take(5)
+                .map(|delay| delay.as_secs())
+                .collect::<Vec<u64>>();
+
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+    }
+}
@@ -0,0 +1,256 @@
+//! Running the SDK clients on `wasm32-unknown-unknown`
+//!
+//! The default HTTP client, sleep and time source of the SDK depend on tokio
+//! and the operating system, which are not available there. This module
+//! replaces them with the JavaScript host's `fetch()`, `setTimeout()` and
+//! `Date.now()`, e.g. in Cloudflare Workers or in the browser:
+//!
+//! ```no_run
+//! # #[cfg(target_arch = "wasm32")]
+//! # async fn f() -> Result<(), aws_sdk_s3::Error> {
+//! use aws_credential_types::Credentials;
+//! use aws_lib::{wasm, Region};
+//!
+//! let credentials = Credentials::new("AKIA...", "secret", None, None, "worker");
+//! let s3 = wasm::s3_client(Region::EuCentral1, credentials);
+//! let output = s3.list_buckets().send().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! There are no shared config files or instance metadata, so credentials
+//! have to be passed explicitly and [`super::load_sdk_clients()`] cannot be
+//! used. Request bodies have to be in memory, streaming uploads are not
+//! supported by `fetch()` in all hosts.
+//!
+//! The polling helpers of this crate use tokio timers and are not available
+//! on wasm: the [waiters](super::waiter), rate limiting, the retries of batch
+//! requests (bulk tagging and reconciliation, Kinesis and Firehose puts,
+//! DynamoDB batch writes, multipart uploads) and the SQS consumer stream,
+//! log tails and other `wait_for_*()` functions. The same goes for the
+//! secret cache, which needs a clock for its TTL.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aws_credential_types::Credentials;
+use aws_smithy_async::{
+    rt::sleep::{AsyncSleep, Sleep},
+    time::TimeSource,
+};
+use aws_smithy_runtime_api::{
+    client::{
+        http::{
+            HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings,
+            SharedHttpConnector,
+        },
+        orchestrator::{HttpRequest, HttpResponse},
+        result::ConnectorError,
+        runtime_components::RuntimeComponents,
+    },
+    http::StatusCode,
+};
+use aws_smithy_types::body::SdkBody;
+use futures_channel::oneshot;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast as _, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+use super::Region;
+
+#[wasm_bindgen]
+extern "C" {
+    /// The global `fetch()`, which exists in browsers as well as in workers
+    #[wasm_bindgen(js_name = fetch)]
+    fn fetch_with_request(input: &web_sys::Request) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &js_sys::Function, timeout: i32) -> JsValue;
+}
+
+/// The parts of a request that are needed for `fetch()`. Unlike the request
+/// itself, they can be moved into the local task that runs it.
+struct FetchRequest {
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+struct FetchResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+fn js_error(value: &JsValue) -> String {
+    value.as_string().unwrap_or_else(|| format!("{value:?}"))
+}
+
+async fn fetch(request: FetchRequest) -> Result<FetchResponse, String> {
+    let headers = web_sys::Headers::new().map_err(|e| js_error(&e))?;
+    for header in &request.headers {
+        headers
+            .set(&header.0, &header.1)
+            .map_err(|e| js_error(&e))?;
+    }
+
+    let init = web_sys::RequestInit::new();
+    init.set_method(&request.method);
+    init.set_headers(&headers);
+    if !request.body.is_empty() {
+        init.set_body(&js_sys::Uint8Array::from(request.body.as_slice()));
+    }
+
+    let js_request =
+        web_sys::Request::new_with_str_and_init(&request.uri, &init).map_err(|e| js_error(&e))?;
+
+    let response: web_sys::Response = JsFuture::from(fetch_with_request(&js_request))
+        .await
+        .map_err(|e| js_error(&e))?
+        .dyn_into()
+        .map_err(|e| js_error(&e))?;
+
+    let mut response_headers = vec![];
+    if let Some(entries) = js_sys::try_iter(&response.headers()).map_err(|e| js_error(&e))? {
+        for entry in entries {
+            let entry: js_sys::Array = entry
+                .map_err(|e| js_error(&e))?
+                .dyn_into()
+                .map_err(|e| js_error(&e))?;
+            if let (Some(name), Some(value)) = (entry.get(0).as_string(), entry.get(1).as_string())
+            {
+                response_headers.push((name, value));
+            }
+        }
+    }
+
+    let buffer = JsFuture::from(response.array_buffer().map_err(|e| js_error(&e))?)
+        .await
+        .map_err(|e| js_error(&e))?;
+
+    Ok(FetchResponse {
+        status: response.status(),
+        headers: response_headers,
+        body: js_sys::Uint8Array::new(&buffer).to_vec(),
+    })
+}
+
+/// An HTTP client that sends requests via `fetch()`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchHttpClient;
+
+impl HttpConnector for FetchHttpClient {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let Some(body) = request.body().bytes() else {
+            return HttpConnectorFuture::ready(Err(ConnectorError::user(
+                "streaming request bodies are not supported on wasm".into(),
+            )));
+        };
+
+        let fetch_request = FetchRequest {
+            method: request.method().to_owned(),
+            uri: request.uri().to_owned(),
+            headers: request
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect(),
+            body: body.to_vec(),
+        };
+
+        // JavaScript futures are not `Send`, so the request runs in a local
+        // task and only its result is sent back
+        let (sender, receiver) = oneshot::channel();
+        spawn_local(async move {
+            let _unused = sender.send(fetch(fetch_request).await);
+        });
+
+        HttpConnectorFuture::new(async move {
+            let response = receiver
+                .await
+                .map_err(|e| ConnectorError::other(e.into(), None))?
+                .map_err(|e| ConnectorError::io(e.into()))?;
+
+            let status = StatusCode::try_from(response.status)
+                .map_err(|e| ConnectorError::other(e.into(), None))?;
+            let mut http_response = HttpResponse::new(status, SdkBody::from(response.body));
+            for (name, value) in response.headers {
+                let _previous = http_response.headers_mut().insert(name, value);
+            }
+            Ok(http_response)
+        })
+    }
+}
+
+impl HttpClient for FetchHttpClient {
+    fn http_connector(
+        &self,
+        _settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(*self)
+    }
+}
+
+/// Sleeps via `setTimeout()`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WasmSleep;
+
+impl AsyncSleep for WasmSleep {
+    fn sleep(&self, duration: Duration) -> Sleep {
+        let millis = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+
+        let (sender, receiver) = oneshot::channel::<()>();
+        spawn_local(async move {
+            let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+                let _handle = set_timeout(&resolve, millis);
+            });
+            let _result = JsFuture::from(promise).await;
+            let _unused = sender.send(());
+        });
+
+        Sleep::new(async move {
+            let _result = receiver.await;
+        })
+    }
+}
+
+/// The current time via `Date.now()`, as `SystemTime::now()` panics on
+/// `wasm32-unknown-unknown`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsTimeSource;
+
+impl TimeSource for JsTimeSource {
+    fn now(&self) -> SystemTime {
+        // Whole milliseconds as a float. Converting via BigInt fails for
+        // anything that is not an integer, u64 for negative values.
+        let since_epoch = js_sys::BigInt::new(&JsValue::from_f64(js_sys::Date::now()))
+            .ok()
+            .and_then(|millis| u64::try_from(JsValue::from(millis)).ok())
+            .map(Duration::from_millis)
+            .unwrap_or_default();
+        UNIX_EPOCH.checked_add(since_epoch).unwrap_or(UNIX_EPOCH)
+    }
+}
+
+macro_rules! wasm_client {
+    ($sdk:ident, $region:expr, $credentials:expr) => {
+        $sdk::Client::from_conf(
+            $sdk::Config::builder()
+                .behavior_version($sdk::config::BehaviorVersion::latest())
+                .region($sdk::config::Region::new($region.as_str()))
+                .credentials_provider($credentials)
+                .http_client(FetchHttpClient)
+                .sleep_impl(WasmSleep)
+                .time_source(JsTimeSource)
+                .build(),
+        )
+    };
+}
+
+pub fn s3_client(region: Region, credentials: Credentials) -> aws_sdk_s3::Client {
+    wasm_client!(aws_sdk_s3, region, credentials)
+}
+
+pub fn sts_client(region: Region, credentials: Credentials) -> aws_sdk_sts::Client {
+    wasm_client!(aws_sdk_sts, region, credentials)
+}